@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+
+// The industrial I/O devices, among which the accelerometers.
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+// The screen is considered flat when the gravity in its plane is weaker than this fraction of the total.
+const FLAT_RATIO: f32 = 0.4;
+
+// An accelerometer exposed through the industrial I/O subsystem. It's only
+// used on the devices that don't report their orientation through input events.
+pub struct Accelerometer {
+    axes: [PathBuf; 3],
+}
+
+impl Accelerometer {
+    pub fn find() -> Option<Accelerometer> {
+        fs::read_dir(IIO_DEVICES).ok()?
+          .filter_map(|entry| entry.ok())
+          .map(|entry| entry.path())
+          .find(|path| path.join("in_accel_x_raw").exists())
+          .map(|path| Accelerometer {
+              axes: ["x", "y", "z"].map(|axis| path.join(format!("in_accel_{}_raw", axis))),
+          })
+    }
+
+    // The acceleration along the X, Y and Z axes, in raw units.
+    pub fn read(&self) -> Result<[f32; 3], Error> {
+        let mut values = [0.0; 3];
+        for (value, path) in values.iter_mut().zip(self.axes.iter()) {
+            *value = read_value(path)?;
+        }
+        Ok(values)
+    }
+}
+
+fn read_value(path: &Path) -> Result<f32, Error> {
+    let buf = fs::read_to_string(path)?;
+    Ok(buf.trim_end().parse()?)
+}
+
+// Returns the index of the orientation in `GYROSCOPE_ROTATIONS` (landscape left,
+// portrait up, landscape right, portrait down) that matches the gravity vector.
+// The current orientation is kept until the tilt goes *hysteresis* degrees past
+// the bisector of two orientations, and while the screen lies flat.
+pub fn orientation_index(acceleration: [f32; 3], current: Option<i8>, hysteresis: f32) -> Option<i8> {
+    let [x, y, z] = acceleration;
+    let planar = x.hypot(y);
+    if planar < FLAT_RATIO * planar.hypot(z) {
+        return current;
+    }
+
+    // The angle of the gravity vector, counterclockwise from the bottom edge of the portrait screen.
+    let angle = (-x).atan2(-y).to_degrees().rem_euclid(360.0);
+    let index_of = |sector: i32| ((sector + 1) % 4) as i8;
+    let sector_of = |index: i8| (index as i32 + 3) % 4;
+
+    if let Some(current) = current {
+        let center = 90.0 * sector_of(current) as f32;
+        let delta = (angle - center + 180.0).rem_euclid(360.0) - 180.0;
+        if delta.abs() <= 45.0 + hysteresis {
+            return Some(current);
+        }
+    }
+
+    let sector = ((angle + 45.0) / 90.0).floor() as i32 % 4;
+    Some(index_of(sector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orientation_index() {
+        // Portrait, upright.
+        assert_eq!(orientation_index([0.0, -9.8, 0.0], None, 15.0), Some(1));
+        // Upside down.
+        assert_eq!(orientation_index([0.0, 9.8, 0.0], None, 15.0), Some(3));
+        // Lying flat: keep the current orientation.
+        assert_eq!(orientation_index([0.5, -0.5, 9.8], Some(2), 15.0), Some(2));
+        assert_eq!(orientation_index([0.5, -0.5, 9.8], None, 15.0), None);
+        // Tilted by 55°: past the bisector, but within the hysteresis.
+        let (s, c) = 55f32.to_radians().sin_cos();
+        let tilted = [-9.8 * s, -9.8 * c, 0.0];
+        assert_eq!(orientation_index(tilted, Some(1), 15.0), Some(1));
+        assert_eq!(orientation_index(tilted, None, 15.0), orientation_index([-9.8, 0.0, 0.0], None, 15.0));
+        assert_ne!(orientation_index(tilted, Some(1), 5.0), Some(1));
+    }
+}
@@ -0,0 +1,246 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf, Component};
+use std::collections::BTreeMap;
+use fxhash::FxHashMap;
+use walkdir::WalkDir;
+use zip::{ZipArchive, ZipWriter};
+use zip::write::FileOptions;
+use anyhow::{Error, Context, bail, format_err};
+use crate::library::{Library, READING_STATES_DIRNAME, PROFILES_DIRNAME};
+use crate::metadata::ReaderInfo;
+use crate::settings::{Settings, LibraryMode, DEFAULT_PROFILE_NAME, SETTINGS_PATH};
+use crate::helpers::{Fingerprint, Fp, IsHidden};
+
+pub const BACKUP_EXTENSION: &str = "plato-backup";
+// The reading states are stored by library index, profile name and document path.
+const LIBRARIES_DIRNAME: &str = "libraries";
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RestoreReport {
+    pub restored: usize,
+    // The reading states of the documents missing from this device.
+    pub missing: usize,
+}
+
+// The paths of the documents, relative to the library's home, by fingerprint.
+fn document_paths(library: &Library) -> FxHashMap<Fp, PathBuf> {
+    if library.mode == LibraryMode::Database {
+        return library.db.iter().map(|(fp, info)| (*fp, info.file.path.clone())).collect();
+    }
+
+    WalkDir::new(&library.home)
+            .min_depth(1).into_iter()
+            .filter_entry(|entry| !entry.is_hidden())
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !entry.file_type().is_dir())
+            .filter_map(|entry| {
+                let fp = entry.metadata().ok()?.fingerprint(library.fat32_epoch).ok()?;
+                let path = entry.path().strip_prefix(&library.home).ok()?;
+                Some((fp, path.to_path_buf()))
+            }).collect()
+}
+
+// The reading states directories of a library, by profile name.
+fn profile_dirs(home: &Path) -> Vec<(String, PathBuf)> {
+    let mut dirs = vec![(DEFAULT_PROFILE_NAME.to_string(), home.join(READING_STATES_DIRNAME))];
+    if let Ok(entries) = fs::read_dir(home.join(PROFILES_DIRNAME)) {
+        dirs.extend(entries.filter_map(|entry| entry.ok())
+                           .filter(|entry| entry.path().is_dir())
+                           .filter_map(|entry| {
+                               let name = entry.file_name().into_string().ok()?;
+                               Some((name, entry.path()))
+                           }));
+    }
+    dirs
+}
+
+fn entry_name(index: usize, profile: &str, path: &Path) -> String {
+    format!("{}/{}/{}/{}.json", LIBRARIES_DIRNAME, index, profile, path.display())
+}
+
+// Splits an entry name into a library index, a profile name and a document path.
+fn parse_entry_name(name: &str) -> Option<(usize, String, PathBuf)> {
+    let rest = name.strip_prefix(LIBRARIES_DIRNAME)?.strip_prefix('/')?;
+    let (index, rest) = rest.split_once('/')?;
+    let (profile, path) = rest.split_once('/')?;
+    let path = Path::new(path.strip_suffix(".json")?);
+    let is_safe = path.components().all(|c| matches!(c, Component::Normal(_)));
+    if profile.is_empty() || profile.starts_with('.') || profile.contains('\\') ||
+       !is_safe || path.as_os_str().is_empty() {
+        return None;
+    }
+    Some((index.parse().ok()?, profile.to_string(), path.to_path_buf()))
+}
+
+// Writes the settings and the reading states of every library and profile into a single archive.
+// Returns the number of reading states saved.
+pub fn create<P: AsRef<Path>>(settings: &Settings, current: &Library, path: P) -> Result<usize, Error> {
+    let file = File::create(path.as_ref())
+                    .with_context(|| format!("can't create file {}", path.as_ref().display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default();
+    let mut count = 0;
+
+    let content = toml::to_string(settings).context("can't convert the settings to TOML format")?;
+    zip.start_file(SETTINGS_PATH, options)?;
+    zip.write_all(content.as_bytes())?;
+
+    for (index, library_settings) in settings.libraries.iter().enumerate() {
+        let other;
+        let library = if library_settings.path == current.home {
+            current
+        } else {
+            match Library::new(&library_settings.path, library_settings.mode) {
+                Ok(library) => {
+                    other = library;
+                    &other
+                },
+                Err(e) => {
+                    eprintln!("Can't open library {}: {:#}.", library_settings.path.display(), e);
+                    continue;
+                },
+            }
+        };
+
+        let paths = document_paths(library);
+
+        for (profile, dir) in profile_dirs(&library.home) {
+            let entries = if let Ok(entries) = fs::read_dir(&dir) { entries } else { continue };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let state_path = entry.path();
+                let document_path = state_path.file_stem().and_then(|v| v.to_str())
+                                              .and_then(|v| v.parse::<Fp>().ok())
+                                              .and_then(|fp| paths.get(&fp));
+                if let Some(document_path) = document_path {
+                    let content = fs::read(&state_path)
+                                     .with_context(|| format!("can't read file {}", state_path.display()))?;
+                    zip.start_file(entry_name(index, &profile, document_path), options)?;
+                    zip.write_all(&content)?;
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    zip.finish()?;
+    Ok(count)
+}
+
+// Writes the reading states of the given archive into the libraries of the restored settings.
+// The documents are identified by path, since their fingerprints differ from one device to another.
+pub fn restore<P: AsRef<Path>>(path: P) -> Result<(Settings, RestoreReport), Error> {
+    let file = File::open(path.as_ref())
+                    .with_context(|| format!("can't open file {}", path.as_ref().display()))?;
+    let mut zip = ZipArchive::new(file)?;
+
+    let mut content = String::new();
+    zip.by_name(SETTINGS_PATH)
+       .map_err(|_| format_err!("the archive has no settings"))?
+       .read_to_string(&mut content)?;
+    let mut settings: Settings = toml::from_str(&content)
+                                      .context("can't parse the settings of the archive")?;
+
+    if settings.libraries.is_empty() {
+        bail!("the archive has no libraries");
+    }
+
+    if settings.selected_library >= settings.libraries.len() {
+        settings.selected_library = 0;
+    }
+
+    let mut states: BTreeMap<usize, BTreeMap<String, Vec<(PathBuf, ReaderInfo)>>> = BTreeMap::new();
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        if let Some((index, profile, path)) = parse_entry_name(entry.name()) {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            match serde_json::from_slice::<ReaderInfo>(&content) {
+                Ok(reader_info) => states.entry(index).or_default()
+                                         .entry(profile).or_default()
+                                         .push((path, reader_info)),
+                Err(e) => eprintln!("Can't parse reading state {}: {:#}.", entry.name(), e),
+            }
+        }
+    }
+
+    let mut report = RestoreReport::default();
+
+    for (index, profiles) in states {
+        let count = profiles.values().map(Vec::len).sum::<usize>();
+        let library_settings = if let Some(library_settings) = settings.libraries.get(index) {
+            library_settings
+        } else {
+            report.missing += count;
+            continue;
+        };
+
+        let mut library = match Library::new(&library_settings.path, library_settings.mode) {
+            Ok(library) => library,
+            Err(e) => {
+                eprintln!("Can't open library {}: {:#}.", library_settings.path.display(), e);
+                report.missing += count;
+                continue;
+            },
+        };
+
+        // The documents copied before the restoration need to be known to the database.
+        if library.mode == LibraryMode::Database &&
+           profiles.values().flatten().any(|(path, _)| !library.paths.contains_key(path)) {
+            library.import(&settings.import);
+        }
+
+        for (profile, items) in profiles {
+            library.set_profile(&profile);
+            for (path, reader_info) in items {
+                let exists = if library.mode == LibraryMode::Database {
+                    library.paths.contains_key(&path)
+                } else {
+                    library.home.join(&path).is_file()
+                };
+                if exists {
+                    library.sync_reader_info(&path, &reader_info);
+                    report.restored += 1;
+                } else {
+                    report.missing += 1;
+                }
+            }
+        }
+
+        library.set_profile(DEFAULT_PROFILE_NAME);
+        library.flush();
+    }
+
+    Ok((settings, report))
+}
+
+// The backups found in the given directory, latest first.
+pub fn find<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
+    let mut paths = fs::read_dir(dir.as_ref()).into_iter().flatten()
+                       .filter_map(|entry| entry.ok())
+                       .map(|entry| entry.path())
+                       .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(BACKUP_EXTENSION))
+                       .collect::<Vec<PathBuf>>();
+    paths.sort_by(|a, b| b.cmp(a));
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_names() {
+        let name = entry_name(1, "Kids", Path::new("Comics/Tintin.cbz"));
+        assert_eq!(name, "libraries/1/Kids/Comics/Tintin.cbz.json");
+        assert_eq!(parse_entry_name(&name), Some((1, "Kids".to_string(), PathBuf::from("Comics/Tintin.cbz"))));
+        assert_eq!(parse_entry_name("Settings.toml"), None);
+        assert_eq!(parse_entry_name("libraries/0/Default/../../etc/passwd.json"), None);
+        assert_eq!(parse_entry_name("libraries/0/../a.epub.json"), None);
+        assert_eq!(parse_entry_name("libraries/x/Default/a.epub.json"), None);
+    }
+}
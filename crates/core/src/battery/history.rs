@@ -0,0 +1,234 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+
+pub const BATTERY_HISTORY_PATH: &str = "battery-history.bin";
+
+// The number of records kept: two weeks of samples, and some room for the markers.
+const CAPACITY: u32 = 4096;
+const HEADER_SIZE: u64 = 8;
+const RECORD_SIZE: usize = 16;
+// The minimum duration between two samples, in seconds.
+const SAMPLE_INTERVAL: i64 = 240;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RecordKind {
+    Sample,
+    Suspend,
+    Resume,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Record {
+    // The number of seconds since the Unix epoch.
+    pub time: i64,
+    pub capacity: f32,
+    pub charging: bool,
+    pub kind: RecordKind,
+}
+
+impl Record {
+    fn to_bytes(self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.time.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.capacity.to_le_bytes());
+        bytes[12] = match self.kind {
+            RecordKind::Sample => 0,
+            RecordKind::Suspend => 1,
+            RecordKind::Resume => 2,
+        };
+        bytes[13] = self.charging as u8;
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Record> {
+        let kind = match bytes[12] {
+            0 => RecordKind::Sample,
+            1 => RecordKind::Suspend,
+            2 => RecordKind::Resume,
+            _ => return None,
+        };
+        Some(Record {
+            time: i64::from_le_bytes(bytes[0..8].try_into().ok()?),
+            capacity: f32::from_le_bytes(bytes[8..12].try_into().ok()?),
+            charging: bytes[13] != 0,
+            kind,
+        })
+    }
+}
+
+// A ring buffer of records stored in a file: the header holds the index of
+// the next slot and the number of records, the oldest records are overwritten.
+pub struct BatteryHistory {
+    path: PathBuf,
+    last_sample: i64,
+}
+
+impl BatteryHistory {
+    pub fn new<P: AsRef<Path>>(path: P) -> BatteryHistory {
+        BatteryHistory {
+            path: path.as_ref().to_path_buf(),
+            last_sample: 0,
+        }
+    }
+
+    // Samples are skipped when the previous one is too recent.
+    pub fn push(&mut self, record: Record) -> Result<(), Error> {
+        if record.kind == RecordKind::Sample {
+            if record.time - self.last_sample < SAMPLE_INTERVAL {
+                return Ok(());
+            }
+            self.last_sample = record.time;
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&self.path)?;
+        let mut header = [0; HEADER_SIZE as usize];
+        let (next, count) = if file.read_exact(&mut header).is_ok() {
+            (u32::from_le_bytes(header[0..4].try_into()?) % CAPACITY,
+             u32::from_le_bytes(header[4..8].try_into()?).min(CAPACITY))
+        } else {
+            (0, 0)
+        };
+
+        file.seek(SeekFrom::Start(HEADER_SIZE + next as u64 * RECORD_SIZE as u64))?;
+        file.write_all(&record.to_bytes())?;
+        header[0..4].copy_from_slice(&((next + 1) % CAPACITY).to_le_bytes());
+        header[4..8].copy_from_slice(&(count + 1).min(CAPACITY).to_le_bytes());
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        Ok(())
+    }
+
+    // Returns the records, from the oldest to the newest.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Record>, Error> {
+        let mut bytes = Vec::new();
+        File::open(path.as_ref())?.read_to_end(&mut bytes)?;
+        Ok(decode(&bytes))
+    }
+}
+
+fn decode(bytes: &[u8]) -> Vec<Record> {
+    if bytes.len() < HEADER_SIZE as usize {
+        return Vec::new();
+    }
+    let next = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % CAPACITY;
+    let count = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]).min(CAPACITY);
+    let start = if count < CAPACITY { 0 } else { next };
+    (0..count).filter_map(|i| {
+        let offset = HEADER_SIZE as usize + ((start + i) % CAPACITY) as usize * RECORD_SIZE;
+        bytes.get(offset..offset + RECORD_SIZE).and_then(Record::from_bytes)
+    }).collect()
+}
+
+// Samples the capacity at evenly spaced times, from the first known record or
+// `start`, whichever comes last, to `end`. The capacity in between two records
+// is interpolated. Returns the time of the first sample and the values.
+pub fn capacity_curve(records: &[Record], start: i64, end: i64, count: usize) -> Option<(i64, Vec<f32>)> {
+    let first = records.iter().find(|r| r.time <= end)?.time.max(start);
+    if count < 2 || first >= end {
+        return None;
+    }
+    let step = (end - first) as f64 / (count - 1) as f64;
+    let mut index = 0;
+    let values = (0..count).map(|i| {
+        let time = first as f64 + i as f64 * step;
+        while index + 1 < records.len() && (records[index + 1].time as f64) <= time {
+            index += 1;
+        }
+        let a = &records[index];
+        match records.get(index + 1) {
+            Some(b) if b.time > a.time && (a.time as f64) <= time => {
+                let t = ((time - a.time as f64) / (b.time - a.time) as f64) as f32;
+                a.capacity + t * (b.capacity - a.capacity)
+            },
+            _ => a.capacity,
+        }
+    }).collect();
+    Some((first, values))
+}
+
+// The periods during which the device was asleep, clipped to the given range.
+pub fn sleep_intervals(records: &[Record], start: i64, end: i64) -> Vec<(i64, i64)> {
+    let mut intervals = Vec::new();
+    let mut asleep = None;
+    for record in records {
+        match record.kind {
+            RecordKind::Suspend => asleep = Some(record.time),
+            RecordKind::Resume => {
+                if let Some(since) = asleep.take() {
+                    if record.time > start && since < end {
+                        intervals.push((since.max(start), record.time.min(end)));
+                    }
+                }
+            },
+            RecordKind::Sample => (),
+        }
+    }
+    intervals
+}
+
+// The average capacity lost per hour while the device was asleep and not charging.
+pub fn standby_drain(records: &[Record]) -> Option<f32> {
+    let mut lost = 0.0;
+    let mut seconds = 0;
+    for pair in records.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if a.kind == RecordKind::Suspend && b.kind == RecordKind::Resume &&
+           !a.charging && !b.charging && b.time > a.time {
+            lost += (a.capacity - b.capacity).max(0.0);
+            seconds += b.time - a.time;
+        }
+    }
+    if seconds > 0 {
+        Some(3600.0 * lost / seconds as f32)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(time: i64, capacity: f32, kind: RecordKind) -> Record {
+        Record { time, capacity, charging: false, kind }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let path = std::env::temp_dir().join(format!("battery-history-{}.bin", std::process::id()));
+        let mut history = BatteryHistory::new(&path);
+        for i in 0..CAPACITY as i64 + 2 {
+            history.push(record(i * SAMPLE_INTERVAL, 50.0, RecordKind::Sample)).unwrap();
+        }
+        history.push(record(0, 40.0, RecordKind::Sample)).unwrap();
+        let records = BatteryHistory::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(records.len(), CAPACITY as usize);
+        assert_eq!(records[0].time, 2 * SAMPLE_INTERVAL);
+        assert_eq!(records.last().unwrap().time, (CAPACITY as i64 + 1) * SAMPLE_INTERVAL);
+    }
+
+    #[test]
+    fn test_capacity_curve() {
+        let records = [record(100, 80.0, RecordKind::Sample),
+                       record(200, 60.0, RecordKind::Suspend),
+                       record(400, 40.0, RecordKind::Resume)];
+        let (first, values) = capacity_curve(&records, 0, 500, 5).unwrap();
+        assert_eq!(first, 100);
+        assert_eq!(values, vec![80.0, 60.0, 50.0, 40.0, 40.0]);
+        assert!(capacity_curve(&records, 0, 50, 5).is_none());
+    }
+
+    #[test]
+    fn test_sleep_and_drain() {
+        let records = [record(0, 80.0, RecordKind::Suspend),
+                       record(3600, 79.0, RecordKind::Resume),
+                       record(4000, 78.0, RecordKind::Sample),
+                       record(5000, 78.0, RecordKind::Suspend),
+                       record(8600, 75.0, RecordKind::Resume)];
+        assert_eq!(sleep_intervals(&records, 1800, 6000), vec![(1800, 3600), (5000, 6000)]);
+        assert_eq!(standby_drain(&records), Some(2.0));
+    }
+}
@@ -1,5 +1,6 @@
 mod kobo;
 mod fake;
+pub mod history;
 
 use anyhow::Error;
 
@@ -3,15 +3,19 @@ use std::path::Path;
 use std::collections::{BTreeMap, VecDeque};
 use fxhash::FxHashMap;
 use chrono::Local;
+use anyhow::{Error, bail};
 use globset::Glob;
 use walkdir::WalkDir;
 use rand_core::SeedableRng;
 use rand_xoshiro::Xoroshiro128Plus;
 use crate::dictionary::{Dictionary, load_dictionary_from_file};
 use crate::framebuffer::{Framebuffer, Display};
-use crate::view::ViewId;
-use crate::helpers::{load_json, IsHidden};
-use crate::settings::Settings;
+use crate::view::{ViewId, RefreshScheduler};
+use crate::view::focus::Focus;
+use crate::view::notification::NotificationQueue;
+use crate::helpers::{load_json, load_toml, IsHidden};
+use crate::document::pse::relocate_page_caches;
+use crate::settings::{Settings, RotationLock, SETTINGS_PATH};
 use crate::frontlight::Frontlight;
 use crate::lightsensor::LightSensor;
 use crate::battery::Battery;
@@ -20,8 +24,15 @@ use crate::device::CURRENT_DEVICE;
 use crate::library::Library;
 use crate::font::Fonts;
 use crate::rtc::Rtc;
+use crate::vocabulary::{Vocabulary, VOCABULARY_PATH};
+use crate::downloads::{DownloadManager, DOWNLOADS_PATH};
+use crate::weather::{WeatherService, WEATHER_PATH};
+use crate::jobs::Jobs;
+use crate::metrics::Metrics;
+use crate::timer::Timer;
+use crate::planner;
 
-const KEYBOARD_LAYOUTS_DIRNAME: &str = "keyboard-layouts";
+pub const KEYBOARD_LAYOUTS_DIRNAME: &str = "keyboard-layouts";
 const DICTIONARIES_DIRNAME: &str = "dictionaries";
 const INPUT_HISTORY_SIZE: usize = 32;
 
@@ -34,18 +45,31 @@ pub struct Context {
     pub library: Library,
     pub fonts: Fonts,
     pub dictionaries: BTreeMap<String, Dictionary>,
+    pub vocabulary: Vocabulary,
+    pub downloads: DownloadManager,
+    pub weather: WeatherService,
+    pub jobs: Jobs,
+    pub metrics: Metrics,
+    pub refresh: RefreshScheduler,
+    pub timer: Timer,
     pub keyboard_layouts: BTreeMap<String, Layout>,
     pub input_history: FxHashMap<ViewId, VecDeque<String>>,
+    // Passwords entered during the current session.
+    // They're never written to disk.
+    pub passwords: Vec<String>,
     pub frontlight: Box<dyn Frontlight>,
     pub battery: Box<dyn Battery>,
     pub lightsensor: Box<dyn LightSensor>,
-    pub notification_index: u8,
+    pub notifications: NotificationQueue,
     pub kb_rect: Rectangle,
+    pub focus: Focus,
     pub rng: Xoroshiro128Plus,
     pub plugged: bool,
     pub covered: bool,
     pub shared: bool,
     pub online: bool,
+    // Whether the orientation is polled from an accelerometer.
+    pub accelerometer: bool,
 }
 
 impl Context {
@@ -57,10 +81,28 @@ impl Context {
         let rng = Xoroshiro128Plus::seed_from_u64(Local::now().timestamp_nanos() as u64);
         Context { fb, rtc, display: Display { dims, rotation },
                   library, settings, fonts, dictionaries: BTreeMap::new(),
+                  vocabulary: Vocabulary::default(),
+                  downloads: DownloadManager::default(),
+                  weather: WeatherService::default(),
+                  jobs: Jobs::default(),
+                  metrics: Metrics::default(),
+                  refresh: RefreshScheduler::default(),
+                  timer: Timer::default(),
                   keyboard_layouts: BTreeMap::new(), input_history: FxHashMap::default(),
-                  battery, frontlight, lightsensor, notification_index: 0,
-                  kb_rect: Rectangle::default(), rng, plugged: false, covered: false,
-                  shared: false, online: false }
+                  passwords: Vec::new(),
+                  battery, frontlight, lightsensor, notifications: NotificationQueue::default(),
+                  kb_rect: Rectangle::default(), focus: Focus::default(), rng, plugged: false, covered: false,
+                  shared: false, online: false, accelerometer: false }
+    }
+
+    pub fn has_orientation_sensor(&self) -> bool {
+        CURRENT_DEVICE.has_gyroscope() || self.accelerometer
+    }
+
+    // The rotation gesture is replaced by the orientation sensor, unless the latter is ignored.
+    pub fn rotate_gesture_enabled(&self) -> bool {
+        !self.has_orientation_sensor() || self.settings.auto_rotation.rotate_gesture ||
+        self.settings.rotation_lock == Some(RotationLock::Current)
     }
 
     pub fn batch_import(&mut self) {
@@ -72,6 +114,8 @@ impl Context {
             }
             if let Ok(mut library) = Library::new(&library_settings.path, library_settings.mode)
                                              .map_err(|e| eprintln!("{:#?}", e)) {
+                library.set_profile(&self.settings.profile);
+                library.relocate_caches(self.settings.cache_path.as_deref());
                 library.import(&self.settings.import);
                 library.flush();
             }
@@ -97,6 +141,112 @@ impl Context {
         }
     }
 
+    pub fn reload_keyboard_layouts(&mut self) {
+        self.keyboard_layouts.clear();
+        self.load_keyboard_layouts();
+    }
+
+    // Replaces the settings with the content of the settings file.
+    // Returns whether anything changed.
+    pub fn reload_settings(&mut self) -> Result<bool, Error> {
+        let mut settings = load_toml::<Settings, _>(SETTINGS_PATH)?;
+
+        if settings.libraries.is_empty() {
+            bail!("no libraries");
+        }
+
+        if settings.selected_library >= settings.libraries.len() {
+            settings.selected_library = 0;
+        }
+
+        if toml::to_string(&settings).ok() == toml::to_string(&self.settings).ok() {
+            return Ok(false);
+        }
+
+        let library_settings = &settings.libraries[settings.selected_library];
+        let current_settings = &self.settings.libraries[self.settings.selected_library];
+
+        if library_settings.path != current_settings.path || library_settings.mode != current_settings.mode {
+            let library = Library::new(&library_settings.path, library_settings.mode)?;
+            self.library.flush();
+            self.library = library;
+            self.library.sort(library_settings.sort_method, library_settings.sort_method.reverse_order());
+        }
+
+        self.library.set_profile(&settings.profile);
+        self.library.relocate_caches(settings.cache_path.as_deref());
+        relocate_page_caches(settings.cache_path.as_deref());
+        settings.network.export();
+
+        if settings.inverted != self.settings.inverted {
+            self.fb.set_inverted(settings.inverted);
+        }
+
+        if settings.frontlight != self.settings.frontlight ||
+           settings.frontlight_levels != self.settings.frontlight_levels {
+            if settings.frontlight {
+                let levels = settings.frontlight_levels;
+                self.frontlight.set_warmth(levels.warmth);
+                self.frontlight.set_intensity(levels.intensity);
+            } else {
+                self.frontlight.set_intensity(0.0);
+                self.frontlight.set_warmth(0.0);
+            }
+        }
+
+        self.settings = settings;
+        Ok(true)
+    }
+
+    pub fn load_vocabulary(&mut self) {
+        self.vocabulary = Vocabulary::load(VOCABULARY_PATH)
+                                     .map_err(|e| eprintln!("Can't load vocabulary: {:#}.", e))
+                                     .unwrap_or_default();
+    }
+
+    pub fn save_vocabulary(&self) {
+        if let Err(e) = self.vocabulary.save(VOCABULARY_PATH) {
+            eprintln!("Can't save vocabulary: {:#}.", e);
+        }
+    }
+
+    pub fn load_downloads(&mut self) {
+        if !Path::new(DOWNLOADS_PATH).exists() {
+            return;
+        }
+        self.downloads = DownloadManager::load(DOWNLOADS_PATH)
+                                         .map_err(|e| eprintln!("Can't load downloads: {:#}.", e))
+                                         .unwrap_or_default();
+    }
+
+    pub fn load_weather(&mut self) {
+        if Path::new(WEATHER_PATH).exists() {
+            self.weather = WeatherService::load(WEATHER_PATH);
+        }
+    }
+
+    // Writes the planner template of the day, and rewrites it when `force` is set.
+    pub fn update_planner(&self, force: bool) {
+        let settings = &self.settings.sketch.planner;
+        if !settings.enabled {
+            return;
+        }
+        let (width, height) = self.display.dims;
+        let dims = (width.min(height), width.max(height));
+        let path = self.library.home.join(&settings.path);
+        let calendar_dir = self.library.home.join(&self.settings.calendar.path);
+        if let Err(e) = planner::update(&path, Local::now().date_naive(), dims, settings,
+                                        self.weather.forecast.as_ref(), &calendar_dir, force) {
+            eprintln!("Can't write the planner: {:#}.", e);
+        }
+    }
+
+    pub fn save_downloads(&self) {
+        if let Err(e) = self.downloads.save(DOWNLOADS_PATH) {
+            eprintln!("Can't save downloads: {:#}.", e);
+        }
+    }
+
     pub fn load_dictionaries(&mut self) {
         let glob = Glob::new("**/*.index").unwrap().compile_matcher();
         for entry in WalkDir::new(Path::new(DICTIONARIES_DIRNAME)).min_depth(1)
@@ -155,6 +305,24 @@ impl Context {
             self.frontlight.set_warmth(0.0);
         }
     }
+
+    // Saves the reading states and the preferences of the current profile,
+    // and loads those of the given profile.
+    pub fn switch_profile(&mut self, index: usize) {
+        if self.settings.frontlight {
+            self.settings.frontlight_levels = self.frontlight.levels();
+        }
+        self.settings.switch_profile(index);
+        self.library.set_profile(&self.settings.profile);
+        if self.settings.frontlight {
+            let levels = self.settings.frontlight_levels;
+            self.frontlight.set_warmth(levels.warmth);
+            self.frontlight.set_intensity(levels.intensity);
+        } else {
+            self.frontlight.set_intensity(0.0);
+            self.frontlight.set_warmth(0.0);
+        }
+    }
 }
 
 
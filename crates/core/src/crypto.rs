@@ -0,0 +1,97 @@
+use argon2::Argon2;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce, Key, KeyInit};
+use chacha20poly1305::aead::{Aead, AeadCore, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use anyhow::{Error, format_err};
+
+// Layout of an encrypted payload:
+// MAGIC | VERSION | SALT | NONCE | CIPHERTEXT | TAG.
+// The payload is sealed with XChaCha20-Poly1305, the header being authenticated
+// with it. The key is derived from the passphrase with Argon2id.
+pub const MAGIC: &[u8; 8] = b"PLATOENC";
+
+const VERSION: u8 = 2;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const TAG_SIZE: usize = 16;
+const HEADER_SIZE: usize = MAGIC.len() + 1 + SALT_SIZE + NONCE_SIZE;
+
+pub fn is_encrypted(buf: &[u8]) -> bool {
+    buf.len() >= HEADER_SIZE + TAG_SIZE && buf.starts_with(MAGIC)
+}
+
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    encrypt_with(data, passphrase, &salt, &nonce)
+}
+
+pub fn decrypt(buf: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    if !is_encrypted(buf) {
+        return Err(format_err!("not an encrypted payload"));
+    }
+
+    if buf[MAGIC.len()] != VERSION {
+        return Err(format_err!("unsupported encryption version: {}", buf[MAGIC.len()]));
+    }
+
+    let (header, sealed) = buf.split_at(HEADER_SIZE);
+    let salt = &header[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_SIZE];
+    let nonce = XNonce::from_slice(&header[MAGIC.len() + 1 + SALT_SIZE..]);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher.decrypt(nonce, Payload { msg: sealed, aad: header })
+          .map_err(|_| format_err!("wrong passphrase or corrupted data"))
+}
+
+fn encrypt_with(data: &[u8], passphrase: &str, salt: &[u8], nonce: &XNonce) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(HEADER_SIZE + data.len() + TAG_SIZE);
+    buf.extend_from_slice(MAGIC);
+    buf.push(VERSION);
+    buf.extend_from_slice(salt);
+    buf.extend_from_slice(nonce);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    let sealed = cipher.encrypt(nonce, Payload { msg: data, aad: &buf })
+                       .map_err(|_| format_err!("can't encrypt the data"))?;
+    buf.extend_from_slice(&sealed);
+    Ok(buf)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, Error> {
+    let mut key = Key::default();
+    Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                     .map_err(|e| format_err!("can't derive the key: {}", e))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data = b"The quick brown fox jumps over the lazy dog.";
+        let buf = encrypt_with(data, "secret", &[7; SALT_SIZE], XNonce::from_slice(&[u8::MAX; NONCE_SIZE])).unwrap();
+        assert!(is_encrypted(&buf));
+        assert_eq!(buf.len(), HEADER_SIZE + data.len() + TAG_SIZE);
+        assert_ne!(&buf[HEADER_SIZE..HEADER_SIZE + data.len()], &data[..]);
+        assert_eq!(decrypt(&buf, "secret").unwrap(), data.to_vec());
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let buf = encrypt(b"annotations", "secret").unwrap();
+        assert!(decrypt(&buf, "guess").is_err());
+    }
+
+    #[test]
+    fn test_tampered_payload() {
+        let mut buf = encrypt(b"annotations", "secret").unwrap();
+        buf[HEADER_SIZE] ^= 1;
+        assert!(decrypt(&buf, "secret").is_err());
+        // The header is authenticated too.
+        let mut buf = encrypt(b"annotations", "secret").unwrap();
+        buf[MAGIC.len() + 1] ^= 1;
+        assert!(decrypt(&buf, "secret").is_err());
+    }
+}
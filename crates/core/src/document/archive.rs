@@ -0,0 +1,148 @@
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use anyhow::{Error, format_err};
+use fxhash::FxHashSet;
+use zip::ZipArchive;
+use zip::read::ZipFile;
+use crate::helpers::Fp;
+
+pub const ARCHIVE_EXTENSION: &str = "zip";
+// The entries are read in memory: larger entries are refused.
+pub const MAX_ENTRY_SIZE: u64 = 128 * 1024 * 1024;
+
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    // The name of the entry within the archive.
+    pub name: String,
+    pub kind: String,
+    pub size: u64,
+    pub fp: Fp,
+}
+
+// A virtual path is made of the path of a ZIP archive followed by the name of one of its entries,
+// e.g. `Comics/Collection.zip/Volume 1.cbz`. Returns the path of the archive and the entry's name.
+pub fn split_virtual_path(path: &Path) -> Option<(PathBuf, String)> {
+    let mut archive = PathBuf::new();
+    let mut components = path.components();
+    while let Some(component) = components.next() {
+        archive.push(component);
+        let is_archive = Path::new(component.as_os_str()).extension()
+                              .and_then(OsStr::to_str)
+                              .is_some_and(|ext| ext.eq_ignore_ascii_case(ARCHIVE_EXTENSION));
+        if is_archive {
+            let name = components.as_path().to_str()?;
+            if name.is_empty() {
+                return None;
+            }
+            return Some((archive, name.to_string()));
+        }
+    }
+    None
+}
+
+// Returns the archive and the entry designated by *path*, if it doesn't exist on the file system.
+pub fn resolve(path: &Path) -> Option<(PathBuf, String)> {
+    if path.exists() {
+        return None;
+    }
+    split_virtual_path(path).filter(|(archive, _)| archive.is_file())
+}
+
+pub fn exists(path: &Path) -> bool {
+    path.exists() || resolve(path).is_some()
+}
+
+pub fn read_entry(archive: &Path, name: &str) -> Result<Vec<u8>, Error> {
+    let mut archive = ZipArchive::new(File::open(archive)?)?;
+    let entry = archive.by_name(name)?;
+    let size = entry.size();
+    read_limited(entry, size, MAX_ENTRY_SIZE)
+}
+
+// The declared *size* of an entry can't be trusted: the reading stops past *limit*.
+fn read_limited<R: Read>(reader: R, size: u64, limit: u64) -> Result<Vec<u8>, Error> {
+    if size > limit {
+        return Err(format_err!("entry too large: {} bytes", size));
+    }
+    let mut buf = Vec::with_capacity(size as usize);
+    reader.take(limit + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > limit {
+        return Err(format_err!("entry larger than {} bytes", limit));
+    }
+    Ok(buf)
+}
+
+// Reads the entry designated by the virtual *path*.
+pub fn read_virtual_path(path: &Path) -> Result<Vec<u8>, Error> {
+    let (archive, name) = resolve(path).ok_or_else(|| format_err!("not a virtual path: {}", path.display()))?;
+    read_entry(&archive, &name)
+}
+
+// The entries of the archive whose kind belongs to *kinds*. The fingerprint of an entry
+// depends on its checksum and size, so that it survives the updates of the archive.
+pub fn entries(archive: &Path, kinds: &FxHashSet<String>) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut archive = ZipArchive::new(File::open(archive)?)?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let entry = archive_entry(&entry);
+        if kinds.contains(&entry.kind) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+pub fn entry(archive: &Path, name: &str) -> Result<ArchiveEntry, Error> {
+    let mut archive = ZipArchive::new(File::open(archive)?)?;
+    let entry = archive.by_name(name)?;
+    Ok(archive_entry(&entry))
+}
+
+fn archive_entry(entry: &ZipFile) -> ArchiveEntry {
+    let kind = Path::new(entry.name()).extension()
+                   .and_then(OsStr::to_str)
+                   .map(str::to_lowercase)
+                   .unwrap_or_default();
+    let mut fp = Fp::default();
+    *fp = (entry.crc32() as u64).rotate_left(32) ^ entry.size();
+    ArchiveEntry {
+        name: entry.name().to_string(),
+        kind,
+        size: entry.size(),
+        fp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_virtual_path() {
+        assert_eq!(split_virtual_path(Path::new("Comics/Collection.ZIP/Volume 1.cbz")),
+                   Some((PathBuf::from("Comics/Collection.ZIP"), "Volume 1.cbz".to_string())));
+        assert_eq!(split_virtual_path(Path::new("a.zip/b/c.epub")),
+                   Some((PathBuf::from("a.zip"), "b/c.epub".to_string())));
+        assert_eq!(split_virtual_path(Path::new("Books/a.zip")), None);
+        assert_eq!(split_virtual_path(Path::new("Books/a.epub")), None);
+    }
+
+    #[test]
+    fn test_read_limited() {
+        let data = [0u8; 16];
+        assert_eq!(read_limited(&data[..], 16, 16).unwrap().len(), 16);
+        assert!(read_limited(&data[..], 17, 16).is_err());
+        // The declared size understates the actual size.
+        assert!(read_limited(&data[..], 4, 8).is_err());
+    }
+}
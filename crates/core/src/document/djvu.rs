@@ -8,7 +8,8 @@ use std::os::unix::ffi::OsStrExt;
 use super::{Document, Location, TextLocation, BoundedText, TocEntry};
 use super::{chapter, chapter_relative};
 use crate::metadata::TextAlign;
-use crate::framebuffer::Pixmap;
+use super::html::layout::Typography;
+use crate::framebuffer::{Pixmap, take_buffer};
 use crate::geom::{Rectangle, Boundary, CycleDir};
 
 impl Into<DjvuRect> for Rectangle {
@@ -116,6 +117,15 @@ impl Document for DjvuDocument {
         self.page(index).and_then(|page| page.pixmap(scale)).map(|pixmap| (pixmap, index))
     }
 
+    fn has_tiles(&self) -> bool {
+        true
+    }
+
+    fn tile_pixmap(&mut self, loc: Location, scale: f32, rect: &Rectangle) -> Option<Pixmap> {
+        let index = self.resolve_location(loc)?;
+        self.page(index).and_then(|page| page.tile_pixmap(scale, rect))
+    }
+
     fn toc(&mut self) -> Option<Vec<TocEntry>> {
         unsafe {
             let mut exp = ddjvu_document_get_outline(self.doc);
@@ -251,6 +261,9 @@ impl Document for DjvuDocument {
     fn set_margin_width(&mut self, _width: i32) {
     }
 
+    fn set_vertical_margin_width(&mut self, _width: Option<i32>) {
+    }
+
     fn set_line_height(&mut self, _line_height: f32) {
     }
 
@@ -260,9 +273,15 @@ impl Document for DjvuDocument {
     fn set_stretch_tolerance(&mut self, _stretch_tolerance: f32) {
     }
 
+    fn set_typography(&mut self, _typography: Typography) {
+    }
+
     fn set_ignore_document_css(&mut self, _ignore: bool) {
     }
 
+    fn set_language(&mut self, _language: &str) {
+    }
+
 }
 
 impl DjvuDocument {
@@ -389,15 +408,34 @@ impl DjvuDocument {
 
 impl<'a> DjvuPage<'a> {
     pub fn pixmap(&self, scale: f32) -> Option<Pixmap> {
-        unsafe {
-            let (width, height) = self.dims();
-            let rect = DjvuRect {
-                x: 0,
-                y: 0,
-                w: (scale * width as f32) as libc::c_uint,
-                h: (scale * height as f32) as libc::c_uint,
-            };
+        let rect = self.scaled_rect(scale);
+        self.render(&rect, &rect)
+    }
+
+    // Renders the given area of the page scaled by the given factor.
+    pub fn tile_pixmap(&self, scale: f32, area: &Rectangle) -> Option<Pixmap> {
+        let page_rect = self.scaled_rect(scale);
+        let render_rect = DjvuRect {
+            x: area.min.x,
+            y: area.min.y,
+            w: area.width(),
+            h: area.height(),
+        };
+        self.render(&page_rect, &render_rect)
+    }
 
+    fn scaled_rect(&self, scale: f32) -> DjvuRect {
+        let (width, height) = self.dims();
+        DjvuRect {
+            x: 0,
+            y: 0,
+            w: (scale * width as f32) as libc::c_uint,
+            h: (scale * height as f32) as libc::c_uint,
+        }
+    }
+
+    fn render(&self, page_rect: &DjvuRect, rect: &DjvuRect) -> Option<Pixmap> {
+        unsafe {
             let fmt = ddjvu_format_create(DDJVU_FORMAT_GREY8, 0, ptr::null());
 
             if fmt.is_null() {
@@ -408,15 +446,16 @@ impl<'a> DjvuPage<'a> {
             ddjvu_format_set_y_direction(fmt, 1);
 
             let len = (rect.w * rect.h) as usize;
-            let mut data = Vec::new();
-            if data.try_reserve_exact(len).is_err() {
+            let mut data = if let Some(data) = take_buffer(len) {
+                data
+            } else {
                 ddjvu_format_release(fmt);
                 return None;
-            }
+            };
             data.resize(len, 0xff);
 
             ddjvu_page_render(self.page, DDJVU_RENDER_COLOR,
-                              &rect, &rect, fmt,
+                              page_rect, rect, fmt,
                               rect.w as libc::c_ulong, data.as_mut_ptr());
 
             let job = ddjvu_page_job(self.page);
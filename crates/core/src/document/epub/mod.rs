@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{Read, Cursor};
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::collections::BTreeSet;
@@ -6,27 +6,36 @@ use fxhash::FxHashMap;
 use zip::ZipArchive;
 use percent_encoding::percent_decode_str;
 use anyhow::{Error, format_err};
+use serde::{Serialize, Deserialize};
 use crate::framebuffer::Pixmap;
-use crate::helpers::{Normalize, decode_entities};
+use crate::helpers::{Normalize, decode_entities, load_json, save_json};
 use crate::document::{Document, Location, TextLocation, TocEntry, BoundedText, chapter_from_uri};
+use crate::document::{Landmark, LandmarkKind};
 use crate::unit::pt_to_px;
-use crate::geom::{Boundary, CycleDir};
+use crate::metadata::normalize_isbn;
+use crate::geom::{Boundary, CycleDir, Edge, Rectangle};
 use super::pdf::PdfOpener;
+use super::archive::{self, ReadSeek};
 use super::html::dom::{XmlTree, NodeRef};
 use super::html::engine::{Page, Engine, ResourceFetcher};
 use super::html::layout::{StyleData, LoopContext};
-use super::html::layout::{RootData, DrawState, DrawCommand, TextCommand, ImageCommand};
-use super::html::layout::TextAlign;
+use super::html::layout::{RootData, DrawState, DrawCommand, TextCommand, ImageCommand, TableCommand};
+use super::html::layout::{TextAlign, Typography};
 use super::html::style::StyleSheet;
 use super::html::css::CssParser;
 use super::html::xml::XmlParser;
 
 const VIEWER_STYLESHEET: &str = "css/epub.css";
 const USER_STYLESHEET: &str = "css/epub-user.css";
+// Pre-paginated pages are laid out in CSS pixels.
+const CSS_DPI: u16 = 96;
+const FIXED_LAYOUT_FONT_SIZE: f32 = 12.0;
+// The height, relatively to the page's, available to a table shown on its own.
+const TABLE_HEIGHT_RATIO: u32 = 4;
 
 type UriCache = FxHashMap<String, usize>;
 
-impl ResourceFetcher for ZipArchive<File> {
+impl ResourceFetcher for ZipArchive<Box<dyn ReadSeek>> {
     fn fetch(&mut self, name: &str) -> Result<Vec<u8>, Error> {
         let mut file = self.by_name(name)?;
         let mut buf = Vec::new();
@@ -36,19 +45,32 @@ impl ResourceFetcher for ZipArchive<File> {
 }
 
 pub struct EpubDocument {
-    archive: ZipArchive<File>,
+    archive: ZipArchive<Box<dyn ReadSeek>>,
     info: XmlTree,
     parent: PathBuf,
     engine: Engine,
     spine: Vec<Chunk>,
     cache: FxHashMap<usize, Vec<Page>>,
+    // The offsets of the first draw command of each page, per spine index.
+    pages: FxHashMap<usize, Vec<usize>>,
+    font_family: Option<String>,
     ignore_document_css: bool,
+    // Overrides the language given by the metadata.
+    language: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LayoutCache {
+    signature: String,
+    pages: FxHashMap<usize, Vec<usize>>,
 }
 
 #[derive(Debug)]
 struct Chunk {
     path: String,
     size: usize,
+    // The dimensions, in CSS pixels, of a pre-paginated page.
+    viewport: Option<(u32, u32)>,
 }
 
 unsafe impl Send for EpubDocument {}
@@ -56,8 +78,11 @@ unsafe impl Sync for EpubDocument {}
 
 impl EpubDocument {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<EpubDocument, Error> {
-        let file = File::open(path)?;
-        let mut archive = ZipArchive::new(file)?;
+        let reader: Box<dyn ReadSeek> = match archive::resolve(path.as_ref()) {
+            Some((zip_path, name)) => Box::new(Cursor::new(archive::read_entry(&zip_path, &name)?)),
+            None => Box::new(File::open(path)?),
+        };
+        let mut archive = ZipArchive::new(reader)?;
 
         let opf_path = {
             let mut zf = archive.by_name("META-INF/container.xml")?;
@@ -89,9 +114,29 @@ impl EpubDocument {
             let spn = info.root().find("spine")
                          .ok_or_else(|| format_err!("the spine is missing"))?;
 
+            let pre_paginated = info.root().find("metadata").is_some_and(|md| {
+                md.children().any(|child| child.tag_name() == Some("meta") &&
+                                          child.attribute("property") == Some("rendition:layout") &&
+                                          child.text().trim() == "pre-paginated")
+            });
+
             for child in spn.children() {
+                let properties = child.attribute("properties").unwrap_or_default();
+                let fixed = if properties.contains("rendition:layout-pre-paginated") {
+                    true
+                } else if properties.contains("rendition:layout-reflowable") {
+                    false
+                } else {
+                    pre_paginated
+                };
+
                 let vertebra_opt = child.attribute("idref").and_then(|idref| {
                     manifest.find_by_id(idref)
+                }).filter(|entry| {
+                    // Media overlays and audio clips aren't pages.
+                    entry.attribute("media-type").is_none_or(|media_type| {
+                        media_type != "application/smil+xml" && !media_type.starts_with("audio/")
+                    })
                 }).and_then(|entry| {
                     entry.attribute("href")
                 }).and_then(|href| {
@@ -107,7 +152,8 @@ impl EpubDocument {
                 });
 
                 if let Some((size, path)) = vertebra_opt {
-                    spine.push(Chunk { path, size });
+                    let viewport = if fixed { fixed_viewport(&mut archive, &path) } else { None };
+                    spine.push(Chunk { path, size, viewport });
                 }
             }
         }
@@ -123,7 +169,10 @@ impl EpubDocument {
             engine: Engine::new(),
             spine,
             cache: FxHashMap::default(),
+            pages: FxHashMap::default(),
+            font_family: None,
             ignore_document_css: false,
+            language: None,
         })
     }
 
@@ -256,24 +305,33 @@ impl EpubDocument {
     #[inline]
     fn page_index(&mut self, offset: usize, index: usize, start_offset: usize) -> Option<usize> {
         if !self.cache.contains_key(&index) {
-            let display_list = self.build_display_list(index, start_offset);
-            self.cache.insert(index, display_list);
+            self.cache_display_list(index, start_offset);
         }
-        self.cache.get(&index).map(|display_list| {
-            if display_list.len() < 2 || display_list[1].first().map(|dc| offset < dc.offset()) == Some(true) {
-                return 0;
-            } else if display_list[display_list.len() - 1].first().map(|dc| offset >= dc.offset()) == Some(true) {
-                return display_list.len() - 1;
-            } else {
-                for i in 1..display_list.len()-1 {
-                    if display_list[i].first().map(|dc| offset >= dc.offset()) == Some(true) &&
-                       display_list[i+1].first().map(|dc| offset < dc.offset()) == Some(true) {
-                        return i;
-                    }
-                }
-            }
-            0
-        })
+        self.pages.get(&index).map(|page_offsets| page_position(page_offsets, offset))
+    }
+
+    // Returns the page offsets of the given spine index, without building
+    // its display list when the layout cache already knows them.
+    fn page_offsets(&mut self, index: usize, start_offset: usize) -> Option<&Vec<usize>> {
+        if !self.pages.contains_key(&index) {
+            self.cache_display_list(index, start_offset);
+        }
+        self.pages.get(&index)
+    }
+
+    fn cache_display_list(&mut self, index: usize, start_offset: usize) {
+        let display_list = self.build_display_list(index, start_offset);
+        let page_offsets = display_list.iter()
+                                       .map(|page| page.first().map_or(start_offset, DrawCommand::offset))
+                                       .collect();
+        self.pages.insert(index, page_offsets);
+        self.cache.insert(index, display_list);
+    }
+
+    fn clear_layout(&mut self) {
+        self.cache.clear();
+        self.pages.clear();
+        self.engine.clear_inline_images();
     }
 
     fn resolve_link(&mut self, uri: &str, cache: &mut UriCache) -> Option<usize> {
@@ -292,10 +350,8 @@ impl EpubDocument {
             self.cache_uris(root.root(), name, start_offset, cache);
             cache.get(uri).cloned()
         } else {
-            let page_index = self.page_index(start_offset, index, start_offset)?;
-            let offset = self.cache.get(&index)
-                             .and_then(|display_list| display_list[page_index].first())
-                             .map(DrawCommand::offset)?;
+            let offset = self.page_offsets(index, start_offset)
+                             .and_then(|page_offsets| page_offsets.first().cloned())?;
             cache.insert(uri.to_string(), offset);
             Some(offset)
         }
@@ -311,7 +367,33 @@ impl EpubDocument {
         }
     }
 
+    // Pre-paginated pages are laid out in their own viewport, without margins.
+    fn enter_viewport(&mut self, viewport: (u32, u32)) -> ((u32, u32), Edge, u16, f32) {
+        let state = (self.engine.dims, self.engine.margin, self.engine.dpi, self.engine.font_size);
+        self.engine.dims = viewport;
+        self.engine.margin = Edge::uniform(0);
+        self.engine.dpi = CSS_DPI;
+        self.engine.font_size = FIXED_LAYOUT_FONT_SIZE;
+        state
+    }
+
+    fn leave_viewport(&mut self, state: ((u32, u32), Edge, u16, f32)) {
+        (self.engine.dims, self.engine.margin, self.engine.dpi, self.engine.font_size) = state;
+    }
+
     fn build_display_list(&mut self, index: usize, start_offset: usize) -> Vec<Page> {
+        if let Some(viewport) = self.spine[index].viewport {
+            let state = self.enter_viewport(viewport);
+            let display_list = self.build_reflowed_display_list(index, start_offset);
+            self.leave_viewport(state);
+            // The content is meant to fit on a single page.
+            return vec![display_list.concat()];
+        }
+        self.build_reflowed_display_list(index, start_offset)
+    }
+
+    // Parses the chunk and gathers its style sheet.
+    fn load_chunk(&mut self, index: usize) -> (XmlTree, StyleSheet, PathBuf) {
         let mut text = String::new();
         let mut spine_dir = PathBuf::default();
 
@@ -366,28 +448,37 @@ impl EpubDocument {
             stylesheet.append(&mut inner_css, true);
         }
 
+        (root, stylesheet, spine_dir)
+    }
+
+    fn root_style(&self, root: &XmlTree, rect: &Rectangle) -> StyleData {
+        let language = self.language.clone().or_else(|| self.language()).or_else(|| {
+            root.root().find("html")
+                .and_then(|html| html.attribute("xml:lang"))
+                .map(String::from)
+        });
+
+        StyleData {
+            language,
+            font_size: self.engine.font_size,
+            line_height: pt_to_px(self.engine.line_height * self.engine.font_size, self.engine.dpi).round() as i32,
+            text_align: self.engine.text_align,
+            start_x: rect.min.x,
+            end_x: rect.max.x,
+            width: rect.max.x - rect.min.x,
+            .. Default::default()
+        }
+    }
+
+    fn build_reflowed_display_list(&mut self, index: usize, start_offset: usize) -> Vec<Page> {
+        let (root, stylesheet, spine_dir) = self.load_chunk(index);
         let mut display_list = Vec::new();
 
         if let Some(body) = root.root().find("body") {
             let mut rect = self.engine.rect();
             rect.shrink(&self.engine.margin);
 
-            let language = self.language().or_else(|| {
-                root.root().find("html")
-                    .and_then(|html| html.attribute("xml:lang"))
-                    .map(String::from)
-            });
-
-            let style = StyleData {
-                language,
-                font_size: self.engine.font_size,
-                line_height: pt_to_px(self.engine.line_height * self.engine.font_size, self.engine.dpi).round() as i32,
-                text_align: self.engine.text_align,
-                start_x: rect.min.x,
-                end_x: rect.max.x,
-                width: rect.max.x - rect.min.x,
-                .. Default::default()
-            };
+            let style = self.root_style(&root, &rect);
 
             let loop_context = LoopContext::default();
             let mut draw_state = DrawState {
@@ -415,6 +506,11 @@ impl EpubDocument {
         display_list
     }
 
+    fn layout_signature(&self) -> String {
+        format!("{} {:?} {} {:?}", self.engine.layout_signature(),
+                self.font_family, self.ignore_document_css, self.language)
+    }
+
     pub fn categories(&self) -> BTreeSet<String> {
         let mut result = BTreeSet::new();
 
@@ -578,6 +674,104 @@ impl EpubDocument {
     pub fn year(&self) -> Option<String> {
         self.metadata("dc:date").map(|s| s.chars().take(4).collect())
     }
+
+    // The first identifier that is a valid ISBN, in its 13 digits form.
+    pub fn isbn(&self) -> Option<String> {
+        self.info.root().find("metadata")
+            .and_then(|md| md.children()
+                             .filter(|child| child.tag_qualified_name() == Some("dc:identifier"))
+                             .find_map(|child| normalize_isbn(&child.text())))
+    }
+}
+
+// Reads the dimensions given by the viewport meta tag of a pre-paginated page.
+fn fixed_viewport(archive: &mut ZipArchive<Box<dyn ReadSeek>>, path: &str) -> Option<(u32, u32)> {
+    let mut text = String::new();
+    archive.by_name(path).ok()?.read_to_string(&mut text).ok()?;
+    let root = XmlParser::new(&text).parse();
+    let head = root.root().find("head")?;
+    let content = head.children()
+                      .find(|child| child.tag_name() == Some("meta") &&
+                                    child.attribute("name") == Some("viewport"))?
+                      .attribute("content")?
+                      .to_string();
+    parse_viewport(&content)
+}
+
+// Parses the content of a viewport meta tag, e.g. `width=1200, height=1600`.
+fn parse_viewport(content: &str) -> Option<(u32, u32)> {
+    let mut width = None;
+    let mut height = None;
+    for pair in content.split([',', ';']) {
+        let mut parts = pair.splitn(2, '=').map(str::trim);
+        match (parts.next(), parts.next()) {
+            (Some("width"), Some(value)) => width = value.trim_end_matches("px").parse::<f32>().ok(),
+            (Some("height"), Some(value)) => height = value.trim_end_matches("px").parse::<f32>().ok(),
+            _ => (),
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) if width >= 1.0 && height >= 1.0 => Some((width as u32, height as u32)),
+        _ => None,
+    }
+}
+
+// The kind of a landmark, given its EPUB types, its ARIA role or its guide type.
+fn landmark_kind(types: &str) -> Option<LandmarkKind> {
+    types.split_whitespace().find_map(|kind| match kind.trim_start_matches("doc-") {
+        "cover" => Some(LandmarkKind::Cover),
+        "toc" => Some(LandmarkKind::Toc),
+        "bodymatter" | "text" | "start" => Some(LandmarkKind::BodyMatter),
+        "footnotes" | "endnotes" | "rearnotes" | "notes" => Some(LandmarkKind::Notes),
+        "loi" => Some(LandmarkKind::Figures),
+        "lot" => Some(LandmarkKind::Tables),
+        "index" => Some(LandmarkKind::Index),
+        _ => None,
+    })
+}
+
+fn landmark(kind: Option<&str>, title: &str, href: &str, dir: &Path) -> Option<Landmark> {
+    let kind = kind.and_then(landmark_kind).unwrap_or(LandmarkKind::Other);
+    let title = decode_entities(title).trim().to_string();
+    if kind == LandmarkKind::Other && title.is_empty() {
+        return None;
+    }
+    let href = percent_decode_str(&decode_entities(href)).decode_utf8_lossy().into_owned();
+    let uri = dir.join(&href).normalize().to_str()?.to_string();
+    Some(Landmark {
+        title: if title.is_empty() { kind.label().to_string() } else { title },
+        kind,
+        location: Location::Uri(uri),
+    })
+}
+
+// The links of the landmarks navigation list of an EPUB 3 book.
+fn nav_landmarks(nav: NodeRef, dir: &Path) -> Vec<Landmark> {
+    nav.descendants()
+       .filter(|node| node.tag_name() == Some("a"))
+       .filter_map(|link| {
+           let kind = link.attribute("epub:type").or_else(|| link.attribute("role"));
+           link.attribute("href").and_then(|href| landmark(kind, &link.text(), href, dir))
+       })
+       .collect()
+}
+
+// The references of the guide of an EPUB 2 book.
+fn guide_landmarks(guide: NodeRef, dir: &Path) -> Vec<Landmark> {
+    guide.children()
+         .filter(|node| node.tag_name() == Some("reference"))
+         .filter_map(|reference| {
+             reference.attribute("href").and_then(|href| {
+                 landmark(reference.attribute("type"), reference.attribute("title").unwrap_or_default(), href, dir)
+             })
+         })
+         .collect()
+}
+
+// Returns the index of the page that contains the given offset.
+fn page_position(page_offsets: &[usize], offset: usize) -> usize {
+    page_offsets.iter().rposition(|&page_offset| offset >= page_offset)
+                .unwrap_or(0)
 }
 
 impl Document for EpubDocument {
@@ -606,8 +800,11 @@ impl Document for EpubDocument {
     }
 
     #[inline]
-    fn dims(&self, _index: usize) -> Option<(f32, f32)> {
-        Some((self.engine.dims.0 as f32, self.engine.dims.1 as f32))
+    fn dims(&self, index: usize) -> Option<(f32, f32)> {
+        let (width, height) = self.vertebra_coordinates(index)
+                                  .and_then(|(index, _)| self.spine[index].viewport)
+                                  .unwrap_or(self.engine.dims);
+        Some((width as f32, height as f32))
     }
 
     fn pages_count(&self) -> usize {
@@ -659,6 +856,37 @@ impl Document for EpubDocument {
         }
     }
 
+    fn landmarks(&mut self) -> Vec<Landmark> {
+        let nav_name = self.info.root().find("manifest")
+                           .and_then(|manifest| manifest.children().find(|child| {
+                               child.attribute("properties").iter()
+                                    .any(|props| props.split_whitespace().any(|prop| prop == "nav"))
+                           }))
+                           .and_then(|entry| entry.attribute("href"))
+                           .map(|href| self.parent.join(href).normalize()
+                                           .to_string_lossy().into_owned());
+
+        let mut landmarks = nav_name.and_then(|name| {
+            let mut text = String::new();
+            self.archive.by_name(&name).ok()?.read_to_string(&mut text).ok()?;
+            let root = XmlParser::new(&text).parse();
+            let nav_dir = Path::new(&name).parent()
+                               .unwrap_or_else(|| Path::new(""));
+            root.root().descendants()
+                .find(|desc| desc.tag_name() == Some("nav") &&
+                             desc.attribute("epub:type") == Some("landmarks"))
+                .map(|nav| nav_landmarks(nav, nav_dir))
+        }).unwrap_or_default();
+
+        if landmarks.is_empty() {
+            if let Some(guide) = self.info.root().find("guide") {
+                landmarks = guide_landmarks(guide, &self.parent);
+            }
+        }
+
+        landmarks
+    }
+
     fn chapter<'a>(&mut self, offset: usize, toc: &'a [TocEntry]) -> Option<(&'a TocEntry, f32)> {
         let next_offset = self.resolve_location(Location::Next(offset))
                               .unwrap_or(usize::MAX);
@@ -715,46 +943,37 @@ impl Document for EpubDocument {
         match loc {
             Location::Exact(offset) => {
                 let (index, start_offset) = self.vertebra_coordinates(offset)?;
-                let page_index = self.page_index(offset, index, start_offset)?;
-                self.cache.get(&index)
-                    .and_then(|display_list| display_list[page_index].first())
-                    .map(DrawCommand::offset)
+                let page_offsets = self.page_offsets(index, start_offset)?;
+                page_offsets.get(page_position(page_offsets, offset)).cloned()
             },
             Location::Previous(offset) => {
                 let (index, start_offset) = self.vertebra_coordinates(offset)?;
-                let page_index = self.page_index(offset, index, start_offset)?;
+                let page_offsets = self.page_offsets(index, start_offset)?;
+                let page_index = page_position(page_offsets, offset);
                 if page_index > 0 {
-                    self.cache.get(&index)
-                        .and_then(|display_list| display_list[page_index-1].first().map(DrawCommand::offset))
+                    page_offsets.get(page_index-1).cloned()
                 } else {
                     if index == 0 {
                         return None;
                     }
                     let (index, start_offset) = (index - 1, start_offset - self.spine[index-1].size);
-                    if !self.cache.contains_key(&index) {
-                        let display_list = self.build_display_list(index, start_offset);
-                        self.cache.insert(index, display_list);
-                    }
-                    self.cache.get(&index)
-                        .and_then(|display_list| display_list.last().and_then(|page| page.first()).map(DrawCommand::offset))
+                    self.page_offsets(index, start_offset)
+                        .and_then(|page_offsets| page_offsets.last().cloned())
                 }
             },
             Location::Next(offset) => {
                 let (index, start_offset) = self.vertebra_coordinates(offset)?;
-                let page_index = self.page_index(offset, index, start_offset)?;
-                if page_index < self.cache.get(&index).map(Vec::len)? - 1 {
-                    self.cache.get(&index).and_then(|display_list| display_list[page_index+1].first().map(DrawCommand::offset))
+                let page_offsets = self.page_offsets(index, start_offset)?;
+                let page_index = page_position(page_offsets, offset);
+                if page_index < page_offsets.len() - 1 {
+                    page_offsets.get(page_index+1).cloned()
                 } else {
                     if index == self.spine.len() - 1 {
                         return None;
                     }
                     let (index, start_offset) = (index + 1, start_offset + self.spine[index].size);
-                    if !self.cache.contains_key(&index) {
-                        let display_list = self.build_display_list(index, start_offset);
-                        self.cache.insert(index, display_list);
-                    }
-                    self.cache.get(&index)
-                        .and_then(|display_list| display_list.first().and_then(|page| page.first()).map(|dc| dc.offset()))
+                    self.page_offsets(index, start_offset)
+                        .and_then(|page_offsets| page_offsets.first().cloned())
                 }
             },
             Location::LocalUri(offset, ref uri) => {
@@ -835,6 +1054,73 @@ impl Document for EpubDocument {
         })
     }
 
+    fn tables(&mut self, loc: Location) -> Option<(Vec<(Boundary, usize)>, usize)> {
+        if self.spine.is_empty() {
+            return None;
+        }
+
+        let offset = self.resolve_location(loc)?;
+        let (index, start_offset) = self.vertebra_coordinates(offset)?;
+        let page_index = self.page_index(offset, index, start_offset)?;
+
+        self.cache.get(&index).map(|display_list| {
+            (display_list[page_index].iter().filter_map(|dc| {
+                match dc {
+                    DrawCommand::Table(TableCommand { rect, offset }) => Some(((*rect).into(), *offset)),
+                    _ => None,
+                }
+            }).collect(), offset)
+        })
+    }
+
+    // The table is laid out on a single page of the given width.
+    fn table_pixmap(&mut self, offset: usize, width: u32, scale: f32) -> Option<Pixmap> {
+        if self.spine.is_empty() {
+            return None;
+        }
+
+        self.engine.load_fonts();
+        let (index, start_offset) = self.vertebra_coordinates(offset)?;
+        let (root, stylesheet, spine_dir) = self.load_chunk(index);
+        let table = root.root().descendants().find(|node| {
+            node.tag_name() == Some("table") && start_offset + node.offset() == offset
+        })?;
+
+        let dims = self.engine.dims;
+        self.engine.dims = (width, TABLE_HEIGHT_RATIO * dims.1);
+        let mut rect = self.engine.rect();
+        rect.shrink(&self.engine.margin);
+
+        let style = self.root_style(&root, &rect);
+        let mut draw_state = DrawState {
+            position: rect.min,
+            .. Default::default()
+        };
+        let root_data = RootData {
+            start_offset,
+            spine_dir,
+            rect,
+        };
+
+        let mut display_list = vec![Vec::new()];
+        self.engine.build_display_list(table, &style, &LoopContext::default(), &stylesheet, &root_data, &mut self.archive, &mut draw_state, &mut display_list);
+
+        // The rows that don't fit are left out.
+        let page = display_list.into_iter().next().unwrap_or_default();
+        let bounds = page.iter().filter_map(|dc| dc.rect()).reduce(|mut a, b| {
+            a.absorb(&b);
+            a
+        });
+        let pixmap = bounds.and_then(|bounds| {
+            let dims = ((bounds.max.x + self.engine.margin.right).max(0) as u32,
+                        (bounds.max.y + self.engine.margin.bottom).max(0) as u32);
+            self.engine.render_page_with_dims(&page, dims, scale, &mut self.archive)
+        });
+
+        self.engine.dims = dims;
+        pixmap
+    }
+
     fn images(&mut self, loc: Location) -> Option<(Vec<Boundary>, usize)> {
         if self.spine.is_empty() {
             return None;
@@ -865,49 +1151,94 @@ impl Document for EpubDocument {
         let page_index = self.page_index(offset, index, start_offset)?;
         let page = self.cache.get(&index)?.get(page_index)?.clone();
 
-        let pixmap = self.engine.render_page(&page, scale, &mut self.archive)?;
+        let pixmap = if let Some(viewport) = self.spine[index].viewport {
+            let state = self.enter_viewport(viewport);
+            let pixmap = self.engine.render_page_with_dims(&page, viewport, scale, &mut self.archive);
+            self.leave_viewport(state);
+            pixmap?
+        } else {
+            self.engine.render_page(&page, scale, &mut self.archive)?
+        };
 
         Some((pixmap, offset))
     }
 
     fn layout(&mut self, width: u32, height: u32, font_size: f32, dpi: u16) {
         self.engine.layout(width, height, font_size, dpi);
-        self.cache.clear();
+        self.clear_layout();
     }
 
     fn set_text_align(&mut self, text_align: TextAlign) {
         self.engine.set_text_align(text_align);
-        self.cache.clear();
+        self.clear_layout();
     }
 
     fn set_font_family(&mut self, family_name: &str, search_path: &str) {
         self.engine.set_font_family(family_name, search_path);
-        self.cache.clear();
+        self.font_family = Some(family_name.to_string());
+        self.clear_layout();
     }
 
     fn set_margin_width(&mut self, width: i32) {
         self.engine.set_margin_width(width);
-        self.cache.clear();
+        self.clear_layout();
+    }
+
+    fn set_vertical_margin_width(&mut self, width: Option<i32>) {
+        self.engine.set_vertical_margin_width(width);
+        self.clear_layout();
     }
 
     fn set_line_height(&mut self, line_height: f32) {
         self.engine.set_line_height(line_height);
-        self.cache.clear();
+        self.clear_layout();
     }
 
     fn set_hyphen_penalty(&mut self, hyphen_penalty: i32) {
         self.engine.set_hyphen_penalty(hyphen_penalty);
-        self.cache.clear();
+        self.clear_layout();
     }
 
     fn set_stretch_tolerance(&mut self, stretch_tolerance: f32) {
         self.engine.set_stretch_tolerance(stretch_tolerance);
-        self.cache.clear();
+        self.clear_layout();
+    }
+
+    fn set_typography(&mut self, typography: Typography) {
+        self.engine.set_typography(typography);
+        self.clear_layout();
     }
 
     fn set_ignore_document_css(&mut self, ignore: bool) {
         self.ignore_document_css = ignore;
-        self.cache.clear();
+        self.clear_layout();
+    }
+
+    fn set_language(&mut self, language: &str) {
+        self.language = Some(language.to_string());
+        self.clear_layout();
+    }
+
+    fn load_layout_cache(&mut self, path: &Path) {
+        if let Ok(layout_cache) = load_json::<LayoutCache, _>(path) {
+            if layout_cache.signature == self.layout_signature() {
+                self.pages = layout_cache.pages;
+                // The display lists at hand might disagree with the cache.
+                self.cache.clear();
+                self.engine.clear_inline_images();
+            }
+        }
+    }
+
+    fn save_layout_cache(&self, path: &Path) -> Result<(), Error> {
+        if self.pages.is_empty() {
+            return Ok(());
+        }
+        let layout_cache = LayoutCache {
+            signature: self.layout_signature(),
+            pages: self.pages.clone(),
+        };
+        save_json(&layout_cache, path)
     }
 
     fn title(&self) -> Option<String> {
@@ -926,10 +1257,57 @@ impl Document for EpubDocument {
     }
 
     fn is_reflowable(&self) -> bool {
-        true
+        self.spine.iter().any(|chunk| chunk.viewport.is_none())
     }
 
     fn has_synthetic_page_numbers(&self) -> bool {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_viewport() {
+        assert_eq!(parse_viewport("width=1200, height=1600"), Some((1200, 1600)));
+        assert_eq!(parse_viewport("height = 800px; width = 600px"), Some((600, 800)));
+        assert_eq!(parse_viewport("width=device-width, initial-scale=1"), None);
+    }
+
+    #[test]
+    fn test_landmarks() {
+        let text = r#"<html><body>
+            <nav epub:type="landmarks"><ol>
+              <li><a epub:type="cover" href="cover.xhtml"></a></li>
+              <li><a epub:type="bodymatter" href="text/ch01.xhtml#start">Begin Reading</a></li>
+              <li><a role="doc-endnotes" href="text/notes.xhtml">Notes</a></li>
+              <li><a epub:type="loi" href="text/figures%20list.xhtml">Illustrations</a></li>
+              <li><a epub:type="colophon" href="text/colophon.xhtml"></a></li>
+            </ol></nav></body></html>"#;
+        let root = XmlParser::new(text).parse();
+        let nav = root.root().descendants().find(|node| node.tag_name() == Some("nav")).unwrap();
+        let landmarks = nav_landmarks(nav, Path::new("OEBPS/nav"));
+        let summary = landmarks.iter().map(|landmark| {
+            let uri = match landmark.location { Location::Uri(ref uri) => uri.as_str(), _ => "" };
+            (landmark.kind, landmark.title.as_str(), uri)
+        }).collect::<Vec<_>>();
+        assert_eq!(summary, vec![(LandmarkKind::Cover, "Cover", "OEBPS/nav/cover.xhtml"),
+                                 (LandmarkKind::BodyMatter, "Begin Reading", "OEBPS/nav/text/ch01.xhtml#start"),
+                                 (LandmarkKind::Notes, "Notes", "OEBPS/nav/text/notes.xhtml"),
+                                 (LandmarkKind::Figures, "Illustrations", "OEBPS/nav/text/figures list.xhtml")]);
+
+        let text = r#"<package><guide>
+            <reference type="toc" title="Table of Contents" href="toc.html"/>
+            <reference type="text" href="../ch01.html"/>
+            </guide></package>"#;
+        let root = XmlParser::new(text).parse();
+        let guide = root.root().find("guide").unwrap();
+        let kinds = guide_landmarks(guide, Path::new("OPS/xhtml")).iter()
+                                  .map(|landmark| (landmark.kind, landmark.title.clone()))
+                                  .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(LandmarkKind::Toc, "Table of Contents".to_string()),
+                               (LandmarkKind::BodyMatter, "Start".to_string())]);
+    }
+}
@@ -1,5 +1,6 @@
-use std::path::PathBuf;
+use std::mem;
 use std::convert::TryFrom;
+use fxhash::FxHashMap;
 use anyhow::Error;
 use kl_hyphenate::{Standard, Hyphenator, Iter};
 use paragraph_breaker::{Item as ParagraphItem, Breakpoint, INFINITE_PENALTY};
@@ -24,10 +25,13 @@ use super::parse::{parse_line_height, parse_vertical_align, parse_color, parse_l
 use super::dom::{NodeRef, NodeData, ElementData, TextData, WRAPPER_TAG_NAME};
 use super::layout::{StyleData, InlineMaterial, TextMaterial, ImageMaterial};
 use super::layout::{GlueMaterial, PenaltyMaterial, ChildArtifact, SiblingStyle, LoopContext};
-use super::layout::{RootData, DrawState, DrawCommand, TextCommand, ImageCommand, FontKind, Fonts};
+use super::layout::{RootData, DrawState, DrawCommand, TextCommand, ImageCommand, TableCommand, FontKind, Fonts};
 use super::layout::{TextAlign, ParagraphElement, TextElement, ImageElement, Display, Float};
-use super::layout::{WordSpacing, ListStyleType, LineStats};
-use super::layout::{hyph_lang, collapse_margins, DEFAULT_HYPH_LANG, HYPHENATION_PATTERNS};
+use super::layout::{WordSpacing, ListStyleType, LineStats, FontStyle};
+use super::math::{linearize, MathItem, MathRun};
+use super::layout::{hyph_lang, collapse_margins, distribute_column_widths, spread_spanning_width};
+use super::layout::{DEFAULT_HYPH_LANG, HYPHENATION_PATTERNS};
+use super::layout::{Typography, HANGING_PUNCTUATION, join_hyphen_segments, page_breaks};
 use super::layout::{EM_SPACE_RATIOS, WORD_SPACE_RATIOS, FONT_SPACES};
 use super::style::{StyleSheet, specified_values};
 use super::xml::XmlExt;
@@ -35,6 +39,9 @@ use super::xml::XmlExt;
 const DEFAULT_DPI: u16 = 300;
 const DEFAULT_WIDTH: u32 = 1404;
 const DEFAULT_HEIGHT: u32 = 1872;
+// The number of rasterized vector images kept around.
+const RASTERS_CACHE_SIZE: usize = 32;
+const SVG_NAMESPACES: &str = r#"xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink""#;
 
 pub type Page = Vec<DrawCommand>;
 
@@ -50,8 +57,11 @@ pub struct Engine {
     hyphen_penalty: i32,
     // The stretching/shrinking allowed for word spaces.
     stretch_tolerance: f32,
+    typography: Typography,
     // Page margins in pixels.
     pub margin: Edge,
+    // Overrides the top and bottom margins, in millimeters.
+    vertical_margin_width: Option<i32>,
     // Font size in points.
     pub font_size: f32,
     // Text alignment.
@@ -62,6 +72,10 @@ pub struct Engine {
     pub dims: (u32, u32),
     // Device DPI.
     pub dpi: u16,
+    // The SVG documents embedded in the content, by synthetic path, until the next layout.
+    inline_images: FxHashMap<String, Vec<u8>>,
+    // The rasterized SVG images, by path and scale.
+    rasters: FxHashMap<(String, u32), Pixmap>,
 }
 
 impl Engine {
@@ -73,12 +87,16 @@ impl Engine {
             fonts: None,
             hyphen_penalty: HYPHEN_PENALTY,
             stretch_tolerance: STRETCH_TOLERANCE,
+            typography: Typography::default(),
             margin,
             font_size: DEFAULT_FONT_SIZE,
             text_align: DEFAULT_TEXT_ALIGN,
             line_height,
             dims: (DEFAULT_WIDTH, DEFAULT_HEIGHT),
             dpi: DEFAULT_DPI,
+            vertical_margin_width: None,
+            inline_images: FxHashMap::default(),
+            rasters: FxHashMap::default(),
         }
     }
 
@@ -89,6 +107,42 @@ impl Engine {
         }
     }
 
+    // Forgets the SVG documents embedded in the content that was laid out.
+    pub fn clear_inline_images(&mut self) {
+        self.inline_images.clear();
+        self.rasters.clear();
+    }
+
+    fn fetch_resource(&self, path: &str, resource_fetcher: &mut dyn ResourceFetcher) -> Result<Vec<u8>, Error> {
+        if let Some(buf) = self.inline_images.get(path) {
+            return Ok(buf.clone());
+        }
+        resource_fetcher.fetch(path)
+    }
+
+    fn render_image(&mut self, path: &str, scale: f32, resource_fetcher: &mut dyn ResourceFetcher) -> Option<Pixmap> {
+        let is_vector = path.ends_with(".svg");
+        let key = (path.to_string(), (scale * 1000.0).round() as u32);
+        if is_vector {
+            if let Some(pixmap) = self.rasters.get(&key) {
+                return Some(pixmap.clone());
+            }
+        }
+        let buf = self.fetch_resource(path, resource_fetcher).ok()?;
+        let (pixmap, _) = PdfOpener::new().and_then(|opener| {
+            opener.open_memory(path, &buf)
+        }).and_then(|mut doc| {
+            doc.pixmap(Location::Exact(0), scale)
+        })?;
+        if is_vector {
+            if self.rasters.len() >= RASTERS_CACHE_SIZE {
+                self.rasters.clear();
+            }
+            self.rasters.insert(key, pixmap.clone());
+        }
+        Some(pixmap)
+    }
+
     pub fn set_hyphen_penalty(&mut self, hyphen_penalty: i32) {
         self.hyphen_penalty = hyphen_penalty;
     }
@@ -97,6 +151,10 @@ impl Engine {
         self.stretch_tolerance = stretch_tolerance;
     }
 
+    pub fn set_typography(&mut self, typography: Typography) {
+        self.typography = typography;
+    }
+
     pub fn set_margin(&mut self, margin: &Edge) {
         self.margin = *margin;
     }
@@ -112,6 +170,13 @@ impl Engine {
         self.font_size = font_size;
     }
 
+    // Describes every parameter that affects the pagination.
+    pub fn layout_signature(&self) -> String {
+        format!("{} {} {:?} {:?} {:?} {} {} {:?} {}",
+                self.hyphen_penalty, self.stretch_tolerance, self.typography,
+                self.margin, self.text_align, self.font_size, self.line_height, self.dims, self.dpi)
+    }
+
     pub fn set_text_align(&mut self, text_align: TextAlign) {
         self.text_align = text_align;
     }
@@ -127,10 +192,26 @@ impl Engine {
 
     pub fn set_margin_width(&mut self, width: i32) {
         if width >= 0 && width <= 10 {
-            self.margin = Edge::uniform(mm_to_px(width as f32, self.dpi).round() as i32);
+            let margin = mm_to_px(width as f32, self.dpi).round() as i32;
+            self.margin.left = margin;
+            self.margin.right = margin;
+            if self.vertical_margin_width.is_none() {
+                self.margin.top = margin;
+                self.margin.bottom = margin;
+            }
         }
     }
 
+    pub fn set_vertical_margin_width(&mut self, width: Option<i32>) {
+        if width.is_some_and(|width| !(0..=10).contains(&width)) {
+            return;
+        }
+        self.vertical_margin_width = width;
+        let margin = width.map_or(self.margin.left, |width| mm_to_px(width as f32, self.dpi).round() as i32);
+        self.margin.top = margin;
+        self.margin.bottom = margin;
+    }
+
     pub fn set_line_height(&mut self, line_height: f32) {
         self.line_height = line_height;
     }
@@ -173,9 +254,19 @@ impl Engine {
                 draw_state.column_widths.clear();
                 draw_state.min_column_widths.clear();
                 draw_state.max_column_widths.clear();
+                draw_state.spanning_cells.clear();
                 draw_state.center_table = style.display == Display::InlineTable &&
                                           parent_style.text_align == TextAlign::Center;
                 self.compute_column_widths(node, parent_style, loop_context, stylesheet, root_data, resource_fetcher, draw_state);
+                for (index, colspan, min_width, max_width) in mem::take(&mut draw_state.spanning_cells) {
+                    let columns_count = draw_state.min_column_widths.len();
+                    if index + colspan > columns_count {
+                        draw_state.min_column_widths.resize(index + colspan, 0);
+                        draw_state.max_column_widths.resize(index + colspan, 0);
+                    }
+                    spread_spanning_width(&mut draw_state.min_column_widths[index..index+colspan], min_width);
+                    spread_spanning_width(&mut draw_state.max_column_widths[index..index+colspan], max_width);
+                }
                 draw_state.position = position;
             },
             _ => (),
@@ -314,6 +405,8 @@ impl Engine {
             draw_state.position.y = root_data.rect.min.y;
         }
 
+        let first_page = display_list.len() - 1;
+
         draw_state.position.y += style.padding.top;
 
         let has_blocks = node.children().any(|n| n.is_block());
@@ -331,27 +424,9 @@ impl Engine {
                     inner_loop_context.is_last = loop_context.is_last;
 
                     if draw_state.column_widths.is_empty() {
-                        let min_row_width: i32 = draw_state.min_column_widths.iter().sum();
-                        let max_row_width: i32 = draw_state.max_column_widths.iter().sum();
-                        // https://www.w3.org/MarkUp/html3/tables.html
-                        if min_row_width >= width {
-                            draw_state.column_widths =
-                                draw_state.min_column_widths.iter()
-                                          .map(|w| ((*w as f32 / min_row_width as f32) *
-                                                   width as f32).round() as i32)
-                                          .collect();
-                        } else if max_row_width <= width {
-                            draw_state.column_widths = draw_state.max_column_widths.clone();
-                        } else {
-                            let dw = (width - min_row_width) as f32;
-                            let dr = (max_row_width - min_row_width) as f32;
-                            let gf = dw / dr;
-                            draw_state.column_widths =
-                                draw_state.min_column_widths.iter()
-                                          .zip(draw_state.max_column_widths.iter())
-                                          .map(|(a, b)| a + ((b - a) as f32 * gf).round() as i32)
-                                          .collect();
-                        }
+                        draw_state.column_widths = distribute_column_widths(&draw_state.min_column_widths,
+                                                                            &draw_state.max_column_widths,
+                                                                            width);
                     }
 
                     if draw_state.center_table {
@@ -482,7 +557,7 @@ impl Engine {
                     markers.push(node.offset());
                 }
                 for child in node.children() {
-                    self.gather_inline_material(child, stylesheet, &style, &root_data.spine_dir, &mut markers, &mut inlines);
+                    self.gather_inline_material(child, stylesheet, &style, root_data, &mut markers, &mut inlines);
                 }
                 if !inlines.is_empty() {
                     draw_state.prefix = match style.list_style_type {
@@ -525,6 +600,16 @@ impl Engine {
 
         draw_state.position.y += style.padding.bottom;
 
+        // Lets the reader open the tables in their own viewer.
+        if node.tag_name() == Some("table") {
+            let offset = root_data.start_offset + node.offset();
+            for (i, rect) in rects.iter().enumerate() {
+                if let (Some(rect), Some(page)) = (rect, display_list.get_mut(first_page + i)) {
+                    page.push(DrawCommand::Table(TableCommand { offset, rect: *rect }));
+                }
+            }
+        }
+
         if props.get("page-break-after").map(String::as_str) == Some("always") {
             display_list.push(Vec::new());
             draw_state.position.y = root_data.rect.min.y;
@@ -563,7 +648,9 @@ impl Engine {
                 let max_width = artifact.rects.into_iter()
                                         .filter_map(|v| v.map(|r| r.width() as i32 + horiz_padding))
                                         .max().unwrap_or(0);
-                if colspan == 1 {
+                if colspan > 1 {
+                    draw_state.spanning_cells.push((index, colspan, min_width, max_width));
+                } else {
                     if let Some(cw) = draw_state.min_column_widths.get_mut(index) {
                         *cw = (*cw).max(min_width);
                     } else {
@@ -585,7 +672,7 @@ impl Engine {
         }
     }
 
-    fn gather_inline_material(&self, node: NodeRef, stylesheet: &StyleSheet, parent_style: &StyleData, spine_dir: &PathBuf, markers: &mut Vec<usize>, inlines: &mut Vec<InlineMaterial>) {
+    fn gather_inline_material(&mut self, node: NodeRef, stylesheet: &StyleSheet, parent_style: &StyleData, root_data: &RootData, markers: &mut Vec<usize>, inlines: &mut Vec<InlineMaterial>) {
         match node.data() {
             NodeData::Element(ElementData { offset, name, attributes, .. }) => {
                 let mut style = StyleData::default();
@@ -661,15 +748,26 @@ impl Engine {
                 }
 
                 match name.as_ref() {
-                    "img" | "image" => {
-                        let attr = if name == "img" { "src" } else { "xlink:href" };
-
-                        let path = attributes.get(attr).and_then(|src| {
-                            spine_dir.join(src).normalize().to_str()
-                                     .map(|uri| percent_decode_str(&decode_entities(uri))
-                                                                  .decode_utf8_lossy()
-                                                                  .into_owned())
-                        }).unwrap_or_default();
+                    // SVG elements wrapping a bitmap are handled through their image element.
+                    "img" | "image" | "svg" if name != "svg" || node.find("image").is_none() => {
+                        let path = if name == "svg" {
+                            // The offset is unique across the chapters of the document.
+                            let path = format!("{}.svg", root_data.start_offset + *offset);
+                            let mut xml = node.to_xml();
+                            if !attributes.contains_key("xmlns") {
+                                xml = xml.replacen("<svg", &format!("<svg {}", SVG_NAMESPACES), 1);
+                            }
+                            self.inline_images.insert(path.clone(), xml.into_bytes());
+                            path
+                        } else {
+                            let attr = if name == "img" { "src" } else { "xlink:href" };
+                            attributes.get(attr).and_then(|src| {
+                                root_data.spine_dir.join(src).normalize().to_str()
+                                         .map(|uri| percent_decode_str(&decode_entities(uri))
+                                                                      .decode_utf8_lossy()
+                                                                      .into_owned())
+                            }).unwrap_or_default()
+                        };
 
                         style.float = props.get("float").and_then(|value| parse_float(value));
 
@@ -703,6 +801,31 @@ impl Engine {
                         inlines.push(InlineMaterial::LineBreak);
                         return;
                     },
+                    "math" => {
+                        let is_block = style.display == Display::Block ||
+                                       attributes.get("display").map(String::as_str) == Some("block");
+                        if is_block {
+                            inlines.push(InlineMaterial::LineBreak);
+                        }
+                        for item in linearize(node) {
+                            match item {
+                                MathItem::Text(MathRun { offset, text, italic, scale, shift }) => {
+                                    let mut run_style = style.clone();
+                                    run_style.font_size *= scale;
+                                    run_style.vertical_align += pt_to_px(shift * style.font_size, self.dpi).round() as i32;
+                                    if italic {
+                                        run_style.font_style = FontStyle::Italic;
+                                    }
+                                    inlines.push(InlineMaterial::Text(TextMaterial { offset, text, style: run_style }));
+                                },
+                                MathItem::LineBreak => inlines.push(InlineMaterial::LineBreak),
+                            }
+                        }
+                        if is_block {
+                            inlines.push(InlineMaterial::LineBreak);
+                        }
+                        return;
+                    },
                     _ => {},
                 }
 
@@ -712,7 +835,7 @@ impl Engine {
                 }
 
                 for child in node.children() {
-                    self.gather_inline_material(child, stylesheet, &style, spine_dir, markers, inlines);
+                    self.gather_inline_material(child, stylesheet, &style, root_data, markers, inlines);
                 }
 
                 if let Some(mut v) = props.get("-plato-insert-after")
@@ -763,7 +886,7 @@ impl Engine {
                     let mut scale = 1.0;
                     let dpi = self.dpi;
 
-                    if let Ok(buf) = resource_fetcher.fetch(path) {
+                    if let Ok(buf) = self.fetch_resource(path, resource_fetcher) {
                         if let Some(doc) = PdfOpener::new().and_then(|opener| opener.open_memory(path, &buf)) {
                             if let Some((w, h)) = doc.dims(0) {
                                 if width == 0 && height == 0 {
@@ -916,7 +1039,10 @@ impl Engine {
 
                                     match parent_style.text_align {
                                         TextAlign::Justify => {
-                                            items.push(ParagraphItem::Glue { width, stretch: width/2, shrink: width/3 });
+                                            let (min_spacing, max_spacing) = self.typography.word_spacing;
+                                            let stretch = ((max_spacing - 1.0).max(0.0) * width as f32) as i32;
+                                            let shrink = ((1.0 - min_spacing).clamp(0.0, 1.0) * width as f32) as i32;
+                                            items.push(ParagraphItem::Glue { width, stretch, shrink });
                                         },
                                         TextAlign::Center => {
                                             if style.font_kind == FontKind::Monospace || is_unbreakable {
@@ -1126,7 +1252,7 @@ impl Engine {
         let mut glue_drifts = Vec::new();
 
         if bps.is_empty() {
-            let dictionary = if style.text_align == TextAlign::Justify {
+            let dictionary = if style.text_align == TextAlign::Justify && self.typography.hyphenation {
                 hyph_lang(style.language.as_ref().map_or(DEFAULT_HYPH_LANG, String::as_str))
                          .and_then(|lang| HYPHENATION_PATTERNS.get(&lang))
             } else {
@@ -1176,6 +1302,27 @@ impl Engine {
             items = self.cleanup_paragraph(items, &hyph_indices, &mut glue_drifts, &mut bps);
         }
 
+        let max_y = root_data.rect.max.y - space_bottom;
+        let has_images = items.iter().any(|itm| matches!(itm, ParagraphItem::Box { data: ParagraphElement::Image(..), .. }));
+
+        // The lines of the paragraphs without floats or images all have the same height,
+        // the page breaks that avoid the widows and the orphans can be planned.
+        let breaks = if (self.typography.orphans > 1 || self.typography.widows > 1) && style.line_height > 0 &&
+                        !has_images && !draw_state.floats.contains_key(&page_index) {
+            let first = ((max_y - position.y) / style.line_height + 1).max(0) as usize;
+            let full = ((max_y - root_data.rect.min.y - space_top) / style.line_height + 1).max(0) as usize;
+            page_breaks(bps.len(), first, full, self.typography.orphans, self.typography.widows)
+        } else {
+            Vec::new()
+        };
+
+        if breaks.first() == Some(&0) {
+            rects.push(page_rect.take());
+            display_list.push(page);
+            position.y = root_data.rect.min.y + space_top;
+            page = Vec::new();
+        }
+
         let mut last_index = 0;
         let mut markers_index = 0;
         let mut last_x_position = 0;
@@ -1235,6 +1382,29 @@ impl Engine {
                 last_index += 1;
             }
 
+            // Stretch the spaces a bit more, to push the final punctuation into the margin.
+            if self.typography.hanging_punctuation && style.text_align == TextAlign::Justify &&
+               ratio.is_finite() && index < items.len() - 1 {
+                let hang = self.hanging_width(&items, last_index, index);
+                if hang > 0 {
+                    let line_length = line_lengths[j.min(line_lengths.len() - 1)] + hang;
+                    let (stretch, shrink) = items[last_index..index].iter().fold((0, 0), |(st, sh), itm| {
+                        match itm {
+                            ParagraphItem::Glue { stretch, shrink, .. } => (st + stretch, sh + shrink),
+                            _ => (st, sh),
+                        }
+                    });
+                    let delta = line_length - width;
+                    let amplitude = if delta >= 0 { stretch } else { shrink };
+                    if amplitude > 0 {
+                        let hanging_ratio = delta as f32 / amplitude as f32;
+                        if hanging_ratio.abs() <= self.stretch_tolerance {
+                            ratio = hanging_ratio;
+                        }
+                    }
+                }
+            }
+
             let start_command_index = page.len();
 
             for i in last_index..index {
@@ -1437,7 +1607,7 @@ impl Engine {
                 position.y += style.line_height;
             }
 
-            if position.y > root_data.rect.max.y - space_bottom {
+            if position.y > max_y || breaks.contains(&(j + 1)) {
                 rects.push(page_rect.take());
                 display_list.push(page);
                 position.y = root_data.rect.min.y + space_top;
@@ -1465,6 +1635,35 @@ impl Engine {
         display_list.push(page);
     }
 
+    // The width of the punctuation that ends the line between `start` and `end`.
+    fn hanging_width(&mut self, items: &[ParagraphItem<ParagraphElement>], start: usize, end: usize) -> i32 {
+        if let ParagraphItem::Penalty { width, .. } = items[end] {
+            if width > 0 {
+                return width;
+            }
+        }
+
+        let last_box = items[start..end].iter().rev().find_map(|itm| match itm {
+            ParagraphItem::Box { width, data } if *width > 0 => Some(data),
+            _ => None,
+        });
+
+        if let Some(ParagraphElement::Text(element)) = last_box {
+            let count = element.text.chars().rev()
+                               .take_while(|c| HANGING_PUNCTUATION.contains(*c))
+                               .count();
+            if count > 0 {
+                let index = element.text.char_indices().rev().nth(count - 1).map_or(0, |(i, _)| i);
+                let font = self.fonts.as_mut().unwrap()
+                               .get_mut(element.font_kind, element.font_style, element.font_weight);
+                font.set_size(element.font_size, self.dpi);
+                return font.plan(&element.text[index..], None, element.font_features.as_deref()).width;
+            }
+        }
+
+        0
+    }
+
     #[inline]
     fn box_from_chunk(&mut self, chunk: &str, index: usize, element: &TextElement) -> ParagraphItem<ParagraphElement> {
         let offset = element.offset + index;
@@ -1529,7 +1728,9 @@ impl Engine {
                             let mut index = 0;
                             let chunk = &text[index_before..index_after];
                             let len_before = hyph_items.len();
-                            for segment in dict.hyphenate(chunk).iter().segments() {
+                            let word = dict.hyphenate(chunk);
+                            let segments = word.iter().segments().collect::<Vec<&str>>();
+                            for segment in join_hyphen_segments(chunk, &segments, self.typography.hyphen_minima) {
                                 let subelem = self.box_from_chunk(segment,
                                                                   index_before + index,
                                                                   element);
@@ -1671,8 +1872,13 @@ impl Engine {
     }
 
     pub fn render_page(&mut self, page: &[DrawCommand], scale_factor: f32, resource_fetcher: &mut dyn ResourceFetcher) -> Option<Pixmap> {
-        let width = (self.dims.0 as f32 * scale_factor) as u32;
-        let height = (self.dims.1 as f32 * scale_factor) as u32;
+        self.render_page_with_dims(page, self.dims, scale_factor, resource_fetcher)
+    }
+
+    // Renders a page whose dimensions differ from the layout's, e.g. a pre-paginated page.
+    pub fn render_page_with_dims(&mut self, page: &[DrawCommand], dims: (u32, u32), scale_factor: f32, resource_fetcher: &mut dyn ResourceFetcher) -> Option<Pixmap> {
+        let width = (dims.0 as f32 * scale_factor) as u32;
+        let height = (dims.1 as f32 * scale_factor) as u32;
         let mut fb = Pixmap::try_new(width, height)?;
 
         for dc in page {
@@ -1690,15 +1896,9 @@ impl Engine {
                     font.render(&mut fb, *color, &plan, position);
                 },
                 DrawCommand::Image(ImageCommand { position, path, scale, .. }) => {
-                    if let Ok(buf) = resource_fetcher.fetch(path) {
-                        if let Some((pixmap, _)) = PdfOpener::new().and_then(|opener| {
-                            opener.open_memory(path, &buf)
-                        }).and_then(|mut doc| {
-                            doc.pixmap(Location::Exact(0), scale_factor * *scale)
-                        }) {
-                            let position = Point::from(scale_factor * Vec2::from(*position));
-                            fb.draw_pixmap(&pixmap, position);
-                        }
+                    if let Some(pixmap) = self.render_image(path, scale_factor * *scale, resource_fetcher) {
+                        let position = Point::from(scale_factor * Vec2::from(*position));
+                        fb.draw_pixmap(&pixmap, position);
                     }
                 },
                 _ => (),
@@ -10,6 +10,8 @@ pub use crate::metadata::TextAlign;
 use crate::color::BLACK;
 
 pub const DEFAULT_HYPH_LANG: &str = "en";
+// The characters that may hang in the right margin.
+pub const HANGING_PUNCTUATION: &str = ".,:;-\u{2010}\u{2013}\u{2014}'\u{2019}\"\u{201D}\u{00BB}\u{203A}";
 
 #[derive(Debug, Clone)]
 pub struct RootData {
@@ -26,6 +28,8 @@ pub struct DrawState {
     pub min_column_widths: Vec<i32>,
     pub max_column_widths: Vec<i32>,
     pub column_widths: Vec<i32>,
+    // The first column, the number of columns, and the minimum and maximum widths of the spanning cells.
+    pub spanning_cells: Vec<(usize, usize, i32, i32)>,
     pub center_table: bool,
 }
 
@@ -38,6 +42,7 @@ impl Default for DrawState {
             min_column_widths: Vec::new(),
             max_column_widths: Vec::new(),
             column_widths: Vec::new(),
+            spanning_cells: Vec::new(),
             center_table: false,
         }
     }
@@ -357,6 +362,8 @@ pub enum DrawCommand {
     Text(TextCommand),
     ExtraText(TextCommand),
     Image(ImageCommand),
+    // The area covered by a table on a page.
+    Table(TableCommand),
     Marker(usize),
 }
 
@@ -385,12 +392,19 @@ pub struct ImageCommand {
     pub rect: Rectangle,
 }
 
+#[derive(Debug, Clone)]
+pub struct TableCommand {
+    pub offset: usize,
+    pub rect: Rectangle,
+}
+
 impl DrawCommand {
     pub fn offset(&self) -> usize {
         match *self {
             DrawCommand::Text(TextCommand { offset, .. }) => offset,
             DrawCommand::ExtraText(TextCommand { offset, .. }) => offset,
             DrawCommand::Image(ImageCommand { offset, .. }) => offset,
+            DrawCommand::Table(TableCommand { offset, .. }) => offset,
             DrawCommand::Marker(offset) => offset,
         }
     }
@@ -424,10 +438,176 @@ pub fn collapse_margins(a: i32, b: i32) -> i32 {
     }
 }
 
+// The typographic rules of the line and page breaking.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Typography {
+    // Whether the justified paragraphs are hyphenated.
+    pub hyphenation: bool,
+    // The narrowest and the widest word spaces of the justified lines, relative to
+    // their natural width, when the stretch tolerance is one.
+    pub word_spacing: (f32, f32),
+    // Whether the punctuation that ends a justified line hangs in the right margin.
+    pub hanging_punctuation: bool,
+    // The minimum number of letters before and after a hyphen.
+    pub hyphen_minima: (usize, usize),
+    // The minimum number of lines of a paragraph left at the bottom of a page.
+    pub orphans: usize,
+    // The minimum number of lines of a paragraph carried over to the top of a page.
+    pub widows: usize,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Typography {
+            hyphenation: true,
+            word_spacing: (0.67, 1.5),
+            hanging_punctuation: false,
+            hyphen_minima: (2, 2),
+            orphans: 1,
+            widows: 1,
+        }
+    }
+}
+
+// Joins the hyphenation segments of a word that would leave too few letters before or after a hyphen.
+pub fn join_hyphen_segments<'a>(word: &'a str, segments: &[&str], (before, after): (usize, usize)) -> Vec<&'a str> {
+    let count = word.chars().count();
+    let mut result = Vec::with_capacity(segments.len());
+    let (mut start, mut end, mut letters) = (0, 0, 0);
+    for (index, segment) in segments.iter().enumerate() {
+        end += segment.len();
+        letters += segment.chars().count();
+        if index == segments.len() - 1 || (letters >= before && count - letters >= after) {
+            result.push(&word[start..end]);
+            start = end;
+        }
+    }
+    result
+}
+
+// Returns the indices of the lines of a paragraph that start a new page.
+// `first` lines fit on the current page, and `full` lines on the next ones.
+pub fn page_breaks(lines: usize, first: usize, full: usize, orphans: usize, widows: usize) -> Vec<usize> {
+    if first >= lines || full == 0 {
+        return Vec::new();
+    }
+
+    let mut sizes = Vec::new();
+    let mut capacity = first;
+
+    // Move the whole paragraph to the next page rather than leaving orphans.
+    if first < orphans.min(lines) {
+        sizes.push(0);
+        capacity = full;
+    }
+
+    let mut remaining = lines;
+    while remaining > 0 {
+        let size = capacity.min(remaining);
+        sizes.push(size);
+        remaining -= size;
+        capacity = full;
+    }
+
+    // Carry more lines over to the last page, if the previous one can spare them.
+    let count = sizes.len();
+    if count >= 2 && widows <= full {
+        let last = sizes[count - 1];
+        let previous = sizes[count - 2];
+        if last < widows && previous >= widows - last + orphans.max(1) {
+            sizes[count - 2] -= widows - last;
+            sizes[count - 1] = widows;
+        }
+    }
+
+    sizes.iter().take(count - 1)
+         .scan(0, |index, size| { *index += size; Some(*index) })
+         .collect()
+}
+
+// Widens the columns spanned by a cell until they can hold it.
+pub fn spread_spanning_width(widths: &mut [i32], width: i32) {
+    let total: i32 = widths.iter().sum();
+    if total >= width || widths.is_empty() {
+        return;
+    }
+    let extra = width - total;
+    let count = widths.len() as i32;
+    for (index, w) in widths.iter_mut().enumerate() {
+        *w += extra / count + if (index as i32) < extra % count { 1 } else { 0 };
+    }
+}
+
+// Shares the available width between the columns, given their minimum and maximum widths.
+// https://www.w3.org/MarkUp/html3/tables.html
+pub fn distribute_column_widths(min_column_widths: &[i32], max_column_widths: &[i32], width: i32) -> Vec<i32> {
+    let min_row_width: i32 = min_column_widths.iter().sum();
+    let max_row_width: i32 = max_column_widths.iter().sum();
+    if min_row_width >= width {
+        min_column_widths.iter()
+                         .map(|w| ((*w as f32 / min_row_width.max(1) as f32) *
+                                  width as f32).round() as i32)
+                         .collect()
+    } else if max_row_width <= width {
+        max_column_widths.to_vec()
+    } else {
+        let dw = (width - min_row_width) as f32;
+        let dr = (max_row_width - min_row_width) as f32;
+        let gf = dw / dr;
+        min_column_widths.iter()
+                         .zip(max_column_widths.iter())
+                         .map(|(a, b)| a + ((b - a) as f32 * gf).round() as i32)
+                         .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_join_hyphen_segments() {
+        let word = "hyphenation";
+        let segments = ["hy", "phen", "a", "tion"];
+        assert_eq!(join_hyphen_segments(word, &segments, (2, 2)), vec!["hy", "phen", "a", "tion"]);
+        assert_eq!(join_hyphen_segments(word, &segments, (3, 5)), vec!["hyphen", "ation"]);
+        assert_eq!(join_hyphen_segments(word, &segments, (7, 1)), vec!["hyphena", "tion"]);
+        assert_eq!(join_hyphen_segments(word, &segments, (9, 9)), vec!["hyphenation"]);
+        assert_eq!(join_hyphen_segments("été", &["é", "té"], (1, 2)), vec!["é", "té"]);
+        assert_eq!(join_hyphen_segments("été", &["é", "té"], (2, 2)), vec!["été"]);
+    }
+
+    #[test]
+    fn test_page_breaks() {
+        // The paragraph fits.
+        assert_eq!(page_breaks(3, 3, 10, 2, 2), Vec::<usize>::new());
+        assert_eq!(page_breaks(5, 2, 10, 1, 1), vec![2]);
+        // An orphan moves the paragraph to the next page.
+        assert_eq!(page_breaks(5, 1, 10, 2, 1), vec![0]);
+        // A widow takes a line from the previous page.
+        assert_eq!(page_breaks(5, 4, 10, 2, 2), vec![3]);
+        assert_eq!(page_breaks(24, 3, 10, 2, 3), vec![3, 13, 21]);
+        // Unless the previous page would be left with orphans.
+        assert_eq!(page_breaks(3, 2, 10, 2, 2), vec![2]);
+        assert_eq!(page_breaks(3, 2, 10, 1, 2), vec![1]);
+    }
+
+    #[test]
+    fn test_distribute_column_widths() {
+        assert_eq!(distribute_column_widths(&[10, 20], &[40, 80], 200), vec![40, 80]);
+        assert_eq!(distribute_column_widths(&[10, 20], &[40, 80], 15), vec![5, 10]);
+        assert_eq!(distribute_column_widths(&[10, 20], &[30, 40], 50), vec![20, 30]);
+    }
+
+    #[test]
+    fn test_spread_spanning_width() {
+        let mut widths = [10, 20, 30];
+        spread_spanning_width(&mut widths, 40);
+        assert_eq!(widths, [10, 20, 30]);
+        spread_spanning_width(&mut widths, 71);
+        assert_eq!(widths, [14, 24, 33]);
+    }
+
     #[test]
     fn test_hyph_lang() {
         assert_eq!(hyph_lang("zh-latn-pinyin"), Some(Language::Chinese));
@@ -0,0 +1,277 @@
+use super::dom::{NodeRef, NodeData, TextData};
+
+// The font size ratio of the scripts.
+const SCRIPT_SCALE: f32 = 0.7;
+// The smallest font size ratio.
+const MIN_SCALE: f32 = 0.5;
+// The baseline shifts of the superscripts and subscripts, in ems.
+const SUPERSCRIPT_SHIFT: f32 = 0.4;
+const SUBSCRIPT_SHIFT: f32 = -0.2;
+
+// A piece of a formula, written on a single line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathItem {
+    Text(MathRun),
+    LineBreak,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MathRun {
+    pub offset: usize,
+    pub text: String,
+    pub italic: bool,
+    // The font size ratio, relatively to the formula's.
+    pub scale: f32,
+    // The baseline shift, in ems of the formula's font size.
+    pub shift: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Level {
+    scale: f32,
+    shift: f32,
+}
+
+impl Level {
+    fn script(self, shift: f32) -> Level {
+        Level {
+            scale: (self.scale * SCRIPT_SCALE).max(MIN_SCALE),
+            shift: self.shift + shift * self.scale,
+        }
+    }
+}
+
+// Binary operators and relations are surrounded by spaces.
+const SPACED_OPERATORS: &[&str] = &["+", "-", "−", "=", "≠", "<", ">", "≤", "≥", "≈", "≡",
+                                    "×", "÷", "±", "∓", "→", "←", "↔", "⇒", "⇔", "∈", "∉",
+                                    "⊂", "⊆", "∪", "∩", "∧", "∨"];
+
+// Turns a MathML element into a linear formula, e.g. `(a + b)/2` for a fraction.
+pub fn linearize(node: NodeRef) -> Vec<MathItem> {
+    let mut items = Vec::new();
+    gather(node, Level { scale: 1.0, shift: 0.0 }, &mut items);
+    items
+}
+
+fn push_text(items: &mut Vec<MathItem>, offset: usize, text: &str, italic: bool, level: Level) {
+    if text.is_empty() {
+        return;
+    }
+    items.push(MathItem::Text(MathRun {
+        offset,
+        text: text.to_string(),
+        italic,
+        scale: level.scale,
+        shift: level.shift,
+    }));
+}
+
+fn element_children(node: NodeRef) -> Vec<NodeRef> {
+    node.children().filter(|child| child.is_element()).collect()
+}
+
+// Whether the node is written with a single token, and doesn't need parentheses.
+fn is_atomic(node: NodeRef) -> bool {
+    match node.tag_name() {
+        Some("mi") | Some("mn") | Some("mtext") | Some("ms") => true,
+        Some("mrow") | Some("mstyle") => {
+            let children = element_children(node);
+            children.len() == 1 && is_atomic(children[0])
+        },
+        Some("msup") | Some("msub") | Some("msubsup") | Some("msqrt") => true,
+        _ => false,
+    }
+}
+
+// Wraps the node between parentheses when it isn't atomic.
+fn gather_group(node: NodeRef, level: Level, items: &mut Vec<MathItem>) {
+    if is_atomic(node) {
+        gather(node, level, items);
+    } else {
+        push_text(items, node.offset(), "(", false, level);
+        gather(node, level, items);
+        push_text(items, node.offset(), ")", false, level);
+    }
+}
+
+fn gather_children(node: NodeRef, level: Level, items: &mut Vec<MathItem>) {
+    for child in node.children() {
+        gather(child, level, items);
+    }
+}
+
+fn gather(node: NodeRef, level: Level, items: &mut Vec<MathItem>) {
+    let name = match node.data() {
+        NodeData::Element(..) => node.tag_name().unwrap_or_default(),
+        NodeData::Text(TextData { offset, text }) => {
+            push_text(items, *offset, text.trim(), false, level);
+            return;
+        },
+        _ => return,
+    };
+
+    let offset = node.offset();
+    let children = element_children(node);
+
+    match name {
+        "mi" => {
+            let text = node.text();
+            let text = text.trim();
+            // Single letter identifiers are italic by default.
+            let italic = match node.attribute("mathvariant") {
+                Some(variant) => variant.contains("italic"),
+                None => text.chars().count() == 1,
+            };
+            push_text(items, offset, text, italic, level);
+        },
+        "mn" | "mtext" => {
+            push_text(items, offset, node.text().trim(), false, level);
+        },
+        "ms" => {
+            push_text(items, offset, &format!("\"{}\"", node.text().trim()), false, level);
+        },
+        "mo" => {
+            let text = node.text();
+            let text = text.trim();
+            if SPACED_OPERATORS.contains(&text) {
+                push_text(items, offset, &format!(" {} ", text), false, level);
+            } else if text == "," || text == ";" {
+                push_text(items, offset, &format!("{} ", text), false, level);
+            } else {
+                push_text(items, offset, text, false, level);
+            }
+        },
+        "mspace" => {
+            push_text(items, offset, " ", false, level);
+        },
+        "msup" | "mover" if children.len() >= 2 => {
+            gather(children[0], level, items);
+            gather(children[1], level.script(SUPERSCRIPT_SHIFT), items);
+        },
+        "msub" | "munder" if children.len() >= 2 => {
+            gather(children[0], level, items);
+            gather(children[1], level.script(SUBSCRIPT_SHIFT), items);
+        },
+        "msubsup" | "munderover" if children.len() >= 3 => {
+            gather(children[0], level, items);
+            gather(children[1], level.script(SUBSCRIPT_SHIFT), items);
+            gather(children[2], level.script(SUPERSCRIPT_SHIFT), items);
+        },
+        "mmultiscripts" if !children.is_empty() => {
+            gather(children[0], level, items);
+            for pair in children[1..].chunks(2) {
+                if pair[0].tag_name() == Some("mprescripts") {
+                    break;
+                }
+                if pair[0].tag_name() != Some("none") {
+                    gather(pair[0], level.script(SUBSCRIPT_SHIFT), items);
+                }
+                if let Some(sup) = pair.get(1).filter(|sup| sup.tag_name() != Some("none")) {
+                    gather(*sup, level.script(SUPERSCRIPT_SHIFT), items);
+                }
+            }
+        },
+        "mfrac" if children.len() >= 2 => {
+            gather_group(children[0], level, items);
+            push_text(items, offset, "/", false, level);
+            gather_group(children[1], level, items);
+        },
+        "msqrt" => {
+            push_text(items, offset, "√", false, level);
+            if children.len() == 1 && is_atomic(children[0]) {
+                gather(children[0], level, items);
+            } else {
+                push_text(items, offset, "(", false, level);
+                gather_children(node, level, items);
+                push_text(items, offset, ")", false, level);
+            }
+        },
+        "mroot" if children.len() >= 2 => {
+            gather(children[1], level.script(SUPERSCRIPT_SHIFT), items);
+            push_text(items, offset, "√", false, level);
+            gather_group(children[0], level, items);
+        },
+        "mfenced" => {
+            let open = node.attribute("open").unwrap_or("(");
+            let close = node.attribute("close").unwrap_or(")");
+            let separators: Vec<char> = node.attribute("separators").unwrap_or(",")
+                                            .chars().filter(|c| !c.is_whitespace()).collect();
+            push_text(items, offset, open, false, level);
+            for (index, child) in children.iter().enumerate() {
+                if index > 0 {
+                    let separator = separators.get(index - 1).or_else(|| separators.last());
+                    if let Some(separator) = separator {
+                        push_text(items, offset, &format!("{} ", separator), false, level);
+                    }
+                }
+                gather(*child, level, items);
+            }
+            push_text(items, offset, close, false, level);
+        },
+        "mtable" => {
+            for (index, row) in children.iter().enumerate() {
+                if index > 0 {
+                    items.push(MathItem::LineBreak);
+                }
+                for (index, cell) in element_children(*row).into_iter().enumerate() {
+                    if index > 0 {
+                        push_text(items, offset, "  ", false, level);
+                    }
+                    gather(cell, level, items);
+                }
+            }
+        },
+        // The first child holds the presentation markup.
+        "semantics" | "maction" => {
+            if let Some(child) = children.first() {
+                gather(*child, level, items);
+            }
+        },
+        "annotation" | "annotation-xml" | "mphantom" | "none" | "mprescripts" => (),
+        _ => gather_children(node, level, items),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::xml::XmlParser;
+
+    fn linear_text(text: &str) -> String {
+        let tree = XmlParser::new(text).parse();
+        let math = tree.root().find("math").unwrap();
+        linearize(math).into_iter().map(|item| match item {
+            MathItem::Text(run) => run.text,
+            MathItem::LineBreak => "\n".to_string(),
+        }).collect()
+    }
+
+    #[test]
+    fn test_fraction() {
+        let text = "<math><mfrac><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mn>2</mn></mfrac></math>";
+        assert_eq!(linear_text(text), "(a + b)/2");
+    }
+
+    #[test]
+    fn test_scripts() {
+        let text = "<math><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><msub><mi>y</mi><mi>i</mi></msub></math>";
+        assert_eq!(linear_text(text), "x2 + yi");
+        let tree = XmlParser::new(text).parse();
+        let items = linearize(tree.root().find("math").unwrap());
+        if let MathItem::Text(run) = &items[1] {
+            assert_eq!(run.scale, SCRIPT_SCALE);
+            assert!(run.shift > 0.0);
+        }
+        if let MathItem::Text(run) = &items[4] {
+            assert!(run.shift < 0.0);
+        }
+    }
+
+    #[test]
+    fn test_roots_and_annotations() {
+        let text = "<math><semantics><mrow><msqrt><mi>x</mi></msqrt><mo>=</mo>\
+                    <mroot><mrow><mi>y</mi><mo>+</mo><mn>1</mn></mrow><mn>3</mn></mroot></mrow>\
+                    <annotation encoding=\"application/x-tex\">\\sqrt{x}</annotation></semantics></math>";
+        assert_eq!(linear_text(text), "√x = 3√(y + 1)");
+    }
+}
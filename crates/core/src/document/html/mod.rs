@@ -4,6 +4,7 @@ pub mod css;
 pub mod parse;
 pub mod style;
 pub mod layout;
+pub mod math;
 pub mod engine;
 
 use std::io::{Read, Write};
@@ -18,7 +19,7 @@ use crate::unit::pt_to_px;
 use crate::geom::{Boundary, Edge, CycleDir};
 use self::dom::{XmlTree, NodeRef};
 use self::layout::{RootData, StyleData, DrawState, LoopContext};
-use self::layout::{DrawCommand, TextCommand, ImageCommand, TextAlign};
+use self::layout::{DrawCommand, TextCommand, ImageCommand, TextAlign, Typography};
 use self::engine::{Page, Engine, ResourceFetcher};
 use self::style::StyleSheet;
 use self::css::CssParser;
@@ -39,6 +40,7 @@ pub struct HtmlDocument {
     viewer_stylesheet: PathBuf,
     user_stylesheet: PathBuf,
     ignore_document_css: bool,
+    language: Option<String>,
 }
 
 impl ResourceFetcher for PathBuf {
@@ -73,6 +75,7 @@ impl HtmlDocument {
             viewer_stylesheet: PathBuf::from(VIEWER_STYLESHEET),
             user_stylesheet: PathBuf::from(USER_STYLESHEET),
             ignore_document_css: false,
+            language: None,
         })
     }
 
@@ -91,6 +94,7 @@ impl HtmlDocument {
             viewer_stylesheet: PathBuf::from(VIEWER_STYLESHEET),
             user_stylesheet: PathBuf::from(USER_STYLESHEET),
             ignore_document_css: false,
+            language: None,
         }
     }
 
@@ -160,6 +164,7 @@ impl HtmlDocument {
     }
 
     fn build_pages(&mut self) -> Vec<Page> {
+        self.engine.clear_inline_images();
         let mut stylesheet = StyleSheet::new();
         let spine_dir = PathBuf::default();
 
@@ -204,10 +209,12 @@ impl HtmlDocument {
         let mut rect = self.engine.rect();
         rect.shrink(&self.engine.margin);
 
-        let language = self.content.root()
-                           .find("html")
-                           .and_then(|html| html.attribute("xml:lang"))
-                           .map(String::from);
+        let language = self.language.clone().or_else(|| {
+            self.content.root()
+                .find("html")
+                .and_then(|html| html.attribute("xml:lang"))
+                .map(String::from)
+        });
 
         let style = StyleData {
             language,
@@ -401,6 +408,11 @@ impl Document for HtmlDocument {
         self.pages.clear();
     }
 
+    fn set_vertical_margin_width(&mut self, width: Option<i32>) {
+        self.engine.set_vertical_margin_width(width);
+        self.pages.clear();
+    }
+
     fn set_line_height(&mut self, line_height: f32) {
         self.engine.set_line_height(line_height);
         self.pages.clear();
@@ -416,11 +428,21 @@ impl Document for HtmlDocument {
         self.pages.clear();
     }
 
+    fn set_typography(&mut self, typography: Typography) {
+        self.engine.set_typography(typography);
+        self.pages.clear();
+    }
+
     fn set_ignore_document_css(&mut self, ignore: bool) {
         self.ignore_document_css = ignore;
         self.pages.clear();
     }
 
+    fn set_language(&mut self, language: &str) {
+        self.language = Some(language.to_string());
+        self.pages.clear();
+    }
+
     fn title(&self) -> Option<String> {
         self.content.root()
             .find("head")
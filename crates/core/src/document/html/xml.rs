@@ -167,6 +167,14 @@ impl XmlExt for char {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_xml() {
+        let text = r#"<svg viewBox="0 0 10 10"><path d="M0 0L10 10" style='fill:"x"'/> <g>&amp;</g></svg>"#;
+        let xml = XmlParser::new(text).parse();
+        let n = xml.root().first_child().unwrap();
+        assert_eq!(n.to_xml(), r#"<svg viewBox="0 0 10 10"><path d="M0 0L10 10" style="fill:&quot;x&quot;"/> <g>&amp;</g></svg>"#);
+    }
+
     #[test]
     fn test_simple_element() {
         let text = "<a/>";
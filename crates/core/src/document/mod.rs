@@ -2,6 +2,8 @@ pub mod djvu;
 pub mod pdf;
 pub mod epub;
 pub mod html;
+pub mod archive;
+pub mod pse;
 
 mod djvulibre_sys;
 mod mupdf_sys;
@@ -27,11 +29,14 @@ use self::djvu::DjvuOpener;
 use self::pdf::PdfOpener;
 use self::epub::EpubDocument;
 use self::html::HtmlDocument;
-use crate::geom::{Boundary, CycleDir};
+use self::pse::PseDocument;
+use self::html::layout::Typography;
+use crate::geom::{Boundary, CycleDir, Rectangle};
 use crate::metadata::{TextAlign, Annotation};
-use crate::framebuffer::Pixmap;
+use crate::framebuffer::{Pixmap, pool_stats};
 use crate::settings::INTERNAL_CARD_ROOT;
 use crate::device::CURRENT_DEVICE;
+use crate::view::Hub;
 
 pub const BYTES_PER_PAGE: f64 = 2048.0;
 
@@ -84,6 +89,41 @@ pub struct TocEntry {
     pub children: Vec<TocEntry>,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LandmarkKind {
+    Cover,
+    Toc,
+    BodyMatter,
+    Notes,
+    Figures,
+    Tables,
+    Index,
+    Other,
+}
+
+impl LandmarkKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LandmarkKind::Cover => "Cover",
+            LandmarkKind::Toc => "Contents",
+            LandmarkKind::BodyMatter => "Start",
+            LandmarkKind::Notes => "Notes",
+            LandmarkKind::Figures => "List of Figures",
+            LandmarkKind::Tables => "List of Tables",
+            LandmarkKind::Index => "Index",
+            LandmarkKind::Other => "Other",
+        }
+    }
+}
+
+// The major structural parts of a document, declared by its publisher.
+#[derive(Debug, Clone)]
+pub struct Landmark {
+    pub kind: LandmarkKind,
+    pub title: String,
+    pub location: Location,
+}
+
 #[derive(Debug, Clone)]
 pub struct Neighbors {
     pub previous_page: Option<usize>,
@@ -106,11 +146,15 @@ pub trait Document: Send+Sync {
     fn layout(&mut self, width: u32, height: u32, font_size: f32, dpi: u16);
     fn set_font_family(&mut self, family_name: &str, search_path: &str);
     fn set_margin_width(&mut self, width: i32);
+    // Overrides the margin width for the top and bottom margins.
+    fn set_vertical_margin_width(&mut self, width: Option<i32>);
     fn set_text_align(&mut self, text_align: TextAlign);
     fn set_line_height(&mut self, line_height: f32);
     fn set_hyphen_penalty(&mut self, hyphen_penalty: i32);
     fn set_stretch_tolerance(&mut self, stretch_tolerance: f32);
+    fn set_typography(&mut self, typography: Typography);
     fn set_ignore_document_css(&mut self, ignore: bool);
+    fn set_language(&mut self, language: &str);
 
     fn title(&self) -> Option<String>;
     fn author(&self) -> Option<String>;
@@ -122,10 +166,41 @@ pub trait Document: Send+Sync {
         false
     }
 
+    // The areas and offsets of the tables.
+    fn tables(&mut self, _loc: Location) -> Option<(Vec<(Boundary, usize)>, usize)> {
+        None
+    }
+
+    // Renders the table at the given offset laid out for the given width.
+    fn table_pixmap(&mut self, _offset: usize, _width: u32, _scale: f32) -> Option<Pixmap> {
+        None
+    }
+
     fn save(&self, _path: &str) -> Result<(), Error> {
         Err(format_err!("this document can't be saved"))
     }
 
+    fn load_layout_cache(&mut self, _path: &Path) {
+    }
+
+    fn save_layout_cache(&self, _path: &Path) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // The pages that aren't available yet are announced through the given hub when they arrive.
+    fn set_hub(&mut self, _hub: &Hub) {
+    }
+
+    // Whether the pages can be rendered area by area, through `tile_pixmap`.
+    fn has_tiles(&self) -> bool {
+        false
+    }
+
+    // Renders the given area of the page scaled by the given factor.
+    fn tile_pixmap(&mut self, _loc: Location, _scale: f32, _rect: &Rectangle) -> Option<Pixmap> {
+        None
+    }
+
     fn preview_pixmap(&mut self, width: f32, height: f32) -> Option<Pixmap> {
         self.dims(0).and_then(|dims| {
             let scale = (width / dims.0).min(height / dims.1);
@@ -134,34 +209,43 @@ pub trait Document: Send+Sync {
     }
 
     fn resolve_location(&mut self, loc: Location) -> Option<usize> {
-        if self.pages_count() == 0 {
-            return None;
-        }
+        resolve_page_location(loc, self.pages_count())
+    }
 
-        match loc {
-            Location::Exact(index) => {
-                if index >= self.pages_count() {
-                    None
-                } else {
-                    Some(index)
-                }
-            },
-            Location::Previous(index) => {
-                if index > 0 {
-                    Some(index - 1)
-                } else {
-                    None
-                }
-            },
-            Location::Next(index) => {
-                if index < self.pages_count() - 1 {
-                    Some(index + 1)
-                } else {
-                    None
-                }
+    fn landmarks(&mut self) -> Vec<Landmark> {
+        Vec::new()
+    }
+}
+
+// Resolves the locations that don't depend on the document's content.
+pub fn resolve_page_location(loc: Location, pages_count: usize) -> Option<usize> {
+    if pages_count == 0 {
+        return None;
+    }
+
+    match loc {
+        Location::Exact(index) => {
+            if index >= pages_count {
+                None
+            } else {
+                Some(index)
+            }
+        },
+        Location::Previous(index) => {
+            if index > 0 {
+                Some(index - 1)
+            } else {
+                None
+            }
+        },
+        Location::Next(index) => {
+            if index < pages_count - 1 {
+                Some(index + 1)
+            } else {
+                None
             }
-            _ => None,
         }
+        _ => None,
     }
 }
 
@@ -240,6 +324,11 @@ pub fn open<P: AsRef<Path>>(path: P) -> Option<Box<dyn Document>> {
                      .map(|d| Box::new(d) as Box<dyn Document>)
                 })
             },
+            "pse" => {
+                PseDocument::new(&path)
+                            .map_err(|e| eprintln!("{}: {:#}.", path.as_ref().display(), e))
+                            .map(|d| Box::new(d) as Box<dyn Document>).ok()
+            },
             _ => {
                 PdfOpener::new().and_then(|o| {
                     o.open(path)
@@ -250,6 +339,31 @@ pub fn open<P: AsRef<Path>>(path: P) -> Option<Box<dyn Document>> {
     })
 }
 
+// Opens the document at *path*, unlocking it with the first matching
+// password if it's protected.
+pub fn open_with_passwords<P: AsRef<Path>, S: AsRef<str>>(path: P, passwords: &[S]) -> Option<Box<dyn Document>> {
+    match file_kind(path.as_ref()).as_deref() {
+        Some("epub") | Some("html") | Some("htm") | Some("djvu") | Some("djv") | Some("pse") | None => open(path),
+        _ => {
+            PdfOpener::new().and_then(|o| o.open(path)).and_then(|mut doc| {
+                if !doc.is_protected() || passwords.iter().any(|p| doc.authenticate(p.as_ref())) {
+                    Some(Box::new(doc) as Box<dyn Document>)
+                } else {
+                    None
+                }
+            })
+        },
+    }
+}
+
+pub fn is_protected<P: AsRef<Path>>(path: P) -> bool {
+    match file_kind(path.as_ref()).as_deref() {
+        Some("epub") | Some("html") | Some("htm") | Some("djvu") | Some("djv") | Some("pse") | None => false,
+        _ => PdfOpener::new().and_then(|o| o.open(path))
+                             .is_some_and(|doc| doc.is_protected()),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SimpleTocEntry {
@@ -456,6 +570,25 @@ pub fn chapter_from_uri<'a>(target_uri: &str, toc: &'a [TocEntry]) -> Option<&'a
     None
 }
 
+// The entry at the given position, counted from one, of the flattened table of contents.
+pub fn toc_entry_at(toc: &[TocEntry], position: usize) -> Option<&TocEntry> {
+    toc_entry_at_aux(toc, position, &mut 0)
+}
+
+fn toc_entry_at_aux<'a>(toc: &'a [TocEntry], position: usize, count: &mut usize) -> Option<&'a TocEntry> {
+    for entry in toc {
+        *count += 1;
+        if *count == position {
+            return Some(entry);
+        }
+        let result = toc_entry_at_aux(&entry.children, position, count);
+        if result.is_some() {
+            return result;
+        }
+    }
+    None
+}
+
 const CPUINFO_KEYS: [&str; 3] = ["Processor", "Features", "Hardware"];
 const HWINFO_KEYS: [&str; 19] = ["CPU", "PCB", "DisplayPanel", "DisplayCtrl", "DisplayBusWidth",
                                  "DisplayResolution", "FrontLight", "FrontLight_LEDrv", "FL_PWM",
@@ -535,6 +668,18 @@ pub fn sys_info_as_html() -> String {
         buf.push_str("\t\t\t</tr>\n");
     }
 
+    let stats = pool_stats();
+    buf.push_str("\t\t\t<tr>\n");
+    buf.push_str("\t\t\t\t<td>Pixmap Pool (Hits / Misses)</td>\n");
+    buf.push_str(&format!("\t\t\t\t<td>{} / {}</td>\n", stats.hits, stats.misses));
+    buf.push_str("\t\t\t</tr>\n");
+    buf.push_str("\t\t\t<tr>\n");
+    buf.push_str("\t\t\t\t<td>Pixmap Pool (Idle)</td>\n");
+    buf.push_str(&format!("\t\t\t\t<td>{} in {} buffers</td>\n",
+                          (stats.pooled_bytes as u64).human_size(),
+                          stats.pooled_buffers));
+    buf.push_str("\t\t\t</tr>\n");
+
     buf.push_str("\t\t\t<tr class=\"sep\"></tr>\n");
 
     if let Ok(info) = fs::read_to_string("/proc/cpuinfo") {
@@ -52,8 +52,10 @@ extern {
     pub fn fz_drop_stream(ctx: *mut FzContext, stream: *mut FzStream);
     pub fn mp_count_pages(ctx: *mut FzContext, doc: *mut FzDocument) -> libc::c_int;
     pub fn mp_page_number_from_location(ctx: *mut FzContext, doc: *mut FzDocument, loc: FzLocation) -> libc::c_int;
+    pub fn mp_resolve_link(ctx: *mut FzContext, doc: *mut FzDocument, uri: *const libc::c_char, xp: *mut libc::c_float, yp: *mut libc::c_float) -> FzLocation;
     pub fn fz_lookup_metadata(ctx: *mut FzContext, doc: *mut FzDocument, key: *const libc::c_char, buf: *mut libc::c_char, size: libc::c_int) -> libc::c_int;
     pub fn fz_needs_password(ctx: *mut FzContext, doc: *mut FzDocument) -> libc::c_int;
+    pub fn fz_authenticate_password(ctx: *mut FzContext, doc: *mut FzDocument, password: *const libc::c_char) -> libc::c_int;
     pub fn fz_is_document_reflowable(ctx: *mut FzContext, doc: *mut FzDocument) -> libc::c_int;
     pub fn fz_layout_document(ctx: *mut FzContext, doc: *mut FzDocument, w: libc::c_float, h: libc::c_float, em: libc::c_float);
     pub fn mp_load_outline(ctx: *mut FzContext, doc: *mut FzDocument) -> *mut FzOutline;
@@ -62,6 +64,7 @@ extern {
     pub fn fz_device_gray(ctx: *mut FzContext) -> *mut FzColorspace;
     pub fn fz_scale(sx: libc::c_float, sy: libc::c_float) -> FzMatrix;
     pub fn mp_new_pixmap_from_page(ctx: *mut FzContext, page: *mut FzPage, mat: FzMatrix, cs: *mut FzColorspace, alpha: libc::c_int) -> *mut FzPixmap;
+    pub fn mp_new_pixmap_from_page_area(ctx: *mut FzContext, page: *mut FzPage, mat: FzMatrix, area: FzIrect, cs: *mut FzColorspace) -> *mut FzPixmap;
     pub fn fz_set_pixmap_resolution(ctx: *mut FzContext, pix: *mut FzPixmap, xres: libc::c_int, yres: libc::c_int);
     pub fn fz_drop_pixmap(ctx: *mut FzContext, pixmap: *mut FzPixmap);
     pub fn mp_load_page(ctx: *mut FzContext, doc: *mut FzDocument, page_idx: libc::c_int) -> *mut FzPage;
@@ -102,6 +105,15 @@ impl Default for FzRect {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FzIrect {
+    pub x0: libc::c_int,
+    pub y0: libc::c_int,
+    pub x1: libc::c_int,
+    pub y1: libc::c_int,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct FzPoint {
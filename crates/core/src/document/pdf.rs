@@ -11,11 +11,13 @@ use std::ffi::{CString, CStr};
 use std::os::unix::ffi::OsStrExt;
 use anyhow::Error;
 use super::{Document, Location, TextLocation, BoundedText, TocEntry};
-use super::{chapter, chapter_relative};
+use super::{chapter, chapter_relative, resolve_page_location};
+use super::archive;
 use crate::metadata::TextAlign;
-use crate::geom::{Boundary, CycleDir};
+use super::html::layout::Typography;
+use crate::geom::{Boundary, CycleDir, Rectangle};
 use crate::unit::pt_to_px;
-use crate::framebuffer::Pixmap;
+use crate::framebuffer::{Pixmap, take_buffer};
 
 impl Into<Boundary> for FzRect {
     fn into(self) -> Boundary {
@@ -33,6 +35,8 @@ pub struct PdfOpener(Rc<PdfContext>);
 pub struct PdfDocument {
     ctx: Rc<PdfContext>,
     doc: *mut FzDocument,
+    // The bytes read by the document, when it was opened from memory.
+    _buffer: Vec<u8>,
 }
 
 pub struct PdfPage<'a> {
@@ -58,6 +62,14 @@ impl PdfOpener {
     }
 
     pub fn open<P: AsRef<Path>>(&self, path: P) -> Option<PdfDocument> {
+        // The entries of ZIP archives are read in memory.
+        if let Some((zip_path, name)) = archive::resolve(path.as_ref()) {
+            return archive::read_entry(&zip_path, &name)
+                           .map_err(|e| eprintln!("Can't read {}: {:#}.", path.as_ref().display(), e))
+                           .ok()
+                           .and_then(|buf| self.open_buffer(&name, buf));
+        }
+
         unsafe {
             let c_path = CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
             let doc = mp_open_document((self.0).0, c_path.as_ptr());
@@ -67,6 +79,7 @@ impl PdfOpener {
                 Some(PdfDocument {
                     ctx: self.0.clone(),
                     doc,
+                    _buffer: Vec::new(),
                 })
             }
         }
@@ -87,11 +100,20 @@ impl PdfOpener {
                 Some(PdfDocument {
                     ctx: self.0.clone(),
                     doc,
+                    _buffer: Vec::new(),
                 })
             }
         }
     }
 
+    // Same as `open_memory`, but the document keeps the buffer alive.
+    pub fn open_buffer(&self, magic: &str, buf: Vec<u8>) -> Option<PdfDocument> {
+        self.open_memory(magic, &buf).map(|mut doc| {
+            doc._buffer = buf;
+            doc
+        })
+    }
+
     pub fn set_user_css<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
         let mut file = File::open(path)?;
         let mut buf = Vec::new();
@@ -158,9 +180,34 @@ impl PdfDocument {
         }
     }
 
+    // Resolves the internal links, e.g. `#page=12` or `#nameddest=figure.3`.
+    fn resolve_link(&self, uri: &str) -> Option<usize> {
+        let c_uri = CString::new(uri).ok()?;
+        unsafe {
+            let loc = mp_resolve_link(self.ctx.0, self.doc, c_uri.as_ptr(), ptr::null_mut(), ptr::null_mut());
+            if loc.page < 0 {
+                return None;
+            }
+            let num = mp_page_number_from_location(self.ctx.0, self.doc, loc);
+            if num > -1 {
+                Some(num as usize)
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn is_protected(&self) -> bool {
         unsafe { fz_needs_password(self.ctx.0, self.doc) == 1 }
     }
+
+    pub fn authenticate(&mut self, password: &str) -> bool {
+        let c_password = match CString::new(password) {
+            Ok(c_password) => c_password,
+            Err(_) => return false,
+        };
+        unsafe { fz_authenticate_password(self.ctx.0, self.doc, c_password.as_ptr()) != 0 }
+    }
 }
 
 impl Document for PdfDocument {
@@ -177,6 +224,15 @@ impl Document for PdfDocument {
         self.page(index).and_then(|page| page.pixmap(scale)).map(|pixmap| (pixmap, index))
     }
 
+    fn has_tiles(&self) -> bool {
+        true
+    }
+
+    fn tile_pixmap(&mut self, loc: Location, scale: f32, rect: &Rectangle) -> Option<Pixmap> {
+        let index = self.resolve_location(loc)?;
+        self.page(index).and_then(|page| page.tile_pixmap(scale, rect))
+    }
+
     fn toc(&mut self) -> Option<Vec<TocEntry>> {
         unsafe {
             let outline = mp_load_outline(self.ctx.0, self.doc);
@@ -232,6 +288,13 @@ impl Document for PdfDocument {
         self.page(index).and_then(|page| page.links()).map(|links| (links, index))
     }
 
+    fn resolve_location(&mut self, loc: Location) -> Option<usize> {
+        match loc {
+            Location::Uri(ref uri) | Location::LocalUri(_, ref uri) => self.resolve_link(uri),
+            _ => resolve_page_location(loc, self.pages_count()),
+        }
+    }
+
     fn title(&self) -> Option<String> {
         self.metadata(FZ_META_INFO_TITLE)
     }
@@ -263,6 +326,9 @@ impl Document for PdfDocument {
     fn set_margin_width(&mut self, _width: i32) {
     }
 
+    fn set_vertical_margin_width(&mut self, _width: Option<i32>) {
+    }
+
     fn set_line_height(&mut self, _line_height: f32) {
     }
 
@@ -272,11 +338,17 @@ impl Document for PdfDocument {
     fn set_stretch_tolerance(&mut self, _stretch_tolerance: f32) {
     }
 
+    fn set_typography(&mut self, _typography: Typography) {
+    }
+
     fn set_ignore_document_css(&mut self, ignore: bool) {
         unsafe {
             fz_set_use_document_css(self.ctx.0, !ignore as libc::c_int);
         }
     }
+
+    fn set_language(&mut self, _language: &str) {
+    }
 }
 
 impl<'a> PdfPage<'a> {
@@ -430,6 +502,38 @@ impl<'a> PdfPage<'a> {
         }
     }
 
+    // Renders the given area of the page scaled by the given factor.
+    pub fn tile_pixmap(&self, scale: f32, rect: &Rectangle) -> Option<Pixmap> {
+        unsafe {
+            let mat = fz_scale(scale as libc::c_float, scale as libc::c_float);
+            let area = FzIrect { x0: rect.min.x, y0: rect.min.y, x1: rect.max.x, y1: rect.max.y };
+            let pixmap = mp_new_pixmap_from_page_area(self.ctx.0,
+                                                      self.page,
+                                                      mat,
+                                                      area,
+                                                      fz_device_gray(self.ctx.0));
+            if pixmap.is_null() {
+                return None;
+            }
+
+            let width = (*pixmap).w as u32;
+            let height = (*pixmap).h as u32;
+            let len = (width * height) as usize;
+            let samples = slice::from_raw_parts((*pixmap).samples, len);
+            let mut data = if let Some(data) = take_buffer(len) {
+                data
+            } else {
+                fz_drop_pixmap(self.ctx.0, pixmap);
+                return None;
+            };
+            data.extend(samples);
+
+            fz_drop_pixmap(self.ctx.0, pixmap);
+
+            Some(Pixmap { width, height, data })
+        }
+    }
+
     pub fn pixmap(&self, scale: f32) -> Option<Pixmap> {
         unsafe {
             let mat = fz_scale(scale as libc::c_float, scale as libc::c_float);
@@ -446,11 +550,12 @@ impl<'a> PdfPage<'a> {
             let height = (*pixmap).h as u32;
             let len = (width * height) as usize;
             let samples = slice::from_raw_parts((*pixmap).samples, len);
-            let mut data = Vec::new();
-            if data.try_reserve(len).is_err() {
+            let mut data = if let Some(data) = take_buffer(len) {
+                data
+            } else {
                 fz_drop_pixmap(self.ctx.0, pixmap);
                 return None;
-            }
+            };
             data.extend(samples);
 
             fz_drop_pixmap(self.ctx.0, pixmap);
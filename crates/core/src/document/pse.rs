@@ -0,0 +1,390 @@
+use std::fs::{self, File};
+use std::thread;
+use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::ops::Range;
+use std::time::{Duration, Instant, SystemTime};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Sender, Receiver};
+use lazy_static::lazy_static;
+use fxhash::{FxHashMap, FxHashSet};
+use serde::{Serialize, Deserialize};
+use anyhow::{Error, Context, format_err};
+use super::{Document, Location, BoundedText, TocEntry};
+use super::pdf::PdfOpener;
+use super::html::HtmlDocument;
+use crate::metadata::TextAlign;
+use super::html::layout::Typography;
+use crate::geom::{Boundary, CycleDir};
+use crate::framebuffer::Pixmap;
+use crate::device::CURRENT_DEVICE;
+use crate::storage::relocated_caches;
+use crate::settings::network_env;
+use crate::view::{Event, Hub};
+
+// The program that downloads a page: it receives the page's URL and the path to write it to.
+// It's run in its directory, and its requests time out.
+pub const STREAMER_PROGRAM: &str = "bin/streamer/streamer";
+// The pages downloaded, in one directory per stream.
+pub const PAGE_CACHES_DIRNAME: &str = ".page-caches";
+// The maximum number of pages kept, for all the streams.
+const PAGE_CACHE_SIZE: usize = 256;
+// The number of pages downloaded ahead of the current one.
+const PREFETCH_COUNT: usize = 3;
+// The size, in points, of the pages whose dimensions aren't known yet.
+const DEFAULT_PAGE_DIMS: (f32, f32) = (600.0, 900.0);
+// A page that couldn't be downloaded isn't requested again before this delay.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+// A comic streamed page by page, as described by the OPDS Page Streaming Extension:
+// https://github.com/anansi-project/opds-pse.
+// The credentials, if any, are given in the settings of the streamer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PseStream {
+    // Contains `{pageNumber}`, starting at zero, and optionally `{maxWidth}`.
+    pub url: String,
+    pub count: usize,
+    pub title: String,
+    pub author: String,
+}
+
+impl PseStream {
+    pub fn page_url(&self, index: usize, max_width: u32) -> String {
+        self.url.replace("{pageNumber}", &index.to_string())
+                .replace("{maxWidth}", &max_width.to_string())
+    }
+}
+
+pub struct PseDocument {
+    stream: PseStream,
+    cache_dir: PathBuf,
+    // Filled as the pages are downloaded and rendered.
+    dims: Arc<Mutex<FxHashMap<usize, (f32, f32)>>>,
+    // The pages whose last download failed, and when.
+    failures: Arc<Mutex<FxHashMap<usize, Instant>>>,
+    // The pages to download, sent to the thread that downloads them.
+    requests: Option<Sender<(usize, String, PathBuf)>>,
+    hub: Option<Hub>,
+}
+
+// The MIME type of an image, guessed from its first bytes.
+fn image_kind(buf: &[u8]) -> &'static str {
+    if buf.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if buf.starts_with(b"GIF8") {
+        "image/gif"
+    } else if buf.len() > 12 && &buf[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+lazy_static! {
+    // Where the page caches are, which can be moved to the cache directory.
+    static ref PAGE_CACHES_DIR: Mutex<PathBuf> = Mutex::new(PathBuf::from(PAGE_CACHES_DIRNAME));
+    // The pages being downloaded, or waiting to be, by any document.
+    static ref PENDING_PAGES: Mutex<FxHashSet<PathBuf>> = Mutex::new(FxHashSet::default());
+}
+
+pub fn relocate_page_caches(cache_path: Option<&Path>) {
+    let dir = relocated_caches(cache_path, &PAGE_CACHES_DIRNAME[1..])
+                  .unwrap_or_else(|| PathBuf::from(PAGE_CACHES_DIRNAME));
+    *PAGE_CACHES_DIR.lock().unwrap() = dir;
+}
+
+fn page_caches_dir() -> PathBuf {
+    PAGE_CACHES_DIR.lock().unwrap().clone()
+}
+
+// Removes the least recently used pages beyond the cache size.
+fn prune_cache(root: &Path) {
+    let mut pages: Vec<(SystemTime, PathBuf)> = fs::read_dir(root).into_iter().flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_dir(entry.path()).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let used = entry.metadata().and_then(|md| md.modified()).ok()?;
+            Some((used, entry.path()))
+        })
+        .collect();
+
+    if pages.len() <= PAGE_CACHE_SIZE {
+        return;
+    }
+
+    pages.sort();
+    let excess = pages.len() - PAGE_CACHE_SIZE;
+    for (_, path) in pages.into_iter().take(excess) {
+        fs::remove_file(path).ok();
+    }
+}
+
+fn download(url: &str, path: &Path) -> Result<(), Error> {
+    let program = Path::new(STREAMER_PROGRAM).canonicalize()?;
+    let parent = program.parent()
+                        .unwrap_or_else(|| Path::new(""));
+    // The streamer reads its settings from its own directory.
+    let output = network_env(&mut Command::new(&program))
+                         .current_dir(parent)
+                         .arg(url)
+                         .arg(path)
+                         .output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format_err!("{}", String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+// Renders the text shown instead of a page that isn't there.
+fn placeholder(text: &str, dims: (f32, f32), scale: f32) -> Option<Pixmap> {
+    let html = format!("<html><body><p style=\"text-align: center; margin-top: 20em\">{}</p></body></html>", text);
+    let mut doc = HtmlDocument::new_from_memory(&html);
+    doc.layout(dims.0.round() as u32, dims.1.round() as u32, dims.0 / 30.0, 72);
+    doc.pixmap(Location::Exact(0), scale).map(|(pixmap, _)| pixmap)
+}
+
+// Downloads the requested pages, one at a time, until the document is dropped.
+fn fetch_pages(requests: Receiver<(usize, String, PathBuf)>, dims: Arc<Mutex<FxHashMap<usize, (f32, f32)>>>,
+               failures: Arc<Mutex<FxHashMap<usize, Instant>>>, hub: Option<Hub>) {
+    for (index, url, path) in requests {
+        let partial = path.with_extension("part");
+        let result = download(&url, &partial)
+                             .and_then(|_| fs::rename(&partial, &path).map_err(Error::from));
+        PENDING_PAGES.lock().unwrap().remove(&path);
+        if let Err(e) = result {
+            // The page is shown as unavailable, and requested again after a while.
+            fs::remove_file(&partial).ok();
+            eprintln!("Can't download page {}: {:#}.", index + 1, e);
+            failures.lock().unwrap().insert(index, Instant::now());
+            if let Some(hub) = hub.as_ref() {
+                hub.send(Event::PageStreamed(index)).ok();
+            }
+            continue;
+        }
+        failures.lock().unwrap().remove(&index);
+        prune_cache(&page_caches_dir());
+        if let Some(page_dims) = fs::read(&path).ok().and_then(|buf| {
+            let doc = PdfOpener::new()?.open_memory(image_kind(&buf), &buf)?;
+            doc.page(0).map(|page| page.dims())
+        }) {
+            dims.lock().unwrap().insert(index, page_dims);
+        }
+        if let Some(hub) = hub.as_ref() {
+            hub.send(Event::PageStreamed(index)).ok();
+        }
+    }
+}
+
+impl PseDocument {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<PseDocument, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+                        .with_context(|| format!("can't open file {}", path.display()))?;
+        let stream: PseStream = serde_json::from_reader(file)
+                                           .with_context(|| format!("can't parse stream {}", path.display()))?;
+        if stream.count == 0 || !stream.url.contains("{pageNumber}") {
+            return Err(format_err!("invalid stream"));
+        }
+        // The pages are identified by the stream's URL, which doesn't depend on the file.
+        let cache_dir = page_caches_dir().join(format!("{:016X}", fxhash::hash64(&stream.url)));
+        fs::create_dir_all(&cache_dir)?;
+        // The streamer doesn't run in the current directory.
+        let cache_dir = cache_dir.canonicalize()?;
+        Ok(PseDocument {
+            stream,
+            cache_dir,
+            dims: Arc::new(Mutex::new(FxHashMap::default())),
+            failures: Arc::new(Mutex::new(FxHashMap::default())),
+            requests: None,
+            hub: None,
+        })
+    }
+
+    fn page_path(&self, index: usize) -> PathBuf {
+        self.cache_dir.join(format!("{:05}", index))
+    }
+
+    fn page_url(&self, index: usize) -> String {
+        self.stream.page_url(index, CURRENT_DEVICE.dims.0)
+    }
+
+    // Returns the content of the page, if it was downloaded.
+    fn page_data(&self, index: usize) -> Option<Vec<u8>> {
+        let path = self.page_path(index);
+        // Mark the page as recently used.
+        File::options().append(true).open(&path)
+             .and_then(|file| file.set_modified(SystemTime::now())).ok()?;
+        fs::read(&path).ok()
+    }
+
+    fn has_failed_recently(&self, index: usize) -> bool {
+        self.failures.lock().unwrap().get(&index)
+            .is_some_and(|failed| failed.elapsed() < RETRY_DELAY)
+    }
+
+    // Downloads the given pages in the background, unless they're already there.
+    fn request(&mut self, pages: Range<usize>) {
+        for index in pages.start..pages.end.min(self.stream.count) {
+            let path = self.page_path(index);
+            if path.exists() || self.has_failed_recently(index) ||
+               !PENDING_PAGES.lock().unwrap().insert(path.clone()) {
+                continue;
+            }
+            let url = self.page_url(index);
+            let requests = self.requests.get_or_insert_with(|| {
+                let (tx, rx) = mpsc::channel();
+                let dims = Arc::clone(&self.dims);
+                let failures = Arc::clone(&self.failures);
+                let hub = self.hub.clone();
+                thread::spawn(move || fetch_pages(rx, dims, failures, hub));
+                tx
+            });
+            if requests.send((index, url, path.clone())).is_err() {
+                PENDING_PAGES.lock().unwrap().remove(&path);
+            }
+        }
+    }
+
+    fn estimated_dims(&self) -> (f32, f32) {
+        let dims = self.dims.lock().unwrap();
+        dims.get(&0).or_else(|| dims.values().next())
+            .cloned().unwrap_or(DEFAULT_PAGE_DIMS)
+    }
+}
+
+impl Document for PseDocument {
+    fn dims(&self, index: usize) -> Option<(f32, f32)> {
+        if index >= self.stream.count {
+            return None;
+        }
+        let dims = self.dims.lock().unwrap().get(&index).cloned();
+        Some(dims.unwrap_or_else(|| self.estimated_dims()))
+    }
+
+    fn pages_count(&self) -> usize {
+        self.stream.count
+    }
+
+    // The pages that weren't downloaded yet are requested, replaced by a placeholder,
+    // and announced through the hub when they arrive.
+    fn pixmap(&mut self, loc: Location, scale: f32) -> Option<(Pixmap, usize)> {
+        let index = self.resolve_location(loc)?;
+        self.request(index..index + 1 + PREFETCH_COUNT);
+        let Some(buf) = self.page_data(index) else {
+            let text = if self.has_failed_recently(index) {
+                format!("Page {} is unavailable offline.", index + 1)
+            } else {
+                format!("Loading page {}…", index + 1)
+            };
+            return placeholder(&text, self.estimated_dims(), scale).map(|pixmap| (pixmap, index));
+        };
+        let doc = PdfOpener::new()?.open_memory(image_kind(&buf), &buf)?;
+        let page = doc.page(0)?;
+        self.dims.lock().unwrap().insert(index, page.dims());
+        page.pixmap(scale).map(|pixmap| (pixmap, index))
+    }
+
+    fn toc(&mut self) -> Option<Vec<TocEntry>> {
+        None
+    }
+
+    fn chapter<'a>(&mut self, _offset: usize, _toc: &'a [TocEntry]) -> Option<(&'a TocEntry, f32)> {
+        None
+    }
+
+    fn chapter_relative<'a>(&mut self, _offset: usize, _dir: CycleDir, _toc: &'a [TocEntry]) -> Option<&'a TocEntry> {
+        None
+    }
+
+    fn words(&mut self, _loc: Location) -> Option<(Vec<BoundedText>, usize)> {
+        None
+    }
+
+    fn lines(&mut self, _loc: Location) -> Option<(Vec<BoundedText>, usize)> {
+        None
+    }
+
+    fn links(&mut self, _loc: Location) -> Option<(Vec<BoundedText>, usize)> {
+        None
+    }
+
+    fn images(&mut self, _loc: Location) -> Option<(Vec<Boundary>, usize)> {
+        None
+    }
+
+    fn title(&self) -> Option<String> {
+        Some(self.stream.title.clone()).filter(|title| !title.is_empty())
+    }
+
+    fn author(&self) -> Option<String> {
+        Some(self.stream.author.clone()).filter(|author| !author.is_empty())
+    }
+
+    fn metadata(&self, _key: &str) -> Option<String> {
+        None
+    }
+
+    fn is_reflowable(&self) -> bool {
+        false
+    }
+
+    fn layout(&mut self, _width: u32, _height: u32, _font_size: f32, _dpi: u16) {
+    }
+
+    fn set_text_align(&mut self, _text_align: TextAlign) {
+    }
+
+    fn set_font_family(&mut self, _family_name: &str, _search_path: &str) {
+    }
+
+    fn set_margin_width(&mut self, _width: i32) {
+    }
+
+    fn set_vertical_margin_width(&mut self, _width: Option<i32>) {
+    }
+
+    fn set_line_height(&mut self, _line_height: f32) {
+    }
+
+    fn set_hyphen_penalty(&mut self, _hyphen_penalty: i32) {
+    }
+
+    fn set_stretch_tolerance(&mut self, _stretch_tolerance: f32) {
+    }
+
+    fn set_typography(&mut self, _typography: Typography) {
+    }
+
+    fn set_ignore_document_css(&mut self, _ignore: bool) {
+    }
+
+    fn set_language(&mut self, _language: &str) {
+    }
+
+    fn set_hub(&mut self, hub: &Hub) {
+        self.hub = Some(hub.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_url() {
+        let stream = PseStream {
+            url: "https://komga.local/opds/v1.2/books/42/pages/{pageNumber}?zero_based=true&width={maxWidth}".to_string(),
+            count: 3,
+            .. Default::default()
+        };
+        assert_eq!(stream.page_url(2, 1072),
+                   "https://komga.local/opds/v1.2/books/42/pages/2?zero_based=true&width=1072");
+        assert_eq!(image_kind(b"\x89PNG\r\n\x1a\n"), "image/png");
+        assert_eq!(image_kind(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(image_kind(b"\xff\xd8\xff\xe0"), "image/jpeg");
+    }
+}
@@ -0,0 +1,420 @@
+use std::fs;
+use std::thread;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf, Component};
+use std::process::{Command, Child, Stdio};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+use anyhow::{Error, format_err};
+use crate::metadata::{Info, FileInfo};
+use crate::integrity::checksum;
+use crate::helpers::{load_json, save_json};
+use crate::jobs::RetryPolicy;
+use crate::settings::network_env;
+use crate::view::{Event, Hub};
+
+pub const DOWNLOADS_PATH: &str = ".downloads.json";
+// The program that downloads a file: it receives the URL, the path to write it to and the
+// additional headers, and resumes from the existing content of the path.
+pub const DOWNLOADER_PROGRAM: &str = "bin/downloader/downloader";
+// The attempts made before a download is marked as failed, and the delays between them.
+const RETRY_POLICY: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    initial_delay: Duration::from_secs(15),
+    max_delay: Duration::from_secs(120),
+};
+const PARTIAL_EXTENSION: &str = "part";
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DownloadStatus {
+    #[default]
+    Queued,
+    Running,
+    Paused,
+    Failed,
+    Completed,
+}
+
+impl DownloadStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            DownloadStatus::Queued => "Queued",
+            DownloadStatus::Running => "Downloading",
+            DownloadStatus::Paused => "Paused",
+            DownloadStatus::Failed => "Failed",
+            DownloadStatus::Completed => "Completed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Download {
+    pub id: u64,
+    pub url: String,
+    // The destination: relative to the library when queued, absolute afterwards.
+    pub path: PathBuf,
+    // The expected size and SHA-256 digest, when known.
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+    // The additional request headers, e.g. the authorization required by a server.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    // The information of the document added to the library once downloaded.
+    pub info: Option<Info>,
+    pub status: DownloadStatus,
+    pub done: u64,
+    pub total: Option<u64>,
+    pub attempts: usize,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    // A failed download isn't attempted again before this instant.
+    #[serde(skip)]
+    pub retry_at: Option<Instant>,
+}
+
+impl Download {
+    pub fn name(&self) -> String {
+        self.info.as_ref()
+            .map(|info| info.title.clone())
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| self.path.file_name()
+                                   .map(|name| name.to_string_lossy().into_owned())
+                                   .unwrap_or_default())
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        self.total.or(self.size)
+            .filter(|total| *total > 0)
+            .map(|total| (self.done as f32 / total as f32).min(1.0))
+    }
+
+    fn is_ready(&self, now: Instant) -> bool {
+        self.status == DownloadStatus::Queued &&
+        self.retry_at.is_none_or(|retry_at| retry_at <= now)
+    }
+
+    // Queues the download again after a delay, unless it was attempted too many times.
+    fn retry(&mut self, hub: &Hub) {
+        if self.attempts >= RETRY_POLICY.attempts as usize {
+            self.status = DownloadStatus::Failed;
+            return;
+        }
+        let delay = RETRY_POLICY.delay(self.attempts as u32);
+        self.status = DownloadStatus::Queued;
+        self.retry_at = Some(Instant::now() + delay);
+        let hub2 = hub.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            hub2.send(Event::RetryDownloads).ok();
+        });
+    }
+
+    fn partial_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".");
+        path.push(PARTIAL_EXTENSION);
+        PathBuf::from(path)
+    }
+}
+
+// The destination of a download can't leave the library.
+fn is_valid_path(path: &Path) -> bool {
+    path.components().count() > 0 &&
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+// Checks the size and the digest of a downloaded file against the expected ones.
+pub fn verify(path: &Path, size: Option<u64>, sha256: Option<&str>) -> Result<(), Error> {
+    let actual_size = fs::metadata(path)?.len();
+    if let Some(size) = size.filter(|size| *size != actual_size) {
+        return Err(format_err!("size mismatch: expected {}, got {}", size, actual_size));
+    }
+    if let Some(sha256) = sha256 {
+        let actual_sha256 = checksum(path)?;
+        if !actual_sha256.eq_ignore_ascii_case(sha256) {
+            return Err(format_err!("checksum mismatch"));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct DownloadManager {
+    pub items: Vec<Download>,
+    current: Option<(u64, Child)>,
+    // The identifiers aren't reused: a stale event can't be mistaken for a new download's.
+    next_id: u64,
+}
+
+impl DownloadManager {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DownloadManager, Error> {
+        let mut items: Vec<Download> = load_json(path)?;
+        // The downloads interrupted by the previous session are resumed.
+        for item in &mut items {
+            if item.status == DownloadStatus::Running {
+                item.status = DownloadStatus::Queued;
+            }
+        }
+        let next_id = items.iter().map(|item| item.id + 1).max().unwrap_or(0);
+        Ok(DownloadManager { items, current: None, next_id })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        save_json(&self.items, path)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.items.iter().any(|item| matches!(item.status, DownloadStatus::Queued | DownloadStatus::Running))
+    }
+
+    // Returns the identifier of the new download, unless its destination is invalid or already queued.
+    pub fn push(&mut self, mut download: Download, home: &Path) -> Option<u64> {
+        if !is_valid_path(&download.path) {
+            return None;
+        }
+        download.path = home.join(&download.path);
+        if self.items.iter().any(|item| item.path == download.path && item.status != DownloadStatus::Completed) {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        download.id = id;
+        download.status = DownloadStatus::Queued;
+        download.done = 0;
+        download.attempts = 0;
+        download.error.clear();
+        download.retry_at = None;
+        self.items.push(download);
+        Some(id)
+    }
+
+    // Starts the next queued download, if none is running.
+    pub fn process(&mut self, online: bool, hub: &Hub) {
+        if self.current.is_some() || !online {
+            return;
+        }
+        let now = Instant::now();
+        let item = if let Some(item) = self.items.iter_mut().find(|item| item.is_ready(now)) {
+            item
+        } else {
+            return;
+        };
+        let partial = item.partial_path();
+        if let Some(parent) = partial.parent() {
+            fs::create_dir_all(parent).ok();
+        }
+        match spawn(item.id, &item.url, &partial, &item.headers, hub) {
+            Ok(child) => {
+                item.status = DownloadStatus::Running;
+                item.attempts += 1;
+                item.retry_at = None;
+                self.current = Some((item.id, child));
+            },
+            Err(e) => {
+                item.status = DownloadStatus::Failed;
+                item.error = format!("{:#}", e);
+            },
+        }
+    }
+
+    pub fn update_progress(&mut self, id: u64, done: u64, total: Option<u64>) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.done = done;
+            if total.is_some() {
+                item.total = total;
+            }
+        }
+    }
+
+    // Handles the end of the downloader's process.
+    // Returns the information of the document ready to be added to the given library.
+    pub fn finish(&mut self, id: u64, error: Option<String>, home: &Path, hub: &Hub) -> Option<Info> {
+        let success = match self.current.take() {
+            Some((current_id, mut child)) if current_id == id => {
+                child.wait().map(|status| status.success()).unwrap_or(false)
+            },
+            current => {
+                self.current = current;
+                return None;
+            },
+        };
+
+        let item = self.items.iter_mut().find(|item| item.id == id)?;
+
+        // Paused or removed while running.
+        if item.status != DownloadStatus::Running {
+            return None;
+        }
+
+        let partial = item.partial_path();
+
+        if !success {
+            item.error = error.unwrap_or_else(|| "interrupted".to_string());
+            item.retry(hub);
+            return None;
+        }
+
+        let result = verify(&partial, item.size, item.sha256.as_deref())
+                           .and_then(|_| fs::rename(&partial, &item.path).map_err(Into::into));
+
+        if let Err(e) = result {
+            // A corrupted file can't be resumed.
+            fs::remove_file(&partial).ok();
+            item.done = 0;
+            item.error = format!("{:#}", e);
+            item.retry(hub);
+            return None;
+        }
+
+        item.status = DownloadStatus::Completed;
+        item.error.clear();
+
+        let relative_path = item.path.strip_prefix(home).ok()?;
+        let size = fs::metadata(&item.path).map_or(item.done, |md| md.len());
+        let kind = item.path.extension()
+                       .and_then(|ext| ext.to_str())
+                       .map(str::to_lowercase)
+                       .unwrap_or_default();
+        let mut info = item.info.clone().unwrap_or_default();
+        if info.title.is_empty() {
+            info.title = item.name();
+        }
+        info.file = FileInfo {
+            path: relative_path.to_path_buf(),
+            kind,
+            size,
+            hash: item.sha256.clone().map(|hash| hash.to_lowercase()).unwrap_or_default(),
+        };
+        Some(info)
+    }
+
+    pub fn pause(&mut self, id: u64) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            if matches!(item.status, DownloadStatus::Queued | DownloadStatus::Running) {
+                item.status = DownloadStatus::Paused;
+            }
+        }
+        self.stop(id);
+    }
+
+    pub fn resume(&mut self, id: u64) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            if matches!(item.status, DownloadStatus::Paused | DownloadStatus::Failed) {
+                item.status = DownloadStatus::Queued;
+                item.attempts = 0;
+                item.error.clear();
+                item.retry_at = None;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.stop(id);
+        if let Some(index) = self.items.iter().position(|item| item.id == id) {
+            let item = self.items.remove(index);
+            if item.status != DownloadStatus::Completed {
+                fs::remove_file(item.partial_path()).ok();
+            }
+        }
+    }
+
+    pub fn clear_completed(&mut self) {
+        self.items.retain(|item| item.status != DownloadStatus::Completed);
+    }
+
+    // The partial file is kept: the download can be resumed later.
+    fn stop(&mut self, id: u64) {
+        if let Some((_, child)) = self.current.as_mut().filter(|(current_id, _)| *current_id == id) {
+            unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+            child.wait().ok();
+            self.current = None;
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        if let Some(id) = self.current.as_ref().map(|(id, _)| *id) {
+            self.stop(id);
+            if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+                item.status = DownloadStatus::Queued;
+                item.attempts = item.attempts.saturating_sub(1);
+            }
+        }
+    }
+}
+
+// The downloader reports its advancement and its errors on its standard output.
+fn spawn(id: u64, url: &str, partial: &Path, headers: &[(String, String)], hub: &Hub) -> Result<Child, Error> {
+    let mut child = network_env(&mut Command::new(DOWNLOADER_PROGRAM))
+                            .arg(url)
+                            .arg(partial)
+                            .args(headers.iter().map(|(name, value)| format!("{}: {}", name, value)))
+                            .stdout(Stdio::piped())
+                            .spawn()?;
+    let stdout = child.stdout.take()
+                      .ok_or_else(|| format_err!("can't take stdout"))?;
+    let hub2 = hub.clone();
+    thread::spawn(move || {
+        let mut error = None;
+        let reader = BufReader::new(stdout);
+        for line in reader.lines().map_while(Result::ok) {
+            if let Ok(event) = serde_json::from_str::<JsonValue>(&line) {
+                match event.get("type").and_then(JsonValue::as_str) {
+                    Some("progress") => {
+                        if let Some(done) = event.get("done").and_then(JsonValue::as_u64) {
+                            let total = event.get("total").and_then(JsonValue::as_u64);
+                            hub2.send(Event::DownloadProgress(id, done, total)).ok();
+                        }
+                    },
+                    Some("error") => {
+                        error = event.get("message").and_then(JsonValue::as_str).map(String::from);
+                    },
+                    _ => (),
+                }
+            }
+        }
+        hub2.send(Event::DownloadFinished(id, error)).ok();
+    });
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue() {
+        let mut manager = DownloadManager::default();
+        let download = Download { url: "https://example.org/a.epub".to_string(),
+                                  path: PathBuf::from("Downloads/a.epub"), .. Default::default() };
+        let home = Path::new("/mnt/onboard");
+        assert_eq!(manager.push(download.clone(), home), Some(0));
+        assert_eq!(manager.push(download.clone(), home), None);
+        assert_eq!(manager.push(Download { path: PathBuf::from("../a.epub"), .. download.clone() }, home), None);
+        assert_eq!(manager.push(Download { path: PathBuf::from("/tmp/a.epub"), .. download.clone() }, home), None);
+        assert_eq!(manager.push(Download { path: PathBuf::from("Downloads/b.epub"), .. download }, home), Some(1));
+        assert_eq!(manager.items[0].path, Path::new("/mnt/onboard/Downloads/a.epub"));
+        manager.pause(0);
+        assert_eq!(manager.items[0].status, DownloadStatus::Paused);
+        manager.resume(0);
+        assert_eq!(manager.items[0].status, DownloadStatus::Queued);
+        manager.update_progress(1, 50, Some(200));
+        assert_eq!(manager.items[1].progress(), Some(0.25));
+        assert_eq!(manager.items[1].name(), "b.epub");
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let now = Instant::now();
+        let mut download = Download { status: DownloadStatus::Queued, .. Default::default() };
+        assert!(download.is_ready(now));
+        download.retry_at = Some(now + RETRY_POLICY.delay(1));
+        assert!(!download.is_ready(now));
+        assert!(download.is_ready(now + RETRY_POLICY.max_delay));
+        assert!(RETRY_POLICY.delay(2) > RETRY_POLICY.delay(1));
+        download.status = DownloadStatus::Paused;
+        assert!(!download.is_ready(now + RETRY_POLICY.max_delay));
+    }
+}
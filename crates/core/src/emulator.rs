@@ -169,9 +169,9 @@ impl Framebuffer for WindowCanvas {
         }
     }
 
-    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<u32, Error> {
+    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<Option<u32>, Error> {
         self.present();
-        Ok(Local::now().timestamp_subsec_millis())
+        Ok(Some(Local::now().timestamp_subsec_millis()))
     }
 
     fn wait(&self, _tok: u32) -> Result<i32, Error> {
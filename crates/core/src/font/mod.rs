@@ -43,6 +43,12 @@ pub const SPECIAL_STYLE: Style = Style {
     size: FONT_SIZES[1],
 };
 
+pub const BOLD_STYLE: Style = Style {
+    family: Family::SansSerif,
+    variant: Variant::BOLD,
+    size: FONT_SIZES[1],
+};
+
 pub const KBD_CHAR: Style = Style {
     family: Family::Keyboard,
     variant: Variant::REGULAR,
@@ -1368,6 +1374,32 @@ impl Font {
         }
     }
 
+    // Breaks the text into lines that fit within the given width.
+    pub fn wrap(&mut self, text: &str, max_width: i32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && self.plan(&candidate, None, None).width > max_width {
+                lines.push(line);
+                line = word.to_string();
+            } else {
+                line = candidate;
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+
     #[inline]
     pub fn crop_right(&self, render_plan: &mut RenderPlan, max_width: i32) {
         if render_plan.width <= max_width {
@@ -0,0 +1,228 @@
+use std::hash::Hasher;
+use fxhash::FxHasher;
+use crate::geom::Rectangle;
+use super::UpdateMode;
+
+// Side of the square tiles used to detect the changes between two updates.
+const TILE_SIZE: u32 = 32;
+// The largest fraction of the screen, in tiles, that is hashed.
+const MAX_HASHED_FRACTION: u32 = 8;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FrameLayout {
+    pub width: u32,
+    pub height: u32,
+    pub xoffset: u32,
+    pub yoffset: u32,
+    pub line_length: u32,
+    pub bytes_per_pixel: u32,
+}
+
+// Remembers a hash of each tile, as it was the last time the tile was sent to the EPDC.
+// Gray level updates can then be restricted to the tiles that actually changed.
+// Only the small regions are hashed: the large ones, such as the pages, almost always
+// change, and hashing them would only delay their update.
+pub struct DamageTracker {
+    layout: FrameLayout,
+    columns: u32,
+    rows: u32,
+    hashes: Vec<Option<u64>>,
+}
+
+impl DamageTracker {
+    pub fn new(layout: FrameLayout) -> DamageTracker {
+        let mut tracker = DamageTracker {
+            layout,
+            columns: 0,
+            rows: 0,
+            hashes: Vec::new(),
+        };
+        tracker.reset(layout);
+        tracker
+    }
+
+    pub fn reset(&mut self, layout: FrameLayout) {
+        self.layout = layout;
+        self.columns = layout.width.div_ceil(TILE_SIZE);
+        self.rows = layout.height.div_ceil(TILE_SIZE);
+        self.hashes.clear();
+        self.hashes.resize((self.columns * self.rows) as usize, None);
+    }
+
+    // Returns the region that needs to be sent to the EPDC for an update of `rect`,
+    // or `None` if the update can be skipped.
+    // The fast waveforms are always sent as is: they leave artifacts that the next
+    // gray level update of the same region is expected to clean.
+    pub fn update_region(&mut self, frame: &[u8], rect: &Rectangle, mode: UpdateMode) -> Option<Rectangle> {
+        match mode {
+            UpdateMode::Gui | UpdateMode::Partial if self.is_small(rect) => self.damaged(frame, rect),
+            UpdateMode::Full if self.is_small(rect) => {
+                self.refresh(frame, rect);
+                Some(*rect)
+            },
+            _ => {
+                self.invalidate(rect);
+                Some(*rect)
+            },
+        }
+    }
+
+    fn is_small(&self, rect: &Rectangle) -> bool {
+        self.tiles(rect).is_some_and(|(c0, r0, c1, r1)| {
+            (c1 - c0) * (r1 - r0) * MAX_HASHED_FRACTION <= self.columns * self.rows
+        })
+    }
+
+    // Forget everything: the next updates will be sent as is.
+    pub fn clear(&mut self) {
+        self.hashes.iter_mut().for_each(|h| *h = None);
+    }
+
+    // Forget the tiles overlapping `rect`.
+    pub fn invalidate(&mut self, rect: &Rectangle) {
+        if let Some((c0, r0, c1, r1)) = self.tiles(rect) {
+            for r in r0..r1 {
+                for c in c0..c1 {
+                    self.hashes[(r * self.columns + c) as usize] = None;
+                }
+            }
+        }
+    }
+
+    // Records the tiles entirely covered by `rect` as being displayed.
+    pub fn refresh(&mut self, frame: &[u8], rect: &Rectangle) {
+        if let Some((c0, r0, c1, r1)) = self.tiles(rect) {
+            for r in r0..r1 {
+                for c in c0..c1 {
+                    let index = (r * self.columns + c) as usize;
+                    self.hashes[index] = if rect.contains(&self.tile_rect(c, r)) {
+                        self.hash_tile(frame, c, r)
+                    } else {
+                        None
+                    };
+                }
+            }
+        }
+    }
+
+    // Returns the bounding rectangle of the tiles overlapping `rect` that
+    // changed since they were last sent, or `None` if nothing changed.
+    // The tiles are sent whole: the changes they might contain outside of
+    // `rect` are therefore also accounted for.
+    pub fn damaged(&mut self, frame: &[u8], rect: &Rectangle) -> Option<Rectangle> {
+        let (c0, r0, c1, r1) = self.tiles(rect)?;
+        let mut damage: Option<Rectangle> = None;
+
+        for r in r0..r1 {
+            for c in c0..c1 {
+                let index = (r * self.columns + c) as usize;
+                let hash = self.hash_tile(frame, c, r);
+                if hash.is_none() || hash != self.hashes[index] {
+                    self.hashes[index] = hash;
+                    let tile = self.tile_rect(c, r);
+                    if let Some(damage) = damage.as_mut() {
+                        damage.absorb(&tile);
+                    } else {
+                        damage = Some(tile);
+                    }
+                }
+            }
+        }
+
+        damage
+    }
+
+    fn tiles(&self, rect: &Rectangle) -> Option<(u32, u32, u32, u32)> {
+        let rect = rect.intersection(&(self.layout.width, self.layout.height).into())?;
+        Some((rect.min.x as u32 / TILE_SIZE,
+              rect.min.y as u32 / TILE_SIZE,
+              (rect.max.x as u32).div_ceil(TILE_SIZE),
+              (rect.max.y as u32).div_ceil(TILE_SIZE)))
+    }
+
+    fn tile_rect(&self, c: u32, r: u32) -> Rectangle {
+        rect![(c * TILE_SIZE) as i32,
+              (r * TILE_SIZE) as i32,
+              ((c + 1) * TILE_SIZE).min(self.layout.width) as i32,
+              ((r + 1) * TILE_SIZE).min(self.layout.height) as i32]
+    }
+
+    fn hash_tile(&self, frame: &[u8], c: u32, r: u32) -> Option<u64> {
+        let FrameLayout { width, height, xoffset, yoffset, line_length, bytes_per_pixel } = self.layout;
+        let x0 = c * TILE_SIZE;
+        let x1 = (x0 + TILE_SIZE).min(width);
+        let y0 = r * TILE_SIZE;
+        let y1 = (y0 + TILE_SIZE).min(height);
+        let mut hasher = FxHasher::default();
+
+        for y in y0..y1 {
+            let start = ((xoffset + x0) * bytes_per_pixel + (yoffset + y) * line_length) as usize;
+            let end = start + ((x1 - x0) * bytes_per_pixel) as usize;
+            hasher.write(frame.get(start..end)?);
+        }
+
+        Some(hasher.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> FrameLayout {
+        FrameLayout {
+            width: 100,
+            height: 70,
+            xoffset: 0,
+            yoffset: 0,
+            line_length: 104,
+            bytes_per_pixel: 1,
+        }
+    }
+
+    #[test]
+    fn test_damaged_tiles() {
+        let layout = layout();
+        let mut frame = vec![255u8; (layout.line_length * layout.height) as usize];
+        let mut tracker = DamageTracker::new(layout);
+        let screen: Rectangle = (layout.width, layout.height).into();
+
+        assert_eq!(tracker.damaged(&frame, &screen), Some(screen));
+        assert_eq!(tracker.damaged(&frame, &screen), None);
+
+        frame[(40 * layout.line_length + 70) as usize] = 0;
+        assert_eq!(tracker.damaged(&frame, &rect![0, 0, 40, 40]), None);
+        assert_eq!(tracker.damaged(&frame, &rect![60, 35, 80, 45]), Some(rect![64, 32, 96, 64]));
+        assert_eq!(tracker.damaged(&frame, &screen), None);
+
+        tracker.invalidate(&rect![10, 10, 20, 20]);
+        assert_eq!(tracker.damaged(&frame, &screen), Some(rect![0, 0, 32, 32]));
+    }
+
+    #[test]
+    fn test_large_regions() {
+        let layout = layout();
+        let frame = vec![255u8; (layout.line_length * layout.height) as usize];
+        let mut tracker = DamageTracker::new(layout);
+        let screen: Rectangle = (layout.width, layout.height).into();
+
+        assert_eq!(tracker.update_region(&frame, &rect![0, 0, 20, 20], UpdateMode::Gui), Some(rect![0, 0, 32, 32]));
+        assert_eq!(tracker.update_region(&frame, &rect![0, 0, 20, 20], UpdateMode::Gui), None);
+        // The large regions are sent as is, and forgotten.
+        assert_eq!(tracker.update_region(&frame, &screen, UpdateMode::Gui), Some(screen));
+        assert_eq!(tracker.update_region(&frame, &screen, UpdateMode::Full), Some(screen));
+        assert_eq!(tracker.update_region(&frame, &rect![0, 0, 20, 20], UpdateMode::Gui), Some(rect![0, 0, 32, 32]));
+    }
+
+    #[test]
+    fn test_partial_refresh() {
+        let layout = layout();
+        let frame = vec![0u8; (layout.line_length * layout.height) as usize];
+        let mut tracker = DamageTracker::new(layout);
+
+        tracker.refresh(&frame, &rect![0, 0, 100, 50]);
+        assert_eq!(tracker.damaged(&frame, &rect![0, 0, 100, 32]), None);
+        assert_eq!(tracker.damaged(&frame, &rect![0, 40, 10, 50]), Some(rect![0, 32, 32, 64]));
+        assert_eq!(tracker.damaged(&frame, &rect![96, 64, 100, 70]), Some(rect![96, 64, 100, 70]));
+    }
+}
@@ -1,7 +1,9 @@
+use std::mem;
 use std::fs::File;
 use std::path::Path;
 use anyhow::{Error, Context, format_err};
 use super::{Framebuffer, UpdateMode};
+use super::pool::{take_buffer, recycle_buffer};
 use crate::color::WHITE;
 use crate::geom::{Rectangle, lerp};
 
@@ -15,17 +17,18 @@ pub struct Pixmap {
 impl Pixmap {
     pub fn new(width: u32, height: u32) -> Pixmap {
         let len = (width * height) as usize;
+        let mut data = take_buffer(len).unwrap_or_default();
+        data.resize(len, WHITE);
         Pixmap {
             width,
             height,
-            data: vec![WHITE; len],
+            data,
         }
     }
 
     pub fn try_new(width: u32, height: u32) -> Option<Pixmap> {
-        let mut data = Vec::new();
         let len = (width * height) as usize;
-        data.try_reserve_exact(len).ok()?;
+        let mut data = take_buffer(len)?;
         data.resize(len, WHITE);
         Some(Pixmap {
             width,
@@ -68,6 +71,83 @@ impl Pixmap {
         let addr = (y * self.width + x) as usize;
         self.data[addr]
     }
+
+    // Shrinks the pixmap by averaging the pixels covered by each destination pixel.
+    pub fn downscaled(&self, width: u32, height: u32) -> Pixmap {
+        let width = width.clamp(1, self.width.max(1));
+        let height = height.clamp(1, self.height.max(1));
+        let mut pixmap = Pixmap::new(width, height);
+        if self.data.is_empty() {
+            return pixmap;
+        }
+        for y in 0..height {
+            let y_start = y * self.height / height;
+            let y_end = ((y + 1) * self.height / height).max(y_start + 1);
+            for x in 0..width {
+                let x_start = x * self.width / width;
+                let x_end = ((x + 1) * self.width / width).max(x_start + 1);
+                let mut sum = 0u32;
+                for sy in y_start..y_end {
+                    let row = (sy * self.width) as usize;
+                    sum += self.data[row + x_start as usize..row + x_end as usize]
+                               .iter().map(|&c| c as u32).sum::<u32>();
+                }
+                let count = (y_end - y_start) * (x_end - x_start);
+                pixmap.data[(y * width + x) as usize] = (sum / count) as u8;
+            }
+        }
+        pixmap
+    }
+
+    // Copies the given region, clipped to the pixmap's bounds.
+    pub fn cropped(&self, rect: &Rectangle) -> Pixmap {
+        let bounds = rect![0, 0, self.width as i32, self.height as i32];
+        let rect = rect.intersection(&bounds).unwrap_or_default();
+        let mut pixmap = Pixmap::new(rect.width(), rect.height());
+        if self.data.is_empty() {
+            return pixmap;
+        }
+        for y in rect.min.y..rect.max.y {
+            let start = (y as u32 * self.width + rect.min.x as u32) as usize;
+            let dest = ((y - rect.min.y) as u32 * rect.width()) as usize;
+            pixmap.data[dest..dest + rect.width() as usize]
+                  .copy_from_slice(&self.data[start..start + rect.width() as usize]);
+        }
+        pixmap
+    }
+
+    // Rotates the pixmap clockwise by the given number of quarter turns.
+    pub fn rotated(&self, quarter_turns: i8) -> Pixmap {
+        let quarter_turns = quarter_turns.rem_euclid(4);
+        let (width, height) = if quarter_turns % 2 == 1 {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let mut pixmap = Pixmap::new(width, height);
+        if self.data.is_empty() {
+            return pixmap;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = match quarter_turns {
+                    1 => (y, self.height - 1 - x),
+                    2 => (self.width - 1 - x, self.height - 1 - y),
+                    3 => (self.width - 1 - y, x),
+                    _ => (x, y),
+                };
+                pixmap.data[(y * width + x) as usize] = self.data[(sy * self.width + sx) as usize];
+            }
+        }
+        pixmap
+    }
+}
+
+// The buffers of the dropped pixmaps are reused by the next ones.
+impl Drop for Pixmap {
+    fn drop(&mut self) {
+        recycle_buffer(mem::take(&mut self.data));
+    }
 }
 
 impl Framebuffer for Pixmap {
@@ -98,6 +178,14 @@ impl Framebuffer for Pixmap {
         self.data[addr] = blended_color;
     }
 
+    fn gray_row_mut(&mut self, x: u32, y: u32, len: usize) -> Option<&mut [u8]> {
+        if self.data.is_empty() || y >= self.height || x as usize + len > self.width as usize {
+            return None;
+        }
+        let start = (y * self.width + x) as usize;
+        Some(&mut self.data[start..start+len])
+    }
+
     fn invert_region(&mut self, rect: &Rectangle) {
         if self.data.is_empty() {
             return;
@@ -124,8 +212,8 @@ impl Framebuffer for Pixmap {
         }
     }
 
-    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<u32, Error> {
-        Ok(1)
+    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<Option<u32>, Error> {
+        Ok(Some(1))
     }
 
     fn wait(&self, _: u32) -> Result<i32, Error> {
@@ -179,3 +267,37 @@ impl Framebuffer for Pixmap {
         self.height
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downscaled() {
+        let mut pixmap = Pixmap::new(4, 2);
+        pixmap.data.copy_from_slice(&[0, 0, 255, 255,
+                                      0, 0, 255, 255]);
+        let thumbnail = pixmap.downscaled(2, 1);
+        assert_eq!((thumbnail.width, thumbnail.height), (2, 1));
+        assert_eq!(thumbnail.data, vec![0, 255]);
+        let thumbnail = pixmap.downscaled(1, 1);
+        assert_eq!(thumbnail.data, vec![127]);
+        let thumbnail = pixmap.downscaled(8, 8);
+        assert_eq!((thumbnail.width, thumbnail.height), (4, 2));
+    }
+
+    #[test]
+    fn test_cropped_and_rotated() {
+        let mut pixmap = Pixmap::new(3, 2);
+        pixmap.data.copy_from_slice(&[1, 2, 3,
+                                      4, 5, 6]);
+        let region = pixmap.cropped(&rect![1, 0, 5, 2]);
+        assert_eq!((region.width, region.height), (2, 2));
+        assert_eq!(region.data, vec![2, 3, 5, 6]);
+        let rotated = pixmap.rotated(1);
+        assert_eq!((rotated.width, rotated.height), (2, 3));
+        assert_eq!(rotated.data, vec![4, 1, 5, 2, 6, 3]);
+        assert_eq!(pixmap.rotated(2).data, vec![6, 5, 4, 3, 2, 1]);
+        assert_eq!(pixmap.rotated(-1).data, vec![3, 6, 2, 5, 1, 4]);
+    }
+}
@@ -12,6 +12,7 @@ use super::{UpdateMode, Framebuffer};
 use super::linuxfb_sys::*;
 use super::mxcfb_sys::*;
 use super::transform::*;
+use super::damage::{DamageTracker, FrameLayout};
 
 impl Into<MxcfbRect> for Rectangle {
     fn into(self) -> MxcfbRect {
@@ -43,6 +44,7 @@ pub struct KoboFramebuffer1 {
     bytes_per_pixel: u8,
     var_info: VarScreenInfo,
     fix_info: FixScreenInfo,
+    damage: DamageTracker,
 }
 
 impl KoboFramebuffer1 {
@@ -76,6 +78,7 @@ impl KoboFramebuffer1 {
             } else {
                 (set_pixel_rgb_8, get_pixel_rgb_8, as_rgb_8)
             };
+            let damage = DamageTracker::new(frame_layout(&var_info, &fix_info));
             Ok(KoboFramebuffer1 {
                    file,
                    frame,
@@ -91,6 +94,7 @@ impl KoboFramebuffer1 {
                    bytes_per_pixel: bytes_per_pixel as u8,
                    var_info,
                    fix_info,
+                   damage,
                })
         }
     }
@@ -106,6 +110,20 @@ impl Framebuffer for KoboFramebuffer1 {
         (self.set_pixel_rgb)(self, x, y, [c, c, c]);
     }
 
+    fn gray_row_mut(&mut self, x: u32, y: u32, len: usize) -> Option<&mut [u8]> {
+        let identity = !self.dithered || CURRENT_DEVICE.mark() >= 7;
+        if self.bytes_per_pixel != 1 || !identity ||
+           y >= self.var_info.yres || x as usize + len > self.var_info.xres as usize {
+            return None;
+        }
+        let addr = (self.var_info.xoffset + x) as usize +
+                   (self.var_info.yoffset + y) as usize * self.fix_info.line_length as usize;
+        if addr + len > self.frame_size {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts_mut((self.frame as *mut u8).add(addr), len) })
+    }
+
     fn set_blended_pixel(&mut self, x: u32, y: u32, color: u8, alpha: f32) {
         if alpha >= 1.0 {
             self.set_pixel(x, y, color);
@@ -139,7 +157,13 @@ impl Framebuffer for KoboFramebuffer1 {
     }
 
     // Tell the driver that the screen needs to be redrawn.
-    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<u32, Error> {
+    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<Option<u32>, Error> {
+        let frame = unsafe { slice::from_raw_parts(self.frame as *const u8, self.frame_size) };
+        let rect = match self.damage.update_region(frame, rect, mode) {
+            Some(rect) => rect,
+            None => return Ok(None),
+        };
+        let rect = &rect;
         let update_marker = self.token;
         let mark = CURRENT_DEVICE.mark();
         let mut flags = self.flags;
@@ -240,7 +264,7 @@ impl Framebuffer for KoboFramebuffer1 {
             Err(e) => Err(Error::from(e).context("can't send framebuffer update")),
             _ => {
                 self.token = self.token.wrapping_add(1);
-                Ok(update_marker)
+                Ok(Some(update_marker))
             }
         }
     }
@@ -306,6 +330,7 @@ impl Framebuffer for KoboFramebuffer1 {
 
         self.fix_info = fix_screen_info(&self.file)?;
         self.frame_size = (self.var_info.yres * self.fix_info.line_length) as libc::size_t;
+        self.damage.reset(frame_layout(&self.var_info, &self.fix_info));
 
         println!("Framebuffer rotation: {} -> {}.", n, self.rotation());
 
@@ -318,6 +343,7 @@ impl Framebuffer for KoboFramebuffer1 {
         } else {
             self.flags &= !EPDC_FLAG_ENABLE_INVERSION;
         }
+        self.damage.clear();
     }
 
     fn inverted(&self) -> bool {
@@ -326,6 +352,7 @@ impl Framebuffer for KoboFramebuffer1 {
 
     fn set_monochrome(&mut self, enable: bool) {
         self.monochrome = enable;
+        self.damage.clear();
     }
 
     fn monochrome(&self) -> bool {
@@ -338,6 +365,7 @@ impl Framebuffer for KoboFramebuffer1 {
         }
 
         self.dithered = enable;
+        self.damage.clear();
 
         if CURRENT_DEVICE.mark() < 7 {
             if enable {
@@ -369,6 +397,17 @@ impl Drop for KoboFramebuffer1 {
     }
 }
 
+fn frame_layout(var_info: &VarScreenInfo, fix_info: &FixScreenInfo) -> FrameLayout {
+    FrameLayout {
+        width: var_info.xres,
+        height: var_info.yres,
+        xoffset: var_info.xoffset,
+        yoffset: var_info.yoffset,
+        line_length: fix_info.line_length,
+        bytes_per_pixel: var_info.bits_per_pixel / 8,
+    }
+}
+
 fn set_pixel_rgb_8(fb: &mut KoboFramebuffer1, x: u32, y: u32, rgb: [u8; 3]) {
     let addr = (fb.var_info.xoffset as isize + x as isize) * (fb.bytes_per_pixel as isize) +
                (fb.var_info.yoffset as isize + y as isize) * (fb.fix_info.line_length as isize);
@@ -15,6 +15,7 @@ use super::linuxfb_sys::*;
 use super::ion_sys::*;
 use super::sunxi_sys::*;
 use super::transform::*;
+use super::damage::{DamageTracker, FrameLayout};
 
 impl From<Rectangle> for AreaInfo {
     fn from(rect: Rectangle) -> Self {
@@ -42,6 +43,7 @@ pub struct KoboFramebuffer2 {
     monochrome: bool,
     inverted: bool,
     dithered: bool,
+    damage: DamageTracker,
 }
 
 const MEM_ALIGN: u32 = 4096;
@@ -198,6 +200,8 @@ impl KoboFramebuffer2 {
             layer_id: 1,
         };
 
+        let damage = DamageTracker::new(frame_layout(&var_info, &fix_info));
+
         Ok(KoboFramebuffer2 {
                ion,
                display: display.unwrap(),
@@ -213,6 +217,7 @@ impl KoboFramebuffer2 {
                transform: transform_identity,
                var_info,
                fix_info,
+               damage,
            })
     }
 
@@ -275,7 +280,13 @@ impl Framebuffer for KoboFramebuffer2 {
     }
 
     // Tell the driver that the screen needs to be redrawn.
-    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<u32, Error> {
+    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<Option<u32>, Error> {
+        let frame = unsafe { slice::from_raw_parts(self.frame as *const u8, self.frame_size) };
+        let rect = match self.damage.update_region(frame, rect, mode) {
+            Some(rect) => rect,
+            None => return Ok(None),
+        };
+        let rect = &rect;
         let mut flags = 0;
         let mut monochrome = self.monochrome;
 
@@ -343,7 +354,7 @@ impl Framebuffer for KoboFramebuffer2 {
         match result {
             Err(e) => Err(Error::from(e).context("can't send framebuffer update")),
             _ => {
-                Ok(self.token)
+                Ok(Some(self.token))
             }
         }
     }
@@ -393,6 +404,7 @@ impl Framebuffer for KoboFramebuffer2 {
             self.fix_info.line_length = self.var_info.xres_virtual;
             self.fix_info.smem_len = self.fix_info.line_length * self.var_info.yres_virtual;
             self.frame_size = (self.var_info.yres * self.fix_info.line_length) as usize;
+            self.damage.reset(frame_layout(&self.var_info, &self.fix_info));
         }
 
         self.var_info.rotate = n as u32;
@@ -405,6 +417,7 @@ impl Framebuffer for KoboFramebuffer2 {
         }
 
         self.inverted = enable;
+        self.damage.clear();
     }
 
     fn inverted(&self) -> bool {
@@ -413,6 +426,7 @@ impl Framebuffer for KoboFramebuffer2 {
 
     fn set_monochrome(&mut self, enable: bool) {
         self.monochrome = enable;
+        self.damage.clear();
     }
 
     fn monochrome(&self) -> bool {
@@ -425,6 +439,7 @@ impl Framebuffer for KoboFramebuffer2 {
         }
 
         self.dithered = enable;
+        self.damage.clear();
 
         if enable {
             self.transform = transform_dither_g16;
@@ -446,6 +461,17 @@ impl Framebuffer for KoboFramebuffer2 {
     }
 }
 
+fn frame_layout(var_info: &VarScreenInfo, fix_info: &FixScreenInfo) -> FrameLayout {
+    FrameLayout {
+        width: var_info.xres,
+        height: var_info.yres,
+        xoffset: 0,
+        yoffset: 0,
+        line_length: fix_info.line_length,
+        bytes_per_pixel: 1,
+    }
+}
+
 impl Drop for KoboFramebuffer2 {
     fn drop(&mut self) {
         unsafe {
@@ -4,17 +4,22 @@ mod mxcfb_sys;
 mod sunxi_sys;
 mod image;
 mod transform;
+mod pool;
+mod simd;
+mod damage;
 mod kobo1;
 mod kobo2;
 
 use anyhow::Error;
 use crate::geom::{Point, Rectangle, surface_area, nearest_segment_point, lerp};
 use crate::geom::{CornerSpec, BorderSpec, ColorSource, Vec2};
+use crate::geom::{clockwise_fraction, ring_slices, bar_rects, line_points};
 use crate::color::{BLACK, WHITE};
 
 pub use self::kobo1::KoboFramebuffer1;
 pub use self::kobo2::KoboFramebuffer2;
 pub use self::image::Pixmap;
+pub use self::pool::{PoolStats, take_buffer, pool_stats};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Display {
@@ -22,6 +27,17 @@ pub struct Display {
     pub rotation: i8,
 }
 
+// A fill made of black and white pixels, which stays legible in monochrome update modes.
+#[derive(Debug, Copy, Clone)]
+pub struct DitheredFill(pub u8);
+
+impl ColorSource for DitheredFill {
+    #[inline]
+    fn color(&self, x: i32, y: i32) -> u8 {
+        transform::transform_dither_g2(x.max(0) as u32, y.max(0) as u32, self.0)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum UpdateMode {
     Gui,
@@ -36,7 +52,9 @@ pub trait Framebuffer {
     fn set_blended_pixel(&mut self, x: u32, y: u32, color: u8, alpha: f32);
     fn invert_region(&mut self, rect: &Rectangle);
     fn shift_region(&mut self, rect: &Rectangle, drift: u8);
-    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<u32, Error>;
+    // Returns the token of the update, or nothing if the update was skipped
+    // because the region didn't change.
+    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<Option<u32>, Error>;
     fn wait(&self, token: u32) -> Result<i32, Error>;
     fn save(&self, path: &str) -> Result<(), Error>;
     fn set_rotation(&mut self, n: i8) -> Result<(u32, u32), Error>;
@@ -65,6 +83,12 @@ pub trait Framebuffer {
         0
     }
 
+    // Gives direct access to a row of 8-bit gray pixels, when the pixels
+    // are stored as such and written without any color transform.
+    fn gray_row_mut(&mut self, _x: u32, _y: u32, _len: usize) -> Option<&mut [u8]> {
+        None
+    }
+
     fn dims(&self) -> (u32, u32) {
         (self.width(), self.height())
     }
@@ -161,8 +185,29 @@ pub trait Framebuffer {
         }
     }
 
+    // Maps the colors of the pixmap through the given table.
+    fn draw_framed_pixmap_mapped(&mut self, pixmap: &Pixmap, rect: &Rectangle, pt: Point, table: &[u8; 256]) {
+        for y in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                let px = x - rect.min.x + pt.x;
+                let py = y - rect.min.y + pt.y;
+                let color = table[pixmap.get_pixel(x as u32, y as u32) as usize];
+                self.set_pixel(px as u32, py as u32, color);
+            }
+        }
+    }
+
     fn draw_framed_pixmap_halftone(&mut self, pixmap: &Pixmap, rect: &Rectangle, pt: Point) {
+        let len = rect.width() as usize;
         for y in rect.min.y..rect.max.y {
+            let py = y - rect.min.y + pt.y;
+            if !pixmap.data.is_empty() && pt.x >= 0 && py >= 0 {
+                let start = (y * pixmap.width as i32 + rect.min.x) as usize;
+                if let Some(row) = self.gray_row_mut(pt.x as u32, py as u32, len) {
+                    simd::halftone_row(row, &pixmap.data[start..start+len], rect.min.x as u32, y as u32);
+                    continue;
+                }
+            }
             for x in rect.min.x..rect.max.x {
                 let px = x - rect.min.x + pt.x;
                 let py = y - rect.min.y + pt.y;
@@ -180,7 +225,15 @@ pub trait Framebuffer {
     }
 
     fn draw_blended_pixmap(&mut self, pixmap: &Pixmap, pt: Point, color: u8) {
+        let len = pixmap.width as usize;
         for y in 0..pixmap.height {
+            if !pixmap.data.is_empty() && pt.x >= 0 && pt.y >= 0 {
+                let start = (y * pixmap.width) as usize;
+                if let Some(row) = self.gray_row_mut(pt.x as u32, y + pt.y as u32, len) {
+                    simd::blend_row(row, &pixmap.data[start..start+len], color);
+                    continue;
+                }
+            }
             for x in 0..pixmap.width {
                 let px = x + pt.x as u32;
                 let py = y + pt.y as u32;
@@ -382,7 +435,16 @@ pub trait Framebuffer {
         let a = vec2!(start.x as f32, start.y as f32) + 0.5;
         let b = vec2!(end.x as f32, end.y as f32) + 0.5;
 
+        let segment = simd::Segment { start: a, end: b, start_radius, end_radius };
+        let len = rect.width() as usize;
+
         for y in rect.min.y..rect.max.y {
+            if rect.min.x >= 0 && y >= 0 {
+                if let Some(row) = self.gray_row_mut(rect.min.x as u32, y as u32, len) {
+                    simd::segment_row(row, rect.min.x, y, &segment, color);
+                    continue;
+                }
+            }
             for x in rect.min.x..rect.max.x {
                 let p = vec2!(x as f32, y as f32) + 0.5;
                 let (n, t) = nearest_segment_point(p, a, b);
@@ -393,4 +455,59 @@ pub trait Framebuffer {
             }
         }
     }
+
+    // Draws one bar per value, scaled so that `max` fills the height of `rect`.
+    fn draw_bars(&mut self, rect: &Rectangle, values: &[f32], max: f32, gap: i32, color: &dyn ColorSource) {
+        for bar in bar_rects(rect, values, max, gap) {
+            for y in bar.min.y..bar.max.y {
+                for x in bar.min.x..bar.max.x {
+                    self.set_pixel(x as u32, y as u32, color.color(x, y));
+                }
+            }
+        }
+    }
+
+    // Joins the values, scaled so that `max` reaches the top of `rect`.
+    fn draw_line_chart(&mut self, rect: &Rectangle, values: &[f32], max: f32, thickness: f32, color: u8) {
+        let points = line_points(rect, values, max);
+        let radius = thickness / 2.0;
+        for pair in points.windows(2) {
+            self.draw_segment(pair[0], pair[1], radius, radius, color);
+        }
+        if let [point] = points[..] {
+            self.draw_disk(point, radius.ceil() as i32, color);
+        }
+    }
+
+    // Draws a ring whose slices, clockwise from the north, are proportional to the values.
+    // With a thickness equal to the radius, the ring becomes a pie.
+    fn draw_ring_chart(&mut self, center: Point, radius: i32, thickness: i32, values: &[f32], colors: &[&dyn ColorSource]) {
+        if colors.is_empty() {
+            return;
+        }
+        let slices = ring_slices(values);
+        let inner_radius = (radius - thickness).max(0) as f32;
+        let rect = Rectangle::from_disk(center, radius);
+
+        for y in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                let v = vec2!((x - center.x) as f32, (y - center.y) as f32) + 0.5;
+                let angle = v.angle();
+                let dist = v.length();
+                let alpha = if inner_radius > 0.0 && dist < (inner_radius + radius as f32) / 2.0 {
+                    surface_area(inner_radius - dist, angle)
+                } else {
+                    surface_area(dist - radius as f32, angle)
+                };
+                if alpha <= 0.0 {
+                    continue;
+                }
+                let fraction = clockwise_fraction(v);
+                if let Some(index) = slices.iter().position(|&end| fraction < end) {
+                    let color = colors[index % colors.len()].color(x, y);
+                    self.set_blended_pixel(x as u32, y as u32, color, alpha);
+                }
+            }
+        }
+    }
 }
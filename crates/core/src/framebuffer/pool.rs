@@ -0,0 +1,132 @@
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+
+// Bounds on the memory held by the idle buffers.
+const MAX_POOLED_BYTES: usize = 48 * 1024 * 1024;
+const MAX_POOLED_BUFFERS: usize = 8;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PoolStats {
+    // Buffers served from the pool.
+    pub hits: usize,
+    // Buffers that had to be allocated.
+    pub misses: usize,
+    pub recycled: usize,
+    pub discarded: usize,
+    pub pooled_buffers: usize,
+    pub pooled_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+    stats: PoolStats,
+}
+
+impl BufferPool {
+    fn take(&mut self, len: usize) -> Option<Vec<u8>> {
+        // Don't waste more than half of a recycled buffer.
+        let index = self.buffers.iter().enumerate()
+                        .filter(|(_, buf)| buf.capacity() >= len && buf.capacity() <= 2 * len)
+                        .min_by_key(|(_, buf)| buf.capacity())
+                        .map(|(index, _)| index);
+        if let Some(index) = index {
+            let mut buf = self.buffers.swap_remove(index);
+            self.stats.pooled_bytes -= buf.capacity();
+            self.stats.pooled_buffers = self.buffers.len();
+            self.stats.hits += 1;
+            buf.clear();
+            Some(buf)
+        } else {
+            self.stats.misses += 1;
+            None
+        }
+    }
+
+    fn recycle(&mut self, buf: Vec<u8>) {
+        let capacity = buf.capacity();
+
+        if capacity > MAX_POOLED_BYTES {
+            self.stats.discarded += 1;
+            return;
+        }
+
+        // Evict the oldest buffers first.
+        while self.buffers.len() >= MAX_POOLED_BUFFERS ||
+              self.stats.pooled_bytes + capacity > MAX_POOLED_BYTES {
+            let old = self.buffers.remove(0);
+            self.stats.pooled_bytes -= old.capacity();
+            self.stats.discarded += 1;
+        }
+
+        self.stats.pooled_bytes += capacity;
+        self.stats.recycled += 1;
+        self.buffers.push(buf);
+        self.stats.pooled_buffers = self.buffers.len();
+    }
+}
+
+lazy_static! {
+    static ref BUFFER_POOL: Mutex<BufferPool> = Mutex::new(BufferPool::default());
+}
+
+// Returns an empty buffer that can hold at least `len` bytes.
+pub fn take_buffer(len: usize) -> Option<Vec<u8>> {
+    if len == 0 {
+        return Some(Vec::new());
+    }
+
+    if let Some(buf) = BUFFER_POOL.lock().ok().and_then(|mut pool| pool.take(len)) {
+        return Some(buf);
+    }
+
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(len).ok()?;
+    Some(buf)
+}
+
+pub fn recycle_buffer(buf: Vec<u8>) {
+    if buf.capacity() == 0 {
+        return;
+    }
+
+    if let Ok(mut pool) = BUFFER_POOL.lock() {
+        pool.recycle(buf);
+    }
+}
+
+pub fn pool_stats() -> PoolStats {
+    BUFFER_POOL.lock().map(|pool| pool.stats).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_fit() {
+        let mut pool = BufferPool::default();
+        pool.recycle(Vec::with_capacity(4000));
+        pool.recycle(Vec::with_capacity(1000));
+        pool.recycle(Vec::with_capacity(1500));
+        assert!(pool.take(300).is_none());
+        assert_eq!(pool.take(900).map(|buf| buf.capacity()), Some(1000));
+        assert_eq!(pool.take(900).map(|buf| buf.capacity()), Some(1500));
+        assert_eq!(pool.stats.hits, 2);
+        assert_eq!(pool.stats.misses, 1);
+        assert_eq!(pool.stats.pooled_bytes, 4000);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut pool = BufferPool::default();
+        for _ in 0..MAX_POOLED_BUFFERS + 2 {
+            pool.recycle(Vec::with_capacity(16));
+        }
+        assert_eq!(pool.stats.pooled_buffers, MAX_POOLED_BUFFERS);
+        assert_eq!(pool.stats.discarded, 2);
+        pool.recycle(Vec::with_capacity(MAX_POOLED_BYTES));
+        assert_eq!(pool.stats.pooled_buffers, 1);
+        assert_eq!(pool.stats.pooled_bytes, MAX_POOLED_BYTES);
+    }
+}
@@ -0,0 +1,225 @@
+// Row kernels for the hot drawing paths.
+// On AArch64, the NEON versions are selected at runtime. The ARMv7 NEON intrinsics
+// aren't available on the stable compiler: the devices run the portable versions.
+// The blending and the halftone kernels only use integer arithmetic on independent
+// lanes, which LLVM can vectorize when the ARMv7 targets are built with NEON enabled.
+// The segment kernel saves the per-pixel dispatch and square roots, but ARMv7 NEON
+// doesn't vectorize its floating point arithmetic.
+
+use crate::geom::{Vec2, lerp};
+use super::transform::{DITHER_G2_DRIFTS, DITHER_PITCH};
+
+const PITCH: usize = DITHER_PITCH as usize;
+
+// Blends `color` into `dst`: the weight of each destination pixel is given by `mask`.
+// This computes `lerp(dst, color, (255 - mask) / 255)`.
+pub fn blend_row(dst: &mut [u8], mask: &[u8], color: u8) {
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            unsafe { neon::blend_row(dst, mask, color) };
+            return;
+        }
+    }
+
+    portable::blend_row(dst, mask, color)
+}
+
+// Dithers `src` into `dst` with a G2 ordered dithering.
+// The first pixel of the row is at (x, y).
+pub fn halftone_row(dst: &mut [u8], src: &[u8], x: u32, y: u32) {
+    halftone_row_with(dst, src, x, y, &DITHER_G2_DRIFTS)
+}
+
+fn halftone_row_with(dst: &mut [u8], src: &[u8], x: u32, y: u32, drifts: &[i8]) {
+    let len = dst.len().min(src.len());
+    let row = (y as usize % PITCH) * PITCH;
+    let mut start = 0;
+
+    // Split the row at the boundaries of the dither matrix.
+    while start < len {
+        let column = (x as usize + start) % PITCH;
+        let end = (start + PITCH - column).min(len);
+        let drifts = &drifts[row + column..row + column + end - start];
+        halftone_run(&mut dst[start..end], &src[start..end], drifts);
+        start = end;
+    }
+}
+
+fn halftone_run(dst: &mut [u8], src: &[u8], drifts: &[i8]) {
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            unsafe { neon::halftone_run(dst, src, drifts) };
+            return;
+        }
+    }
+
+    portable::halftone_run(dst, src, drifts)
+}
+
+// A segment whose radius varies linearly from its start to its end.
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub start_radius: f32,
+    pub end_radius: f32,
+}
+
+// Paints, with `color`, the pixels of the row that are covered by the segment.
+// The first pixel of the row is at (x, y).
+pub fn segment_row(dst: &mut [u8], x: i32, y: i32, segment: &Segment, color: u8) {
+    let ab = segment.end - segment.start;
+    let l2 = ab.dot(ab);
+    let py = y as f32 + 0.5 - segment.start.y;
+
+    for (i, d) in dst.iter_mut().enumerate() {
+        let px = (x + i as i32) as f32 + 0.5 - segment.start.x;
+        let t = if l2 < f32::EPSILON {
+            0.0
+        } else {
+            ((px * ab.x + py * ab.y) / l2).clamp(0.0, 1.0)
+        };
+        let (dx, dy) = (px - t * ab.x, py - t * ab.y);
+        let radius = lerp(segment.start_radius, segment.end_radius, t);
+        if dx * dx + dy * dy <= radius * radius {
+            *d = color;
+        }
+    }
+}
+
+mod portable {
+    // Exact for x ∈ {0 .. 65025}.
+    #[inline]
+    fn div255(x: u16) -> u8 {
+        let x = x + 1;
+        ((x + (x >> 8)) >> 8) as u8
+    }
+
+    pub fn blend_row(dst: &mut [u8], mask: &[u8], color: u8) {
+        let color = color as u16;
+        for (d, &m) in dst.iter_mut().zip(mask) {
+            let m = m as u16;
+            *d = div255(*d as u16 * m + color * (255 - m));
+        }
+    }
+
+    pub fn halftone_run(dst: &mut [u8], src: &[u8], drifts: &[i8]) {
+        for ((d, &s), &drift) in dst.iter_mut().zip(src).zip(drifts) {
+            let c = s as i16 + drift as i16;
+            *d = if s == 255 || c >= 128 { 255 } else { 0 };
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn blend_row(dst: &mut [u8], mask: &[u8], color: u8) {
+        let len = dst.len().min(mask.len());
+        let n = len - len % 16;
+        let c = vdupq_n_u8(color);
+        let one = vdupq_n_u16(1);
+
+        for i in (0..n).step_by(16) {
+            let d = vld1q_u8(dst.as_ptr().add(i));
+            let m = vld1q_u8(mask.as_ptr().add(i));
+            let im = vmvnq_u8(m);
+            let lo = vmlal_u8(vmull_u8(vget_low_u8(d), vget_low_u8(m)), vget_low_u8(c), vget_low_u8(im));
+            let hi = vmlal_high_u8(vmull_high_u8(d, m), c, im);
+            let lo = vaddq_u16(lo, one);
+            let hi = vaddq_u16(hi, one);
+            let lo = vsraq_n_u16::<8>(lo, lo);
+            let hi = vsraq_n_u16::<8>(hi, hi);
+            vst1q_u8(dst.as_mut_ptr().add(i), vcombine_u8(vshrn_n_u16::<8>(lo), vshrn_n_u16::<8>(hi)));
+        }
+
+        super::portable::blend_row(&mut dst[n..len], &mask[n..len], color);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn halftone_run(dst: &mut [u8], src: &[u8], drifts: &[i8]) {
+        let len = dst.len().min(src.len()).min(drifts.len());
+        let n = len - len % 16;
+        let threshold = vdupq_n_s16(128);
+        let white = vdupq_n_u8(255);
+
+        for i in (0..n).step_by(16) {
+            let s = vld1q_u8(src.as_ptr().add(i));
+            let d = vld1q_s8(drifts.as_ptr().add(i));
+            let lo = vaddq_s16(vreinterpretq_s16_u16(vmovl_u8(vget_low_u8(s))), vmovl_s8(vget_low_s8(d)));
+            let hi = vaddq_s16(vreinterpretq_s16_u16(vmovl_high_u8(s)), vmovl_high_s8(d));
+            let lo = vmovn_u16(vcgeq_s16(lo, threshold));
+            let hi = vmovn_u16(vcgeq_s16(hi, threshold));
+            let out = vorrq_u8(vcombine_u8(lo, hi), vceqq_u8(s, white));
+            vst1q_u8(dst.as_mut_ptr().add(i), out);
+        }
+
+        super::portable::halftone_run(&mut dst[n..len], &src[n..len], &drifts[n..len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn test_blend_row() {
+        let mask = samples(1000, 1);
+        let mut dst = samples(1000, 2);
+        let reference: Vec<u8> = dst.iter().zip(&mask).map(|(&d, &m)| {
+            ((d as u32 * m as u32 + 17 * (255 - m as u32)) / 255) as u8
+        }).collect();
+        blend_row(&mut dst, &mask, 17);
+        assert_eq!(dst, reference);
+    }
+
+    #[test]
+    fn test_segment_row() {
+        use crate::geom::nearest_segment_point;
+        let segment = Segment { start: vec2!(12.5, 7.5), end: vec2!(90.5, 41.5),
+                                start_radius: 3.0, end_radius: 9.5 };
+        for y in 0..50 {
+            let mut dst = vec![255; 110];
+            segment_row(&mut dst, -5, y, &segment, 0);
+            for (i, &d) in dst.iter().enumerate() {
+                let p = vec2!((i as i32 - 5) as f32 + 0.5, y as f32 + 0.5);
+                let (n, t) = nearest_segment_point(p, segment.start, segment.end);
+                let (distance, radius) = ((n - p).length(), lerp(segment.start_radius, segment.end_radius, t));
+                // The rounding may only differ on the edge.
+                if (distance - radius).abs() > 1e-3 {
+                    assert_eq!(d == 0, distance <= radius);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_halftone_row() {
+        let drifts: Vec<i8> = samples(PITCH * PITCH, 3).into_iter().map(|v| v as i8).collect();
+        let mut src = samples(300, 4);
+        src[0] = 0;
+        src[1] = 255;
+        let mut dst = vec![127; src.len()];
+        let (x, y) = (70, 200);
+        halftone_row_with(&mut dst, &src, x, y, &drifts);
+        for (i, (&d, &s)) in dst.iter().zip(&src).enumerate() {
+            let addr = (x as usize + i) % PITCH + (y as usize % PITCH) * PITCH;
+            let c = (s as i16 + drifts[addr] as i16).clamp(0, 255);
+            let expected = if s == 255 || c >= 128 { 255 } else { 0 };
+            assert_eq!(d, expected);
+        }
+        assert_eq!(dst[0], 0);
+        assert_eq!(dst[1], 255);
+    }
+}
@@ -3,7 +3,7 @@ use super::image::Pixmap;
 
 pub type ColorTransform = fn(u32, u32, u8) -> u8;
 
-const DITHER_PITCH: u32 = 128;
+pub const DITHER_PITCH: u32 = 128;
 
 lazy_static! {
     // Tileable blue noise matrix.
@@ -1,14 +1,16 @@
 mod standard;
 mod natural;
 mod premixed;
+mod pwm;
 
 use serde::{Serialize, Deserialize};
 pub use self::standard::StandardFrontlight;
 pub use self::natural::NaturalFrontlight;
 pub use self::premixed::PremixedFrontlight;
+pub use self::pwm::PwmFrontlight;
 use crate::geom::lerp;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LightLevels {
     pub intensity: f32,
     pub warmth: f32,
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Error, Context};
+use crate::settings::FrontlightPwmSettings;
+use super::{Frontlight, LightLevels};
+
+const PWM_PERIOD: &str = "period";
+const PWM_DUTY_CYCLE: &str = "duty_cycle";
+const PWM_ENABLE: &str = "enable";
+
+// Intensities below this fraction of the flicker-free minimum are turned off instead.
+const OFF_THRESHOLD: f32 = 0.5;
+
+// Keeps the intensity within the flicker-free range: the intensities
+// below the range are raised to its minimum, or turned off.
+pub fn flicker_free_intensity(value: f32, settings: &FrontlightPwmSettings) -> f32 {
+    if !settings.flicker_sensitive || value <= 0.0 {
+        return value;
+    }
+    let (min, max) = (settings.flicker_free_min.clamp(0.0, 100.0),
+                      settings.flicker_free_max.clamp(0.0, 100.0));
+    if value < OFF_THRESHOLD * min {
+        0.0
+    } else {
+        value.clamp(min, max.max(min))
+    }
+}
+
+struct Pwm {
+    dir: PathBuf,
+    // In nanoseconds.
+    period: u64,
+    inverted: bool,
+}
+
+impl Pwm {
+    fn new(dir: &Path, frequency: u32, inverted: bool) -> Result<Pwm, Error> {
+        let period = if frequency > 0 {
+            let period = 1_000_000_000 / frequency as u64;
+            // The duty cycle can't exceed the period.
+            fs::write(dir.join(PWM_DUTY_CYCLE), "0").ok();
+            fs::write(dir.join(PWM_PERIOD), period.to_string())
+               .with_context(|| format!("can't set the PWM period of {}", dir.display()))?;
+            period
+        } else {
+            fs::read_to_string(dir.join(PWM_PERIOD))?.trim().parse()?
+        };
+        fs::write(dir.join(PWM_ENABLE), "1")
+           .with_context(|| format!("can't enable {}", dir.display()))?;
+        Ok(Pwm { dir: dir.to_path_buf(), period, inverted })
+    }
+
+    fn set_duty(&self, percent: f32) {
+        let mut duty = (percent.clamp(0.0, 100.0) / 100.0 * self.period as f32) as u64;
+        if self.inverted {
+            duty = self.period - duty.min(self.period);
+        }
+        if let Err(e) = fs::write(self.dir.join(PWM_DUTY_CYCLE), duty.to_string()) {
+            eprintln!("Can't set the PWM duty cycle: {:#}.", e);
+        }
+    }
+}
+
+// Applies the flicker-free range to the intensity and, when a PWM channel is given,
+// drives the intensity through its duty cycle at the configured frequency.
+pub struct PwmFrontlight {
+    inner: Box<dyn Frontlight>,
+    pwm: Option<Pwm>,
+    settings: FrontlightPwmSettings,
+    intensity: f32,
+}
+
+impl PwmFrontlight {
+    // Returns the given frontlight untouched when there's nothing to control.
+    pub fn wrap(inner: Box<dyn Frontlight>, settings: &FrontlightPwmSettings) -> Box<dyn Frontlight> {
+        let pwm = settings.path.as_ref().and_then(|path| {
+            Pwm::new(path, settings.frequency, settings.inverted)
+               .map_err(|e| eprintln!("Can't control the frontlight's PWM: {:#}.", e))
+               .ok()
+        });
+        if pwm.is_none() && !settings.flicker_sensitive {
+            return inner;
+        }
+        let intensity = inner.levels().intensity;
+        Box::new(PwmFrontlight {
+            inner,
+            pwm,
+            settings: settings.clone(),
+            intensity,
+        }) as Box<dyn Frontlight>
+    }
+}
+
+impl Frontlight for PwmFrontlight {
+    fn set_intensity(&mut self, value: f32) {
+        let value = flicker_free_intensity(value, &self.settings);
+        if let Some(pwm) = self.pwm.as_ref() {
+            pwm.set_duty(value);
+        } else {
+            self.inner.set_intensity(value);
+        }
+        self.intensity = value;
+    }
+
+    fn set_warmth(&mut self, value: f32) {
+        self.inner.set_warmth(value);
+    }
+
+    fn levels(&self) -> LightLevels {
+        LightLevels {
+            intensity: self.intensity,
+            warmth: self.inner.levels().warmth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flicker_free_intensity() {
+        let mut settings = FrontlightPwmSettings {
+            flicker_free_min: 20.0,
+            flicker_free_max: 90.0,
+            .. Default::default()
+        };
+        assert_eq!(flicker_free_intensity(5.0, &settings), 5.0);
+        settings.flicker_sensitive = true;
+        assert_eq!(flicker_free_intensity(0.0, &settings), 0.0);
+        assert_eq!(flicker_free_intensity(5.0, &settings), 0.0);
+        assert_eq!(flicker_free_intensity(12.0, &settings), 20.0);
+        assert_eq!(flicker_free_intensity(50.0, &settings), 50.0);
+        assert_eq!(flicker_free_intensity(100.0, &settings), 90.0);
+    }
+}
@@ -350,6 +350,52 @@ pub fn circular_distances(a: u16, mut b: u16, p: u16) -> (u16, u16) {
     (d0, d1)
 }
 
+// Returns the fraction of a full turn, clockwise from the north, of the direction of v.
+pub fn clockwise_fraction(v: Vec2) -> f32 {
+    (v.x.atan2(-v.y) / (2.0 * consts::PI)).rem_euclid(1.0)
+}
+
+// Returns the cumulative fractions at which each slice of a ring chart ends.
+pub fn ring_slices(values: &[f32]) -> Vec<f32> {
+    let total: f32 = values.iter().map(|v| v.max(0.0)).sum();
+    let mut sum = 0.0;
+    values.iter().map(|v| {
+        sum += v.max(0.0);
+        if total > 0.0 { sum / total } else { 0.0 }
+    }).collect()
+}
+
+// Lays out one bar per value, left to right, from the bottom of the given rectangle.
+pub fn bar_rects(rect: &Rectangle, values: &[f32], max: f32, gap: i32) -> Vec<Rectangle> {
+    let count = values.len() as i32;
+    if count == 0 {
+        return Vec::new();
+    }
+    let height = rect.height() as f32;
+    let mut x = rect.min.x;
+    divide(rect.width() as i32 - gap * (count - 1), count).into_iter()
+        .zip(values.iter())
+        .map(|(width, &value)| {
+            let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+            let bar = Rectangle::new(pt!(x, rect.max.y - (ratio * height).round() as i32),
+                                     pt!(x + width, rect.max.y));
+            x += width + gap;
+            bar
+        }).collect()
+}
+
+// Spreads the values evenly over the width of the given rectangle.
+pub fn line_points(rect: &Rectangle, values: &[f32], max: f32) -> Vec<Point> {
+    let last = values.len().saturating_sub(1).max(1) as f32;
+    let width = (rect.width() as i32 - 1) as f32;
+    let height = (rect.height() as i32 - 1) as f32;
+    values.iter().enumerate().map(|(i, &value)| {
+        let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+        pt!(rect.min.x + (i as f32 * width / last).round() as i32,
+            rect.max.y - 1 - (ratio * height).round() as i32)
+    }).collect()
+}
+
 impl Point {
     pub fn new(x: i32, y: i32) -> Point {
         Point { x, y }
@@ -1366,7 +1412,7 @@ impl Region {
 
 #[cfg(test)]
 mod tests {
-    use super::{divide, LinearDir};
+    use super::{divide, LinearDir, clockwise_fraction, ring_slices, bar_rects, line_points};
 
     #[test]
     fn test_linear_dir_opposite() {
@@ -1441,4 +1487,28 @@ mod tests {
         assert_eq!(pt4.rdist2(&rect), 5);
         assert_eq!(pt5.rdist2(&rect), 1);
     }
+
+    #[test]
+    fn test_clockwise_fraction() {
+        assert_eq!(clockwise_fraction(vec2!(0.0, -1.0)), 0.0);
+        assert_eq!(clockwise_fraction(vec2!(1.0, 0.0)), 0.25);
+        assert_eq!(clockwise_fraction(vec2!(0.0, 1.0)), 0.5);
+        assert_eq!(clockwise_fraction(vec2!(-1.0, 0.0)), 0.75);
+    }
+
+    #[test]
+    fn test_ring_slices() {
+        assert_eq!(ring_slices(&[1.0, 1.0, 2.0]), vec![0.25, 0.5, 1.0]);
+        assert_eq!(ring_slices(&[0.0, -3.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_chart_layout() {
+        let rect = rect![0, 0, 32, 10];
+        let bars = bar_rects(&rect, &[10.0, 5.0, 0.0, 20.0], 10.0, 2);
+        assert_eq!(bars, vec![rect![0, 0, 6, 10], rect![8, 5, 15, 10],
+                              rect![17, 10, 23, 10], rect![25, 0, 32, 10]]);
+        let points = line_points(&rect, &[0.0, 5.0, 10.0], 10.0);
+        assert_eq!(points, vec![pt!(0, 9), pt!(16, 4), pt!(31, 0)]);
+    }
 }
@@ -6,6 +6,7 @@ use std::f64;
 use std::time::Duration;
 use std::thread;
 use crate::unit::mm_to_px;
+use crate::letters;
 use crate::input::{DeviceEvent, FingerStatus, ButtonCode, ButtonStatus};
 use crate::view::Event;
 use crate::device::CURRENT_DEVICE;
@@ -13,6 +14,7 @@ use crate::geom::{Point, Vec2, Dir, DiagDir, Axis, nearest_segment_point, elbow}
 
 pub const TAP_JITTER_MM: f32 = 6.0;
 pub const HOLD_JITTER_MM: f32 = 1.5;
+pub const LETTER_MIN_SIZE_MM: f32 = 12.0;
 pub const HOLD_DELAY_SHORT: Duration = Duration::from_millis(666);
 pub const HOLD_DELAY_LONG: Duration = Duration::from_millis(1333);
 
@@ -72,6 +74,7 @@ pub enum GestureEvent {
     },
     Cross(Point),
     Diamond(Point),
+    Letter(char, Point),
     HoldFingerShort(Point, i32),
     HoldFingerLong(Point, i32),
     HoldButtonShort(ButtonCode),
@@ -95,6 +98,7 @@ impl fmt::Display for GestureEvent {
             GestureEvent::Rotate { center, quarter_turns, .. } => write!(f, "Rotate {} {}", center, *quarter_turns as i32 * 90),
             GestureEvent::Cross(pt) => write!(f, "Cross {}", pt),
             GestureEvent::Diamond(pt) => write!(f, "Diamond {}", pt),
+            GestureEvent::Letter(letter, pt) => write!(f, "Letter {} {}", letter, pt),
             GestureEvent::HoldFingerShort(pt, id) => write!(f, "Short-held finger {} {}", id, pt),
             GestureEvent::HoldFingerLong(pt, id) => write!(f, "Long-held finger {} {}", id, pt),
             GestureEvent::HoldButtonShort(code) => write!(f, "Short-held button {:?}", code),
@@ -122,6 +126,7 @@ pub fn parse_gesture_events(rx: &Receiver<DeviceEvent>, ty: &Sender<Event>) {
     let segments: Arc<Mutex<Vec<Vec<Point>>>> = Arc::new(Mutex::new(Vec::new()));
     let tap_jitter = mm_to_px(TAP_JITTER_MM, CURRENT_DEVICE.dpi);
     let hold_jitter = mm_to_px(HOLD_JITTER_MM, CURRENT_DEVICE.dpi);
+    let letter_min_size = mm_to_px(LETTER_MIN_SIZE_MM, CURRENT_DEVICE.dpi);
 
     while let Ok(evt) = rx.recv() {
         ty.send(Event::Device(evt)).ok();
@@ -192,7 +197,11 @@ pub fn parse_gesture_events(rx: &Receiver<DeviceEvent>, ty: &Sender<Event>) {
                 if ct.is_empty() && !sg.is_empty() {
                     let len = sg.len();
                     if len == 1 {
-                        ty.send(Event::Gesture(interpret_segment(&sg.pop().unwrap(), tap_jitter))).ok();
+                        let sp = sg.pop().unwrap();
+                        let ge = letters::recognize(&sp, letter_min_size)
+                                         .map(|(letter, center)| GestureEvent::Letter(letter, center))
+                                         .unwrap_or_else(|| interpret_segment(&sp, tap_jitter));
+                        ty.send(Event::Gesture(ge)).ok();
                     } else if len == 2 {
                         let ge1 = interpret_segment(&sg.pop().unwrap(), tap_jitter);
                         let ge2 = interpret_segment(&sg.pop().unwrap(), tap_jitter);
@@ -0,0 +1,118 @@
+use crate::metadata::BookQuery;
+use crate::notes::{self, NoteKind};
+use crate::view::{EntryKind, EntryId, AppCmd};
+use crate::view::common::main_menu_entries;
+use crate::context::Context;
+
+// The maximum number of results of each kind.
+const MAX_BOOKS: usize = 32;
+const MAX_HEADWORDS: usize = 8;
+const MAX_NOTES: usize = 16;
+
+const SEPARATOR: &str = " › ";
+
+// Flattens the commands of a menu: each one is labeled with the path of its sub-menus.
+pub fn flatten_entries(entries: &[EntryKind], path: &str, results: &mut Vec<(String, EntryId)>) {
+    for entry in entries {
+        match entry {
+            EntryKind::Command(name, id) |
+            EntryKind::CheckBox(name, id, _) |
+            EntryKind::RadioButton(name, id, _) => {
+                results.push((format!("{}{}", path, name), id.clone()));
+            },
+            EntryKind::SubMenu(name, entries) => {
+                flatten_entries(entries, &format!("{}{}{}", path, name, SEPARATOR), results);
+            },
+            EntryKind::More(entries) => {
+                flatten_entries(entries, path, results);
+            },
+            EntryKind::Message(..) | EntryKind::Separator => (),
+        }
+    }
+}
+
+pub fn matches(text: &str, query: &str) -> bool {
+    let text = text.to_lowercase();
+    query.to_lowercase().split_whitespace().all(|word| text.contains(word))
+}
+
+// Searches the library, the main menu, the dictionaries and the notes.
+// Each result is labeled with its kind and opens its target when selected.
+pub fn search(query: &str, context: &mut Context) -> Vec<(String, EntryId)> {
+    let mut results = Vec::new();
+    let query = query.trim();
+    if query.is_empty() {
+        return results;
+    }
+
+    if let Some(book_query) = BookQuery::new(query) {
+        let (files, _) = context.library.list(&context.library.home, Some(&book_query), false);
+        for info in files.into_iter().take(MAX_BOOKS) {
+            let title = if info.title.is_empty() {
+                info.file.path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+            } else if info.author.is_empty() {
+                info.title.clone()
+            } else {
+                format!("{}, {}", info.title, info.author)
+            };
+            results.push((format!("Book: {}", title), EntryId::OpenDocument(info.file.path.clone())));
+        }
+    }
+
+    let mut entries = Vec::new();
+    flatten_entries(&main_menu_entries(context), "", &mut entries);
+    results.extend(entries.into_iter()
+                          .filter(|(name, _)| matches(name, query))
+                          .map(|(name, id)| (format!("Setting: {}", name), id)));
+
+    let mut headwords = 0;
+    for (name, dictionary) in context.dictionaries.iter_mut() {
+        if headwords >= MAX_HEADWORDS {
+            break;
+        }
+        match dictionary.lookup(query, false) {
+            Ok(entries) => {
+                for [headword, _] in entries.into_iter().take(MAX_HEADWORDS - headwords) {
+                    let id = EntryId::Launch(AppCmd::Dictionary { query: headword.clone(), language: String::new() });
+                    results.push((format!("Word: {} ({})", headword, name), id));
+                    headwords += 1;
+                }
+            },
+            Err(e) => eprintln!("Can't search {}: {:#}.", name, e),
+        }
+    }
+
+    let dir = context.library.home.join(&context.settings.notes.path);
+    let notes = notes::list(&dir, &context.settings.notes.pinned);
+    for note in notes.into_iter().filter(|note| note.matches(query)).take(MAX_NOTES) {
+        let app_cmd = if note.kind == NoteKind::Handwritten {
+            AppCmd::HandwrittenNote { name: Some(note.name.clone()) }
+        } else {
+            AppCmd::Notes { note: Some(note.name.clone()) }
+        };
+        results.push((format!("Note: {}", note.title), EntryId::Launch(app_cmd)));
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_entries() {
+        let entries = vec![EntryKind::Command("About".to_string(), EntryId::About),
+                           EntryKind::Separator,
+                           EntryKind::SubMenu("Applications".to_string(),
+                                              vec![EntryKind::Command("Calculator".to_string(),
+                                                                      EntryId::Launch(AppCmd::Calculator))]),
+                           EntryKind::CheckBox("Invert Colors".to_string(), EntryId::ToggleInverted, false)];
+        let mut results = Vec::new();
+        flatten_entries(&entries, "", &mut results);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1], ("Applications › Calculator".to_string(), EntryId::Launch(AppCmd::Calculator)));
+        assert!(matches(&results[2].0, "colors inv"));
+        assert!(!matches(&results[0].0, "colors"));
+    }
+}
@@ -0,0 +1,74 @@
+use std::str::FromStr;
+use crate::helpers::Fp;
+
+const SCHEME: &str = "plato:";
+
+// The reading position of a book, passed from one device to another as a short text,
+// e.g. `plato:0123456789ABCDEF:42:9780141439518`. The identifier, usually an ISBN,
+// finds the book when the other device has a different copy of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handoff {
+    pub fp: Fp,
+    pub page: usize,
+    pub identifier: Option<String>,
+}
+
+impl Handoff {
+    pub fn new(fp: Fp, page: usize, identifier: &str) -> Handoff {
+        let identifier = Some(identifier.trim())
+                             .filter(|id| !id.is_empty() && !id.contains(':'))
+                             .map(String::from);
+        Handoff { fp, page, identifier }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self.identifier {
+            Some(ref identifier) => format!("{}{}:{}:{}", SCHEME, self.fp, self.page, identifier),
+            None => format!("{}{}:{}", SCHEME, self.fp, self.page),
+        }
+    }
+
+    // Accepts typed payloads: the scheme is optional, and case and spaces don't matter.
+    pub fn parse(text: &str) -> Option<Handoff> {
+        let text = text.split_whitespace().collect::<String>();
+        let text = if text.len() >= SCHEME.len() && text[..SCHEME.len()].eq_ignore_ascii_case(SCHEME) {
+            &text[SCHEME.len()..]
+        } else {
+            &text[..]
+        };
+        let mut parts = text.splitn(3, ':');
+        let fp = parts.next().filter(|fp| fp.len() == 16)
+                      .and_then(|fp| Fp::from_str(fp).ok())?;
+        let page = parts.next()?.parse::<usize>().ok()?;
+        let identifier = parts.next().filter(|id| !id.is_empty()).map(String::from);
+        Some(Handoff { fp, page, identifier })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let fp = Fp::from_str("0123456789ABCDEF").unwrap();
+        let handoff = Handoff::new(fp, 42, " 9780141439518 ");
+        assert_eq!(handoff.to_text(), "plato:0123456789ABCDEF:42:9780141439518");
+        assert_eq!(Handoff::parse(&handoff.to_text()), Some(handoff));
+        let handoff = Handoff::new(fp, 7, "urn:uuid:1234");
+        assert_eq!(handoff.to_text(), "plato:0123456789ABCDEF:7");
+        assert_eq!(Handoff::parse(&handoff.to_text()), Some(handoff));
+    }
+
+    #[test]
+    fn test_typed() {
+        let fp = Fp::from_str("0123456789ABCDEF").unwrap();
+        assert_eq!(Handoff::parse(" PLATO:0123 4567 89ab cdef:12 "),
+                   Some(Handoff { fp, page: 12, identifier: None }));
+        assert_eq!(Handoff::parse("0123456789abcdef:0:"),
+                   Some(Handoff { fp, page: 0, identifier: None }));
+        assert_eq!(Handoff::parse("plato:0123456789ABCDEF"), None);
+        assert_eq!(Handoff::parse("plato:0123:12"), None);
+        assert_eq!(Handoff::parse("plato:0123456789ABCDEF:twelve"), None);
+    }
+}
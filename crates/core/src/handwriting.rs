@@ -0,0 +1,170 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value as JsonValue};
+use anyhow::{Error, format_err};
+use crate::geom::Rectangle;
+use crate::settings::{HandwritingSettings, network_env};
+
+// The extension of the recognition results, saved next to their sketches.
+pub const JIIX_EXTENSION: &str = "jiix";
+
+const MILLIMETERS_PER_INCH: f32 = 25.4;
+
+// The points of a stroke: their positions, in pixels, and their times, in seconds.
+pub type Stroke = Vec<(i32, i32, f64)>;
+
+// The recognized text, as exported by MyScript's interactive ink SDK.
+// https://developer.myscript.com/docs/interactive-ink/latest/reference/jiix/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Jiix {
+    pub label: String,
+    pub words: Vec<JiixWord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JiixWord {
+    pub label: String,
+    pub candidates: Vec<String>,
+    // The spaces and the line breaks have no bounding box.
+    #[serde(rename = "bounding-box", skip_serializing_if = "Option::is_none")]
+    pub bounding_box: Option<BoundingBox>,
+}
+
+// In millimeters.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Jiix {
+    pub fn parse(text: &str) -> Result<Jiix, Error> {
+        serde_json::from_str(text).map_err(Into::into)
+    }
+
+    // The words and their rectangles, in the pixels of a page of the given resolution.
+    pub fn words(&self, dpi: u16) -> Vec<(String, Rectangle)> {
+        let scale = dpi as f32 / MILLIMETERS_PER_INCH;
+        self.words.iter().filter_map(|word| {
+            let bb = word.bounding_box?;
+            if word.label.trim().is_empty() {
+                return None;
+            }
+            let rect = rect![(bb.x * scale).round() as i32, (bb.y * scale).round() as i32,
+                             ((bb.x + bb.width) * scale).round() as i32,
+                             ((bb.y + bb.height) * scale).round() as i32];
+            Some((word.label.clone(), rect))
+        }).collect()
+    }
+}
+
+// The body of a request to the batch API: the strokes, in a single group, to be recognized as text.
+// https://developer.myscript.com/docs/interactive-ink/latest/web/reference/rest-api/
+pub fn batch_request(strokes: &[Stroke], width: u32, height: u32, dpi: u16, language: &str) -> JsonValue {
+    let strokes = strokes.iter().filter(|stroke| !stroke.is_empty()).map(|stroke| {
+        json!({
+            "x": stroke.iter().map(|p| p.0).collect::<Vec<i32>>(),
+            "y": stroke.iter().map(|p| p.1).collect::<Vec<i32>>(),
+            "t": stroke.iter().map(|p| (p.2 * 1000.0).round() as i64).collect::<Vec<i64>>(),
+        })
+    }).collect::<Vec<JsonValue>>();
+    json!({
+        "configuration": {
+            "lang": language,
+            "export": {
+                "jiix": {
+                    "bounding-box": true,
+                    "strokes": false,
+                    "text": { "chars": false, "words": true },
+                },
+            },
+        },
+        "xDPI": dpi,
+        "yDPI": dpi,
+        "contentType": "Text",
+        "width": width,
+        "height": height,
+        "strokeGroups": [{ "strokes": strokes }],
+    })
+}
+
+// The HMAC of the request's body, keyed with both the application and the HMAC keys.
+pub fn signature(body: &str, application_key: &str, hmac_key: &str) -> String {
+    let key = format!("{}{}", application_key, hmac_key);
+    let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Runs the program that queries the recognition service, and returns the JIIX document it prints.
+pub fn recognize(strokes: &[Stroke], width: u32, height: u32, dpi: u16, settings: &HandwritingSettings) -> Result<String, Error> {
+    let application_key = settings.application_key.as_ref()
+                                  .ok_or_else(|| format_err!("missing application key"))?;
+    let hmac_key = settings.hmac_key.as_deref().unwrap_or_default();
+    let body = batch_request(strokes, width, height, dpi, &settings.language).to_string();
+    let mut child = network_env(&mut Command::new(&settings.program))
+                            .arg(&settings.url)
+                            .env("PLATO_MYSCRIPT_APPLICATION_KEY", application_key)
+                            .env("PLATO_MYSCRIPT_HMAC", signature(&body, application_key, hmac_key))
+                            .stdin(Stdio::piped())
+                            .stdout(Stdio::piped())
+                            .stderr(Stdio::piped())
+                            .spawn()
+                            .map_err(|e| format_err!("can't run {}: {}", settings.program.display(), e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format_err!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    Jiix::parse(&text)?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_request() {
+        let strokes = vec![vec![(10, 20, 1.5), (12, 24, 1.5125)], Vec::new(), vec![(40, 20, 2.0)]];
+        let request = batch_request(&strokes, 600, 800, 300, "fr_FR");
+        assert_eq!(request["configuration"]["lang"], "fr_FR");
+        assert_eq!(request["xDPI"], 300);
+        let strokes = request["strokeGroups"][0]["strokes"].as_array().unwrap();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0]["x"], json!([10, 12]));
+        assert_eq!(strokes[0]["t"], json!([1500, 1513]));
+        assert_eq!(strokes[1]["y"], json!([20]));
+        // RFC 4231, test case 2.
+        assert_eq!(signature("what do ya want for nothing?", "Je", "fe"),
+                   "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737");
+    }
+
+    #[test]
+    fn test_words() {
+        let text = r#"{"type": "Text", "label": "Hello world", "words": [
+                        {"label": "Hello", "candidates": ["Hello", "hello"],
+                         "bounding-box": {"x": 2.54, "y": 5.08, "width": 10.16, "height": 2.5}},
+                        {"label": " "},
+                        {"label": "world", "bounding-box": {"x": 15.0, "y": 5.0, "width": 8.0, "height": 3.0}}],
+                      "version": "3", "id": "MainBlock"}"#;
+        let jiix = Jiix::parse(text).unwrap();
+        assert_eq!(jiix.label, "Hello world");
+        assert_eq!(jiix.words[0].candidates.len(), 2);
+        let words = jiix.words(100);
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].0, "Hello");
+        assert_eq!(words[0].1, rect![10, 20, 50, 30]);
+        assert_eq!(words[1].0, "world");
+    }
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::io::{self, Write};
 use std::char;
 use std::fmt;
 use std::str::FromStr;
@@ -12,9 +12,10 @@ use std::ops::{Deref, DerefMut};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use serde::de::{self, Visitor};
 use lazy_static::lazy_static;
+use regex::Regex;
 use entities::ENTITIES;
 use walkdir::DirEntry;
-use anyhow::{Error, Context};
+use anyhow::{Error, Context, format_err};
 
 lazy_static! {
     pub static ref CHARACTER_ENTITIES: FxHashMap<&'static str, &'static str> = {
@@ -66,6 +67,14 @@ pub fn decode_entities(text: &str) -> Cow<str> {
     Cow::Owned(buf)
 }
 
+// Removes the markup of an HTML fragment.
+pub fn plain_text(html: &str) -> String {
+    lazy_static! {
+        static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    }
+    decode_entities(&TAG.replace_all(html, " ")).into_owned()
+}
+
 pub fn load_json<T, P: AsRef<Path>>(path: P) -> Result<T, Error> where for<'a> T: Deserialize<'a> {
     let file = File::open(path.as_ref())
                     .with_context(|| format!("can't open file {}", path.as_ref().display()))?;
@@ -75,11 +84,9 @@ pub fn load_json<T, P: AsRef<Path>>(path: P) -> Result<T, Error> where for<'a> T
 }
 
 pub fn save_json<T, P: AsRef<Path>>(data: &T, path: P) -> Result<(), Error> where T: Serialize {
-    let file = File::create(path.as_ref())
-                    .with_context(|| format!("can't create file {}", path.as_ref().display()))?;
-    serde_json::to_writer_pretty(file, data)
-               .with_context(|| format!("can't serialize to JSON file {}", path.as_ref().display()))
-               .map_err(Into::into)
+    let buf = serde_json::to_vec_pretty(data)
+                         .with_context(|| format!("can't serialize to JSON file {}", path.as_ref().display()))?;
+    write_atomically(path, &buf)
 }
 
 pub fn load_toml<T, P: AsRef<Path>>(path: P) -> Result<T, Error> where for<'a> T: Deserialize<'a> {
@@ -93,9 +100,34 @@ pub fn load_toml<T, P: AsRef<Path>>(path: P) -> Result<T, Error> where for<'a> T
 pub fn save_toml<T, P: AsRef<Path>>(data: &T, path: P) -> Result<(), Error> where T: Serialize {
     let s = toml::to_string(data)
                  .context("can't convert to TOML format")?;
-    fs::write(path.as_ref(), &s)
-       .with_context(|| format!("can't write to file {}", path.as_ref().display()))
-       .map_err(Into::into)
+    write_atomically(path, s.as_bytes())
+}
+
+// Replaces the content of a file at once: the data is written to a temporary file
+// of the same directory, which is synced and then renamed over the target. If the
+// power fails midway, the target keeps either its previous or its new content.
+pub fn write_atomically<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<(), Error> {
+    let path = path.as_ref();
+    let file_name = path.file_name()
+                        .ok_or_else(|| format_err!("invalid file path {}", path.display()))?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let written = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        fs::remove_file(&tmp_path).ok();
+        return Err(Error::new(e).context(format!("can't write to file {}", tmp_path.display())));
+    }
+
+    fs::rename(&tmp_path, path)
+       .with_context(|| format!("can't rename {} to {}", tmp_path.display(), path.display()))?;
+
+    // Persist the rename itself.
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    File::open(dir).and_then(|dir| dir.sync_all()).ok();
+    Ok(())
 }
 
 pub trait Fingerprint {
@@ -110,7 +142,7 @@ impl Fingerprint for Metadata {
     }
 }
 
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Hash, Eq, PartialEq)]
 pub struct Fp(u64);
 
 impl Deref for Fp {
@@ -244,6 +276,19 @@ impl IsHidden for DirEntry {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_write_atomically() {
+        let dir = std::env::temp_dir().join(format!("plato-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        write_atomically(&path, b"old").unwrap();
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(write_atomically(dir.join("missing").join("state.json"), b"new").is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_entities() {
         assert_eq!(decode_entities("a &amp b"), "a &amp b");
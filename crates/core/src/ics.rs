@@ -0,0 +1,547 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use fxhash::FxHashMap;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use anyhow::{Error, format_err};
+use crate::view::{Event, Hub};
+use crate::jobs::{Jobs, RetryPolicy};
+use crate::settings::network_env;
+
+pub const CALENDAR_PROGRAM: &str = "bin/calendar/calendar";
+
+// Guards against rules that never produce an occurrence in the requested range.
+const MAX_ITERATIONS: usize = 10_000;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<usize>,
+    pub until: Option<NaiveDateTime>,
+    // The days of the week, with their rank in the month for monthly rules: *-1* for the last one,
+    // *0* for all of them.
+    pub weekdays: Vec<(i32, Weekday)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub location: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+    pub recurrence: Option<Recurrence>,
+    pub exceptions: Vec<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Occurrence<'a> {
+    pub event: &'a CalendarEvent,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl<'a> Occurrence<'a> {
+    pub fn time_range(&self) -> String {
+        if self.event.all_day {
+            "All day".to_string()
+        } else if self.end > self.start {
+            format!("{}–{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+        } else {
+            self.start.format("%H:%M").to_string()
+        }
+    }
+}
+
+// Joins the folded lines: a line that starts with a space or a tab continues the previous one.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+    lines
+}
+
+// The name, the parameters and the value of a content line.
+type ContentLine<'a> = (String, Vec<(String, String)>, &'a str);
+
+fn split_line(line: &str) -> Option<ContentLine<'_>> {
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    }).map(|(i, _)| i)?;
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts.filter_map(|param| {
+        param.split_once('=').map(|(key, value)| (key.to_ascii_uppercase(), value.trim_matches('"').to_string()))
+    }).collect();
+    Some((name, params, &line[colon+1..]))
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => text.push('\n'),
+                Some(c) => text.push(c),
+                None => (),
+            }
+        } else {
+            text.push(c);
+        }
+    }
+    text
+}
+
+// Returns the local date and time, and whether the value is a date.
+// The times given with a `TZID` are taken as local times: the devices don't have the time zone database.
+fn parse_date_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    if value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok()
+                         .map(|date| (date.and_time(NaiveTime::MIN), true));
+    }
+    if let Some(value) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).with_timezone(&Local).naive_local(), false));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+                  .map(|time| (time, false))
+}
+
+// Parses durations such as `PT1H30M` or `P2D`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+    let mut seconds = 0i64;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => (),
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match c {
+                    'W' => 7 * 86_400,
+                    'D' => 86_400,
+                    'H' => 3_600,
+                    'M' => 60,
+                    'S' => 1,
+                    _ => return None,
+                };
+            },
+        }
+    }
+    Some(Duration::seconds(sign * seconds))
+}
+
+// Parses days such as `TU` or `-1SU`.
+fn parse_weekday(value: &str) -> Option<(i32, Weekday)> {
+    let index = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (rank, name) = value.split_at(index);
+    let rank = if rank.is_empty() { 0 } else { rank.parse().ok()? };
+    let day = match name {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }?;
+    Some((rank, day))
+}
+
+fn parse_rule(value: &str) -> Option<Recurrence> {
+    let mut recurrence = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        weekdays: Vec::new(),
+    };
+    let mut frequency = None;
+    for part in value.split(';') {
+        let (key, value) = match part.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    "YEARLY" => Some(Frequency::Yearly),
+                    _ => None,
+                };
+            },
+            "INTERVAL" => recurrence.interval = value.parse().unwrap_or(1).max(1),
+            "COUNT" => recurrence.count = value.parse().ok(),
+            "UNTIL" => recurrence.until = parse_date_time(value).map(|(time, is_date)| {
+                if is_date { time + Duration::days(1) - Duration::seconds(1) } else { time }
+            }),
+            "BYDAY" => recurrence.weekdays = value.split(',').filter_map(parse_weekday).collect(),
+            _ => (),
+        }
+    }
+    recurrence.frequency = frequency?;
+    Some(recurrence)
+}
+
+#[derive(Default)]
+struct Builder {
+    uid: String,
+    summary: String,
+    location: String,
+    start: Option<(NaiveDateTime, bool)>,
+    end: Option<NaiveDateTime>,
+    duration: Option<Duration>,
+    recurrence: Option<Recurrence>,
+    exceptions: Vec<NaiveDateTime>,
+    recurrence_id: Option<NaiveDateTime>,
+    cancelled: bool,
+}
+
+impl Builder {
+    fn build(self) -> Option<(CalendarEvent, Option<NaiveDateTime>)> {
+        let (start, all_day) = self.start?;
+        let end = self.end
+                      .or_else(|| self.duration.map(|d| start + d))
+                      .unwrap_or_else(|| if all_day { start + Duration::days(1) } else { start });
+        Some((CalendarEvent {
+            uid: self.uid,
+            summary: self.summary,
+            location: self.location,
+            start,
+            end: end.max(start),
+            all_day,
+            recurrence: self.recurrence,
+            exceptions: self.exceptions,
+        }, self.recurrence_id))
+    }
+}
+
+// Reads the events of an iCalendar file.
+pub fn parse(text: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut overrides = Vec::new();
+    let mut builder: Option<Builder> = None;
+
+    for line in unfold(text) {
+        let (name, params, value) = match split_line(&line) {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if name == "BEGIN" && value.eq_ignore_ascii_case("VEVENT") {
+            builder = Some(Builder::default());
+            continue;
+        }
+        let current = match builder.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        match name.as_str() {
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(current) = builder.take().filter(|b| !b.cancelled) {
+                    if let Some((event, recurrence_id)) = current.build() {
+                        if let Some(time) = recurrence_id {
+                            overrides.push((event.uid.clone(), time));
+                        }
+                        events.push(event);
+                    }
+                }
+            },
+            "UID" => current.uid = value.to_string(),
+            "SUMMARY" => current.summary = unescape(value),
+            "LOCATION" => current.location = unescape(value),
+            "DTSTART" => {
+                let is_date = params.iter().any(|(k, v)| k == "VALUE" && v == "DATE");
+                current.start = parse_date_time(value).map(|(time, date)| (time, date || is_date));
+            },
+            "DTEND" => current.end = parse_date_time(value).map(|(time, _)| time),
+            "DURATION" => current.duration = parse_duration(value),
+            "RRULE" => current.recurrence = parse_rule(value),
+            "EXDATE" => current.exceptions.extend(value.split(',').filter_map(|v| parse_date_time(v).map(|(time, _)| time))),
+            "RECURRENCE-ID" => current.recurrence_id = parse_date_time(value).map(|(time, _)| time),
+            "STATUS" => current.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => (),
+        }
+    }
+
+    // A modified occurrence replaces the one of the recurring event.
+    let mut masters: FxHashMap<String, usize> = FxHashMap::default();
+    for (index, event) in events.iter().enumerate() {
+        if event.recurrence.is_some() {
+            masters.insert(event.uid.clone(), index);
+        }
+    }
+    for (uid, time) in overrides {
+        if let Some(&index) = masters.get(&uid) {
+            events[index].exceptions.push(time);
+        }
+    }
+
+    events
+}
+
+// Loads the events of the `.ics` files of the given directory.
+pub fn load_calendars(dir: &Path) -> Result<Vec<CalendarEvent>, Error> {
+    let mut events = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|e| format_err!("can't read {}: {}", dir.display(), e))?;
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ics")) {
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(text) => events.extend(parse(&text)),
+            Err(e) => eprintln!("Can't read {}: {:#}.", path.display(), e),
+        }
+    }
+    Ok(events)
+}
+
+// Downloads the calendars in the given directory: each address gets its own file.
+pub fn fetch(urls: &[String], dir: &Path, jobs: &Jobs, hub: &Hub) {
+    let mut args = Vec::new();
+    for url in urls {
+        args.push(url.clone());
+        args.push(dir.join(format!("remote-{:016x}.ics", fxhash::hash64(url))).to_string_lossy().into_owned());
+    }
+    let hub2 = hub.clone();
+    jobs.spawn("Fetch the calendars", RetryPolicy::NETWORK, hub, move |attempt| {
+        let error = match network_env(&mut Command::new(CALENDAR_PROGRAM)).args(&args).output() {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        match error {
+            None => {
+                hub2.send(Event::CalendarsFetched(None)).ok();
+                Ok(())
+            },
+            Some(error) => {
+                if attempt.last {
+                    hub2.send(Event::CalendarsFetched(Some(error.clone()))).ok();
+                }
+                Err(format_err!("{}", error))
+            },
+        }
+    });
+}
+
+fn add_months(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    let index = date.year() * 12 + date.month0() as i32 + months as i32;
+    NaiveDate::from_ymd_opt(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, date.day())
+}
+
+// The days of the month of the given date that match the given rank and day of the week.
+fn month_weekdays(date: NaiveDate, rank: i32, weekday: Weekday) -> Vec<NaiveDate> {
+    let days = (1..=31).filter_map(|day| NaiveDate::from_ymd_opt(date.year(), date.month(), day))
+                       .filter(|day| day.weekday() == weekday)
+                       .collect::<Vec<NaiveDate>>();
+    match rank {
+        0 => days,
+        r if r > 0 => days.get(r as usize - 1).cloned().into_iter().collect(),
+        r => days.len().checked_sub(r.unsigned_abs() as usize)
+                 .and_then(|i| days.get(i).cloned()).into_iter().collect(),
+    }
+}
+
+// The candidate start times of the given period of a rule.
+fn period_starts(event: &CalendarEvent, recurrence: &Recurrence, period: u32) -> Vec<NaiveDateTime> {
+    let start = event.start;
+    let step = period * recurrence.interval;
+    match recurrence.frequency {
+        Frequency::Daily => vec![start + Duration::days(step as i64)],
+        Frequency::Weekly if recurrence.weekdays.is_empty() => vec![start + Duration::weeks(step as i64)],
+        Frequency::Weekly => {
+            let monday = start.date() - Duration::days(start.weekday().num_days_from_monday() as i64)
+                                      + Duration::weeks(step as i64);
+            let mut starts = recurrence.weekdays.iter().map(|(_, day)| {
+                (monday + Duration::days(day.num_days_from_monday() as i64)).and_time(start.time())
+            }).filter(|time| *time >= start).collect::<Vec<NaiveDateTime>>();
+            starts.sort();
+            starts
+        },
+        Frequency::Monthly if !recurrence.weekdays.is_empty() => {
+            let first = match start.date().with_day(1).and_then(|date| add_months(date, step)) {
+                Some(first) => first,
+                None => return Vec::new(),
+            };
+            let mut starts = recurrence.weekdays.iter()
+                                       .flat_map(|&(rank, day)| month_weekdays(first, rank, day))
+                                       .map(|date| date.and_time(start.time()))
+                                       .filter(|time| *time >= start)
+                                       .collect::<Vec<NaiveDateTime>>();
+            starts.sort();
+            starts
+        },
+        Frequency::Monthly => add_months(start.date(), step).map(|date| date.and_time(start.time()))
+                                                            .into_iter().collect(),
+        Frequency::Yearly => add_months(start.date(), 12 * step).map(|date| date.and_time(start.time()))
+                                                                .into_iter().collect(),
+    }
+}
+
+// Returns the occurrences that overlap the given range, sorted by start time.
+pub fn occurrences(events: &[CalendarEvent], from: NaiveDateTime, to: NaiveDateTime) -> Vec<Occurrence<'_>> {
+    let mut result = Vec::new();
+
+    for event in events {
+        let duration = event.end - event.start;
+        let overlaps = |start: NaiveDateTime| {
+            let end = start + duration;
+            start < to && (end > from || (end == start && start >= from))
+        };
+
+        let recurrence = match event.recurrence.as_ref() {
+            Some(recurrence) => recurrence,
+            None => {
+                if overlaps(event.start) {
+                    result.push(Occurrence { event, start: event.start, end: event.end });
+                }
+                continue;
+            },
+        };
+
+        let mut count = 0;
+        'periods: for period in 0..MAX_ITERATIONS as u32 {
+            for start in period_starts(event, recurrence, period) {
+                if start >= to || recurrence.until.is_some_and(|until| start > until) ||
+                   recurrence.count.is_some_and(|max| count >= max) {
+                    break 'periods;
+                }
+                count += 1;
+                if overlaps(start) && !event.exceptions.contains(&start) {
+                    result.push(Occurrence { event, start, end: start + duration });
+                }
+            }
+        }
+    }
+
+    result.sort_by_key(|occurrence| (occurrence.start, !occurrence.event.all_day));
+    result
+}
+
+// Groups the occurrences by day: the events that started earlier are listed on the first day.
+pub fn agenda<'a>(occurrences: Vec<Occurrence<'a>>, from: NaiveDate) -> Vec<(NaiveDate, Vec<Occurrence<'a>>)> {
+    let mut days: Vec<(NaiveDate, Vec<Occurrence>)> = Vec::new();
+    for occurrence in occurrences {
+        let date = occurrence.start.date().max(from);
+        match days.last_mut() {
+            Some((last, list)) if *last == date => list.push(occurrence),
+            _ => days.push((date, vec![occurrence])),
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+SUMMARY:Stand-up\\, daily\r\n\
+LOCATION:Room 4\r\n\
+DTSTART;TZID=Europe/Paris:20240603T093000\r\n\
+DURATION:PT15M\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=5\r\n\
+EXDATE;TZID=Europe/Paris:20240605T093000\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+RECURRENCE-ID;TZID=Europe/Paris:20240607T093000\r\n\
+SUMMARY:Stand-up (moved)\r\n\
+DTSTART;TZID=Europe/Paris:20240607T110000\r\n\
+DTEND;TZID=Europe/Paris:20240607T111500\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:holiday\r\n\
+SUMMARY:A very long\r\n\
+\x20 holiday\r\n\
+DTSTART;VALUE=DATE:20240604\r\n\
+DTEND;VALUE=DATE:20240606\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:cancelled\r\n\
+SUMMARY:Cancelled\r\n\
+STATUS:CANCELLED\r\n\
+DTSTART:20240604T100000\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let events = parse(CALENDAR);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "Stand-up, daily");
+        assert_eq!(events[0].end, time("2024-06-03 09:45"));
+        assert_eq!(events[2].summary, "A very long holiday");
+        assert!(events[2].all_day);
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let events = parse(CALENDAR);
+        let list = occurrences(&events, time("2024-06-03 00:00"), time("2024-06-17 00:00"));
+        let summary = list.iter().map(|o| (o.start.format("%d %H:%M").to_string(), o.event.summary.as_str()))
+                          .collect::<Vec<(String, &str)>>();
+        assert_eq!(summary, vec![("03 09:30".to_string(), "Stand-up, daily"),
+                                 ("04 00:00".to_string(), "A very long holiday"),
+                                 ("07 11:00".to_string(), "Stand-up (moved)"),
+                                 ("10 09:30".to_string(), "Stand-up, daily"),
+                                 ("12 09:30".to_string(), "Stand-up, daily")]);
+        let days = agenda(list, time("2024-06-03 00:00").date());
+        assert_eq!(days.len(), 5);
+        assert_eq!(days[1].1[0].time_range(), "All day");
+        assert_eq!(days[0].1[0].time_range(), "09:30–09:45");
+    }
+
+    #[test]
+    fn test_monthly() {
+        let events = parse("BEGIN:VEVENT\nDTSTART:20240131T080000\nRRULE:FREQ=MONTHLY;UNTIL=20240601\nEND:VEVENT\n");
+        let list = occurrences(&events, time("2024-01-01 00:00"), time("2025-01-01 00:00"));
+        assert_eq!(list.iter().map(|o| o.start.month()).collect::<Vec<u32>>(), vec![1, 3, 5]);
+        let events = parse("BEGIN:VEVENT\nDTSTART:20240109T080000\nRRULE:FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=4\nEND:VEVENT\n");
+        let list = occurrences(&events, time("2024-01-01 00:00"), time("2025-01-01 00:00"));
+        assert_eq!(list.iter().map(|o| o.start.format("%m-%d").to_string()).collect::<Vec<String>>(),
+                   vec!["01-09", "01-26", "02-13", "02-23"]);
+    }
+}
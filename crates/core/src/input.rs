@@ -56,6 +56,14 @@ pub const KEY_BACKWARD: u16 = 193;
 pub const KEY_FORWARD: u16 = 194;
 pub const PEN_ERASE: u16 = 331;
 pub const PEN_HIGHLIGHT: u16 = 332;
+// Navigation keys of external keyboards
+pub const KEY_TAB: u16 = 15;
+pub const KEY_ENTER: u16 = 28;
+pub const KEY_SPACE: u16 = 57;
+pub const KEY_UP: u16 = 103;
+pub const KEY_LEFT: u16 = 105;
+pub const KEY_RIGHT: u16 = 106;
+pub const KEY_DOWN: u16 = 108;
 pub const SLEEP_COVER: [u16; 2] = [59, 35];
 // Synthetic touch button
 pub const BTN_TOUCH: u16 = 330;
@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use anyhow::Error;
+use sha2::{Sha256, Digest};
+use crate::document::archive;
+use crate::metadata::FileInfo;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Problem {
+    Missing,
+    Corrupted,
+}
+
+impl Problem {
+    pub fn label(self) -> &'static str {
+        match self {
+            Problem::Missing => "Missing",
+            Problem::Corrupted => "Corrupted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub problems: Vec<(PathBuf, Problem)>,
+    // The checksums of the documents imported before the checksums were recorded.
+    pub checksums: Vec<(PathBuf, String)>,
+}
+
+// The hexadecimal SHA-256 digest of a document, which might be stored inside a ZIP archive.
+pub fn checksum(path: &Path) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    if let Some((zip_path, name)) = archive::resolve(path) {
+        hasher.update(archive::read_entry(&zip_path, &name)?);
+    } else {
+        let mut file = File::open(path)?;
+        let mut buf = vec![0; 1 << 16];
+        loop {
+            match file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => hasher.update(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub struct IntegrityCheck {
+    pub home: PathBuf,
+    pub files: Vec<FileInfo>,
+}
+
+impl IntegrityCheck {
+    // Compares the documents with the sizes and checksums recorded in the database.
+    // The progress is reported as (checked files, total files).
+    pub fn run<F>(&self, cancel: &AtomicBool, mut progress: F) -> IntegrityReport where F: FnMut(usize, usize) {
+        let total = self.files.len();
+        let mut report = IntegrityReport::default();
+
+        for (index, file) in self.files.iter().enumerate() {
+            if cancel.load(AtomicOrdering::Relaxed) {
+                break;
+            }
+
+            progress(index, total);
+
+            let path = self.home.join(&file.path);
+            if !archive::exists(&path) {
+                report.problems.push((file.path.clone(), Problem::Missing));
+                continue;
+            }
+
+            match checksum(&path) {
+                Ok(hash) if file.hash.is_empty() => {
+                    report.checksums.push((file.path.clone(), hash));
+                },
+                Ok(hash) if hash != file.hash => {
+                    report.problems.push((file.path.clone(), Problem::Corrupted));
+                },
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Can't read {}: {:#}.", path.display(), e);
+                    report.problems.push((file.path.clone(), Problem::Corrupted));
+                },
+            }
+        }
+
+        progress(total, total);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_integrity_check() {
+        let home = std::env::temp_dir().join(format!("integrity-{}", std::process::id()));
+        fs::create_dir_all(&home).unwrap();
+        fs::write(home.join("a.epub"), b"abc").unwrap();
+        fs::write(home.join("b.pdf"), b"def").unwrap();
+        fs::write(home.join("c.pdf"), b"ghi").unwrap();
+        let hash = checksum(&home.join("a.epub")).unwrap();
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let file = |path: &str, hash: &str| FileInfo {
+            path: PathBuf::from(path),
+            hash: hash.to_string(),
+            .. Default::default()
+        };
+        let check = IntegrityCheck {
+            home: home.clone(),
+            files: vec![file("a.epub", &hash), file("b.pdf", &hash), file("c.pdf", ""), file("d.cbz", "")],
+        };
+        let report = check.run(&AtomicBool::new(false), |_, _| ());
+        fs::remove_dir_all(&home).ok();
+
+        assert_eq!(report.problems, vec![(PathBuf::from("b.pdf"), Problem::Corrupted),
+                                         (PathBuf::from("d.cbz"), Problem::Missing)]);
+        assert_eq!(report.checksums.len(), 1);
+        assert_eq!(report.checksums[0].0, PathBuf::from("c.pdf"));
+    }
+}
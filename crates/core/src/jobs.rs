@@ -0,0 +1,241 @@
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use anyhow::Error;
+use crate::view::{Event, Hub};
+
+// The number of finished jobs kept for the status view.
+const MAX_FINISHED_JOBS: usize = 16;
+// How often a job waiting for its next attempt checks whether it was cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub type JobId = u64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    // A single attempt.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        attempts: 1,
+        initial_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    pub const NETWORK: RetryPolicy = RetryPolicy {
+        attempts: 4,
+        initial_delay: Duration::from_secs(5),
+        max_delay: Duration::from_secs(60),
+    };
+
+    // The delay that follows the given failed attempt, counted from one: it doubles after each failure.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn flag(&self) -> &AtomicBool {
+        &self.0
+    }
+}
+
+// What a job's function knows about the current attempt.
+pub struct Attempt<'a> {
+    pub number: u32,
+    pub last: bool,
+    pub cancel: &'a CancelToken,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Running,
+    // Waiting for the next attempt after the given error.
+    Retrying(String),
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed(..) | JobStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRecord {
+    pub id: JobId,
+    pub name: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub started: DateTime<Local>,
+    cancel: CancelToken,
+}
+
+// Runs the background jobs in their own threads, retries them when they fail,
+// and keeps track of their status. `Event::JobsChanged` is sent whenever a status changes.
+#[derive(Debug, Clone, Default)]
+pub struct Jobs {
+    records: Arc<Mutex<Vec<JobRecord>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Jobs {
+    pub fn spawn<F>(&self, name: &str, policy: RetryPolicy, hub: &Hub, mut job: F) -> CancelToken
+                   where F: FnMut(&Attempt) -> Result<(), Error> + Send + 'static {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = CancelToken::default();
+        self.push(JobRecord {
+            id,
+            name: name.to_string(),
+            status: JobStatus::Running,
+            attempts: 0,
+            started: Local::now(),
+            cancel: cancel.clone(),
+        });
+        hub.send(Event::JobsChanged).ok();
+
+        let jobs = self.clone();
+        let cancel2 = cancel.clone();
+        let name = name.to_string();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let attempts = policy.attempts.max(1);
+            let mut number = 1;
+            let status = loop {
+                jobs.update(id, |record| {
+                    record.status = JobStatus::Running;
+                    record.attempts = number;
+                });
+                let attempt = Attempt { number, last: number == attempts, cancel: &cancel2 };
+                match job(&attempt) {
+                    Ok(()) => break JobStatus::Done,
+                    Err(_) if cancel2.is_cancelled() => break JobStatus::Cancelled,
+                    Err(e) if number == attempts => {
+                        eprintln!("Can't {}: {:#}.", name.to_lowercase(), e);
+                        break JobStatus::Failed(format!("{:#}", e));
+                    },
+                    Err(e) => {
+                        eprintln!("Can't {} (attempt {} of {}): {:#}.", name.to_lowercase(), number, attempts, e);
+                        jobs.update(id, |record| record.status = JobStatus::Retrying(format!("{:#}", e)));
+                        hub.send(Event::JobsChanged).ok();
+                        if !wait(policy.delay(number), &cancel2) {
+                            break JobStatus::Cancelled;
+                        }
+                        number += 1;
+                    },
+                }
+            };
+            jobs.update(id, |record| record.status = status);
+            hub.send(Event::JobsChanged).ok();
+        });
+
+        cancel
+    }
+
+    pub fn records(&self) -> Vec<JobRecord> {
+        self.records.lock().map(|records| records.clone()).unwrap_or_default()
+    }
+
+    pub fn cancel(&self, id: JobId) {
+        if let Ok(records) = self.records.lock() {
+            if let Some(record) = records.iter().find(|record| record.id == id) {
+                record.cancel.cancel();
+            }
+        }
+    }
+
+    pub fn clear_finished(&self) {
+        if let Ok(mut records) = self.records.lock() {
+            records.retain(|record| !record.status.is_finished());
+        }
+    }
+
+    fn push(&self, record: JobRecord) {
+        if let Ok(mut records) = self.records.lock() {
+            let finished = records.iter().filter(|record| record.status.is_finished()).count();
+            if finished >= MAX_FINISHED_JOBS {
+                if let Some(index) = records.iter().position(|record| record.status.is_finished()) {
+                    records.remove(index);
+                }
+            }
+            records.push(record);
+        }
+    }
+
+    fn update<F>(&self, id: JobId, f: F) where F: FnOnce(&mut JobRecord) {
+        if let Ok(mut records) = self.records.lock() {
+            if let Some(record) = records.iter_mut().find(|record| record.id == id) {
+                f(record);
+            }
+        }
+    }
+}
+
+// Returns false if the job was cancelled in the meantime.
+fn wait(delay: Duration, cancel: &CancelToken) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < delay {
+        if cancel.is_cancelled() {
+            return false;
+        }
+        thread::sleep(CANCEL_POLL_INTERVAL.min(delay.saturating_sub(start.elapsed())));
+    }
+    !cancel.is_cancelled()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use crate::view::hub;
+    use anyhow::format_err;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::NETWORK;
+        assert_eq!(policy.delay(1), Duration::from_secs(5));
+        assert_eq!(policy.delay(3), Duration::from_secs(20));
+        assert_eq!(policy.delay(5), Duration::from_secs(60));
+        assert_eq!(policy.delay(40), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retries() {
+        let (hub, rx) = hub::channel();
+        let jobs = Jobs::default();
+        let policy = RetryPolicy { attempts: 3, initial_delay: Duration::from_millis(1), max_delay: Duration::from_millis(2) };
+        let (tx, results) = mpsc::channel();
+        jobs.spawn("Count", policy, &hub, move |attempt| {
+            tx.send(attempt.number).ok();
+            if attempt.last { Ok(()) } else { Err(format_err!("not yet")) }
+        });
+        assert_eq!(results.iter().take(3).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        while jobs.records().iter().any(|record| !record.status.is_finished()) {
+            rx.recv().ok();
+        }
+        let records = jobs.records();
+        assert_eq!((records[0].status.clone(), records[0].attempts), (JobStatus::Done, 3));
+        jobs.clear_finished();
+        assert!(jobs.records().is_empty());
+    }
+}
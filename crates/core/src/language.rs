@@ -0,0 +1,132 @@
+use crate::document::{Document, Location};
+
+// The languages offered in the menus.
+pub const LANGUAGES: [&str; 14] = ["en", "fr", "de", "es", "it", "pt", "nl",
+                                   "sv", "pl", "cs", "ru", "el", "ja", "zh"];
+
+// The number of locations sampled, evenly spread, within a document.
+const SAMPLES_COUNT: usize = 4;
+// The minimum number of words needed to make a guess.
+const MIN_WORDS_COUNT: usize = 20;
+// The minimum proportion of stop words required to recognize a language.
+const MIN_SCORE: f32 = 0.08;
+
+const STOP_WORDS: [(&str, &[&str]); 10] = [
+    ("en", &["the", "and", "of", "to", "in", "is", "that", "it", "was", "he",
+             "with", "for", "his", "as", "on", "you", "her", "but", "not", "had"]),
+    ("fr", &["le", "la", "les", "et", "des", "un", "une", "est", "que", "qui",
+             "dans", "il", "pas", "pour", "sur", "au", "du", "elle", "ne", "je"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "sie", "er", "zu",
+             "den", "mit", "sich", "des", "auf", "dem", "ein", "eine", "auch", "es"]),
+    ("es", &["el", "los", "las", "y", "que", "en", "una", "es", "por", "con",
+             "no", "se", "del", "lo", "su", "para", "como", "pero", "al", "le"]),
+    ("it", &["il", "di", "che", "e", "è", "un", "per", "non", "della", "si",
+             "con", "gli", "lo", "ma", "più", "sono", "nel", "alla", "anche", "era"]),
+    ("pt", &["o", "os", "que", "e", "do", "da", "em", "um", "uma", "não",
+             "para", "com", "se", "no", "na", "por", "mais", "como", "ele", "ao"]),
+    ("nl", &["de", "het", "een", "en", "van", "is", "dat", "niet", "ik", "je",
+             "op", "te", "zijn", "met", "voor", "hij", "er", "die", "maar", "ook"]),
+    ("sv", &["och", "att", "det", "som", "en", "är", "på", "för", "med", "inte",
+             "jag", "har", "till", "av", "den", "om", "var", "han", "hon", "så"]),
+    ("pl", &["i", "w", "nie", "się", "na", "z", "że", "to", "jest", "do",
+             "jak", "ale", "co", "o", "po", "tak", "od", "za", "jego", "już"]),
+    ("cs", &["a", "v", "se", "na", "je", "že", "to", "s", "z", "do",
+             "o", "jsem", "ale", "jak", "by", "tak", "jako", "po", "za", "který"]),
+];
+
+// The languages recognized by their script.
+fn script_language(c: char) -> Option<&'static str> {
+    match c {
+        '\u{0370}'..='\u{03FF}' => Some("el"),
+        '\u{0400}'..='\u{04FF}' => Some("ru"),
+        '\u{0590}'..='\u{05FF}' => Some("he"),
+        '\u{0600}'..='\u{06FF}' => Some("ar"),
+        '\u{3040}'..='\u{30FF}' => Some("ja"),
+        '\u{AC00}'..='\u{D7AF}' => Some("ko"),
+        '\u{4E00}'..='\u{9FFF}' => Some("zh"),
+        _ => None,
+    }
+}
+
+// Guesses the language of a text, as an IETF language tag,
+// from its script or the frequency of its stop words.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts: Vec<(&str, usize)> = Vec::new();
+    let mut letters_count = 0;
+
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters_count += 1;
+        if let Some(lang) = script_language(c) {
+            if let Some(entry) = scripts.iter_mut().find(|(l, _)| *l == lang) {
+                entry.1 += 1;
+            } else {
+                scripts.push((lang, 1));
+            }
+        }
+    }
+
+    let scripts_count: usize = scripts.iter().map(|(_, count)| count).sum();
+    if scripts_count > 0 && 2 * scripts_count >= letters_count {
+        // Japanese texts also contain Han characters.
+        if scripts.iter().any(|&(lang, _)| lang == "ja") {
+            return Some("ja");
+        }
+        return scripts.iter().max_by_key(|(_, count)| *count).map(|&(lang, _)| lang);
+    }
+
+    let words: Vec<String> = text.split(|c: char| !c.is_alphabetic())
+                                 .filter(|w| !w.is_empty())
+                                 .map(|w| w.to_lowercase())
+                                 .collect();
+
+    if words.len() < MIN_WORDS_COUNT {
+        return None;
+    }
+
+    STOP_WORDS.iter().map(|(lang, stop_words)| {
+        let hits = words.iter().filter(|w| stop_words.contains(&w.as_str())).count();
+        (*lang, hits as f32 / words.len() as f32)
+    }).filter(|&(_, score)| score >= MIN_SCORE)
+      .max_by(|a, b| a.1.total_cmp(&b.1))
+      .map(|(lang, _)| lang)
+}
+
+// Extracts the words found at a few locations of the document.
+pub fn sample_text(doc: &mut dyn Document) -> String {
+    let pages_count = doc.pages_count();
+    let mut text = String::new();
+
+    for i in 1..=SAMPLES_COUNT {
+        let location = i * pages_count / (SAMPLES_COUNT + 1);
+        if let Some((words, _)) = doc.words(Location::Exact(location)) {
+            for word in words {
+                text.push_str(&word.text);
+                text.push(' ');
+            }
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let text = "It was the best of times, it was the worst of times, it was the age of wisdom, \
+                    it was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity.";
+        assert_eq!(detect_language(text), Some("en"));
+        let text = "Longtemps, je me suis couché de bonne heure. Parfois, à peine ma bougie éteinte, \
+                    mes yeux se fermaient si vite que je n'avais pas le temps de me dire : « Je m'endors. »";
+        assert_eq!(detect_language(text), Some("fr"));
+        let text = "Als Gregor Samsa eines Morgens aus unruhigen Träumen erwachte, fand er sich in seinem Bett \
+                    zu einem ungeheueren Ungeziefer verwandelt. Er lag auf seinem panzerartig harten Rücken und sah, \
+                    wenn er den Kopf ein wenig hob, seinen gewölbten, braunen Bauch.";
+        assert_eq!(detect_language(text), Some("de"));
+        let text = "Все счастливые семьи похожи друг на друга, каждая несчастливая семья несчастлива по-своему.";
+        assert_eq!(detect_language(text), Some("ru"));
+        assert_eq!(detect_language("Too short."), None);
+    }
+}
@@ -0,0 +1,168 @@
+use std::f32::consts::PI;
+use crate::geom::{Point, Vec2};
+
+// The number of points of the resampled strokes.
+const SAMPLES_COUNT: usize = 64;
+// The minimum similarity between a stroke and its closest template.
+const MIN_SCORE: f32 = 0.82;
+// The length of the path of a letter is at least this multiple of the distance between its ends.
+// It sets the letters apart from the swipes, arrows and corners.
+const MIN_PATH_RATIO: f32 = 1.8;
+
+// The templates: single-stroke letters drawn in a unit square, the y axis pointing downwards.
+fn templates() -> Vec<(char, Vec<Vec2>)> {
+    let bumps = |top: f32| {
+        let mut points = arc(Vec2::new(0.45, top + 0.25), 0.25, -90.0, 90.0);
+        points.insert(0, Vec2::new(0.0, top));
+        points.push(Vec2::new(0.0, top + 0.5));
+        points
+    };
+    let mut b_up = vec![Vec2::new(0.0, 1.0)];
+    b_up.extend(bumps(0.0));
+    b_up.extend(bumps(0.5));
+    let mut b_down = vec![Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0)];
+    b_down.extend(bumps(0.0));
+    b_down.extend(bumps(0.5));
+
+    let mut g = arc(Vec2::new(0.5, 0.5), 0.5, -60.0, -360.0);
+    g.push(Vec2::new(0.6, 0.5));
+
+    let mut s = arc(Vec2::new(0.5, 0.25), 0.25, -30.0, -270.0);
+    s.extend(arc(Vec2::new(0.5, 0.75), 0.25, -90.0, 150.0));
+
+    vec![
+        ('B', b_up),
+        ('B', b_down),
+        ('C', arc(Vec2::new(0.5, 0.5), 0.5, -60.0, -300.0)),
+        ('G', g),
+        ('M', vec![Vec2::new(0.0, 1.0), Vec2::new(0.1, 0.0), Vec2::new(0.5, 0.7), Vec2::new(0.9, 0.0), Vec2::new(1.0, 1.0)]),
+        ('N', vec![Vec2::new(0.0, 1.0), Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0)]),
+        ('S', s),
+        ('Z', vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0)]),
+    ]
+}
+
+// The points of the arc of the given center and radius, between two angles in degrees.
+// The angles increase clockwise, because the y axis points downwards.
+fn arc(center: Vec2, radius: f32, from: f32, to: f32) -> Vec<Vec2> {
+    let steps = ((to - from).abs() / 10.0).ceil() as usize;
+    (0..=steps).map(|i| {
+        let angle = (from + (to - from) * i as f32 / steps as f32) * PI / 180.0;
+        center + Vec2::new(angle.cos(), angle.sin()) * radius
+    }).collect()
+}
+
+fn path_length(points: &[Vec2]) -> f32 {
+    points.windows(2).map(|w| (w[1] - w[0]).length()).sum()
+}
+
+// Resamples the path into equally spaced points.
+fn resample(points: &[Vec2]) -> Vec<Vec2> {
+    let step = path_length(points) / (SAMPLES_COUNT - 1) as f32;
+    let mut result = vec![points[0]];
+    let mut distance = 0.0;
+    let mut previous = points[0];
+    for &point in &points[1..] {
+        let mut d = (point - previous).length();
+        while distance + d >= step && result.len() < SAMPLES_COUNT {
+            let t = (step - distance) / d;
+            previous = previous + (point - previous) * t;
+            result.push(previous);
+            d = (point - previous).length();
+            distance = 0.0;
+        }
+        distance += d;
+        previous = point;
+    }
+    while result.len() < SAMPLES_COUNT {
+        result.push(points[points.len() - 1]);
+    }
+    result
+}
+
+// Scales the points uniformly to fit a unit square and centers them on the origin.
+// The letters aren't rotated: N and Z only differ by a quarter turn.
+fn normalize(points: &[Vec2]) -> Vec<Vec2> {
+    let points = resample(points);
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in &points {
+        min = Vec2::new(min.x.min(p.x), min.y.min(p.y));
+        max = Vec2::new(max.x.max(p.x), max.y.max(p.y));
+    }
+    let size = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+    let centroid = points.iter().fold(Vec2::new(0.0, 0.0), |c, &p| c + p) / SAMPLES_COUNT as f32;
+    points.iter().map(|&p| (p - centroid) / size).collect()
+}
+
+fn score(a: &[Vec2], b: &[Vec2]) -> f32 {
+    let distance = a.iter().zip(b).map(|(&p, &q)| (p - q).length()).sum::<f32>() / SAMPLES_COUNT as f32;
+    1.0 - distance / (0.5 * 2f32.sqrt())
+}
+
+// Returns the letter drawn by the given stroke, if any, and the center of the stroke.
+// Strokes smaller than *min_size* pixels are ignored.
+pub fn recognize(points: &[Point], min_size: f32) -> Option<(char, Point)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let points: Vec<Vec2> = points.iter().map(|&pt| pt.into()).collect();
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in &points {
+        min = Vec2::new(min.x.min(p.x), min.y.min(p.y));
+        max = Vec2::new(max.x.max(p.x), max.y.max(p.y));
+    }
+    if (max.x - min.x).max(max.y - min.y) < min_size {
+        return None;
+    }
+    let chord = (points[points.len() - 1] - points[0]).length();
+    if path_length(&points) < MIN_PATH_RATIO * chord {
+        return None;
+    }
+    let stroke = normalize(&points);
+    let (letter, best) = templates().into_iter()
+                                    .map(|(letter, template)| (letter, score(&stroke, &normalize(&template))))
+                                    .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if best < MIN_SCORE {
+        return None;
+    }
+    let center = (min + max) / 2.0;
+    Some((letter, Point::new(center.x as i32, center.y as i32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(points: &[Vec2], scale: f32, offset: Point) -> Vec<Point> {
+        resample(points).into_iter()
+                        .map(|p| offset + Point::new((p.x * scale) as i32, (p.y * scale) as i32))
+                        .collect()
+    }
+
+    #[test]
+    fn test_recognize_templates() {
+        for (letter, template) in templates() {
+            let points = stroke(&template, 300.0, Point::new(100, 200));
+            assert_eq!(recognize(&points, 50.0).map(|(l, _)| l), Some(letter));
+        }
+    }
+
+    #[test]
+    fn test_recognize_distorted() {
+        // A wider S with a slightly slanted lower half.
+        let mut s = arc(Vec2::new(0.5, 0.25), 0.3, -30.0, -270.0);
+        s.extend(arc(Vec2::new(0.45, 0.8), 0.3, -90.0, 150.0));
+        let points = stroke(&s, 250.0, Point::new(0, 0));
+        assert_eq!(recognize(&points, 50.0).map(|(l, _)| l), Some('S'));
+    }
+
+    #[test]
+    fn test_reject_simple_strokes() {
+        let swipe = stroke(&[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)], 400.0, Point::new(0, 0));
+        assert_eq!(recognize(&swipe, 50.0), None);
+        let arrow = stroke(&[Vec2::new(0.0, 0.0), Vec2::new(0.5, 0.5), Vec2::new(0.0, 1.0)], 400.0, Point::new(0, 0));
+        assert_eq!(recognize(&arrow, 50.0), None);
+        let small = stroke(&arc(Vec2::new(0.5, 0.5), 0.5, -60.0, -300.0), 20.0, Point::new(0, 0));
+        assert_eq!(recognize(&small, 50.0), None);
+    }
+}
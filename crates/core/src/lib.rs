@@ -4,22 +4,59 @@ mod unit;
 mod color;
 pub mod device;
 pub mod framebuffer;
+pub mod tone;
 pub mod frontlight;
 pub mod lightsensor;
 pub mod battery;
+pub mod accelerometer;
 pub mod input;
 pub mod helpers;
+pub mod crypto;
+pub mod qr;
+pub mod sqlite;
 mod dictionary;
+pub mod vocabulary;
+pub mod translation;
+pub mod share;
+pub mod handoff;
+pub mod language;
 pub mod document;
 pub mod library;
+pub mod storage;
+pub mod integrity;
+pub mod sidecars;
+pub mod backup;
+pub mod snapshot;
+pub mod downloads;
+pub mod portal;
 pub mod view;
 pub mod metadata;
 mod symbolic_path;
 pub mod rtc;
+pub mod ntp;
+pub mod timezone;
+pub mod weather;
+pub mod ics;
+pub mod notes;
+pub mod notebook;
+pub mod handwriting;
+pub mod timer;
+pub mod sudoku;
+pub mod planner;
+pub mod speech;
+pub mod ocr;
+pub mod statistics;
+pub mod global_search;
+pub mod session;
+pub mod jobs;
+pub mod metrics;
 pub mod settings;
 pub mod font;
 pub mod context;
+pub mod watcher;
+pub mod remote;
 pub mod gesture;
+pub mod letters;
 
 pub use anyhow;
 pub use fxhash;
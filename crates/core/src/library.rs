@@ -1,25 +1,56 @@
 use std::fs::{self, File};
 use std::str::FromStr;
 use std::time::{SystemTime, Duration};
-use std::path::{PathBuf, Path};
+use std::path::{PathBuf, Path, Component};
 use std::collections::BTreeSet;
 use std::io::{Error as IoError, ErrorKind};
-use walkdir::WalkDir;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use walkdir::{WalkDir, DirEntry};
 use indexmap::IndexMap;
 use fxhash::{FxHashMap, FxHashSet, FxBuildHasher};
 use chrono::{Local, TimeZone};
 use filetime::{FileTime, set_file_mtime, set_file_handle_times};
 use anyhow::{Error, bail, format_err};
+use rand_core::RngCore;
 use crate::metadata::{Info, ReaderInfo, FileInfo, BookQuery, SimpleStatus, SortMethod};
-use crate::metadata::{sort, sorter, extract_metadata_from_document};
-use crate::settings::{LibraryMode, ImportSettings};
+use crate::metadata::{sort, sorter, sort_series, extract_metadata_from_document};
+use crate::settings::{LibraryMode, ImportSettings, DEFAULT_PROFILE_NAME};
 use crate::document::file_kind;
+use crate::statistics::words_per_minute;
+use crate::document::archive::{self, ARCHIVE_EXTENSION};
+use crate::integrity::{IntegrityCheck, IntegrityReport, Problem, checksum};
+use crate::sidecars::SidecarImporter;
+use crate::storage::relocated_caches;
 use crate::helpers::{Fingerprint, Fp, save_json, load_json, IsHidden};
+use crate::handoff::Handoff;
 
 pub const METADATA_FILENAME: &str = ".metadata.json";
 pub const FAT32_EPOCH_FILENAME: &str = ".fat32-epoch";
 pub const READING_STATES_DIRNAME: &str = ".reading-states";
+// The reading states of the profiles other than the default one, in a subdirectory per profile.
+pub const PROFILES_DIRNAME: &str = ".profiles";
 pub const THUMBNAIL_PREVIEWS_DIRNAME: &str = ".thumbnail-previews";
+pub const LAYOUT_CACHES_DIRNAME: &str = ".layout-caches";
+pub const OCR_CACHES_DIRNAME: &str = ".ocr-caches";
+
+// The books with fewer pages are short, those with more than twice as many are long.
+const SHORT_BOOK_PAGES: usize = 200;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BookLength {
+    Short,
+    Long,
+}
+
+impl BookLength {
+    // Only the books whose statistics are known have a length.
+    pub fn matches(self, info: &Info) -> bool {
+        info.statistics.as_ref().is_some_and(|s| match self {
+            BookLength::Short => s.pages < SHORT_BOOK_PAGES,
+            BookLength::Long => s.pages > 2 * SHORT_BOOK_PAGES,
+        })
+    }
+}
 
 pub struct Library {
     pub home: PathBuf,
@@ -28,11 +59,186 @@ pub struct Library {
     pub paths: FxHashMap<PathBuf, Fp>,
     pub reading_states: FxHashMap<Fp, ReaderInfo>,
     pub modified_reading_states: FxHashSet<Fp>,
+    // The reading states directory of the current profile.
+    reading_states_dir: PathBuf,
+    // The directory holding the thumbnails, the layout and the OCR caches.
+    cache_home: PathBuf,
     pub has_db_changed: bool,
     pub fat32_epoch: SystemTime,
     pub sort_method: SortMethod,
     pub reverse_order: bool,
     pub show_hidden: bool,
+    // The documents flagged by the last integrity check.
+    pub problems: FxHashMap<PathBuf, Problem>,
+}
+
+pub struct Importer {
+    home: PathBuf,
+    fat32_epoch: SystemTime,
+    fingerprints: FxHashSet<Fp>,
+    paths: FxHashSet<PathBuf>,
+    settings: ImportSettings,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportEntry {
+    path: PathBuf,
+    size: u64,
+    fp: Fp,
+    // The fingerprints computed with an epoch shifted by ±1s.
+    neighbors: (Fp, Fp),
+    // Only set for the documents unknown to the library.
+    info: Option<Box<Info>>,
+}
+
+impl ImportEntry {
+    pub fn info(&self) -> Option<&Info> {
+        self.info.as_deref()
+    }
+}
+
+impl Importer {
+    // Scans the library's directory and extracts the metadata of the new documents.
+    // The progress is reported as (scanned files, total files).
+    pub fn run<F>(&self, cancel: &AtomicBool, mut progress: F) -> Vec<ImportEntry> where F: FnMut(usize, usize) {
+        let files = WalkDir::new(&self.home).min_depth(1).into_iter()
+                            .filter_entry(|e| !e.is_hidden())
+                            .filter_map(|e| e.ok())
+                            .filter(|e| !e.file_type().is_dir())
+                            .collect::<Vec<DirEntry>>();
+        let total = files.len();
+        let mut entries = Vec::new();
+        let mut sidecars = SidecarImporter::default();
+
+        for (index, entry) in files.iter().enumerate() {
+            if cancel.load(AtomicOrdering::Relaxed) {
+                break;
+            }
+
+            progress(index, total);
+
+            let path = entry.path();
+            let relat = path.strip_prefix(&self.home)
+                            .unwrap_or(path);
+            if self.settings.browse_archives && is_archive(path) {
+                entries.extend(self.archive_entries(path, relat));
+                continue;
+            }
+            let md = if let Ok(md) = entry.metadata() {
+                md
+            } else {
+                continue;
+            };
+            let fp = if let Ok(fp) = md.fingerprint(self.fat32_epoch) {
+                fp
+            } else {
+                continue;
+            };
+            let fp1 = self.fat32_epoch.checked_sub(Duration::from_secs(1))
+                          .and_then(|epoch| md.fingerprint(epoch).ok()).unwrap_or(fp);
+            let fp2 = self.fat32_epoch.checked_add(Duration::from_secs(1))
+                          .and_then(|epoch| md.fingerprint(epoch).ok()).unwrap_or(fp);
+
+            let is_known = [fp, fp1, fp2].iter().any(|fp| self.fingerprints.contains(fp)) ||
+                           self.paths.contains(relat);
+            let info = if is_known {
+                None
+            } else {
+                let kind = file_kind(path).unwrap_or_default();
+                if self.settings.allowed_kinds.contains(&kind) {
+                    let hash = if self.settings.record_checksums {
+                        checksum(path).map_err(|e| eprintln!("Can't compute the checksum of {}: {:#}.", path.display(), e))
+                                      .unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    let file = FileInfo {
+                        path: relat.to_path_buf(),
+                        kind,
+                        size: md.len(),
+                        hash,
+                    };
+                    let mut info = Info {
+                        file,
+                        .. Default::default()
+                    };
+                    if self.settings.metadata_kinds.contains(&info.file.kind) {
+                        extract_metadata_from_document(&self.home, &mut info);
+                    }
+                    if self.settings.import_annotations {
+                        sidecars.import(path, &mut info);
+                    }
+                    Some(Box::new(info))
+                } else {
+                    None
+                }
+            };
+
+            entries.push(ImportEntry {
+                path: relat.to_path_buf(),
+                size: md.len(),
+                fp,
+                neighbors: (fp1, fp2),
+                info,
+            });
+        }
+
+        progress(total, total);
+        entries
+    }
+
+    // The documents stored in a ZIP archive are imported under virtual paths.
+    fn archive_entries(&self, path: &Path, relat: &Path) -> Vec<ImportEntry> {
+        let entries = match archive::entries(path, &self.settings.allowed_kinds) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Can't read {}: {:#}.", path.display(), e);
+                return Vec::new();
+            },
+        };
+
+        entries.into_iter().map(|entry| {
+            let relat = relat.join(&entry.name);
+            let is_known = self.fingerprints.contains(&entry.fp) ||
+                           self.paths.contains(&relat);
+            let info = if is_known {
+                None
+            } else {
+                let hash = if self.settings.record_checksums {
+                    checksum(&path.join(&entry.name)).unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                let file = FileInfo {
+                    path: relat.clone(),
+                    kind: entry.kind,
+                    size: entry.size,
+                    hash,
+                };
+                let mut info = Info {
+                    file,
+                    .. Default::default()
+                };
+                if self.settings.metadata_kinds.contains(&info.file.kind) {
+                    extract_metadata_from_document(&self.home, &mut info);
+                }
+                Some(Box::new(info))
+            };
+            ImportEntry {
+                path: relat,
+                size: entry.size,
+                fp: entry.fp,
+                neighbors: (entry.fp, entry.fp),
+                info,
+            }
+        }).collect()
+    }
+}
+
+fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(ARCHIVE_EXTENSION))
 }
 
 impl Library {
@@ -93,6 +299,16 @@ impl Library {
             fs::create_dir(&path).ok();
         }
 
+        let path = home.as_ref().join(LAYOUT_CACHES_DIRNAME);
+        if !path.exists() {
+            fs::create_dir(&path).ok();
+        }
+
+        let path = home.as_ref().join(OCR_CACHES_DIRNAME);
+        if !path.exists() {
+            fs::create_dir(&path).ok();
+        }
+
         let paths = if mode == LibraryMode::Database {
             db.iter().map(|(fp, info)| (info.file.path.clone(), *fp)).collect()
         } else {
@@ -117,11 +333,14 @@ impl Library {
             paths,
             reading_states,
             modified_reading_states: FxHashSet::default(),
+            reading_states_dir: home.as_ref().join(READING_STATES_DIRNAME),
+            cache_home: home.as_ref().to_path_buf(),
             has_db_changed: false,
             fat32_epoch,
             sort_method,
             reverse_order: sort_method.reverse_order(),
             show_hidden: false,
+            problems: FxHashMap::default(),
         })
     }
 
@@ -196,6 +415,7 @@ impl Library {
                             path: relat.to_path_buf(),
                             kind,
                             size,
+                            hash: String::new(),
                         };
                         let secs = (*fp >> 32) as i64;
                         let nsecs = ((*fp & ((1<<32) - 1)) % 1_000_000_000) as u32;
@@ -219,26 +439,31 @@ impl Library {
     }
 
     pub fn import(&mut self, settings: &ImportSettings) {
+        if let Some(importer) = self.importer(settings) {
+            let entries = importer.run(&AtomicBool::new(false), |_, _| ());
+            self.merge(&entries);
+        }
+    }
+
+    // Returns an importer that can scan the library on another thread.
+    pub fn importer(&self, settings: &ImportSettings) -> Option<Importer> {
         if self.mode == LibraryMode::Filesystem {
-            return;
+            return None;
         }
 
-        for entry in WalkDir::new(&self.home).min_depth(1).into_iter()
-                             .filter_entry(|e| !e.is_hidden()) {
-            if entry.is_err() {
-                continue;
-            }
+        Some(Importer {
+            home: self.home.clone(),
+            fat32_epoch: self.fat32_epoch,
+            fingerprints: self.db.keys().cloned().collect(),
+            paths: self.paths.keys().cloned().collect(),
+            settings: settings.clone(),
+        })
+    }
 
-            let entry = entry.unwrap();
-            if entry.file_type().is_dir() {
-                continue;
-            }
-
-            let path = entry.path();
-            let relat = path.strip_prefix(&self.home)
-                            .unwrap_or(path);
-            let md = entry.metadata().unwrap();
-            let fp = md.fingerprint(self.fat32_epoch).unwrap();
+    pub fn merge(&mut self, entries: &[ImportEntry]) {
+        for entry in entries {
+            let ImportEntry { path: ref relat, size, fp, neighbors: (fp1, fp2), ref info } = *entry;
+            let relat = relat.as_path();
 
             // The fp is know: update the path if it changed.
             if self.db.contains_key(&fp) {
@@ -255,22 +480,15 @@ impl Library {
                 println!("Update fingerprint for {}: {} → {}.", relat.display(), fp2, fp);
                 let info = self.db.remove(&fp2).unwrap();
                 self.db.insert(fp, info);
-                self.db[&fp].file.size = md.len();
+                self.db[&fp].file.size = size;
                 self.paths.insert(relat.to_path_buf(), fp);
-                let rp1 = self.reading_state_path(fp2);
-                let rp2 = self.reading_state_path(fp);
-                fs::rename(rp1, rp2).ok();
+                self.rename_reading_states(fp2, fp);
                 let tpp = self.thumbnail_preview_path(fp2);
                 if tpp.exists() {
                     fs::remove_file(tpp).ok();
                 }
                 self.has_db_changed = true;
             } else {
-                let fp1 = self.fat32_epoch.checked_sub(Duration::from_secs(1))
-                              .and_then(|epoch| md.fingerprint(epoch).ok()).unwrap_or(fp);
-                let fp2 = self.fat32_epoch.checked_add(Duration::from_secs(1))
-                              .and_then(|epoch| md.fingerprint(epoch).ok()).unwrap_or(fp);
-
                 let nfp = if fp1 != fp && self.db.contains_key(&fp1) {
                     Some(fp1)
                 } else if fp2 != fp && self.db.contains_key(&fp2) {
@@ -287,9 +505,7 @@ impl Library {
                     println!("Update fingerprint for {}: {} → {}.", self.db[&nfp].file.path.display(), nfp, fp);
                     let info = self.db.remove(&nfp).unwrap();
                     self.db.insert(fp, info);
-                    let rp1 = self.reading_state_path(nfp);
-                    let rp2 = self.reading_state_path(fp);
-                    fs::rename(rp1, rp2).ok();
+                    self.rename_reading_states(nfp, fp);
                     let tp1 = self.thumbnail_preview_path(nfp);
                     let tp2 = self.thumbnail_preview_path(fp);
                     fs::rename(tp1, tp2).ok();
@@ -301,27 +517,15 @@ impl Library {
                         self.db[&fp].file.path = relat.to_path_buf();
                     }
                 // We found a new file: add it to the db.
-                } else {
-                    let kind = file_kind(&path).unwrap_or_default();
-                    if !settings.allowed_kinds.contains(&kind) {
-                        continue;
-                    }
+                } else if let Some(info) = info {
                     println!("Add new entry: {}, {}.", fp, relat.display());
-                    let size = md.len();
-                    let file = FileInfo {
-                        path: relat.to_path_buf(),
-                        kind,
-                        size,
-                    };
-                    let mut info = Info {
-                        file,
-                        .. Default::default()
-                    };
-                    if settings.metadata_kinds.contains(&info.file.kind) {
-                        extract_metadata_from_document(&self.home, &mut info);
+                    if info.reader.is_some() {
+                        self.modified_reading_states.insert(fp);
                     }
-                    self.db.insert(fp, info);
+                    self.db.insert(fp, Info::clone(info));
                     self.paths.insert(relat.to_path_buf(), fp);
+                } else {
+                    continue;
                 }
 
                 self.has_db_changed = true;
@@ -333,7 +537,7 @@ impl Library {
 
         self.db.retain(|fp, info| {
             let path = home.join(&info.file.path);
-            if path.exists() {
+            if archive::exists(&path) {
                 true
             } else {
                 println!("Remove entry: {}, {}.", fp, info.file.path.display());
@@ -347,10 +551,13 @@ impl Library {
             self.paths.retain(|_, fp| db.contains_key(fp));
             self.modified_reading_states.retain(|fp| db.contains_key(fp));
 
-            let reading_states_dir = home.join(READING_STATES_DIRNAME);
-            let thumbnail_previews_dir = home.join(THUMBNAIL_PREVIEWS_DIRNAME);
-            for entry in fs::read_dir(&reading_states_dir).unwrap()
-                            .chain(fs::read_dir(&thumbnail_previews_dir).unwrap()) {
+            let thumbnail_previews_dir = self.cache_home.join(THUMBNAIL_PREVIEWS_DIRNAME);
+            let layout_caches_dir = self.cache_home.join(LAYOUT_CACHES_DIRNAME);
+            let ocr_caches_dir = self.cache_home.join(OCR_CACHES_DIRNAME);
+            for entry in self.reading_states_dirs().iter().flat_map(fs::read_dir).flatten()
+                            .chain(fs::read_dir(&thumbnail_previews_dir).into_iter().flatten())
+                            .chain(fs::read_dir(&layout_caches_dir).into_iter().flatten())
+                            .chain(fs::read_dir(&ocr_caches_dir).into_iter().flatten()) {
                 if entry.is_err() {
                     continue;
                 }
@@ -366,6 +573,66 @@ impl Library {
         }
     }
 
+    // Returns an integrity check that can run on another thread.
+    pub fn integrity_check(&self) -> Option<IntegrityCheck> {
+        if self.mode == LibraryMode::Filesystem {
+            return None;
+        }
+
+        Some(IntegrityCheck {
+            home: self.home.clone(),
+            files: self.db.values().map(|info| info.file.clone()).collect(),
+        })
+    }
+
+    pub fn apply_integrity_report(&mut self, report: &IntegrityReport) {
+        for (path, hash) in &report.checksums {
+            if let Some(info) = self.paths.get(path).and_then(|fp| self.db.get_mut(fp)) {
+                info.file.hash = hash.clone();
+                self.has_db_changed = true;
+            }
+        }
+        self.problems = report.problems.iter().cloned().collect();
+    }
+
+    // Accepts the current state of a document flagged by the integrity check:
+    // its size, checksum and fingerprint are updated.
+    pub fn reimport<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let full_path = self.home.join(path.as_ref());
+        let fp = self.paths.get(path.as_ref()).cloned()
+                     .ok_or_else(|| format_err!("unknown document {}", path.as_ref().display()))?;
+
+        let (nfp, size) = if let Some((zip_path, name)) = archive::resolve(&full_path) {
+            let entry = archive::entry(&zip_path, &name)?;
+            (entry.fp, entry.size)
+        } else {
+            let md = full_path.metadata()?;
+            (md.fingerprint(self.fat32_epoch)?, md.len())
+        };
+        let hash = checksum(&full_path)?;
+
+        if nfp != fp {
+            println!("Update fingerprint for {}: {} → {}.", path.as_ref().display(), fp, nfp);
+            let info = self.db.remove(&fp).unwrap();
+            self.db.insert(nfp, info);
+            self.paths.insert(path.as_ref().to_path_buf(), nfp);
+            self.rename_reading_states(fp, nfp);
+            let tpp = self.thumbnail_preview_path(fp);
+            if tpp.exists() {
+                fs::remove_file(tpp).ok();
+            }
+        }
+
+        if let Some(info) = self.db.get_mut(&nfp) {
+            info.file.size = size;
+            info.file.hash = hash;
+        }
+
+        self.problems.remove(path.as_ref());
+        self.has_db_changed = true;
+        Ok(())
+    }
+
     pub fn add_document(&mut self, info: Info) {
         if self.mode == LibraryMode::Filesystem {
             return;
@@ -404,6 +671,40 @@ impl Library {
         Ok(())
     }
 
+    // Moves a document into the given directory, relative to the library's path.
+    pub fn move_to_directory<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, path: P, directory: Q) -> Result<PathBuf, Error> {
+        let src = self.home.join(path.as_ref());
+        let file_name = src.file_name()
+                           .ok_or_else(|| format_err!("can't get file name of {}", path.as_ref().display()))?;
+        let dest_dir = self.home.join(directory.as_ref());
+        let dest = dest_dir.join(file_name);
+
+        if dest.exists() {
+            return Err(format_err!("{} already exists", dest.display()));
+        }
+
+        let fp = self.paths.get(path.as_ref()).cloned().or_else(|| {
+           src.metadata().ok()
+              .and_then(|md| md.fingerprint(self.fat32_epoch).ok())
+        }).ok_or_else(|| format_err!("can't get fingerprint of {}", path.as_ref().display()))?;
+        let new_path = dest.strip_prefix(&self.home)?.to_path_buf();
+
+        fs::create_dir_all(&dest_dir)?;
+        fs::rename(&src, &dest)?;
+
+        self.paths.remove(path.as_ref());
+
+        if self.mode == LibraryMode::Database {
+            self.paths.insert(new_path.clone(), fp);
+            if let Some(info) = self.db.get_mut(&fp) {
+                info.file.path = new_path.clone();
+                self.has_db_changed = true;
+            }
+        }
+
+        Ok(new_path)
+    }
+
     pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
         let full_path = self.home.join(path.as_ref());
 
@@ -442,6 +743,7 @@ impl Library {
         }
 
         self.modified_reading_states.remove(&fp);
+        self.problems.remove(path.as_ref());
 
         Ok(())
     }
@@ -629,10 +931,9 @@ impl Library {
         });
         self.modified_reading_states.retain(|fp| fps.contains(fp));
 
-        let reading_states_dir = self.home.join(READING_STATES_DIRNAME);
-        let thumbnail_previews_dir = self.home.join(THUMBNAIL_PREVIEWS_DIRNAME);
-        for entry in fs::read_dir(&reading_states_dir).unwrap()
-                        .chain(fs::read_dir(&thumbnail_previews_dir).unwrap()) {
+        let thumbnail_previews_dir = self.cache_home.join(THUMBNAIL_PREVIEWS_DIRNAME);
+        for entry in self.reading_states_dirs().iter().flat_map(fs::read_dir).flatten()
+                        .chain(fs::read_dir(&thumbnail_previews_dir).into_iter().flatten()) {
             if entry.is_err() {
                 continue;
             }
@@ -676,6 +977,107 @@ impl Library {
         self.has_db_changed = true;
     }
 
+    pub fn update_info<P: AsRef<Path>, F>(&mut self, path: P, f: F) where F: FnOnce(&mut Info) {
+        if self.mode == LibraryMode::Filesystem {
+            return;
+        }
+
+        if let Some(info) = self.paths.get(path.as_ref()).and_then(|fp| self.db.get_mut(fp)) {
+            f(info);
+            self.has_db_changed = true;
+        }
+    }
+
+    // Picks a random unread book among those matching the given query and length.
+    pub fn surprise<R: RngCore>(&self, query: Option<&BookQuery>, length: Option<BookLength>, rng: &mut R) -> Option<&Info> {
+        let candidates = self.db.values()
+                             .filter(|info| info.simple_status() == SimpleStatus::New &&
+                                            query.is_none_or(|q| q.is_match(info)) &&
+                                            length.is_none_or(|l| l.matches(info)))
+                             .collect::<Vec<&Info>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = (rng.next_u64() % candidates.len() as u64) as usize;
+        Some(candidates[index])
+    }
+
+    // Finds the book of the given handoff, or another copy of it, and moves its reading
+    // state to the handed off position.
+    pub fn accept_handoff(&mut self, handoff: &Handoff) -> Option<Info> {
+        let mut info = self.db.get(&handoff.fp).or_else(|| {
+            let identifier = handoff.identifier.as_ref()?;
+            self.db.values().find(|info| info.identifier == *identifier)
+        }).cloned()?;
+        let mut reader = info.reader.take().unwrap_or_default();
+        reader.current_page = handoff.page;
+        reader.finished = false;
+        self.sync_reader_info(&info.file.path, &reader);
+        info.reader = Some(reader);
+        Some(info)
+    }
+
+    // The books opened most recently, except the given one, latest first.
+    pub fn recent(&self, except: &Path, count: usize) -> Vec<&Info> {
+        let mut books = self.db.values()
+                            .filter(|info| info.reader.is_some() && info.file.path != except)
+                            .collect::<Vec<&Info>>();
+        books.sort_by(|a, b| b.reader.as_ref().map(|r| r.opened)
+                              .cmp(&a.reader.as_ref().map(|r| r.opened)));
+        books.truncate(count);
+        books
+    }
+
+    // Returns the first unfinished book that follows the given one in its series.
+    pub fn next_in_series(&self, info: &Info) -> Option<&Info> {
+        if info.series.is_empty() {
+            return None;
+        }
+        let number = info.series_number();
+        self.db.values()
+            .filter(|other| other.series == info.series &&
+                            other.file.path != info.file.path &&
+                            other.simple_status() != SimpleStatus::Finished &&
+                            other.series_number().zip(number).is_some_and(|(a, b)| a > b))
+            .min_by(|a, b| sort_series(a, b))
+    }
+
+    // The given path, relative to the home, unless it leads outside of the library.
+    pub fn relative_path<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        let relat = path.strip_prefix(&self.home).unwrap_or(path);
+        if relat.components().all(|c| matches!(c, Component::Normal(_))) {
+            Some(relat)
+        } else {
+            None
+        }
+    }
+
+    // The document at the given path, absolute or relative to the home.
+    pub fn info_from_path<P: AsRef<Path>>(&self, path: P) -> Option<Info> {
+        let relat = self.relative_path(path.as_ref())?;
+
+        if self.mode == LibraryMode::Database {
+            return self.paths.get(relat).and_then(|fp| self.db.get(fp)).cloned();
+        }
+
+        let path = self.home.join(relat);
+        let kind = file_kind(&path)?;
+        let md = path.metadata().ok()?;
+        let fp = md.fingerprint(self.fat32_epoch).ok()?;
+        let file = FileInfo {
+            path: relat.to_path_buf(),
+            kind,
+            size: md.len(),
+            hash: String::new(),
+        };
+
+        Some(Info {
+            file,
+            reader: self.reading_states.get(&fp).cloned(),
+            .. Default::default()
+        })
+    }
+
     pub fn sync_reader_info<P: AsRef<Path>>(&mut self, path: P, reader: &ReaderInfo) {
         let fp = self.paths.get(path.as_ref()).cloned().unwrap_or_else(|| {
             self.home.join(path.as_ref())
@@ -697,7 +1099,7 @@ impl Library {
 
     pub fn thumbnail_preview<P: AsRef<Path>>(&self, path: P) -> PathBuf {
         if path.as_ref().starts_with(THUMBNAIL_PREVIEWS_DIRNAME) {
-            self.home.join(path.as_ref())
+            self.cache_home.join(path.as_ref())
         } else {
             let fp = self.paths.get(path.as_ref()).cloned().unwrap_or_else(|| {
                 self.home.join(path.as_ref())
@@ -765,13 +1167,70 @@ impl Library {
             }
         }
 
-        let path = self.home.join(READING_STATES_DIRNAME);
-
         self.modified_reading_states.clear();
         if self.mode == LibraryMode::Filesystem {
             self.reading_states.clear();
         }
 
+        self.load_reading_states();
+
+        if self.mode == LibraryMode::Database {
+            self.paths = self.db.iter().map(|(fp, info)| (info.file.path.clone(), *fp)).collect();
+        }
+    }
+
+    // The directory of the thumbnails and of the caches.
+    pub fn cache_home(&self) -> &Path {
+        &self.cache_home
+    }
+
+    // Moves the caches to a subdirectory of the given directory, named after the library,
+    // or back into the library if the directory isn't available.
+    pub fn relocate_caches(&mut self, cache_path: Option<&Path>) {
+        let name = format!("{:016X}", fxhash::hash64(&self.home));
+        self.cache_home = relocated_caches(cache_path, &name).unwrap_or_else(|| self.home.clone());
+        for name in [THUMBNAIL_PREVIEWS_DIRNAME, LAYOUT_CACHES_DIRNAME, OCR_CACHES_DIRNAME] {
+            let path = self.cache_home.join(name);
+            if !path.exists() {
+                fs::create_dir(&path).ok();
+            }
+        }
+    }
+
+    // Switches to the reading states of the given profile.
+    pub fn set_profile(&mut self, name: &str) {
+        let dir = if name == DEFAULT_PROFILE_NAME {
+            self.home.join(READING_STATES_DIRNAME)
+        } else {
+            self.home.join(PROFILES_DIRNAME).join(name)
+        };
+
+        if dir == self.reading_states_dir {
+            return;
+        }
+
+        if let Err(e) = fs::create_dir_all(&dir) {
+            eprintln!("Can't create reading states directory: {:#}.", e);
+            return;
+        }
+
+        self.flush();
+        self.reading_states_dir = dir;
+
+        if self.mode == LibraryMode::Database {
+            for info in self.db.values_mut() {
+                info.reader = None;
+            }
+        } else {
+            self.reading_states.clear();
+        }
+
+        self.load_reading_states();
+    }
+
+    fn load_reading_states(&mut self) {
+        let path = self.reading_states_dir.clone();
+
         for entry in fs::read_dir(&path).unwrap() {
             let entry = entry.unwrap();
             let path = entry.path();
@@ -790,10 +1249,6 @@ impl Library {
                 }
             }
         }
-
-        if self.mode == LibraryMode::Database {
-            self.paths = self.db.iter().map(|(fp, info)| (info.file.path.clone(), *fp)).collect();
-        }
     }
 
     pub fn flush(&mut self) {
@@ -826,14 +1281,58 @@ impl Library {
         }
     }
 
+    // The fingerprint changes whenever the document is modified,
+    // which invalidates its layout cache.
+    // The reading speed measured over all the books, in words per minute.
+    pub fn words_per_minute(&self) -> Option<f32> {
+        words_per_minute(self.db.values().filter_map(|info| info.reader.as_ref())
+                                .chain(self.reading_states.values()))
+    }
+
+    pub fn layout_cache_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let fp = self.home.join(path.as_ref())
+                     .metadata().ok()?
+                     .fingerprint(self.fat32_epoch).ok()?;
+        Some(self.cache_home
+                 .join(LAYOUT_CACHES_DIRNAME)
+                 .join(format!("{}.json", fp)))
+    }
+
+    pub fn ocr_cache_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let fp = self.home.join(path.as_ref())
+                     .metadata().ok()?
+                     .fingerprint(self.fat32_epoch).ok()?;
+        Some(self.cache_home
+                 .join(OCR_CACHES_DIRNAME)
+                 .join(format!("{}.json", fp)))
+    }
+
     fn reading_state_path(&self, fp: Fp) -> PathBuf {
-        self.home
-            .join(READING_STATES_DIRNAME)
+        self.reading_states_dir
             .join(format!("{}.json", fp))
     }
 
+    // The reading states directories of all the profiles.
+    fn reading_states_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.home.join(READING_STATES_DIRNAME)];
+        if let Ok(entries) = fs::read_dir(self.home.join(PROFILES_DIRNAME)) {
+            dirs.extend(entries.filter_map(|entry| entry.ok())
+                               .map(|entry| entry.path())
+                               .filter(|path| path.is_dir()));
+        }
+        dirs
+    }
+
+    // The fingerprint of a document changed: the reading states of every profile follow.
+    fn rename_reading_states(&self, from: Fp, to: Fp) {
+        for dir in self.reading_states_dirs() {
+            fs::rename(dir.join(format!("{}.json", from)),
+                       dir.join(format!("{}.json", to))).ok();
+        }
+    }
+
     fn thumbnail_preview_path(&self, fp: Fp) -> PathBuf {
-        self.home
+        self.cache_home
             .join(THUMBNAIL_PREVIEWS_DIRNAME)
             .join(format!("{}.png", fp))
     }
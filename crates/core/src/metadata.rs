@@ -11,6 +11,7 @@ use serde::{Serialize, Deserialize};
 use lazy_static::lazy_static;
 use titlecase::titlecase;
 use crate::geom::Point;
+use crate::tone::ToneCurve;
 use crate::document::{Document, SimpleTocEntry, TextLocation};
 use crate::document::asciify;
 use crate::document::epub::EpubDocument;
@@ -58,6 +59,10 @@ pub struct Info {
     pub _reader: Option<ReaderInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub toc: Option<Vec<SimpleTocEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<DocumentStatistics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<Cover>,
     #[serde(with = "datetime_format")]
     pub added: DateTime<Local>,
 }
@@ -68,6 +73,9 @@ pub struct FileInfo {
     pub path: PathBuf,
     pub kind: String,
     pub size: u64,
+    // The SHA-256 digest computed during the import.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub hash: String,
 }
 
 impl Default for FileInfo {
@@ -76,6 +84,7 @@ impl Default for FileInfo {
             path: PathBuf::default(),
             kind: String::default(),
             size: u64::default(),
+            hash: String::default(),
         }
     }
 }
@@ -103,6 +112,42 @@ impl Default for Annotation {
     }
 }
 
+// Counted over the whole document.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStatistics {
+    pub words: usize,
+    pub pages: usize,
+    pub images: usize,
+}
+
+// The words read while turning the pages, and the time it took.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingSpeed {
+    pub words: usize,
+    pub seconds: u64,
+}
+
+// The source of the thumbnail preview, instead of the first page.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Cover {
+    Page(usize),
+    // Relative to the library's home.
+    Image(PathBuf),
+}
+
+// A reading session timed by the focus timer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    #[serde(with = "datetime_format")]
+    pub start: DateTime<Local>,
+    pub minutes: u32,
+    pub page_turns: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Margin {
     pub top: f32,
@@ -200,6 +245,10 @@ pub struct ReaderInfo {
     pub pages_count: usize,
     pub finished: bool,
     pub dithered: bool,
+    // Ignore the orientation of the device.
+    pub keep_orientation: bool,
+    // Turn the pages from right to left, and show two pages side by side in landscape.
+    pub manga: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zoom_mode: Option<ZoomMode>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -213,6 +262,8 @@ pub struct ReaderInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub margin_width: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub vertical_margin_width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub screen_margin_width: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub font_family: Option<String>,
@@ -226,12 +277,33 @@ pub struct ReaderInfo {
     pub contrast_exponent: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub contrast_gray: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tone_curve: Option<ToneCurve>,
+    // Overrides the default refresh policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_policy: Option<RefreshPolicy>,
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     pub page_names: BTreeMap<usize, String>,
     #[serde(skip_serializing_if = "BTreeSet::is_empty")]
     pub bookmarks: BTreeSet<usize>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub annotations: Vec<Annotation>,
+    // The sketches drawn over pages, relative to the sketches directory.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub sketches: BTreeMap<usize, PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub review: String,
+    // The languages translated from and to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation_source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation_target: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub focus_sessions: Vec<FocusSession>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reading_speed: Option<ReadingSpeed>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -247,6 +319,36 @@ pub enum ScrollMode {
     Page,
 }
 
+// When the screen is fully refreshed while reading.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefreshPolicy {
+    // Every few page turns, as given by the refresh rate.
+    Interval,
+    // When a new chapter starts.
+    Chapter,
+    Never,
+}
+
+impl RefreshPolicy {
+    pub fn label(self) -> &'static str {
+        match self {
+            RefreshPolicy::Interval => "Every Few Pages",
+            RefreshPolicy::Chapter => "At Chapters",
+            RefreshPolicy::Never => "Never",
+        }
+    }
+
+    // Whether the page shown after the given number of page turns is fully refreshed.
+    pub fn full_refresh(self, rate: u8, page_turns: usize, chapter_started: bool) -> bool {
+        match self {
+            RefreshPolicy::Interval => rate > 0 && page_turns.is_multiple_of(rate as usize),
+            RefreshPolicy::Chapter => chapter_started,
+            RefreshPolicy::Never => false,
+        }
+    }
+}
+
 impl PartialEq for ZoomMode {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -274,12 +376,15 @@ impl Default for ReaderInfo {
             pages_count: 1,
             finished: false,
             dithered: false,
+            keep_orientation: false,
+            manga: false,
             zoom_mode: None,
             scroll_mode: None,
             page_offset: None,
             rotation: None,
             cropping_margins: None,
             margin_width: None,
+            vertical_margin_width: None,
             screen_margin_width: None,
             font_family: None,
             font_size: None,
@@ -287,9 +392,18 @@ impl Default for ReaderInfo {
             line_height: None,
             contrast_exponent: None,
             contrast_gray: None,
+            tone_curve: None,
+            refresh_policy: None,
             page_names: BTreeMap::new(),
             bookmarks: BTreeSet::new(),
             annotations: Vec::new(),
+            sketches: BTreeMap::new(),
+            rating: None,
+            review: String::new(),
+            translation_source: None,
+            translation_target: None,
+            focus_sessions: Vec::new(),
+            reading_speed: None,
         }
     }
 }
@@ -314,6 +428,8 @@ impl Default for Info {
             reader: None,
             _reader: None,
             toc: None,
+            statistics: None,
+            cover: None,
         }
     }
 }
@@ -351,6 +467,11 @@ impl Info {
         }
     }
 
+    // The position of the book within its series: *3*, *03* and *3.5* are understood.
+    pub fn series_number(&self) -> Option<f32> {
+        self.number.trim().parse::<f32>().ok()
+    }
+
     pub fn simple_status(&self) -> SimpleStatus {
         if let Some(ref r) = self.reader {
             if r.finished {
@@ -455,6 +576,229 @@ pub fn make_query(text: &str) -> Option<Regex> {
           .ok()
 }
 
+// A boolean search expression, e.g. `author:le_guin AND tag:sf NOT read`.
+// Terms are implicitly joined with `AND`, `NOT` binds tighter than `AND`,
+// which binds tighter than `OR`. Underscores in field values stand for spaces.
+#[derive(Debug, Clone)]
+pub enum QueryExpr {
+    And(Box<QueryExpr>, Box<QueryExpr>),
+    Or(Box<QueryExpr>, Box<QueryExpr>),
+    Not(Box<QueryExpr>),
+    Field(QueryField, Regex),
+    Status(SimpleStatus),
+    Annotated,
+    Bookmarked,
+    Free(Regex),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum QueryField {
+    Title,
+    Subtitle,
+    Author,
+    Year,
+    Language,
+    Publisher,
+    Series,
+    Edition,
+    Volume,
+    Number,
+    Tag,
+    Kind,
+    Path,
+}
+
+impl QueryField {
+    fn from_name(name: &str) -> Option<QueryField> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(QueryField::Title),
+            "subtitle" => Some(QueryField::Subtitle),
+            "author" => Some(QueryField::Author),
+            "year" => Some(QueryField::Year),
+            "lang" | "language" => Some(QueryField::Language),
+            "publisher" => Some(QueryField::Publisher),
+            "series" => Some(QueryField::Series),
+            "edition" => Some(QueryField::Edition),
+            "volume" => Some(QueryField::Volume),
+            "number" => Some(QueryField::Number),
+            "tag" | "category" => Some(QueryField::Tag),
+            "kind" | "format" => Some(QueryField::Kind),
+            "path" => Some(QueryField::Path),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum QueryToken {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+fn tokenize_query(text: &str) -> Vec<QueryToken> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+
+    let flush = |word: &mut String, tokens: &mut Vec<QueryToken>| {
+        if word.is_empty() {
+            return;
+        }
+        let token = match word.as_str() {
+            "AND" | "&&" => QueryToken::And,
+            "OR" | "||" => QueryToken::Or,
+            "NOT" | "!" => QueryToken::Not,
+            _ => QueryToken::Word(word.clone()),
+        };
+        tokens.push(token);
+        word.clear();
+    };
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => word.push(if c == ' ' { '_' } else { c }),
+            '(' | ')' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(if c == '(' { QueryToken::Open } else { QueryToken::Close });
+            },
+            _ if c.is_whitespace() => flush(&mut word, &mut tokens),
+            _ => word.push(c),
+        }
+    }
+
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+// Returns true if the text should be parsed as a boolean expression rather
+// than with the legacy `'x` prefixes.
+fn is_boolean_query(tokens: &[QueryToken]) -> bool {
+    tokens.iter().any(|token| match token {
+        QueryToken::Word(word) => word.split_once(':')
+                                      .is_some_and(|(name, _)| QueryField::from_name(name).is_some()),
+        _ => true,
+    })
+}
+
+struct QueryParser {
+    tokens: Vec<QueryToken>,
+    index: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&QueryToken> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<QueryToken> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Option<QueryExpr> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&QueryToken::Or) {
+            self.next();
+            let rhs = self.parse_and()?;
+            expr = QueryExpr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Some(expr)
+    }
+
+    fn parse_and(&mut self) -> Option<QueryExpr> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(QueryToken::And) => { self.next(); },
+                Some(QueryToken::Not) | Some(QueryToken::Open) | Some(QueryToken::Word(..)) => (),
+                _ => break,
+            }
+            let rhs = self.parse_unary()?;
+            expr = QueryExpr::And(Box::new(expr), Box::new(rhs));
+        }
+        Some(expr)
+    }
+
+    fn parse_unary(&mut self) -> Option<QueryExpr> {
+        match self.next()? {
+            QueryToken::Not => self.parse_unary().map(|expr| QueryExpr::Not(Box::new(expr))),
+            QueryToken::Open => {
+                let expr = self.parse_or()?;
+                if self.next() != Some(QueryToken::Close) {
+                    return None;
+                }
+                Some(expr)
+            },
+            QueryToken::Word(word) => parse_query_term(&word),
+            _ => None,
+        }
+    }
+}
+
+fn parse_query_term(word: &str) -> Option<QueryExpr> {
+    if let Some((name, value)) = word.split_once(':') {
+        if let Some(field) = QueryField::from_name(name) {
+            return make_query(&value.replace('_', " ")).map(|re| QueryExpr::Field(field, re));
+        }
+    }
+    match word.to_lowercase().as_str() {
+        "new" | "unread" => Some(QueryExpr::Status(SimpleStatus::New)),
+        "reading" => Some(QueryExpr::Status(SimpleStatus::Reading)),
+        "read" | "finished" => Some(QueryExpr::Status(SimpleStatus::Finished)),
+        "annotated" => Some(QueryExpr::Annotated),
+        "bookmarked" => Some(QueryExpr::Bookmarked),
+        _ => make_query(&word.replace('_', " ")).map(QueryExpr::Free),
+    }
+}
+
+impl QueryExpr {
+    pub fn parse(text: &str) -> Option<QueryExpr> {
+        let mut parser = QueryParser { tokens: tokenize_query(text), index: 0 };
+        let expr = parser.parse_or()?;
+        if parser.index < parser.tokens.len() {
+            return None;
+        }
+        Some(expr)
+    }
+
+    pub fn is_match(&self, info: &Info) -> bool {
+        match self {
+            QueryExpr::And(lhs, rhs) => lhs.is_match(info) && rhs.is_match(info),
+            QueryExpr::Or(lhs, rhs) => lhs.is_match(info) || rhs.is_match(info),
+            QueryExpr::Not(expr) => !expr.is_match(info),
+            QueryExpr::Field(field, re) => match field {
+                QueryField::Title => re.is_match(&info.title),
+                QueryField::Subtitle => re.is_match(&info.subtitle),
+                QueryField::Author => re.is_match(&info.author),
+                QueryField::Year => re.is_match(&info.year),
+                QueryField::Language => re.is_match(&info.language),
+                QueryField::Publisher => re.is_match(&info.publisher),
+                QueryField::Series => re.is_match(&info.series),
+                QueryField::Edition => re.is_match(&info.edition),
+                QueryField::Volume => re.is_match(&info.volume),
+                QueryField::Number => re.is_match(&info.number),
+                QueryField::Tag => info.categories.iter().any(|c| re.is_match(c)),
+                QueryField::Kind => re.is_match(&info.file.kind),
+                QueryField::Path => info.file.path.to_str().is_some_and(|s| re.is_match(s)),
+            },
+            QueryExpr::Status(status) => info.simple_status() == *status,
+            QueryExpr::Annotated => info.reader.as_ref().is_some_and(|r| !r.annotations.is_empty()),
+            QueryExpr::Bookmarked => info.reader.as_ref().is_some_and(|r| !r.bookmarks.is_empty()),
+            QueryExpr::Free(re) => re.is_match(&info.title) ||
+                                   re.is_match(&info.subtitle) ||
+                                   re.is_match(&info.author) ||
+                                   re.is_match(&info.series) ||
+                                   info.file.path.to_str().is_some_and(|s| re.is_match(s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct BookQuery {
     pub free: Option<Regex>,
@@ -475,10 +819,15 @@ pub struct BookQuery {
     pub bookmarks: Option<bool>,
     pub opened_after: Option<(bool, DateTime<Local>)>,
     pub added_after: Option<(bool, DateTime<Local>)>,
+    pub expr: Option<QueryExpr>,
 }
 
 impl BookQuery {
     pub fn new(text: &str) -> Option<BookQuery> {
+        if is_boolean_query(&tokenize_query(text)) {
+            return QueryExpr::parse(text).map(|expr| BookQuery { expr: Some(expr), .. Default::default() });
+        }
+
         let mut buf = Vec::new();
         let mut query = BookQuery::default();
         for word in text.rsplit(' ') {
@@ -575,7 +924,8 @@ impl BookQuery {
         self.annotations.as_ref().map(|eq| info.reader.as_ref().map_or(false, |r| !r.annotations.is_empty()) == *eq) != Some(false) &&
         self.bookmarks.as_ref().map(|eq| info.reader.as_ref().map_or(false, |r| !r.bookmarks.is_empty()) == *eq) != Some(false) &&
         self.opened_after.as_ref().map(|(eq, opened)| info.reader.as_ref().map_or(false, |r| r.opened.gt(opened)) == *eq) != Some(false) &&
-        self.added_after.as_ref().map(|(eq, added)| info.added.gt(added) == *eq) != Some(false)
+        self.added_after.as_ref().map(|(eq, added)| info.added.gt(added) == *eq) != Some(false) &&
+        self.expr.as_ref().map(|expr| expr.is_match(info)) != Some(false)
     }
 
 
@@ -736,9 +1086,10 @@ pub fn sort_year(i1: &Info, i2: &Info) -> Ordering {
 
 pub fn sort_series(i1: &Info, i2: &Info) -> Ordering {
     i1.series.cmp(&i2.series).then_with(|| {
-        usize::from_str_radix(&i1.number, 10).ok()
-              .zip(usize::from_str_radix(&i2.number, 10).ok())
-              .map_or_else(|| i1.number.cmp(&i2.number), |(a, b)| a.cmp(&b))
+        i1.series_number()
+          .zip(i2.series_number())
+          .and_then(|(a, b)| a.partial_cmp(&b))
+          .unwrap_or_else(|| i1.number.cmp(&i2.number))
     })
 }
 
@@ -757,6 +1108,7 @@ lazy_static! {
         p.insert("fr", Regex::new(r"^(Les?\s|La\s|L’|Une?\s|Des?\s|Du\s)").unwrap());
         p
     };
+    static ref ISBN_CANDIDATE: Regex = Regex::new(r"(?:97[89][- ]?)?(?:\d[- ]?){9}[\dXx]").unwrap();
 }
 
 #[inline]
@@ -781,6 +1133,9 @@ pub fn extract_metadata_from_document(prefix: &Path, info: &mut Info) {
                     }
                     info.language = doc.language().unwrap_or_default();
                     info.categories.append(&mut doc.categories());
+                    if info.identifier.is_empty() {
+                        info.identifier = doc.isbn().unwrap_or_default();
+                    }
                 },
                 Err(e) => eprintln!("Can't open {}: {:#}.", info.file.path.display(), e),
             }
@@ -870,6 +1225,83 @@ pub fn extract_metadata_from_filename(_prefix: &Path, info: &mut Info) {
     }
 }
 
+// Returns the 13 digits form of the given ISBN, if its checksum is valid.
+pub fn normalize_isbn(text: &str) -> Option<String> {
+    let text = text.trim();
+    let text = text.strip_prefix("urn:isbn:")
+                   .or_else(|| text.strip_prefix("isbn:"))
+                   .unwrap_or(text);
+    let chars: Vec<char> = text.chars()
+                               .filter(|c| *c != '-' && *c != ' ')
+                               .map(|c| c.to_ascii_uppercase())
+                               .collect();
+    match chars.len() {
+        10 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                let digit = match c {
+                    'X' if i == 9 => 10,
+                    _ => c.to_digit(10)?,
+                };
+                sum += (10 - i as u32) * digit;
+            }
+            if sum % 11 != 0 {
+                return None;
+            }
+            let body: String = "978".chars().chain(chars[..9].iter().cloned()).collect();
+            let check = isbn13_check_digit(&body)?;
+            Some(format!("{}{}", body, check))
+        },
+        13 => {
+            let text: String = chars.iter().collect();
+            let check = isbn13_check_digit(&text[..12])?;
+            if text.ends_with(char::from_digit(check, 10)?) && (text.starts_with("978") || text.starts_with("979")) {
+                Some(text)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn isbn13_check_digit(body: &str) -> Option<u32> {
+    let mut sum = 0;
+    for (i, c) in body.chars().enumerate() {
+        sum += c.to_digit(10)? * if i % 2 == 0 { 1 } else { 3 };
+    }
+    Some((10 - sum % 10) % 10)
+}
+
+// Looks for an ISBN in the identifier, then in the file name.
+pub fn find_isbn(info: &Info) -> Option<String> {
+    normalize_isbn(&info.identifier).or_else(|| {
+        let file_name = info.file.path.file_stem().and_then(OsStr::to_str)?;
+        ISBN_CANDIDATE.find_iter(file_name)
+                      .find_map(|m| normalize_isbn(m.as_str()))
+    })
+}
+
+// Fills the empty fields of `info` with the values of `other`.
+pub fn merge_missing(info: &mut Info, other: &Info) {
+    for (field, value) in [(&mut info.title, &other.title),
+                           (&mut info.subtitle, &other.subtitle),
+                           (&mut info.author, &other.author),
+                           (&mut info.year, &other.year),
+                           (&mut info.language, &other.language),
+                           (&mut info.publisher, &other.publisher),
+                           (&mut info.series, &other.series),
+                           (&mut info.number, &other.number),
+                           (&mut info.identifier, &other.identifier)] {
+        if field.is_empty() {
+            *field = value.clone();
+        }
+    }
+    if info.categories.is_empty() {
+        info.categories = other.categories.clone();
+    }
+}
+
 pub fn consolidate(_prefix: &Path, info: &mut Info) {
     if info.subtitle.is_empty() {
         if let Some(index) = info.title.find(':') {
@@ -939,3 +1371,78 @@ pub fn file_name_from_info(info: &Info) -> String {
         .replace('!', "")
         .replace(':', "")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_isbn() {
+        assert_eq!(normalize_isbn("0-306-40615-2").as_deref(), Some("9780306406157"));
+        assert_eq!(normalize_isbn("urn:isbn:978-3-16-148410-0").as_deref(), Some("9783161484100"));
+        assert_eq!(normalize_isbn("978-3-16-148410-1"), None);
+        assert_eq!(normalize_isbn("0-306-40615-3"), None);
+        assert_eq!(normalize_isbn("urn:uuid:1234"), None);
+    }
+
+    #[test]
+    fn test_find_isbn() {
+        let mut info = Info::default();
+        info.file.path = PathBuf::from("Books/Dune 978-0-441-17271-9.epub");
+        assert_eq!(find_isbn(&info).as_deref(), Some("9780441172719"));
+        info.identifier = "0306406152".to_string();
+        assert_eq!(find_isbn(&info).as_deref(), Some("9780306406157"));
+    }
+
+    #[test]
+    fn test_sort_series() {
+        let books: Vec<Info> = [("Dune", "10"), ("Dune", "2"), ("Dune", "2.5"), ("Culture", "3")]
+            .iter().map(|&(series, number)| Info {
+                series: series.to_string(),
+                number: number.to_string(),
+                .. Default::default()
+            }).collect();
+        let mut sorted = books.clone();
+        sorted.sort_by(sort_series);
+        let numbers: Vec<&str> = sorted.iter().map(|info| info.number.as_str()).collect();
+        assert_eq!(numbers, vec!["3", "2", "2.5", "10"]);
+    }
+
+    #[test]
+    fn test_boolean_query() {
+        let info = Info {
+            title: "The Dispossessed".to_string(),
+            author: "Ursula K. Le Guin".to_string(),
+            categories: ["SF".to_string()].into_iter().collect(),
+            .. Default::default()
+        };
+
+        let query = BookQuery::new("author:le_guin AND tag:sf NOT read").unwrap();
+        assert!(query.is_match(&info));
+        let query = BookQuery::new("author:le_guin NOT tag:sf").unwrap();
+        assert!(!query.is_match(&info));
+        let query = BookQuery::new("(author:tolkien OR title:dispossessed) new").unwrap();
+        assert!(query.is_match(&info));
+        let query = BookQuery::new("title:\"the dispossessed\" annotated").unwrap();
+        assert!(!query.is_match(&info));
+        assert!(BookQuery::new("author:le_guin AND").is_none());
+        assert!(BookQuery::new("(tag:sf").is_none());
+    }
+
+    #[test]
+    fn test_refresh_policy() {
+        assert!(RefreshPolicy::Interval.full_refresh(8, 16, false));
+        assert!(!RefreshPolicy::Interval.full_refresh(8, 17, true));
+        assert!(!RefreshPolicy::Interval.full_refresh(0, 16, false));
+        assert!(RefreshPolicy::Chapter.full_refresh(8, 3, true));
+        assert!(!RefreshPolicy::Chapter.full_refresh(8, 16, false));
+        assert!(!RefreshPolicy::Never.full_refresh(8, 16, true));
+    }
+
+    #[test]
+    fn test_legacy_query() {
+        let query = BookQuery::new("le guin 'a").unwrap();
+        assert!(query.expr.is_none());
+        assert!(query.author.is_some());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crate::framebuffer::UpdateMode;
+
+// The number of samples kept for the averages and the maxima.
+const MAX_SAMPLES: usize = 64;
+// The frames submitted during this period give the frame rate.
+const FRAME_RATE_PERIOD: Duration = Duration::from_secs(5);
+
+const UPDATE_MODES: [(UpdateMode, &str); 5] = [(UpdateMode::Gui, "Gui"),
+                                               (UpdateMode::Partial, "Partial"),
+                                               (UpdateMode::Full, "Full"),
+                                               (UpdateMode::Fast, "Fast"),
+                                               (UpdateMode::FastMono, "Mono")];
+
+// Measures the time spent rendering the frames and handling the events,
+// and counts the refreshes of each mode. Nothing is recorded while it's disabled.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    enabled: bool,
+    // When each frame was submitted, and how long it took to render.
+    frames: VecDeque<(Instant, Duration)>,
+    events: VecDeque<Duration>,
+    refreshes: [usize; UPDATE_MODES.len()],
+}
+
+impl Metrics {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // The measures are reset when the metrics are enabled.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            *self = Metrics::default();
+        }
+        self.enabled = enabled;
+    }
+
+    pub fn record_frame(&mut self, start: Instant, duration: Duration) {
+        if self.enabled {
+            push_sample(&mut self.frames, (start, duration));
+        }
+    }
+
+    pub fn record_event(&mut self, duration: Duration) {
+        if self.enabled {
+            push_sample(&mut self.events, duration);
+        }
+    }
+
+    pub fn record_refresh(&mut self, mode: UpdateMode) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(index) = UPDATE_MODES.iter().position(|(m, _)| *m == mode) {
+            self.refreshes[index] += 1;
+        }
+    }
+
+    pub fn frames_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn frame_rate(&self, now: Instant) -> f32 {
+        let count = self.frames.iter()
+                        .filter(|(start, _)| now.saturating_duration_since(*start) <= FRAME_RATE_PERIOD)
+                        .count();
+        count as f32 / FRAME_RATE_PERIOD.as_secs_f32()
+    }
+
+    // The lines shown by the metrics overlay.
+    pub fn summary(&self, now: Instant) -> Vec<String> {
+        let (frame_average, frame_max) = statistics(self.frames.iter().map(|(_, d)| *d));
+        let (event_average, event_max) = statistics(self.events.iter().copied());
+        let refreshes = UPDATE_MODES.iter().zip(self.refreshes.iter())
+                                    .map(|((_, name), count)| format!("{} {}", name, count))
+                                    .collect::<Vec<String>>()
+                                    .join(" · ");
+        vec![format!("Render {} ms · max {} ms · {:.1} fps",
+                     frame_average.as_millis(), frame_max.as_millis(), self.frame_rate(now)),
+             format!("Events {} ms · max {} ms",
+                     event_average.as_millis(), event_max.as_millis()),
+             refreshes]
+    }
+}
+
+fn push_sample<T>(samples: &mut VecDeque<T>, sample: T) {
+    if samples.len() >= MAX_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+// The average and the maximum.
+fn statistics<I>(durations: I) -> (Duration, Duration) where I: Iterator<Item=Duration> {
+    let (mut sum, mut max, mut count) = (Duration::ZERO, Duration::ZERO, 0);
+    for duration in durations {
+        sum += duration;
+        max = max.max(duration);
+        count += 1;
+    }
+    (sum.checked_div(count).unwrap_or_default(), max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut metrics = Metrics::default();
+        let now = Instant::now();
+        metrics.record_frame(now, Duration::from_millis(10));
+        assert_eq!(metrics.frames_count(), 0);
+        metrics.set_enabled(true);
+        for i in 0..MAX_SAMPLES as u64 + 6 {
+            metrics.record_frame(now, Duration::from_millis(10 * (i % 2 + 1)));
+            metrics.record_event(Duration::from_millis(4));
+        }
+        metrics.record_refresh(UpdateMode::Gui);
+        metrics.record_refresh(UpdateMode::Fast);
+        metrics.record_refresh(UpdateMode::Fast);
+        assert_eq!(metrics.frames_count(), MAX_SAMPLES);
+        let summary = metrics.summary(now);
+        assert_eq!(summary[0], "Render 15 ms · max 20 ms · 12.8 fps");
+        assert_eq!(summary[1], "Events 4 ms · max 4 ms");
+        assert_eq!(summary[2], "Gui 1 · Partial 0 · Full 0 · Fast 2 · Mono 0");
+        assert_eq!(metrics.frame_rate(now + Duration::from_secs(6)), 0.0);
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, NaiveDateTime};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use walkdir::WalkDir;
+use anyhow::{Error, bail};
+use crate::crypto::is_encrypted;
+use crate::helpers::{IsHidden, write_atomically};
+
+// The height, in points, of the band above each sketch that holds its date.
+const HEADER_HEIGHT: f32 = 24.0;
+const HEADER_FONT_SIZE: f32 = 10.0;
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// A sketch, decoded as an 8-bit gray image.
+pub struct Page {
+    pub date: NaiveDateTime,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+// The date encoded in the names given by the sketch application,
+// e.g. `sketch-20240603_091500.png` or `20240603_091500.png`.
+fn name_date(name: &str) -> Option<NaiveDateTime> {
+    let stem = name.split('.').next()?;
+    let stem = stem.strip_prefix("sketch-").unwrap_or(stem);
+    NaiveDateTime::parse_from_str(stem, "%Y%m%d_%H%M%S").ok()
+}
+
+// The sketches of the given directory and its subdirectories, from the oldest to the newest.
+// The encrypted sketches are left out.
+pub fn sketches(dir: &Path) -> Vec<(PathBuf, NaiveDateTime)> {
+    let mut sketches: Vec<(PathBuf, NaiveDateTime)> =
+        WalkDir::new(dir).min_depth(1).into_iter()
+                .filter_entry(|entry| !entry.is_hidden())
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file() &&
+                                entry.file_name().to_string_lossy().to_lowercase().ends_with(".png"))
+                .map(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let date = name_date(&name).unwrap_or_else(|| {
+                        entry.metadata().ok()
+                             .and_then(|md| md.modified().ok())
+                             .map(|time| DateTime::<Local>::from(time).naive_local())
+                             .unwrap_or_default()
+                    });
+                    (entry.into_path(), date)
+                }).collect();
+    sketches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    sketches
+}
+
+fn has_encrypted_sketches(dir: &Path) -> bool {
+    WalkDir::new(dir).min_depth(1).into_iter()
+            .filter_entry(|entry| !entry.is_hidden())
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_type().is_file() &&
+                         entry.file_name().to_string_lossy().to_lowercase().ends_with(".png.enc"))
+}
+
+fn load_page(path: &Path, date: NaiveDateTime) -> Result<Page, Error> {
+    let buf = fs::read(path)?;
+    if is_encrypted(&buf) {
+        bail!("the sketch is encrypted");
+    }
+    let mut decoder = png::Decoder::new(buf.as_slice());
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels)?;
+    let samples = info.color_type.samples();
+    let data = pixels[..info.buffer_size()].chunks(samples).map(|p| {
+        match info.color_type {
+            png::ColorType::Rgb | png::ColorType::Rgba => {
+                (0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32).round() as u8
+            },
+            _ => p[0],
+        }
+    }).collect();
+    Ok(Page { date, width: info.width, height: info.height, data })
+}
+
+fn escape(text: &str) -> String {
+    text.chars().filter(char::is_ascii).fold(String::new(), |mut s, c| {
+        if matches!(c, '(' | ')' | '\\') {
+            s.push('\\');
+        }
+        s.push(c);
+        s
+    })
+}
+
+// A text string of the document information, encoded in UTF-16.
+fn text_string(text: &str) -> String {
+    text.encode_utf16().fold("<FEFF".to_string(), |s, u| s + &format!("{:04X}", u)) + ">"
+}
+
+struct PdfWriter {
+    buf: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> PdfWriter {
+        PdfWriter { buf: b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(), offsets: Vec::new() }
+    }
+
+    // The objects are numbered from one, in the order of their addition.
+    fn add(&mut self, dict: &str, stream: Option<&[u8]>) {
+        self.offsets.push(self.buf.len());
+        let num = self.offsets.len();
+        match stream {
+            Some(data) => {
+                write!(self.buf, "{} 0 obj\n<< {} /Length {} >>\nstream\n", num, dict, data.len()).ok();
+                self.buf.extend_from_slice(data);
+                self.buf.extend_from_slice(b"\nendstream\nendobj\n");
+            },
+            None => {
+                write!(self.buf, "{} 0 obj\n{}\nendobj\n", num, dict).ok();
+            },
+        }
+    }
+
+    fn finish(mut self, root: usize, info: usize) -> Vec<u8> {
+        let start = self.buf.len();
+        write!(self.buf, "xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1).ok();
+        for offset in &self.offsets {
+            writeln!(self.buf, "{:010} 00000 n ", offset).ok();
+        }
+        write!(self.buf, "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+               self.offsets.len() + 1, root, info, start).ok();
+        self.buf
+    }
+}
+
+// Lays out a PDF document with one page per sketch, its date above it.
+// The size of the pages follows the size of the sketches at the given resolution.
+pub fn pdf(pages: &[Page], title: &str, dpi: u16) -> Result<Vec<u8>, Error> {
+    let mut writer = PdfWriter::new();
+    let scale = 72.0 / dpi.max(1) as f32;
+    // The catalog, the page tree and the font come first.
+    let kids = (0..pages.len()).map(|i| format!("{} 0 R", 4 + 3 * i)).collect::<Vec<String>>();
+    writer.add("<< /Type /Catalog /Pages 2 0 R >>", None);
+    writer.add(&format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()), None);
+    writer.add("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>", None);
+
+    for (i, page) in pages.iter().enumerate() {
+        let num = 4 + 3 * i;
+        let width = page.width as f32 * scale;
+        let height = page.height as f32 * scale;
+        let header = escape(&page.date.format(DATE_FORMAT).to_string());
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q\nBT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                              width, height, HEADER_FONT_SIZE, HEADER_FONT_SIZE,
+                              height + (HEADER_HEIGHT - HEADER_FONT_SIZE) / 2.0 + 2.0, header);
+        writer.add(&format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                              /Resources << /Font << /F1 3 0 R >> /XObject << /Im0 {} 0 R >> >> \
+                              /Contents {} 0 R >>",
+                            width, height + HEADER_HEIGHT, num + 2, num + 1), None);
+        writer.add("", Some(content.as_bytes()));
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&page.data)?;
+        writer.add(&format!("/Type /XObject /Subtype /Image /Width {} /Height {} \
+                             /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode",
+                            page.width, page.height), Some(&encoder.finish()?));
+    }
+
+    let created = Local::now().format("D:%Y%m%d%H%M%S");
+    writer.add(&format!("<< /Title {} /Producer (Plato) /CreationDate ({}) >>", text_string(title), created), None);
+    let info = writer.offsets.len();
+    Ok(writer.finish(1, info))
+}
+
+// Bundles the sketches of a directory into a PDF document saved next to it, e.g. `Sketches.pdf`.
+// Returns the path of the document and the number of its pages.
+// The document would be readable by anyone: the directories holding encrypted sketches are refused.
+pub fn bundle(dir: &Path, dpi: u16) -> Result<(PathBuf, usize), Error> {
+    if has_encrypted_sketches(dir) {
+        bail!("{} holds encrypted sketches", dir.display());
+    }
+    let mut pages = Vec::new();
+    for (path, date) in sketches(dir) {
+        match load_page(&path, date) {
+            Ok(page) => pages.push(page),
+            Err(e) => eprintln!("Can't add sketch {}: {:#}.", path.display(), e),
+        }
+    }
+    if pages.is_empty() {
+        bail!("no sketches in {}", dir.display());
+    }
+    let title = dir.file_name().map(|name| name.to_string_lossy().into_owned())
+                   .unwrap_or_default();
+    let data = pdf(&pages, &title, dpi)?;
+    let path = dir.with_extension("pdf");
+    write_atomically(&path, &data)?;
+    Ok((path, pages.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_date() {
+        let date = NaiveDateTime::parse_from_str("2024-06-03 09:15:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(name_date("sketch-20240603_091500.png"), Some(date));
+        assert_eq!(name_date("20240603_091500.png.enc"), Some(date));
+        assert_eq!(name_date("Groceries.png"), None);
+    }
+
+    #[test]
+    fn test_cross_references() {
+        let date = NaiveDateTime::parse_from_str("2024-06-03 09:15:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let pages = vec![Page { date, width: 4, height: 2, data: vec![255; 8] },
+                         Page { date, width: 2, height: 2, data: vec![0; 4] }];
+        let data = pdf(&pages, "Notes (été)", 300).unwrap();
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("(2024-06-03 09:15) Tj"));
+        // Each entry of the cross-reference table points at its object.
+        // The images are binary: the offsets are only valid in the raw bytes.
+        let start = data.windows(10).rposition(|w| w == b"startxref\n").unwrap() + 10;
+        let tail = std::str::from_utf8(&data[start..]).unwrap();
+        let xref = tail.lines().next().unwrap().parse::<usize>().unwrap();
+        assert!(data[xref..].starts_with(b"xref"));
+        let table = std::str::from_utf8(&data[xref..]).unwrap();
+        let entries = table.lines().skip(3).take_while(|line| line.ends_with(" n "));
+        for (index, entry) in entries.enumerate() {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(data[offset..].starts_with(format!("{} 0 obj", index + 1).as_bytes()));
+        }
+        assert_eq!(escape("(a\\b)"), "\\(a\\\\b\\)");
+    }
+}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Local};
+use anyhow::{Error, format_err};
+
+pub const TEXT_EXTENSION: &str = "md";
+pub const FILENAME_PATTERN: &str = "%Y%m%d_%H%M%S";
+const EXCERPT_LENGTH: usize = 80;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NoteKind {
+    Text,
+    // Drawn with the sketch application, and saved as PNG.
+    Handwritten,
+}
+
+impl NoteKind {
+    pub fn from_name(name: &str) -> Option<NoteKind> {
+        let name = name.to_lowercase();
+        if name.ends_with(".md") {
+            Some(NoteKind::Text)
+        } else if name.ends_with(".png") || name.ends_with(".png.enc") {
+            Some(NoteKind::Handwritten)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Note {
+    // The file name, relative to the notes directory.
+    pub name: String,
+    pub kind: NoteKind,
+    pub title: String,
+    pub excerpt: String,
+    pub modified: DateTime<Local>,
+    pub pinned: bool,
+    text: String,
+}
+
+impl Note {
+    pub fn new(name: &str, text: &str, modified: DateTime<Local>, pinned: bool) -> Option<Note> {
+        let kind = NoteKind::from_name(name)?;
+        let (title, excerpt) = match kind {
+            NoteKind::Text => title_and_excerpt(text),
+            NoteKind::Handwritten => (String::new(), String::new()),
+        };
+        let title = if title.is_empty() { stem(name).to_string() } else { title };
+        Some(Note {
+            name: name.to_string(),
+            kind,
+            title,
+            excerpt,
+            modified,
+            pinned,
+            text: text.to_string(),
+        })
+    }
+
+    // All the words of the query must appear in the title or the text.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!("{}\n{}", self.title, self.text).to_lowercase();
+        query.to_lowercase().split_whitespace().all(|word| haystack.contains(word))
+    }
+}
+
+fn stem(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+// The first non-empty line, without its heading and task marks, and the beginning of the next one.
+pub fn title_and_excerpt(text: &str) -> (String, String) {
+    let mut lines = text.lines().map(|line| match parse_line(line) {
+        LineKind::Heading(_, text) | LineKind::Task(_, text) | LineKind::Text(text) => text.trim(),
+    }).filter(|line| !line.is_empty());
+    let title = lines.next().unwrap_or_default().to_string();
+    let excerpt = lines.next().unwrap_or_default();
+    let excerpt = match excerpt.char_indices().nth(EXCERPT_LENGTH) {
+        Some((index, _)) => format!("{}…", &excerpt[..index]),
+        None => excerpt.to_string(),
+    };
+    (title, excerpt)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LineKind<'a> {
+    // The level and the text.
+    Heading(usize, &'a str),
+    // Whether the task is done, and the text.
+    Task(bool, &'a str),
+    Text(&'a str),
+}
+
+pub fn parse_line(line: &str) -> LineKind<'_> {
+    let trimmed = line.trim_start();
+    for (prefix, done) in [("- [ ] ", false), ("- [x] ", true), ("- [X] ", true)] {
+        if let Some(text) = trimmed.strip_prefix(prefix) {
+            return LineKind::Task(done, text);
+        }
+    }
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level > 0 && trimmed[level..].starts_with(' ') {
+        return LineKind::Heading(level, trimmed[level..].trim_start());
+    }
+    LineKind::Text(line)
+}
+
+pub fn toggle_task(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    match parse_line(line) {
+        LineKind::Task(done, text) => format!("{}- [{}] {}", indent, if done { ' ' } else { 'x' }, text),
+        _ => line.to_string(),
+    }
+}
+
+// Lists the notes of the given directory: the pinned ones first, then the most recent ones.
+pub fn list(dir: &Path, pinned: &[String]) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return notes,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let kind = match NoteKind::from_name(&name) {
+            Some(kind) if !name.starts_with('.') => kind,
+            _ => continue,
+        };
+        let modified = entry.metadata().and_then(|m| m.modified())
+                            .map(DateTime::<Local>::from)
+                            .unwrap_or_else(|_| Local::now());
+        let text = if kind == NoteKind::Text {
+            fs::read_to_string(entry.path()).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        if let Some(note) = Note::new(&name, &text, modified, pinned.contains(&name)) {
+            notes.push(note);
+        }
+    }
+    sort(&mut notes);
+    notes
+}
+
+pub fn sort(notes: &mut [Note]) {
+    notes.sort_by(|a, b| b.pinned.cmp(&a.pinned).then_with(|| b.modified.cmp(&a.modified)));
+}
+
+pub fn new_text_name() -> String {
+    format!("{}.{}", Local::now().format(FILENAME_PATTERN), TEXT_EXTENSION)
+}
+
+// The file name given to a note named after its title.
+pub fn rename(dir: &Path, name: &str, title: &str) -> Result<String, Error> {
+    let title = title.trim().replace(['/', '\\'], "-");
+    if title.is_empty() {
+        return Err(format_err!("empty name"));
+    }
+    let extension = name.split_once('.').map_or("", |(_, extension)| extension);
+    let new_name = format!("{}.{}", title, extension);
+    let path = dir.join(&new_name);
+    if path.exists() {
+        return Err(format_err!("{} already exists", new_name));
+    }
+    fs::rename(dir.join(name), path)?;
+    Ok(new_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_title_and_excerpt() {
+        let (title, excerpt) = title_and_excerpt("\n# Groceries\n- [ ] Milk\n- [x] Bread\n");
+        assert_eq!(title, "Groceries");
+        assert_eq!(excerpt, "Milk");
+        assert_eq!(title_and_excerpt("").0, "");
+    }
+
+    #[test]
+    fn test_tasks() {
+        assert_eq!(parse_line("  - [x] Bread"), LineKind::Task(true, "Bread"));
+        assert_eq!(parse_line("## Today"), LineKind::Heading(2, "Today"));
+        assert_eq!(parse_line("#hashtag"), LineKind::Text("#hashtag"));
+        assert_eq!(toggle_task("  - [ ] Milk"), "  - [x] Milk");
+        assert_eq!(toggle_task("- [x] Milk"), "- [ ] Milk");
+        assert_eq!(toggle_task("Milk"), "Milk");
+    }
+
+    #[test]
+    fn test_sort_and_search() {
+        let time = |h| Local.with_ymd_and_hms(2024, 6, 3, h, 0, 0).unwrap();
+        let mut notes = vec![Note::new("a.md", "# Ideas\nA reading list", time(9), false).unwrap(),
+                             Note::new("b.png", "", time(10), false).unwrap(),
+                             Note::new("c.md", "Groceries", time(8), true).unwrap()];
+        sort(&mut notes);
+        assert_eq!(notes.iter().map(|n| n.name.as_str()).collect::<Vec<&str>>(), vec!["c.md", "b.png", "a.md"]);
+        assert_eq!(notes[1].title, "b");
+        assert!(notes[2].matches("READING ideas"));
+        assert!(!notes[2].matches("groceries"));
+        assert!(Note::new("d.txt", "", time(8), false).is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use std::io;
+use std::net::UdpSocket;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{Error, bail, format_err};
+use crate::view::{Event, Hub};
+use crate::jobs::{Jobs, RetryPolicy};
+
+const NTP_PORT: u16 = 123;
+const PACKET_SIZE: usize = 48;
+// The number of seconds between 1900-01-01 and 1970-01-01.
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Leap indicator 0, version 4, client mode.
+pub fn request_packet(transmit: f64) -> [u8; PACKET_SIZE] {
+    let mut packet = [0; PACKET_SIZE];
+    packet[0] = 0x23;
+    packet[40..48].copy_from_slice(&to_timestamp(transmit));
+    packet
+}
+
+fn to_timestamp(time: f64) -> [u8; 8] {
+    let time = time + NTP_UNIX_OFFSET;
+    let seconds = time.trunc() as u32;
+    let fraction = (time.fract() * 4_294_967_296.0) as u32;
+    let mut bytes = [0; 8];
+    bytes[..4].copy_from_slice(&seconds.to_be_bytes());
+    bytes[4..].copy_from_slice(&fraction.to_be_bytes());
+    bytes
+}
+
+fn from_timestamp(bytes: &[u8]) -> f64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as f64;
+    seconds + fraction / 4_294_967_296.0 - NTP_UNIX_OFFSET
+}
+
+// Returns the offset, in seconds, of the local clock, given the times at which the request was
+// sent and the response received.
+pub fn parse_response(packet: &[u8], sent: f64, received: f64) -> Result<f64, Error> {
+    if packet.len() < PACKET_SIZE {
+        bail!("truncated response");
+    }
+    if packet[0] & 0x07 != 4 {
+        bail!("unexpected mode");
+    }
+    if packet[1] == 0 {
+        bail!("the server refused to answer");
+    }
+    let receive = from_timestamp(&packet[32..40]);
+    let transmit = from_timestamp(&packet[40..48]);
+    Ok(((receive - sent) + (transmit - received)) / 2.0)
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+                     .map(|d| d.as_secs_f64())
+                     .unwrap_or_default()
+}
+
+pub fn query(server: &str) -> Result<f64, Error> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    socket.connect((server, NTP_PORT))?;
+    let sent = unix_now();
+    socket.send(&request_packet(sent))?;
+    let mut buf = [0; 2 * PACKET_SIZE];
+    let n = socket.recv(&mut buf)?;
+    let received = unix_now();
+    parse_response(&buf[..n], sent, received)
+}
+
+// Shifts the system clock by the given number of seconds.
+pub fn adjust_clock(offset: f64) -> Result<(), Error> {
+    let time = unix_now() + offset;
+    let tv = libc::timeval {
+        tv_sec: time.trunc() as libc::time_t,
+        tv_usec: (time.fract() * 1e6) as libc::suseconds_t,
+    };
+    if unsafe { libc::settimeofday(&tv, std::ptr::null()) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+pub fn describe_offset(offset: i64) -> String {
+    let seconds = (offset.abs() + 500) / 1000;
+    let direction = if offset < 0 { "back" } else { "forward" };
+    if seconds < 60 {
+        format!("{} s {}", seconds, direction)
+    } else if seconds < 3600 {
+        format!("{} min {}", (seconds + 30) / 60, direction)
+    } else {
+        format!("{} h {} min {}", seconds / 3600, (seconds % 3600) / 60, direction)
+    }
+}
+
+// Asks the servers, in order, for the time, and sets the clock according to the first answer.
+pub fn sync(servers: &[String], notify: bool, jobs: &Jobs, hub: &Hub) {
+    let servers = servers.to_vec();
+    let hub2 = hub.clone();
+    jobs.spawn("Synchronize the clock", RetryPolicy::NETWORK, hub, move |attempt| {
+        let mut last_error = format_err!("no servers");
+        for server in &servers {
+            match query(server).and_then(|offset| adjust_clock(offset).map(|_| offset)) {
+                Ok(offset) => {
+                    hub2.send(Event::ClockSynced {
+                        offset: (1000.0 * offset) as i64,
+                        notify,
+                    }).ok();
+                    return Ok(());
+                },
+                Err(e) => {
+                    eprintln!("Can't synchronize the clock with {}: {:#}.", server, e);
+                    last_error = e;
+                },
+            }
+        }
+        if notify && attempt.last {
+            hub2.send(Event::Notify(format!("Can't synchronize the clock: {:#}.", last_error))).ok();
+        }
+        Err(last_error)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps() {
+        let time = 1_700_000_000.25;
+        assert!((from_timestamp(&to_timestamp(time)) - time).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let mut packet = request_packet(0.0);
+        packet[0] = 0x24;
+        packet[1] = 2;
+        // The server's clock is ten seconds ahead, and each way takes one second.
+        packet[32..40].copy_from_slice(&to_timestamp(1011.0));
+        packet[40..48].copy_from_slice(&to_timestamp(1011.5));
+        let offset = parse_response(&packet, 1000.0, 1002.5).unwrap();
+        assert!((offset - 10.0).abs() < 1e-3);
+        packet[1] = 0;
+        assert!(parse_response(&packet, 1000.0, 1002.5).is_err());
+        assert!(parse_response(&packet[..40], 1000.0, 1002.5).is_err());
+    }
+
+    #[test]
+    fn test_describe_offset() {
+        assert_eq!(describe_offset(-4_200), "4 s back");
+        assert_eq!(describe_offset(185_000), "3 min forward");
+        assert_eq!(describe_offset(3_780_000), "1 h 3 min forward");
+    }
+}
@@ -0,0 +1,141 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use anyhow::{Error, format_err};
+use crate::document::{Document, Location, BoundedText, TextLocation};
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Boundary, Vec2};
+use crate::helpers::{load_json, save_json};
+use crate::settings::OcrSettings;
+
+// The words with a lower confidence are dropped.
+const MIN_CONFIDENCE: f32 = 10.0;
+// The level of the words in tesseract's TSV output.
+const WORD_LEVEL: &str = "5";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OcrWord {
+    text: String,
+    // In page coordinates: left, top, right and bottom.
+    rect: [f32; 4],
+}
+
+// The words recognized on the pages of a document, by page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OcrCache {
+    pages: BTreeMap<usize, Vec<OcrWord>>,
+}
+
+impl OcrCache {
+    pub fn load(path: &Path) -> OcrCache {
+        if !path.exists() {
+            return OcrCache::default();
+        }
+        load_json(path).map_err(|e| eprintln!("Can't load the OCR cache: {:#}.", e))
+                       .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        save_json(self, path)
+    }
+
+    pub fn contains(&self, page: usize) -> bool {
+        self.pages.contains_key(&page)
+    }
+
+    pub fn words(&self, page: usize) -> Option<Vec<BoundedText>> {
+        self.pages.get(&page).map(|words| {
+            words.iter().enumerate().map(|(index, word)| BoundedText {
+                text: word.text.clone(),
+                rect: Boundary::new(Vec2::new(word.rect[0], word.rect[1]),
+                                    Vec2::new(word.rect[2], word.rect[3])),
+                location: TextLocation::Static(page, index),
+            }).collect()
+        })
+    }
+
+    pub fn insert(&mut self, page: usize, words: &[BoundedText]) {
+        self.pages.insert(page, words.iter().map(|word| OcrWord {
+            text: word.text.clone(),
+            rect: [word.rect.min.x, word.rect.min.y, word.rect.max.x, word.rect.max.y],
+        }).collect());
+    }
+}
+
+// Parses the TSV output of tesseract for an image rendered at the given scale.
+pub fn parse_tsv(tsv: &str, scale: f32, page: usize) -> Vec<BoundedText> {
+    let mut words = Vec::new();
+    for line in tsv.lines().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 12 || fields[0] != WORD_LEVEL {
+            continue;
+        }
+        let text = fields[11].trim();
+        let confidence = fields[10].parse::<f32>().unwrap_or(-1.0);
+        if text.is_empty() || confidence < MIN_CONFIDENCE {
+            continue;
+        }
+        let values: Vec<f32> = fields[6..10].iter().filter_map(|v| v.parse().ok()).collect();
+        if values.len() != 4 {
+            continue;
+        }
+        let (left, top, width, height) = (values[0], values[1], values[2], values[3]);
+        words.push(BoundedText {
+            text: text.to_string(),
+            rect: Boundary::new(Vec2::new(left / scale, top / scale),
+                                Vec2::new((left + width) / scale, (top + height) / scale)),
+            location: TextLocation::Static(page, words.len()),
+        });
+    }
+    words
+}
+
+// Renders the page and runs the OCR program on its image.
+pub fn recognize(doc: &mut dyn Document, page: usize, settings: &OcrSettings) -> Result<Vec<BoundedText>, Error> {
+    let scale = settings.dpi.max(72) as f32 / 72.0;
+    let (pixmap, _) = doc.pixmap(Location::Exact(page), scale)
+                         .ok_or_else(|| format_err!("can't render page {}", page))?;
+    let path = env::temp_dir().join(format!("plato-ocr-{}.png", page));
+    pixmap.save(&path.to_string_lossy())?;
+    let output = Command::new(&settings.program)
+                         .arg(&path)
+                         .arg("stdout")
+                         .arg("-l")
+                         .arg(&settings.languages)
+                         .arg("tsv")
+                         .output();
+    fs::remove_file(&path).ok();
+    let output = output.map_err(|e| format_err!("can't run {}: {}", settings.program.display(), e))?;
+    if !output.status.success() {
+        return Err(format_err!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout), scale, page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t2480\t3508\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t300\t600\t150\t60\t96.5\tHello\n\
+                   5\t1\t1\t1\t1\t2\t480\t600\t210\t60\t3.2\t~\n\
+                   5\t1\t1\t1\t1\t3\t720\t603\t240\t63\t91.0\tworld.\n";
+        let words = parse_tsv(tsv, 3.0, 7);
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!(words[1].location, TextLocation::Static(7, 1));
+        assert_eq!((words[1].rect.min.x, words[1].rect.min.y), (240.0, 201.0));
+        assert_eq!((words[1].rect.max.x, words[1].rect.max.y), (320.0, 222.0));
+
+        let mut cache = OcrCache::default();
+        cache.insert(7, &words);
+        assert!(cache.contains(7) && !cache.contains(8));
+        assert_eq!(cache.words(7).map(|words| words[1].text.clone()), Some("world.".to_string()));
+    }
+}
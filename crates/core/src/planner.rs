@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::Path;
+use std::fmt::Write;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Timelike};
+use anyhow::Error;
+use crate::ics::{load_calendars, occurrences};
+use crate::settings::PlannerSettings;
+use crate::weather::{Forecast, condition};
+
+// The number of task boxes of the right column.
+const TASKS_COUNT: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct PlannerEvent {
+    // `None` for the events that last all day.
+    pub start: Option<NaiveTime>,
+    pub summary: String,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Draws a daily planner: the date and the weather above a schedule grid, next to a task list.
+pub fn render(date: NaiveDate, dims: (u32, u32), settings: &PlannerSettings, weather: Option<&str>, events: &[PlannerEvent]) -> String {
+    let (width, height) = (dims.0 as f32, dims.1 as f32);
+    let margin = width / 20.0;
+    let header = height / 10.0;
+    let large = height / 28.0;
+    let small = height / 60.0;
+    let stroke = (width / 800.0).max(1.0);
+    let mut svg = String::new();
+
+    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif">"#,
+             w = width, h = height).ok();
+    writeln!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, width, height).ok();
+    writeln!(svg, r#"<text x="{}" y="{}" font-size="{}" font-weight="bold">{}</text>"#,
+             margin, margin + large, large, date.format("%A %-d %B")).ok();
+    writeln!(svg, r##"<text x="{}" y="{}" font-size="{}" fill="#666">{}</text>"##,
+             margin, margin + large + 1.8 * small, small, date.format("Week %V, %Y")).ok();
+    if let Some(weather) = weather.filter(|_| settings.weather) {
+        writeln!(svg, r#"<text x="{}" y="{}" font-size="{}" text-anchor="end">{}</text>"#,
+                 width - margin, margin + large, small, escape(weather)).ok();
+    }
+
+    let top = margin + header;
+    let bottom = height - margin;
+    let split = margin + 0.6 * (width - 2.0 * margin);
+    writeln!(svg, r#"<line x1="{x1}" y1="{y}" x2="{x2}" y2="{y}" stroke="black" stroke-width="{s}"/>"#,
+             x1 = margin, x2 = width - margin, y = top - small, s = 2.0 * stroke).ok();
+
+    // The schedule: one row per hour, split in halves.
+    let (start_hour, end_hour) = (settings.start_hour.min(23), settings.end_hour.clamp(settings.start_hour.min(23) + 1, 24));
+    let all_day: Vec<&PlannerEvent> = events.iter().filter(|event| event.start.is_none()).collect();
+    let schedule_top = top + all_day.len() as f32 * 1.5 * small;
+    for (index, event) in all_day.iter().enumerate() {
+        writeln!(svg, r##"<text x="{}" y="{}" font-size="{}" fill="#444">{}</text>"##,
+                 margin, top + (index as f32 + 1.0) * 1.5 * small - 0.5 * small, small, escape(&event.summary)).ok();
+    }
+    let rows = end_hour - start_hour;
+    let row = (bottom - schedule_top) / rows as f32;
+    let label_width = 3.5 * small;
+    for index in 0..=rows {
+        let y = schedule_top + index as f32 * row;
+        writeln!(svg, r#"<line x1="{x1}" y1="{y}" x2="{x2}" y2="{y}" stroke="black" stroke-width="{s}"/>"#,
+                 x1 = margin, x2 = split - small, y = y, s = stroke).ok();
+        if index < rows {
+            writeln!(svg, r##"<text x="{}" y="{}" font-size="{}" fill="#666">{:02}:00</text>"##,
+                     margin, y + 1.2 * small, small, start_hour + index).ok();
+            writeln!(svg, r##"<line x1="{x1}" y1="{y}" x2="{x2}" y2="{y}" stroke="#999" stroke-width="{s}" stroke-dasharray="{d} {d}"/>"##,
+                     x1 = margin + label_width, x2 = split - small, y = y + row / 2.0, s = stroke, d = 2.0 * stroke).ok();
+        }
+    }
+    for event in events {
+        let start = match event.start {
+            Some(start) => start,
+            None => continue,
+        };
+        let hour = start.hour().clamp(start_hour, end_hour - 1);
+        let offset = if start.hour() < start_hour { 0.0 } else { start.minute() as f32 / 60.0 };
+        let y = schedule_top + ((hour - start_hour) as f32 + offset) * row + 1.2 * small;
+        writeln!(svg, r##"<text x="{}" y="{}" font-size="{}" fill="#444">{} {}</text>"##,
+                 margin + label_width, y, small, start.format("%H:%M"), escape(&event.summary)).ok();
+    }
+
+    // The task list.
+    let left = split + small;
+    writeln!(svg, r#"<text x="{}" y="{}" font-size="{}" font-weight="bold">Tasks</text>"#, left, top + small, small).ok();
+    let task_row = (bottom - top - 2.0 * small) / TASKS_COUNT as f32;
+    let side = small;
+    for index in 0..TASKS_COUNT {
+        let y = top + 2.0 * small + (index as f32 + 0.5) * task_row;
+        writeln!(svg, r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="none" stroke="black" stroke-width="{}"/>"#,
+                 left, y - side, stroke, s = side).ok();
+        writeln!(svg, r##"<line x1="{x1}" y1="{y}" x2="{x2}" y2="{y}" stroke="#999" stroke-width="{s}"/>"##,
+                 x1 = left + 1.5 * side, x2 = width - margin, y = y, s = stroke).ok();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Writes the planner of the given day, unless it was already written that day and `force` isn't set.
+// Returns whether a new planner was written.
+pub fn update(path: &Path, date: NaiveDate, dims: (u32, u32), settings: &PlannerSettings,
+              forecast: Option<&Forecast>, calendar_dir: &Path, force: bool) -> Result<bool, Error> {
+    let written = fs::metadata(path).and_then(|m| m.modified()).ok()
+                     .map(|time| DateTime::<Local>::from(time).date_naive());
+    if !force && written == Some(date) {
+        return Ok(false);
+    }
+
+    let weather = forecast.and_then(|forecast| forecast.days.iter().find(|day| day.date == date))
+                          .map(|day| format!("{:.0}°/{:.0}° {}", day.min, day.max, condition(day.code)));
+
+    let mut events = Vec::new();
+    if settings.calendar && calendar_dir.exists() {
+        let calendars = load_calendars(calendar_dir).unwrap_or_default();
+        let from = date.and_time(NaiveTime::MIN);
+        for occurrence in occurrences(&calendars, from, from + Duration::days(1)) {
+            events.push(PlannerEvent {
+                start: Some(occurrence.start.time()).filter(|_| !occurrence.event.all_day && occurrence.start >= from),
+                summary: occurrence.event.summary.clone(),
+            });
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, render(date, dims, settings, weather.as_deref(), &events))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let settings = PlannerSettings {
+            start_hour: 8,
+            end_hour: 18,
+            weather: true,
+            .. Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        let events = vec![PlannerEvent { start: NaiveTime::from_hms_opt(9, 30, 0), summary: "R&D <sync>".to_string() },
+                          PlannerEvent { start: None, summary: "Holiday".to_string() }];
+        let svg = render(date, (1072, 1448), &settings, Some("12°/21° Rain"), &events);
+        assert!(svg.contains("Monday 3 June"));
+        assert!(svg.contains("09:30 R&amp;D &lt;sync&gt;"));
+        assert!(svg.contains(">Holiday<"));
+        assert!(svg.contains("12°/21° Rain"));
+        assert!(svg.contains(">08:00<") && svg.contains(">17:00<") && !svg.contains(">18:00<"));
+    }
+}
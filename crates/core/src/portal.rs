@@ -0,0 +1,361 @@
+use std::thread;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Child, ChildStdin, Stdio};
+use serde_json::{json, Value as JsonValue};
+use anyhow::{Error, format_err};
+use crate::document::html::xml::XmlParser;
+use crate::document::html::dom::NodeRef;
+use crate::view::{Event, Hub};
+use crate::settings::network_env;
+
+// The program that talks to the captive portals: it keeps the cookies of the session.
+pub const PORTAL_PROGRAM: &str = "bin/portal/portal";
+// The maximum number of characters of the page's text.
+const TEXT_LENGTH: usize = 800;
+const MAX_ACTIONS: usize = 8;
+// The unchecked boxes whose name matches one of these are only checked once the user accepts them.
+const CONSENT_WORDS: [&str; 5] = ["accept", "agree", "terms", "consent", "tos"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PortalAction {
+    pub label: String,
+    pub method: String,
+    pub url: String,
+    pub fields: Vec<(String, String)>,
+    // The names and the labels of the text fields filled by the user.
+    pub inputs: Vec<(String, String)>,
+    // The names, the values and the labels of the consent boxes to be accepted by the user.
+    pub consents: Vec<(String, String, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PortalPage {
+    pub url: String,
+    pub title: String,
+    pub text: String,
+    pub actions: Vec<PortalAction>,
+}
+
+// Resolves a link relative to the given page's URL.
+pub fn resolve_url(base: &str, href: &str) -> String {
+    let href = href.trim();
+    if href.contains("://") {
+        return href.to_string();
+    }
+
+    let (scheme, rest) = base.split_once("://").unwrap_or(("http", base));
+
+    if let Some(href) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, href);
+    }
+
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let origin = format!("{}://{}", scheme, &rest[..authority_end]);
+    let path = rest[authority_end..].split(['?', '#']).next().unwrap_or_default();
+
+    if href.is_empty() {
+        return base.split('#').next().unwrap_or(base).to_string();
+    }
+
+    match href.chars().next() {
+        Some('/') => format!("{}{}", origin, href),
+        Some('?') => format!("{}{}{}", origin, if path.is_empty() { "/" } else { path }, href),
+        Some('#') => format!("{}{}", base.split('#').next().unwrap_or(base), href),
+        _ => {
+            let dir = &path[..path.rfind('/').map_or(0, |index| index + 1)];
+            format!("{}{}{}", origin, if dir.is_empty() { "/" } else { dir }, href)
+        },
+    }
+}
+
+fn is_hidden(node: &NodeRef) -> bool {
+    node.ancestor_elements().chain(Some(*node))
+        .any(|n| matches!(n.tag_name(), Some("script" | "style" | "noscript" | "template" | "head")))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// The text of the label of the given input: the enclosing label, or the one pointing to it.
+fn label_text(form: NodeRef, node: NodeRef) -> Option<String> {
+    node.ancestor_elements()
+        .find(|n| n.tag_name() == Some("label"))
+        .or_else(|| {
+            let id = node.attribute("id")?;
+            form.descendants().find(|n| n.tag_name() == Some("label") && n.attribute("for") == Some(id))
+        })
+        .map(|n| collapse_whitespace(&n.text()))
+        .filter(|text| !text.is_empty())
+}
+
+fn parse_form(form: NodeRef, url: &str) -> PortalAction {
+    let mut action = PortalAction {
+        method: form.attribute("method").unwrap_or("get").to_lowercase(),
+        url: resolve_url(url, form.attribute("action").unwrap_or_default()),
+        .. Default::default()
+    };
+
+    for node in form.descendants().filter(|n| n.is_element()) {
+        let name = node.attribute("name").unwrap_or_default().to_string();
+        match node.tag_name() {
+            Some("input") => {
+                let kind = node.attribute("type").unwrap_or("text").to_lowercase();
+                let value = node.attribute("value").unwrap_or_default().to_string();
+                match kind.as_str() {
+                    "hidden" if !name.is_empty() => action.fields.push((name, value)),
+                    "checkbox" | "radio" if !name.is_empty() => {
+                        let value = if value.is_empty() { "on".to_string() } else { value };
+                        let lowercase_name = name.to_lowercase();
+                        if action.fields.iter().any(|(n, _)| *n == name) {
+                            continue;
+                        }
+                        if node.attribute("checked").is_some() {
+                            action.fields.push((name, value));
+                        } else if kind == "checkbox" && CONSENT_WORDS.iter().any(|w| lowercase_name.contains(w)) {
+                            let label = label_text(form, node).unwrap_or_else(|| name.clone());
+                            action.consents.push((name, value, label));
+                        }
+                    },
+                    "submit" | "image" if action.label.is_empty() => {
+                        action.label = value.clone();
+                        if !name.is_empty() {
+                            action.fields.push((name, value));
+                        }
+                    },
+                    "submit" | "image" | "button" | "reset" | "file" => (),
+                    _ if !name.is_empty() => {
+                        let label = node.attribute("placeholder")
+                                        .or_else(|| node.attribute("aria-label"))
+                                        .unwrap_or(&name).to_string();
+                        action.inputs.push((name, label));
+                    },
+                    _ => (),
+                }
+            },
+            Some("button") if action.label.is_empty() && node.attribute("type") != Some("button") => {
+                action.label = collapse_whitespace(&node.text());
+                if !name.is_empty() {
+                    action.fields.push((name, node.attribute("value").unwrap_or_default().to_string()));
+                }
+            },
+            Some("select") if !name.is_empty() => {
+                let options: Vec<NodeRef> = node.descendants().filter(|n| n.tag_name() == Some("option")).collect();
+                let option = options.iter().find(|o| o.attribute("selected").is_some()).or_else(|| options.first());
+                if let Some(option) = option {
+                    let value = option.attribute("value").map(String::from)
+                                      .unwrap_or_else(|| collapse_whitespace(&option.text()));
+                    action.fields.push((name, value));
+                }
+            },
+            _ => (),
+        }
+    }
+
+    if action.label.is_empty() {
+        action.label = "Submit".to_string();
+    }
+
+    action
+}
+
+// Extracts the title, the text, the forms and the links of a portal's page.
+pub fn parse_page(url: &str, html: &str) -> PortalPage {
+    let tree = XmlParser::new(html).parse();
+    let root = tree.root();
+    let mut page = PortalPage { url: url.to_string(), .. Default::default() };
+
+    page.title = root.descendants()
+                     .find(|n| n.tag_name() == Some("title"))
+                     .map(|n| collapse_whitespace(&n.text()))
+                     .unwrap_or_default();
+
+    let text = root.descendants()
+                   .filter(|n| !n.is_element() && !is_hidden(n))
+                   .map(|n| n.text())
+                   .collect::<Vec<String>>()
+                   .join(" ");
+    page.text = collapse_whitespace(&text).chars().take(TEXT_LENGTH).collect();
+
+    // Some portals redirect through a refresh directive.
+    let refresh = root.descendants()
+                      .find(|n| n.tag_name() == Some("meta") &&
+                                n.attribute("http-equiv").is_some_and(|v| v.eq_ignore_ascii_case("refresh")))
+                      .and_then(|n| n.attribute("content"))
+                      .and_then(|content| {
+                          let index = content.to_ascii_lowercase().find("url=")?;
+                          Some(content[index+4..].trim_matches(|c: char| c == '\'' || c == '"' || c.is_whitespace()).to_string())
+                      });
+    if let Some(href) = refresh {
+        page.actions.push(PortalAction {
+            label: "Continue".to_string(),
+            method: "get".to_string(),
+            url: resolve_url(url, &href),
+            .. Default::default()
+        });
+    }
+
+    for form in root.descendants().filter(|n| n.tag_name() == Some("form")) {
+        page.actions.push(parse_form(form, url));
+    }
+
+    for link in root.descendants().filter(|n| n.tag_name() == Some("a") && !is_hidden(n)) {
+        let href = link.attribute("href").unwrap_or_default().trim();
+        let label = collapse_whitespace(&link.text());
+        if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") ||
+           href.starts_with("mailto:") || label.is_empty() {
+            continue;
+        }
+        page.actions.push(PortalAction {
+            label,
+            method: "get".to_string(),
+            url: resolve_url(url, href),
+            .. Default::default()
+        });
+    }
+
+    page.actions.truncate(MAX_ACTIONS);
+    page
+}
+
+fn send_response(line: &str, hub: &Hub) -> bool {
+    let event = if let Ok(event) = serde_json::from_str::<JsonValue>(line) {
+        event
+    } else {
+        return false;
+    };
+    match event.get("type").and_then(JsonValue::as_str) {
+        Some("page") => {
+            let url = event.get("url").and_then(JsonValue::as_str).unwrap_or_default();
+            let html = event.get("html").and_then(JsonValue::as_str).unwrap_or_default();
+            hub.send(Event::PortalPage(Box::new(parse_page(url, html)))).ok();
+            true
+        },
+        Some("online") => {
+            hub.send(Event::PortalOnline).ok();
+            true
+        },
+        Some("error") => {
+            if let Some(message) = event.get("message").and_then(JsonValue::as_str) {
+                hub.send(Event::Notify(format!("Portal: {}.", message))).ok();
+            }
+            true
+        },
+        _ => false,
+    }
+}
+
+// Checks, in the background, whether the network is behind a captive portal.
+pub fn detect(hub: &Hub) {
+    let hub2 = hub.clone();
+    thread::spawn(move || {
+        let output = match network_env(&mut Command::new(PORTAL_PROGRAM)).arg("check").output() {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("Can't run {}: {:#}.", PORTAL_PROGRAM, e);
+                return;
+            },
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let is_portal = stdout.lines()
+                              .filter_map(|line| serde_json::from_str::<JsonValue>(line).ok())
+                              .any(|event| event.get("type").and_then(JsonValue::as_str) == Some("page"));
+        if is_portal {
+            hub2.send(Event::PortalDetected).ok();
+        }
+    });
+}
+
+// An interactive session with a captive portal.
+pub struct PortalSession {
+    process: Child,
+    stdin: ChildStdin,
+}
+
+impl PortalSession {
+    pub fn new(hub: &Hub) -> Result<PortalSession, Error> {
+        let mut process = network_env(&mut Command::new(PORTAL_PROGRAM))
+                                  .stdin(Stdio::piped())
+                                  .stdout(Stdio::piped())
+                                  .spawn()?;
+        let stdout = process.stdout.take()
+                            .ok_or_else(|| format_err!("can't take stdout"))?;
+        let stdin = process.stdin.take()
+                           .ok_or_else(|| format_err!("can't take stdin"))?;
+        let hub2 = hub.clone();
+        thread::spawn(move || {
+            let reader = BufReader::new(stdout);
+            for line in reader.lines().map_while(Result::ok) {
+                send_response(&line, &hub2);
+            }
+        });
+        Ok(PortalSession { process, stdin })
+    }
+
+    pub fn send(&mut self, action: &PortalAction, values: &[(String, String)]) -> Result<(), Error> {
+        let mut fields = action.fields.clone();
+        fields.extend_from_slice(values);
+        let request = json!({
+            "type": "request",
+            "method": action.method,
+            "url": action.url,
+            "fields": fields,
+        });
+        writeln!(self.stdin, "{}", request)?;
+        Ok(())
+    }
+}
+
+impl Drop for PortalSession {
+    fn drop(&mut self) {
+        unsafe { libc::kill(self.process.id() as libc::pid_t, libc::SIGTERM) };
+        self.process.wait().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "http://portal.example.org/login/index.html?next=1";
+        assert_eq!(resolve_url(base, "https://example.org/"), "https://example.org/");
+        assert_eq!(resolve_url(base, "//cdn.example.org/a"), "http://cdn.example.org/a");
+        assert_eq!(resolve_url(base, "/accept"), "http://portal.example.org/accept");
+        assert_eq!(resolve_url(base, "accept.php"), "http://portal.example.org/login/accept.php");
+        assert_eq!(resolve_url(base, "?step=2"), "http://portal.example.org/login/index.html?step=2");
+        assert_eq!(resolve_url("http://10.0.0.1", "go"), "http://10.0.0.1/go");
+    }
+
+    #[test]
+    fn test_parse_page() {
+        let html = r##"<html><head><title>Guest WiFi</title><script>var x = 1;</script></head>
+                      <body><p>Welcome to the   hotel.</p>
+                      <form method="POST" action="/login">
+                      <input type="hidden" name="token" value="abc"/>
+                      <input type="checkbox" name="accept_terms" id="terms"/>
+                      <label for="terms">I accept the   terms</label>
+                      <input type="text" name="room" placeholder="Room number"/>
+                      <button type="submit">Connect</button>
+                      </form>
+                      <a href="terms.html">Terms of use</a> <a href="#top">Top</a></body></html>"##;
+        let page = parse_page("http://portal.example.org/", html);
+        assert_eq!(page.title, "Guest WiFi");
+        assert!(page.text.starts_with("Welcome to the hotel."));
+        assert!(!page.text.contains("var x"));
+        assert_eq!(page.actions.len(), 2);
+        let form = &page.actions[0];
+        assert_eq!((form.label.as_str(), form.method.as_str(), form.url.as_str()),
+                   ("Connect", "post", "http://portal.example.org/login"));
+        assert_eq!(form.fields, vec![("token".to_string(), "abc".to_string())]);
+        assert_eq!(form.consents, vec![("accept_terms".to_string(), "on".to_string(),
+                                        "I accept the terms".to_string())]);
+        assert_eq!(form.inputs, vec![("room".to_string(), "Room number".to_string())]);
+        assert_eq!(page.actions[1].url, "http://portal.example.org/terms.html");
+
+        let html = r#"<html><head><meta http-equiv="Refresh" content="0; URL='/splash?id=3'"/></head></html>"#;
+        let page = parse_page("http://10.0.0.1/", html);
+        assert_eq!(page.actions[0].url, "http://10.0.0.1/splash?id=3");
+    }
+}
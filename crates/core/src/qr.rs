@@ -0,0 +1,490 @@
+// A QR code encoder, limited to the byte mode and the medium error correction level.
+// https://www.thonky.com/qr-code-tutorial/
+
+use anyhow::{Error, format_err};
+
+const MAX_VERSION: usize = 40;
+
+// Indexed by version, for the medium error correction level.
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26,
+    26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+const ECC_BLOCKS: [usize; MAX_VERSION + 1] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16,
+    17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+// The two bits of the medium error correction level.
+const ECC_LEVEL_BITS: u32 = 0b00;
+const PAD_BYTES: [u8; 2] = [0xEC, 0x11];
+
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    pub fn encode(data: &[u8]) -> Result<QrCode, Error> {
+        let version = (1..=MAX_VERSION).find(|&v| data_capacity(v) >= data.len())
+                                       .ok_or_else(|| format_err!("too much data to encode: {} bytes", data.len()))?;
+        let codewords = add_error_correction(version, &data_codewords(version, data));
+        let mut grid = Grid::new(version);
+        grid.draw_function_patterns();
+        grid.draw_codewords(&codewords);
+
+        let mask = (0..8).min_by_key(|&mask| {
+            let mut candidate = grid.clone();
+            candidate.apply_mask(mask);
+            candidate.draw_format_bits(mask);
+            candidate.penalty()
+        }).unwrap_or(0);
+
+        grid.apply_mask(mask);
+        grid.draw_format_bits(mask);
+
+        Ok(QrCode { size: grid.size, modules: grid.modules })
+    }
+
+    // The number of modules on each side, without the quiet zone.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Grid {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    // Whether each module belongs to a function pattern.
+    reserved: Vec<bool>,
+}
+
+impl Grid {
+    fn new(version: usize) -> Grid {
+        let size = 4 * version + 17;
+        Grid {
+            version,
+            size,
+            modules: vec![false; size * size],
+            reserved: vec![false; size * size],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.reserved[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+
+        for i in 0..size {
+            self.set_function(6, i, i.is_multiple_of(2));
+            self.set_function(i, 6, i.is_multiple_of(2));
+        }
+
+        self.draw_finder_pattern(3, 3);
+        self.draw_finder_pattern(size - 4, 3);
+        self.draw_finder_pattern(3, size - 4);
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Skip the three corners occupied by the finder patterns.
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                self.draw_alignment_pattern(x, y);
+            }
+        }
+
+        // Reserve the format areas, the actual bits are drawn after masking.
+        self.draw_format_bits(0);
+        self.draw_version_bits();
+    }
+
+    fn draw_finder_pattern(&mut self, cx: usize, cy: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                if x < 0 || y < 0 || x >= self.size as i32 || y >= self.size as i32 {
+                    continue;
+                }
+                let dist = dx.abs().max(dy.abs());
+                self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, cx: usize, cy: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let (x, y) = ((cx as i32 + dx) as usize, (cy as i32 + dy) as usize);
+                self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+        let size = self.size;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+
+        // The dark module.
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version_bits(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+
+        let bits = version_bits(self.version);
+
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    // Fills the non-function modules in a zigzag, two columns at a time,
+    // from the bottom right corner.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let total_bits = 8 * codewords.len();
+        let mut index = 0;
+        let mut right = size as i32 - 1;
+
+        while right >= 1 {
+            // Skip the vertical timing pattern.
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    if self.reserved[y * size + x] || index >= total_bits {
+                        continue;
+                    }
+                    self.modules[y * size + x] = (codewords[index / 8] >> (7 - index % 8)) & 1 == 1;
+                    index += 1;
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8) {
+        let size = self.size;
+        for y in 0..size {
+            for x in 0..size {
+                if !self.reserved[y * size + x] && mask_bit(mask, x, y) {
+                    self.modules[y * size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    // Scores the runs, the blocks and the balance of the dark modules.
+    // The patterns looking like finders aren't taken into account: the mask
+    // might not be the optimal one, but any mask yields a readable symbol.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut score = 0;
+
+        for horizontal in [true, false] {
+            for i in 0..size {
+                let mut run = 0;
+                let mut previous = None;
+                for j in 0..size {
+                    let dark = if horizontal { self.get(j, i) } else { self.get(i, j) };
+                    if previous == Some(dark) {
+                        run += 1;
+                    } else {
+                        if run >= 5 {
+                            score += run - 2;
+                        }
+                        run = 1;
+                        previous = Some(dark);
+                    }
+                }
+                if run >= 5 {
+                    score += run - 2;
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.get(x, y);
+                if dark == self.get(x + 1, y) && dark == self.get(x, y + 1) && dark == self.get(x + 1, y + 1) {
+                    score += 3;
+                }
+            }
+        }
+
+        let dark_count = self.modules.iter().filter(|&&dark| dark).count();
+        let total = size * size;
+        // The number of 5% steps away from an even balance.
+        let deviation = (20 * dark_count).abs_diff(10 * total).div_ceil(total).saturating_sub(1);
+        score + 10 * deviation
+    }
+}
+
+fn mask_bit(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+fn format_bits(mask: u8) -> u32 {
+    let data = (ECC_LEVEL_BITS << 3) | mask as u32;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+fn version_bits(version: usize) -> u32 {
+    let mut rem = version as u32;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+    }
+    ((version as u32) << 12) | rem
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let size = 4 * version + 17;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+// The number of modules available for the codewords.
+fn raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn data_codewords_count(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * ECC_BLOCKS[version]
+}
+
+fn count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+// The number of bytes that fit in the given version.
+fn data_capacity(version: usize) -> usize {
+    (8 * data_codewords_count(version) - 4 - count_bits(version)) / 8
+}
+
+struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 1 << (7 - self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+fn data_codewords(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = data_codewords_count(version);
+    let mut buffer = BitBuffer { bytes: Vec::with_capacity(capacity), len: 0 };
+
+    // The byte mode indicator.
+    buffer.push(0b0100, 4);
+    buffer.push(data.len() as u32, count_bits(version));
+    for &byte in data {
+        buffer.push(byte as u32, 8);
+    }
+
+    let terminator = (8 * capacity - buffer.len).min(4);
+    buffer.push(0, terminator);
+    let mut codewords = buffer.bytes;
+    for pad in PAD_BYTES.iter().cycle() {
+        if codewords.len() >= capacity {
+            break;
+        }
+        codewords.push(*pad);
+    }
+    codewords
+}
+
+// Splits the data into blocks, computes the error correction codewords
+// of each block, and interleaves the results.
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks_count = ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks_count = blocks_count - raw_codewords % blocks_count;
+    let short_block_len = raw_codewords / blocks_count - ecc_len;
+    let divisor = reed_solomon_divisor(ecc_len);
+
+    let mut blocks = Vec::with_capacity(blocks_count);
+    let mut start = 0;
+    for i in 0..blocks_count {
+        let len = short_block_len + if i < short_blocks_count { 0 } else { 1 };
+        let block = &data[start..start + len];
+        blocks.push((block, reed_solomon_remainder(block, &divisor)));
+        start += len;
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_block_len {
+        for (block, _) in &blocks {
+            if let Some(&byte) = block.get(i) {
+                result.push(byte);
+            }
+        }
+    }
+    for i in 0..ecc_len {
+        for (_, ecc) in &blocks {
+            result.push(ecc[i]);
+        }
+    }
+    result
+}
+
+// Multiplication in GF(2⁸) modulo x⁸ + x⁴ + x³ + x² + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+// The coefficients of the generator polynomial, from the highest to the
+// lowest degree, without the leading coefficient.
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor.iter()) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reed_solomon() {
+        // The *HELLO WORLD* example, in version 1 with the medium level.
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        let ecc = reed_solomon_remainder(&data, &reed_solomon_divisor(10));
+        assert_eq!(ecc, vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+    }
+
+    #[test]
+    fn test_format_and_version_bits() {
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(5), 0b100000011001110);
+        assert_eq!(version_bits(7), 0b000111110010010100);
+        assert_eq!(alignment_positions(2), vec![6, 18]);
+        assert_eq!(alignment_positions(7), vec![6, 22, 38]);
+        assert_eq!(data_codewords_count(1), 16);
+        assert_eq!(data_capacity(1), 14);
+        assert_eq!(data_capacity(40), 2331);
+    }
+
+    #[test]
+    fn test_encode() {
+        let code = QrCode::encode(b"https://github.com/baskerville/plato").unwrap();
+        assert_eq!(code.size(), 29);
+        // The top left finder pattern and its separator.
+        assert!((0..7).all(|i| code.is_dark(i, 0) && code.is_dark(0, i)));
+        assert!((0..8).all(|i| !code.is_dark(i, 7) && !code.is_dark(7, i)));
+        assert!(code.is_dark(8, code.size() - 8));
+        assert!(QrCode::encode(&[b'a'; 3000]).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs::{self, Permissions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use serde::Deserialize;
+use serde_json::json;
+use anyhow::Error;
+use crate::view::{Event, Hub};
+
+// A command sent through the command socket, as a single line of JSON.
+// For example: `{"command": "go-to", "page": "42"}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum RemoteCommand {
+    // Opens the document at the given path, relative to the current library.
+    Open { path: PathBuf },
+    // Accepts the same values as the *Go To Page* input of the reader.
+    GoTo { page: String },
+    SetFrontlight {
+        #[serde(default)]
+        intensity: Option<f32>,
+        #[serde(default)]
+        warmth: Option<f32>,
+    },
+    Screenshot,
+    // Imports the new documents of the current library.
+    Import,
+    // Writes the settings and the reading states to disk, e.g. before copying them over SSH.
+    Sync,
+    // Opens a book at a position handed off by another device, e.g. `plato:0123456789ABCDEF:42`.
+    Handoff { payload: String },
+    // Bundles the sketches of a directory, absolute or relative to the current library, into a PDF document.
+    BundleSketches { path: PathBuf },
+}
+
+fn reply(line: &str, hub: &Hub) -> String {
+    match serde_json::from_str::<RemoteCommand>(line) {
+        Ok(cmd) => {
+            if hub.send(Event::Remote(cmd)).is_ok() {
+                json!({"status": "ok"}).to_string()
+            } else {
+                json!({"status": "error", "message": "plato is quitting"}).to_string()
+            }
+        },
+        Err(e) => json!({"status": "error", "message": e.to_string()}).to_string(),
+    }
+}
+
+fn serve(stream: UnixStream, hub: &Hub) -> Result<(), Error> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", reply(&line, hub))?;
+    }
+
+    Ok(())
+}
+
+// Listens to the commands sent to the socket at the given path.
+// Each command is answered with a status, once it has been queued.
+// The commands received while the device is locked are dropped.
+pub fn listen<P: AsRef<Path>>(path: P, hub: &Hub) -> Result<(), Error> {
+    let path = path.as_ref();
+    // The socket of a previous session.
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, Permissions::from_mode(0o600))?;
+
+    let hub = hub.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+            let hub = hub.clone();
+            thread::spawn(move || {
+                serve(stream, &hub).map_err(|e| eprintln!("Command socket: {:#}.", e)).ok();
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let parse = |s: &str| serde_json::from_str::<RemoteCommand>(s).ok();
+        assert_eq!(parse(r#"{"command": "open", "path": "Books/a.epub"}"#),
+                   Some(RemoteCommand::Open { path: PathBuf::from("Books/a.epub") }));
+        assert_eq!(parse(r#"{"command": "go-to", "page": "50%"}"#),
+                   Some(RemoteCommand::GoTo { page: "50%".to_string() }));
+        assert_eq!(parse(r#"{"command": "set-frontlight", "intensity": 12.5}"#),
+                   Some(RemoteCommand::SetFrontlight { intensity: Some(12.5), warmth: None }));
+        assert_eq!(parse(r#"{"command": "screenshot"}"#), Some(RemoteCommand::Screenshot));
+        assert_eq!(parse(r#"{"command": "sync"}"#), Some(RemoteCommand::Sync));
+        assert_eq!(parse(r#"{"command": "handoff", "payload": "plato:0123456789ABCDEF:42"}"#),
+                   Some(RemoteCommand::Handoff { payload: "plato:0123456789ABCDEF:42".to_string() }));
+        assert_eq!(parse(r#"{"command": "bundle-sketches", "path": "Sketches"}"#),
+                   Some(RemoteCommand::BundleSketches { path: PathBuf::from("Sketches") }));
+        assert_eq!(parse(r#"{"command": "format-disk"}"#), None);
+    }
+}
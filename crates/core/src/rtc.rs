@@ -4,11 +4,12 @@ use std::path::Path;
 use std::os::unix::io::AsRawFd;
 use anyhow::Error;
 use nix::{ioctl_read, ioctl_write_ptr, ioctl_none};
-use chrono::{Duration, Utc, Datelike, Timelike};
+use chrono::{DateTime, Duration, Utc, Datelike, Timelike};
 
 ioctl_read!(rtc_read_alarm, b'p', 0x10, RtcWkalrm);
 ioctl_write_ptr!(rtc_write_alarm, b'p', 0x0f, RtcWkalrm);
 ioctl_none!(rtc_disable_alarm, b'p', 0x02);
+ioctl_write_ptr!(rtc_set_time, b'p', 0x0a, RtcTime);
 
 #[repr(C)]
 #[derive(Debug, Clone)]
@@ -24,6 +25,22 @@ pub struct RtcTime {
     tm_isdst: libc::c_int,
 }
 
+impl RtcTime {
+    fn from_datetime(wt: DateTime<Utc>) -> RtcTime {
+        RtcTime {
+            tm_sec: wt.second() as libc::c_int,
+            tm_min: wt.minute() as libc::c_int,
+            tm_hour: wt.hour() as libc::c_int,
+            tm_mday: wt.day() as libc::c_int,
+            tm_mon: wt.month0() as libc::c_int,
+            tm_year: (wt.year() - 1900) as libc::c_int,
+            tm_wday: -1,
+            tm_yday: -1,
+            tm_isdst: -1,
+        }
+    }
+}
+
 impl Default for RtcWkalrm {
     fn default() -> Self {
         unsafe { mem::zeroed() }
@@ -60,17 +77,7 @@ impl Rtc {
         let rwa = RtcWkalrm {
             enabled: 1,
             pending: 0,
-            time: RtcTime {
-                tm_sec: wt.second() as libc::c_int,
-                tm_min: wt.minute() as libc::c_int,
-                tm_hour: wt.hour() as libc::c_int,
-                tm_mday: wt.day() as libc::c_int,
-                tm_mon: wt.month0() as libc::c_int,
-                tm_year: (wt.year() - 1900) as libc::c_int,
-                tm_wday: -1,
-                tm_yday: -1,
-                tm_isdst: -1,
-            },
+            time: RtcTime::from_datetime(wt),
         };
         unsafe { rtc_write_alarm(self.0.as_raw_fd(), &rwa).map_err(|e| e.into()) }
     }
@@ -82,4 +89,10 @@ impl Rtc {
     pub fn disable_alarm(&self) -> Result<i32, Error> {
         unsafe { rtc_disable_alarm(self.0.as_raw_fd()).map_err(|e| e.into()) }
     }
+
+    // Keeps the synchronized time across reboots.
+    pub fn set_time(&self, time: DateTime<Utc>) -> Result<i32, Error> {
+        let rt = RtcTime::from_datetime(time);
+        unsafe { rtc_set_time(self.0.as_raw_fd(), &rt).map_err(|e| e.into()) }
+    }
 }
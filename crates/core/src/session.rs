@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use anyhow::Error;
+use crate::helpers::{load_json, save_json};
+use crate::view::{View, AppCmd};
+
+pub const SESSION_PATH: &str = "session.json";
+
+// The state of the views, saved when the device goes to sleep and restored at startup.
+// Each view records its own part through `View::save_state`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Session {
+    pub home: HomeState,
+    // The document being read, relative to the library's home.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reader: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application: Option<ApplicationState>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HomeState {
+    pub directory: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    pub page: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ApplicationState {
+    // The file is relative to the sketches directory.
+    Sketch { file: Option<String> },
+    HandwrittenNote { name: Option<String> },
+    Notes { note: Option<String> },
+    Dictionary { query: String, language: String },
+}
+
+impl ApplicationState {
+    pub fn app_cmd(&self) -> AppCmd {
+        match self {
+            ApplicationState::Sketch { .. } => AppCmd::Sketch,
+            ApplicationState::HandwrittenNote { name } => AppCmd::HandwrittenNote { name: name.clone() },
+            ApplicationState::Notes { note } => AppCmd::Notes { note: note.clone() },
+            ApplicationState::Dictionary { query, language } => AppCmd::Dictionary { query: query.clone(),
+                                                                                     language: language.clone() },
+        }
+    }
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Session {
+        if !path.exists() {
+            return Session::default();
+        }
+        load_json(path).map_err(|e| eprintln!("Can't load the session: {:#}.", e))
+                       .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        save_json(self, path)
+    }
+
+    // The views are given from the bottom to the top of the stack.
+    pub fn from_views<'a, I>(views: I) -> Session where I: Iterator<Item=&'a dyn View> {
+        let mut session = Session::default();
+        for view in views {
+            view.save_state(&mut session);
+        }
+        session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let session = Session {
+            home: HomeState { directory: PathBuf::from("/mnt/onboard/Comics"), query: Some("'a Hergé".to_string()), page: 2 },
+            reader: Some(PathBuf::from("Comics/Tintin.cbz")),
+            application: Some(ApplicationState::Notes { note: Some("todo.md".to_string()) }),
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(json.contains("\"kind\":\"notes\""));
+        assert_eq!(serde_json::from_str::<Session>(&json).unwrap(), session);
+        assert_eq!(serde_json::from_str::<Session>("{}").unwrap(), Session::default());
+    }
+}
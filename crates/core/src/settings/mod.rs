@@ -1,26 +1,36 @@
 mod preset;
 
 use std::env;
+use std::mem;
 use std::ops::Index;
 use std::fmt::{self, Debug};
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
 use std::collections::BTreeMap;
+use lazy_static::lazy_static;
 use fxhash::FxHashSet;
 use serde::{Serialize, Deserialize};
-use crate::metadata::{SortMethod, TextAlign};
+use crate::metadata::{SortMethod, TextAlign, RefreshPolicy, ZoomMode};
 use crate::frontlight::LightLevels;
 use crate::color::BLACK;
 use crate::device::CURRENT_DEVICE;
 use crate::unit::mm_to_px;
+use crate::geom::Point;
+use crate::document::html::layout::Typography;
 
 pub use self::preset::{LightPreset, guess_frontlight};
 
 pub const SETTINGS_PATH: &str = "Settings.toml";
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
 pub const DEFAULT_FONT_PATH: &str = "/mnt/onboard/fonts";
 pub const INTERNAL_CARD_ROOT: &str = "/mnt/onboard";
 pub const EXTERNAL_CARD_ROOT: &str = "/mnt/sd";
 pub const LOGO_SPECIAL_PATH: &str = "logo:";
 pub const COVER_SPECIAL_PATH: &str = "cover:";
+// The environment variable giving the additional certificate authorities to the helpers.
+pub const CA_CERTIFICATES_VAR: &str = "PLATO_CA_CERTIFICATES";
 // Default font size in points.
 pub const DEFAULT_FONT_SIZE: f32 = 11.0;
 // Default margin width in millimeters.
@@ -97,6 +107,8 @@ impl Index<IntermKind> for Intermissions {
 #[serde(default, rename_all = "kebab-case")]
 pub struct Settings {
     pub selected_library: usize,
+    // The name of the current profile.
+    pub profile: String,
     pub keyboard_layout: String,
     pub frontlight: bool,
     pub wifi: bool,
@@ -108,23 +120,83 @@ pub struct Settings {
     pub button_scheme: ButtonScheme,
     pub auto_suspend: u8,
     pub auto_power_off: u8,
+    // Apply the changes made to the settings and the keyboard layouts while running.
+    pub hot_reload: bool,
+    // Reopen the views that were shown when the application last stopped.
+    pub restore_session: bool,
+    // The minimum delay, in milliseconds, between two fast updates of the display.
+    pub frame_interval: u64,
     pub time_format: String,
     pub date_format: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_urls_queue: Option<PathBuf>,
+    // A Unix socket accepting JSON commands, one per line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_socket: Option<PathBuf>,
+    // Where the thumbnails, the layout and the page caches are kept instead of the libraries, e.g. on the card.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_path: Option<PathBuf>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub libraries: Vec<LibrarySettings>,
     pub intermissions: Intermissions,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub frontlight_presets: Vec<LightPreset>,
+    // The other profiles.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
     pub home: HomeSettings,
     pub reader: ReaderSettings,
     pub import: ImportSettings,
     pub dictionary: DictionarySettings,
+    pub translation: TranslationSettings,
+    pub share: ShareSettings,
+    pub speech: SpeechSettings,
+    pub ocr: OcrSettings,
+    pub handwriting: HandwritingSettings,
+    pub network: NetworkSettings,
+    pub clock: ClockSettings,
+    pub weather: WeatherSettings,
+    pub calendar: CalendarSettings,
+    pub notes: NotesSettings,
+    pub timer: TimerSettings,
     pub sketch: SketchSettings,
     pub calculator: CalculatorSettings,
     pub battery: BatterySettings,
+    pub snapshots: SnapshotSettings,
+    pub kiosk: KioskSettings,
+    pub lock: LockSettings,
+    pub auto_rotation: AutoRotationSettings,
     pub frontlight_levels: LightLevels,
+    pub frontlight_pwm: FrontlightPwmSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FrontlightPwmSettings {
+    // The sysfs directory of the PWM channel driving the frontlight, e.g. `/sys/class/pwm/pwmchip0/pwm0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    // The PWM frequency, in hertz. Zero keeps the current one.
+    pub frequency: u32,
+    // The LEDs are lit while the signal is low.
+    pub inverted: bool,
+    // Restrict the intensity to the flicker-free range.
+    pub flicker_sensitive: bool,
+    pub flicker_free_min: f32,
+    pub flicker_free_max: f32,
+}
+
+impl Default for FrontlightPwmSettings {
+    fn default() -> Self {
+        FrontlightPwmSettings {
+            path: None,
+            frequency: 0,
+            inverted: false,
+            flicker_sensitive: false,
+            flicker_free_min: 20.0,
+            flicker_free_max: 100.0,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -171,6 +243,15 @@ pub struct ImportSettings {
     pub startup_trigger: bool,
     pub metadata_kinds: FxHashSet<String>,
     pub allowed_kinds: FxHashSet<String>,
+    // Import the documents stored in ZIP archives.
+    pub browse_archives: bool,
+    // Record the checksums of the imported documents, for the integrity check.
+    pub record_checksums: bool,
+    // Query online services for the missing metadata of the imported documents.
+    pub fetch_metadata: bool,
+    pub metadata_fetcher: PathBuf,
+    // Import the highlights and bookmarks made with KOReader or Kobo's reader.
+    pub import_annotations: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,6 +261,8 @@ pub struct DictionarySettings {
     pub font_size: f32,
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     pub languages: BTreeMap<String, Vec<String>>,
+    // Add the looked up words to the vocabulary.
+    pub record_lookups: bool,
 }
 
 impl Default for DictionarySettings {
@@ -188,16 +271,302 @@ impl Default for DictionarySettings {
             font_size: 11.0,
             margin_width: 4,
             languages: BTreeMap::new(),
+            record_lookups: true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationBackend {
+    Dictionary,
+    LibreTranslate,
+    DeepL,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TranslationSettings {
+    pub backend: TranslationBackend,
+    // The language translated to, unless the book specifies another one.
+    pub target_language: String,
+    // The bilingual dictionaries used by the dictionary backend,
+    // indexed by language pairs, e.g. `en-fr`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dictionaries: BTreeMap<String, String>,
+    // The program that queries the online backends.
+    pub program: PathBuf,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+impl Default for TranslationSettings {
+    fn default() -> Self {
+        TranslationSettings {
+            backend: TranslationBackend::Dictionary,
+            target_language: "en".to_string(),
+            dictionaries: BTreeMap::new(),
+            program: PathBuf::from("bin/translator/translator"),
+            url: "https://libretranslate.com".to_string(),
+            api_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareBackend {
+    Webhook,
+    Email,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ShareSettings {
+    pub backend: ShareBackend,
+    // The program that sends the shared quotes and pages.
+    pub program: PathBuf,
+    // The endpoint of the webhook backend.
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    // The address and port of the SMTP server of the email backend, reached over TLS.
+    pub smtp_server: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: String,
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        ShareSettings {
+            backend: ShareBackend::Webhook,
+            program: PathBuf::from("bin/sharer/sharer"),
+            url: String::new(),
+            api_key: None,
+            smtp_server: String::new(),
+            username: None,
+            password: None,
+            from: String::new(),
+            to: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SpeechSettings {
+    // The program that speaks the text.
+    pub program: PathBuf,
+    // The pace of the voices at normal speed, used to follow the spoken words.
+    pub words_per_minute: f32,
+    // The voices, indexed by primary language tags, e.g. `fr`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<String, Voice>,
+}
+
+impl Default for SpeechSettings {
+    fn default() -> Self {
+        SpeechSettings {
+            program: PathBuf::from("scripts/speak.sh"),
+            words_per_minute: 160.0,
+            languages: BTreeMap::new(),
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct OcrSettings {
+    // The tesseract executable.
+    pub program: PathBuf,
+    // The languages of the models, e.g. `eng+fra`.
+    pub languages: String,
+    // The resolution of the images given to the program.
+    pub dpi: u16,
+    // Recognize the pages without text as soon as they're shown.
+    pub auto: bool,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        OcrSettings {
+            program: PathBuf::from("tesseract"),
+            languages: "eng".to_string(),
+            dpi: 300,
+            auto: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HandwritingSettings {
+    // The program that queries MyScript's batch API.
+    pub program: PathBuf,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hmac_key: Option<String>,
+    // The language of the handwriting, e.g. `fr_FR`.
+    pub language: String,
+    // Write in the text inputs instead of typing on the keyboard.
+    pub pad: bool,
+}
+
+impl Default for HandwritingSettings {
+    fn default() -> Self {
+        HandwritingSettings {
+            program: PathBuf::from("bin/recognizer/recognizer"),
+            url: "https://cloud.myscript.com/api/v4.0/iink/batch".to_string(),
+            application_key: None,
+            hmac_key: None,
+            language: "en_US".to_string(),
+            pad: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Voice {
+    // The name of the voice given to the program, instead of the language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // Relative to the normal speed and pitch.
+    pub speed: f32,
+    pub pitch: f32,
+}
+
+impl Default for Voice {
+    fn default() -> Self {
+        Voice {
+            name: None,
+            speed: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NetworkSettings {
+    // The URL of the HTTP(S) proxy used by the helpers and the hooks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    // The hosts reached directly, separated by commas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    // A PEM file of certificate authorities trusted in addition to the built-in ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_certificates: Option<PathBuf>,
+    // Look for a captive portal when the WiFi connects.
+    pub detect_portal: bool,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        NetworkSettings {
+            proxy: None,
+            no_proxy: None,
+            ca_certificates: None,
+            detect_portal: true,
+        }
+    }
+}
+
+lazy_static! {
+    // The variables set, or unset when empty, in the environment of the helpers and the hooks.
+    static ref NETWORK_ENV: Mutex<Vec<(&'static str, Option<OsString>)>> = Mutex::new(Vec::new());
+}
+
+impl NetworkSettings {
+    // The environment of the application is left untouched: it's given to each helper and hook
+    // through `network_env`.
+    pub fn export(&self) {
+        let mut vars = Vec::new();
+        for name in ["http_proxy", "https_proxy", "HTTP_PROXY", "HTTPS_PROXY"] {
+            vars.push((name, self.proxy.as_ref().map(OsString::from)));
+        }
+        for name in ["no_proxy", "NO_PROXY"] {
+            vars.push((name, self.no_proxy.as_ref().map(OsString::from)));
+        }
+        // The helpers don't run from the application's directory.
+        vars.push((CA_CERTIFICATES_VAR, self.ca_certificates.as_ref().map(|path| {
+            env::current_dir().map(|dir| dir.join(path))
+                              .unwrap_or_else(|_| path.clone())
+                              .into_os_string()
+        })));
+        *NETWORK_ENV.lock().unwrap() = vars;
+    }
+}
+
+// Sets the proxy and the certificate authorities of a program that uses the network.
+pub fn network_env(command: &mut Command) -> &mut Command {
+    for (name, value) in NETWORK_ENV.lock().unwrap().iter() {
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name),
+        };
+    }
+    command
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct SketchSettings {
     pub save_path: PathBuf,
     pub notify_success: bool,
+    // Refresh the heavily drawn regions once the fingers are lifted.
+    pub refresh_ghosting: bool,
+    // Save the strokes of the sketches next to them, to replay them.
+    pub record_strokes: bool,
+    // When set, sketches are saved encrypted with this passphrase.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    // A template (PNG or SVG) drawn beneath the new sketches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<PathBuf>,
     pub pen: Pen,
+    // The most recently picked pen colors, latest first.
+    pub recent_colors: Vec<u8>,
+    // Additional gray levels shown in the color picker, pinned by holding a swatch.
+    pub custom_colors: Vec<u8>,
+    pub planner: PlannerSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PlannerSettings {
+    // Write a daily planner template every morning.
+    pub enabled: bool,
+    // The path of the template, relative to the library's path.
+    pub path: PathBuf,
+    // The hours covered by the schedule.
+    pub start_hour: u32,
+    pub end_hour: u32,
+    // Show the forecast of the day.
+    pub weather: bool,
+    // Show the calendar events of the day.
+    pub calendar: bool,
+}
+
+impl Default for PlannerSettings {
+    fn default() -> Self {
+        PlannerSettings {
+            enabled: false,
+            path: PathBuf::from("Templates/planner.svg"),
+            start_hour: 8,
+            end_hour: 20,
+            weather: true,
+            calendar: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -208,7 +577,7 @@ pub struct CalculatorSettings {
     pub history_size: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct Pen {
     pub size: i32,
@@ -217,6 +586,18 @@ pub struct Pen {
     pub amplitude: f32,
     pub min_speed: f32,
     pub max_speed: f32,
+    // The correction applied to the touch positions, set by the pen calibration.
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+}
+
+impl Pen {
+    pub fn correct(&self, pt: Point) -> Point {
+        pt!((self.scale_x * pt.x as f32).round() as i32 + self.offset_x,
+            (self.scale_y * pt.y as f32).round() as i32 + self.offset_y)
+    }
 }
 
 impl Default for Pen {
@@ -228,6 +609,10 @@ impl Default for Pen {
             amplitude: 4.0,
             min_speed: 0.0,
             max_speed: mm_to_px(254.0, CURRENT_DEVICE.dpi),
+            offset_x: 0,
+            offset_y: 0,
+            scale_x: 1.0,
+            scale_y: 1.0,
         }
     }
 }
@@ -237,7 +622,14 @@ impl Default for SketchSettings {
         SketchSettings {
             save_path: PathBuf::from("Sketches"),
             notify_success: true,
+            refresh_ghosting: true,
+            record_strokes: false,
+            passphrase: None,
+            background: None,
             pen: Pen::default(),
+            recent_colors: Vec::new(),
+            custom_colors: Vec::new(),
+            planner: PlannerSettings::default(),
         }
     }
 }
@@ -302,16 +694,62 @@ pub struct HomeSettings {
     pub navigation_bar: bool,
     pub max_levels: usize,
     pub max_trash_size: u64,
+    pub smart_collections: Vec<SmartCollection>,
+}
+
+// A saved library search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SmartCollection {
+    pub name: String,
+    pub query: String,
 }
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct RefreshRateSettings {
+    pub policy: RefreshPolicy,
     pub regular: u8,
     pub inverted: u8,
 }
 
+// The widths, in millimeters, of the screen edges where taps are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DeadZones {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+// The defaults of either the reflowable or the fixed-layout documents.
+// The unset values fall back on the general reader settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ReaderProfile {
+    // The margin of the text for the reflowable documents, of the screen otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin_width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom_mode: Option<ZoomMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contrast_exponent: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contrast_gray: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_width: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corner_width: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub south_east_corner: Option<SouthEastCornerAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub west_strip: Option<WestStripAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub east_strip: Option<EastStripAction>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct ReaderSettings {
@@ -321,6 +759,8 @@ pub struct ReaderSettings {
     pub south_strip: SouthStripAction,
     pub west_strip: WestStripAction,
     pub east_strip: EastStripAction,
+    // Whether swiping along the west (resp. east) edge sets the frontlight intensity (resp. warmth).
+    pub edge_light_swipes: bool,
     pub strip_width: f32,
     pub corner_width: f32,
     pub font_path: String,
@@ -338,6 +778,59 @@ pub struct ReaderSettings {
     pub dithered_kinds: FxHashSet<String>,
     pub paragraph_breaker: ParagraphBreakerSettings,
     pub refresh_rate: RefreshRateSettings,
+    pub dead_zones: DeadZones,
+    // The actions triggered by the letters drawn with one finger.
+    pub letter_gestures: BTreeMap<String, LetterAction>,
+    // Where finished books are moved by the end-of-book panel. Relative to the library's path.
+    pub archive_directory: PathBuf,
+    // How long, in seconds, the chip that leads back to the page left by a jump stays visible.
+    // Zero disables the chip.
+    pub return_chip_duration: u64,
+    // How the images are shown when the colors are inverted.
+    pub night_images: NightImages,
+    // The brightness of the dimmed images, between 0 and 1.
+    pub night_images_brightness: f32,
+    // Open the new books at the start of their body matter, as declared by their landmarks.
+    pub skip_front_matter: bool,
+    pub reflowable: ReaderProfile,
+    pub fixed_layout: ReaderProfile,
+}
+
+impl ReaderSettings {
+    // The profile of the documents of the given kind.
+    pub fn profile(&self, reflowable: bool) -> &ReaderProfile {
+        if reflowable {
+            &self.reflowable
+        } else {
+            &self.fixed_layout
+        }
+    }
+
+    // The documents' margin defaults to zero when they aren't reflowable.
+    pub fn margin_width(&self, reflowable: bool) -> i32 {
+        self.profile(reflowable).margin_width
+            .unwrap_or(if reflowable { self.margin_width } else { 0 })
+    }
+
+    pub fn strip_width(&self, reflowable: bool) -> f32 {
+        self.profile(reflowable).strip_width.unwrap_or(self.strip_width)
+    }
+
+    pub fn corner_width(&self, reflowable: bool) -> f32 {
+        self.profile(reflowable).corner_width.unwrap_or(self.corner_width)
+    }
+
+    pub fn south_east_corner(&self, reflowable: bool) -> SouthEastCornerAction {
+        self.profile(reflowable).south_east_corner.unwrap_or(self.south_east_corner)
+    }
+
+    pub fn west_strip(&self, reflowable: bool) -> WestStripAction {
+        self.profile(reflowable).west_strip.unwrap_or(self.west_strip)
+    }
+
+    pub fn east_strip(&self, reflowable: bool) -> EastStripAction {
+        self.profile(reflowable).east_strip.unwrap_or(self.east_strip)
+    }
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -345,6 +838,27 @@ pub struct ReaderSettings {
 pub struct ParagraphBreakerSettings {
     pub hyphen_penalty: i32,
     pub stretch_tolerance: f32,
+    pub hyphenation: bool,
+    pub min_word_spacing: f32,
+    pub max_word_spacing: f32,
+    pub hanging_punctuation: bool,
+    pub min_chars_before_hyphen: usize,
+    pub min_chars_after_hyphen: usize,
+    pub orphans: usize,
+    pub widows: usize,
+}
+
+impl ParagraphBreakerSettings {
+    pub fn typography(&self) -> Typography {
+        Typography {
+            hyphenation: self.hyphenation,
+            word_spacing: (self.min_word_spacing, self.max_word_spacing),
+            hanging_punctuation: self.hanging_punctuation,
+            hyphen_minima: (self.min_chars_before_hyphen, self.min_chars_after_hyphen),
+            orphans: self.orphans,
+            widows: self.widows,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -352,6 +866,209 @@ pub struct ParagraphBreakerSettings {
 pub struct BatterySettings {
     pub warn: f32,
     pub power_off: f32,
+    // The number of days initially shown by the battery history.
+    pub history_days: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SnapshotSettings {
+    pub enabled: bool,
+    // The number of daily snapshots kept.
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct KioskSettings {
+    pub enabled: bool,
+    // The kiosk mode can't be entered as long as this is empty.
+    pub pin: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LockSettings {
+    // The device isn't locked as long as this is empty.
+    pub pin: String,
+    // The minimum duration of the sleep, in minutes, that locks the device.
+    pub timeout: u16,
+}
+
+// The preferences of a profile, saved while another profile is current.
+// Each profile also has its own reading states.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Profile {
+    pub name: String,
+    pub frontlight: bool,
+    pub frontlight_levels: LightLevels,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub frontlight_presets: Vec<LightPreset>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub smart_collections: Vec<SmartCollection>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            name: String::default(),
+            frontlight: true,
+            frontlight_levels: LightLevels::default(),
+            frontlight_presets: Vec::new(),
+            smart_collections: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ClockSettings {
+    // Synchronize the clock when the WiFi connects.
+    pub sync: bool,
+    pub ntp_servers: Vec<String>,
+    // The name of a time zone, e.g. *Europe/Paris*, or a POSIX TZ rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl Default for ClockSettings {
+    fn default() -> Self {
+        ClockSettings {
+            sync: true,
+            ntp_servers: vec!["pool.ntp.org".to_string()],
+            timezone: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WeatherSettings {
+    // Show the current conditions in the top bar of the home screen.
+    pub enabled: bool,
+    pub location: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub temperature_unit: TemperatureUnit,
+    // The number of minutes after which the forecast is renewed.
+    pub refresh_interval: u64,
+    pub forecast_days: usize,
+    // Write the forecast at the bottom of the sleep screen.
+    pub sleep_screen: bool,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        WeatherSettings {
+            enabled: false,
+            location: String::new(),
+            latitude: 48.8566,
+            longitude: 2.3522,
+            temperature_unit: TemperatureUnit::Celsius,
+            refresh_interval: 60,
+            forecast_days: 3,
+            sleep_screen: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CalendarSettings {
+    // The directory of the `.ics` files, relative to the library's path.
+    pub path: PathBuf,
+    // The addresses of the calendars downloaded into `path`.
+    pub urls: Vec<String>,
+    // The number of days shown at once by the agenda.
+    pub days: usize,
+}
+
+impl Default for CalendarSettings {
+    fn default() -> Self {
+        CalendarSettings {
+            path: PathBuf::from("Calendars"),
+            urls: Vec::new(),
+            days: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NotesSettings {
+    // The directory of the notes, relative to the library's path.
+    pub path: PathBuf,
+    // The file names of the notes listed in the main menu.
+    pub pinned: Vec<String>,
+}
+
+impl Default for NotesSettings {
+    fn default() -> Self {
+        NotesSettings {
+            path: PathBuf::from(".notes"),
+            pinned: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TimerSettings {
+    pub focus_minutes: u32,
+    pub break_minutes: u32,
+    pub long_break_minutes: u32,
+    // The number of focus sessions after which the break is a long one.
+    pub long_break_interval: usize,
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        TimerSettings {
+            focus_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_interval: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AutoRotationSettings {
+    // How long, in milliseconds, the device must stay in a new orientation before the screen rotates.
+    pub delay: u64,
+    // How far, in degrees, the device must tilt past the bisector of two orientations.
+    pub hysteresis: f32,
+    // Whether the rotation gesture stays enabled when the screen follows the orientation of the device.
+    pub rotate_gesture: bool,
+}
+
+impl Default for AutoRotationSettings {
+    fn default() -> Self {
+        AutoRotationSettings {
+            delay: 600,
+            hysteresis: 15.0,
+            rotate_gesture: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NightImages {
+    Original,
+    Dimmed,
+    Inverted,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -359,6 +1076,7 @@ pub struct BatterySettings {
 pub enum FinishedAction {
     Notify,
     Close,
+    Panel,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -375,6 +1093,20 @@ pub enum BottomRightGestureAction {
     ToggleInverted,
 }
 
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LetterAction {
+    Search,
+    ToggleBookmark,
+    TableOfContents,
+    GoToPage,
+    PreviousChapter,
+    NextChapter,
+    ToggleBars,
+    ToggleInverted,
+    ToggleDithered,
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum SouthStripAction {
@@ -401,6 +1133,7 @@ pub enum WestStripAction {
 impl Default for RefreshRateSettings {
     fn default() -> Self {
         RefreshRateSettings {
+            policy: RefreshPolicy::Interval,
             regular: 8,
             inverted: 2,
         }
@@ -414,6 +1147,7 @@ impl Default for HomeSettings {
             navigation_bar: true,
             max_levels: 3,
             max_trash_size: 32 * (1 << 20),
+            smart_collections: Vec::new(),
         }
     }
 }
@@ -423,6 +1157,14 @@ impl Default for ParagraphBreakerSettings {
         ParagraphBreakerSettings {
             hyphen_penalty: HYPHEN_PENALTY,
             stretch_tolerance: STRETCH_TOLERANCE,
+            hyphenation: true,
+            min_word_spacing: 0.67,
+            max_word_spacing: 1.5,
+            hanging_punctuation: false,
+            min_chars_before_hyphen: 2,
+            min_chars_after_hyphen: 2,
+            orphans: 1,
+            widows: 1,
         }
     }
 }
@@ -436,6 +1178,7 @@ impl Default for ReaderSettings {
             south_strip: SouthStripAction::ToggleBars,
             west_strip: WestStripAction::PreviousPage,
             east_strip: EastStripAction::NextPage,
+            edge_light_swipes: false,
             strip_width: 0.6,
             corner_width: 0.4,
             font_path: DEFAULT_FONT_PATH.to_string(),
@@ -453,6 +1196,20 @@ impl Default for ReaderSettings {
             dithered_kinds: ["cbz", "png", "jpg", "jpeg"].iter().map(|k| k.to_string()).collect(),
             paragraph_breaker: ParagraphBreakerSettings::default(),
             refresh_rate: RefreshRateSettings::default(),
+            dead_zones: DeadZones::default(),
+            letter_gestures: [("S", LetterAction::Search),
+                              ("B", LetterAction::ToggleBookmark),
+                              ("C", LetterAction::TableOfContents),
+                              ("G", LetterAction::GoToPage)].iter()
+                                                            .map(|(l, a)| (l.to_string(), *a))
+                                                            .collect(),
+            archive_directory: PathBuf::from("Archive"),
+            return_chip_duration: 8,
+            night_images: NightImages::Original,
+            night_images_brightness: 0.6,
+            skip_front_matter: false,
+            reflowable: ReaderProfile::default(),
+            fixed_layout: ReaderProfile::default(),
         }
     }
 }
@@ -464,7 +1221,12 @@ impl Default for ImportSettings {
             startup_trigger: true,
             metadata_kinds: ["epub", "pdf", "djvu"].iter().map(|k| k.to_string()).collect(),
             allowed_kinds: ["pdf", "djvu", "epub", "fb2",
-                            "xps", "oxps", "cbz"].iter().map(|k| k.to_string()).collect(),
+                            "xps", "oxps", "cbz", "pse"].iter().map(|k| k.to_string()).collect(),
+            browse_archives: false,
+            record_checksums: true,
+            fetch_metadata: false,
+            metadata_fetcher: PathBuf::from("bin/metadata_fetcher/metadata_fetcher"),
+            import_annotations: true,
         }
     }
 }
@@ -474,6 +1236,16 @@ impl Default for BatterySettings {
         BatterySettings {
             warn: 10.0,
             power_off: 3.0,
+            history_days: 7,
+        }
+    }
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        SnapshotSettings {
+            enabled: true,
+            keep: 7,
         }
     }
 }
@@ -482,6 +1254,7 @@ impl Default for Settings {
     fn default() -> Self {
         Settings {
             selected_library: 0,
+            profile: DEFAULT_PROFILE_NAME.to_string(),
             libraries: vec![
                 LibrarySettings {
                     name: "On Board".to_string(),
@@ -514,6 +1287,8 @@ impl Default for Settings {
                 },
             ],
             external_urls_queue: Some(PathBuf::from("bin/article_fetcher/urls.txt")),
+            command_socket: None,
+            cache_path: None,
             keyboard_layout: "English".to_string(),
             frontlight: true,
             wifi: false,
@@ -524,6 +1299,9 @@ impl Default for Settings {
             button_scheme: ButtonScheme::Natural,
             auto_suspend: 30,
             auto_power_off: 3,
+            hot_reload: true,
+            restore_session: true,
+            frame_interval: 40,
             time_format: "%H:%M".to_string(),
             date_format: "%A, %B %-d, %Y".to_string(),
             intermissions: Intermissions {
@@ -535,11 +1313,44 @@ impl Default for Settings {
             reader: ReaderSettings::default(),
             import: ImportSettings::default(),
             dictionary: DictionarySettings::default(),
+            translation: TranslationSettings::default(),
+            share: ShareSettings::default(),
+            speech: SpeechSettings::default(),
+            ocr: OcrSettings::default(),
+            handwriting: HandwritingSettings::default(),
+            network: NetworkSettings::default(),
+            clock: ClockSettings::default(),
+            weather: WeatherSettings::default(),
+            calendar: CalendarSettings::default(),
+            notes: NotesSettings::default(),
+            timer: TimerSettings::default(),
             sketch: SketchSettings::default(),
             calculator: CalculatorSettings::default(),
             battery: BatterySettings::default(),
+            snapshots: SnapshotSettings::default(),
+            kiosk: KioskSettings::default(),
+            lock: LockSettings::default(),
+            auto_rotation: AutoRotationSettings::default(),
             frontlight_levels: LightLevels::default(),
+            frontlight_pwm: FrontlightPwmSettings::default(),
             frontlight_presets: Vec::new(),
+            profiles: Vec::new(),
         }
     }
 }
+
+impl Settings {
+    // Saves the preferences of the current profile in place of those of the given profile,
+    // which becomes the current one.
+    pub fn switch_profile(&mut self, index: usize) {
+        let next = mem::take(&mut self.profiles[index]);
+        let previous = Profile {
+            name: mem::replace(&mut self.profile, next.name),
+            frontlight: mem::replace(&mut self.frontlight, next.frontlight),
+            frontlight_levels: mem::replace(&mut self.frontlight_levels, next.frontlight_levels),
+            frontlight_presets: mem::replace(&mut self.frontlight_presets, next.frontlight_presets),
+            smart_collections: mem::replace(&mut self.home.smart_collections, next.smart_collections),
+        };
+        self.profiles[index] = previous;
+    }
+}
@@ -0,0 +1,86 @@
+use std::thread;
+use std::path::PathBuf;
+use std::process::Command;
+use crate::settings::{ShareSettings, ShareBackend, network_env};
+use crate::view::{Event, Hub};
+
+// Where the page snapshots are written before being sent.
+pub const SNAPSHOT_PATH: &str = "/tmp/plato-share.png";
+
+#[derive(Debug, Clone)]
+pub enum Payload {
+    Quote(String),
+    // The path of a PNG image.
+    Page(PathBuf),
+}
+
+// The quoted text followed by its source, e.g. `“…”\n— Author, Title`.
+pub fn format_quote(text: &str, title: &str, author: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let source = match (author.is_empty(), title.is_empty()) {
+        (false, false) => format!("{}, {}", author, title),
+        (false, true) => author.to_string(),
+        (true, false) => title.to_string(),
+        (true, true) => return format!("“{}”", text),
+    };
+    format!("“{}”\n— {}", text, source)
+}
+
+// Runs the program that sends the payload in the background.
+// The outcome is reported through a notification.
+pub fn share(payload: Payload, title: &str, settings: &ShareSettings, hub: &Hub) {
+    let backend = match settings.backend {
+        ShareBackend::Webhook => "webhook",
+        ShareBackend::Email => "email",
+    };
+    let (kind, content) = match payload {
+        Payload::Quote(text) => ("quote", text),
+        Payload::Page(path) => ("page", path.to_string_lossy().into_owned()),
+    };
+    let settings = settings.clone();
+    let title = title.to_string();
+    let hub = hub.clone();
+
+    thread::spawn(move || {
+        let output = network_env(&mut Command::new(&settings.program))
+                             .arg(backend)
+                             .arg(kind)
+                             .arg(&content)
+                             .arg(&title)
+                             .env("PLATO_SHARE_URL", &settings.url)
+                             .env("PLATO_SHARE_API_KEY", settings.api_key.as_deref().unwrap_or_default())
+                             .env("PLATO_SHARE_SMTP_SERVER", &settings.smtp_server)
+                             .env("PLATO_SHARE_USERNAME", settings.username.as_deref().unwrap_or_default())
+                             .env("PLATO_SHARE_PASSWORD", settings.password.as_deref().unwrap_or_default())
+                             .env("PLATO_SHARE_FROM", &settings.from)
+                             .env("PLATO_SHARE_TO", &settings.to)
+                             .output();
+        let message = match output {
+            Ok(output) if output.status.success() => {
+                format!("Shared the {}.", kind)
+            },
+            Ok(output) => {
+                eprintln!("Can't share: {}.", String::from_utf8_lossy(&output.stderr).trim());
+                format!("Can't share the {}.", kind)
+            },
+            Err(e) => {
+                eprintln!("Can't spawn {}: {:#}.", settings.program.display(), e);
+                format!("Can't share the {}.", kind)
+            },
+        };
+        hub.send(Event::Notify(message)).ok();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_quote() {
+        assert_eq!(format_quote("It was  a\nbright day.", "1984", "George Orwell"),
+                   "“It was a bright day.”\n— George Orwell, 1984");
+        assert_eq!(format_quote("Hello.", "", ""), "“Hello.”");
+        assert_eq!(format_quote("Hello.", "Title", ""), "“Hello.”\n— Title");
+    }
+}
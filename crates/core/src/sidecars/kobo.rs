@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+use fxhash::FxHashMap;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc, DateTime};
+use anyhow::Error;
+use crate::sqlite::{Database, Value};
+use super::{ForeignAnnotation, ForeignKind};
+
+// The database of Kobo's reader.
+pub const KOBO_DATABASE_PATH: &str = "/mnt/onboard/.kobo/KoboReader.sqlite";
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+// The dates are stored in UTC, with optional fractional seconds.
+fn datetime(text: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(text.get(..19)?, DATETIME_FORMAT).ok()?;
+    Some(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+// Groups the bookmarks and highlights of the *Bookmark* table by document path.
+pub fn foreign_annotations(columns: &[String], rows: &[Vec<Value>]) -> FxHashMap<PathBuf, Vec<ForeignAnnotation>> {
+    let index = |name: &str| columns.iter().position(|c| c.eq_ignore_ascii_case(name));
+    let (volume, text, note, created, modified, kind, hidden, context) =
+        (index("VolumeID"), index("Text"), index("Annotation"), index("DateCreated"),
+         index("DateModified"), index("Type"), index("Hidden"), index("ContextString"));
+    let get = |row: &Vec<Value>, index: Option<usize>| -> String {
+        index.and_then(|i| row.get(i)).and_then(Value::as_str).unwrap_or_default().trim().to_string()
+    };
+    let mut result: FxHashMap<PathBuf, Vec<ForeignAnnotation>> = FxHashMap::default();
+
+    for row in rows {
+        let is_hidden = hidden.and_then(|i| row.get(i)).is_some_and(|v| {
+            v.as_i64() == Some(1) || v.as_str() == Some("true")
+        });
+        if is_hidden {
+            continue;
+        }
+        let path = get(row, volume);
+        let path = if let Some(path) = path.strip_prefix("file://") { path } else { continue };
+        let marked = get(row, text);
+        let kind = match get(row, kind).as_str() {
+            "dogear" => ForeignKind::Bookmark,
+            _ if !marked.is_empty() => ForeignKind::Highlight,
+            _ => continue,
+        };
+        let text = if kind == ForeignKind::Bookmark { get(row, context) } else { marked };
+        let modified = datetime(&get(row, modified)).or_else(|| datetime(&get(row, created)));
+        result.entry(PathBuf::from(path)).or_default().push(ForeignAnnotation {
+            kind,
+            text,
+            note: get(row, note),
+            page: None,
+            modified,
+        });
+    }
+
+    result
+}
+
+pub fn annotations<P: AsRef<Path>>(path: P) -> Result<FxHashMap<PathBuf, Vec<ForeignAnnotation>>, Error> {
+    let db = Database::open(path)?;
+    let (columns, rows) = db.table("Bookmark")?;
+    Ok(foreign_annotations(&columns, &rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_foreign_annotations() {
+        let columns = ["VolumeID", "Text", "Annotation", "DateCreated", "Type", "Hidden", "ContextString"]
+                      .iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        let text = |s: &str| Value::Text(s.to_string());
+        let rows = vec![
+            vec![text("file:///mnt/onboard/a.epub"), text(" Quoted\n"), text("Mine"),
+                 text("2023-05-01T12:34:56.000"), text("note"), text("false"), Value::Null],
+            vec![text("file:///mnt/onboard/a.epub"), Value::Null, Value::Null,
+                 text("2023-05-02T08:00:00Z"), text("dogear"), Value::Integer(0), text("The start")],
+            vec![text("file:///mnt/onboard/b.epub"), text("Gone"), Value::Null,
+                 Value::Null, text("highlight"), text("true"), Value::Null],
+        ];
+        let annotations = foreign_annotations(&columns, &rows);
+        assert_eq!(annotations.len(), 1);
+        let a = &annotations[Path::new("/mnt/onboard/a.epub")];
+        assert_eq!(a.len(), 2);
+        assert_eq!((a[0].kind, a[0].text.as_str(), a[0].note.as_str()), (ForeignKind::Highlight, "Quoted", "Mine"));
+        assert!(a[0].modified.is_some());
+        assert_eq!((a[1].kind, a[1].text.as_str()), (ForeignKind::Bookmark, "The start"));
+    }
+}
@@ -0,0 +1,375 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{Local, NaiveDateTime, TimeZone, DateTime};
+use anyhow::{Error, format_err, bail};
+use super::{ForeignAnnotation, ForeignKind};
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// The subset of Lua's values found in KOReader's metadata files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LuaValue {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Table(Vec<(LuaValue, LuaValue)>),
+}
+
+impl LuaValue {
+    pub fn get(&self, key: &str) -> Option<&LuaValue> {
+        match self {
+            LuaValue::Table(entries) => entries.iter().find_map(|(k, v)| {
+                match k {
+                    LuaValue::String(s) if s == key => Some(v),
+                    _ => None,
+                }
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(LuaValue, LuaValue)] {
+        match self {
+            LuaValue::Table(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            LuaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            LuaValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_blanks(&mut self) {
+        loop {
+            while self.index < self.bytes.len() && self.bytes[self.index].is_ascii_whitespace() {
+                self.index += 1;
+            }
+            if self.bytes[self.index..].starts_with(b"--") {
+                self.index += 2;
+                if self.bytes[self.index..].starts_with(b"[[") {
+                    let end = find(&self.bytes[self.index..], b"]]").map_or(self.bytes.len(), |i| self.index + i + 2);
+                    self.index = end;
+                } else {
+                    while self.index < self.bytes.len() && self.bytes[self.index] != b'\n' {
+                        self.index += 1;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_blanks();
+        self.bytes.get(self.index).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        if self.peek() == Some(byte) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(format_err!("expected '{}' at {}", byte as char, self.index))
+        }
+    }
+
+    fn name(&mut self) -> &'a str {
+        let start = self.index;
+        while self.index < self.bytes.len() &&
+              (self.bytes[self.index].is_ascii_alphanumeric() || self.bytes[self.index] == b'_') {
+            self.index += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.index]).unwrap_or_default()
+    }
+
+    fn value(&mut self) -> Result<LuaValue, Error> {
+        match self.peek() {
+            Some(b'{') => self.table(),
+            Some(quote @ (b'"' | b'\'')) => self.string(quote).map(LuaValue::String),
+            Some(b'-' | b'.' | b'0'..=b'9') => self.number(),
+            Some(b) if b.is_ascii_alphabetic() => {
+                match self.name() {
+                    "nil" => Ok(LuaValue::Nil),
+                    "true" => Ok(LuaValue::Boolean(true)),
+                    "false" => Ok(LuaValue::Boolean(false)),
+                    name => Err(format_err!("unexpected name: {}", name)),
+                }
+            },
+            _ => Err(format_err!("unexpected input at {}", self.index)),
+        }
+    }
+
+    fn number(&mut self) -> Result<LuaValue, Error> {
+        let start = self.index;
+        while self.index < self.bytes.len() &&
+              matches!(self.bytes[self.index], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+            self.index += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.index])?;
+        Ok(LuaValue::Number(text.parse()?))
+    }
+
+    fn string(&mut self, quote: u8) -> Result<String, Error> {
+        self.index += 1;
+        let mut buf = Vec::new();
+        while let Some(&byte) = self.bytes.get(self.index) {
+            self.index += 1;
+            match byte {
+                b if b == quote => return Ok(String::from_utf8_lossy(&buf).into_owned()),
+                b'\\' => {
+                    let escaped = *self.bytes.get(self.index).ok_or_else(|| format_err!("unterminated string"))?;
+                    self.index += 1;
+                    match escaped {
+                        b'n' | b'\n' => buf.push(b'\n'),
+                        b't' => buf.push(b'\t'),
+                        b'r' => buf.push(b'\r'),
+                        b'0'..=b'9' => {
+                            let start = self.index - 1;
+                            while self.index < self.bytes.len() && self.index - start < 3 &&
+                                  self.bytes[self.index].is_ascii_digit() {
+                                self.index += 1;
+                            }
+                            let code: u32 = std::str::from_utf8(&self.bytes[start..self.index])?.parse()?;
+                            buf.push(code.min(255) as u8);
+                        },
+                        b => buf.push(b),
+                    }
+                },
+                b => buf.push(b),
+            }
+        }
+        bail!("unterminated string")
+    }
+
+    fn table(&mut self) -> Result<LuaValue, Error> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        let mut position = 1.0;
+        loop {
+            match self.peek() {
+                Some(b'}') => {
+                    self.index += 1;
+                    break;
+                },
+                Some(b'[') => {
+                    self.index += 1;
+                    let key = self.value()?;
+                    self.expect(b']')?;
+                    self.expect(b'=')?;
+                    entries.push((key, self.value()?));
+                },
+                Some(b) if b.is_ascii_alphabetic() || b == b'_' => {
+                    let start = self.index;
+                    let name = self.name().to_string();
+                    if self.peek() == Some(b'=') {
+                        self.index += 1;
+                        entries.push((LuaValue::String(name), self.value()?));
+                    } else {
+                        self.index = start;
+                        entries.push((LuaValue::Number(position), self.value()?));
+                        position += 1.0;
+                    }
+                },
+                Some(_) => {
+                    entries.push((LuaValue::Number(position), self.value()?));
+                    position += 1.0;
+                },
+                None => bail!("unterminated table"),
+            }
+            if matches!(self.peek(), Some(b',' | b';')) {
+                self.index += 1;
+            }
+        }
+        Ok(LuaValue::Table(entries))
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+// Parses the table returned by a metadata file.
+pub fn parse(text: &str) -> Result<LuaValue, Error> {
+    let mut parser = Parser { bytes: text.as_bytes(), index: 0 };
+    parser.skip_blanks();
+    if parser.bytes[parser.index..].starts_with(b"return") {
+        parser.index += 6;
+    }
+    parser.value()
+}
+
+// KOReader keeps the metadata of *Book.epub* in *Book.sdr/metadata.epub.lua*.
+pub fn metadata_path(path: &Path) -> Option<PathBuf> {
+    let dir = path.with_extension("sdr");
+    if !dir.is_dir() {
+        return None;
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let path = dir.join(format!("metadata.{}.lua", ext));
+    if path.exists() {
+        return Some(path);
+    }
+    fs::read_dir(&dir).ok()?
+      .filter_map(|entry| entry.ok())
+      .map(|entry| entry.path())
+      .find(|path| path.file_name().and_then(|n| n.to_str())
+                       .is_some_and(|n| n.starts_with("metadata.") && n.ends_with(".lua")))
+}
+
+fn datetime(value: Option<&LuaValue>) -> Option<DateTime<Local>> {
+    let text = value?.as_str()?;
+    let naive = NaiveDateTime::parse_from_str(text, DATETIME_FORMAT).ok()?;
+    Local.from_local_datetime(&naive).single()
+}
+
+fn text(value: Option<&LuaValue>) -> String {
+    value.and_then(LuaValue::as_str).unwrap_or_default().to_string()
+}
+
+// The page numbers start at one.
+fn page(value: Option<&LuaValue>) -> Option<usize> {
+    value.and_then(LuaValue::as_f64)
+         .filter(|n| *n >= 1.0)
+         .map(|n| n as usize - 1)
+}
+
+// Converts the highlights and bookmarks of the current (*annotations*) and
+// former (*highlight* and *bookmarks*) formats.
+pub fn foreign_annotations(metadata: &LuaValue) -> Vec<ForeignAnnotation> {
+    let mut result = Vec::new();
+
+    for (_, item) in metadata.get("annotations").map_or(&[][..], LuaValue::entries) {
+        let is_highlight = item.get("pos0").is_some() || item.get("drawer").is_some();
+        result.push(ForeignAnnotation {
+            kind: if is_highlight { ForeignKind::Highlight } else { ForeignKind::Bookmark },
+            text: if is_highlight { text(item.get("text")) } else { String::new() },
+            note: text(item.get("note")),
+            page: page(item.get("pageno")).or_else(|| page(item.get("page"))),
+            modified: datetime(item.get("datetime")),
+        });
+    }
+
+    if !result.is_empty() {
+        return result;
+    }
+
+    let bookmarks = metadata.get("bookmarks").map_or(&[][..], LuaValue::entries);
+
+    for (key, items) in metadata.get("highlight").map_or(&[][..], LuaValue::entries) {
+        for (_, item) in items.entries() {
+            // The notes of the highlights were stored in the matching bookmarks.
+            let note = item.get("pos0").and_then(|pos0| {
+                bookmarks.iter().find(|(_, b)| b.get("highlighted") == Some(&LuaValue::Boolean(true)) &&
+                                               b.get("pos0") == Some(pos0))
+            }).map(|(_, b)| text(b.get("text"))).unwrap_or_default();
+            result.push(ForeignAnnotation {
+                kind: ForeignKind::Highlight,
+                text: text(item.get("text")),
+                note,
+                page: page(Some(key)),
+                modified: datetime(item.get("datetime")),
+            });
+        }
+    }
+
+    for (_, item) in bookmarks {
+        if item.get("highlighted") == Some(&LuaValue::Boolean(true)) {
+            continue;
+        }
+        result.push(ForeignAnnotation {
+            kind: ForeignKind::Bookmark,
+            text: text(item.get("notes")),
+            note: String::new(),
+            page: page(item.get("page")),
+            modified: datetime(item.get("datetime")),
+        });
+    }
+
+    result
+}
+
+pub fn annotations(path: &Path) -> Result<Vec<ForeignAnnotation>, Error> {
+    let metadata_path = if let Some(path) = metadata_path(path) { path } else { return Ok(Vec::new()) };
+    let text = fs::read_to_string(metadata_path)?;
+    Ok(foreign_annotations(&parse(&text)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "-- we can read Lua syntax here!\nreturn {\n    [\"a\"] = { 1, 2.5, -3, },\n    \
+                    [\"b\"] = \"it's \\\"quoted\\\"\\\nand \\195\\169\",\n    c = true, [4] = nil;\n}\n";
+        let value = parse(text).unwrap();
+        assert_eq!(value.get("a"), Some(&LuaValue::Table(vec![(LuaValue::Number(1.0), LuaValue::Number(1.0)),
+                                                               (LuaValue::Number(2.0), LuaValue::Number(2.5)),
+                                                               (LuaValue::Number(3.0), LuaValue::Number(-3.0))])));
+        assert_eq!(value.get("b").and_then(LuaValue::as_str), Some("it's \"quoted\"\nand é"));
+        assert_eq!(value.get("c"), Some(&LuaValue::Boolean(true)));
+        assert!(parse("return { [\"a\"] = ").is_err());
+    }
+
+    #[test]
+    fn test_foreign_annotations() {
+        let current = parse(r#"return {
+            ["annotations"] = {
+                [1] = {
+                    ["datetime"] = "2024-01-05 20:11:04",
+                    ["drawer"] = "lighten",
+                    ["note"] = "Nice.",
+                    ["pageno"] = 12,
+                    ["pos0"] = "/body/DocFragment[3]/body/p[5]/text().0",
+                    ["pos1"] = "/body/DocFragment[3]/body/p[5]/text().9",
+                    ["text"] = "Some text",
+                },
+                [2] = {
+                    ["datetime"] = "2024-01-06 08:00:00",
+                    ["page"] = "/body/DocFragment[4]/body/p[1]/text().0",
+                    ["pageno"] = 30,
+                    ["text"] = "Page 30",
+                },
+            },
+        }"#).unwrap();
+        let annotations = foreign_annotations(&current);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].kind, ForeignKind::Highlight);
+        assert_eq!((annotations[0].text.as_str(), annotations[0].note.as_str(), annotations[0].page), ("Some text", "Nice.", Some(11)));
+        assert!(annotations[0].modified.is_some());
+        assert_eq!((annotations[1].kind, annotations[1].page, annotations[1].text.as_str()), (ForeignKind::Bookmark, Some(29), ""));
+
+        let former = parse(r#"return {
+            ["bookmarks"] = {
+                [1] = { ["highlighted"] = true, ["pos0"] = "x", ["text"] = "A note" },
+                [2] = { ["page"] = 7, ["notes"] = "Start of page 7" },
+            },
+            ["highlight"] = {
+                [3] = { [1] = { ["pos0"] = "x", ["text"] = "Highlighted" } },
+            },
+        }"#).unwrap();
+        let annotations = foreign_annotations(&former);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!((annotations[0].text.as_str(), annotations[0].note.as_str(), annotations[0].page), ("Highlighted", "A note", Some(2)));
+        assert_eq!((annotations[1].kind, annotations[1].text.as_str(), annotations[1].page), (ForeignKind::Bookmark, "Start of page 7", Some(6)));
+    }
+}
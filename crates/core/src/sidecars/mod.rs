@@ -0,0 +1,194 @@
+mod koreader;
+mod kobo;
+
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use fxhash::FxHashMap;
+use chrono::{DateTime, Local};
+use crate::document::{Document, Location, TextLocation, open};
+use crate::metadata::{Info, ReaderInfo, Annotation};
+
+pub use self::kobo::KOBO_DATABASE_PATH;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ForeignKind {
+    Highlight,
+    Bookmark,
+}
+
+// An annotation made with another reader.
+#[derive(Debug, Clone)]
+pub struct ForeignAnnotation {
+    pub kind: ForeignKind,
+    // The highlighted text, or the beginning of the bookmarked page.
+    pub text: String,
+    pub note: String,
+    // The index of the page, as laid out by the other reader.
+    pub page: Option<usize>,
+    pub modified: Option<DateTime<Local>>,
+}
+
+// Imports the highlights and bookmarks found in KOReader's sidecars and in Kobo's database.
+#[derive(Default)]
+pub struct SidecarImporter {
+    // Loaded on demand, by document path.
+    kobo: Option<FxHashMap<PathBuf, Vec<ForeignAnnotation>>>,
+}
+
+impl SidecarImporter {
+    pub fn import(&mut self, path: &Path, info: &mut Info) {
+        let mut items = koreader::annotations(path)
+                                 .map_err(|e| eprintln!("Can't read the KOReader metadata of {}: {:#}.", path.display(), e))
+                                 .unwrap_or_default();
+        let kobo = self.kobo.get_or_insert_with(|| {
+            if !Path::new(KOBO_DATABASE_PATH).exists() {
+                return FxHashMap::default();
+            }
+            kobo::annotations(KOBO_DATABASE_PATH)
+                 .map_err(|e| eprintln!("Can't read the Kobo database: {:#}.", e))
+                 .unwrap_or_default()
+        });
+        if let Some(more) = kobo.get(path) {
+            items.extend_from_slice(more);
+        }
+
+        if items.is_empty() {
+            return;
+        }
+
+        let mut doc = if let Some(doc) = open(path) { doc } else { return };
+        let mut reader = info.reader.take().unwrap_or_default();
+        let count = merge(doc.as_mut(), &items, &mut reader);
+
+        if count > 0 {
+            println!("Imported {} annotations for {}.", count, path.display());
+            reader.pages_count = doc.pages_count();
+            if let Some(opened) = items.iter().filter_map(|item| item.modified).max() {
+                reader.opened = opened;
+            }
+            info.reader = Some(reader);
+        }
+    }
+}
+
+// The text of a range of pages, and the locations of its words.
+struct TextIndex {
+    text: String,
+    // Maps the offsets of the words in the text to their page and location.
+    words: BTreeMap<usize, (usize, TextLocation)>,
+}
+
+impl TextIndex {
+    fn new(doc: &mut dyn Document, first: usize, last: Option<usize>) -> TextIndex {
+        let mut text = String::new();
+        let mut words = BTreeMap::new();
+        let mut loc = Location::Exact(first);
+
+        while let Some(location) = doc.resolve_location(loc) {
+            if last.is_some_and(|last| location > last) {
+                break;
+            }
+            if let Some((page_words, _)) = doc.words(Location::Exact(location)) {
+                for word in page_words {
+                    if text.ends_with('\u{00AD}') {
+                        text.pop();
+                    } else if !text.ends_with('-') && !text.is_empty() {
+                        text.push(' ');
+                    }
+                    words.insert(text.len(), (location, word.location));
+                    text += &word.text;
+                }
+            }
+            loc = Location::Next(location);
+        }
+
+        TextIndex { text, words }
+    }
+
+    // Returns the page and the selection of the first occurrence of the given text.
+    fn find(&self, needle: &str) -> Option<(usize, [TextLocation; 2])> {
+        let needle = needle.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if needle.is_empty() {
+            return None;
+        }
+        let start = self.text.find(&needle)?;
+        let end = start + needle.len();
+        let (_, &(page, first)) = self.words.range(..= start).next_back()?;
+        let (_, &(_, last)) = self.words.range(.. end).next_back()?;
+        Some((page, [first, last]))
+    }
+}
+
+// Adds the items that can be located in the document and returns their number.
+fn merge(doc: &mut dyn Document, items: &[ForeignAnnotation], reader: &mut ReaderInfo) -> usize {
+    let reflowable = doc.is_reflowable();
+    let pages_count = doc.pages_count();
+    // The whole document, only built when needed.
+    let mut index: Option<TextIndex> = None;
+    let mut count = 0;
+
+    for item in items {
+        match item.kind {
+            ForeignKind::Bookmark => {
+                let location = if !reflowable {
+                    item.page.filter(|&page| page < pages_count)
+                } else if !item.text.is_empty() {
+                    index.get_or_insert_with(|| TextIndex::new(doc, 0, None))
+                         .find(&item.text).map(|(page, _)| page)
+                } else {
+                    None
+                };
+                if let Some(location) = location {
+                    if reader.bookmarks.insert(location) {
+                        count += 1;
+                    }
+                }
+            },
+            ForeignKind::Highlight => {
+                // The page numbers of the fixed layout documents are reliable hints.
+                let mut found = None;
+                if let Some(page) = item.page.filter(|_| !reflowable) {
+                    found = TextIndex::new(doc, page.saturating_sub(1), Some(page + 1)).find(&item.text);
+                }
+                if found.is_none() {
+                    found = index.get_or_insert_with(|| TextIndex::new(doc, 0, None))
+                                 .find(&item.text);
+                }
+                if let Some((_, selection)) = found {
+                    if reader.annotations.iter().all(|a| a.selection != selection) {
+                        reader.annotations.push(Annotation {
+                            text: item.text.clone(),
+                            note: item.note.clone(),
+                            selection,
+                            modified: item.modified.unwrap_or_else(Local::now),
+                        });
+                        count += 1;
+                    }
+                }
+            },
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_index() {
+        let mut index = TextIndex { text: String::new(), words: BTreeMap::new() };
+        for (i, (page, word)) in [(0, "The"), (0, "quick"), (0, "brown"), (1, "fox"), (1, "jumps")].iter().enumerate() {
+            if !index.text.is_empty() {
+                index.text.push(' ');
+            }
+            index.words.insert(index.text.len(), (*page, TextLocation::Dynamic(10 * i)));
+            index.text += word;
+        }
+        assert_eq!(index.find("brown\n fox"), Some((0, [TextLocation::Dynamic(20), TextLocation::Dynamic(30)])));
+        assert_eq!(index.find("jumps"), Some((1, [TextLocation::Dynamic(40), TextLocation::Dynamic(40)])));
+        assert_eq!(index.find("lazy dog"), None);
+        assert_eq!(index.find(" "), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf, Component};
+use chrono::Local;
+use walkdir::WalkDir;
+use zip::{ZipArchive, ZipWriter};
+use zip::write::FileOptions;
+use anyhow::{Error, Context};
+use crate::library::{Library, READING_STATES_DIRNAME, PROFILES_DIRNAME};
+
+// The snapshots of the reading states of a library, one per day, named after their date.
+pub const SNAPSHOTS_DIRNAME: &str = ".snapshots";
+const SNAPSHOT_EXTENSION: &str = "zip";
+
+// Only the reading states directories can be written by a restoration.
+fn is_reading_state(name: &Path) -> bool {
+    let mut components = name.components();
+    let is_safe = name.components().all(|c| matches!(c, Component::Normal(_)));
+    let is_json = name.extension().and_then(|ext| ext.to_str()) == Some("json");
+    let depth = name.components().count();
+    is_safe && is_json && match components.next().and_then(|c| c.as_os_str().to_str()) {
+        Some(READING_STATES_DIRNAME) => depth == 2,
+        Some(PROFILES_DIRNAME) => depth == 3,
+        _ => false,
+    }
+}
+
+// The snapshots of the given library, latest first.
+pub fn list(library: &Library) -> Vec<PathBuf> {
+    let mut paths = fs::read_dir(library.home.join(SNAPSHOTS_DIRNAME)).into_iter().flatten()
+                       .filter_map(|entry| entry.ok())
+                       .map(|entry| entry.path())
+                       .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(SNAPSHOT_EXTENSION))
+                       .collect::<Vec<PathBuf>>();
+    paths.sort_by(|a, b| b.cmp(a));
+    paths
+}
+
+// Saves the reading states of every profile, unless today's snapshot already exists,
+// and removes the snapshots beyond the given number.
+// Returns the path of the new snapshot.
+pub fn take(library: &Library, keep: usize) -> Result<Option<PathBuf>, Error> {
+    let dir = library.home.join(SNAPSHOTS_DIRNAME);
+    let path = dir.join(format!("{}.{}", Local::now().format("%Y-%m-%d"), SNAPSHOT_EXTENSION));
+
+    if path.exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(&dir)
+       .with_context(|| format!("can't create directory {}", dir.display()))?;
+
+    // An interrupted snapshot must not be mistaken for a complete one.
+    let partial = path.with_extension("part");
+    let file = File::create(&partial)
+                    .with_context(|| format!("can't create file {}", partial.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default();
+
+    for top in &[READING_STATES_DIRNAME, PROFILES_DIRNAME] {
+        for entry in WalkDir::new(library.home.join(top)).into_iter()
+                            .filter_map(|entry| entry.ok())
+                            .filter(|entry| entry.file_type().is_file()) {
+            let name = entry.path().strip_prefix(&library.home)?;
+            if !is_reading_state(name) {
+                continue;
+            }
+            let content = fs::read(entry.path())
+                             .with_context(|| format!("can't read file {}", entry.path().display()))?;
+            zip.start_file(name.to_string_lossy(), options)?;
+            zip.write_all(&content)?;
+        }
+    }
+
+    zip.finish()?;
+    fs::rename(&partial, &path)?;
+
+    for old in list(library).into_iter().skip(keep.max(1)) {
+        fs::remove_file(&old).map_err(|e| eprintln!("Can't remove snapshot {}: {:#}.", old.display(), e)).ok();
+    }
+
+    Ok(Some(path))
+}
+
+// Writes back the reading states of the given snapshot.
+// The reading states created since are kept.
+// Returns the number of reading states restored.
+pub fn restore<P: AsRef<Path>>(library: &Library, path: P) -> Result<usize, Error> {
+    let file = File::open(path.as_ref())
+                    .with_context(|| format!("can't open file {}", path.as_ref().display()))?;
+    let mut zip = ZipArchive::new(file)?;
+    let mut count = 0;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let name = PathBuf::from(entry.name());
+        if entry.is_dir() || !is_reading_state(&name) {
+            continue;
+        }
+        let target = library.home.join(&name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(&target)
+                            .with_context(|| format!("can't create file {}", target.display()))?;
+        io::copy(&mut entry, &mut file)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_state_names() {
+        assert!(is_reading_state(Path::new(".reading-states/0123456789ABCDEF.json")));
+        assert!(is_reading_state(Path::new(".profiles/Kids/0123456789ABCDEF.json")));
+        assert!(!is_reading_state(Path::new(".profiles/0123456789ABCDEF.json")));
+        assert!(!is_reading_state(Path::new(".reading-states/../.metadata.json")));
+        assert!(!is_reading_state(Path::new("/etc/passwd.json")));
+        assert!(!is_reading_state(Path::new("Books/a.json")));
+    }
+}
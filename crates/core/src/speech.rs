@@ -0,0 +1,171 @@
+use std::thread;
+use std::ops::Range;
+use std::time::Duration;
+use std::process::{Command, Child, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::settings::{SpeechSettings, Voice};
+use crate::view::{Event, Hub};
+
+// The longest sentences sent to the speech program, in words.
+const MAX_SENTENCE_WORDS: usize = 40;
+// How often the speech program is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// The primary subtag of a language tag, e.g. `pt` for `pt-BR`.
+pub fn language_key(language: &str) -> String {
+    language.split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+}
+
+// The voice given for the language, or the default one.
+pub fn voice(settings: &SpeechSettings, language: &str) -> Voice {
+    settings.languages.get(&language_key(language))
+            .cloned()
+            .unwrap_or_default()
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', '’', '”', '»', ')', ']'])
+        .ends_with(['.', '!', '?', '…', '。', '！', '？'])
+}
+
+// Splits the words into sentences, cutting the longest ones.
+pub fn sentences(words: &[&str]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for (index, word) in words.iter().enumerate() {
+        if ends_sentence(word) || index + 1 - start >= MAX_SENTENCE_WORDS {
+            result.push(start..index + 1);
+            start = index + 1;
+        }
+    }
+    if start < words.len() {
+        result.push(start..words.len());
+    }
+    result
+}
+
+// Estimates how long each word lasts when spoken: the time of a sentence is
+// shared according to the lengths of its words, and punctuation adds pauses.
+pub fn word_durations(words: &[&str], words_per_minute: f32, speed: f32) -> Vec<Duration> {
+    let average = 60.0 / (words_per_minute * speed).max(1.0);
+    let weights: Vec<f32> = words.iter().map(|word| {
+        let letters = word.chars().filter(|c| c.is_alphanumeric()).count() as f32;
+        let pause = if ends_sentence(word) { 3.0 } else if word.ends_with([',', ';', ':']) { 1.5 } else { 0.0 };
+        letters + 2.0 + pause
+    }).collect();
+    let total: f32 = weights.iter().sum();
+    weights.iter()
+           .map(|weight| Duration::from_secs_f32(average * words.len() as f32 * weight / total.max(1.0)))
+           .collect()
+}
+
+// Runs the speech program, one utterance at a time.
+#[derive(Default)]
+pub struct Speaker {
+    token: Arc<AtomicUsize>,
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl Speaker {
+    // Speaks the words in the background. `Event::SpeechWord` is sent when a word is
+    // expected to be spoken, and `Event::SpeechDone` once the program exits.
+    // Returns the token of the utterance held by these events.
+    pub fn speak(&mut self, words: Vec<String>, language: &str, settings: &SpeechSettings, hub: &Hub) -> usize {
+        self.stop();
+        let token = self.token.load(Ordering::Relaxed);
+        let voice = voice(settings, language);
+        let text = words.join(" ");
+        let child = Command::new(&settings.program)
+                            .arg(voice.name.as_deref().unwrap_or(&language_key(language)))
+                            .arg(format!("{:.2}", voice.speed))
+                            .arg(format!("{:.2}", voice.pitch))
+                            .arg(&text)
+                            .stdout(Stdio::null())
+                            .spawn();
+        let child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Can't spawn {}: {:#}.", settings.program.display(), e);
+                hub.send(Event::Notify("Can't read aloud.".to_string())).ok();
+                return token;
+            },
+        };
+        *self.child.lock().unwrap() = Some(child);
+
+        let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+        let durations = word_durations(&refs, settings.words_per_minute, voice.speed);
+        let current = self.token.clone();
+        let child = self.child.clone();
+        let hub = hub.clone();
+
+        thread::spawn(move || {
+            for (index, duration) in durations.into_iter().enumerate() {
+                if current.load(Ordering::Relaxed) != token {
+                    return;
+                }
+                hub.send(Event::SpeechWord(token, index)).ok();
+                thread::sleep(duration);
+            }
+            loop {
+                if current.load(Ordering::Relaxed) != token {
+                    return;
+                }
+                let status = child.lock().unwrap().as_mut().map(|child| child.try_wait());
+                match status {
+                    Some(Ok(None)) => thread::sleep(POLL_INTERVAL),
+                    Some(Ok(Some(status))) if !status.success() => {
+                        eprintln!("The speech program exited with {}.", status);
+                        break;
+                    },
+                    Some(Err(e)) => {
+                        eprintln!("Can't wait for the speech program: {:#}.", e);
+                        break;
+                    },
+                    _ => break,
+                }
+            }
+            child.lock().unwrap().take();
+            hub.send(Event::SpeechDone(token)).ok();
+        });
+
+        token
+    }
+
+    // Interrupts the current utterance: its pending events won't be sent.
+    pub fn stop(&mut self) {
+        self.token.fetch_add(1, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        let words = ["Il", "pleut.", "«", "Viens-tu", "?", "»", "Non!”", "Bon"];
+        assert_eq!(sentences(&words), vec![0..2, 2..5, 5..7, 7..8]);
+        let words = vec!["a"; 2 * MAX_SENTENCE_WORDS + 1];
+        assert_eq!(sentences(&words).len(), 3);
+    }
+
+    #[test]
+    fn test_word_durations() {
+        let words = ["I", "understand,", "thanks."];
+        let durations = word_durations(&words, 120.0, 1.0);
+        let total: f32 = durations.iter().map(Duration::as_secs_f32).sum();
+        assert!((total - 1.5).abs() < 0.01);
+        assert!(durations[0] < durations[1] && durations[2] < durations[1]);
+        let slower = word_durations(&words, 120.0, 0.5);
+        assert!(slower[0] > durations[0]);
+    }
+}
@@ -0,0 +1,351 @@
+use std::fs::File;
+use std::path::Path;
+use std::os::unix::fs::FileExt;
+use anyhow::{Error, format_err, bail};
+
+// A minimal read-only access to the tables of SQLite databases, as described in
+// https://www.sqlite.org/fileformat.html. Only the UTF-8 databases are supported.
+
+const HEADER_MAGIC: &[u8] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+const TABLE_LEAF_PAGE: u8 = 0x0D;
+const TABLE_INTERIOR_PAGE: u8 = 0x05;
+// Guards against the cycles of corrupted databases.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Integer(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+pub struct Database {
+    file: File,
+    page_size: usize,
+    usable_size: usize,
+}
+
+impl Database {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Database, Error> {
+        let file = File::open(path)?;
+        let mut header = [0; HEADER_SIZE];
+        file.read_exact_at(&mut header, 0)?;
+        if &header[..16] != HEADER_MAGIC {
+            bail!("not an SQLite database");
+        }
+        let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+            1 => 65536,
+            n => n as usize,
+        };
+        let usable_size = page_size - header[20] as usize;
+        let encoding = u32::from_be_bytes([header[56], header[57], header[58], header[59]]);
+        if encoding > 1 {
+            bail!("unsupported text encoding: {}", encoding);
+        }
+        Ok(Database { file, page_size, usable_size })
+    }
+
+    fn page(&self, number: u32) -> Result<Vec<u8>, Error> {
+        if number == 0 {
+            bail!("invalid page number");
+        }
+        let mut page = vec![0; self.page_size];
+        self.file.read_exact_at(&mut page, (number as u64 - 1) * self.page_size as u64)?;
+        Ok(page)
+    }
+
+    // Returns the column names and the rows of the given table.
+    pub fn table(&self, name: &str) -> Result<(Vec<String>, Vec<Vec<Value>>), Error> {
+        let mut schema = Vec::new();
+        self.scan(1, 0, &mut schema)?;
+        let (root_page, sql) = schema.iter().find_map(|row| {
+            match row.as_slice() {
+                [Value::Text(kind), Value::Text(table_name), _, Value::Integer(root_page), Value::Text(sql), ..]
+                    if kind == "table" && table_name.eq_ignore_ascii_case(name) => Some((*root_page, sql.clone())),
+                _ => None,
+            }
+        }).ok_or_else(|| format_err!("table not found: {}", name))?;
+        let columns = column_names(&sql);
+        let mut rows = Vec::new();
+        self.scan(root_page as u32, 0, &mut rows)?;
+        Ok((columns, rows))
+    }
+
+    // Collects the records of the table B-tree rooted at the given page.
+    fn scan(&self, number: u32, depth: usize, rows: &mut Vec<Vec<Value>>) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            bail!("the B-tree is too deep");
+        }
+        let page = self.page(number)?;
+        let start = if number == 1 { HEADER_SIZE } else { 0 };
+        let header = page.get(start..start + 12).ok_or_else(|| format_err!("truncated page"))?;
+        let kind = header[0];
+        let cells_count = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let header_size = if kind == TABLE_INTERIOR_PAGE { 12 } else { 8 };
+
+        for i in 0..cells_count {
+            let at = start + header_size + 2 * i;
+            let offset = u16::from_be_bytes([page[at], page[at + 1]]) as usize;
+            match kind {
+                TABLE_LEAF_PAGE => {
+                    let (payload_size, n) = read_varint(&page[offset..]);
+                    let (_, m) = read_varint(&page[offset + n..]);
+                    let payload = self.payload(&page, offset + n + m, payload_size as usize)?;
+                    rows.push(parse_record(&payload)?);
+                },
+                TABLE_INTERIOR_PAGE => {
+                    let child = u32::from_be_bytes(page[offset..offset + 4].try_into()?);
+                    self.scan(child, depth + 1, rows)?;
+                },
+                _ => bail!("unexpected page type: {}", kind),
+            }
+        }
+
+        if kind == TABLE_INTERIOR_PAGE {
+            let right_most = u32::from_be_bytes(header[8..12].try_into()?);
+            self.scan(right_most, depth + 1, rows)?;
+        }
+
+        Ok(())
+    }
+
+    // Reads a payload, which might spill onto overflow pages.
+    fn payload(&self, page: &[u8], offset: usize, size: usize) -> Result<Vec<u8>, Error> {
+        let usable_size = self.usable_size;
+        let max_local = usable_size - 35;
+        let local_size = if size <= max_local {
+            size
+        } else {
+            let min_local = (usable_size - 12) * 32 / 255 - 23;
+            let k = min_local + (size - min_local) % (usable_size - 4);
+            if k <= max_local { k } else { min_local }
+        };
+        let mut payload = page.get(offset..offset + local_size)
+                              .ok_or_else(|| format_err!("truncated cell"))?
+                              .to_vec();
+        if local_size < size {
+            let at = offset + local_size;
+            let mut next = u32::from_be_bytes(page[at..at + 4].try_into()?);
+            while payload.len() < size && next != 0 {
+                let overflow = self.page(next)?;
+                next = u32::from_be_bytes(overflow[..4].try_into()?);
+                let len = (size - payload.len()).min(usable_size - 4);
+                payload.extend_from_slice(&overflow[4..4 + len]);
+            }
+            if payload.len() < size {
+                bail!("truncated overflow chain");
+            }
+        }
+        Ok(payload)
+    }
+}
+
+// Returns the value of a variable-length integer and its length in bytes.
+fn read_varint(bytes: &[u8]) -> (u64, usize) {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(9) {
+        if i == 8 {
+            return ((value << 8) | byte as u64, 9);
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+    }
+    (value, bytes.len().min(9))
+}
+
+fn parse_record(payload: &[u8]) -> Result<Vec<Value>, Error> {
+    let (header_size, mut n) = read_varint(payload);
+    let header_size = header_size as usize;
+    let mut body = header_size;
+    let mut values = Vec::new();
+
+    while n < header_size {
+        let (serial_type, m) = read_varint(&payload[n..]);
+        n += m;
+        let size = match serial_type {
+            0 | 8 | 9 => 0,
+            1..=4 => serial_type as usize,
+            5 => 6,
+            6 | 7 => 8,
+            n if n >= 12 => (n as usize - 12) / 2,
+            _ => bail!("invalid serial type: {}", serial_type),
+        };
+        let bytes = payload.get(body..body + size)
+                           .ok_or_else(|| format_err!("truncated record"))?;
+        body += size;
+        let value = match serial_type {
+            0 => Value::Null,
+            8 => Value::Integer(0),
+            9 => Value::Integer(1),
+            1..=6 => {
+                // Sign-extend the big-endian integer.
+                let mut value = if bytes[0] & 0x80 != 0 { -1i64 } else { 0 };
+                for &byte in bytes {
+                    value = (value << 8) | byte as i64;
+                }
+                Value::Integer(value)
+            },
+            7 => Value::Real(f64::from_be_bytes(bytes.try_into()?)),
+            n if n % 2 == 0 => Value::Blob(bytes.to_vec()),
+            _ => Value::Text(String::from_utf8_lossy(bytes).into_owned()),
+        };
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+// Extracts the column names from a `CREATE TABLE` statement.
+fn column_names(sql: &str) -> Vec<String> {
+    let (start, end) = match (sql.find('('), sql.rfind(')')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return Vec::new(),
+    };
+    let mut columns = Vec::new();
+    let mut depth = 0;
+    let mut definition = String::new();
+    for c in sql[start + 1..end].chars().chain(Some(',')) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                let name = definition.split_whitespace().next().unwrap_or_default()
+                                     .trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+                let is_constraint = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"].iter()
+                                                                                         .any(|k| name.eq_ignore_ascii_case(k));
+                if !name.is_empty() && !is_constraint {
+                    columns.push(name.to_string());
+                }
+                definition.clear();
+                continue;
+            },
+            _ => (),
+        }
+        definition.push(c);
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, 0x80 | (value & 0x7F) as u8);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    fn record(values: &[Value]) -> Vec<u8> {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for value in values {
+            match value {
+                Value::Null => header.push(0),
+                Value::Integer(n) => {
+                    header.push(6);
+                    body.extend_from_slice(&n.to_be_bytes());
+                },
+                Value::Real(x) => {
+                    header.push(7);
+                    body.extend_from_slice(&x.to_be_bytes());
+                },
+                Value::Text(s) => {
+                    header.extend(varint(13 + 2 * s.len() as u64));
+                    body.extend_from_slice(s.as_bytes());
+                },
+                Value::Blob(b) => {
+                    header.extend(varint(12 + 2 * b.len() as u64));
+                    body.extend_from_slice(b);
+                },
+            }
+        }
+        let mut payload = varint(header.len() as u64 + 1);
+        payload.extend(header);
+        payload.extend(body);
+        payload
+    }
+
+    // A table leaf page holding the given records.
+    fn leaf_page(page_size: usize, start: usize, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut page = vec![0; page_size];
+        page[start] = TABLE_LEAF_PAGE;
+        page[start + 3..start + 5].copy_from_slice(&(records.len() as u16).to_be_bytes());
+        let mut end = page_size;
+        for (i, payload) in records.iter().enumerate() {
+            let mut cell = varint(payload.len() as u64);
+            cell.extend(varint(i as u64 + 1));
+            cell.extend_from_slice(payload);
+            end -= cell.len();
+            page[end..end + cell.len()].copy_from_slice(&cell);
+            let at = start + 8 + 2 * i;
+            page[at..at + 2].copy_from_slice(&(end as u16).to_be_bytes());
+        }
+        page
+    }
+
+    #[test]
+    fn test_varint() {
+        assert_eq!(read_varint(&[0x05]), (5, 1));
+        assert_eq!(read_varint(&[0x81, 0x00]), (128, 2));
+        assert_eq!(read_varint(&varint(1 << 40)), (1 << 40, 6));
+    }
+
+    #[test]
+    fn test_column_names() {
+        assert_eq!(column_names("CREATE TABLE Bookmark (BookmarkID TEXT NOT NULL, \"Text\" TEXT, \
+                                 Version NUMERIC(3, 1), PRIMARY KEY (BookmarkID))"),
+                   vec!["BookmarkID", "Text", "Version"]);
+    }
+
+    #[test]
+    fn test_table() {
+        let page_size = 512;
+        let sql = "CREATE TABLE notes (id INTEGER, text TEXT, score REAL)";
+        let master = record(&[Value::Text("table".to_string()), Value::Text("notes".to_string()),
+                              Value::Text("notes".to_string()), Value::Integer(2), Value::Text(sql.to_string())]);
+        let mut first = leaf_page(page_size, HEADER_SIZE, &[master]);
+        first[..16].copy_from_slice(HEADER_MAGIC);
+        first[16..18].copy_from_slice(&(page_size as u16).to_be_bytes());
+        first[56..60].copy_from_slice(&1u32.to_be_bytes());
+        let rows = vec![vec![Value::Integer(-3), Value::Text("Première".to_string()), Value::Real(0.5)],
+                        vec![Value::Integer(70000), Value::Null, Value::Blob(vec![1, 2])]];
+        let second = leaf_page(page_size, 0, &rows.iter().map(|r| record(r)).collect::<Vec<Vec<u8>>>());
+
+        let path = std::env::temp_dir().join(format!("plato-test-{}.sqlite", std::process::id()));
+        fs::write(&path, [first, second].concat()).unwrap();
+        let db = Database::open(&path).unwrap();
+        let (columns, table) = db.table("Notes").unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(columns, vec!["id", "text", "score"]);
+        assert_eq!(table, rows);
+        assert!(db.table("missing").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::document::{Document, Location};
+use crate::metadata::{DocumentStatistics, ReaderInfo, ReadingSpeed};
+
+// The speed assumed until the user's is measured, in words per minute.
+pub const DEFAULT_WORDS_PER_MINUTE: f32 = 250.0;
+// The reading time required before the measured speed is trusted, in seconds.
+const MIN_MEASURED_SECONDS: u64 = 10 * 60;
+
+// Counts the words and the images of every page.
+pub fn compute(doc: &mut dyn Document) -> DocumentStatistics {
+    let mut statistics = DocumentStatistics {
+        pages: doc.pages_count(),
+        .. Default::default()
+    };
+    let mut loc = Location::Exact(0);
+    while let Some(location) = doc.resolve_location(loc) {
+        if let Some((words, _)) = doc.words(Location::Exact(location)) {
+            statistics.words += words.len();
+        }
+        if let Some((images, _)) = doc.images(Location::Exact(location)) {
+            statistics.images += images.len();
+        }
+        loc = Location::Next(location);
+    }
+    statistics
+}
+
+// The speed measured over all the given books, if enough was read.
+pub fn words_per_minute<'a, I>(readers: I) -> Option<f32> where I: Iterator<Item=&'a ReaderInfo> {
+    let total = readers.filter_map(|r| r.reading_speed)
+                       .fold(ReadingSpeed::default(), |total, speed| ReadingSpeed {
+                           words: total.words + speed.words,
+                           seconds: total.seconds + speed.seconds,
+                       });
+    if total.seconds < MIN_MEASURED_SECONDS {
+        return None;
+    }
+    Some(60.0 * total.words as f32 / total.seconds as f32)
+}
+
+pub fn format_duration(minutes: f32) -> String {
+    let minutes = minutes.round() as u64;
+    if minutes < 1 {
+        "Less than a minute".to_string()
+    } else if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {:02} min", minutes / 60, minutes % 60)
+    }
+}
+
+// The rows of the statistics panel.
+pub fn summary(statistics: &DocumentStatistics, progress: f32, words_per_minute: Option<f32>) -> Vec<(String, String)> {
+    let speed = words_per_minute.unwrap_or(DEFAULT_WORDS_PER_MINUTE).max(1.0);
+    let minutes = statistics.words as f32 / speed;
+    let mut rows = vec![("Words".to_string(), statistics.words.to_string()),
+                        ("Pages".to_string(), statistics.pages.to_string()),
+                        ("Images".to_string(), statistics.images.to_string()),
+                        ("Reading Time".to_string(), format_duration(minutes))];
+    if progress > 0.0 && progress < 1.0 {
+        rows.push(("Time Left".to_string(), format_duration((1.0 - progress) * minutes)));
+    }
+    let speed = match words_per_minute {
+        Some(wpm) => format!("{:.0} words/min", wpm),
+        None => format!("{:.0} words/min (not measured yet)", speed),
+    };
+    rows.push(("Speed".to_string(), speed));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.3), "Less than a minute");
+        assert_eq!(format_duration(42.2), "42 min");
+        assert_eq!(format_duration(125.0), "2 h 05 min");
+    }
+
+    #[test]
+    fn test_words_per_minute() {
+        let mut a = ReaderInfo::default();
+        let mut b = ReaderInfo::default();
+        a.reading_speed = Some(ReadingSpeed { words: 1000, seconds: 200 });
+        assert_eq!(words_per_minute([&a, &b].into_iter()), None);
+        b.reading_speed = Some(ReadingSpeed { words: 2000, seconds: 520 });
+        assert_eq!(words_per_minute([&a, &b].into_iter()), Some(250.0));
+        let statistics = DocumentStatistics { words: 25_000, pages: 100, images: 3 };
+        let rows = summary(&statistics, 0.5, Some(250.0));
+        assert_eq!(rows[3].1, "1 h 40 min");
+        assert_eq!(rows[4].1, "50 min");
+    }
+}
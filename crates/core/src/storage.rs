@@ -0,0 +1,265 @@
+use std::fs;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use fxhash::{FxHashMap, FxHashSet};
+use nix::sys::statvfs;
+use walkdir::WalkDir;
+use crate::library::{THUMBNAIL_PREVIEWS_DIRNAME, LAYOUT_CACHES_DIRNAME, OCR_CACHES_DIRNAME, READING_STATES_DIRNAME};
+use crate::view::home::TRASH_DIRNAME;
+use crate::document::HumanSize;
+
+pub const LOG_FILENAMES: [&str; 2] = ["info.log", "archive.log"];
+// The number of biggest files remembered.
+const BIGGEST_COUNT: usize = 32;
+// Below these amounts, the imports and the downloads are refused, and the sketch saves are warned about.
+const MIN_FREE_SPACE: u64 = 32 * 1024 * 1024;
+const MIN_AVAILABLE_MEMORY: u64 = 16 * 1024 * 1024;
+const MEMINFO_PATH: &str = "/proc/meminfo";
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Category {
+    // The documents of the given kind.
+    Books(String),
+    Sketches,
+    Thumbnails,
+    LayoutCaches,
+    OcrCaches,
+    ReadingStates,
+    Trash,
+    Logs,
+    Other,
+}
+
+impl Category {
+    pub fn label(&self) -> String {
+        match self {
+            Category::Books(kind) => kind.to_uppercase(),
+            Category::Sketches => "Sketches".to_string(),
+            Category::Thumbnails => "Thumbnails".to_string(),
+            Category::LayoutCaches => "Layout Caches".to_string(),
+            Category::OcrCaches => "OCR Caches".to_string(),
+            Category::ReadingStates => "Reading States".to_string(),
+            Category::Trash => "Trash".to_string(),
+            Category::Logs => "Logs".to_string(),
+            Category::Other => "Other Files".to_string(),
+        }
+    }
+
+    // Whether the files can be removed without losing anything but time.
+    pub fn is_cache(&self) -> bool {
+        matches!(self, Category::Thumbnails | Category::LayoutCaches | Category::OcrCaches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryUsage {
+    pub category: Category,
+    pub size: u64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StorageUsage {
+    // From the biggest to the smallest.
+    pub categories: Vec<CategoryUsage>,
+    // The biggest documents and sketches, from the biggest, relatively to the library's home.
+    pub biggest: Vec<(PathBuf, u64)>,
+    pub free: Option<u64>,
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Shortage {
+    // The free space, in bytes.
+    Space(u64),
+    // The available memory, in bytes.
+    Memory(u64),
+}
+
+impl Shortage {
+    pub fn message(&self) -> String {
+        match self {
+            Shortage::Space(free) => format!("Only {} left on the storage.", free.human_size()),
+            Shortage::Memory(available) => format!("Only {} of memory available.", available.human_size()),
+        }
+    }
+}
+
+// The block counts and sizes are only 32 bits wide on the 32-bit targets.
+fn blocks_size<C: Into<u64>, S: Into<u64>>(count: C, size: S) -> u64 {
+    count.into() * size.into()
+}
+
+pub fn free_space(path: &Path) -> Option<u64> {
+    statvfs::statvfs(path).ok()
+            .map(|info| blocks_size(info.blocks_available(), info.fragment_size()))
+}
+
+// Extracts the available memory, in bytes, from the content of *meminfo*.
+fn parse_meminfo(text: &str) -> Option<u64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))
+        .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+pub fn available_memory() -> Option<u64> {
+    fs::read_to_string(MEMINFO_PATH).ok()
+       .and_then(|text| parse_meminfo(&text))
+}
+
+// Checks that there's enough space on the file system of the given path, and enough memory,
+// to write a few files.
+pub fn check(path: &Path) -> Option<Shortage> {
+    if let Some(free) = free_space(path).filter(|&free| free < MIN_FREE_SPACE) {
+        return Some(Shortage::Space(free));
+    }
+    available_memory().filter(|&available| available < MIN_AVAILABLE_MEMORY)
+                      .map(Shortage::Memory)
+}
+
+// The given directory within the configured cache directory, created if needed.
+// Nothing is returned when the cache directory isn't set or is missing, e.g. when
+// the card holding it isn't inserted: the caches then stay in their usual place.
+pub fn relocated_caches(cache_path: Option<&Path>, name: &str) -> Option<PathBuf> {
+    let cache_path = cache_path?;
+    if !cache_path.is_dir() {
+        eprintln!("Can't find the cache directory {}.", cache_path.display());
+        return None;
+    }
+    let path = cache_path.join(name);
+    fs::create_dir_all(&path)
+       .map_err(|e| eprintln!("Can't create {}: {:#}.", path.display(), e))
+       .ok()?;
+    Some(path)
+}
+
+// Removes the caches of a library, which are rebuilt on demand.
+// Returns the number of bytes freed.
+pub fn clear_caches(cache_home: &Path) -> u64 {
+    let mut freed = 0;
+    for name in [THUMBNAIL_PREVIEWS_DIRNAME, LAYOUT_CACHES_DIRNAME, OCR_CACHES_DIRNAME] {
+        let path = cache_home.join(name);
+        let entries = match fs::read_dir(&path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Can't read {}: {:#}.", path.display(), e);
+                continue;
+            },
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let size = entry.metadata().map_or(0, |md| md.len());
+            if fs::remove_file(entry.path()).is_ok() {
+                freed += size;
+            }
+        }
+    }
+    freed
+}
+
+// Sorts a path, relative to the library's home, into its category.
+pub fn categorize(path: &Path, sketches: &Path, book_kinds: &FxHashSet<String>) -> Category {
+    let first = path.components().next().map(|c| c.as_os_str());
+    match first.and_then(OsStr::to_str) {
+        Some(THUMBNAIL_PREVIEWS_DIRNAME) => Category::Thumbnails,
+        Some(LAYOUT_CACHES_DIRNAME) => Category::LayoutCaches,
+        Some(OCR_CACHES_DIRNAME) => Category::OcrCaches,
+        Some(READING_STATES_DIRNAME) => Category::ReadingStates,
+        Some(TRASH_DIRNAME) => Category::Trash,
+        _ if path.starts_with(sketches) => Category::Sketches,
+        _ => {
+            path.extension()
+                .and_then(OsStr::to_str)
+                .map(str::to_lowercase)
+                .filter(|kind| book_kinds.contains(kind))
+                .map_or(Category::Other, Category::Books)
+        },
+    }
+}
+
+// Gathers the sizes of the given files, relative to the library's home, by category.
+pub fn aggregate<I>(files: I, sketches: &Path, book_kinds: &FxHashSet<String>) -> StorageUsage
+                where I: Iterator<Item=(PathBuf, u64)> {
+    let mut totals: FxHashMap<Category, (u64, usize)> = FxHashMap::default();
+    let mut biggest = Vec::new();
+
+    for (path, size) in files {
+        let category = categorize(&path, sketches, book_kinds);
+        if matches!(category, Category::Books(..) | Category::Sketches) {
+            biggest.push((path, size));
+        }
+        let total = totals.entry(category).or_insert((0, 0));
+        total.0 += size;
+        total.1 += 1;
+    }
+
+    biggest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    biggest.truncate(BIGGEST_COUNT);
+
+    let mut categories: Vec<CategoryUsage> = totals.into_iter()
+                                                   .map(|(category, (size, count))| CategoryUsage { category, size, count })
+                                                   .collect();
+    categories.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.category.label().cmp(&b.category.label())));
+
+    StorageUsage {
+        categories,
+        biggest,
+        .. Default::default()
+    }
+}
+
+// Walks the library and adds the logs found in the current directory.
+pub fn scan(home: &Path, sketches: &Path, book_kinds: &FxHashSet<String>) -> StorageUsage {
+    let files = WalkDir::new(home).min_depth(1).into_iter()
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.file_type().is_file())
+                        .filter_map(|entry| {
+                            let size = entry.metadata().ok()?.len();
+                            let path = entry.path().strip_prefix(home).ok()?.to_path_buf();
+                            Some((path, size))
+                        });
+    let mut usage = aggregate(files, sketches, book_kinds);
+
+    let (size, count) = LOG_FILENAMES.iter()
+                                     .filter_map(|name| fs::metadata(name).ok())
+                                     .fold((0, 0), |(size, count), md| (size + md.len(), count + 1));
+    if count > 0 {
+        usage.categories.push(CategoryUsage { category: Category::Logs, size, count });
+        usage.categories.sort_by_key(|c| std::cmp::Reverse(c.size));
+    }
+
+    if let Ok(info) = statvfs::statvfs(home) {
+        usage.free = Some(blocks_size(info.blocks_available(), info.fragment_size()));
+        usage.total = Some(blocks_size(info.blocks(), info.fragment_size()));
+    }
+
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        let kinds: FxHashSet<String> = ["epub", "pdf"].iter().map(|k| k.to_string()).collect();
+        let files = [("a/b.epub", 300), ("c.PDF", 500), ("d.txt", 10),
+                     (".thumbnail-previews/1.png", 20), (".thumbnail-previews/2.png", 30),
+                     ("Sketches/e.svg", 400), (".trash/f.epub", 1000)];
+        let usage = aggregate(files.iter().map(|&(p, s)| (PathBuf::from(p), s)),
+                              Path::new("Sketches"), &kinds);
+        assert_eq!(usage.categories[0], CategoryUsage { category: Category::Trash, size: 1000, count: 1 });
+        assert_eq!(usage.categories[1].category, Category::Books("pdf".to_string()));
+        assert_eq!(usage.categories[4], CategoryUsage { category: Category::Thumbnails, size: 50, count: 2 });
+        assert_eq!(usage.categories[5].category, Category::Other);
+        assert_eq!(usage.biggest.iter().map(|(p, _)| p.to_str().unwrap()).collect::<Vec<_>>(),
+                   vec!["c.PDF", "Sketches/e.svg", "a/b.epub"]);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let text = "MemTotal:         510176 kB\nMemFree:           20736 kB\nMemAvailable:     258744 kB\n";
+        assert_eq!(parse_meminfo(text), Some(258744 * 1024));
+        assert_eq!(parse_meminfo("MemTotal: 510176 kB\n"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use rand_core::RngCore;
+
+pub const CELLS: usize = 81;
+
+// The digits of the cells, row by row. Empty cells are zeros.
+pub type Grid = [u8; CELLS];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    // The number of given digits.
+    fn clues(self) -> usize {
+        match self {
+            Difficulty::Easy => 40,
+            Difficulty::Medium => 32,
+            Difficulty::Hard => 26,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub givens: Grid,
+    pub solution: Grid,
+}
+
+fn peers(index: usize) -> impl Iterator<Item=usize> {
+    let (row, column) = (index / 9, index % 9);
+    let (top, left) = (3 * (row / 3), 3 * (column / 3));
+    (0..9).flat_map(move |i| [9 * row + i, 9 * i + column, 9 * (top + i / 3) + left + i % 3])
+          .filter(move |&peer| peer != index)
+}
+
+// The digits that can be written in the given cell, as a bit mask.
+fn candidates(grid: &Grid, index: usize) -> u16 {
+    let used = peers(index).fold(0u16, |mask, peer| mask | (1 << grid[peer]));
+    !used & 0b11_1111_1110
+}
+
+// The empty cell with the fewest candidates.
+fn best_cell(grid: &Grid) -> Option<(usize, u16)> {
+    (0..CELLS).filter(|&index| grid[index] == 0)
+              .map(|index| (index, candidates(grid, index)))
+              .min_by_key(|(_, mask)| mask.count_ones())
+}
+
+fn shuffle<T, R: RngCore>(items: &mut [T], rng: &mut R) {
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+// Fills the empty cells, trying the digits in a random order.
+fn fill<R: RngCore>(grid: &mut Grid, rng: &mut R) -> bool {
+    let (index, mask) = match best_cell(grid) {
+        Some(cell) => cell,
+        None => return true,
+    };
+    let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    shuffle(&mut digits, rng);
+    for digit in digits {
+        if mask & (1 << digit) != 0 {
+            grid[index] = digit;
+            if fill(grid, rng) {
+                return true;
+            }
+        }
+    }
+    grid[index] = 0;
+    false
+}
+
+// Counts the solutions of the grid, up to `limit`.
+pub fn count_solutions(grid: &mut Grid, limit: usize) -> usize {
+    let (index, mask) = match best_cell(grid) {
+        Some(cell) => cell,
+        None => return 1,
+    };
+    let mut count = 0;
+    for digit in 1..=9 {
+        if mask & (1 << digit) != 0 {
+            grid[index] = digit;
+            count += count_solutions(grid, limit - count);
+            if count >= limit {
+                break;
+            }
+        }
+    }
+    grid[index] = 0;
+    count
+}
+
+// Removes digits from a random solution, as long as the puzzle has a single solution.
+pub fn generate<R: RngCore>(difficulty: Difficulty, rng: &mut R) -> Puzzle {
+    let mut solution = [0; CELLS];
+    fill(&mut solution, rng);
+    let mut givens = solution;
+    let mut order: Vec<usize> = (0..CELLS).collect();
+    shuffle(&mut order, rng);
+    let mut clues = CELLS;
+    for index in order {
+        if clues <= difficulty.clues() {
+            break;
+        }
+        givens[index] = 0;
+        if count_solutions(&mut givens.clone(), 2) == 1 {
+            clues -= 1;
+        } else {
+            givens[index] = solution[index];
+        }
+    }
+    Puzzle { givens, solution }
+}
+
+// Whether the digit of the given cell is also found in its row, column or box.
+pub fn conflicts(grid: &Grid, index: usize) -> bool {
+    grid[index] != 0 && peers(index).any(|peer| grid[peer] == grid[index])
+}
+
+pub fn is_solved(grid: &Grid) -> bool {
+    (0..CELLS).all(|index| grid[index] != 0 && !conflicts(grid, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::SeedableRng;
+    use rand_xoshiro::Xoroshiro128Plus;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = Xoroshiro128Plus::seed_from_u64(7);
+        for difficulty in [Difficulty::Easy, Difficulty::Hard] {
+            let puzzle = generate(difficulty, &mut rng);
+            assert!(is_solved(&puzzle.solution));
+            assert!((0..CELLS).all(|i| puzzle.givens[i] == 0 || puzzle.givens[i] == puzzle.solution[i]));
+            assert!(puzzle.givens.iter().filter(|&&d| d != 0).count() >= difficulty.clues());
+            assert_eq!(count_solutions(&mut puzzle.givens.clone(), 2), 1);
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut grid = [0; CELLS];
+        grid[0] = 5;
+        grid[80] = 5;
+        assert!(!conflicts(&grid, 0));
+        grid[20] = 5;
+        assert!(conflicts(&grid, 0));
+        assert!(conflicts(&grid, 20));
+        assert!(!conflicts(&grid, 80));
+        assert!(!is_solved(&grid));
+    }
+}
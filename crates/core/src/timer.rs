@@ -0,0 +1,171 @@
+use std::thread;
+use chrono::{DateTime, Duration, Local};
+use crate::settings::TimerSettings;
+use crate::view::{Event, Hub};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimerPhase {
+    Focus,
+    Break,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimerState {
+    Idle,
+    Running { phase: TimerPhase, ends: DateTime<Local> },
+    Paused { phase: TimerPhase, remaining: Duration },
+}
+
+// A focus timer: focus sessions are followed by breaks, and each break by a paused focus session.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub state: TimerState,
+    // The number of focus sessions completed since the timer was started.
+    pub sessions: usize,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer {
+            state: TimerState::Idle,
+            sessions: 0,
+        }
+    }
+}
+
+impl Timer {
+    pub fn start(&mut self, settings: &TimerSettings, now: DateTime<Local>) {
+        self.sessions = 0;
+        self.state = TimerState::Running {
+            phase: TimerPhase::Focus,
+            ends: now + Duration::minutes(settings.focus_minutes as i64),
+        };
+    }
+
+    pub fn pause(&mut self, now: DateTime<Local>) {
+        if let TimerState::Running { phase, ends } = self.state {
+            self.state = TimerState::Paused { phase, remaining: (ends - now).max(Duration::zero()) };
+        }
+    }
+
+    pub fn resume(&mut self, now: DateTime<Local>) {
+        if let TimerState::Paused { phase, remaining } = self.state {
+            self.state = TimerState::Running { phase, ends: now + remaining };
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.state = TimerState::Idle;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state != TimerState::Idle
+    }
+
+    pub fn is_focusing(&self) -> bool {
+        matches!(self.state, TimerState::Running { phase: TimerPhase::Focus, .. })
+    }
+
+    // Moves to the next phase when the current one is over, and returns the phase that ended.
+    pub fn poll(&mut self, settings: &TimerSettings, now: DateTime<Local>) -> Option<TimerPhase> {
+        let (phase, ends) = match self.state {
+            TimerState::Running { phase, ends } if now >= ends => (phase, ends),
+            _ => return None,
+        };
+        match phase {
+            TimerPhase::Focus => {
+                self.sessions += 1;
+                let interval = settings.long_break_interval.max(1);
+                let minutes = if self.sessions.is_multiple_of(interval) {
+                    settings.long_break_minutes
+                } else {
+                    settings.break_minutes
+                };
+                // The break starts when the session ended, even if it's noticed later.
+                self.state = TimerState::Running {
+                    phase: TimerPhase::Break,
+                    ends: (ends + Duration::minutes(minutes as i64)).max(now),
+                };
+            },
+            TimerPhase::Break => {
+                self.state = TimerState::Paused {
+                    phase: TimerPhase::Focus,
+                    remaining: Duration::minutes(settings.focus_minutes as i64),
+                };
+            },
+        }
+        Some(phase)
+    }
+
+    pub fn remaining(&self, now: DateTime<Local>) -> Option<Duration> {
+        match self.state {
+            TimerState::Idle => None,
+            TimerState::Running { ends, .. } => Some((ends - now).max(Duration::zero())),
+            TimerState::Paused { remaining, .. } => Some(remaining),
+        }
+    }
+
+    // The text of the timer chip.
+    pub fn label(&self, now: DateTime<Local>) -> Option<String> {
+        let remaining = self.remaining(now)?;
+        // Rounded up, so that the last minute reads 1 and not 0.
+        let minutes = (remaining.num_seconds() + 59) / 60;
+        let name = match self.state {
+            TimerState::Paused { .. } => "Paused",
+            TimerState::Running { phase: TimerPhase::Focus, .. } => "Focus",
+            TimerState::Running { phase: TimerPhase::Break, .. } => "Break",
+            TimerState::Idle => return None,
+        };
+        Some(format!("{} · {} min", name, minutes))
+    }
+
+    // Sends `Event::TimerTick` when the current phase ends.
+    pub fn schedule(&self, hub: &Hub) {
+        if let TimerState::Running { ends, .. } = self.state {
+            let delay = (ends - Local::now()).to_std().unwrap_or_default();
+            let hub = hub.clone();
+            thread::spawn(move || {
+                thread::sleep(delay);
+                hub.send(Event::TimerTick).ok();
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_phases() {
+        let settings = TimerSettings {
+            focus_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_interval: 2,
+        };
+        let start = Local.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut timer = Timer::default();
+        timer.start(&settings, start);
+        assert!(timer.is_focusing());
+        assert_eq!(timer.poll(&settings, start + Duration::minutes(24)), None);
+        assert_eq!(timer.label(start + Duration::seconds(24 * 60 + 30)).as_deref(), Some("Focus · 1 min"));
+        assert_eq!(timer.poll(&settings, start + Duration::minutes(26)), Some(TimerPhase::Focus));
+        assert_eq!(timer.remaining(start + Duration::minutes(26)), Some(Duration::minutes(4)));
+        assert_eq!(timer.poll(&settings, start + Duration::minutes(30)), Some(TimerPhase::Break));
+        assert_eq!(timer.state, TimerState::Paused { phase: TimerPhase::Focus, remaining: Duration::minutes(25) });
+
+        let later = start + Duration::hours(1);
+        timer.resume(later);
+        timer.pause(later + Duration::minutes(10));
+        assert_eq!(timer.label(later + Duration::hours(1)).as_deref(), Some("Paused · 15 min"));
+        timer.resume(later + Duration::minutes(20));
+        assert_eq!(timer.poll(&settings, later + Duration::minutes(35)), Some(TimerPhase::Focus));
+        // Every second break is a long one.
+        assert_eq!(timer.remaining(later + Duration::minutes(35)), Some(Duration::minutes(15)));
+        timer.stop();
+        assert!(!timer.is_active());
+        assert_eq!(timer.label(later), None);
+    }
+}
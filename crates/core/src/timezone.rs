@@ -0,0 +1,99 @@
+use std::env;
+
+// The devices don't ship the time zone database: each zone is given by its POSIX TZ rule.
+pub const TIMEZONES: &[(&str, &[(&str, &str)])] = &[
+    ("Africa", &[
+        ("Africa/Casablanca", "<+01>-1"),
+        ("Africa/Lagos", "WAT-1"),
+        ("Africa/Johannesburg", "SAST-2"),
+        ("Africa/Cairo", "EET-2EEST,M4.5.5/0,M10.5.4/24"),
+        ("Africa/Nairobi", "EAT-3"),
+    ]),
+    ("America", &[
+        ("America/Anchorage", "AKST9AKDT,M3.2.0,M11.1.0"),
+        ("America/Los_Angeles", "PST8PDT,M3.2.0,M11.1.0"),
+        ("America/Phoenix", "MST7"),
+        ("America/Denver", "MST7MDT,M3.2.0,M11.1.0"),
+        ("America/Mexico_City", "CST6"),
+        ("America/Chicago", "CST6CDT,M3.2.0,M11.1.0"),
+        ("America/Bogota", "<-05>5"),
+        ("America/New_York", "EST5EDT,M3.2.0,M11.1.0"),
+        ("America/Toronto", "EST5EDT,M3.2.0,M11.1.0"),
+        ("America/Halifax", "AST4ADT,M3.2.0,M11.1.0"),
+        ("America/Santiago", "<-04>4<-03>,M9.1.6/24,M4.1.6/24"),
+        ("America/Sao_Paulo", "<-03>3"),
+        ("America/Buenos_Aires", "<-03>3"),
+    ]),
+    ("Asia", &[
+        ("Asia/Jerusalem", "IST-2IDT,M3.4.4/26,M10.5.0"),
+        ("Asia/Tehran", "<+0330>-3:30"),
+        ("Asia/Dubai", "<+04>-4"),
+        ("Asia/Karachi", "PKT-5"),
+        ("Asia/Kolkata", "IST-5:30"),
+        ("Asia/Dhaka", "<+06>-6"),
+        ("Asia/Bangkok", "<+07>-7"),
+        ("Asia/Shanghai", "CST-8"),
+        ("Asia/Singapore", "<+08>-8"),
+        ("Asia/Seoul", "KST-9"),
+        ("Asia/Tokyo", "JST-9"),
+    ]),
+    ("Atlantic", &[
+        ("Atlantic/Azores", "<-01>1<+00>,M3.5.0/0,M10.5.0/1"),
+        ("Atlantic/Reykjavik", "GMT0"),
+    ]),
+    ("Australia", &[
+        ("Australia/Perth", "AWST-8"),
+        ("Australia/Adelaide", "ACST-9:30ACDT,M10.1.0,M4.1.0/3"),
+        ("Australia/Brisbane", "AEST-10"),
+        ("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ]),
+    ("Europe", &[
+        ("Europe/Lisbon", "WET0WEST,M3.5.0/1,M10.5.0"),
+        ("Europe/London", "GMT0BST,M3.5.0/1,M10.5.0"),
+        ("Europe/Madrid", "CET-1CEST,M3.5.0,M10.5.0/3"),
+        ("Europe/Paris", "CET-1CEST,M3.5.0,M10.5.0/3"),
+        ("Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"),
+        ("Europe/Rome", "CET-1CEST,M3.5.0,M10.5.0/3"),
+        ("Europe/Athens", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+        ("Europe/Helsinki", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+        ("Europe/Kyiv", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+        ("Europe/Istanbul", "<+03>-3"),
+        ("Europe/Moscow", "MSK-3"),
+    ]),
+    ("Pacific", &[
+        ("Pacific/Honolulu", "HST10"),
+        ("Pacific/Fiji", "<+12>-12"),
+        ("Pacific/Auckland", "NZST-12NZDT,M9.5.0,M4.1.0/3"),
+    ]),
+    ("UTC", &[
+        ("UTC", "UTC0"),
+    ]),
+];
+
+// Names that aren't listed are taken to be POSIX TZ rules.
+pub fn rule(name: &str) -> &str {
+    TIMEZONES.iter()
+             .flat_map(|(_, zones)| zones.iter())
+             .find(|(zone, _)| *zone == name)
+             .map_or(name, |(_, rule)| *rule)
+}
+
+// The helpers and the hooks inherit the environment of the application. It can't be
+// changed safely once other threads are running: this is only called at startup,
+// and a new time zone is applied by the next start.
+pub fn export(timezone: Option<&str>) {
+    if let Some(name) = timezone {
+        env::set_var("TZ", rule(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule() {
+        assert_eq!(rule("Europe/Paris"), "CET-1CEST,M3.5.0,M10.5.0/3");
+        assert_eq!(rule("EST5"), "EST5");
+    }
+}
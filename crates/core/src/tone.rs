@@ -0,0 +1,76 @@
+use serde::{Serialize, Deserialize};
+use crate::framebuffer::Pixmap;
+
+// Gray level curves applied to the rendered pages of scanned documents.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToneCurve {
+    // Turns the light grays of yellowed paper into white.
+    BleachBackground,
+    // Increases the contrast around the middle grays.
+    BoostMidtones,
+    // Darkens the faded text.
+    DarkenText,
+    // Combines a white background with a darker text.
+    Newsprint,
+}
+
+pub const TONE_CURVES: [ToneCurve; 4] = [ToneCurve::BleachBackground, ToneCurve::BoostMidtones,
+                                         ToneCurve::DarkenText, ToneCurve::Newsprint];
+
+impl ToneCurve {
+    pub fn label(self) -> &'static str {
+        match self {
+            ToneCurve::BleachBackground => "Bleach Background",
+            ToneCurve::BoostMidtones => "Boost Midtones",
+            ToneCurve::DarkenText => "Darken Text",
+            ToneCurve::Newsprint => "Newsprint",
+        }
+    }
+
+    pub fn table(self) -> [u8; 256] {
+        let mut table = [0; 256];
+        for (color, value) in table.iter_mut().enumerate() {
+            let x = color as f32 / 255.0;
+            let y = match self {
+                ToneCurve::BleachBackground => levels(x, 0.0, 0.75),
+                ToneCurve::BoostMidtones => x * x * (3.0 - 2.0 * x),
+                ToneCurve::DarkenText => x.powf(1.8),
+                ToneCurve::Newsprint => levels(x, 0.2, 0.8).powf(1.4),
+            };
+            *value = (255.0 * y).round() as u8;
+        }
+        table
+    }
+
+    pub fn apply(self, pixmap: &mut Pixmap) {
+        let table = self.table();
+        for color in pixmap.data_mut() {
+            *color = table[*color as usize];
+        }
+    }
+}
+
+// Maps the range [black, white] to [0, 1].
+fn levels(x: f32, black: f32, white: f32) -> f32 {
+    ((x - black) / (white - black)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_curves() {
+        for curve in TONE_CURVES {
+            let table = curve.table();
+            assert_eq!(table[0], 0);
+            assert_eq!(table[255], 255);
+            assert!(table.windows(2).all(|w| w[0] <= w[1]));
+        }
+        assert_eq!(ToneCurve::BleachBackground.table()[200], 255);
+        assert!(ToneCurve::DarkenText.table()[128] < 128);
+        assert!(ToneCurve::BoostMidtones.table()[64] < 64);
+        assert!(ToneCurve::BoostMidtones.table()[192] > 192);
+    }
+}
@@ -0,0 +1,104 @@
+use std::thread;
+use std::process::Command;
+use std::collections::BTreeMap;
+use anyhow::{Error, format_err};
+use crate::dictionary::Dictionary;
+use crate::helpers::plain_text;
+use crate::settings::{TranslationSettings, TranslationBackend, network_env};
+use crate::view::{Event, Hub};
+
+// Lets the online backends detect the source language.
+pub const AUTO_LANGUAGE: &str = "auto";
+
+// The primary subtag of a language tag, e.g. `pt` for `pt-BR`.
+fn primary_language(tag: &str) -> String {
+    tag.split(['-', '_'])
+       .next()
+       .unwrap_or_default()
+       .trim()
+       .to_lowercase()
+}
+
+// Returns the languages to translate from and to: the ones remembered for the book
+// take precedence over the book's language and the default target language.
+pub fn language_pair(source: Option<&String>, target: Option<&String>, book_language: &str, settings: &TranslationSettings) -> (String, String) {
+    let source = source.cloned()
+                       .or_else(|| Some(primary_language(book_language)).filter(|l| !l.is_empty()))
+                       .unwrap_or_else(|| AUTO_LANGUAGE.to_string());
+    let target = target.cloned()
+                       .unwrap_or_else(|| settings.target_language.clone());
+    (source, target)
+}
+
+// Looks the text up in the bilingual dictionary associated with the language pair.
+pub fn translate_offline(text: &str, source: &str, target: &str, settings: &TranslationSettings, dictionaries: &mut BTreeMap<String, Dictionary>) -> Result<String, Error> {
+    let key = format!("{}-{}", source, target);
+    let name = settings.dictionaries.get(&key)
+                       .ok_or_else(|| format_err!("no dictionary for {}", key))?;
+    let dictionary = dictionaries.get_mut(name)
+                                 .ok_or_else(|| format_err!("dictionary not found: {}", name))?;
+    let results = dictionary.lookup(text, false)?;
+    if results.is_empty() {
+        return Err(format_err!("no translations found for {}", text));
+    }
+    Ok(results.iter()
+              .map(|[_, body]| plain_text(body).split_whitespace().collect::<Vec<&str>>().join(" "))
+              .collect::<Vec<String>>()
+              .join("\n"))
+}
+
+// Runs the program that queries the online backend in the background.
+// The result is sent as a `Translation` event.
+pub fn translate_online(text: String, source: String, target: String, settings: &TranslationSettings, hub: &Hub) {
+    let backend = match settings.backend {
+        TranslationBackend::LibreTranslate => "libretranslate",
+        TranslationBackend::DeepL => "deepl",
+        TranslationBackend::Dictionary => return,
+    };
+    let program = settings.program.clone();
+    let url = settings.url.clone();
+    let api_key = settings.api_key.clone().unwrap_or_default();
+    let hub = hub.clone();
+
+    thread::spawn(move || {
+        let output = network_env(&mut Command::new(&program))
+                             .arg(backend)
+                             .arg(&url)
+                             .arg(&source)
+                             .arg(&target)
+                             .arg(&text)
+                             .env("PLATO_TRANSLATION_API_KEY", &api_key)
+                             .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let translation = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                hub.send(Event::Translation { text, translation }).ok();
+            },
+            Ok(output) => {
+                eprintln!("Can't translate: {}.", String::from_utf8_lossy(&output.stderr).trim());
+                hub.send(Event::Notify("Can't translate the selection.".to_string())).ok();
+            },
+            Err(e) => {
+                eprintln!("Can't spawn {}: {:#}.", program.display(), e);
+                hub.send(Event::Notify("Can't translate the selection.".to_string())).ok();
+            },
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_pair() {
+        let settings = TranslationSettings { target_language: "fr".to_string(), .. Default::default() };
+        assert_eq!(language_pair(None, None, "en-US", &settings),
+                   ("en".to_string(), "fr".to_string()));
+        assert_eq!(language_pair(None, None, "", &settings),
+                   (AUTO_LANGUAGE.to_string(), "fr".to_string()));
+        let (source, target) = ("de".to_string(), "es".to_string());
+        assert_eq!(language_pair(Some(&source), Some(&target), "en", &settings),
+                   ("de".to_string(), "es".to_string()));
+    }
+}
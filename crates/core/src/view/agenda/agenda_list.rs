@@ -0,0 +1,152 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER, THICKNESS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+pub enum AgendaRow {
+    Day(String),
+    Event {
+        time: String,
+        summary: String,
+    },
+    Message(String),
+}
+
+// The days and their events, one per row, split into pages.
+pub struct AgendaList {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    rows: Vec<AgendaRow>,
+    row_height: i32,
+    current_page: usize,
+}
+
+impl AgendaList {
+    pub fn new(rect: Rectangle, context: &mut Context) -> AgendaList {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        AgendaList {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            rows: Vec::new(),
+            row_height: 3 * font.line_height() / 2,
+            current_page: 0,
+        }
+    }
+
+    pub fn update(&mut self, rows: Vec<AgendaRow>, rq: &mut RenderQueue) {
+        self.rows = rows;
+        self.current_page = 0;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn rows_per_page(&self) -> usize {
+        (self.rect.height() as i32 / self.row_height.max(1)).max(1) as usize
+    }
+
+    fn pages_count(&self) -> usize {
+        self.rows.len().max(1).div_ceil(self.rows_per_page())
+    }
+
+    // Returns false when there are no more pages in the given direction.
+    pub fn go_to_page(&mut self, next: bool, rq: &mut RenderQueue) -> bool {
+        let page = if next {
+            self.current_page + 1
+        } else {
+            match self.current_page.checked_sub(1) {
+                Some(page) => page,
+                None => return false,
+            }
+        };
+        if page >= self.pages_count() {
+            return false;
+        }
+        self.current_page = page;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        true
+    }
+}
+
+impl View for AgendaList {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let row_height = self.row_height;
+        let per_page = self.rows_per_page();
+        let start = self.current_page * per_page;
+
+        for (index, row) in self.rows.iter().skip(start).take(per_page).enumerate() {
+            let y = self.rect.min.y + index as i32 * row_height;
+            match row {
+                AgendaRow::Day(text) => {
+                    let font = font_from_style(fonts, &BOLD_STYLE, dpi);
+                    let padding = font.em() as i32;
+                    let x_height = font.x_heights.0 as i32;
+                    fb.draw_rectangle(&rect![self.rect.min.x, y, self.rect.max.x, y + row_height], GRAY12);
+                    let plan = font.plan(text, Some(self.rect.width() as i32 - 2 * padding), None);
+                    font.render(fb, BLACK, &plan, pt!(self.rect.min.x + padding, y + (row_height + x_height) / 2));
+                },
+                AgendaRow::Event { time, summary } => {
+                    let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+                    let padding = font.em() as i32;
+                    let x_height = font.x_heights.0 as i32;
+                    let baseline = y + (row_height + x_height) / 2;
+                    let time_width = font.plan("00:00–00:00", None, None).width;
+                    let time_plan = font.plan(time, Some(time_width), None);
+                    font.render(fb, BLACK, &time_plan, pt!(self.rect.min.x + padding, baseline));
+                    let x = self.rect.min.x + 2 * padding + time_width;
+                    let plan = font.plan(summary, Some(self.rect.max.x - padding - x), None);
+                    font.render(fb, BLACK, &plan, pt!(x, baseline));
+                    fb.draw_rectangle(&rect![self.rect.min.x + padding, y + row_height - thickness,
+                                             self.rect.max.x - padding, y + row_height],
+                                      GRAY12);
+                },
+                AgendaRow::Message(text) => {
+                    let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+                    let x_height = font.x_heights.0 as i32;
+                    let plan = font.plan(text, Some(self.rect.width() as i32), None);
+                    let dx = (self.rect.width() as i32 - plan.width) / 2;
+                    font.render(fb, GRAY08, &plan, pt!(self.rect.min.x + dx, y + (row_height + x_height) / 2));
+                },
+            }
+        }
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.rect = rect;
+        self.current_page = self.current_page.min(self.pages_count() - 1);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
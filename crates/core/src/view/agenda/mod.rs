@@ -0,0 +1,375 @@
+mod agenda_list;
+mod month_grid;
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveTime};
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, CycleDir, Dir, halves, divide};
+use crate::gesture::GestureEvent;
+use crate::ics::{CalendarEvent, load_calendars, occurrences, agenda, fetch};
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::notification::Notification;
+use crate::view::date_picker::{first_of_month, shift_month};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::agenda_list::{AgendaList, AgendaRow};
+use self::month_grid::MonthGrid;
+
+// Shows the events of the `.ics` files of the calendar directory, as a list of days or as a month.
+pub struct Agenda {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    events: Vec<CalendarEvent>,
+    // The first day of the list.
+    start: NaiveDate,
+    // The first day of the displayed month.
+    month: NaiveDate,
+    show_month: bool,
+}
+
+impl Agenda {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Agenda {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+        let today = Local::now().date_naive();
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  "Agenda".to_string(),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let agenda_list = AgendaList::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                                rect.max.x, rect.max.y - small_height - small_thickness],
+                                          context);
+        children.push(Box::new(agenda_list) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut agenda = Agenda {
+            id,
+            rect,
+            children,
+            events: Vec::new(),
+            start: today,
+            month: first_of_month(today),
+            show_month: false,
+        };
+
+        agenda.load_events(context);
+        agenda.update_content(&mut RenderQueue::new(), context);
+        agenda.update_buttons(&mut RenderQueue::new(), context);
+
+        if context.online && !context.settings.calendar.urls.is_empty() {
+            let dir = context.library.home.join(&context.settings.calendar.path);
+            fetch(&context.settings.calendar.urls, &dir, &context.jobs, hub);
+        }
+
+        agenda
+    }
+
+    fn load_events(&mut self, context: &Context) {
+        let dir = context.library.home.join(&context.settings.calendar.path);
+        self.events = if dir.exists() {
+            load_calendars(&dir).map_err(|e| eprintln!("Can't load the calendars: {:#}.", e))
+                                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+    }
+
+    fn title(&self, context: &Context) -> String {
+        if self.show_month {
+            self.month.format("%B %Y").to_string()
+        } else {
+            let end = self.start + Duration::days(context.settings.calendar.days.max(1) as i64 - 1);
+            format!("{} – {}", self.start.format("%-d %b"), end.format("%-d %b %Y"))
+        }
+    }
+
+    fn agenda_rows(&self, context: &Context) -> Vec<AgendaRow> {
+        if self.events.is_empty() {
+            return vec![AgendaRow::Message(format!("No events in {}.", context.settings.calendar.path.display()))];
+        }
+        let from = self.start.and_time(NaiveTime::MIN);
+        let to = from + Duration::days(context.settings.calendar.days.max(1) as i64);
+        let days = agenda(occurrences(&self.events, from, to), self.start);
+        if days.is_empty() {
+            return vec![AgendaRow::Message("No events.".to_string())];
+        }
+        let mut rows = Vec::new();
+        for (date, list) in days {
+            rows.push(AgendaRow::Day(date.format("%A %-d %B").to_string()));
+            for occurrence in list {
+                rows.push(AgendaRow::Event {
+                    time: occurrence.time_range(),
+                    summary: describe(occurrence.event),
+                });
+            }
+        }
+        rows
+    }
+
+    // The summaries of the events of each day of the displayed month.
+    fn month_days(&self) -> Vec<Vec<String>> {
+        let first = self.month;
+        let next = shift_month(first, CycleDir::Next);
+        let mut days = vec![Vec::new(); (next - first).num_days() as usize];
+        let list = occurrences(&self.events, first.and_time(NaiveTime::MIN), next.and_time(NaiveTime::MIN));
+        for occurrence in list {
+            let text = if occurrence.event.all_day {
+                describe(occurrence.event)
+            } else {
+                format!("{} {}", occurrence.start.format("%H:%M"), describe(occurrence.event))
+            };
+            // The events that last several days are written in each of them.
+            let last = (occurrence.end - Duration::seconds(1)).date().max(occurrence.start.date());
+            let mut date = occurrence.start.date().max(first);
+            while date <= last && date < next {
+                days[date.day0() as usize].push(text.clone());
+                date += Duration::days(1);
+            }
+        }
+        days
+    }
+
+    fn update_content(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let rect = *self.children[2].rect();
+        if self.show_month {
+            self.children[2] = Box::new(MonthGrid::new(rect, self.month, self.month_days())) as Box<dyn View>;
+        } else {
+            let mut agenda_list = AgendaList::new(rect, context);
+            agenda_list.update(self.agenda_rows(context), &mut RenderQueue::new());
+            self.children[2] = Box::new(agenda_list) as Box<dyn View>;
+        }
+        rq.add(RenderData::new(self.children[2].id(), rect, UpdateMode::Gui));
+        let title = self.title(context);
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title, rq);
+        }
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue, context: &Context) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let mut buttons = vec![(Event::Select(EntryId::ShiftAgenda(CycleDir::Previous)), "Earlier"),
+                               (Event::Select(EntryId::AgendaToday), "Today"),
+                               (Event::Select(EntryId::ShiftAgenda(CycleDir::Next)), "Later"),
+                               (Event::Select(EntryId::ToggleAgendaMonth),
+                                if self.show_month { "Agenda" } else { "Month" })];
+        if !context.settings.calendar.urls.is_empty() {
+            buttons.push((Event::Select(EntryId::RefreshCalendars), "Refresh"));
+        }
+        let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for (index, ((event, text), w)) in buttons.into_iter().zip(widths).enumerate() {
+            let button_rect = rect![x, rect.min.y + padding, x + w - padding, rect.max.y - padding];
+            self.children.insert(5 + index, Box::new(Button::new(button_rect, event, text.to_string())) as Box<dyn View>);
+            x += w;
+        }
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn shift(&mut self, dir: CycleDir, rq: &mut RenderQueue, context: &mut Context) {
+        if self.show_month {
+            self.month = shift_month(self.month, dir);
+        } else {
+            let days = Duration::days(context.settings.calendar.days.max(1) as i64);
+            self.start = match dir {
+                CycleDir::Next => self.start + days,
+                CycleDir::Previous => self.start - days,
+            };
+        }
+        self.update_content(rq, context);
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn describe(event: &CalendarEvent) -> String {
+    let summary = if event.summary.is_empty() { "Untitled" } else { event.summary.as_str() };
+    if event.location.is_empty() {
+        summary.to_string()
+    } else {
+        format!("{} · {}", summary, event.location)
+    }
+}
+
+impl View for Agenda {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(EntryId::ShiftAgenda(dir)) => {
+                self.shift(dir, rq, context);
+                true
+            },
+            Event::Select(EntryId::AgendaToday) => {
+                let today = Local::now().date_naive();
+                self.start = today;
+                self.month = first_of_month(today);
+                self.update_content(rq, context);
+                true
+            },
+            Event::Select(EntryId::ToggleAgendaMonth) => {
+                self.show_month = !self.show_month;
+                if self.show_month {
+                    self.month = first_of_month(self.start);
+                }
+                self.update_content(rq, context);
+                self.update_buttons(rq, context);
+                true
+            },
+            Event::Select(EntryId::RefreshCalendars) => {
+                let dir = context.library.home.join(&context.settings.calendar.path);
+                fetch(&context.settings.calendar.urls, &dir, &context.jobs, hub);
+                true
+            },
+            Event::CalendarsFetched(ref error) => {
+                if let Some(error) = error {
+                    let notif = Notification::new(format!("Can't fetch the calendars: {}", error), hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
+                }
+                self.load_events(context);
+                self.update_content(rq, context);
+                true
+            },
+            Event::SetDate(ViewId::Agenda, time) => {
+                self.start = time.date();
+                self.show_month = false;
+                self.update_content(rq, context);
+                self.update_buttons(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.children[2].rect().includes(start) => {
+                let next = matches!(dir, Dir::West | Dir::North);
+                let paged = self.children[2].downcast_mut::<AgendaList>()
+                                            .is_some_and(|list| list.go_to_page(next, rq));
+                if !paged {
+                    self.shift(if next { CycleDir::Next } else { CycleDir::Previous }, rq, context);
+                }
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            // Follow the change of day, when the list starts on the current day.
+            Event::ClockTick => {
+                let today = Local::now().date_naive();
+                if !self.show_month && self.start + Duration::days(1) == today {
+                    self.start = today;
+                    self.update_content(rq, context);
+                }
+                false
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new(), context);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, BOLD_STYLE, MD_SIZE};
+use crate::gesture::GestureEvent;
+use crate::view::date_picker::{WEEKDAYS, month_grid};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, ViewId};
+use crate::view::{Id, ID_FEEDER, THICKNESS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+// A wall calendar: the summaries of the events are written in the cells of their days.
+// Tapping a day pushes `Event::SetDate(ViewId::Agenda, …)`.
+pub struct MonthGrid {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    // The first day of the displayed month.
+    month: NaiveDate,
+    // The summaries of the events of each day of the month.
+    days: Vec<Vec<String>>,
+    today: NaiveDate,
+}
+
+impl MonthGrid {
+    pub fn new(rect: Rectangle, month: NaiveDate, days: Vec<Vec<String>>) -> MonthGrid {
+        MonthGrid {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            month,
+            days,
+            today: Local::now().date_naive(),
+        }
+    }
+
+    fn header_height(&self) -> i32 {
+        self.rect.height() as i32 / 16
+    }
+
+    fn cell_size(&self) -> (i32, i32) {
+        (self.rect.width() as i32 / 7, (self.rect.height() as i32 - self.header_height()) / 6)
+    }
+
+    fn day_at(&self, x: i32, y: i32) -> Option<NaiveDate> {
+        let (width, height) = self.cell_size();
+        let y = y - self.rect.min.y - self.header_height();
+        if y < 0 {
+            return None;
+        }
+        let column = ((x - self.rect.min.x) / width.max(1)).min(6) as usize;
+        let row = (y / height.max(1)).min(5) as usize;
+        month_grid(self.month)[row][column].and_then(|day| {
+            NaiveDate::from_ymd_opt(self.month.year(), self.month.month(), day)
+        })
+    }
+}
+
+impl View for MonthGrid {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                if let Some(date) = self.day_at(center.x, center.y) {
+                    bus.push_back(Event::SetDate(ViewId::Agenda, date.and_time(NaiveTime::MIN)));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+        let header_height = self.header_height();
+        let (width, height) = self.cell_size();
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        {
+            let font = font_from_style(fonts, &BOLD_STYLE, dpi);
+            let x_height = font.x_heights.0 as i32;
+            for (i, name) in WEEKDAYS.iter().enumerate() {
+                let plan = font.plan(name, None, None);
+                let x = self.rect.min.x + i as i32 * width + (width - plan.width) / 2;
+                let y = self.rect.min.y + (header_height + x_height) / 2;
+                font.render(fb, BLACK, &plan, pt!(x, y));
+            }
+        }
+
+        let font = font_from_style(fonts, &MD_SIZE, dpi);
+        let padding = font.em() as i32 / 2;
+        let line_height = font.line_height();
+        let x_height = font.x_heights.0 as i32;
+
+        for (row, week) in month_grid(self.month).iter().enumerate() {
+            let y_min = self.rect.min.y + header_height + row as i32 * height;
+            fb.draw_rectangle(&rect![self.rect.min.x, y_min, self.rect.max.x, y_min + thickness], GRAY12);
+            for (column, day) in week.iter().enumerate() {
+                let x_min = self.rect.min.x + column as i32 * width;
+                if column > 0 {
+                    fb.draw_rectangle(&rect![x_min, y_min, x_min + thickness, y_min + height], GRAY12);
+                }
+                let day = if let Some(day) = day { *day } else { continue };
+                let date = NaiveDate::from_ymd_opt(self.month.year(), self.month.month(), day);
+
+                let plan = font.plan(day.to_string(), None, None);
+                let mut y = y_min + padding + line_height / 2 + x_height / 2;
+                if date == Some(self.today) {
+                    fb.draw_rectangle(&rect![x_min + thickness, y_min + thickness,
+                                             x_min + width, y_min + padding + line_height],
+                                      BLACK);
+                    font.render(fb, WHITE, &plan, pt!(x_min + padding, y));
+                } else {
+                    font.render(fb, BLACK, &plan, pt!(x_min + padding, y));
+                }
+
+                let summaries = self.days.get(day as usize - 1).map(Vec::as_slice).unwrap_or_default();
+                let available = ((y_min + height - y) / line_height.max(1)) as usize;
+                for (index, summary) in summaries.iter().enumerate() {
+                    if index >= available {
+                        break;
+                    }
+                    y += line_height;
+                    let (text, color) = if index + 1 == available && summaries.len() > available {
+                        (format!("+{}", summaries.len() - index), GRAY08)
+                    } else {
+                        (summary.clone(), BLACK)
+                    };
+                    let plan = font.plan(&text, Some(width - 2 * padding), None);
+                    font.render(fb, color, &plan, pt!(x_min + padding, y));
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.rect = rect;
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,25 @@
+use crate::geom::Rectangle;
+use crate::view::{View, Hub, RenderQueue};
+use crate::view::sudoku::Sudoku;
+use crate::context::Context;
+
+// The mini-apps are the applications that only need a rectangle to be built.
+// They're listed in the *Games* submenu of the applications menu, and launched
+// through `AppCmd::MiniApp`: to add one, write its view and register it below.
+pub struct MiniApp {
+    pub name: &'static str,
+    build: fn(Rectangle, &Hub, &mut RenderQueue, &mut Context) -> Box<dyn View>,
+}
+
+pub const MINI_APPS: &[MiniApp] = &[
+    MiniApp {
+        name: "Sudoku",
+        build: |rect, hub, rq, context| Box::new(Sudoku::new(rect, hub, rq, context)),
+    },
+];
+
+pub fn build(name: &str, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Option<Box<dyn View>> {
+    MINI_APPS.iter()
+             .find(|app| app.name == name)
+             .map(|app| (app.build)(rect, hub, rq, context))
+}
@@ -0,0 +1,139 @@
+use chrono::{Local, TimeZone, Duration};
+use crate::device::CURRENT_DEVICE;
+use crate::battery::history::{Record, capacity_curve, sleep_intervals};
+use crate::framebuffer::{Framebuffer, UpdateMode, DitheredFill};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+const DAY_SECONDS: i64 = 86_400;
+
+// Plots the capacity over the last days: the gray bands are the periods
+// of sleep and the strip below the axis marks the periods of charge.
+pub struct Chart {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    records: Vec<Record>,
+    days: u16,
+    end: i64,
+}
+
+impl Chart {
+    pub fn new(rect: Rectangle, records: Vec<Record>, days: u16) -> Chart {
+        Chart {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            records,
+            days,
+            end: Local::now().timestamp(),
+        }
+    }
+
+    pub fn update(&mut self, records: Vec<Record>, days: u16, rq: &mut RenderQueue) {
+        self.records = records;
+        self.days = days;
+        self.end = Local::now().timestamp();
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for Chart {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32;
+        let x_height = font.x_heights.0 as i32;
+        let label_width = font.plan("100%", None, None).width + padding / 2;
+        let plot = rect![self.rect.min.x + padding + label_width, self.rect.min.y + padding,
+                         self.rect.max.x - padding, self.rect.max.y - 3 * padding];
+        if plot.width() < 2 || plot.height() < 2 {
+            return;
+        }
+
+        let start = self.end - self.days as i64 * DAY_SECONDS;
+        let span = (self.end - start) as f32;
+        let x_at = |time: i64| plot.min.x + ((time - start) as f32 * plot.width() as f32 / span).round() as i32;
+
+        for (since, until) in sleep_intervals(&self.records, start, self.end) {
+            let band = rect![x_at(since), plot.min.y, x_at(until).max(x_at(since) + 1), plot.max.y];
+            fb.draw_bars(&band, &[1.0], 1.0, 0, &DitheredFill(GRAY12));
+        }
+
+        for pair in self.records.windows(2) {
+            if pair[0].charging && pair[1].time > start {
+                let strip = rect![x_at(pair[0].time.max(start)), plot.max.y + thickness,
+                                  x_at(pair[1].time).max(x_at(pair[0].time.max(start)) + 1), plot.max.y + 3 * thickness];
+                fb.draw_rectangle(&strip, BLACK);
+            }
+        }
+
+        // The horizontal lines at 0, 50 and 100%.
+        for (ratio, label) in [(0, "0%"), (50, "50%"), (100, "100%")] {
+            let y = plot.max.y - 1 - ratio * (plot.height() as i32 - 1) / 100;
+            fb.draw_rectangle(&rect![plot.min.x, y, plot.max.x, y + 1], GRAY08);
+            let plan = font.plan(label, None, None);
+            font.render(fb, BLACK, &plan, pt!(plot.min.x - plan.width - padding / 2, y + x_height / 2));
+        }
+
+        // The midnights, labeled with the following day.
+        let today = Local::now().date_naive().and_hms_opt(0, 0, 0)
+                         .and_then(|midnight| Local.from_local_datetime(&midnight).single());
+        if let Some(today) = today {
+            let format = if self.days <= 7 { "%a" } else { "%-d" };
+            for n in 0..=self.days as i64 {
+                let midnight = today - Duration::days(n);
+                let time = midnight.timestamp();
+                if time < start {
+                    break;
+                }
+                let x = x_at(time);
+                fb.draw_rectangle(&rect![x, plot.min.y, x + 1, plot.max.y], GRAY08);
+                let plan = font.plan(midnight.format(format).to_string(), None, None);
+                let x = (x + padding / 4).min(plot.max.x - plan.width);
+                font.render(fb, BLACK, &plan, pt!(x, self.rect.max.y - padding));
+            }
+        }
+
+        let count = (plot.width() as usize / 2).max(2);
+        if let Some((first, values)) = capacity_curve(&self.records, start, self.end, count) {
+            let curve = rect![x_at(first), plot.min.y, plot.max.x, plot.max.y];
+            if curve.width() > 0 {
+                fb.draw_line_chart(&curve, &values, 100.0, thickness as f32, BLACK);
+            }
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,222 @@
+mod chart;
+
+use chrono::Local;
+use crate::device::CURRENT_DEVICE;
+use crate::battery::history::{BatteryHistory, Record, BATTERY_HISTORY_PATH, standby_drain};
+use crate::geom::{Rectangle, Dir, halves};
+use crate::gesture::GestureEvent;
+use crate::view::filler::Filler;
+use crate::view::label::Label;
+use crate::view::top_bar::TopBar;
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData, ViewId, Id, ID_FEEDER, Align};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::BLACK;
+use crate::context::Context;
+use self::chart::Chart;
+
+// The selectable ranges, in days.
+const RANGES: [u16; 4] = [1, 3, 7, 14];
+
+// Shows the battery history: swipe west or east to widen or narrow the range.
+pub struct BatteryGraph {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    days: u16,
+}
+
+impl BatteryGraph {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> BatteryGraph {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+        let days = context.settings.battery.history_days.max(1);
+        let records = load_records();
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  title(days),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let label = Label::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                     rect.max.x, rect.max.y],
+                               summary(&records, days),
+                               Align::Center);
+
+        let chart = Chart::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                     rect.max.x, rect.max.y - small_height - small_thickness],
+                               records, days);
+        children.push(Box::new(chart) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+        children.push(Box::new(label) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        BatteryGraph {
+            id,
+            rect,
+            children,
+            days,
+        }
+    }
+
+    fn update(&mut self, rq: &mut RenderQueue) {
+        let records = load_records();
+        if let Some(label) = self.children[4].downcast_mut::<Label>() {
+            label.update(&summary(&records, self.days), rq);
+        }
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title(self.days), rq);
+        }
+        if let Some(chart) = self.children[2].downcast_mut::<Chart>() {
+            chart.update(records, self.days, rq);
+        }
+    }
+
+    fn change_range(&mut self, dir: Dir, rq: &mut RenderQueue) {
+        let index = RANGES.iter().position(|&days| days >= self.days).unwrap_or(RANGES.len() - 1);
+        let index = match dir {
+            Dir::West => (index + 1).min(RANGES.len() - 1),
+            Dir::East => index.saturating_sub(1),
+            _ => return,
+        };
+        if RANGES[index] != self.days {
+            self.days = RANGES[index];
+            self.update(rq);
+        }
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn load_records() -> Vec<Record> {
+    BatteryHistory::load(BATTERY_HISTORY_PATH)
+        .map_err(|e| eprintln!("Can't load battery history: {:#}.", e))
+        .unwrap_or_default()
+}
+
+fn title(days: u16) -> String {
+    if days == 1 {
+        "Battery History (1 day)".to_string()
+    } else {
+        format!("Battery History ({} days)", days)
+    }
+}
+
+fn summary(records: &[Record], days: u16) -> String {
+    let start = Local::now().timestamp() - days as i64 * 86_400;
+    let first = records.iter().position(|r| r.time >= start).unwrap_or(records.len());
+    match standby_drain(&records[first..]) {
+        Some(drain) => format!("Standby drain: {:.2}% per hour.", drain),
+        None => "No standby recorded.".to_string(),
+    }
+}
+
+impl View for BatteryGraph {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.children[2].rect().includes(start) => {
+                self.change_range(dir, rq);
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
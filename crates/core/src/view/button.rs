@@ -119,4 +119,8 @@ impl View for Button {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
 }
@@ -20,7 +20,7 @@ use crate::view::top_bar::TopBar;
 use crate::view::keyboard::Keyboard;
 use crate::view::menu::{Menu, MenuKind};
 use crate::view::common::{locate_by_id};
-use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
 use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
 use crate::view::{EntryKind, EntryId, ViewId, Id, ID_FEEDER};
 use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
@@ -518,7 +518,7 @@ impl View for Calculator {
                 self.set_margin_width(width, rq, context);
                 true
             },
-            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 => {
+            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 && context.rotate_gesture_enabled() => {
                 let (_, dir) = CURRENT_DEVICE.mirroring_scheme();
                 let n = (4 + (context.display.rotation - dir * quarter_turns)) % 4;
                 hub.send(Event::Select(EntryId::Rotate(n))).ok();
@@ -540,6 +540,10 @@ impl View for Calculator {
                 toggle_clock_menu(self, rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
             Event::ToggleNear(ViewId::MarginWidthMenu, rect) => {
                 self.toggle_margin_width_menu(rect, None, rq, context);
                 true
@@ -0,0 +1,310 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, EntryId, Align};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_LARGE, THICKNESS_SMALL, BORDER_RADIUS_MEDIUM, BORDER_RADIUS_SMALL};
+use super::label::Label;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+const SWATCHES_PER_ROW: usize = 8;
+pub const RECENT_COLORS_MAX: usize = SWATCHES_PER_ROW;
+
+// A grid of color swatches: the gray levels of the display, followed by the
+// custom and the recently used colors. The owner receives
+// `Event::Select(EntryId::SetPenColor)` when a swatch is tapped.
+// Holding a swatch adds its color to the custom colors, or removes it.
+pub struct ColorPicker {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    current: u8,
+}
+
+impl ColorPicker {
+    pub fn new(view_id: ViewId, current: u8, context: &mut Context) -> ColorPicker {
+        let mut color_picker = ColorPicker {
+            id: ID_FEEDER.next(),
+            rect: picker_rect(context),
+            children: Vec::new(),
+            view_id,
+            current,
+        };
+
+        color_picker.build_children(context);
+        color_picker
+    }
+
+    fn build_children(&mut self, context: &mut Context) {
+        let cell = (self.rect.width() as i32 - 2 * margin()) / SWATCHES_PER_ROW as i32;
+        let min_x = self.rect.min.x + margin();
+        let max_x = self.rect.max.x - margin();
+        let mut y = self.rect.min.y + margin();
+
+        self.children.clear();
+
+        for (title, colors) in sections(context) {
+            let label_rect = rect![min_x, y, max_x, y + cell];
+            self.children.push(Box::new(Label::new(label_rect, title.to_string(), Align::Left(0))) as Box<dyn View>);
+            y += cell;
+            for row in colors.chunks(SWATCHES_PER_ROW) {
+                for (i, &color) in row.iter().enumerate() {
+                    let x = min_x + i as i32 * cell;
+                    let swatch_rect = rect![x, y, x + cell, y + cell];
+                    let swatch = Swatch::new(swatch_rect, color, color == self.current);
+                    self.children.push(Box::new(swatch) as Box<dyn View>);
+                }
+                y += cell;
+            }
+        }
+    }
+}
+
+// The picker is centered, and tall enough for all its sections.
+fn picker_rect(context: &Context) -> Rectangle {
+    let (width, height) = context.display.dims;
+    let cell = cell_size(width);
+    let rows: usize = sections(context).iter()
+                                       .map(|(_, colors)| 1 + colors.len().div_ceil(SWATCHES_PER_ROW))
+                                       .sum();
+    let picker_width = SWATCHES_PER_ROW as i32 * cell + 2 * margin();
+    let picker_height = rows as i32 * cell + 2 * margin();
+    let dx = (width as i32 - picker_width) / 2;
+    let dy = (height as i32 - picker_height) / 2;
+    rect![dx, dy, dx + picker_width, dy + picker_height]
+}
+
+fn margin() -> i32 {
+    let dpi = CURRENT_DEVICE.dpi;
+    scale_by_dpi(THICKNESS_LARGE, dpi) as i32 + scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32
+}
+
+fn cell_size(width: u32) -> i32 {
+    let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32;
+    (3 * small_height / 4).min((width as i32 - 2 * margin()) / (SWATCHES_PER_ROW as i32 + 1))
+}
+
+fn sections(context: &Context) -> Vec<(&'static str, Vec<u8>)> {
+    let settings = &context.settings.sketch;
+    [("Grays", palette()),
+     ("Custom", settings.custom_colors.clone()),
+     ("Recent", settings.recent_colors.clone())]
+        .into_iter()
+        .filter(|(_, colors)| !colors.is_empty())
+        .collect()
+}
+
+// The sixteen gray levels of the display, from black to white.
+fn palette() -> Vec<u8> {
+    (0..16).map(|i| i * 17).collect()
+}
+
+// Adds the given color to the custom colors, or removes it if it's already there.
+pub fn toggle_custom_color(custom: &mut Vec<u8>, color: u8) {
+    if let Some(index) = custom.iter().position(|&c| c == color) {
+        custom.remove(index);
+    } else {
+        custom.push(color);
+    }
+}
+
+// Moves or inserts the given color at the front of the recent colors.
+pub fn push_recent_color(recent: &mut Vec<u8>, color: u8) {
+    recent.retain(|&c| c != color);
+    recent.insert(0, color);
+    recent.truncate(RECENT_COLORS_MAX);
+}
+
+impl View for ColorPicker {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(EntryId::SetPenColor(..)) => {
+                bus.push_back(Event::Close(self.view_id));
+                false
+            },
+            Event::Select(EntryId::ToggleCustomColor(color)) => {
+                toggle_custom_color(&mut context.settings.sketch.custom_colors, color);
+                let mut rect = self.rect;
+                self.rect = picker_rect(context);
+                self.build_children(context);
+                rect.absorb(&self.rect);
+                rq.add(RenderData::expose(rect, UpdateMode::Gui));
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) &&
+                                                         !context.kb_rect.includes(center) => {
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { position, .. }) => !context.kb_rect.includes(position),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.rect = picker_rect(context);
+        self.build_children(context);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
+
+struct Swatch {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    color: u8,
+    selected: bool,
+}
+
+impl Swatch {
+    fn new(rect: Rectangle, color: u8, selected: bool) -> Swatch {
+        Swatch {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            color,
+            selected,
+        }
+    }
+}
+
+impl View for Swatch {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::Select(EntryId::SetPenColor(self.color)));
+                true
+            },
+            Event::Gesture(GestureEvent::HoldFingerShort(center, ..)) if self.rect.includes(center) => {
+                bus.push_back(Event::Select(EntryId::ToggleCustomColor(self.color)));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_SMALL, dpi) as i32;
+        let thickness = if self.selected {
+            scale_by_dpi(THICKNESS_LARGE, dpi)
+        } else {
+            scale_by_dpi(THICKNESS_SMALL, dpi)
+        } as u16;
+        let padding = self.rect.width() as i32 / 10;
+        let swatch_rect = rect![self.rect.min.x + padding, self.rect.min.y + padding,
+                                self.rect.max.x - padding, self.rect.max.y - padding];
+
+        fb.draw_rectangle(&self.rect, WHITE);
+        fb.draw_rounded_rectangle_with_border(&swatch_rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness, color: BLACK },
+                                              &self.color);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_recent_color() {
+        let mut recent = Vec::new();
+        push_recent_color(&mut recent, 0x00);
+        push_recent_color(&mut recent, 0x88);
+        assert_eq!(recent, vec![0x88, 0x00]);
+        push_recent_color(&mut recent, 0x00);
+        assert_eq!(recent, vec![0x00, 0x88]);
+        for i in 1..=RECENT_COLORS_MAX as u8 {
+            push_recent_color(&mut recent, i);
+        }
+        assert_eq!(recent.len(), RECENT_COLORS_MAX);
+        assert_eq!(recent[0], RECENT_COLORS_MAX as u8);
+    }
+
+    #[test]
+    fn test_toggle_custom_color() {
+        let mut custom = vec![0x18];
+        toggle_custom_color(&mut custom, 0x28);
+        assert_eq!(custom, vec![0x18, 0x28]);
+        toggle_custom_color(&mut custom, 0x18);
+        assert_eq!(custom, vec![0x28]);
+    }
+
+    #[test]
+    fn test_palette() {
+        let colors = palette();
+        assert_eq!(colors.len(), 16);
+        assert_eq!(colors[0], BLACK);
+        assert_eq!(colors[15], WHITE);
+    }
+}
@@ -1,12 +1,19 @@
-use std::sync::mpsc;
+use std::fs;
 use chrono::Local;
 use crate::device::CURRENT_DEVICE;
 use crate::settings::{ButtonScheme, RotationLock};
 use crate::framebuffer::UpdateMode;
 use crate::geom::{Point, Rectangle};
-use super::{View, RenderQueue, RenderData, ViewId, AppCmd, EntryId, EntryKind};
+use super::{hub, View, Event, Hub, Bus, RenderQueue, RenderData, ViewId, AppCmd, EntryId, EntryKind};
 use super::menu::{Menu, MenuKind};
 use super::notification::Notification;
+use super::home::Home;
+use super::timer_chip::TimerChip;
+use super::metrics_overlay::MetricsOverlay;
+use super::apps::MINI_APPS;
+use crate::timezone::TIMEZONES;
+use crate::notes::{NoteKind, title_and_excerpt};
+use crate::timer::{TimerState, TimerPhase};
 use crate::context::Context;
 
 pub fn shift(view: &mut dyn View, delta: Point) {
@@ -49,10 +56,11 @@ pub fn overlapping_rectangle(view: &dyn View) -> Rectangle {
 // Transfer the notifications from the view1 to the view2.
 pub fn transfer_notifications(view1: &mut dyn View, view2: &mut dyn View, rq: &mut RenderQueue, context: &mut Context) {
     for index in (0..view1.len()).rev() {
-        if view1.child(index).is::<Notification>() {
+        if view1.child(index).is::<Notification>() || view1.child(index).is::<TimerChip>() ||
+           view1.child(index).is::<MetricsOverlay>() {
             let mut child = view1.children_mut().remove(index);
             if view2.rect() != view1.rect() {
-                let (tx, _rx) = mpsc::channel();
+                let (tx, _rx) = hub::channel();
                 child.resize(*view2.rect(), &tx, rq, context);
             }
             view2.children_mut().push(child);
@@ -72,71 +80,144 @@ pub fn toggle_main_menu(view: &mut dyn View, rect: Rectangle, enable: Option<boo
             return;
         }
 
-        let rotation = CURRENT_DEVICE.to_canonical(context.display.rotation);
-        let rotate = (0..4).map(|n|
-            EntryKind::RadioButton((n as i16 * 90).to_string(),
-                                   EntryId::Rotate(CURRENT_DEVICE.from_canonical(n)),
-                                   n == rotation)
-        ).collect::<Vec<EntryKind>>();
+        let entries = main_menu_entries(context);
+        let main_menu = Menu::new(rect, ViewId::MainMenu, MenuKind::DropDown, entries, context);
+        rq.add(RenderData::new(main_menu.id(), *main_menu.rect(), UpdateMode::Gui));
+        view.children_mut().push(Box::new(main_menu) as Box<dyn View>);
+    }
+}
+
+// The entries of the main menu, which also appear in the results of the global search.
+pub fn main_menu_entries(context: &Context) -> Vec<EntryKind> {
+    let rotation = CURRENT_DEVICE.to_canonical(context.display.rotation);
+    let rotate = (0..4).map(|n|
+        EntryKind::RadioButton((n as i16 * 90).to_string(),
+                               EntryId::Rotate(CURRENT_DEVICE.from_canonical(n)),
+                               n == rotation)
+    ).collect::<Vec<EntryKind>>();
 
+    if context.settings.kiosk.enabled {
         let apps = vec![EntryKind::Command("Dictionary".to_string(),
                                            EntryId::Launch(AppCmd::Dictionary { query: "".to_string(), language: "".to_string() })),
                         EntryKind::Command("Calculator".to_string(),
-                                           EntryId::Launch(AppCmd::Calculator)),
-                        EntryKind::Command("Sketch".to_string(),
-                                           EntryId::Launch(AppCmd::Sketch)),
-                        EntryKind::Separator,
-                        EntryKind::Command("Touch Events".to_string(),
-                                           EntryId::Launch(AppCmd::TouchEvents)),
-                        EntryKind::Command("Rotation Values".to_string(),
-                                           EntryId::Launch(AppCmd::RotationValues))];
-        let mut entries = vec![EntryKind::Command("About".to_string(),
-                                                  EntryId::About),
-                               EntryKind::Command("System Info".to_string(),
-                                                  EntryId::SystemInfo),
-                               EntryKind::Separator,
-                               EntryKind::CheckBox("Invert Colors".to_string(),
-                                                   EntryId::ToggleInverted,
-                                                   context.fb.inverted()),
-                               EntryKind::CheckBox("Enable WiFi".to_string(),
-                                                   EntryId::ToggleWifi,
-                                                   context.settings.wifi),
-                               EntryKind::Separator,
-                               EntryKind::SubMenu("Rotate".to_string(), rotate),
-                               EntryKind::Command("Take Screenshot".to_string(),
-                                                  EntryId::TakeScreenshot),
-                               EntryKind::Separator,
-                               EntryKind::SubMenu("Applications".to_string(), apps),
-                               EntryKind::Separator];
-
-        entries.push(EntryKind::Command("Reboot".to_string(), EntryId::Reboot));
-        entries.push(EntryKind::Command("Quit".to_string(), EntryId::Quit));
-
-        if CURRENT_DEVICE.has_page_turn_buttons() {
-            let button_scheme = context.settings.button_scheme;
-            let button_schemes = vec![
-                EntryKind::RadioButton(ButtonScheme::Natural.to_string(), EntryId::SetButtonScheme(ButtonScheme::Natural), button_scheme == ButtonScheme::Natural),
-                EntryKind::RadioButton(ButtonScheme::Inverted.to_string(), EntryId::SetButtonScheme(ButtonScheme::Inverted), button_scheme == ButtonScheme::Inverted),
-            ];
-            entries.insert(5, EntryKind::SubMenu("Button Scheme".to_string(), button_schemes));
-        }
-
-        if CURRENT_DEVICE.has_gyroscope() {
-            let rotation_lock = context.settings.rotation_lock;
-            let gyro = vec![
-                EntryKind::RadioButton("Auto".to_string(), EntryId::SetRotationLock(None), rotation_lock.is_none()),
-                EntryKind::Separator,
-                EntryKind::RadioButton("Portrait".to_string(), EntryId::SetRotationLock(Some(RotationLock::Portrait)), rotation_lock == Some(RotationLock::Portrait)),
-                EntryKind::RadioButton("Landscape".to_string(), EntryId::SetRotationLock(Some(RotationLock::Landscape)), rotation_lock == Some(RotationLock::Landscape)),
-                EntryKind::RadioButton("Ignore".to_string(), EntryId::SetRotationLock(Some(RotationLock::Current)), rotation_lock == Some(RotationLock::Current)),
-            ];
-            entries.insert(5, EntryKind::SubMenu("Gyroscope".to_string(), gyro));
-        }
+                                           EntryId::Launch(AppCmd::Calculator))];
+        let entries = vec![EntryKind::Command("About".to_string(),
+                                              EntryId::About),
+                           EntryKind::Separator,
+                           EntryKind::CheckBox("Invert Colors".to_string(),
+                                               EntryId::ToggleInverted,
+                                               context.fb.inverted()),
+                           EntryKind::SubMenu("Rotate".to_string(), rotate),
+                           EntryKind::Separator,
+                           EntryKind::SubMenu("Applications".to_string(), apps),
+                           EntryKind::Separator,
+                           EntryKind::Command("Unlock".to_string(), EntryId::UnlockKiosk)];
+        return entries;
+    }
 
-        let main_menu = Menu::new(rect, ViewId::MainMenu, MenuKind::DropDown, entries, context);
-        rq.add(RenderData::new(main_menu.id(), *main_menu.rect(), UpdateMode::Gui));
-        view.children_mut().push(Box::new(main_menu) as Box<dyn View>);
+    let apps = vec![EntryKind::Command("Dictionary".to_string(),
+                                       EntryId::Launch(AppCmd::Dictionary { query: "".to_string(), language: "".to_string() })),
+                    EntryKind::Command("Calculator".to_string(),
+                                       EntryId::Launch(AppCmd::Calculator)),
+                    EntryKind::Command("Sketch".to_string(),
+                                       EntryId::Launch(AppCmd::Sketch)),
+                    EntryKind::Command("Flashcards".to_string(),
+                                       EntryId::Launch(AppCmd::Flashcards)),
+                    EntryKind::Command("Agenda".to_string(),
+                                       EntryId::Launch(AppCmd::Agenda)),
+                    EntryKind::Command("Notes".to_string(),
+                                       EntryId::Launch(AppCmd::Notes { note: None })),
+                    EntryKind::SubMenu("Games".to_string(),
+                                       MINI_APPS.iter().map(|app| {
+                                           EntryKind::Command(app.name.to_string(),
+                                                              EntryId::Launch(AppCmd::MiniApp { name: app.name }))
+                                       }).collect()),
+                    EntryKind::Separator,
+                    EntryKind::Command("Battery History".to_string(),
+                                       EntryId::Launch(AppCmd::BatteryGraph)),
+                    EntryKind::Command("Storage".to_string(),
+                                       EntryId::Launch(AppCmd::Storage)),
+                    EntryKind::Command("Downloads".to_string(),
+                                       EntryId::Launch(AppCmd::Downloads)),
+                    EntryKind::Command("Background Tasks".to_string(),
+                                       EntryId::ShowJobs),
+                    EntryKind::Command("Network Sign-In".to_string(),
+                                       EntryId::Launch(AppCmd::Portal)),
+                    EntryKind::Command("Touch Events".to_string(),
+                                       EntryId::Launch(AppCmd::TouchEvents)),
+                    EntryKind::Command("Rotation Values".to_string(),
+                                       EntryId::Launch(AppCmd::RotationValues)),
+                    EntryKind::Command("Pen Calibration".to_string(),
+                                       EntryId::Launch(AppCmd::PenCalibration))];
+    let mut entries = vec![EntryKind::Command("About".to_string(),
+                                              EntryId::About),
+                           EntryKind::Command("System Info".to_string(),
+                                              EntryId::SystemInfo),
+                           EntryKind::Separator,
+                           EntryKind::CheckBox("Invert Colors".to_string(),
+                                               EntryId::ToggleInverted,
+                                               context.fb.inverted()),
+                           EntryKind::CheckBox("Enable WiFi".to_string(),
+                                               EntryId::ToggleWifi,
+                                               context.settings.wifi),
+                           EntryKind::Separator,
+                           EntryKind::SubMenu("Rotate".to_string(), rotate),
+                           EntryKind::Command("Take Screenshot".to_string(),
+                                              EntryId::TakeScreenshot),
+                           EntryKind::CheckBox("Show Metrics".to_string(),
+                                               EntryId::ToggleMetrics,
+                                               context.metrics.is_enabled()),
+                           EntryKind::Separator,
+                           EntryKind::SubMenu("Applications".to_string(), apps),
+                           EntryKind::Separator];
+
+    if !context.settings.notes.pinned.is_empty() {
+        let dir = context.library.home.join(&context.settings.notes.path);
+        let pinned = context.settings.notes.pinned.iter().map(|name| {
+            let mut title = name.split('.').next().unwrap_or(name).to_string();
+            let app_cmd = if NoteKind::from_name(name) == Some(NoteKind::Handwritten) {
+                AppCmd::HandwrittenNote { name: Some(name.clone()) }
+            } else {
+                if let Ok(text) = fs::read_to_string(dir.join(name)) {
+                    title = Some(title_and_excerpt(&text).0).filter(|t| !t.is_empty()).unwrap_or(title);
+                }
+                AppCmd::Notes { note: Some(name.clone()) }
+            };
+            EntryKind::Command(title, EntryId::Launch(app_cmd))
+        }).collect::<Vec<EntryKind>>();
+        let index = entries.len() - 1;
+        entries.insert(index, EntryKind::SubMenu("Pinned Notes".to_string(), pinned));
+    }
+
+    if !context.settings.kiosk.pin.is_empty() {
+        entries.push(EntryKind::Command("Kiosk Mode".to_string(), EntryId::LockKiosk));
     }
+
+    entries.push(EntryKind::Command("Reboot".to_string(), EntryId::Reboot));
+    entries.push(EntryKind::Command("Quit".to_string(), EntryId::Quit));
+
+    if CURRENT_DEVICE.has_page_turn_buttons() {
+        let button_scheme = context.settings.button_scheme;
+        let button_schemes = vec![
+            EntryKind::RadioButton(ButtonScheme::Natural.to_string(), EntryId::SetButtonScheme(ButtonScheme::Natural), button_scheme == ButtonScheme::Natural),
+            EntryKind::RadioButton(ButtonScheme::Inverted.to_string(), EntryId::SetButtonScheme(ButtonScheme::Inverted), button_scheme == ButtonScheme::Inverted),
+        ];
+        entries.insert(5, EntryKind::SubMenu("Button Scheme".to_string(), button_schemes));
+    }
+
+    if context.has_orientation_sensor() {
+        let rotation_lock = context.settings.rotation_lock;
+        let gyro = vec![
+            EntryKind::RadioButton("Auto".to_string(), EntryId::SetRotationLock(None), rotation_lock.is_none()),
+            EntryKind::Separator,
+            EntryKind::RadioButton("Portrait".to_string(), EntryId::SetRotationLock(Some(RotationLock::Portrait)), rotation_lock == Some(RotationLock::Portrait)),
+            EntryKind::RadioButton("Landscape".to_string(), EntryId::SetRotationLock(Some(RotationLock::Landscape)), rotation_lock == Some(RotationLock::Landscape)),
+            EntryKind::RadioButton("Ignore".to_string(), EntryId::SetRotationLock(Some(RotationLock::Current)), rotation_lock == Some(RotationLock::Current)),
+        ];
+        entries.insert(5, EntryKind::SubMenu("Gyroscope".to_string(), gyro));
+    }
+
+    entries
 }
 
 pub fn toggle_battery_menu(view: &mut dyn View, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
@@ -183,13 +264,217 @@ pub fn toggle_clock_menu(view: &mut dyn View, rect: Rectangle, enable: Option<bo
             return;
         }
         let text = Local::now().format(&context.settings.date_format).to_string();
-        let entries = vec![EntryKind::Message(text, None)];
+        let current = context.settings.clock.timezone.as_deref().unwrap_or_default();
+        let timezones = TIMEZONES.iter().map(|(region, zones)| {
+            EntryKind::SubMenu(region.to_string(),
+                               zones.iter().map(|(name, _)| {
+                                   let label = name.rsplit('/').next().unwrap_or(name).replace('_', " ");
+                                   EntryKind::RadioButton(label, EntryId::SetTimezone(name.to_string()), *name == current)
+                               }).collect())
+        }).collect::<Vec<EntryKind>>();
+        let entries = vec![EntryKind::Message(text, None),
+                           EntryKind::Command("Calendar".to_string(), EntryId::ShowCalendar),
+                           EntryKind::Separator,
+                           EntryKind::SubMenu("Time Zone".to_string(), timezones),
+                           EntryKind::Command("Synchronize Clock".to_string(), EntryId::SyncClock),
+                           EntryKind::Separator];
+        let entries = [entries, timer_entries(context)].concat();
         let clock_menu = Menu::new(rect, ViewId::ClockMenu, MenuKind::DropDown, entries, context);
         rq.add(RenderData::new(clock_menu.id(), *clock_menu.rect(), UpdateMode::Gui));
         view.children_mut().push(Box::new(clock_menu) as Box<dyn View>);
     }
 }
 
+fn timer_entries(context: &Context) -> Vec<EntryKind> {
+    match context.timer.state {
+        TimerState::Idle => vec![EntryKind::Command("Start Timer".to_string(), EntryId::StartTimer)],
+        TimerState::Running { .. } => vec![EntryKind::Command("Pause Timer".to_string(), EntryId::PauseTimer),
+                                           EntryKind::Command("Stop Timer".to_string(), EntryId::StopTimer)],
+        TimerState::Paused { .. } => vec![EntryKind::Command("Resume Timer".to_string(), EntryId::ResumeTimer),
+                                          EntryKind::Command("Stop Timer".to_string(), EntryId::StopTimer)],
+    }
+}
+
+pub fn toggle_timer_menu(view: &mut dyn View, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(index) = locate_by_id(view, ViewId::TimerMenu) {
+        if let Some(true) = enable {
+            return;
+        }
+        rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+        view.children_mut().remove(index);
+    } else {
+        if let Some(false) = enable {
+            return;
+        }
+        let text = match context.timer.sessions {
+            1 => "1 focus session".to_string(),
+            n => format!("{} focus sessions", n),
+        };
+        let entries = [vec![EntryKind::Message(text, None)], timer_entries(context)].concat();
+        let timer_menu = Menu::new(rect, ViewId::TimerMenu, MenuKind::DropDown, entries, context);
+        rq.add(RenderData::new(timer_menu.id(), *timer_menu.rect(), UpdateMode::Gui));
+        view.children_mut().push(Box::new(timer_menu) as Box<dyn View>);
+    }
+}
+
+// Adds, updates or removes the timer chip, according to the state of the timer.
+pub fn update_timer_chip(view: &mut dyn View, rq: &mut RenderQueue, context: &mut Context) {
+    let text = context.timer.label(Local::now());
+    match (locate::<TimerChip>(view), text) {
+        (Some(index), Some(text)) => {
+            if let Some(chip) = view.child_mut(index).downcast_mut::<TimerChip>() {
+                chip.update(text, rq, context);
+            }
+        },
+        (Some(index), None) => {
+            rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+            view.children_mut().remove(index);
+        },
+        (None, Some(text)) => {
+            let chip = TimerChip::new(text, rq, context);
+            view.children_mut().push(Box::new(chip) as Box<dyn View>);
+        },
+        (None, None) => (),
+    }
+}
+
+// Adds, updates or removes the metrics overlay, according to the state of the metrics.
+pub fn update_metrics_overlay(view: &mut dyn View, rq: &mut RenderQueue, context: &mut Context) {
+    match (locate::<MetricsOverlay>(view), context.metrics.is_enabled()) {
+        (Some(index), true) => {
+            if let Some(overlay) = view.child_mut(index).downcast_mut::<MetricsOverlay>() {
+                overlay.update(rq, context);
+            }
+        },
+        (Some(index), false) => {
+            rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+            view.children_mut().remove(index);
+        },
+        (None, true) => {
+            let overlay = MetricsOverlay::new(rq, context);
+            view.children_mut().push(Box::new(overlay) as Box<dyn View>);
+        },
+        (None, false) => (),
+    }
+}
+
+// Moves the timer to its next phase when the current one is over.
+pub fn poll_timer(view: &mut dyn View, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(phase) = context.timer.poll(&context.settings.timer, Local::now()) {
+        let text = match phase {
+            TimerPhase::Focus => {
+                hub.send(Event::FocusEnded).ok();
+                context.timer.schedule(hub);
+                "Time for a break."
+            },
+            TimerPhase::Break => "The break is over.",
+        };
+        let notif = Notification::new(text.to_string(), hub, rq, context);
+        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+    }
+    update_timer_chip(view, rq, context);
+}
+
+// Adds the downloaded document, if any, to the library.
+pub fn finish_download(view: &mut dyn View, id: u64, error: Option<String>, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(info) = context.downloads.finish(id, error, &context.library.home, hub) {
+        let msg = format!("Added {}.", info.title());
+        context.library.add_document(info.clone());
+        // The other views reseed the library when they're back on top.
+        if view.is::<Home>() {
+            view.handle_event(&Event::Reseed, hub, bus, rq, context);
+        }
+        let notif = Notification::with_action(msg, "Open".to_string(),
+                                              Event::Open(Box::new(info)),
+                                              hub, rq, context);
+        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+    }
+}
+
+pub fn toggle_weather_menu(view: &mut dyn View, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(index) = locate_by_id(view, ViewId::WeatherMenu) {
+        if let Some(true) = enable {
+            return;
+        }
+        rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+        view.children_mut().remove(index);
+    } else {
+        if let Some(false) = enable {
+            return;
+        }
+        let mut entries = Vec::new();
+        if let Some(forecast) = context.weather.forecast.as_ref() {
+            let location = &context.settings.weather.location;
+            let updated = forecast.updated.format("%H:%M");
+            let text = if location.is_empty() {
+                format!("Updated at {}", updated)
+            } else {
+                format!("{}, updated at {}", location, updated)
+            };
+            entries.push(EntryKind::Message(text, None));
+            entries.push(EntryKind::Message(format!("{}, wind {:.0} km/h", forecast.summary(), forecast.wind_speed), None));
+            entries.push(EntryKind::Separator);
+            entries.extend(forecast.days_summaries().into_iter().map(|text| EntryKind::Message(text, None)));
+        } else {
+            entries.push(EntryKind::Message("No forecast yet.".to_string(), None));
+        }
+        entries.push(EntryKind::Separator);
+        entries.push(EntryKind::Command("Refresh".to_string(), EntryId::RefreshWeather));
+        let weather_menu = Menu::new(rect, ViewId::WeatherMenu, MenuKind::DropDown, entries, context);
+        rq.add(RenderData::new(weather_menu.id(), *weather_menu.rect(), UpdateMode::Gui));
+        view.children_mut().push(Box::new(weather_menu) as Box<dyn View>);
+    }
+}
+
+// Updates the progress bar of the notification identified by `view_id`.
+pub fn update_progress(view: &mut dyn View, view_id: ViewId, done: usize, total: usize, rq: &mut RenderQueue) -> bool {
+    if let Some(index) = locate_by_id(view, view_id) {
+        if let Some(notif) = view.child_mut(index).downcast_mut::<Notification>() {
+            notif.set_progress(done, total, rq);
+            return true;
+        }
+    }
+    false
+}
+
+pub fn close_progress(view: &mut dyn View, view_id: ViewId, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(index) = locate_by_id(view, view_id) {
+        rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+        context.notifications.release(view.child(index).id());
+        view.children_mut().remove(index);
+    }
+}
+
+pub fn toggle_notifications_menu(view: &mut dyn View, rect: Rectangle, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(index) = locate_by_id(view, ViewId::NotificationsMenu) {
+        if let Some(true) = enable {
+            return;
+        }
+        rq.add(RenderData::expose(*view.child(index).rect(), UpdateMode::Gui));
+        view.children_mut().remove(index);
+    } else {
+        if let Some(false) = enable {
+            return;
+        }
+        let history = context.notifications.history();
+        let mut entries = history.iter().map(|record| {
+            let text = format!("{} {}", record.time.format(&context.settings.time_format), record.text);
+            EntryKind::Message(text, None)
+        }).collect::<Vec<EntryKind>>();
+        if entries.is_empty() {
+            entries.push(EntryKind::Message("No Notifications".to_string(), None));
+        } else {
+            entries.push(EntryKind::Separator);
+            entries.push(EntryKind::Command("Clear".to_string(), EntryId::ClearNotifications));
+        }
+        context.notifications.mark_read();
+        hub.send(Event::NotificationsChanged).ok();
+        let notifications_menu = Menu::new(rect, ViewId::NotificationsMenu, MenuKind::DropDown, entries, context);
+        rq.add(RenderData::new(notifications_menu.id(), *notifications_menu.rect(), UpdateMode::Gui));
+        view.children_mut().push(Box::new(notifications_menu) as Box<dyn View>);
+    }
+}
+
 pub fn toggle_input_history_menu(view: &mut dyn View, id: ViewId, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
     if let Some(index) = locate_by_id(view, ViewId::InputHistoryMenu) {
         if let Some(true) = enable {
@@ -232,10 +517,14 @@ pub fn toggle_keyboard_layout_menu(view: &mut dyn View, rect: Rectangle, enable:
         if let Some(false) = enable {
             return;
         }
-        let entries = context.keyboard_layouts.keys()
-                             .map(|s| EntryKind::Command(s.to_string(),
-                                                         EntryId::SetKeyboardLayout(s.to_string())))
-                             .collect::<Vec<EntryKind>>();
+        let mut entries = context.keyboard_layouts.keys()
+                                 .map(|s| EntryKind::Command(s.to_string(),
+                                                             EntryId::SetKeyboardLayout(s.to_string())))
+                                 .collect::<Vec<EntryKind>>();
+        entries.push(EntryKind::Separator);
+        entries.push(EntryKind::CheckBox("Handwriting".to_string(),
+                                         EntryId::ToggleHandwritingPad,
+                                         context.settings.handwriting.pad));
         let keyboard_layout_menu = Menu::new(rect, ViewId::KeyboardLayoutMenu, MenuKind::Contextual, entries, context);
         rq.add(RenderData::new(keyboard_layout_menu.id(), *keyboard_layout_menu.rect(), UpdateMode::Gui));
         view.children_mut().push(Box::new(keyboard_layout_menu) as Box<dyn View>);
@@ -0,0 +1,402 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec, CycleDir, Dir};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_LARGE, THICKNESS_MEDIUM, BORDER_RADIUS_MEDIUM};
+use super::label::Label;
+use super::icon::Icon;
+use super::button::Button;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE, TEXT_NORMAL, TEXT_INVERTED_HARD};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+pub(crate) const WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+const MINUTES_STEP: u32 = 5;
+const LABEL_VALIDATE: &str = "OK";
+const LABEL_CANCEL: &str = "Cancel";
+
+const MONTH_LABEL: usize = 1;
+
+// A month view for picking a day, with optional hour and minute steppers.
+// The owner receives `Event::SetDate` when the user validates, and
+// `Event::Close` through the bus when the picker should be removed.
+pub struct DatePicker {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    // The first day of the displayed month.
+    month: NaiveDate,
+    selected: NaiveDate,
+    time: Option<NaiveTime>,
+    today: NaiveDate,
+}
+
+struct Layout {
+    header: Rectangle,
+    weekdays: Rectangle,
+    grid: Rectangle,
+    time: Option<Rectangle>,
+    buttons: Rectangle,
+}
+
+impl DatePicker {
+    // When `with_time` is true, the hours and minutes can also be picked.
+    pub fn new(view_id: ViewId, value: NaiveDateTime, with_time: bool, context: &mut Context) -> DatePicker {
+        let (width, height) = context.display.dims;
+        let cell = cell_size(width);
+        let rows = if with_time { 10 } else { 9 };
+        let picker_width = 7 * cell + 2 * margin();
+        let picker_height = rows * cell + 2 * margin();
+        let dx = (width as i32 - picker_width) / 2;
+        let dy = (height as i32 - picker_height) / 2;
+        let rect = rect![dx, dy, dx + picker_width, dy + picker_height];
+
+        let selected = value.date();
+        let time = if with_time { Some(value.time()) } else { None };
+
+        let mut date_picker = DatePicker {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            view_id,
+            month: first_of_month(selected),
+            selected,
+            time,
+            today: Local::now().date_naive(),
+        };
+
+        date_picker.build_children(context);
+        date_picker
+    }
+
+    fn layout(&self) -> Layout {
+        let cell = (self.rect.width() as i32 - 2 * margin()) / 7;
+        let min_x = self.rect.min.x + margin();
+        let max_x = self.rect.max.x - margin();
+        let mut y = self.rect.min.y + margin();
+        let mut row = |count: i32| {
+            let rect = rect![min_x, y, max_x, y + count * cell];
+            y += count * cell;
+            rect
+        };
+        let header = row(1);
+        let weekdays = row(1);
+        let grid = row(6);
+        let time = self.time.map(|_| row(1));
+        let buttons = row(1);
+        Layout { header, weekdays, grid, time, buttons }
+    }
+
+    fn build_children(&mut self, context: &mut Context) {
+        let layout = self.layout();
+        let side = layout.header.height() as i32;
+        let header = layout.header;
+        let prev_rect = rect![header.min.x, header.min.y, header.min.x + side, header.max.y];
+        let next_rect = rect![header.max.x - side, header.min.y, header.max.x, header.max.y];
+        let label_rect = rect![prev_rect.max.x, header.min.y, next_rect.min.x, header.max.y];
+
+        let button_width = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+            [LABEL_VALIDATE, LABEL_CANCEL].iter()
+                .map(|label| font.plan(label, None, None).width)
+                .max().unwrap_or(0) + 2 * font.em() as i32
+        };
+        let buttons = layout.buttons;
+        let padding = buttons.height() as i32 / 8;
+        let cancel_rect = rect![buttons.min.x, buttons.min.y + padding,
+                                buttons.min.x + button_width, buttons.max.y - padding];
+        let validate_rect = rect![buttons.max.x - button_width, buttons.min.y + padding,
+                                  buttons.max.x, buttons.max.y - padding];
+
+        self.children = vec![
+            Box::new(Icon::new("angle-left", prev_rect, Event::Page(CycleDir::Previous))) as Box<dyn View>,
+            Box::new(Label::new(label_rect, month_name(self.month), Align::Center)) as Box<dyn View>,
+            Box::new(Icon::new("angle-right", next_rect, Event::Page(CycleDir::Next))) as Box<dyn View>,
+            Box::new(Button::new(cancel_rect, Event::Cancel, LABEL_CANCEL.to_string())) as Box<dyn View>,
+            Box::new(Button::new(validate_rect, Event::Validate, LABEL_VALIDATE.to_string())) as Box<dyn View>,
+        ];
+    }
+
+    fn set_month(&mut self, month: NaiveDate, rq: &mut RenderQueue) {
+        self.month = month;
+        if let Some(label) = self.children[MONTH_LABEL].downcast_mut::<Label>() {
+            label.update(&month_name(month), rq);
+        }
+        rq.add(RenderData::new(self.id, self.layout().grid, UpdateMode::Gui));
+    }
+
+    fn value(&self) -> NaiveDateTime {
+        self.selected.and_time(self.time.unwrap_or(NaiveTime::MIN))
+    }
+
+    fn close(&self, bus: &mut Bus) {
+        bus.push_back(Event::Close(self.view_id));
+    }
+
+    fn day_at(&self, grid: &Rectangle, x: i32, y: i32) -> Option<NaiveDate> {
+        let cell = grid.width() as i32 / 7;
+        let column = ((x - grid.min.x) / cell).clamp(0, 6) as usize;
+        let row = ((y - grid.min.y) / cell).clamp(0, 5) as usize;
+        month_grid(self.month)[row][column]
+            .and_then(|day| NaiveDate::from_ymd_opt(self.month.year(), self.month.month(), day))
+    }
+
+    fn step_time(&mut self, part: i32, rq: &mut RenderQueue, time_rect: Rectangle) {
+        if let Some(time) = self.time {
+            let minute = time.minute() - time.minute() % MINUTES_STEP;
+            let delta = match part {
+                0 => Duration::hours(-1),
+                2 => Duration::hours(1),
+                3 => Duration::minutes(-(MINUTES_STEP as i64)),
+                5 => Duration::minutes(MINUTES_STEP as i64),
+                _ => return,
+            };
+            let time = time.with_minute(minute).unwrap_or(time);
+            // The time wraps around midnight without changing the day.
+            self.time = Some(time.overflowing_add_signed(delta).0);
+            rq.add(RenderData::new(self.id, time_rect, UpdateMode::Gui));
+        }
+    }
+}
+
+fn margin() -> i32 {
+    let dpi = CURRENT_DEVICE.dpi;
+    scale_by_dpi(THICKNESS_LARGE, dpi) as i32 + scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32
+}
+
+fn cell_size(width: u32) -> i32 {
+    let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32;
+    (3 * small_height / 4).min((width as i32 - 2 * margin()) / 8)
+}
+
+pub(crate) fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+pub(crate) fn shift_month(month: NaiveDate, dir: CycleDir) -> NaiveDate {
+    let (year, month) = match (dir, month.month()) {
+        (CycleDir::Next, 12) => (month.year() + 1, 1),
+        (CycleDir::Next, m) => (month.year(), m + 1),
+        (CycleDir::Previous, 1) => (month.year() - 1, 12),
+        (CycleDir::Previous, m) => (month.year(), m - 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(NaiveDate::MIN)
+}
+
+fn days_in_month(month: NaiveDate) -> u32 {
+    let next = shift_month(month, CycleDir::Next);
+    next.signed_duration_since(month).num_days() as u32
+}
+
+fn month_name(month: NaiveDate) -> String {
+    month.format("%B %Y").to_string()
+}
+
+// The days of the month, on six rows of weeks starting on Monday.
+pub(crate) fn month_grid(month: NaiveDate) -> [[Option<u32>; 7]; 6] {
+    let mut grid = [[None; 7]; 6];
+    let offset = month.weekday().num_days_from_monday();
+    for day in 1..=days_in_month(month) {
+        let index = (offset + day - 1) as usize;
+        grid[index / 7][index % 7] = Some(day);
+    }
+    grid
+}
+
+impl View for DatePicker {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Page(dir) => {
+                self.set_month(shift_month(self.month, dir), rq);
+                true
+            },
+            Event::Validate => {
+                bus.push_back(Event::SetDate(self.view_id, self.value()));
+                self.close(bus);
+                true
+            },
+            Event::Cancel => {
+                self.close(bus);
+                true
+            },
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.rect.includes(start) => {
+                match dir {
+                    Dir::West | Dir::North => self.set_month(shift_month(self.month, CycleDir::Next), rq),
+                    Dir::East | Dir::South => self.set_month(shift_month(self.month, CycleDir::Previous), rq),
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let layout = self.layout();
+                if layout.grid.includes(center) {
+                    if let Some(day) = self.day_at(&layout.grid, center.x, center.y) {
+                        self.selected = day;
+                        rq.add(RenderData::new(self.id, layout.grid, UpdateMode::Gui));
+                    }
+                } else if let Some(time_rect) = layout.time.filter(|r| r.includes(center)) {
+                    let part = 6 * (center.x - time_rect.min.x) / time_rect.width() as i32;
+                    self.step_time(part, rq, time_rect);
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if !context.kb_rect.includes(center) => {
+                self.close(bus);
+                true
+            },
+            Event::Device(DeviceEvent::Finger { position, .. }) => !context.kb_rect.includes(position),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let layout = self.layout();
+        let cell = layout.grid.width() as i32 / 7;
+
+        {
+            let font = font_from_style(fonts, &BOLD_STYLE, dpi);
+            let x_height = font.x_heights.0 as i32;
+            for (i, name) in WEEKDAYS.iter().enumerate() {
+                let plan = font.plan(name, None, None);
+                let x = layout.weekdays.min.x + i as i32 * cell + (cell - plan.width) / 2;
+                let y = layout.weekdays.max.y - (cell - x_height) / 2;
+                font.render(fb, TEXT_NORMAL[1], &plan, pt!(x, y));
+            }
+        }
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let grid = month_grid(self.month);
+
+        for (row, week) in grid.iter().enumerate() {
+            for (column, day) in week.iter().enumerate() {
+                let day = if let Some(day) = day { *day } else { continue };
+                let date = NaiveDate::from_ymd_opt(self.month.year(), self.month.month(), day);
+                let x_min = layout.grid.min.x + column as i32 * cell;
+                let y_min = layout.grid.min.y + row as i32 * cell;
+                let day_rect = rect![x_min + cell / 8, y_min + cell / 8,
+                                     x_min + cell - cell / 8, y_min + cell - cell / 8];
+                let scheme = if date == Some(self.selected) {
+                    fb.draw_rounded_rectangle(&day_rect, &CornerSpec::Uniform(day_rect.width() as i32 / 2), BLACK);
+                    TEXT_INVERTED_HARD
+                } else {
+                    if date == Some(self.today) {
+                        fb.draw_rounded_rectangle_with_border(&day_rect,
+                                                              &CornerSpec::Uniform(day_rect.width() as i32 / 2),
+                                                              &BorderSpec { thickness, color: BLACK },
+                                                              &WHITE);
+                    }
+                    TEXT_NORMAL
+                };
+                let plan = font.plan(day.to_string(), None, None);
+                let pt = pt!(x_min + (cell - plan.width) / 2, y_min + (cell + x_height) / 2);
+                font.render(fb, scheme[1], &plan, pt);
+            }
+        }
+
+        if let (Some(time), Some(time_rect)) = (self.time, layout.time) {
+            let part = time_rect.width() as i32 / 6;
+            let labels = ["−".to_string(), format!("{:02}", time.hour()), "+".to_string(),
+                          "−".to_string(), format!("{:02}", time.minute()), "+".to_string()];
+            for (i, label) in labels.iter().enumerate() {
+                let plan = font.plan(label, None, None);
+                let x = time_rect.min.x + i as i32 * part + (part - plan.width) / 2;
+                let y = time_rect.max.y - (time_rect.height() as i32 - x_height) / 2;
+                font.render(fb, TEXT_NORMAL[1], &plan, pt!(x, y));
+            }
+            let plan = font.plan(":", None, None);
+            let x = time_rect.min.x + 3 * part - plan.width / 2;
+            let y = time_rect.max.y - (time_rect.height() as i32 - x_height) / 2;
+            font.render(fb, TEXT_NORMAL[1], &plan, pt!(x, y));
+        }
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let (picker_width, picker_height) = (self.rect.width() as i32, self.rect.height() as i32);
+        let dx = (width as i32 - picker_width) / 2;
+        let dy = (height as i32 - picker_height) / 2;
+        self.rect = rect![dx, dy, dx + picker_width, dy + picker_height];
+        self.build_children(context);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_shift_month() {
+        assert_eq!(shift_month(date(2024, 12, 1), CycleDir::Next), date(2025, 1, 1));
+        assert_eq!(shift_month(date(2024, 1, 1), CycleDir::Previous), date(2023, 12, 1));
+        assert_eq!(shift_month(date(2024, 5, 1), CycleDir::Next), date(2024, 6, 1));
+        assert_eq!(days_in_month(date(2024, 2, 1)), 29);
+        assert_eq!(days_in_month(date(2023, 2, 1)), 28);
+        assert_eq!(days_in_month(date(2023, 12, 1)), 31);
+    }
+
+    #[test]
+    fn test_month_grid() {
+        // The first of October 2026 is a Thursday.
+        let grid = month_grid(date(2026, 10, 1));
+        assert_eq!(grid[0], [None, None, None, Some(1), Some(2), Some(3), Some(4)]);
+        assert_eq!(grid[4], [Some(26), Some(27), Some(28), Some(29), Some(30), Some(31), None]);
+        assert_eq!(grid[5], [None; 7]);
+        // The first of June 2025 is a Sunday.
+        let grid = month_grid(date(2025, 6, 1));
+        assert_eq!(grid[0][6], Some(1));
+        assert_eq!(grid[5][0], Some(30));
+    }
+}
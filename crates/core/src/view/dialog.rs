@@ -205,6 +205,10 @@ impl View for Dialog {
         self.id
     }
 
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
     fn view_id(&self) -> Option<ViewId> {
         Some(self.view_id)
     }
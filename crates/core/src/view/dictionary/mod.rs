@@ -7,15 +7,17 @@ use crate::geom::{Rectangle, Point, Dir, CycleDir, halves};
 use crate::unit::scale_by_dpi;
 use crate::font::Fonts;
 use crate::input::{DeviceEvent, ButtonCode, ButtonStatus};
-use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData, PageTurn};
 use crate::view::{ViewId, Id, ID_FEEDER, EntryId, EntryKind};
 use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
 use crate::document::{Document, Location};
 use crate::document::html::HtmlDocument;
+use crate::helpers::plain_text;
 use crate::view::common::{locate_by_id, locate};
-use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
 use crate::gesture::GestureEvent;
 use crate::color::BLACK;
+use crate::session::{Session, ApplicationState};
 use crate::context::Context;
 use crate::view::filler::Filler;
 use crate::view::named_input::NamedInput;
@@ -44,8 +46,10 @@ pub struct Dictionary {
     focus: Option<ViewId>,
 }
 
-fn query_to_content(query: &str, language: &String, fuzzy: bool, target: Option<&String>, context: &mut Context) -> String {
+// Returns the HTML content of the results and the first definition found.
+fn query_to_content(query: &str, language: &String, fuzzy: bool, target: Option<&String>, context: &mut Context) -> (String, Option<String>) {
     let mut content = String::new();
+    let mut definition = None;
 
     for (name, dict) in context.dictionaries.iter_mut() {
         if target.is_some() && target != Some(name) {
@@ -66,6 +70,9 @@ fn query_to_content(query: &str, language: &String, fuzzy: bool, target: Option<
                 content.push_str(&format!("<h1 class=\"dictname\">{}</h1>\n", name.replace('<', "&lt;").replace('>', "&gt;")));
             }
             for [head, body] in results {
+                if definition.is_none() {
+                    definition = Some(plain_text(&body));
+                }
                 if !body.trim_start().starts_with("<h2") {
                     content.push_str(&format!("<h2 class=\"headword\">{}</h2>\n", head.replace('<', "&lt;").replace('>', "&gt;")));
                 }
@@ -86,7 +93,7 @@ fn query_to_content(query: &str, language: &String, fuzzy: bool, target: Option<
         }
     }
 
-    content
+    (content, definition)
 }
 
 impl Dictionary {
@@ -325,6 +332,7 @@ impl Dictionary {
         if let Some(image) = self.children[4].downcast_mut::<Image>() {
             if let Some((pixmap, loc)) = self.doc.pixmap(location, 1.0) {
                 image.update(pixmap, rq);
+                rq.turn_page(PageTurn::new(image.id(), *image.rect()));
                 self.location = loc;
             }
         }
@@ -341,7 +349,12 @@ impl Dictionary {
                 search_bar.set_text(query, rq, context);
             }
         }
-        let content = query_to_content(&self.query, &self.language, self.fuzzy, self.target.as_ref(), context);
+        let (content, definition) = query_to_content(&self.query, &self.language, self.fuzzy, self.target.as_ref(), context);
+        if let Some(definition) = definition {
+            if context.vocabulary.define(&self.query, &definition) {
+                context.save_vocabulary();
+            }
+        }
         self.doc.update(&content);
         if let Some(image) = self.children[4].downcast_mut::<Image>() {
             if let Some((pixmap, loc)) = self.doc.pixmap(Location::Exact(0), 1.0) {
@@ -540,6 +553,10 @@ impl View for Dictionary {
                 toggle_clock_menu(self, rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
             Event::Reseed => {
                 self.reseed(rq, context);
                 true
@@ -643,4 +660,9 @@ impl View for Dictionary {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn save_state(&self, session: &mut Session) {
+        session.application = Some(ApplicationState::Dictionary { query: self.query.clone(),
+                                                                  language: self.language.clone() });
+    }
 }
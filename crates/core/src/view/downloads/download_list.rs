@@ -0,0 +1,128 @@
+use crate::device::CURRENT_DEVICE;
+use crate::document::HumanSize;
+use crate::downloads::{Download, DownloadStatus};
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::gesture::GestureEvent;
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+// One row per download: the name and the status, above a progress bar.
+pub struct DownloadList {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    rows: Vec<Download>,
+    row_height: i32,
+}
+
+impl DownloadList {
+    pub fn new(rect: Rectangle, context: &mut Context) -> DownloadList {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        DownloadList {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            rows: Vec::new(),
+            row_height: 2 * font.line_height(),
+        }
+    }
+
+    pub fn update(&mut self, rows: Vec<Download>, rq: &mut RenderQueue) {
+        self.rows = rows;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn status_text(download: &Download) -> String {
+    match download.status {
+        DownloadStatus::Failed if !download.error.is_empty() => {
+            format!("{}: {}", download.status.label(), download.error)
+        },
+        DownloadStatus::Completed => download.done.human_size(),
+        status => match download.progress() {
+            Some(progress) => format!("{} {}%", status.label(), (100.0 * progress) as u8),
+            None if download.done > 0 => format!("{} {}", status.label(), download.done.human_size()),
+            None => status.label().to_string(),
+        },
+    }
+}
+
+impl View for DownloadList {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let index = ((center.y - self.rect.min.y) / self.row_height.max(1)) as usize;
+                if let Some(download) = self.rows.get(index) {
+                    let y = self.rect.min.y + index as i32 * self.row_height;
+                    let rect = rect![self.rect.min.x, y, self.rect.max.x, y + self.row_height];
+                    bus.push_back(Event::ToggleDownloadMenu(rect, download.id));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32;
+        let x_height = font.x_heights.0 as i32;
+        let row_height = self.row_height;
+        let width = self.rect.width() as i32 - 2 * padding;
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let y = self.rect.min.y + index as i32 * row_height;
+            if y + row_height > self.rect.max.y {
+                break;
+            }
+
+            let status = status_text(row);
+            let name = row.name();
+            let status_plan = font.plan(&status, Some(width / 2), None);
+            let name_plan = font.plan(&name, Some(width - status_plan.width - padding), None);
+            let baseline = y + (row_height + x_height) / 2 - thickness;
+            font.render(fb, BLACK, &name_plan, pt!(self.rect.min.x + padding, baseline));
+            font.render(fb, BLACK, &status_plan, pt!(self.rect.max.x - padding - status_plan.width, baseline));
+
+            let progress = if row.status == DownloadStatus::Completed { 1.0 } else { row.progress().unwrap_or(0.0) };
+            let bar_width = (width as f32 * progress) as i32;
+            let bar_y = y + row_height - 3 * thickness;
+            fb.draw_rectangle(&rect![self.rect.min.x + padding, bar_y,
+                                     self.rect.max.x - padding, bar_y + thickness],
+                              GRAY12);
+            fb.draw_rectangle(&rect![self.rect.min.x + padding, bar_y,
+                                     self.rect.min.x + padding + bar_width, bar_y + thickness],
+                              GRAY08);
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
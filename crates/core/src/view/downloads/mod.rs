@@ -0,0 +1,275 @@
+mod download_list;
+
+use crate::device::CURRENT_DEVICE;
+use crate::downloads::DownloadStatus;
+use crate::geom::{Rectangle, halves};
+use crate::gesture::GestureEvent;
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::menu::{Menu, MenuKind};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::common::locate_by_id;
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryKind, EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::download_list::DownloadList;
+
+// Shows the queued downloads: tap one of them to pause, resume or remove it.
+pub struct Downloads {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+}
+
+impl Downloads {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Downloads {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  title(context),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let download_list = DownloadList::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                                    rect.max.x, rect.max.y - small_height - small_thickness],
+                                              context);
+        children.push(Box::new(download_list) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut downloads = Downloads {
+            id,
+            rect,
+            children,
+        };
+
+        downloads.update_list(&mut RenderQueue::new(), context);
+        downloads.update_buttons(&mut RenderQueue::new());
+        downloads
+    }
+
+    fn update_list(&mut self, rq: &mut RenderQueue, context: &Context) {
+        if let Some(download_list) = self.children[2].downcast_mut::<DownloadList>() {
+            download_list.update(context.downloads.items.clone(), rq);
+        }
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title(context), rq);
+        }
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let button_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                                rect.max.x - padding, rect.max.y - padding];
+        let button = Button::new(button_rect, Event::Select(EntryId::ClearCompletedDownloads),
+                                 "Clear Completed".to_string());
+        self.children.insert(5, Box::new(button) as Box<dyn View>);
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn toggle_download_menu(&mut self, id: u64, rect: Rectangle, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::DownloadMenu) {
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            return;
+        }
+
+        let status = if let Some(download) = context.downloads.items.iter().find(|item| item.id == id) {
+            download.status
+        } else {
+            return;
+        };
+
+        let mut entries = Vec::new();
+
+        match status {
+            DownloadStatus::Queued | DownloadStatus::Running => {
+                entries.push(EntryKind::Command("Pause".to_string(), EntryId::PauseDownload(id)));
+            },
+            DownloadStatus::Paused => {
+                entries.push(EntryKind::Command("Resume".to_string(), EntryId::ResumeDownload(id)));
+            },
+            DownloadStatus::Failed => {
+                entries.push(EntryKind::Command("Retry".to_string(), EntryId::ResumeDownload(id)));
+            },
+            DownloadStatus::Completed => (),
+        }
+
+        entries.push(EntryKind::Command("Remove".to_string(), EntryId::RemoveDownload(id)));
+
+        let download_menu = Menu::new(rect, ViewId::DownloadMenu, MenuKind::Contextual, entries, context);
+        rq.add(RenderData::new(download_menu.id(), *download_menu.rect(), UpdateMode::Gui));
+        self.children.push(Box::new(download_menu) as Box<dyn View>);
+    }
+
+    // Applies a change to the queue, and starts the next download if needed.
+    fn change_queue<F>(&mut self, f: F, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) where F: FnOnce(&mut Context) {
+        f(context);
+        context.downloads.process(context.online, hub);
+        context.save_downloads();
+        self.update_list(rq, context);
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn title(context: &Context) -> String {
+    let count = context.downloads.items.iter()
+                       .filter(|item| item.status != DownloadStatus::Completed)
+                       .count();
+    if count > 0 {
+        format!("Downloads ({})", count)
+    } else {
+        "Downloads".to_string()
+    }
+}
+
+impl View for Downloads {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::ToggleDownloadMenu(rect, id) => {
+                self.toggle_download_menu(id, rect, rq, context);
+                true
+            },
+            Event::Select(EntryId::PauseDownload(id)) => {
+                self.change_queue(|context| context.downloads.pause(id), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ResumeDownload(id)) => {
+                self.change_queue(|context| context.downloads.resume(id), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::RemoveDownload(id)) => {
+                self.change_queue(|context| context.downloads.remove(id), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ClearCompletedDownloads) => {
+                self.change_queue(|context| context.downloads.clear_completed(), hub, rq, context);
+                true
+            },
+            // The queue was already updated by the application.
+            Event::QueueDownload(..) |
+            Event::DownloadProgress(..) |
+            Event::DownloadFinished(..) |
+            Event::RetryDownloads => {
+                self.update_list(rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children.retain(|child| !child.is::<Menu>());
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
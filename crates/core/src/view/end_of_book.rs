@@ -0,0 +1,239 @@
+use std::f32::consts::PI;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Point, Rectangle, CornerSpec, BorderSpec, divide};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, EntryId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::button::Button;
+use super::label::Label;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+pub const MAX_RATING: u8 = 5;
+
+// Shown when the last page of a book is reached: lets the user rate the book,
+// write a short note about it and archive it.
+pub struct EndOfBook {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+}
+
+impl EndOfBook {
+    pub fn new(view_id: ViewId, title: &str, rating: u8, has_review: bool, can_archive: bool, context: &mut Context) -> EndOfBook {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let line_height = 4 * x_height;
+        let star_height = 6 * x_height;
+
+        let panel_width = 2 * width.min(height) as i32 / 3;
+        let panel_height = 3 * line_height + star_height + 5 * padding;
+        let dx = (width as i32 - panel_width) / 2;
+        let dy = (height as i32 - panel_height) / 2;
+        let rect = rect![dx, dy, dx + panel_width, dy + panel_height];
+
+        let mut y = rect.min.y + padding;
+        let rect_title = rect![rect.min.x + padding, y, rect.max.x - padding, y + line_height];
+        children.push(Box::new(Label::new(rect_title, "Finished".to_string(), Align::Center)) as Box<dyn View>);
+        y += line_height;
+
+        let rect_subtitle = rect![rect.min.x + padding, y, rect.max.x - padding, y + line_height];
+        children.push(Box::new(Label::new(rect_subtitle, title.to_string(), Align::Center)) as Box<dyn View>);
+        y += line_height + padding;
+
+        let rect_stars = rect![rect.min.x + padding, y, rect.max.x - padding, y + star_height];
+        children.push(Box::new(StarRating::new(rect_stars, rating)) as Box<dyn View>);
+        y += star_height + padding;
+
+        let mut buttons = vec![(Event::Select(EntryId::EditBookReview),
+                                if has_review { "Edit Note" } else { "Note" })];
+        if can_archive {
+            buttons.push((Event::Select(EntryId::ArchiveBook), "Archive"));
+        }
+        buttons.push((Event::Close(view_id), "Close"));
+
+        let inner_width = rect.width() as i32 - padding;
+        let widths = divide(inner_width, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for ((event, text), w) in buttons.into_iter().zip(widths) {
+            let rect_button = rect![x, y, x + w - padding, y + line_height];
+            children.push(Box::new(Button::new(rect_button, event, text.to_string())) as Box<dyn View>);
+            x += w;
+        }
+
+        EndOfBook {
+            id,
+            rect,
+            children,
+            view_id,
+        }
+    }
+}
+
+impl View for EndOfBook {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let delta = pt!((width as i32 - self.rect.width() as i32) / 2,
+                        (height as i32 - self.rect.height() as i32) / 2) - self.rect.min;
+        self.rect += delta;
+        for child in &mut self.children {
+            *child.rect_mut() += delta;
+        }
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
+
+struct StarRating {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    rating: u8,
+}
+
+impl StarRating {
+    fn new(rect: Rectangle, rating: u8) -> StarRating {
+        StarRating {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            rating: rating.min(MAX_RATING),
+        }
+    }
+
+    fn cell_width(&self) -> i32 {
+        self.rect.width() as i32 / MAX_RATING as i32
+    }
+}
+
+// The outer and inner vertices of a five-pointed star, clockwise from the top.
+fn star_vertices(center: Point, radius: i32) -> Vec<Point> {
+    (0..10).map(|i| {
+        let r = if i % 2 == 0 { radius as f32 } else { 0.4 * radius as f32 };
+        let angle = i as f32 * PI / 5.0;
+        pt!(center.x + (r * angle.sin()).round() as i32,
+            center.y - (r * angle.cos()).round() as i32)
+    }).collect()
+}
+
+fn draw_star(fb: &mut dyn Framebuffer, center: Point, radius: i32, color: u8) {
+    let vertices = star_vertices(center, radius);
+    for i in 0..vertices.len() {
+        let next = vertices[(i + 1) % vertices.len()];
+        fb.draw_triangle(&[center, vertices[i], next], color);
+    }
+}
+
+impl View for StarRating {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let index = ((center.x - self.rect.min.x) / self.cell_width()).clamp(0, MAX_RATING as i32 - 1) as u8 + 1;
+                // Tapping the current rating clears it.
+                self.rating = if index == self.rating { 0 } else { index };
+                bus.push_back(Event::Select(EntryId::SetBookRating(self.rating)));
+                rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as i32;
+        let cell_width = self.cell_width();
+        let radius = (cell_width.min(self.rect.height() as i32) / 2) * 4 / 5;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        for i in 0..MAX_RATING as i32 {
+            let center = pt!(self.rect.min.x + i * cell_width + cell_width / 2,
+                             self.rect.min.y + self.rect.height() as i32 / 2);
+            draw_star(fb, center, radius, BLACK);
+            if i as u8 >= self.rating {
+                draw_star(fb, center, radius - 2 * thickness, WHITE);
+            }
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
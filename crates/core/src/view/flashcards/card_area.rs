@@ -0,0 +1,120 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, Font, font_from_style, NORMAL_STYLE, SPECIAL_STYLE, DISPLAY_STYLE};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER};
+use crate::vocabulary::Card;
+use crate::color::{BLACK, WHITE, GRAY07};
+use crate::context::Context;
+
+pub struct CardArea {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    card: Option<Card>,
+    revealed: bool,
+}
+
+impl CardArea {
+    pub fn new(rect: Rectangle) -> CardArea {
+        CardArea {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            card: None,
+            revealed: false,
+        }
+    }
+
+    pub fn update(&mut self, card: Option<Card>, revealed: bool, rq: &mut RenderQueue) {
+        self.card = card;
+        self.revealed = revealed;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn render_centered_lines(fb: &mut dyn Framebuffer, font: &mut Font, color: u8, text: &str, rect: &Rectangle, y: &mut i32) {
+    let max_width = rect.width() as i32;
+    let line_height = font.line_height();
+    for line in font.wrap(text, max_width) {
+        if *y + line_height > rect.max.y {
+            break;
+        }
+        let plan = font.plan(&line, Some(max_width), None);
+        let dx = (max_width - plan.width) / 2;
+        *y += line_height;
+        font.render(fb, color, &plan, pt!(rect.min.x + dx, *y));
+    }
+}
+
+impl View for CardArea {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let padding = font_from_style(fonts, &NORMAL_STYLE, dpi).em() as i32;
+        let inner_rect = rect![self.rect.min.x + padding, self.rect.min.y + padding,
+                               self.rect.max.x - padding, self.rect.max.y - padding];
+
+        let card = if let Some(card) = self.card.as_ref() {
+            card
+        } else {
+            let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+            let mut y = (self.rect.min.y + self.rect.max.y) / 2 - font.line_height();
+            render_centered_lines(fb, font, BLACK, "No cards to review.", &inner_rect, &mut y);
+            return;
+        };
+
+        let mut y = inner_rect.min.y + inner_rect.height() as i32 / 6;
+
+        let font = font_from_style(fonts, &DISPLAY_STYLE, dpi);
+        render_centered_lines(fb, font, BLACK, &card.word, &inner_rect, &mut y);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        if !card.book.is_empty() {
+            render_centered_lines(fb, font, GRAY07, &card.book, &inner_rect, &mut y);
+        }
+
+        if !self.revealed {
+            return;
+        }
+
+        y += padding;
+
+        if !card.definition.is_empty() {
+            render_centered_lines(fb, font, BLACK, &card.definition, &inner_rect, &mut y);
+            y += padding;
+        }
+
+        if !card.context.is_empty() {
+            let font = font_from_style(fonts, &SPECIAL_STYLE, dpi);
+            render_centered_lines(fb, font, BLACK, &card.context, &inner_rect, &mut y);
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,296 @@
+mod card_area;
+
+use std::fs;
+use chrono::Local;
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, halves, divide};
+use crate::gesture::GestureEvent;
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::notification::Notification;
+use crate::view::menu::{Menu, MenuKind};
+use crate::view::common::locate_by_id;
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryKind, EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::vocabulary::Grade;
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::card_area::CardArea;
+
+const ANKI_EXPORT_FILENAME: &str = "vocabulary-anki.txt";
+const GRADES: [(Grade, &str); 4] = [(Grade::Again, "Again"), (Grade::Hard, "Hard"),
+                                    (Grade::Good, "Good"), (Grade::Easy, "Easy")];
+
+// Reviews the due cards of the vocabulary: tap the card to reveal its
+// definition and context, then grade how well it was remembered.
+pub struct Flashcards {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    queue: Vec<usize>,
+    revealed: bool,
+}
+
+impl Flashcards {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Flashcards {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+        let queue = context.vocabulary.due(Local::now());
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  title(queue.len()),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let mut card_area = CardArea::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                                rect.max.x, rect.max.y - small_height - small_thickness]);
+        card_area.update(queue.first().map(|&i| context.vocabulary.cards[i].clone()), false, &mut RenderQueue::new());
+        children.push(Box::new(card_area) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut flashcards = Flashcards {
+            id,
+            rect,
+            children,
+            queue,
+            revealed: false,
+        };
+
+        flashcards.update_buttons(&mut RenderQueue::new());
+        flashcards
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let buttons: Vec<(Event, &str)> = if self.queue.is_empty() {
+            Vec::new()
+        } else if self.revealed {
+            GRADES.iter().map(|&(grade, text)| (Event::Select(EntryId::GradeCard(grade)), text)).collect()
+        } else {
+            vec![(Event::Select(EntryId::RevealCard), "Show Answer")]
+        };
+
+        if !buttons.is_empty() {
+            let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+            let mut x = rect.min.x + padding;
+            for (index, ((event, text), w)) in buttons.into_iter().zip(widths).enumerate() {
+                let button_rect = rect![x, rect.min.y + padding, x + w - padding, rect.max.y - padding];
+                self.children.insert(5 + index, Box::new(Button::new(button_rect, event, text.to_string())) as Box<dyn View>);
+                x += w;
+            }
+        }
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn update_card(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let card = self.queue.first().map(|&i| context.vocabulary.cards[i].clone());
+        if let Some(card_area) = self.children[2].downcast_mut::<CardArea>() {
+            card_area.update(card, self.revealed, rq);
+        }
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title(self.queue.len()), rq);
+        }
+        self.update_buttons(rq);
+    }
+
+    fn grade(&mut self, grade: Grade, rq: &mut RenderQueue, context: &mut Context) {
+        if self.queue.is_empty() {
+            return;
+        }
+        let index = self.queue.remove(0);
+        context.vocabulary.cards[index].review(grade, Local::now());
+        context.save_vocabulary();
+        // Forgotten cards come back at the end of the session.
+        if grade == Grade::Again {
+            self.queue.push(index);
+        }
+        self.revealed = false;
+        self.update_card(rq, context);
+    }
+
+    fn export(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let path = context.library.home.join(ANKI_EXPORT_FILENAME);
+        let message = match fs::write(&path, context.vocabulary.to_anki()) {
+            Ok(()) => format!("Exported {} cards to {}.", context.vocabulary.cards.len(), ANKI_EXPORT_FILENAME),
+            Err(e) => {
+                eprintln!("Can't write {}: {:#}.", path.display(), e);
+                "Can't export the vocabulary.".to_string()
+            },
+        };
+        let notif = Notification::new(message, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn toggle_title_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::TitleMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let entries = vec![EntryKind::Command("Export for Anki".to_string(), EntryId::ExportVocabulary)];
+            let title_menu = Menu::new(rect, ViewId::TitleMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(title_menu.id(), *title_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(title_menu) as Box<dyn View>);
+        }
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn title(count: usize) -> String {
+    if count == 0 {
+        "Flashcards".to_string()
+    } else {
+        format!("Flashcards ({})", count)
+    }
+}
+
+impl View for Flashcards {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.children[2].rect().includes(center) => {
+                if !self.revealed && !self.queue.is_empty() {
+                    self.revealed = true;
+                    self.update_card(rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::RevealCard) => {
+                self.revealed = true;
+                self.update_card(rq, context);
+                true
+            },
+            Event::Select(EntryId::GradeCard(grade)) => {
+                self.grade(grade, rq, context);
+                true
+            },
+            Event::Select(EntryId::ExportVocabulary) => {
+                self.export(hub, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::TitleMenu, rect) => {
+                self.toggle_title_menu(rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
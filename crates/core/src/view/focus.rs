@@ -0,0 +1,164 @@
+use fxhash::FxHashSet;
+use crate::color::BLACK;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{BorderSpec, CycleDir, Rectangle};
+use crate::gesture::GestureEvent;
+use crate::input::{DeviceEvent, ButtonCode, ButtonStatus};
+use crate::input::{KEY_TAB, KEY_ENTER, KEY_SPACE, KEY_UP, KEY_DOWN, KEY_LEFT, KEY_RIGHT};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+use super::{View, Event, Hub, Id, RenderQueue, RenderData, THICKNESS_MEDIUM};
+
+// The focus can be moved between the focusable views of the top-most focus scope
+// (a menu or a dialog, for example) with the page turn buttons, and the focused
+// view is activated by holding one of them.
+// The navigation keys of an external keyboard work everywhere.
+#[derive(Default)]
+pub struct Focus {
+    pub id: Option<Id>,
+    // The page turn buttons pressed, or held, while a focus scope was open: their
+    // other events belong to the focus navigation, even once the scope is closed.
+    pressed_buttons: FxHashSet<ButtonCode>,
+    held_buttons: FxHashSet<ButtonCode>,
+}
+
+enum Action {
+    Move(CycleDir),
+    Activate,
+}
+
+// Returns the top-most focus scope within `view`.
+fn focus_scope(view: &dyn View) -> Option<&dyn View> {
+    for i in (0..view.len()).rev() {
+        let child = view.child(i);
+        if let Some(scope) = focus_scope(child) {
+            return Some(scope);
+        }
+        if child.is_focus_scope() {
+            return Some(child);
+        }
+    }
+    None
+}
+
+fn focusables(view: &dyn View, acc: &mut Vec<(Id, Rectangle)>) {
+    if view.is_focusable() {
+        acc.push((view.id(), *view.rect()));
+    }
+    for child in view.children() {
+        focusables(child.as_ref(), acc);
+    }
+}
+
+// The focusable views of the scope, in reading order.
+fn focus_chain(scope: &dyn View) -> Vec<(Id, Rectangle)> {
+    let mut chain = Vec::new();
+    focusables(scope, &mut chain);
+    chain.sort_by_key(|(_, rect)| (rect.min.y, rect.min.x));
+    chain
+}
+
+fn move_focus(chain: &[(Id, Rectangle)], dir: CycleDir, rq: &mut RenderQueue, context: &mut Context) {
+    if chain.is_empty() {
+        return;
+    }
+
+    let current = context.focus.id.and_then(|id| chain.iter().position(|(other, _)| *other == id));
+    let index = match (current, dir) {
+        (Some(i), CycleDir::Next) => (i + 1) % chain.len(),
+        (Some(i), CycleDir::Previous) => (i + chain.len() - 1) % chain.len(),
+        (None, CycleDir::Next) => 0,
+        (None, CycleDir::Previous) => chain.len() - 1,
+    };
+
+    if let Some(i) = current {
+        let (id, rect) = chain[i];
+        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+    }
+
+    let (id, rect) = chain[index];
+    context.focus.id = Some(id);
+    rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+}
+
+fn activate_focus(chain: &[(Id, Rectangle)], hub: &Hub, context: &mut Context) {
+    let focused = context.focus.id.and_then(|id| chain.iter().find(|(other, _)| *other == id));
+    if let Some((_, rect)) = focused {
+        hub.send(Event::Gesture(GestureEvent::Tap(rect.center()))).ok();
+    }
+}
+
+fn keyboard_action(code: u16) -> Option<Action> {
+    match code {
+        KEY_TAB | KEY_DOWN | KEY_RIGHT => Some(Action::Move(CycleDir::Next)),
+        KEY_UP | KEY_LEFT => Some(Action::Move(CycleDir::Previous)),
+        KEY_ENTER | KEY_SPACE => Some(Action::Activate),
+        _ => None,
+    }
+}
+
+// Returns `true` if the event was consumed by the focus navigation.
+pub fn handle_event(view: &dyn View, evt: &Event, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> bool {
+    let scope = focus_scope(view);
+
+    let action = match *evt {
+        Event::Device(DeviceEvent::Button { code: ButtonCode::Raw(code), status, .. }) => {
+            match keyboard_action(code) {
+                Some(action) => {
+                    if status == ButtonStatus::Released {
+                        return true;
+                    }
+                    action
+                },
+                None => return false,
+            }
+        },
+        // The releases of the buttons pressed elsewhere are left to their views.
+        Event::Device(DeviceEvent::Button { code: code @ (ButtonCode::Backward | ButtonCode::Forward), status, .. }) => {
+            match status {
+                ButtonStatus::Pressed if scope.is_some() => {
+                    context.focus.pressed_buttons.insert(code);
+                    return true;
+                },
+                ButtonStatus::Repeated => return context.focus.pressed_buttons.contains(&code),
+                ButtonStatus::Released if context.focus.held_buttons.remove(&code) => {
+                    context.focus.pressed_buttons.remove(&code);
+                    return true;
+                },
+                ButtonStatus::Released if context.focus.pressed_buttons.remove(&code) => {
+                    if scope.is_none() {
+                        return true;
+                    }
+                    if code == ButtonCode::Forward {
+                        Action::Move(CycleDir::Next)
+                    } else {
+                        Action::Move(CycleDir::Previous)
+                    }
+                },
+                _ => return false,
+            }
+        },
+        Event::Gesture(GestureEvent::HoldButtonShort(code @ (ButtonCode::Backward | ButtonCode::Forward)))
+                if scope.is_some() && context.focus.pressed_buttons.contains(&code) => {
+            context.focus.held_buttons.insert(code);
+            Action::Activate
+        },
+        _ => return false,
+    };
+
+    let chain = focus_chain(scope.unwrap_or(view));
+
+    match action {
+        Action::Move(dir) => move_focus(&chain, dir, rq, context),
+        Action::Activate => activate_focus(&chain, hub, context),
+    }
+
+    true
+}
+
+pub fn render_focus(fb: &mut dyn Framebuffer, rect: &Rectangle) {
+    let dpi = CURRENT_DEVICE.dpi;
+    let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as u16;
+    fb.draw_rectangle_outline(rect, &BorderSpec { thickness, color: BLACK });
+}
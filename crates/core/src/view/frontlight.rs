@@ -12,6 +12,7 @@ use super::presets_list::PresetsList;
 use super::common::shift;
 use crate::frontlight::LightLevels;
 use crate::gesture::GestureEvent;
+use crate::input::FingerStatus;
 use crate::settings::{LightPreset, guess_frontlight};
 use crate::color::{BLACK, WHITE};
 use crate::unit::scale_by_dpi;
@@ -119,7 +120,8 @@ impl FrontlightWindow {
                                          *slider_id,
                                          value,
                                          0.0,
-                                         100.0);
+                                         100.0)
+                                 .step(1.0);
                 children.push(Box::new(slider) as Box<dyn View>);
             }
 
@@ -133,7 +135,8 @@ impl FrontlightWindow {
                                          SliderId::LightIntensity,
                                          levels.intensity,
                                          0.0,
-                                         100.0);
+                                         100.0)
+                                 .step(1.0);
                 children.push(Box::new(slider) as Box<dyn View>);
         }
 
@@ -233,8 +236,16 @@ impl FrontlightWindow {
 impl View for FrontlightWindow {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
-            Event::Slider(SliderId::LightIntensity, value, _) => {
+            Event::Slider(SliderId::LightIntensity, value, status) => {
                 context.frontlight.set_intensity(value);
+                // The intensity might have been moved into the flicker-free range.
+                let intensity = context.frontlight.levels().intensity;
+                if status == FingerStatus::Up && intensity != value {
+                    let index = if CURRENT_DEVICE.has_natural_light() { 3 } else { 2 };
+                    if let Some(slider_intensity) = self.child_mut(index).downcast_mut::<Slider>() {
+                        slider_intensity.update(intensity, rq);
+                    }
+                }
                 true
             },
             Event::Slider(SliderId::LightWarmth, value, _) => {
@@ -451,4 +462,8 @@ impl View for FrontlightWindow {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
 }
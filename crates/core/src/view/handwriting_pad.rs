@@ -0,0 +1,176 @@
+use std::mem;
+use std::thread;
+use std::time::Duration;
+use fxhash::FxHashMap;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
+use crate::geom::{Point, Rectangle, BorderSpec};
+use crate::gesture::GestureEvent;
+use crate::input::{DeviceEvent, FingerStatus};
+use crate::handwriting::{self, Jiix, Stroke};
+use crate::settings::Pen;
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+use super::sketch::strokes::{TouchState, draw_stroke_segment};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, KeyboardEvent, THICKNESS_SMALL};
+
+// The strokes are recognized once the fingers have been lifted for this long.
+const RECOGNITION_DELAY: Duration = Duration::from_millis(1200);
+
+// A writing area, in place of the keys of the keyboard: the recognized text is typed in the focused input.
+pub struct HandwritingPad {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    pixmap: Pixmap,
+    pen: Pen,
+    // The fingers touching the pad, and the indices of their strokes.
+    fingers: FxHashMap<i32, (TouchState, usize)>,
+    // In the pad's coordinates.
+    strokes: Vec<Stroke>,
+    // Each new stroke postpones the recognition.
+    token: Id,
+}
+
+impl HandwritingPad {
+    pub fn new(rect: Rectangle, context: &Context) -> HandwritingPad {
+        let id = ID_FEEDER.next();
+        HandwritingPad {
+            id,
+            rect,
+            children: Vec::new(),
+            pixmap: Pixmap::new(rect.width(), rect.height()),
+            pen: context.settings.sketch.pen.clone(),
+            fingers: FxHashMap::default(),
+            strokes: Vec::new(),
+            token: id,
+        }
+    }
+
+    fn draw_segment(&mut self, id: i32, position: Point, time: f64, rq: &mut RenderQueue) {
+        let pt = position - self.rect.min;
+        if let Some((ts, index)) = self.fingers.get_mut(&id) {
+            let rect = draw_stroke_segment(&mut self.pixmap, ts, pt, time, &self.pen) + self.rect.min;
+            self.strokes[*index].push((pt.x, pt.y, time));
+            if let Some(render_rect) = rect.intersection(&self.rect) {
+                rq.add(RenderData::no_wait(self.id, render_rect, UpdateMode::FastMono));
+            }
+        }
+    }
+
+    // Sends the strokes to the recognition service in the background, and clears the pad.
+    fn recognize(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
+        let strokes = mem::take(&mut self.strokes);
+        let (width, height) = (self.rect.width(), self.rect.height());
+        let settings = context.settings.handwriting.clone();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            match handwriting::recognize(&strokes, width, height, CURRENT_DEVICE.dpi, &settings)
+                              .and_then(|text| Jiix::parse(&text)) {
+                Ok(jiix) => {
+                    for ch in jiix.label.chars() {
+                        let ch = if ch == '\n' { ' ' } else { ch };
+                        hub.send(Event::Keyboard(KeyboardEvent::Append(ch))).ok();
+                    }
+                },
+                Err(e) => {
+                    eprintln!("Can't recognize handwriting: {:#}.", e);
+                    hub.send(Event::Notify("Can't recognize the handwriting.".to_string())).ok();
+                },
+            }
+        });
+        self.pixmap.clear(WHITE);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for HandwritingPad {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Device(DeviceEvent::Finger { status: FingerStatus::Down, id, position, time }) => {
+                let position = self.pen.correct(position);
+                if !self.rect.includes(position) {
+                    return false;
+                }
+                let pt = position - self.rect.min;
+                let radius = self.pen.size as f32 / 2.0;
+                self.fingers.insert(id, (TouchState::new(pt, time, radius), self.strokes.len()));
+                self.strokes.push(vec![(pt.x, pt.y, time)]);
+                self.token = ID_FEEDER.next();
+                true
+            },
+            Event::Device(DeviceEvent::Finger { status: FingerStatus::Motion, id, position, time }) if self.fingers.contains_key(&id) => {
+                let position = self.pen.correct(position);
+                self.draw_segment(id, position, time, rq);
+                true
+            },
+            Event::Device(DeviceEvent::Finger { status: FingerStatus::Up, id, position, time }) if self.fingers.contains_key(&id) => {
+                let position = self.pen.correct(position);
+                self.draw_segment(id, position, time, rq);
+                self.fingers.remove(&id);
+                if self.fingers.is_empty() {
+                    let hub2 = hub.clone();
+                    let (view_id, token) = (self.id, self.token);
+                    thread::spawn(move || {
+                        thread::sleep(RECOGNITION_DELAY);
+                        hub2.send(Event::RecognizeStrokes(view_id, token)).ok();
+                    });
+                }
+                true
+            },
+            Event::RecognizeStrokes(id, token) if id == self.id => {
+                // Only when no stroke was started in the meantime.
+                if token == self.token && self.fingers.is_empty() && !self.strokes.is_empty() {
+                    self.recognize(hub, rq, context);
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) |
+            Event::Gesture(GestureEvent::HoldFingerShort(center, ..)) if self.rect.includes(center) => true,
+            Event::Gesture(GestureEvent::Swipe { start, .. }) if self.rect.includes(start) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, rect: Rectangle, _fonts: &mut Fonts) {
+        if let Some(region) = rect.intersection(&self.rect) {
+            fb.draw_framed_pixmap_halftone(&self.pixmap, &(region - self.rect.min), region.min);
+        }
+        let thickness = scale_by_dpi(THICKNESS_SMALL, CURRENT_DEVICE.dpi) as u16;
+        fb.draw_rectangle_outline(&self.rect, &BorderSpec { thickness, color: BLACK });
+    }
+
+    fn render_rect(&self, rect: &Rectangle) -> Rectangle {
+        rect.intersection(&self.rect)
+            .unwrap_or(self.rect)
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.pixmap = Pixmap::new(rect.width(), rect.height());
+        self.fingers.clear();
+        self.strokes.clear();
+        self.rect = rect;
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -7,6 +7,7 @@ use crate::color::{BLACK, WHITE, READING_PROGRESS};
 use crate::color::{TEXT_NORMAL, TEXT_INVERTED_HARD};
 use crate::gesture::GestureEvent;
 use crate::metadata::{Info, Status};
+use crate::integrity::Problem;
 use crate::settings::{FirstColumn, SecondColumn};
 use crate::unit::scale_by_dpi;
 use crate::document::{HumanSize, Location, Document};
@@ -26,12 +27,14 @@ pub struct Book {
     first_column: FirstColumn,
     second_column: SecondColumn,
     preview_path: Option<PathBuf>,
+    problem: Option<Problem>,
     active: bool,
 }
 
 impl Book {
     pub fn new(rect: Rectangle, info: Info, index: usize,
-               first_column: FirstColumn, second_column: SecondColumn, preview_path: Option<PathBuf>) -> Book {
+               first_column: FirstColumn, second_column: SecondColumn, preview_path: Option<PathBuf>,
+               problem: Option<Problem>) -> Book {
         Book {
             id: ID_FEEDER.next(),
             rect,
@@ -41,6 +44,7 @@ impl Book {
             first_column,
             second_column,
             preview_path,
+            problem,
             active: false,
         }
     }
@@ -248,9 +252,10 @@ impl View for Book {
             font.render(fb, scheme[1], &plan, pt);
         }
 
-        // File size
+        // File size, or the problem found by the integrity check
         {
-            let size = file_info.size.human_size();
+            let size = self.problem.map_or_else(|| file_info.size.human_size(),
+                                                |problem| problem.label().to_uppercase());
             let font = font_from_style(fonts, &MD_SIZE, dpi);
             let plan = font.plan(&size, None, None);
             let pt = pt!(self.rect.max.x - padding - plan.width,
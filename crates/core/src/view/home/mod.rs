@@ -3,7 +3,7 @@ mod address_bar;
 mod navigation_bar;
 mod directories_bar;
 mod directory;
-mod shelf;
+pub mod shelf;
 mod book;
 mod bottom_bar;
 
@@ -11,33 +11,51 @@ use std::fs;
 use std::mem;
 use std::thread;
 use std::io::Write;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Child, Stdio};
 use std::io::{BufRead, BufReader};
 use fxhash::FxHashMap;
 use rand_core::RngCore;
 use serde_json::{json, Value as JsonValue};
+use chrono::Local;
 use anyhow::{Error, format_err};
-use crate::library::Library;
+use crate::library::{Library, ImportEntry, BookLength};
+use crate::handoff::Handoff;
+use crate::view::statistics::StatisticsView;
+use crate::statistics::{format_duration, DEFAULT_WORDS_PER_MINUTE};
+use crate::backup;
+use crate::snapshot;
+use crate::storage::{self, Shortage};
+use crate::downloads::Download;
+use crate::document::HumanSize;
+use crate::integrity::{IntegrityReport, Problem};
 use crate::framebuffer::{Framebuffer, UpdateMode};
-use crate::metadata::{Info, Metadata, SortMethod, BookQuery, SimpleStatus, sort};
-use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::metadata::{Info, Metadata, SortMethod, BookQuery, SimpleStatus, Cover, sort};
+use crate::metadata::{find_isbn, merge_missing};
+use crate::language::LANGUAGES;
+use crate::global_search;
+use crate::jobs::{CancelToken, RetryPolicy};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData, PageTurn};
 use crate::view::{Id, ID_FEEDER, ViewId, EntryId, EntryKind};
 use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
-use crate::settings::{Hook, LibraryMode, FirstColumn, SecondColumn};
-use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
-use crate::view::common::{locate, rlocate, locate_by_id};
+use crate::settings::{Hook, LibraryMode, FirstColumn, SecondColumn, ImportSettings, SmartCollection, Profile, network_env};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::common::toggle_weather_menu;
+use crate::view::common::{locate, rlocate, locate_by_id, update_progress, close_progress};
 use crate::view::filler::Filler;
 use crate::view::keyboard::Keyboard;
 use crate::view::named_input::NamedInput;
+use crate::view::list_picker::ListPicker;
 use crate::view::menu::{Menu, MenuKind};
 use crate::view::menu_entry::MenuEntry;
 use crate::view::search_bar::SearchBar;
 use crate::view::notification::Notification;
+use crate::view::modal::{Modal, ModalKind};
 use super::top_bar::TopBar;
 use self::address_bar::AddressBar;
 use self::navigation_bar::NavigationBar;
-use self::shelf::Shelf;
+use self::shelf::{Shelf, generate_preview, set_cover};
 use self::bottom_bar::BottomBar;
 use crate::gesture::GestureEvent;
 use crate::geom::{Rectangle, Dir, DiagDir, CycleDir, halves};
@@ -46,9 +64,13 @@ use crate::device::CURRENT_DEVICE;
 use crate::unit::scale_by_dpi;
 use crate::color::BLACK;
 use crate::font::Fonts;
+use crate::session::{Session, HomeState};
 use crate::context::Context;
 
 pub const TRASH_DIRNAME: &str = ".trash";
+// The images of a book's directory offered as its cover.
+const COVER_KINDS: [&str; 3] = ["png", "jpg", "jpeg"];
+const MAX_COVER_IMAGES: usize = 16;
 
 #[derive(Debug)]
 pub struct Home {
@@ -65,9 +87,18 @@ pub struct Home {
     visible_books: Metadata,
     current_directory: PathBuf,
     target_document: Option<PathBuf>,
+    protected_document: Option<Info>,
+    background_import: Option<BackgroundImport>,
+    background_check: Option<BackgroundImport>,
     background_fetchers: FxHashMap<u32, Fetcher>,
 }
 
+#[derive(Debug)]
+struct BackgroundImport {
+    home: PathBuf,
+    cancel: CancelToken,
+}
+
 #[derive(Debug)]
 struct Fetcher {
     path: PathBuf,
@@ -92,7 +123,10 @@ impl Home {
         let selected_library = context.settings.selected_library;
         let library_settings = &context.settings.libraries[selected_library];
 
-        let current_directory = context.library.home.clone();
+        let current_directory = context.settings.kiosk.directory.clone()
+                                       .filter(|dir| context.settings.kiosk.enabled &&
+                                                     dir.starts_with(&context.library.home))
+                                       .unwrap_or_else(|| context.library.home.clone());
         let sort_method = library_settings.sort_method;
         let reverse_order = sort_method.reverse_order();
 
@@ -103,11 +137,14 @@ impl Home {
         let current_page = 0;
         let mut shelf_index = 2;
 
-        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
-                                        rect.max.x, rect.min.y + small_height - small_thickness],
-                                  Event::Toggle(ViewId::SearchBar),
-                                  sort_method.title(),
-                                  hub, rq, context);
+        let mut top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                            rect.max.x, rect.min.y + small_height - small_thickness],
+                                      Event::Toggle(ViewId::SearchBar),
+                                      sort_method.title(),
+                                      hub, rq, context);
+        if context.settings.weather.enabled {
+            top_bar = top_bar.with_weather(hub, rq, context);
+        }
         children.push(Box::new(top_bar) as Box<dyn View>);
 
         let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
@@ -202,21 +239,44 @@ impl Home {
             visible_books,
             current_directory,
             target_document: None,
+            protected_document: None,
+            background_import: None,
+            background_check: None,
             background_fetchers: FxHashMap::default(),
         })
     }
 
+    // Returns to the directory, the search and the page saved in the session.
+    pub fn restore(&mut self, state: &HomeState, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if state.directory.starts_with(&context.library.home) && state.directory.is_dir() {
+            self.select_directory(&state.directory, hub, rq, context);
+        }
+        if let Some(text) = state.query.as_ref() {
+            self.search(text, hub, rq, context);
+        }
+        self.go_to_page(state.page, hub, rq, context);
+    }
+
     fn select_directory(&mut self, path: &Path, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         if self.current_directory == path {
             return;
         }
 
+        if let Some(dir) = context.settings.kiosk.directory.as_ref().filter(|_| context.settings.kiosk.enabled) {
+            if !path.starts_with(dir) {
+                return;
+            }
+        }
+
         let old_path = mem::replace(&mut self.current_directory, path.to_path_buf());
         self.terminate_fetchers(&old_path, true, hub, context);
 
         let selected_library = context.settings.selected_library;
-        for hook in &context.settings.libraries[selected_library].hooks {
-            if context.library.home.join(&hook.path) == path {
+        let hooks = context.settings.libraries[selected_library].hooks.iter()
+                           .filter(|hook| context.library.home.join(&hook.path) == path)
+                           .cloned().collect::<Vec<Hook>>();
+        if !hooks.is_empty() && self.has_resources(hub, rq, context) {
+            for hook in &hooks {
                 self.insert_fetcher(hook, hub, context);
             }
         }
@@ -274,9 +334,15 @@ impl Home {
         }
         self.current_page = index;
         self.update_shelf(false, hub, rq, context);
+        self.turn_shelf_page(rq);
         self.update_bottom_bar(rq, context);
     }
 
+    fn turn_shelf_page(&self, rq: &mut RenderQueue) {
+        let shelf = self.child(self.shelf_index);
+        rq.turn_page(PageTurn::new(shelf.id(), *shelf.rect()));
+    }
+
     fn go_to_neighbor(&mut self, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         match dir {
             CycleDir::Next if self.current_page < self.pages_count.saturating_sub(1) => {
@@ -289,6 +355,7 @@ impl Home {
         }
 
         self.update_shelf(false, hub, rq, context);
+        self.turn_shelf_page(rq);
         self.update_bottom_bar(rq, context);
     }
 
@@ -468,7 +535,8 @@ impl Home {
                                     self.rect.max.x,
                                     self.rect.max.y - small_height - small_thickness];
 
-            let number = matches!(id, Some(ViewId::GoToPageInput));
+            let number = matches!(id, Some(ViewId::GoToPageInput) |
+                                      Some(ViewId::KioskPinInput));
             let keyboard = Keyboard::new(&mut kb_rect, number, context);
             self.children.insert(index, Box::new(keyboard) as Box<dyn View>);
 
@@ -795,6 +863,71 @@ impl Home {
         }
     }
 
+    fn toggle_handoff(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::Handoff) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            if let Some(ViewId::HandoffInput) = self.focus {
+                self.toggle_keyboard(false, true, Some(ViewId::HandoffInput), hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let handoff = NamedInput::new("Handoff".to_string(),
+                                          ViewId::Handoff,
+                                          ViewId::HandoffInput,
+                                          32, context);
+            rq.add(RenderData::new(handoff.id(), *handoff.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::HandoffInput))).ok();
+            self.children.push(Box::new(handoff) as Box<dyn View>);
+        }
+    }
+
+    // Opens the book of the handoff at its position, which replaces the current one.
+    fn receive_handoff(&mut self, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let message = match Handoff::parse(text) {
+            Some(handoff) => match context.library.accept_handoff(&handoff) {
+                Some(info) => {
+                    hub.send(Event::Open(Box::new(info))).ok();
+                    return;
+                },
+                None => "The book isn't in this library.",
+            },
+            None => "Invalid handoff.",
+        };
+        let notif = Notification::new(message.to_string(), hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn toggle_document_password(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::DocumentPassword) {
+            if let Some(true) = enable {
+                return;
+            }
+            self.protected_document = None;
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            if let Some(ViewId::DocumentPasswordInput) = self.focus {
+                self.toggle_keyboard(false, true, Some(ViewId::DocumentPasswordInput), hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let doc_password = NamedInput::new("Password".to_string(),
+                                               ViewId::DocumentPassword,
+                                               ViewId::DocumentPasswordInput,
+                                               16, context);
+            rq.add(RenderData::new(doc_password.id(), *doc_password.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::DocumentPasswordInput))).ok();
+            self.children.push(Box::new(doc_password) as Box<dyn View>);
+        }
+    }
+
     fn toggle_go_to_page(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         if let Some(index) = locate_by_id(self, ViewId::GoToPage) {
             if let Some(true) = enable {
@@ -822,6 +955,244 @@ impl Home {
         }
     }
 
+    fn toggle_global_search(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::GlobalSearch) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            if let Some(ViewId::GlobalSearchInput) = self.focus {
+                self.toggle_keyboard(false, true, Some(ViewId::GlobalSearchInput), hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let global_search = NamedInput::new("Search everywhere".to_string(),
+                                                ViewId::GlobalSearch,
+                                                ViewId::GlobalSearchInput,
+                                                24, context);
+            rq.add(RenderData::new(global_search.id(), *global_search.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::GlobalSearchInput))).ok();
+            self.children.push(Box::new(global_search) as Box<dyn View>);
+        }
+    }
+
+    fn show_global_search_results(&mut self, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let items = global_search::search(text, context);
+        if items.is_empty() {
+            let notif = Notification::new("No results.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        }
+        let dpi = CURRENT_DEVICE.dpi;
+        let (small_height, big_height) = (scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32,
+                                          scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32);
+        // Leave room for the keyboard, which is used by the filter field.
+        let picker_rect = rect![self.rect.min.x + small_height / 2, self.rect.min.y + small_height,
+                                self.rect.max.x - small_height / 2, self.rect.max.y - small_height - 3 * big_height];
+        let picker = ListPicker::new(picker_rect, ViewId::GlobalSearchResults, items, None, rq);
+        self.children.push(Box::new(picker) as Box<dyn View>);
+    }
+
+    fn toggle_kiosk_pin(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::KioskPin) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            if let Some(ViewId::KioskPinInput) = self.focus {
+                self.toggle_keyboard(false, true, Some(ViewId::KioskPinInput), hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let kiosk_pin = NamedInput::new("PIN".to_string(),
+                                            ViewId::KioskPin,
+                                            ViewId::KioskPinInput,
+                                            8, context);
+            rq.add(RenderData::new(kiosk_pin.id(), *kiosk_pin.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::KioskPinInput))).ok();
+            self.children.push(Box::new(kiosk_pin) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_profile_name(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::ProfileName) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+            if let Some(ViewId::ProfileNameInput) = self.focus {
+                self.toggle_keyboard(false, true, Some(ViewId::ProfileNameInput), hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let profile_name = NamedInput::new("Profile name".to_string(),
+                                               ViewId::ProfileName,
+                                               ViewId::ProfileNameInput,
+                                               16, context);
+            rq.add(RenderData::new(profile_name.id(), *profile_name.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::ProfileNameInput))).ok();
+            self.children.push(Box::new(profile_name) as Box<dyn View>);
+        }
+    }
+
+    fn add_profile(&mut self, name: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let message = if name == context.settings.profile ||
+                         context.settings.profiles.iter().any(|p| p.name == name) {
+            format!("The profile '{}' already exists.", name)
+        } else if name.contains(['/', '.']) {
+            "Invalid profile name.".to_string()
+        } else {
+            context.settings.profiles.push(Profile { name: name.to_string(), .. Default::default() });
+            format!("Added the profile '{}'.", name)
+        };
+        let notif = Notification::new(message, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn switch_profile(&mut self, name: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = context.settings.profiles.iter().position(|p| p.name == name) {
+            if self.query.is_some() {
+                self.toggle_search_bar(Some(false), true, hub, rq, context);
+            }
+            context.switch_profile(index);
+            context.library.sort(self.sort_method, self.reverse_order);
+            self.refresh_visibles(true, false, hub, rq, context);
+            let notif = Notification::new(format!("Switched to the profile '{}'.", name), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+        }
+    }
+
+    fn create_backup(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        context.library.flush();
+        let name = format!("Plato-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), backup::BACKUP_EXTENSION);
+        let path = context.library.home.join(&name);
+        let msg = match backup::create(&context.settings, &context.library, &path) {
+            Ok(count) => format!("Saved {} with {} reading states.", name, count),
+            Err(e) => {
+                eprintln!("Can't create backup: {:#}.", e);
+                fs::remove_file(&path).ok();
+                "Can't create the backup.".to_string()
+            },
+        };
+        let notif = Notification::new(msg, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn restore_backup(&mut self, path: &Path, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.query.is_some() {
+            self.toggle_search_bar(Some(false), true, hub, rq, context);
+        }
+
+        context.library.flush();
+
+        let (settings, report) = match backup::restore(path) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Can't restore backup: {:#}.", e);
+                let notif = Notification::new("Can't restore the backup.".to_string(), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                return;
+            },
+        };
+
+        let library_settings = settings.libraries[settings.selected_library].clone();
+        match Library::new(&library_settings.path, library_settings.mode) {
+            Ok(mut library) => {
+                library.set_profile(&settings.profile);
+                library.relocate_caches(settings.cache_path.as_deref());
+                context.library = library;
+            },
+            Err(e) => eprintln!("Can't load library: {:#}.", e),
+        }
+
+        let old_path = mem::take(&mut self.current_directory);
+        self.terminate_fetchers(&old_path, false, hub, context);
+
+        context.settings = settings;
+        self.sort_method = library_settings.sort_method;
+        self.reverse_order = library_settings.sort_method.reverse_order();
+        context.library.sort(self.sort_method, self.reverse_order);
+
+        let search_visible = rlocate::<SearchBar>(self).is_some();
+        self.update_top_bar(search_visible, rq);
+
+        if let Some(shelf) = self.children[self.shelf_index].as_mut().downcast_mut::<Shelf>() {
+            shelf.set_first_column(library_settings.first_column);
+            shelf.set_second_column(library_settings.second_column);
+            shelf.set_thumbnail_previews(library_settings.thumbnail_previews);
+        }
+
+        let home = context.library.home.clone();
+        self.select_directory(&home, hub, rq, context);
+
+        let mut msg = format!("Restored {} reading states.", report.restored);
+        if report.missing > 0 {
+            msg += &format!(" {} belong to missing books.", report.missing);
+        }
+        msg += " Restart to apply every setting.";
+        let notif = Notification::new(msg, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn restore_snapshot(&mut self, path: &Path, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        context.library.flush();
+        let msg = match snapshot::restore(&context.library, path) {
+            Ok(count) => {
+                context.library.reload();
+                context.library.sort(self.sort_method, self.reverse_order);
+                self.refresh_visibles(true, false, hub, rq, context);
+                format!("Restored {} reading states.", count)
+            },
+            Err(e) => {
+                eprintln!("Can't restore snapshot: {:#}.", e);
+                "Can't restore the reading states.".to_string()
+            },
+        };
+        let notif = Notification::new(msg, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn lock_kiosk(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if context.settings.kiosk.pin.is_empty() {
+            return;
+        }
+
+        if self.query.is_some() {
+            self.toggle_search_bar(Some(false), true, hub, rq, context);
+        }
+
+        context.settings.kiosk.enabled = true;
+        context.settings.kiosk.directory = Some(self.current_directory.clone());
+        hub.send(Event::SetWifi(false)).ok();
+
+        let notif = Notification::new("Kiosk mode enabled.".to_string(), hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn unlock_kiosk(&mut self, pin: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let msg = if pin == context.settings.kiosk.pin {
+            context.settings.kiosk.enabled = false;
+            "Kiosk mode disabled."
+        } else {
+            "Wrong PIN."
+        };
+        let notif = Notification::new(msg.to_string(), hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
     fn toggle_sort_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
         if let Some(index) = locate_by_id(self, ViewId::SortMenu) {
             if let Some(true) = enable {
@@ -899,10 +1270,11 @@ impl Home {
             let book_index = self.book_index(index);
             let info = &self.visible_books[book_index];
             let path = &info.file.path;
+            let kiosk = context.settings.kiosk.enabled;
 
             let mut entries = Vec::new();
 
-            if let Some(parent) = path.parent() {
+            if let Some(parent) = path.parent().filter(|_| !kiosk) {
                 entries.push(EntryKind::Command("Select Parent".to_string(),
                                                 EntryId::SelectDirectory(context.library.home.join(parent))));
             }
@@ -912,6 +1284,15 @@ impl Home {
                                                 EntryId::SearchAuthor(info.author.clone())));
             }
 
+            if !info.series.is_empty() {
+                entries.push(EntryKind::Command("Search Series".to_string(),
+                                                EntryId::SearchSeries(info.series.clone())));
+                if let Some(next) = context.library.next_in_series(info) {
+                    entries.push(EntryKind::Command("Open Next in Series".to_string(),
+                                                    EntryId::OpenNextInSeries(next.file.path.clone())));
+                }
+            }
+
             if !entries.is_empty() {
                 entries.push(EntryKind::Separator);
             }
@@ -926,6 +1307,70 @@ impl Home {
                                                                     EntryId::SetStatus(path.clone(), *s)))
                                  .collect();
             entries.push(EntryKind::SubMenu("Mark As".to_string(), submenu));
+
+            let mut languages: Vec<String> = LANGUAGES.iter().map(|l| l.to_string()).collect();
+            if !info.language.is_empty() && !languages.contains(&info.language) {
+                languages.push(info.language.clone());
+            }
+            let mut submenu = vec![EntryKind::RadioButton("Detect".to_string(),
+                                                          EntryId::SetBookLanguage(path.clone(), String::new()),
+                                                          info.language.is_empty())];
+            submenu.extend(languages.into_iter().map(|lang| {
+                let selected = lang == info.language;
+                EntryKind::RadioButton(lang.clone(), EntryId::SetBookLanguage(path.clone(), lang), selected)
+            }));
+            entries.push(EntryKind::SubMenu("Language".to_string(), submenu));
+
+            if !kiosk {
+                let images = path.parent().map(|parent| cover_images(&context.library.home, parent))
+                                 .unwrap_or_default();
+                let mut submenu = vec![EntryKind::RadioButton("First Page".to_string(),
+                                                              EntryId::SetCover(path.clone(), None),
+                                                              info.cover.is_none())];
+                if let Some(Cover::Page(page)) = info.cover {
+                    submenu.push(EntryKind::RadioButton(format!("Page {}", page + 1),
+                                                        EntryId::SetCover(path.clone(), info.cover.clone()),
+                                                        true));
+                }
+                if !images.is_empty() {
+                    submenu.push(EntryKind::Separator);
+                }
+                submenu.extend(images.into_iter().map(|image| {
+                    let name = image.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    let cover = Some(Cover::Image(image));
+                    let selected = cover == info.cover;
+                    EntryKind::RadioButton(name, EntryId::SetCover(path.clone(), cover), selected)
+                }));
+                entries.push(EntryKind::SubMenu("Cover".to_string(), submenu));
+            }
+
+            // Prefer the identifier, usually an ISBN, which is enough to find the book elsewhere.
+            let (text, caption) = if info.identifier.is_empty() {
+                let text = if info.author.is_empty() { info.title.clone() } else { format!("{} — {}", info.title, info.author) };
+                (text, info.title.clone())
+            } else {
+                (info.identifier.clone(), format!("{} ({})", info.title, info.identifier))
+            };
+            if !text.is_empty() {
+                entries.push(EntryKind::Command("Show QR Code".to_string(),
+                                                EntryId::ShowQrCode(text, caption)));
+            }
+
+            entries.push(EntryKind::Command("Statistics".to_string(),
+                                            EntryId::ShowStatistics(path.clone())));
+
+            if !kiosk && find_isbn(info).is_some() {
+                entries.push(EntryKind::Command("Fetch Metadata".to_string(),
+                                                EntryId::FetchMetadata(path.clone())));
+            }
+
+            if kiosk {
+                let book_menu = Menu::new(rect, ViewId::BookMenu, MenuKind::Contextual, entries, context);
+                rq.add(RenderData::new(book_menu.id(), *book_menu.rect(), UpdateMode::Gui));
+                self.children.push(Box::new(book_menu) as Box<dyn View>);
+                return;
+            }
+
             entries.push(EntryKind::Separator);
 
             let selected_library = context.settings.selected_library;
@@ -946,6 +1391,10 @@ impl Home {
                 entries.push(EntryKind::SubMenu("Move To".to_string(), move_to));
             }
 
+            if context.library.problems.contains_key(path) {
+                entries.push(EntryKind::Command("Re-import".to_string(),
+                                                EntryId::Reimport(path.clone())));
+            }
             entries.push(EntryKind::Command("Rename".to_string(),
                                             EntryId::Rename(path.clone())));
             entries.push(EntryKind::Command("Remove".to_string(),
@@ -979,7 +1428,16 @@ impl Home {
             }).collect();
 
             let database = if library_settings.mode == LibraryMode::Database {
-                vec![EntryKind::Command("Import".to_string(), EntryId::Import),
+                vec![if self.background_import.is_some() {
+                         EntryKind::Command("Cancel Import".to_string(), EntryId::CancelImport)
+                     } else {
+                         EntryKind::Command("Import".to_string(), EntryId::Import)
+                     },
+                     if self.background_check.is_some() {
+                         EntryKind::Command("Cancel Integrity Check".to_string(), EntryId::CancelIntegrityCheck)
+                     } else {
+                         EntryKind::Command("Check Integrity".to_string(), EntryId::CheckIntegrity)
+                     },
                      EntryKind::Command("Flush".to_string(), EntryId::Flush)]
             } else {
                 Vec::new()
@@ -1013,6 +1471,69 @@ impl Home {
                 entries.push(EntryKind::SubMenu("Toggle Select".to_string(), hooks));
             }
 
+            let mut collections: Vec<EntryKind> =
+                context.settings.home.smart_collections.iter().enumerate()
+                       .map(|(index, c)| EntryKind::Command(c.name.clone(),
+                                                            EntryId::ApplySmartCollection(index))).collect();
+
+            if self.query.is_some() {
+                if !collections.is_empty() {
+                    collections.push(EntryKind::Separator);
+                }
+                collections.push(EntryKind::Command("Save Current Search".to_string(),
+                                                    EntryId::SaveSmartCollection));
+            }
+
+            if !collections.is_empty() {
+                entries.push(EntryKind::SubMenu("Smart Collections".to_string(), collections));
+            }
+
+            if context.library.mode == LibraryMode::Database {
+                let mut surprise = vec![EntryKind::Command("Any Book".to_string(), EntryId::SurpriseMe(None, None)),
+                                        EntryKind::Command("Short Book".to_string(), EntryId::SurpriseMe(None, Some(BookLength::Short))),
+                                        EntryKind::Command("Long Book".to_string(), EntryId::SurpriseMe(None, Some(BookLength::Long)))];
+                if !context.settings.home.smart_collections.is_empty() {
+                    surprise.push(EntryKind::Separator);
+                    surprise.extend(context.settings.home.smart_collections.iter().enumerate()
+                                           .map(|(index, c)| EntryKind::Command(c.name.clone(),
+                                                                                EntryId::SurpriseMe(Some(index), None))));
+                }
+                entries.push(EntryKind::SubMenu("Surprise Me".to_string(), surprise));
+                entries.push(EntryKind::Command("Receive Handoff".to_string(), EntryId::ReceiveHandoff));
+            }
+
+            let current_profile = &context.settings.profile;
+            let mut profiles = vec![EntryKind::RadioButton(current_profile.clone(),
+                                                           EntryId::SwitchProfile(current_profile.clone()),
+                                                           true)];
+            profiles.extend(context.settings.profiles.iter()
+                                   .map(|p| EntryKind::RadioButton(p.name.clone(),
+                                                                   EntryId::SwitchProfile(p.name.clone()),
+                                                                   false)));
+            profiles.push(EntryKind::Separator);
+            profiles.push(EntryKind::Command("New Profile".to_string(), EntryId::AddProfile));
+            entries.push(EntryKind::SubMenu("Profiles".to_string(), profiles));
+
+            let mut backups = vec![EntryKind::Command("Create Backup".to_string(), EntryId::CreateBackup)];
+            let found = backup::find(&self.current_directory);
+            if !found.is_empty() {
+                backups.push(EntryKind::Separator);
+                backups.extend(found.into_iter().filter_map(|path| {
+                    let name = path.file_name()?.to_string_lossy().into_owned();
+                    Some(EntryKind::Command(format!("Restore {}", name), EntryId::RestoreBackup(path)))
+                }));
+            }
+            let snapshots = snapshot::list(&context.library);
+            if !snapshots.is_empty() {
+                backups.push(EntryKind::Separator);
+                backups.push(EntryKind::SubMenu("Restore Points".to_string(),
+                                                snapshots.into_iter().filter_map(|path| {
+                    let name = path.file_stem()?.to_string_lossy().into_owned();
+                    Some(EntryKind::Command(name, EntryId::RestoreSnapshot(path)))
+                }).collect()));
+            }
+            entries.push(EntryKind::SubMenu("Backup".to_string(), backups));
+
             entries.push(EntryKind::Separator);
 
             let first_column = library_settings.first_column;
@@ -1045,6 +1566,24 @@ impl Home {
         }
     }
 
+    // Shows the search bar filled with the given text and applies its query.
+    fn search(&mut self, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let query = BookQuery::new(text);
+        if query.is_some() {
+            self.query = query;
+            self.toggle_search_bar(Some(true), false, hub, rq, context);
+            self.toggle_keyboard(false, false, None, hub, rq, context);
+            if let Some(search_bar) = self.children[self.shelf_index+2].downcast_mut::<SearchBar>() {
+                search_bar.set_text(text, rq, context);
+            }
+            // Render the search bar and its separator.
+            for i in self.shelf_index + 1 ..= self.shelf_index + 2 {
+                rq.add(RenderData::new(self.child(i).id(), *self.child(i).rect(), UpdateMode::Gui));
+            }
+            self.refresh_visibles(true, true, hub, rq, context);
+        }
+    }
+
     fn add_document(&mut self, info: Info, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         context.library.add_document(info);
         self.sort(false, hub, rq, context);
@@ -1062,6 +1601,33 @@ impl Home {
         self.refresh_visibles(true, false, hub, rq, context);
     }
 
+    fn confirm(&mut self, text: String, event: Event, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let modal = Modal::new(ViewId::Modal, text, ModalKind::Confirm(event), hub, rq, context);
+        rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+        self.children.push(Box::new(modal) as Box<dyn View>);
+    }
+
+    // Offers to free some space when the library's storage is almost full.
+    // Returns whether there's enough space and memory to write new files.
+    fn has_resources(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        let shortage = if let Some(shortage) = storage::check(&context.library.home) {
+            shortage
+        } else {
+            return true;
+        };
+        let (text, kind) = match shortage {
+            Shortage::Space(..) => (format!("{} Free some space first.", shortage.message()),
+                                    ModalKind::Choice(vec![("Clear Caches".to_string(), EntryId::ClearCaches),
+                                                           ("Empty Trash".to_string(), EntryId::EmptyTrash)])),
+            Shortage::Memory(..) => (format!("{} Restart the application first.", shortage.message()),
+                                     ModalKind::Message),
+        };
+        let modal = Modal::new(ViewId::Modal, text, kind, hub, rq, context);
+        rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+        self.children.push(Box::new(modal) as Box<dyn View>);
+        false
+    }
+
     fn empty_trash(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         let trash_path = context.library.home.join(TRASH_DIRNAME);
 
@@ -1130,6 +1696,8 @@ impl Home {
     fn copy_to(&mut self, path: &Path, index: usize, context: &mut Context) -> Result<(), Error> {
         let library_settings = &context.settings.libraries[index];
         let mut library = Library::new(&library_settings.path, library_settings.mode)?;
+        library.set_profile(&context.settings.profile);
+        library.relocate_caches(context.settings.cache_path.as_deref());
         context.library.copy_to(path, &mut library)?;
         library.flush();
         Ok(())
@@ -1138,6 +1706,8 @@ impl Home {
     fn move_to(&mut self, path: &Path, index: usize, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Result<(), Error> {
         let library_settings = &context.settings.libraries[index];
         let mut library = Library::new(&library_settings.path, library_settings.mode)?;
+        library.set_profile(&context.settings.profile);
+        library.relocate_caches(context.settings.cache_path.as_deref());
         context.library.move_to(path, &mut library)?;
         library.flush();
         self.refresh_visibles(true, false, hub, rq, context);
@@ -1191,7 +1761,9 @@ impl Home {
             return;
         }
 
-        let library = library.unwrap();
+        let mut library = library.unwrap();
+        library.set_profile(&context.settings.profile);
+        library.relocate_caches(context.settings.cache_path.as_deref());
 
         let old_path = mem::take(&mut self.current_directory);
         self.terminate_fetchers(&old_path, false, hub, context);
@@ -1231,10 +1803,140 @@ impl Home {
         self.select_directory(&home, hub, rq, context);
     }
 
-    fn import(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
-        context.library.import(&context.settings.import);
+    fn import(&mut self, settings: &ImportSettings, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.background_import.is_some() {
+            let notif = Notification::new("An import is already running.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        }
+
+        if !self.has_resources(hub, rq, context) {
+            return;
+        }
+
+        if let Some(importer) = context.library.importer(settings) {
+            let hub2 = hub.clone();
+            let mut importer = Some(importer);
+
+            let cancel = context.jobs.spawn("Import documents", RetryPolicy::NONE, hub, move |attempt| {
+                let importer = importer.take().ok_or_else(|| format_err!("the import already ran"))?;
+                let mut last_percent = None;
+                let entries = importer.run(attempt.cancel.flag(), |done, total| {
+                    let percent = 100 * done / total.max(1);
+                    if last_percent != Some(percent) {
+                        last_percent = Some(percent);
+                        hub2.send(Event::TaskProgress(ViewId::ImportNotif, done, total)).ok();
+                    }
+                });
+                hub2.send(Event::ImportFinished(entries)).ok();
+                Ok(())
+            });
+
+            self.background_import = Some(BackgroundImport {
+                home: context.library.home.clone(),
+                cancel,
+            });
+            let notif = Notification::with_progress(ViewId::ImportNotif, "Importing documents.".to_string(), rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+        }
+    }
+
+    fn finish_import(&mut self, entries: &[ImportEntry], hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        close_progress(self, ViewId::ImportNotif, rq, context);
+
+        let background_import = if let Some(background_import) = self.background_import.take() {
+            background_import
+        } else {
+            return;
+        };
+
+        // The library might have been switched in the meantime.
+        if background_import.home == context.library.home {
+            if context.settings.import.fetch_metadata {
+                let documents: Vec<(PathBuf, String)> = entries.iter().filter_map(|entry| entry.info())
+                                                               .filter_map(|info| find_isbn(info).map(|isbn| (info.file.path.clone(), isbn)))
+                                                               .collect();
+                if !documents.is_empty() {
+                    self.fetch_metadata(documents, hub, context);
+                }
+            }
+            context.library.merge(entries);
+        }
+
+        let cancelled = background_import.cancel.is_cancelled();
         context.library.sort(self.sort_method, self.reverse_order);
         self.refresh_visibles(true, false, hub, rq, context);
+
+        if cancelled {
+            let notif = Notification::new("Import cancelled.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+        }
+    }
+
+    fn check_integrity(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.background_check.is_some() {
+            let notif = Notification::new("An integrity check is already running.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        }
+
+        if let Some(check) = context.library.integrity_check() {
+            let hub2 = hub.clone();
+            let mut check = Some(check);
+
+            let cancel = context.jobs.spawn("Check the documents", RetryPolicy::NONE, hub, move |attempt| {
+                let check = check.take().ok_or_else(|| format_err!("the check already ran"))?;
+                let mut last_percent = None;
+                let report = check.run(attempt.cancel.flag(), |done, total| {
+                    let percent = 100 * done / total.max(1);
+                    if last_percent != Some(percent) {
+                        last_percent = Some(percent);
+                        hub2.send(Event::TaskProgress(ViewId::IntegrityNotif, done, total)).ok();
+                    }
+                });
+                hub2.send(Event::IntegrityChecked(Box::new(report))).ok();
+                Ok(())
+            });
+
+            self.background_check = Some(BackgroundImport {
+                home: context.library.home.clone(),
+                cancel,
+            });
+            let notif = Notification::with_progress(ViewId::IntegrityNotif, "Checking documents.".to_string(), rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+        }
+    }
+
+    fn finish_integrity_check(&mut self, report: &IntegrityReport, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        close_progress(self, ViewId::IntegrityNotif, rq, context);
+
+        let background_check = if let Some(background_check) = self.background_check.take() {
+            background_check
+        } else {
+            return;
+        };
+
+        // The library might have been switched in the meantime.
+        if background_check.home != context.library.home {
+            return;
+        }
+
+        context.library.apply_integrity_report(report);
+        self.refresh_visibles(true, false, hub, rq, context);
+
+        let missing = report.problems.iter().filter(|(_, problem)| *problem == Problem::Missing).count();
+        let corrupted = report.problems.len() - missing;
+        let message = if background_check.cancel.is_cancelled() {
+            "Integrity check cancelled.".to_string()
+        } else if report.problems.is_empty() {
+            "No problems found.".to_string()
+        } else {
+            let count = report.problems.len();
+            format!("Found {} damaged document{}: {} missing, {} corrupted.",
+                    count, if count != 1 { "s" } else { "" }, missing, corrupted)
+        };
+        let notif = Notification::new(message, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
     }
 
     fn clean_up(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
@@ -1283,7 +1985,9 @@ impl Home {
     fn insert_fetcher(&mut self, hook: &Hook, hub: &Hub, context: &Context) {
         let library_path = &context.library.home;
         let save_path = context.library.home.join(&hook.path);
-        match self.spawn_child(library_path, &save_path, &hook.program, context.settings.wifi, context.online, hub) {
+        let (wifi, online) = (context.settings.wifi.to_string(), context.online.to_string());
+        let args = [library_path.as_os_str(), save_path.as_os_str(), OsStr::new(&wifi), OsStr::new(&online)];
+        match self.spawn_child(&hook.program, &args, hub) {
             Ok(process) => {
                 let mut sort_method = hook.sort_method;
                 let mut first_column = hook.first_column;
@@ -1306,16 +2010,13 @@ impl Home {
         }
     }
 
-    fn spawn_child(&mut self, library_path: &Path, save_path: &Path, program: &Path, wifi: bool, online: bool, hub: &Hub) -> Result<Child, Error> {
+    fn spawn_child(&mut self, program: &Path, args: &[&OsStr], hub: &Hub) -> Result<Child, Error> {
         let path = program.canonicalize()?;
         let parent = path.parent()
                          .unwrap_or_else(|| Path::new(""));
-        let mut process = Command::new(&path)
+        let mut process = network_env(&mut Command::new(&path))
                                  .current_dir(parent)
-                                 .arg(library_path)
-                                 .arg(save_path)
-                                 .arg(wifi.to_string())
-                                 .arg(online.to_string())
+                                 .args(args)
                                  .stdin(Stdio::piped())
                                  .stdout(Stdio::piped())
                                  .spawn()?;
@@ -1336,6 +2037,13 @@ impl Home {
                                     hub2.send(Event::Notify(msg.to_string())).ok();
                                 }
                             },
+                            Some("progress") => {
+                                let done = event.get("done").and_then(JsonValue::as_u64);
+                                let total = event.get("total").and_then(JsonValue::as_u64);
+                                if let Some((done, total)) = done.zip(total) {
+                                    hub2.send(Event::TaskProgress(ViewId::FetcherNotif(id), done as usize, total as usize)).ok();
+                                }
+                            },
                             Some("setWifi") => {
                                 if let Some(enable) = event.get("enable")
                                                            .and_then(JsonValue::as_bool) {
@@ -1349,6 +2057,20 @@ impl Home {
                                     hub2.send(Event::FetcherAddDocument(id, Box::new(info))).ok();
                                 }
                             },
+                            Some("updateDocument") => {
+                                let path = event.get("path")
+                                                .and_then(JsonValue::as_str)
+                                                .map(PathBuf::from);
+                                let info = event.get("info")
+                                                .map(ToString::to_string)
+                                                .and_then(|v| serde_json::from_str(&v).ok());
+                                let cover = event.get("cover")
+                                                 .and_then(JsonValue::as_str)
+                                                 .map(PathBuf::from);
+                                if let Some((path, info)) = path.zip(info) {
+                                    hub2.send(Event::FetcherUpdateDocument { id, path, info: Box::new(info), cover }).ok();
+                                }
+                            },
                             Some("removeDocument") => {
                                 if let Some(path) = event.get("path")
                                                          .and_then(JsonValue::as_str) {
@@ -1367,6 +2089,11 @@ impl Home {
                                                    .and_then(|v| serde_json::from_str(&v).ok());
                                 hub2.send(Event::FetcherSearch { id, path, query, sort_by }).ok();
                             },
+                            Some("download") => {
+                                if let Ok(download) = serde_json::from_value::<Download>(event.clone()) {
+                                    hub2.send(Event::QueueDownload(Box::new(download))).ok();
+                                }
+                            },
                             _ => (),
                         }
                     }
@@ -1379,6 +2106,70 @@ impl Home {
         Ok(process)
     }
 
+    // Spawns the metadata fetcher for the given documents and ISBNs.
+    fn fetch_metadata(&mut self, documents: Vec<(PathBuf, String)>, hub: &Hub, context: &Context) {
+        let library_path = context.library.home.clone();
+        let program = context.settings.import.metadata_fetcher.clone();
+        let wifi = context.settings.wifi.to_string();
+        let online = context.online.to_string();
+        let mut args = vec![library_path.as_os_str(), OsStr::new(&wifi), OsStr::new(&online)];
+        for (path, isbn) in &documents {
+            args.push(path.as_os_str());
+            args.push(OsStr::new(isbn));
+        }
+        match self.spawn_child(&program, &args, hub) {
+            Ok(process) => {
+                self.background_fetchers.insert(process.id(),
+                                                Fetcher { path: program, full_path: PathBuf::default(), process,
+                                                          sort_method: None, first_column: None, second_column: None });
+            },
+            Err(e) => eprintln!("Can't spawn child: {:#}.", e),
+        }
+    }
+
+    fn update_document(&mut self, path: &Path, info: &Info, cover: Option<&Path>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        context.library.update_info(path, |current| merge_missing(current, info));
+
+        if let Some(cover) = cover {
+            let thumb_path = context.library.thumbnail_preview(path);
+            generate_preview(context.library.home.join(cover), 0, path.to_path_buf(), thumb_path, hub);
+        }
+
+        self.refresh_visibles(true, false, hub, rq, context);
+    }
+
+    // Shows a random unread book, with a button to open it.
+    fn surprise(&mut self, collection: Option<usize>, length: Option<BookLength>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let query = match collection {
+            Some(index) => context.settings.home.smart_collections.get(index)
+                                  .and_then(|c| BookQuery::new(&c.query)),
+            None => self.query.clone(),
+        };
+        let info = context.library.surprise(query.as_ref(), length, &mut context.rng).cloned();
+        let Some(info) = info else {
+            let notif = Notification::new("No unread books found.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        };
+        let mut rows = Vec::new();
+        for (name, value) in [("Author", &info.author), ("Series", &info.series), ("Year", &info.year)] {
+            if !value.is_empty() {
+                rows.push((name.to_string(), value.clone()));
+            }
+        }
+        if let Some(statistics) = info.statistics.as_ref() {
+            rows.push(("Pages".to_string(), statistics.pages.to_string()));
+            rows.push(("Reading Time".to_string(),
+                       format_duration(statistics.words as f32 / context.library.words_per_minute()
+                                                                       .unwrap_or(DEFAULT_WORDS_PER_MINUTE))));
+        }
+        let title = info.title();
+        let view = StatisticsView::new(ViewId::Statistics, title, rows, context)
+                                  .action("Open".to_string(), Event::Open(Box::new(info)), context);
+        rq.add(RenderData::new(view.id(), *view.rect(), UpdateMode::Gui));
+        self.children.push(Box::new(view) as Box<dyn View>);
+    }
+
     fn reseed(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         context.library.sort(self.sort_method, self.reverse_order);
         self.refresh_visibles(true, false, hub, &mut RenderQueue::new(), context);
@@ -1391,6 +2182,19 @@ impl Home {
     }
 }
 
+// The images of the given directory, relative to the library's home.
+fn cover_images(home: &Path, dir: &Path) -> Vec<PathBuf> {
+    let mut images = fs::read_dir(home.join(dir)).into_iter().flatten()
+                         .filter_map(|entry| entry.ok())
+                         .map(|entry| dir.join(entry.file_name()))
+                         .filter(|path| path.extension().and_then(OsStr::to_str)
+                                            .is_some_and(|ext| COVER_KINDS.contains(&ext.to_lowercase().as_str())))
+                         .collect::<Vec<PathBuf>>();
+    images.sort();
+    images.truncate(MAX_COVER_IMAGES);
+    images
+}
+
 impl View for Home {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
@@ -1402,6 +2206,8 @@ impl View for Home {
                             self.toggle_navigation_bar(Some(true), true, hub, rq, context);
                         } else if !context.settings.home.address_bar {
                             self.toggle_address_bar(Some(true), true, hub, rq, context);
+                        } else {
+                            self.toggle_global_search(Some(true), hub, rq, context);
                         }
                     },
                     Dir::North if self.children[self.shelf_index].rect().includes(start) &&
@@ -1416,7 +2222,7 @@ impl View for Home {
                 }
                 true
             },
-            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 => {
+            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 && context.rotate_gesture_enabled() => {
                 let (_, dir) = CURRENT_DEVICE.mirroring_scheme();
                 let n = (4 + (context.display.rotation - dir * quarter_turns)) % 4;
                 hub.send(Event::Select(EntryId::Rotate(n))).ok();
@@ -1487,8 +2293,24 @@ impl View for Home {
                 toggle_clock_menu(self, rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::WeatherMenu, rect) => {
+                toggle_weather_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::WeatherUpdated(..) => {
+                if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+                    top_bar.update_weather_label(hub, rq, context);
+                }
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
             Event::ToggleNear(ViewId::LibraryMenu, rect) => {
-                self.toggle_library_menu(rect, None, rq, context);
+                if !context.settings.kiosk.enabled {
+                    self.toggle_library_menu(rect, None, rq, context);
+                }
                 true
             },
             Event::Close(ViewId::AddressBar) => {
@@ -1515,10 +2337,62 @@ impl View for Home {
                 self.toggle_go_to_page(Some(false), hub, rq, context);
                 true
             },
+            Event::Close(ViewId::GlobalSearch) => {
+                self.toggle_global_search(Some(false), hub, rq, context);
+                true
+            },
+            Event::Close(ViewId::GlobalSearchResults) => {
+                if let Some(index) = locate_by_id(self, ViewId::GlobalSearchResults) {
+                    rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+                    self.children.remove(index);
+                }
+                self.toggle_keyboard(false, true, None, hub, rq, context);
+                true
+            },
+            Event::Close(ViewId::Handoff) => {
+                self.toggle_handoff(Some(false), hub, rq, context);
+                true
+            },
             Event::Close(ViewId::RenameDocument) => {
                 self.toggle_rename_document(Some(false), hub, rq, context);
                 true
             },
+            Event::Close(ViewId::DocumentPassword) => {
+                self.toggle_document_password(Some(false), hub, rq, context);
+                true
+            },
+            Event::Protected(ref path) => {
+                if let Some(info) = self.visible_books.iter().find(|info| info.file.path == *path) {
+                    self.protected_document = Some(info.clone());
+                    self.toggle_document_password(Some(true), hub, rq, context);
+                }
+                true
+            },
+            Event::Submit(ViewId::DocumentPasswordInput, ref password) => {
+                if let Some(info) = self.protected_document.take() {
+                    if !context.passwords.contains(password) {
+                        context.passwords.push(password.clone());
+                    }
+                    hub.send(Event::Open(Box::new(info))).ok();
+                }
+                true
+            },
+            Event::Close(ViewId::KioskPin) => {
+                self.toggle_kiosk_pin(Some(false), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::LockKiosk) => {
+                self.lock_kiosk(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::UnlockKiosk) => {
+                self.toggle_kiosk_pin(Some(true), hub, rq, context);
+                true
+            },
+            Event::Submit(ViewId::KioskPinInput, ref pin) => {
+                self.unlock_kiosk(pin, hub, rq, context);
+                true
+            },
             Event::Select(EntryId::Sort(sort_method)) => {
                 let selected_library = context.settings.selected_library;
                 context.settings.libraries[selected_library].sort_method = sort_method;
@@ -1535,7 +2409,60 @@ impl View for Home {
                 true
             },
             Event::Select(EntryId::Import) => {
-                self.import(hub, rq, context);
+                let settings = context.settings.import.clone();
+                self.import(&settings, hub, rq, context);
+                true
+            },
+            Event::Import(ref settings) => {
+                self.import(settings, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::CancelImport) => {
+                if let Some(background_import) = self.background_import.as_ref() {
+                    background_import.cancel.cancel();
+                }
+                true
+            },
+            Event::TaskProgress(view_id @ ViewId::FetcherNotif(..), done, total) => {
+                if done >= total {
+                    close_progress(self, view_id, rq, context);
+                } else {
+                    if locate_by_id(self, view_id).is_none() {
+                        let notif = Notification::with_progress(view_id, "Fetching documents.".to_string(), rq, context);
+                        self.children.push(Box::new(notif) as Box<dyn View>);
+                    }
+                    update_progress(self, view_id, done, total, rq);
+                }
+                true
+            },
+            Event::TaskProgress(view_id, done, total) => {
+                update_progress(self, view_id, done, total, rq)
+            },
+            Event::ImportFinished(ref entries) => {
+                self.finish_import(entries, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::CheckIntegrity) | Event::CheckIntegrity => {
+                self.check_integrity(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::CancelIntegrityCheck) => {
+                if let Some(background_check) = self.background_check.as_ref() {
+                    background_check.cancel.cancel();
+                }
+                true
+            },
+            Event::IntegrityChecked(ref report) => {
+                self.finish_integrity_check(report, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::Reimport(ref path)) => {
+                if let Err(e) = context.library.reimport(path) {
+                    eprintln!("Can't re-import {}: {:#}.", path.display(), e);
+                    let notif = Notification::new(format!("Can't re-import: {}.", e), hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
+                }
+                self.refresh_visibles(true, false, hub, rq, context);
                 true
             },
             Event::Select(EntryId::CleanUp) => {
@@ -1547,9 +2474,43 @@ impl View for Home {
                 true
             },
             Event::FetcherAddDocument(_, ref info) => {
+                let msg = format!("Added {}.", info.title());
+                let notif = Notification::with_action(msg, "Open".to_string(),
+                                                      Event::Open(info.clone()), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
                 self.add_document(*info.clone(), hub, rq, context);
                 true
             },
+            Event::FetcherUpdateDocument { ref path, ref info, ref cover, .. } => {
+                self.update_document(path, info, cover.as_deref(), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::FetchMetadata(ref path)) => {
+                let isbn = self.visible_books.iter()
+                               .find(|info| info.file.path == *path)
+                               .and_then(find_isbn);
+                if let Some(isbn) = isbn {
+                    self.fetch_metadata(vec![(path.clone(), isbn)], hub, context);
+                } else {
+                    let notif = Notification::new("No ISBN found.".to_string(), hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
+                }
+                true
+            },
+            Event::Select(EntryId::SetBookLanguage(ref path, ref language)) => {
+                context.library.update_info(path, |info| info.language = language.clone());
+                if let Some(info) = self.visible_books.iter_mut().find(|info| info.file.path == *path) {
+                    info.language = language.clone();
+                }
+                true
+            },
+            Event::Select(EntryId::SetCover(ref path, ref cover)) => {
+                set_cover(path, cover.clone(), hub, context);
+                if let Some(info) = self.visible_books.iter_mut().find(|info| info.file.path == *path) {
+                    info.cover = cover.clone();
+                }
+                true
+            },
             Event::Select(EntryId::SetStatus(ref path, status)) => {
                 self.set_status(path, status, hub, rq, context);
                 true
@@ -1593,6 +2554,11 @@ impl View for Home {
                 }
                 true
             },
+            Event::Submit(ViewId::GlobalSearchInput, ref text) => {
+                self.toggle_global_search(Some(false), hub, rq, context);
+                self.show_global_search_results(text, hub, rq, context);
+                true
+            },
             Event::Submit(ViewId::GoToPageInput, ref text) => {
                 if text == "(" {
                     self.go_to_page(0, hub, rq, context);
@@ -1624,15 +2590,42 @@ impl View for Home {
                 true
             },
             Event::Select(EntryId::EmptyTrash) => {
+                let event = Event::Select(EntryId::ConfirmEmptyTrash);
+                self.confirm("Permanently remove the books in the trash?".to_string(), event, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ConfirmEmptyTrash) => {
                 self.empty_trash(hub, rq, context);
                 true
             },
+            Event::Select(EntryId::ClearCaches) => {
+                let freed = storage::clear_caches(context.library.cache_home());
+                let notif = Notification::new(format!("Freed {}.", freed.human_size()), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                true
+            },
+            Event::Select(EntryId::ReceiveHandoff) => {
+                self.toggle_handoff(Some(true), hub, rq, context);
+                true
+            },
+            Event::Submit(ViewId::HandoffInput, ref text) => {
+                self.toggle_handoff(Some(false), hub, rq, context);
+                self.receive_handoff(text, hub, rq, context);
+                true
+            },
             Event::Select(EntryId::Rename(ref path)) => {
                 self.target_document = Some(path.clone());
                 self.toggle_rename_document(Some(true), hub, rq, context);
                 true
             },
-            Event::Select(EntryId::Remove(ref path)) | Event::FetcherRemoveDocument(_, ref path) => {
+            Event::Select(EntryId::Remove(ref path)) => {
+                let name = path.file_name().map(|name| name.to_string_lossy().into_owned())
+                               .unwrap_or_default();
+                let event = Event::Select(EntryId::ConfirmRemove(path.clone()));
+                self.confirm(format!("Remove {}?", name), event, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ConfirmRemove(ref path)) | Event::FetcherRemoveDocument(_, ref path) => {
                 self.remove(path, hub, rq, context)
                     .map_err(|e| eprintln!("Can't remove document: {:#}.", e))
                     .ok();
@@ -1667,19 +2660,93 @@ impl View for Home {
             },
             Event::Select(EntryId::SearchAuthor(ref author)) => {
                 let text = format!("'a {}", author);
-                let query = BookQuery::new(&text);
-                if query.is_some() {
-                    self.query = query;
-                    self.toggle_search_bar(Some(true), false, hub, rq, context);
-                    self.toggle_keyboard(false, false, None, hub, rq, context);
-                    if let Some(search_bar) = self.children[self.shelf_index+2].downcast_mut::<SearchBar>() {
-                        search_bar.set_text(&text, rq, context);
-                    }
-                    // Render the search bar and its separator.
-                    for i in self.shelf_index + 1 ..= self.shelf_index + 2 {
-                        rq.add(RenderData::new(self.child(i).id(), *self.child(i).rect(), UpdateMode::Gui));
-                    }
-                    self.refresh_visibles(true, true, hub, rq, context);
+                self.search(&text, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SearchSeries(ref series)) => {
+                let text = format!("series:\"{}\"", series);
+                self.search(&text, hub, rq, context);
+                if self.sort_method != SortMethod::Series {
+                    hub.send(Event::Select(EntryId::Sort(SortMethod::Series))).ok();
+                }
+                true
+            },
+            Event::Select(EntryId::OpenDocument(ref path)) => {
+                if let Some(info) = context.library.info_from_path(path) {
+                    hub.send(Event::Open(Box::new(info))).ok();
+                }
+                true
+            },
+            Event::Select(EntryId::OpenNextInSeries(ref path)) => {
+                if let Some(info) = context.library.db.values().find(|info| info.file.path == *path) {
+                    hub.send(Event::Open(Box::new(info.clone()))).ok();
+                }
+                true
+            },
+            Event::Select(EntryId::ApplySmartCollection(index)) => {
+                if let Some(text) = context.settings.home.smart_collections.get(index)
+                                           .map(|c| c.query.clone()) {
+                    self.search(&text, hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::SurpriseMe(collection, length)) => {
+                self.surprise(collection, length, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SwitchProfile(ref name)) => {
+                self.switch_profile(name, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::CreateBackup) => {
+                self.create_backup(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::RestoreBackup(ref path)) => {
+                let event = Event::Select(EntryId::ConfirmRestoreBackup(path.clone()));
+                self.confirm("Replace the settings and the reading states with the backup's?".to_string(), event, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ConfirmRestoreBackup(ref path)) => {
+                self.restore_backup(path, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::RestoreSnapshot(ref path)) => {
+                let event = Event::Select(EntryId::ConfirmRestoreSnapshot(path.clone()));
+                self.confirm("Roll the reading states back to this restore point?".to_string(), event, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ConfirmRestoreSnapshot(ref path)) => {
+                self.restore_snapshot(path, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::AddProfile) => {
+                self.toggle_profile_name(Some(true), hub, rq, context);
+                true
+            },
+            Event::Close(ViewId::ProfileName) => {
+                self.toggle_profile_name(Some(false), hub, rq, context);
+                true
+            },
+            Event::Submit(ViewId::ProfileNameInput, ref name) => {
+                self.add_profile(name, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SaveSmartCollection) => {
+                let text = self.children.get(self.shelf_index+2)
+                               .and_then(|child| child.downcast_ref::<SearchBar>())
+                               .map(|search_bar| search_bar.text().trim().to_string())
+                               .filter(|text| !text.is_empty());
+                if let Some(text) = text {
+                    let collections = &mut context.settings.home.smart_collections;
+                    let message = if collections.iter().any(|c| c.query == text) {
+                        "This search is already saved.".to_string()
+                    } else {
+                        collections.push(SmartCollection { name: text.clone(), query: text.clone() });
+                        format!("Saved the search '{}'.", text)
+                    };
+                    let notif = Notification::new(message, hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
                 }
                 true
             },
@@ -1735,6 +2802,7 @@ impl View for Home {
                 true
             },
             Event::CheckFetcher(id) => {
+                close_progress(self, ViewId::FetcherNotif(id), rq, context);
                 if let Some(fetcher) = self.background_fetchers.get_mut(&id) {
                     if let Ok(exit_status) = fetcher.process.wait() {
                         if !exit_status.success() {
@@ -1898,4 +2966,16 @@ impl View for Home {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn save_state(&self, session: &mut Session) {
+        let query = self.query.as_ref().and_then(|_| {
+            self.children[self.shelf_index+2].downcast_ref::<SearchBar>()
+                .map(|search_bar| search_bar.text().to_string())
+        });
+        session.home = HomeState {
+            directory: self.current_directory.clone(),
+            query,
+            page: self.current_page,
+        };
+    }
 }
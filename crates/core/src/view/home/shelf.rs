@@ -1,20 +1,20 @@
 use std::thread;
 use std::sync::Mutex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use lazy_static::lazy_static;
 use super::book::Book;
 use crate::device::CURRENT_DEVICE;
 use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData};
 use crate::view::{BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
 use crate::view::filler::Filler;
-use crate::document::open;
+use crate::document::{open, Location};
 use crate::framebuffer::{Framebuffer, UpdateMode};
 use crate::settings::{FirstColumn, SecondColumn};
 use crate::geom::{Rectangle, Dir, CycleDir, halves};
 use crate::color::{WHITE, SEPARATOR_NORMAL};
 use crate::gesture::GestureEvent;
 use crate::unit::scale_by_dpi;
-use crate::metadata::Info;
+use crate::metadata::{Info, Cover};
 use crate::geom::divide;
 use crate::font::Fonts;
 use crate::context::Context;
@@ -23,6 +23,47 @@ lazy_static! {
     static ref EXCLUSIVE_ACCESS: Mutex<u8> = Mutex::new(0);
 }
 
+// Renders the given page of `source` into the thumbnail preview of the document at `path`.
+pub fn generate_preview(source: PathBuf, page: usize, path: PathBuf, thumb_path: PathBuf, hub: &Hub) {
+    let th = scale_by_dpi(BIG_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32;
+    let tw = 3 * th / 4;
+    let hub2 = hub.clone();
+    thread::spawn(move || {
+        // This is a hack to circumvent a segfault (EXC_BAD_ACCESS)
+        // triggered by loading multiple jp2 pixmaps in parallel.
+        let _guard = EXCLUSIVE_ACCESS.lock().unwrap();
+        open(source).and_then(|mut doc| {
+            if page == 0 {
+                return doc.preview_pixmap(tw as f32, th as f32);
+            }
+            let dims = doc.dims(page)?;
+            let scale = (tw as f32 / dims.0).min(th as f32 / dims.1);
+            doc.pixmap(Location::Exact(page), scale).map(|(pixmap, _)| pixmap)
+        }).map(|pixmap| {
+            if pixmap.save(&thumb_path.to_string_lossy()).is_ok() {
+                hub2.send(Event::RefreshBookPreview(path, Some(thumb_path))).ok();
+            }
+        })
+    });
+}
+
+// The file and the page the thumbnail preview of the given book is rendered from.
+pub fn preview_source(home: &Path, path: &Path, cover: Option<&Cover>) -> (PathBuf, usize) {
+    match cover {
+        Some(Cover::Page(page)) => (home.join(path), *page),
+        Some(Cover::Image(image)) => (home.join(image), 0),
+        None => (home.join(path), 0),
+    }
+}
+
+// Records the chosen cover of the given book and renders its thumbnail preview from it.
+pub fn set_cover(path: &Path, cover: Option<Cover>, hub: &Hub, context: &mut Context) {
+    let (source, page) = preview_source(&context.library.home, path, cover.as_ref());
+    context.library.update_info(path, |info| info.cover = cover);
+    let thumb_path = context.library.thumbnail_preview(path);
+    generate_preview(source, page, path.to_path_buf(), thumb_path, hub);
+}
+
 pub struct Shelf {
     id: Id,
     pub rect: Rectangle,
@@ -71,8 +112,6 @@ impl Shelf {
         let max_lines = ((self.rect.height() as i32 + thickness) / big_height) as usize;
         let book_heights = divide(self.rect.height() as i32, max_lines as i32);
         let mut y_pos = self.rect.min.y;
-        let th = big_height;
-        let tw = 3 * th / 4;
 
         for (index, info) in metadata.iter().enumerate() {
             let y_min = y_pos + if index > 0 { big_thickness } else { 0 };
@@ -81,22 +120,8 @@ impl Shelf {
             let preview_path: Option<PathBuf> = if self.thumbnail_previews {
                 let thumb_path = context.library.thumbnail_preview(&info.file.path);
                 if !thumb_path.exists() {
-                    let hub2 = hub.clone();
-                    let thumb_path2 = thumb_path.to_string_lossy().into_owned();
-                    let path = info.file.path.clone();
-                    let full_path = context.library.home.join(&info.file.path);
-                    thread::spawn(move || {
-                        // This is a hack to circumvent a segfault (EXC_BAD_ACCESS)
-                        // triggered by loading multiple jp2 pixmaps in parallel.
-                        let _guard = EXCLUSIVE_ACCESS.lock().unwrap();
-                        open(full_path).and_then(|mut doc| {
-                            doc.preview_pixmap(tw as f32, th as f32)
-                        }).map(|pixmap| {
-                            if pixmap.save(&thumb_path2).is_ok() {
-                                hub2.send(Event::RefreshBookPreview(path, Some(PathBuf::from(thumb_path2)))).ok();
-                            }
-                        })
-                    });
+                    let (source, page) = preview_source(&context.library.home, &info.file.path, info.cover.as_ref());
+                    generate_preview(source, page, info.file.path.clone(), thumb_path, hub);
                     Some(PathBuf::default())
                 } else {
                     Some(thumb_path)
@@ -111,7 +136,8 @@ impl Shelf {
                                  index,
                                  self.first_column,
                                  self.second_column,
-                                 preview_path);
+                                 preview_path,
+                                 context.library.problems.get(&info.file.path).cloned());
             self.children.push(Box::new(book) as Box<dyn View>);
 
             if index < max_lines - 1 {
@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender, SendError, RecvError, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+use fxhash::FxHashSet;
+use super::Event;
+
+// The receiver handles the pending events of the highest priorities first, so that
+// long-running background tasks can't delay the handling of the user's input.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Priority {
+    Input,
+    Interface,
+    Render,
+    Background,
+}
+
+impl Event {
+    pub fn priority(&self) -> Priority {
+        match self {
+            Event::Device(..) | Event::Gesture(..) |
+            Event::Keyboard(..) | Event::Key(..) => Priority::Input,
+            Event::Update(..) | Event::LoadPixmap(..) |
+            Event::RefreshBookPreview(..) => Priority::Render,
+            Event::SearchResult(..) | Event::EndOfSearch | Event::RecognizedText(..) |
+            Event::FetcherAddDocument(..) | Event::FetcherRemoveDocument(..) |
+            Event::FetcherUpdateDocument { .. } |
+            Event::FetcherSearch { .. } | Event::CheckFetcher(..) |
+            Event::DownloadProgress(..) | Event::DownloadFinished(..) | Event::RetryDownloads |
+            Event::TaskProgress(..) | Event::ImportFinished(..) | Event::JobsChanged |
+            Event::ProcessLine(..) | Event::ClockTick | Event::TimerTick | Event::BatteryTick |
+            Event::CheckBattery | Event::MightSuspend => Priority::Background,
+            _ => Priority::Interface,
+        }
+    }
+}
+
+// Each clone of a hub is a distinct sender: its events are received in the order in which
+// they were sent, whatever their priorities. Only the events of different senders overtake
+// each other, since they can't be causally related.
+pub struct Hub {
+    sender: Sender<(SenderId, Event)>,
+    id: SenderId,
+}
+
+type SenderId = u64;
+
+static SENDER_IDS: AtomicU64 = AtomicU64::new(0);
+
+impl Clone for Hub {
+    fn clone(&self) -> Hub {
+        Hub {
+            sender: self.sender.clone(),
+            id: SENDER_IDS.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+pub struct HubReceiver {
+    receiver: mpsc::Receiver<(SenderId, Event)>,
+    // The events received from the channel, in the order in which they were sent.
+    pending: RefCell<VecDeque<(SenderId, Event)>>,
+}
+
+pub fn channel() -> (Hub, HubReceiver) {
+    let (tx, rx) = mpsc::channel();
+    (Hub { sender: tx, id: SENDER_IDS.fetch_add(1, Ordering::Relaxed) },
+     HubReceiver { receiver: rx, pending: RefCell::new(VecDeque::new()) })
+}
+
+impl Hub {
+    pub fn send(&self, evt: Event) -> Result<(), SendError<Event>> {
+        self.sender.send((self.id, evt))
+            .map_err(|SendError((_, evt))| SendError(evt))
+    }
+}
+
+impl HubReceiver {
+    // Moves the events waiting in the channel to the pending ones.
+    // Returns whether the channel is disconnected.
+    fn fetch(&self) -> bool {
+        let mut pending = self.pending.borrow_mut();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(item) => pending.push_back(item),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => return true,
+            }
+        }
+    }
+
+    // Removes the pending event of the highest priority that isn't preceded by an event
+    // of the same sender, or the oldest one among those of equal priority.
+    fn pop(&self) -> Option<Event> {
+        let mut pending = self.pending.borrow_mut();
+        let mut best: Option<(usize, Priority)> = None;
+        let mut blocked = FxHashSet::default();
+        for (index, (id, evt)) in pending.iter().enumerate() {
+            if !blocked.insert(*id) {
+                continue;
+            }
+            let priority = evt.priority();
+            if best.is_none_or(|(_, best_priority)| priority < best_priority) {
+                best = Some((index, priority));
+            }
+        }
+        best.and_then(|(index, _)| pending.remove(index))
+            .map(|(_, evt)| evt)
+    }
+
+    pub fn try_recv(&self) -> Result<Event, TryRecvError> {
+        let disconnected = self.fetch();
+        match self.pop() {
+            Some(evt) => Ok(evt),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv(&self) -> Result<Event, RecvError> {
+        loop {
+            match self.try_recv() {
+                Ok(evt) => return Ok(evt),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => {
+                    let item = self.receiver.recv()?;
+                    self.pending.borrow_mut().push_back(item);
+                },
+            }
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Event, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.try_recv() {
+                Ok(evt) => return Ok(evt),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    let item = self.receiver.recv_timeout(remaining)?;
+                    self.pending.borrow_mut().push_back(item);
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use crate::view::key::KeyKind;
+
+    #[test]
+    fn test_priorities() {
+        let (hub, rx) = channel();
+        hub.clone().send(Event::ClockTick).unwrap();
+        hub.clone().send(Event::Back).unwrap();
+        hub.clone().send(Event::LoadPixmap(3)).unwrap();
+        hub.clone().send(Event::Key(KeyKind::Return)).unwrap();
+        hub.clone().send(Event::Quit).unwrap();
+        let events: Vec<Priority> = (0..5).map(|_| rx.recv().unwrap().priority()).collect();
+        assert_eq!(events, vec![Priority::Input, Priority::Interface, Priority::Interface,
+                                Priority::Render, Priority::Background]);
+        assert!(rx.recv_timeout(Duration::from_millis(5)).is_err());
+    }
+
+    #[test]
+    fn test_sender_order() {
+        let (hub, rx) = channel();
+        let fetcher = hub.clone();
+        let input = hub.clone();
+        fetcher.send(Event::CheckFetcher(1)).unwrap();
+        fetcher.send(Event::Notify("Done.".to_string())).unwrap();
+        hub.send(Event::LoadPixmap(3)).unwrap();
+        hub.send(Event::Back).unwrap();
+        input.send(Event::Key(KeyKind::Return)).unwrap();
+        // The input overtakes the other senders' events, which keep their order.
+        assert!(matches!(rx.recv(), Ok(Event::Key(KeyKind::Return))));
+        assert!(matches!(rx.recv(), Ok(Event::LoadPixmap(3))));
+        assert!(matches!(rx.recv(), Ok(Event::Back)));
+        assert!(matches!(rx.recv(), Ok(Event::CheckFetcher(1))));
+        assert!(matches!(rx.recv(), Ok(Event::Notify(..))));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_disconnection() {
+        let (hub, rx) = channel();
+        let handle = thread::spawn(move || {
+            hub.send(Event::BatteryTick).unwrap();
+        });
+        handle.join().unwrap();
+        assert!(matches!(rx.recv(), Ok(Event::BatteryTick)));
+        assert!(rx.recv().is_err());
+    }
+}
@@ -175,4 +175,8 @@ impl View for Icon {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
 }
@@ -120,6 +120,10 @@ impl InputField {
         }
     }
 
+    pub fn value(&self) -> &str {
+        &self.text
+    }
+
     pub fn text_before_cursor(&self) -> &str {
         &self.text[..self.cursor]
     }
@@ -356,4 +360,8 @@ impl View for InputField {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
 }
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use crate::device::CURRENT_DEVICE;
 use crate::document::{Location, open};
 use crate::geom::Rectangle;
-use crate::font::{Fonts, font_from_style, DISPLAY_STYLE};
+use crate::font::{Fonts, font_from_style, DISPLAY_STYLE, NORMAL_STYLE};
 use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue};
 use crate::framebuffer::Framebuffer;
 use crate::settings::{IntermKind, LOGO_SPECIAL_PATH, COVER_SPECIAL_PATH};
@@ -15,6 +15,7 @@ pub struct Intermission {
     rect: Rectangle,
     children: Vec<Box<dyn View>>,
     message: Message,
+    weather: Option<String>,
     halt: bool,
 }
 
@@ -27,8 +28,11 @@ pub enum Message {
 impl Intermission {
     pub fn new(rect: Rectangle, kind: IntermKind, context: &Context) -> Intermission {
         let path = &context.settings.intermissions[kind];
+        // The cover of the current book isn't shown on a locked device.
+        let locked = kind == IntermKind::Suspend && !context.settings.lock.pin.is_empty();
         let message = match path.to_str() {
             Some(LOGO_SPECIAL_PATH) => Message::Text(kind.text().to_string()),
+            Some(COVER_SPECIAL_PATH) if locked => Message::Text(kind.text().to_string()),
             Some(COVER_SPECIAL_PATH) => {
                 let query = BookQuery {
                     reading: Some(true),
@@ -44,11 +48,24 @@ impl Intermission {
             },
             _ => Message::Image(path.clone()),
         };
+        let weather = if kind == IntermKind::Suspend && context.settings.weather.sleep_screen {
+            context.weather.forecast.as_ref().map(|forecast| {
+                let location = &context.settings.weather.location;
+                if location.is_empty() {
+                    forecast.summary()
+                } else {
+                    format!("{} · {}", location, forecast.summary())
+                }
+            })
+        } else {
+            None
+        };
         Intermission {
             id: ID_FEEDER.next(),
             rect,
             children: Vec::new(),
             message,
+            weather,
             halt: kind == IntermKind::PowerOff,
         }
     }
@@ -135,6 +152,20 @@ impl View for Intermission {
                 }
             },
         }
+
+        if let Some(text) = self.weather.as_ref() {
+            let dpi = CURRENT_DEVICE.dpi;
+            let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+            let padding = font.em() as i32;
+            let plan = font.plan(text, Some(self.rect.width() as i32 - 2 * padding), None);
+            let height = 2 * padding + font.x_heights.0 as i32;
+            let band = rect![self.rect.min.x, self.rect.max.y - height,
+                             self.rect.max.x, self.rect.max.y];
+            fb.draw_rectangle(&band, scheme[0]);
+            let dx = (self.rect.width() as i32 - plan.width) / 2;
+            let dy = (height - font.x_heights.0 as i32) / 2;
+            font.render(fb, scheme[1], &plan, pt!(band.min.x + dx, band.max.y - dy));
+        }
     }
 
     fn might_rotate(&self) -> bool {
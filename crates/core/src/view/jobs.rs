@@ -0,0 +1,175 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, EntryId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::label::Label;
+use super::button::Button;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::jobs::{JobRecord, JobStatus};
+use crate::context::Context;
+
+// Lists the background jobs and their status. Tapping a running job cancels it.
+pub struct JobsView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+}
+
+impl JobsView {
+    pub fn new(context: &mut Context) -> JobsView {
+        let mut jobs_view = JobsView {
+            id: ID_FEEDER.next(),
+            rect: Rectangle::default(),
+            children: Vec::new(),
+        };
+        jobs_view.layout(context);
+        jobs_view
+    }
+
+    fn layout(&mut self, context: &mut Context) {
+        let records = context.jobs.records();
+        let (width, height) = context.display.dims;
+        let (line_height, padding) = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+            (font.line_height(), font.em() as i32)
+        };
+        let max_rows = ((height as i32 - 2 * margin()) / line_height - 5).max(1) as usize;
+        let rows_count = records.len().clamp(1, max_rows);
+        let view_width = 2 * width.min(height) as i32 / 3 + 2 * margin();
+        let view_height = (rows_count as i32 + 4) * line_height + 2 * margin();
+        let dx = (width as i32 - view_width) / 2;
+        let dy = (height as i32 - view_height) / 2;
+        self.rect = rect![dx, dy, dx + view_width, dy + view_height];
+
+        let (left, right) = (self.rect.min.x + margin(), self.rect.max.x - margin());
+        let mut y = self.rect.min.y + margin();
+        self.children.clear();
+        self.children.push(Box::new(Label::new(rect![left, y, right, y + line_height],
+                                               "Background Tasks".to_string(), Align::Center)) as Box<dyn View>);
+        y += 2 * line_height;
+
+        if records.is_empty() {
+            self.children.push(Box::new(Label::new(rect![left, y, right, y + line_height],
+                                                   "No Tasks".to_string(), Align::Center)) as Box<dyn View>);
+        }
+
+        let middle = (left + right) / 2;
+        // The most recent jobs first.
+        for record in records.iter().rev().take(rows_count) {
+            let event = Some(Event::Select(EntryId::CancelJob(record.id)))
+                            .filter(|_| !record.status.is_finished());
+            self.children.push(Box::new(Label::new(rect![left, y, middle, y + line_height],
+                                                   record.name.clone(), Align::Left(padding))
+                                        .event(event.clone())) as Box<dyn View>);
+            self.children.push(Box::new(Label::new(rect![middle, y, right, y + line_height],
+                                                   status_text(record), Align::Right(padding))
+                                        .event(event)) as Box<dyn View>);
+            y += line_height;
+        }
+
+        y = self.rect.max.y - margin() - line_height;
+        let button_width = (right - left) / 2;
+        let button_rect = rect![middle - button_width / 2, y, middle + button_width / 2, y + line_height];
+        self.children.push(Box::new(Button::new(button_rect, Event::Select(EntryId::ClearJobs),
+                                                "Clear Finished".to_string())
+                                    .disabled(!records.iter().any(|r| r.status.is_finished()))) as Box<dyn View>);
+    }
+
+    fn update(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let old_rect = self.rect;
+        self.layout(context);
+        rq.add(RenderData::expose(old_rect, UpdateMode::Gui));
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn status_text(record: &JobRecord) -> String {
+    match record.status {
+        JobStatus::Running if record.attempts > 1 => format!("Running (attempt {})", record.attempts),
+        JobStatus::Running => "Running".to_string(),
+        JobStatus::Retrying(..) => format!("Retrying after attempt {}", record.attempts),
+        JobStatus::Done => format!("Done at {}", record.started.format("%H:%M")),
+        JobStatus::Failed(ref e) => format!("Failed: {}", e),
+        JobStatus::Cancelled => "Cancelled".to_string(),
+    }
+}
+
+fn margin() -> i32 {
+    let dpi = CURRENT_DEVICE.dpi;
+    scale_by_dpi(THICKNESS_LARGE, dpi) as i32 + scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32
+}
+
+impl View for JobsView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::JobsChanged => {
+                self.update(rq, context);
+                false
+            },
+            Event::Select(EntryId::CancelJob(id)) => {
+                context.jobs.cancel(id);
+                true
+            },
+            Event::Select(EntryId::ClearJobs) => {
+                context.jobs.clear_finished();
+                self.update(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::Jobs));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.layout(context);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::Jobs)
+    }
+}
@@ -7,11 +7,12 @@ use crate::gesture::GestureEvent;
 use crate::input::DeviceEvent;
 use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, KeyboardEvent, EntryId, TextKind};
 use super::key::{Key, KeyKind};
+use super::handwriting_pad::HandwritingPad;
 use super::BIG_BAR_HEIGHT;
 use crate::color::KEYBOARD_BG;
 use crate::font::Fonts;
 use crate::context::Context;
-use crate::geom::Rectangle;
+use crate::geom::{Rectangle, LinearDir};
 use crate::unit::scale_by_dpi;
 
 const PADDING_RATIO: f32 = 0.06;
@@ -39,6 +40,8 @@ pub struct Keyboard {
     layout: Layout,
     state: State,
     combine_buffer: String,
+    // Whether the keys are replaced by a handwriting pad.
+    handwriting: bool,
 }
 
 impl Keyboard {
@@ -49,6 +52,10 @@ impl Keyboard {
 
         let layout = context.keyboard_layouts[&context.settings.keyboard_layout].clone();
 
+        if !number && context.settings.handwriting.pad {
+            return Keyboard::handwriting(rect, layout, context);
+        }
+
         let mut state = State::default();
 
         if number {
@@ -117,6 +124,27 @@ impl Keyboard {
             layout,
             state,
             combine_buffer: String::new(),
+            handwriting: false,
+        }
+    }
+
+    // The pad takes the whole keyboard but the bottom row, which holds the delete, space and return keys.
+    fn handwriting(rect: &Rectangle, layout: Layout, context: &mut Context) -> Keyboard {
+        context.kb_rect = *rect;
+        let (pad_rect, key_rects) = handwriting_rects(rect);
+        let mut children = vec![Box::new(HandwritingPad::new(pad_rect, context)) as Box<dyn View>];
+        for (key_rect, kind) in key_rects.into_iter().zip(HANDWRITING_KEYS.iter()) {
+            children.push(Box::new(Key::new(key_rect, *kind)) as Box<dyn View>);
+        }
+
+        Keyboard {
+            id: ID_FEEDER.next(),
+            rect: *rect,
+            children,
+            layout,
+            state: State::default(),
+            combine_buffer: String::new(),
+            handwriting: true,
         }
     }
 
@@ -197,6 +225,33 @@ impl Keyboard {
 
 }
 
+const HANDWRITING_KEYS: [KeyKind; 3] = [KeyKind::Delete(LinearDir::Backward), KeyKind::Output(' '), KeyKind::Return];
+const HANDWRITING_WIDTHS: [f32; 3] = [1.5, 6.0, 1.5];
+
+fn handwriting_rects(rect: &Rectangle) -> (Rectangle, Vec<Rectangle>) {
+    let key_height = rect.height() as f32 / 4.0;
+    let padding = PADDING_RATIO * key_height;
+    let unit = (rect.width() as f32 - padding * (HANDWRITING_WIDTHS.len() + 1) as f32) / HANDWRITING_WIDTHS.iter().sum::<f32>();
+    let y = rect.max.y as f32 - padding - key_height;
+    let mut x = rect.min.x as f32 + padding;
+    let mut key_rects = Vec::new();
+
+    for width in HANDWRITING_WIDTHS.iter() {
+        let key_width = width * unit;
+        key_rects.push(rect![x.round() as i32,
+                             y.round() as i32,
+                             (x + key_width).round() as i32,
+                             (y + key_height).round() as i32]);
+        x += key_width + padding;
+    }
+
+    let pad_rect = rect![(rect.min.x as f32 + padding).round() as i32,
+                         (rect.min.y as f32 + padding).round() as i32,
+                         (rect.max.x as f32 - padding).round() as i32,
+                         (y - padding).round() as i32];
+    (pad_rect, key_rects)
+}
+
 impl View for Keyboard {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
@@ -241,6 +296,12 @@ impl View for Keyboard {
                 };
                 true
             },
+            Event::Select(EntryId::ToggleHandwritingPad) => {
+                context.settings.handwriting.pad = !context.settings.handwriting.pad;
+                *self = Keyboard::new(&mut self.rect, self.state.alternate == 2, context);
+                rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                true
+            },
             Event::Select(EntryId::SetKeyboardLayout(ref name)) => {
                 if *name != context.settings.keyboard_layout {
                     context.settings.keyboard_layout = name.to_string();
@@ -264,6 +325,7 @@ impl View for Keyboard {
                   Event::Key(..) |
                   Event::Gesture(..) |
                   Event::Device(DeviceEvent::Finger { .. }) |
+                  Event::RecognizeStrokes(..) |
                   Event::Select(..))
     }
 
@@ -285,6 +347,17 @@ impl View for Keyboard {
     }
 
     fn resize(&mut self, mut rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.handwriting {
+            let (pad_rect, key_rects) = handwriting_rects(&rect);
+            self.children[0].resize(pad_rect, hub, rq, context);
+            for (index, key_rect) in key_rects.into_iter().enumerate() {
+                self.children[index + 1].resize(key_rect, hub, rq, context);
+            }
+            self.rect = rect;
+            context.kb_rect = rect;
+            return;
+        }
+
         let dpi = CURRENT_DEVICE.dpi;
         let max_width = self.layout.widths.iter().map(|row| (row.len() + 1) as f32 * PADDING_RATIO + row.iter().sum::<f32>())
                             .max_by(|a, b| a.partial_cmp(b).expect("Found NaNs"))
@@ -0,0 +1,145 @@
+use std::thread;
+use std::time::Duration;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, BorderSpec, CornerSpec, Dir};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::color::{BLACK, WHITE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, SliderId};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM, BORDER_RADIUS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The gauge closes when the levels haven't changed for this long.
+const GAUGE_CLOSE_DELAY: Duration = Duration::from_secs(2);
+
+// Shows the level of the frontlight being adjusted, along the edge it's adjusted from.
+pub struct LightGauge {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    slider_id: SliderId,
+    value: f32,
+    side: Dir,
+    // Each change of the value postpones the closing.
+    token: Id,
+}
+
+impl LightGauge {
+    pub fn new(slider_id: SliderId, value: f32, side: Dir, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> LightGauge {
+        let id = ID_FEEDER.next();
+        let rect = gauge_rect(side, context);
+        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+        let mut gauge = LightGauge {
+            id,
+            rect,
+            children: Vec::new(),
+            slider_id,
+            value,
+            side,
+            token: id,
+        };
+        gauge.schedule_close(hub);
+        gauge
+    }
+
+    pub fn slider_id(&self) -> SliderId {
+        self.slider_id
+    }
+
+    pub fn update(&mut self, value: f32, hub: &Hub, rq: &mut RenderQueue) {
+        self.value = value;
+        self.schedule_close(hub);
+        rq.add(RenderData::no_wait(self.id, self.rect, UpdateMode::Fast));
+    }
+
+    fn schedule_close(&mut self, hub: &Hub) {
+        self.token = ID_FEEDER.next();
+        let view_id = ViewId::LightGauge(self.token);
+        let hub2 = hub.clone();
+        thread::spawn(move || {
+            thread::sleep(GAUGE_CLOSE_DELAY);
+            hub2.send(Event::Close(view_id)).ok();
+        });
+    }
+}
+
+fn gauge_rect(side: Dir, context: &Context) -> Rectangle {
+    let dpi = CURRENT_DEVICE.dpi;
+    let (width, height) = context.display.dims;
+    let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+    let gauge_width = small_height;
+    let gauge_height = height as i32 / 3;
+    let dx = if side == Dir::East {
+        width as i32 - small_height - gauge_width
+    } else {
+        small_height
+    };
+    let dy = (height as i32 - gauge_height) / 2;
+    rect![dx, dy, dx + gauge_width, dy + gauge_height]
+}
+
+impl View for LightGauge {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_SMALL, dpi) as i32;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness as u16,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32 / 2;
+        let text = format!("{}", self.value.round() as i32);
+        let plan = font.plan(&text, Some(self.rect.width() as i32 - 2 * padding), None);
+        let x_height = font.x_heights.0 as i32;
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        let label_height = 3 * x_height;
+        font.render(fb, BLACK, &plan, pt!(self.rect.min.x + dx, self.rect.min.y + 2 * x_height + padding / 2));
+
+        // The bar fills from the bottom.
+        let bar_rect = rect![self.rect.min.x + padding, self.rect.min.y + label_height + padding,
+                             self.rect.max.x - padding, self.rect.max.y - padding];
+        fb.draw_rectangle_outline(&bar_rect, &BorderSpec { thickness: border_thickness as u16, color: BLACK });
+        let fill_height = (self.value.clamp(0.0, 100.0) / 100.0 * bar_rect.height() as f32).round() as i32;
+        if fill_height > 0 {
+            fb.draw_rectangle(&rect![bar_rect.min.x, bar_rect.max.y - fill_height,
+                                     bar_rect.max.x, bar_rect.max.y], BLACK);
+        }
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.rect = gauge_rect(self.side, context);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::LightGauge(self.token))
+    }
+}
@@ -0,0 +1,333 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec, CycleDir, Dir};
+use crate::font::Fonts;
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, EntryId, Align};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM, THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::input_field::InputField;
+use super::label::Label;
+use super::icon::Icon;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE, SEPARATOR_NORMAL};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The first children are the filter field and the page controls.
+// The rows and the index letters of the current page follow.
+const PAGE_LABEL: usize = 2;
+const FIXED_CHILDREN_COUNT: usize = 4;
+
+// A paged list of choices, with a filter field.
+// Only the rows of the current page are built, which keeps large lists cheap.
+// The owner receives `Event::Select` followed by `Event::Close` through the bus.
+pub struct ListPicker {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    items: Vec<(String, EntryId)>,
+    current: Option<usize>,
+    filtered: Vec<usize>,
+    page: usize,
+}
+
+impl ListPicker {
+    pub fn new(rect: Rectangle, view_id: ViewId, items: Vec<(String, EntryId)>, current: Option<usize>, rq: &mut RenderQueue) -> ListPicker {
+        let dpi = CURRENT_DEVICE.dpi;
+        let row_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let inner_rect = inner_rect(&rect);
+
+        let mut children = Vec::new();
+        let filter_rect = rect![inner_rect.min.x, inner_rect.min.y,
+                                inner_rect.max.x, inner_rect.min.y + row_height];
+        let input_field = InputField::new(filter_rect, ViewId::ListPickerInput)
+                                     .border(false)
+                                     .placeholder("Filter");
+        children.push(Box::new(input_field) as Box<dyn View>);
+
+        let y_min = inner_rect.max.y - row_height;
+        let prev_rect = rect![inner_rect.min.x, y_min, inner_rect.min.x + row_height, inner_rect.max.y];
+        let next_rect = rect![inner_rect.max.x - row_height, y_min, inner_rect.max.x, inner_rect.max.y];
+        let label_rect = rect![prev_rect.max.x, y_min, next_rect.min.x, inner_rect.max.y];
+        children.push(Box::new(Icon::new("angle-left", prev_rect, Event::Page(CycleDir::Previous))) as Box<dyn View>);
+        children.push(Box::new(Label::new(label_rect, String::new(), Align::Center)) as Box<dyn View>);
+        children.push(Box::new(Icon::new("angle-right", next_rect, Event::Page(CycleDir::Next))) as Box<dyn View>);
+
+        let filtered = (0..items.len()).collect();
+        let rows_count = rows_count(&rect);
+        let page = current.map_or(0, |index| index / rows_count.max(1));
+
+        let mut list_picker = ListPicker {
+            id: ID_FEEDER.next(),
+            rect,
+            children,
+            view_id,
+            items,
+            current,
+            filtered,
+            page,
+        };
+
+        list_picker.build_page(&mut RenderQueue::new());
+        rq.add(RenderData::new(list_picker.id, rect, UpdateMode::Gui));
+        list_picker
+    }
+
+    fn pages_count(&self) -> usize {
+        self.filtered.len().div_ceil(rows_count(&self.rect).max(1)).max(1)
+    }
+
+    fn list_rect(&self) -> Rectangle {
+        let row_height = scale_by_dpi(SMALL_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32;
+        let inner_rect = inner_rect(&self.rect);
+        rect![inner_rect.min.x, inner_rect.min.y + row_height,
+              inner_rect.max.x, inner_rect.max.y - row_height]
+    }
+
+    fn build_page(&mut self, rq: &mut RenderQueue) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let row_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let list_rect = self.list_rect();
+        let rows_count = rows_count(&self.rect);
+        let letters = index_letters(&self.items, &self.filtered);
+        let index_width = if letters.len() > 1 { row_height / 2 } else { 0 };
+
+        self.children.truncate(FIXED_CHILDREN_COUNT);
+
+        let start = self.page * rows_count;
+        for (i, &index) in self.filtered.iter().skip(start).take(rows_count).enumerate() {
+            let (label, entry_id) = &self.items[index];
+            let text = if self.current == Some(index) {
+                format!("• {}", label)
+            } else {
+                label.clone()
+            };
+            let y_min = list_rect.min.y + i as i32 * row_height;
+            let row_rect = rect![list_rect.min.x, y_min, list_rect.max.x - index_width, y_min + row_height];
+            let row = Label::new(row_rect, text, Align::Left(row_height / 4))
+                            .event(Some(Event::Select(entry_id.clone())));
+            self.children.push(Box::new(row) as Box<dyn View>);
+        }
+
+        if index_width > 0 {
+            // Drop letters until they fit in the available height.
+            let slots = (list_rect.height() as i32 / (row_height / 3).max(1)).max(1) as usize;
+            let stride = letters.len().div_ceil(slots);
+            let letters: Vec<(char, usize)> = letters.into_iter().step_by(stride).collect();
+            let letter_height = list_rect.height() as i32 / letters.len() as i32;
+            for (i, (letter, position)) in letters.into_iter().enumerate() {
+                let y_min = list_rect.min.y + i as i32 * letter_height;
+                let letter_rect = rect![list_rect.max.x - index_width, y_min,
+                                        list_rect.max.x, y_min + letter_height];
+                let label = Label::new(letter_rect, letter.to_string(), Align::Center)
+                                  .event(Some(Event::GoTo(position)));
+                self.children.push(Box::new(label) as Box<dyn View>);
+            }
+        }
+
+        let text = format!("{} / {}", self.page + 1, self.pages_count());
+        if let Some(label) = self.children[PAGE_LABEL].downcast_mut::<Label>() {
+            label.update(&text, &mut RenderQueue::new());
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn set_page(&mut self, page: usize, rq: &mut RenderQueue) {
+        let page = page.min(self.pages_count() - 1);
+        if page != self.page {
+            self.page = page;
+            self.build_page(rq);
+        }
+    }
+
+    fn go_to_page(&mut self, dir: CycleDir, rq: &mut RenderQueue) {
+        match dir {
+            CycleDir::Next => self.set_page(self.page + 1, rq),
+            CycleDir::Previous if self.page > 0 => self.set_page(self.page - 1, rq),
+            _ => (),
+        }
+    }
+
+    pub fn set_filter(&mut self, query: &str, rq: &mut RenderQueue) {
+        self.filtered = filter_items(&self.items, query);
+        self.page = 0;
+        self.build_page(rq);
+    }
+}
+
+fn inner_rect(rect: &Rectangle) -> Rectangle {
+    let dpi = CURRENT_DEVICE.dpi;
+    let thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as i32;
+    let radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+    let margin = thickness + radius / 2;
+    rect![rect.min.x + margin, rect.min.y + margin,
+          rect.max.x - margin, rect.max.y - margin]
+}
+
+fn rows_count(rect: &Rectangle) -> usize {
+    let row_height = scale_by_dpi(SMALL_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32;
+    ((inner_rect(rect).height() as i32 - 2 * row_height) / row_height).max(1) as usize
+}
+
+// Returns the indices of the items whose label contains `query`, ignoring the case.
+fn filter_items(items: &[(String, EntryId)], query: &str) -> Vec<usize> {
+    let query = query.to_lowercase();
+    items.iter().enumerate()
+         .filter(|(_, (label, _))| query.is_empty() || label.to_lowercase().contains(&query))
+         .map(|(index, _)| index)
+         .collect()
+}
+
+// Returns the distinct initials of the filtered items, with the position of
+// the first filtered item that starts with each one of them.
+fn index_letters(items: &[(String, EntryId)], filtered: &[usize]) -> Vec<(char, usize)> {
+    let mut letters: Vec<(char, usize)> = Vec::new();
+    for (position, &index) in filtered.iter().enumerate() {
+        let initial = items[index].0.chars().next()
+                                    .map(|c| c.to_uppercase().next().unwrap_or(c));
+        if let Some(initial) = initial {
+            if letters.iter().all(|(letter, _)| *letter != initial) {
+                letters.push((initial, position));
+            }
+        }
+    }
+    letters
+}
+
+impl View for ListPicker {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(ref entry_id) if self.items.iter().any(|(_, id)| id == entry_id) => {
+                bus.push_back(evt.clone());
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Submit(ViewId::ListPickerInput, ref text) => {
+                self.set_filter(text, rq);
+                true
+            },
+            Event::Page(dir) => {
+                self.go_to_page(dir, rq);
+                true
+            },
+            Event::GoTo(position) => {
+                self.set_page(position / rows_count(&self.rect).max(1), rq);
+                true
+            },
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.rect.includes(start) => {
+                match dir {
+                    Dir::North | Dir::West => self.go_to_page(CycleDir::Next, rq),
+                    Dir::South | Dir::East => self.go_to_page(CycleDir::Previous, rq),
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if context.kb_rect.includes(center) => false,
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { position, .. }) => !context.kb_rect.includes(position),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let list_rect = self.list_rect();
+        fb.draw_rectangle(&rect![list_rect.min.x, list_rect.min.y - thickness,
+                                 list_rect.max.x, list_rect.min.y],
+                          SEPARATOR_NORMAL);
+        fb.draw_rectangle(&rect![list_rect.min.x, list_rect.max.y,
+                                 list_rect.max.x, list_rect.max.y + thickness],
+                          SEPARATOR_NORMAL);
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, rq: &mut RenderQueue, _context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let row_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let inner_rect = inner_rect(&rect);
+        let y_min = inner_rect.max.y - row_height;
+        let rects = [rect![inner_rect.min.x, inner_rect.min.y,
+                           inner_rect.max.x, inner_rect.min.y + row_height],
+                     rect![inner_rect.min.x, y_min, inner_rect.min.x + row_height, inner_rect.max.y],
+                     rect![inner_rect.min.x + row_height, y_min, inner_rect.max.x - row_height, inner_rect.max.y],
+                     rect![inner_rect.max.x - row_height, y_min, inner_rect.max.x, inner_rect.max.y]];
+        for (child, rect) in self.children.iter_mut().zip(rects) {
+            *child.rect_mut() = rect;
+        }
+        self.rect = rect;
+        self.page = self.page.min(self.pages_count() - 1);
+        self.build_page(rq);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(labels: &[&str]) -> Vec<(String, EntryId)> {
+        labels.iter().map(|label| (label.to_string(), EntryId::SetFontFamily(label.to_string()))).collect()
+    }
+
+    #[test]
+    fn test_filter_items() {
+        let items = items(&["Alegreya", "Bitter", "Libre Baskerville", "literata"]);
+        assert_eq!(filter_items(&items, ""), vec![0, 1, 2, 3]);
+        assert_eq!(filter_items(&items, "LI"), vec![2, 3]);
+        assert_eq!(filter_items(&items, "ter"), vec![1, 3]);
+        assert!(filter_items(&items, "xyz").is_empty());
+    }
+
+    #[test]
+    fn test_index_letters() {
+        let items = items(&["Alegreya", "Amiri", "Bitter", "bodoni", "Crimson"]);
+        let filtered = vec![0, 1, 2, 3, 4];
+        assert_eq!(index_letters(&items, &filtered), vec![('A', 0), ('B', 2), ('C', 4)]);
+        assert_eq!(index_letters(&items, &[1, 3]), vec![('A', 0), ('B', 1)]);
+    }
+}
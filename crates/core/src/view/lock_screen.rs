@@ -0,0 +1,155 @@
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, LinearDir};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
+use super::{KeyboardEvent, TextKind};
+use super::button::Button;
+use super::label::Label;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::input::DeviceEvent;
+use crate::color::WHITE;
+use crate::context::Context;
+
+const LABEL_PROMPT: &str = "Enter the PIN to unlock.";
+const LABEL_WRONG: &str = "Wrong PIN.";
+const PIN_MASK: char = '•';
+
+// Covers the whole screen and swallows the input until the PIN is entered.
+pub struct LockScreen {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    pin: String,
+}
+
+impl LockScreen {
+    pub fn new(rect: Rectangle, context: &mut Context) -> LockScreen {
+        LockScreen {
+            id: ID_FEEDER.next(),
+            rect,
+            children: build_children(rect, context),
+            pin: String::new(),
+        }
+    }
+
+    fn update_label(&mut self, index: usize, text: &str, rq: &mut RenderQueue) {
+        if let Some(label) = self.children[index].downcast_mut::<Label>() {
+            label.update(text, rq);
+        }
+    }
+
+    fn update_mask(&mut self, rq: &mut RenderQueue) {
+        let mask: String = self.pin.chars().map(|_| PIN_MASK).collect();
+        self.update_label(1, &mask, rq);
+    }
+}
+
+// The prompt, the masked PIN and a keypad with three columns and four rows.
+fn build_children(rect: Rectangle, context: &mut Context) -> Vec<Box<dyn View>> {
+    let dpi = CURRENT_DEVICE.dpi;
+    let (padding, label_height) = {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        (font.em() as i32, 4 * font.x_heights.0 as i32)
+    };
+    let mut children = Vec::new();
+
+    let side = (rect.width() as i32 / 6).min((rect.height() as i32 - 2 * label_height) / 8);
+    let pad_width = 3 * side + 2 * padding;
+    let pad_height = 4 * side + 3 * padding;
+    let top = rect.min.y + (rect.height() as i32 - pad_height - 2 * label_height - padding) / 2;
+    let left = rect.min.x + (rect.width() as i32 - pad_width) / 2;
+
+    let prompt_rect = rect![rect.min.x + padding, top,
+                            rect.max.x - padding, top + label_height];
+    children.push(Box::new(Label::new(prompt_rect, LABEL_PROMPT.to_string(), Align::Center)) as Box<dyn View>);
+    let mask_rect = rect![rect.min.x + padding, prompt_rect.max.y,
+                          rect.max.x - padding, prompt_rect.max.y + label_height];
+    children.push(Box::new(Label::new(mask_rect, String::new(), Align::Center)) as Box<dyn View>);
+
+    let mut keys: Vec<(Event, String)> = ('1'..='9').map(|c| (Event::Keyboard(KeyboardEvent::Append(c)), c.to_string()))
+                                                    .collect();
+    keys.push((Event::Keyboard(KeyboardEvent::Delete { target: TextKind::Char, dir: LinearDir::Backward }),
+               "Delete".to_string()));
+    keys.push((Event::Keyboard(KeyboardEvent::Append('0')), "0".to_string()));
+    keys.push((Event::Keyboard(KeyboardEvent::Submit), "OK".to_string()));
+
+    let pad_top = mask_rect.max.y + padding;
+    for (index, (event, text)) in keys.into_iter().enumerate() {
+        let (row, column) = (index as i32 / 3, index as i32 % 3);
+        let x = left + column * (side + padding);
+        let y = pad_top + row * (side + padding);
+        children.push(Box::new(Button::new(rect![x, y, x + side, y + side], event, text)) as Box<dyn View>);
+    }
+
+    children
+}
+
+impl View for LockScreen {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Keyboard(KeyboardEvent::Append(c)) if c.is_ascii_digit() => {
+                self.pin.push(c);
+                self.update_mask(rq);
+                true
+            },
+            Event::Keyboard(KeyboardEvent::Delete { .. }) => {
+                self.pin.pop();
+                self.update_mask(rq);
+                true
+            },
+            Event::Keyboard(KeyboardEvent::Submit) => {
+                if self.pin == context.settings.lock.pin {
+                    bus.push_back(Event::Close(ViewId::LockScreen));
+                } else {
+                    self.pin.clear();
+                    self.update_mask(rq);
+                    self.update_label(0, LABEL_WRONG, rq);
+                }
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) |
+            Event::Device(DeviceEvent::Button { .. }) |
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        fb.draw_rectangle(&self.rect, WHITE);
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        self.children = build_children(rect, context);
+        self.rect = rect;
+        self.update_mask(rq);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::LockScreen)
+    }
+}
@@ -375,6 +375,10 @@ impl View for Menu {
         self.id
     }
 
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
     fn view_id(&self) -> Option<ViewId> {
         Some(self.view_id)
     }
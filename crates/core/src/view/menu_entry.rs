@@ -191,4 +191,8 @@ impl View for MenuEntry {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, BorderSpec, CornerSpec};
+use crate::font::{Fonts, font_from_style, MD_SIZE};
+use crate::color::{BLACK, WHITE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The shortest time between two refreshes of the overlay.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Shows the render and event latencies, and the refresh counts, in the bottom right corner of the screen.
+// It doesn't handle any event. Its own refreshes are counted.
+pub struct MetricsOverlay {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    lines: Vec<String>,
+    updated: Instant,
+}
+
+impl MetricsOverlay {
+    pub fn new(rq: &mut RenderQueue, context: &mut Context) -> MetricsOverlay {
+        let id = ID_FEEDER.next();
+        let now = Instant::now();
+        let lines = context.metrics.summary(now);
+        let rect = overlay_rect(&lines, context);
+        rq.add(RenderData::no_wait(id, rect, UpdateMode::Fast));
+        MetricsOverlay {
+            id,
+            rect,
+            children: Vec::new(),
+            lines,
+            updated: now,
+        }
+    }
+
+    pub fn update(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let now = Instant::now();
+        if now.duration_since(self.updated) < REFRESH_INTERVAL {
+            return;
+        }
+        let lines = context.metrics.summary(now);
+        if lines == self.lines {
+            return;
+        }
+        let rect = overlay_rect(&lines, context);
+        if rect != self.rect {
+            rq.add(RenderData::expose(self.rect, UpdateMode::Fast));
+        }
+        self.rect = rect;
+        self.lines = lines;
+        self.updated = now;
+        rq.add(RenderData::no_wait(self.id, self.rect, UpdateMode::Fast));
+    }
+}
+
+fn overlay_rect(lines: &[String], context: &mut Context) -> Rectangle {
+    let dpi = CURRENT_DEVICE.dpi;
+    let (width, height) = context.display.dims;
+    let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+    let font = font_from_style(&mut context.fonts, &MD_SIZE, dpi);
+    let padding = font.em() as i32 / 2;
+    let text_width = lines.iter().map(|line| font.plan(line, None, None).width).max().unwrap_or(0);
+    let overlay_width = (text_width + 2 * padding).min(width as i32);
+    let overlay_height = lines.len() as i32 * font.line_height() + 2 * padding;
+    let max_x = width as i32 - padding;
+    let max_y = height as i32 - small_height - padding;
+    rect![max_x - overlay_width, max_y - overlay_height, max_x, max_y]
+}
+
+impl View for MetricsOverlay {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(0),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let font = font_from_style(fonts, &MD_SIZE, dpi);
+        let padding = font.em() as i32 / 2;
+        let line_height = font.line_height();
+        let x_height = font.x_heights.0 as i32;
+        let mut y = self.rect.min.y + padding;
+        for line in &self.lines {
+            let plan = font.plan(line, None, None);
+            let dy = (line_height - x_height) / 2;
+            font.render(fb, BLACK, &plan, pt!(self.rect.min.x + padding, y + line_height - dy));
+            y += line_height;
+        }
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.rect = overlay_rect(&self.lines, context);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::MetricsOverlay)
+    }
+}
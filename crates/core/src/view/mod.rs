@@ -24,45 +24,88 @@ pub mod labeled_icon;
 pub mod top_bar;
 pub mod search_bar;
 pub mod dialog;
+pub mod modal;
 pub mod notification;
+pub mod notification_badge;
+pub mod timer_chip;
+pub mod metrics_overlay;
+pub mod light_gauge;
+pub mod progress_bar;
 pub mod intermission;
+pub mod lock_screen;
 pub mod frontlight;
 pub mod presets_list;
 pub mod preset;
 pub mod menu;
 pub mod menu_entry;
+pub mod list_picker;
+pub mod date_picker;
+pub mod color_picker;
+pub mod qr_code;
+pub mod statistics;
+pub mod jobs;
+pub mod end_of_book;
+pub mod translation;
 pub mod clock;
+pub mod weather_label;
 pub mod battery;
 pub mod keyboard;
 pub mod key;
+pub mod handwriting_pad;
 pub mod home;
 pub mod reader;
 pub mod dictionary;
 pub mod calculator;
+pub mod flashcards;
+pub mod battery_graph;
+pub mod storage;
+pub mod agenda;
+pub mod notes;
+pub mod sudoku;
+pub mod apps;
+pub mod downloads;
+pub mod portal;
 pub mod sketch;
 pub mod touch_events;
 pub mod rotation_values;
+pub mod pen_calibration;
 pub mod packed_view;
+pub mod hub;
+pub mod focus;
 
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::time::{Instant, Duration};
 use std::path::PathBuf;
-use std::sync::mpsc::Sender;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::VecDeque;
 use std::fmt::{self, Debug};
 use fxhash::FxHashMap;
+use chrono::NaiveDateTime;
 use downcast_rs::{Downcast, impl_downcast};
 use crate::font::Fonts;
-use crate::document::{Location, TextLocation};
-use crate::settings::{ButtonScheme, FirstColumn, SecondColumn, RotationLock};
-use crate::metadata::{Info, ZoomMode, ScrollMode, SortMethod, TextAlign, SimpleStatus, PageScheme, Margin};
+use crate::document::{Location, TextLocation, BoundedText};
+use crate::watcher::ConfigFile;
+use crate::remote::RemoteCommand;
+use crate::settings::{ButtonScheme, FirstColumn, SecondColumn, RotationLock, ImportSettings, RefreshRateSettings};
+use crate::library::{ImportEntry, BookLength};
+use crate::integrity::IntegrityReport;
+use crate::downloads::Download;
+use crate::portal::PortalPage;
+use crate::weather::Forecast;
+use crate::sudoku::Difficulty;
+use crate::session::Session;
+use crate::jobs::JobId;
+use crate::vocabulary::Grade;
+use crate::tone::ToneCurve;
+use crate::metadata::{Info, ZoomMode, ScrollMode, SortMethod, TextAlign, SimpleStatus, PageScheme, Margin, RefreshPolicy, DocumentStatistics, Cover};
 use crate::geom::{LinearDir, CycleDir, Rectangle, Boundary};
 use crate::framebuffer::{Framebuffer, UpdateMode};
 use crate::input::{DeviceEvent, FingerStatus};
 use crate::gesture::GestureEvent;
 use self::calculator::LineOrigin;
 use self::key::KeyKind;
+pub use self::hub::{Hub, HubReceiver, Priority};
 use crate::context::Context;
 
 // Border thicknesses in pixels, at 300 DPI.
@@ -83,7 +126,6 @@ pub const BIG_BAR_HEIGHT: f32 = 163.0;
 pub const CLOSE_IGNITION_DELAY: Duration = Duration::from_millis(150);
 
 pub type Bus = VecDeque<Event>;
-pub type Hub = Sender<Event>;
 
 pub trait View: Downcast {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool;
@@ -122,6 +164,11 @@ pub trait View: Downcast {
         true
     }
 
+    // Whether the view follows the orientation of the device.
+    fn auto_rotates(&self) -> bool {
+        self.might_rotate()
+    }
+
     fn is_background(&self) -> bool {
         false
     }
@@ -129,6 +176,20 @@ pub trait View: Downcast {
     fn view_id(&self) -> Option<ViewId> {
         None
     }
+
+    // Whether the view can receive the focus during key navigation.
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    // Whether the key navigation is restricted to the descendants of the view.
+    fn is_focus_scope(&self) -> bool {
+        false
+    }
+
+    // Records what's needed to restore the view at startup.
+    fn save_state(&self, _session: &mut Session) {
+    }
 }
 
 impl_downcast!(View);
@@ -179,8 +240,9 @@ pub fn handle_event(view: &mut dyn View, evt: &Event, hub: &Hub, parent_bus: &mu
 // We render from bottom to top. For a view to render it has to either appear in `ids` or intersect
 // one of the rectangles in `bgs`. When we're about to render a view, if `wait` is true, we'll wait
 // for all the updates in `updating` that intersect with the view.
+// The focused view is outlined once it and its children are rendered.
 pub fn render(view: &dyn View, wait: bool, ids: &FxHashMap<Id, Vec<Rectangle>>, rects: &mut Vec<Rectangle>,
-              bgs: &mut Vec<Rectangle>, fb: &mut dyn Framebuffer, fonts: &mut Fonts, updating: &mut Vec<UpdateData>) {
+              bgs: &mut Vec<Rectangle>, context: &mut Context, updating: &mut Vec<UpdateData>) {
     let mut render_rects = Vec::new();
 
     if view.len() == 0 || view.is_background() {
@@ -193,7 +255,7 @@ pub fn render(view: &dyn View, wait: bool, ids: &FxHashMap<Id, Vec<Rectangle>>,
                 updating.retain(|update| {
                     let overlaps = render_rect.overlaps(&update.rect);
                     if overlaps && !update.has_completed() {
-                        fb.wait(update.token)
+                        context.fb.wait(update.token)
                           .map_err(|e| eprintln!("Can't wait for {}, {}: {:#}",
                                                  update.token, update.rect, e))
                           .ok();
@@ -202,7 +264,7 @@ pub fn render(view: &dyn View, wait: bool, ids: &FxHashMap<Id, Vec<Rectangle>>,
                 });
             }
 
-            view.render(fb, rect, fonts);
+            view.render(context.fb.as_mut(), rect, &mut context.fonts);
             render_rects.push(render_rect);
 
             // Most views can't render a subrectangle of themselves.
@@ -245,12 +307,20 @@ pub fn render(view: &dyn View, wait: bool, ids: &FxHashMap<Id, Vec<Rectangle>>,
     }
 
     for i in 0..view.len() {
-        render(view.child(i), wait, ids, rects, bgs, fb, fonts, updating);
+        render(view.child(i), wait, ids, rects, bgs, context, updating);
+    }
+
+    if context.focus.id == Some(view.id()) && rects.iter().any(|r| r.overlaps(view.rect())) {
+        focus::render_focus(context.fb.as_mut(), view.rect());
     }
 }
 
 #[inline]
 pub fn process_render_queue(view: &dyn View, rq: &mut RenderQueue, context: &mut Context, updating: &mut Vec<UpdateData>) {
+    let start = Instant::now();
+
+    context.refresh.schedule(rq, &context.settings.reader.refresh_rate, context.fb.inverted());
+
     for ((mode, wait), pairs) in rq.drain() {
         let mut ids = FxHashMap::default();
         let mut rects = Vec::new();
@@ -264,16 +334,22 @@ pub fn process_render_queue(view: &dyn View, rq: &mut RenderQueue, context: &mut
             }
         }
 
-        render(view, wait, &ids, &mut rects, &mut bgs,
-               context.fb.as_mut(), &mut context.fonts, updating);
+        render(view, wait, &ids, &mut rects, &mut bgs, context, updating);
 
         for rect in rects {
             match context.fb.update(&rect, mode) {
-                Ok(token) => { updating.push(UpdateData { token, rect, time: Instant::now()}); },
+                Ok(Some(token)) => {
+                    updating.push(UpdateData { token, rect, time: Instant::now()});
+                    context.metrics.record_refresh(mode);
+                },
+                // The region didn't change: there's nothing to wait for.
+                Ok(None) => (),
                 Err(err) => { eprintln!("Can't update {}: {:#}.", rect, err); },
             }
         }
     }
+
+    context.metrics.record_frame(start, start.elapsed());
 }
 
 #[inline]
@@ -298,12 +374,18 @@ pub enum Event {
     Open(Box<Info>),
     OpenHtml(String, Option<String>),
     LoadPixmap(usize),
+    // The given page of a streamed document was downloaded.
+    PageStreamed(usize),
     Update(UpdateMode),
     RefreshBookPreview(PathBuf, Option<PathBuf>),
     Invalid(PathBuf),
+    Protected(PathBuf),
     Notify(String),
+    NotificationsChanged,
+    SetDate(ViewId, NaiveDateTime),
     Page(CycleDir),
     ResultsPage(CycleDir),
+    ThumbnailsPage(CycleDir),
     GoTo(usize),
     GoToLocation(Location),
     ResultsGoTo(usize),
@@ -323,6 +405,8 @@ pub enum Event {
     ToggleInputHistoryMenu(ViewId, Rectangle),
     ToggleBookMenu(Rectangle, usize),
     TogglePresetMenu(Rectangle, usize),
+    ToggleDownloadMenu(Rectangle, u64),
+    ToggleNoteMenu(Rectangle, usize),
     SubMenu(Rectangle, Vec<EntryKind>),
     ProcessLine(LineOrigin, String),
     History(CycleDir, bool),
@@ -332,8 +416,22 @@ pub enum Event {
     CloseSub(ViewId),
     Search(String),
     SearchResult(usize, Vec<Boundary>),
+    // The location targeted by the page slider.
+    Skim(usize, FingerStatus),
+    // The result of an online translation.
+    Translation {
+        text: String,
+        translation: String,
+    },
     FetcherAddDocument(u32, Box<Info>),
     FetcherRemoveDocument(u32, PathBuf),
+    // Fills the missing metadata of a document, and optionally replaces its cover.
+    FetcherUpdateDocument {
+        id: u32,
+        path: PathBuf,
+        info: Box<Info>,
+        cover: Option<PathBuf>,
+    },
     FetcherSearch {
         id: u32,
         path: Option<PathBuf>,
@@ -341,6 +439,48 @@ pub enum Event {
         sort_by: Option<(SortMethod, bool)>,
     },
     CheckFetcher(u32),
+    QueueDownload(Box<Download>),
+    // The number of bytes downloaded, and the total, when known.
+    DownloadProgress(u64, u64, Option<u64>),
+    // The downloader exited, with its last error message.
+    DownloadFinished(u64, Option<String>),
+    // The delay before attempting a failed download again is over.
+    RetryDownloads,
+    // The network redirects the requests to a sign-in page.
+    PortalDetected,
+    PortalPage(Box<PortalPage>),
+    PortalOnline,
+    // The clock was shifted by the given number of milliseconds.
+    ClockSynced {
+        offset: i64,
+        notify: bool,
+    },
+    WeatherUpdated(Box<Forecast>),
+    // The calendars were downloaded, or the error that occurred.
+    CalendarsFetched(Option<String>),
+    // A phase of the focus timer might be over.
+    TimerTick,
+    // A focus session of the timer started or ended.
+    FocusStarted,
+    FocusEnded,
+    // The given word of the utterance is being spoken.
+    SpeechWord(usize, usize),
+    // The utterance was spoken.
+    SpeechDone(usize),
+    StatisticsComputed(PathBuf, DocumentStatistics),
+    JobsChanged,
+    // The text of the given page was recognized, or not.
+    RecognizedText(usize, Option<Vec<BoundedText>>),
+    Import(Box<ImportSettings>),
+    // The device stayed in the given orientation long enough.
+    AutoRotate(i8),
+    // A sketch of the given page was saved under the given name.
+    PageSketched(usize, PathBuf),
+    // The advancement of the task whose progress is shown by the given view.
+    TaskProgress(ViewId, usize, usize),
+    ImportFinished(Vec<ImportEntry>),
+    CheckIntegrity,
+    IntegrityChecked(Box<IntegrityReport>),
     EndOfSearch,
     Finished,
     ClockTick,
@@ -352,6 +492,7 @@ pub enum Event {
     Save,
     Guess,
     CheckBattery,
+    TakeSnapshot,
     SetWifi(bool),
     MightSuspend,
     PrepareSuspend,
@@ -361,6 +502,13 @@ pub enum Event {
     Validate,
     Cancel,
     Reseed,
+    ConfigChanged(ConfigFile),
+    RenderFrame,
+    // Sent by a sketch to itself, with the number of strokes drawn so far.
+    RefreshGhosting(Id, usize),
+    // Sent by a handwriting pad to itself once the fingers have been lifted.
+    RecognizeStrokes(Id, Id),
+    Remote(RemoteCommand),
     Back,
     Quit,
     WakeUp,
@@ -374,8 +522,40 @@ pub enum AppCmd {
         query: String,
         language: String,
     },
+    Flashcards,
+    BatteryGraph,
+    Storage,
+    Downloads,
+    Portal,
+    Agenda,
+    // Opens the given note, if any.
+    Notes {
+        note: Option<String>,
+    },
+    // Draws the given handwritten note, or a new one.
+    HandwrittenNote {
+        name: Option<String>,
+    },
+    // One of the applications of `apps::MINI_APPS`.
+    MiniApp {
+        name: &'static str,
+    },
+    // Draws over a snapshot of a page, optionally reopening its sketch.
+    AnnotatePage {
+        page: usize,
+        background: PathBuf,
+        sketch: Option<PathBuf>,
+    },
     TouchEvents,
     RotationValues,
+    PenCalibration,
+}
+
+impl AppCmd {
+    // The applications that can be launched in kiosk mode.
+    pub fn is_kiosk_safe(&self) -> bool {
+        matches!(self, AppCmd::Dictionary { .. } | AppCmd::Calculator)
+    }
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -389,6 +569,7 @@ pub enum ViewId {
     AnnotationMenu,
     BatteryMenu,
     ClockMenu,
+    NotificationsMenu,
     SearchTargetMenu,
     InputHistoryMenu,
     KeyboardLayoutMenu,
@@ -397,22 +578,59 @@ pub enum ViewId {
     FontSizeMenu,
     TextAlignMenu,
     FontFamilyMenu,
+    FontFamilyPicker,
+    Calendar,
+    PenColorPicker,
+    EndOfBook,
+    BookSwitcher,
+    BookReview,
+    BookReviewInput,
+    Translation,
+    TranslationMenu,
+    Skim,
+    Thumbnails,
+    TonePicker,
+    ZoomView,
+    LockScreen,
+    QrCode,
+    Statistics,
+    Jobs,
+    ListPickerInput,
+    GlobalSearch,
+    GlobalSearchInput,
+    GlobalSearchResults,
     MarginWidthMenu,
     ContrastExponentMenu,
     ContrastGrayMenu,
     LineHeightMenu,
     DirectoryMenu,
     BookMenu,
+    DownloadMenu,
+    PortalInput,
+    PortalInputInput,
+    WeatherMenu,
+    Agenda,
+    NoteMenu,
+    NoteInput,
+    NoteInputInput,
+    TimerChip,
+    MetricsOverlay,
+    TimerMenu,
+    SudokuMenu,
     LibraryMenu,
     PageMenu,
     PresetMenu,
     MarginCropperMenu,
     SearchMenu,
     SketchMenu,
+    SketchWords,
     RenameDocument,
     RenameDocumentInput,
+    Handoff,
+    HandoffInput,
     GoToPage,
     GoToPageInput,
+    GoToPad,
     GoToResultsPage,
     GoToResultsPageInput,
     NamePage,
@@ -421,6 +639,12 @@ pub enum ViewId {
     EditNoteInput,
     EditLanguages,
     EditLanguagesInput,
+    KioskPin,
+    KioskPinInput,
+    ProfileName,
+    ProfileNameInput,
+    DocumentPassword,
+    DocumentPasswordInput,
     HomeSearchInput,
     ReaderSearchInput,
     DictionarySearchInput,
@@ -431,10 +655,16 @@ pub enum ViewId {
     Keyboard,
     AboutDialog,
     ShareDialog,
+    Modal,
     MarginCropper,
     TopBottomBars,
     TableOfContents,
     MessageNotif(Id),
+    LightGauge(Id),
+    ImportNotif,
+    IntegrityNotif,
+    SearchNotif,
+    FetcherNotif(u32),
     SubMenu(u8),
 }
 
@@ -513,12 +743,55 @@ pub enum EntryId {
     Flush,
     Save,
     Import,
+    CancelImport,
+    CheckIntegrity,
+    CancelIntegrityCheck,
+    Reimport(PathBuf),
     CleanUp,
     Sort(SortMethod),
     ReverseOrder,
     EmptyTrash,
+    ConfirmEmptyTrash,
+    ClearCaches,
+    ConfirmClearCaches,
+    ToggleBiggestFiles,
+    PauseDownload(u64),
+    ResumeDownload(u64),
+    RemoveDownload(u64),
+    ClearCompletedDownloads,
+    PortalAction(usize),
+    AcceptPortalTerms(usize),
+    ReloadPortal,
+    SetTimezone(String),
+    SyncClock,
+    RefreshWeather,
+    StartTimer,
+    PauseTimer,
+    ResumeTimer,
+    StopTimer,
+    NewSudoku(Difficulty),
+    // Zero erases the selected cell.
+    SudokuDigit(u8),
+    CheckSudoku,
+    ShiftAgenda(CycleDir),
+    AgendaToday,
+    ToggleAgendaMonth,
+    RefreshCalendars,
+    OpenNote(String),
+    TogglePinNote(String),
+    RenameNote(String),
+    RemoveNote(String),
+    ConfirmRemoveNote(String),
+    NewTextNote,
+    NewHandwrittenNote,
+    SearchNotes,
+    CloseNote,
+    AddNoteLine(bool),
+    EditNoteLine(usize),
+    ToggleNoteTask(usize),
     Rename(PathBuf),
     Remove(PathBuf),
+    ConfirmRemove(PathBuf),
     CopyTo(PathBuf, usize),
     MoveTo(PathBuf, usize),
     AddDirectory(PathBuf),
@@ -526,6 +799,22 @@ pub enum EntryId {
     ToggleSelectDirectory(PathBuf),
     SetStatus(PathBuf, SimpleStatus),
     SearchAuthor(String),
+    SearchSeries(String),
+    OpenNextInSeries(PathBuf),
+    SwitchBook(Id),
+    OpenDocument(PathBuf),
+    ApplySmartCollection(usize),
+    // Picks a random unread book, from the given smart collection or the current search.
+    SurpriseMe(Option<usize>, Option<BookLength>),
+    ReceiveHandoff,
+    SaveSmartCollection,
+    SwitchProfile(String),
+    AddProfile,
+    CreateBackup,
+    RestoreBackup(PathBuf),
+    ConfirmRestoreBackup(PathBuf),
+    RestoreSnapshot(PathBuf),
+    ConfirmRestoreSnapshot(PathBuf),
     RemovePreset(usize),
     FirstColumn(FirstColumn),
     SecondColumn(SecondColumn),
@@ -539,14 +828,35 @@ pub enum EntryId {
     HighlightSelection,
     AnnotateSelection,
     DefineSelection,
+    TranslateSelection,
+    SetTranslationSource(String),
+    SetTranslationTarget(String),
+    Skim,
+    Thumbnails,
+    ToneCurves,
+    ToggleKeepOrientation,
+    ToggleManga,
+    SetToneCurve(Option<ToneCurve>),
+    SetRefreshPolicy(Option<RefreshPolicy>),
+    RecognizeText,
+    GoToDestination,
+    ToggleReadAloud,
+    // Percentages of the normal speed and pitch.
+    SetSpeechSpeed(u16),
+    SetSpeechPitch(u16),
     SearchForSelection,
+    ShareSelection,
+    SharePage,
     AdjustSelection,
     Annotations,
+    AnnotatePage,
     Bookmarks,
     RemoveAnnotation([TextLocation; 2]),
     EditAnnotationNote([TextLocation; 2]),
     RemoveAnnotationNote([TextLocation; 2]),
     GoTo(usize),
+    GoToLandmark(usize),
+    ReturnToPage(usize),
     GoToSelectedPageName,
     SearchDirection(LinearDir),
     SetButtonScheme(ButtonScheme),
@@ -554,6 +864,7 @@ pub enum EntryId {
     SetFontSize(i32),
     SetTextAlign(TextAlign),
     SetMarginWidth(i32),
+    SetVerticalMarginWidth(Option<i32>),
     SetLineHeight(i32),
     SetContrastExponent(i32),
     SetContrastGray(i32),
@@ -561,18 +872,50 @@ pub enum EntryId {
     SetSearchTarget(Option<String>),
     SetInputText(ViewId, String),
     SetKeyboardLayout(String),
+    ToggleHandwritingPad,
     ToggleShowHidden,
     ToggleFuzzy,
     ToggleInverted,
+    ToggleMetrics,
     ToggleDithered,
     ToggleWifi,
+    LockKiosk,
+    UnlockKiosk,
+    ClearNotifications,
+    ShowCalendar,
+    // The text to encode and its caption.
+    ShowQrCode(String, String),
+    ShowStatistics(PathBuf),
+    ShowJobs,
+    CancelJob(JobId),
+    ClearJobs,
+    FetchMetadata(PathBuf),
+    // An empty language means it will be detected when the book is opened.
+    SetBookLanguage(PathBuf, String),
+    // No cover means the first page.
+    SetCover(PathBuf, Option<Cover>),
     Rotate(i8),
     Launch(AppCmd),
     SetPenSize(i32),
     SetPenColor(u8),
+    PickPenColor,
+    ToggleCustomColor(u8),
+    SetBookRating(u8),
+    GradeCard(Grade),
+    RevealCard,
+    ExportVocabulary,
+    EditBookReview,
+    ArchiveBook,
     TogglePenDynamism,
+    ReplayStrokes,
+    ExportSketches,
+    RecognizeHandwriting,
+    ShowWords,
     ReloadDictionaries,
     New,
+    Discard,
+    SaveAndQuit,
+    DiscardAndQuit,
     Refresh,
     TakeScreenshot,
     Reboot,
@@ -663,18 +1006,128 @@ impl UpdateData {
     }
 }
 
+// A page turn, whose update mode is chosen by the refresh scheduler.
+#[derive(Debug, Clone)]
+pub struct PageTurn {
+    pub id: Id,
+    pub rect: Rectangle,
+    // Overrides the default refresh policy, e.g. for the current book.
+    pub policy: Option<RefreshPolicy>,
+    pub chapter_started: bool,
+}
+
+impl PageTurn {
+    pub fn new(id: Id, rect: Rectangle) -> PageTurn {
+        PageTurn { id, rect, policy: None, chapter_started: false }
+    }
+}
+
+// Fully refreshes the screen, to remove the ghosting, on some of the page turns of the views,
+// according to the refresh policy. The page turns are counted across the views.
+#[derive(Debug, Default)]
+pub struct RefreshScheduler {
+    page_turns: usize,
+}
+
+impl RefreshScheduler {
+    // Turns the pending page turns into updates.
+    pub fn schedule(&mut self, rq: &mut RenderQueue, settings: &RefreshRateSettings, inverted: bool) {
+        let rate = if inverted { settings.inverted } else { settings.regular };
+        for turn in mem::take(&mut rq.page_turns) {
+            self.page_turns += 1;
+            let policy = turn.policy.unwrap_or(settings.policy);
+            let mode = if policy.full_refresh(rate, self.page_turns, turn.chapter_started) {
+                UpdateMode::Full
+            } else {
+                UpdateMode::Partial
+            };
+            // The other updates of the page are superseded.
+            for pairs in rq.values_mut() {
+                pairs.retain(|(id, rect)| *id != Some(turn.id) || !turn.rect.contains(rect));
+            }
+            rq.add(RenderData::new(turn.id, turn.rect, mode));
+        }
+    }
+}
+
 type RQ = FxHashMap<(UpdateMode, bool), Vec<(Option<Id>, Rectangle)>>;
-pub struct RenderQueue(RQ);
+pub struct RenderQueue {
+    updates: RQ,
+    page_turns: Vec<PageTurn>,
+}
 
 impl RenderQueue {
     pub fn new() -> RenderQueue {
-        RenderQueue(FxHashMap::default())
+        RenderQueue {
+            updates: FxHashMap::default(),
+            page_turns: Vec::new(),
+        }
     }
 
+    pub fn turn_page(&mut self, page_turn: PageTurn) {
+        self.page_turns.push(page_turn);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty() && self.page_turns.is_empty()
+    }
+
+    // The updates superseded by the new one, for the same view, are dropped.
     pub fn add(&mut self, data: RenderData) {
-        self.entry((data.mode, data.wait)).or_insert_with(|| {
+        let pairs = self.entry((data.mode, data.wait)).or_insert_with(|| {
             Vec::new()
-        }).push((data.id, data.rect));
+        });
+        if pairs.iter().any(|(id, rect)| *id == data.id && rect.contains(&data.rect)) {
+            return;
+        }
+        pairs.retain(|(id, rect)| *id != data.id || !data.rect.contains(rect));
+        pairs.push((data.id, data.rect));
+    }
+
+    // Whether the queue only holds the kind of updates that can be paced.
+    pub fn is_paceable(&self) -> bool {
+        self.page_turns.is_empty() &&
+        self.iter().all(|((mode, _), pairs)| pairs.is_empty() ||
+                                             matches!(mode, UpdateMode::Fast | UpdateMode::FastMono))
+    }
+}
+
+// Limits the rate at which the fast updates are submitted to the e-ink controller,
+// so that the updates queued in the meantime are merged into a single frame.
+pub struct FramePacer {
+    interval: Duration,
+    last_submission: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(interval: Duration) -> FramePacer {
+        FramePacer { interval, last_submission: None }
+    }
+
+    // The time to wait before submitting the given queue.
+    pub fn delay(&self, rq: &RenderQueue, now: Instant) -> Duration {
+        if rq.is_empty() || !rq.is_paceable() {
+            return Duration::ZERO;
+        }
+        self.last_submission.map(|last| (last + self.interval).saturating_duration_since(now))
+            .unwrap_or(Duration::ZERO)
+    }
+
+    // Submits the queue, unless it's too early, in which case the remaining delay is returned.
+    pub fn process(&mut self, view: &dyn View, rq: &mut RenderQueue, context: &mut Context, updating: &mut Vec<UpdateData>) -> Option<Duration> {
+        let now = Instant::now();
+        let delay = self.delay(rq, now);
+
+        if !delay.is_zero() {
+            return Some(delay);
+        }
+
+        if !rq.is_empty() {
+            self.last_submission = Some(now);
+            process_render_queue(view, rq, context, updating);
+        }
+
+        None
     }
 }
 
@@ -688,13 +1141,13 @@ impl Deref for RenderQueue {
     type Target = RQ;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.updates
     }
 }
 
 impl DerefMut for RenderQueue {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.updates
     }
 }
 
@@ -711,3 +1164,30 @@ impl IdFeeder {
         self.0.fetch_add(1, Ordering::Relaxed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_scheduler() {
+        let mut scheduler = RefreshScheduler::default();
+        let settings = RefreshRateSettings { regular: 2, .. Default::default() };
+        let rect = rect![0, 0, 100, 100];
+        let mut rq = RenderQueue::new();
+        rq.add(RenderData::new(1, rect, UpdateMode::Gui));
+        rq.turn_page(PageTurn::new(1, rect));
+        scheduler.schedule(&mut rq, &settings, false);
+        assert_eq!(rq.get(&(UpdateMode::Partial, true)), Some(&vec![(Some(1), rect)]));
+        assert_eq!(rq.get(&(UpdateMode::Gui, true)), Some(&vec![]));
+        // The count goes on with the page turns of another view.
+        let mut rq = RenderQueue::new();
+        rq.turn_page(PageTurn::new(2, rect));
+        scheduler.schedule(&mut rq, &settings, false);
+        assert_eq!(rq.get(&(UpdateMode::Full, true)), Some(&vec![(Some(2), rect)]));
+        let mut rq = RenderQueue::new();
+        rq.turn_page(PageTurn { policy: Some(RefreshPolicy::Chapter), chapter_started: true, .. PageTurn::new(1, rect) });
+        scheduler.schedule(&mut rq, &settings, false);
+        assert!(rq.contains_key(&(UpdateMode::Full, true)));
+    }
+}
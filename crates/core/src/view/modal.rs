@@ -0,0 +1,252 @@
+use std::thread;
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, EntryId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM, CLOSE_IGNITION_DELAY};
+use super::packed_view::{PackedView, Position};
+use super::button::Button;
+use super::filler::Filler;
+use super::label::Label;
+use super::input_field::InputField;
+use crate::framebuffer::Framebuffer;
+use crate::input::DeviceEvent;
+use crate::gesture::GestureEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+const LABEL_VALIDATE: &str = "OK";
+const LABEL_CANCEL: &str = "Cancel";
+
+pub enum ModalKind {
+    // A message with an *OK* button.
+    Message,
+    // The event is sent to the parent when the user validates.
+    Confirm(Event),
+    // The text of the input field is submitted when the user validates.
+    // The parent is responsible for showing the keyboard, and will receive
+    // `Event::Close` through the bus, like with a `NamedInput`.
+    Input(ViewId),
+    // One button per choice: the parent receives `Event::Select` with the
+    // identifier of the chosen entry.
+    Choice(Vec<(String, EntryId)>),
+}
+
+pub struct Modal {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    kind: ModalKind,
+    will_close: bool,
+}
+
+impl Modal {
+    pub fn new(view_id: ViewId, text: String, kind: ModalKind, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Modal {
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+
+        let (padding, button_height, message_width, button_width) = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+            let padding = font.em() as i32;
+            let max_message_width = width as i32 - 3 * padding;
+            let max_button_width = width as i32 / 4;
+            let message_width = font.plan(&text, Some(max_message_width), None).width;
+            let button_width = [LABEL_VALIDATE, LABEL_CANCEL].iter()
+                                    .map(|label| font.plan(label, Some(max_button_width), None).width)
+                                    .max().unwrap_or(0);
+            (padding, 4 * font.x_heights.0 as i32, message_width, button_width + padding)
+        };
+
+        let rows = match kind {
+            ModalKind::Message | ModalKind::Confirm(..) => 0,
+            ModalKind::Input(..) => 1,
+            ModalKind::Choice(ref choices) => choices.len() as i32,
+        };
+
+        let modal_width = message_width.max(width as i32 / 2) + 3 * padding;
+        let modal_height = (rows + 2) * (button_height + padding) + padding;
+        let rect = centered_rect(modal_width, modal_height, width, height);
+        let inner_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                               rect.max.x - padding, rect.max.y - padding];
+        let null_rect = rect![0, 0, 0, 0];
+
+        let mut packed = PackedView::new(inner_rect)
+            .push(Box::new(Label::new(null_rect, text, Align::Center)),
+                  Position::hfilled_top_left(button_height as u32), hub, rq, context);
+
+        match kind {
+            ModalKind::Input(input_id) => {
+                packed = packed
+                    .push(Box::new(Filler::new(null_rect, WHITE)),
+                          Position::hfilled_top_left(padding as u32), hub, rq, context)
+                    .push(Box::new(InputField::new(null_rect, input_id)),
+                          Position::hfilled_top_left(button_height as u32), hub, rq, context);
+            },
+            ModalKind::Choice(ref choices) => {
+                for (label, entry_id) in choices {
+                    packed = packed
+                        .push(Box::new(Filler::new(null_rect, WHITE)),
+                              Position::hfilled_top_left(padding as u32), hub, rq, context)
+                        .push(Box::new(Button::new(null_rect, Event::Select(entry_id.clone()), label.clone())),
+                              Position::hfilled_top_left(button_height as u32), hub, rq, context);
+                }
+            },
+            _ => (),
+        }
+
+        let mut buttons = PackedView::new(null_rect);
+        if !matches!(kind, ModalKind::Message) {
+            buttons = buttons.push(Box::new(Button::new(null_rect, Event::Cancel, LABEL_CANCEL.to_string())),
+                                   Position::top_left(button_width, button_height), hub, rq, context);
+        }
+        if !matches!(kind, ModalKind::Choice(..)) {
+            buttons = buttons.push(Box::new(Button::new(null_rect, Event::Validate, LABEL_VALIDATE.to_string())),
+                                   Position::top_right(button_width, button_height), hub, rq, context);
+        }
+
+        packed = packed.push(Box::new(buttons),
+                             Position::hfilled_bottom_left(button_height as u32), hub, rq, context);
+
+        Modal {
+            id: ID_FEEDER.next(),
+            rect,
+            children: vec![Box::new(packed) as Box<dyn View>],
+            view_id,
+            kind,
+            will_close: false,
+        }
+    }
+
+    fn input_text(&self) -> Option<String> {
+        self.children[0].children().iter()
+            .find_map(|child| child.downcast_ref::<InputField>())
+            .map(|input_field| input_field.value().to_string())
+    }
+
+    fn close(&mut self, hub: &Hub, bus: &mut Bus) {
+        if self.will_close {
+            return;
+        }
+
+        self.will_close = true;
+
+        if let ModalKind::Input(..) = self.kind {
+            bus.push_back(Event::Close(self.view_id));
+        } else {
+            let hub2 = hub.clone();
+            let view_id = self.view_id;
+            thread::spawn(move || {
+                thread::sleep(CLOSE_IGNITION_DELAY);
+                hub2.send(Event::Close(view_id)).ok();
+            });
+        }
+    }
+}
+
+fn centered_rect(modal_width: i32, modal_height: i32, width: u32, height: u32) -> Rectangle {
+    let dx = (width as i32 - modal_width) / 2;
+    let dy = (height as i32 - modal_height) / 2;
+    rect![dx, dy, dx + modal_width, dy + modal_height]
+}
+
+impl View for Modal {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Validate | Event::Cancel => {
+                if self.will_close {
+                    return true;
+                }
+                if let Event::Validate = *evt {
+                    match self.kind {
+                        ModalKind::Confirm(ref event) => bus.push_back(event.clone()),
+                        ModalKind::Input(input_id) => {
+                            if let Some(text) = self.input_text() {
+                                bus.push_back(Event::Submit(input_id, text));
+                            }
+                        },
+                        _ => (),
+                    }
+                }
+                self.close(hub, bus);
+                true
+            },
+            Event::Submit(view_id, _) if matches!(self.kind, ModalKind::Input(input_id) if input_id == view_id) => {
+                self.close(hub, bus);
+                false
+            },
+            Event::Select(ref entry_id) if matches!(self.kind, ModalKind::Choice(ref choices)
+                                                    if choices.iter().any(|(_, id)| id == entry_id)) => {
+                self.close(hub, bus);
+                false
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if context.kb_rect.includes(center) => false,
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                self.close(hub, bus);
+                true
+            },
+            Event::Device(DeviceEvent::Finger { position, .. }) => !context.kb_rect.includes(position),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let rect = centered_rect(self.rect.width() as i32, self.rect.height() as i32, width, height);
+        let padding = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+            font.em() as i32
+        };
+        let inner_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                               rect.max.x - padding, rect.max.y - padding];
+        self.children[0].resize(inner_rect, hub, rq, context);
+        self.rect = rect;
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
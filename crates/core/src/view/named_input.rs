@@ -153,4 +153,8 @@ impl View for NamedInput {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focus_scope(&self) -> bool {
+        true
+    }
 }
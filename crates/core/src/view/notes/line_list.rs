@@ -0,0 +1,154 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, BorderSpec, CornerSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE};
+use crate::gesture::GestureEvent;
+use crate::notes::{LineKind, parse_line};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData, EntryId};
+use crate::view::{Id, ID_FEEDER, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08};
+use crate::context::Context;
+
+// The lines of a text note, one per row. Tap a task's box to check it, or a line to edit it.
+pub struct LineList {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    lines: Vec<String>,
+    row_height: i32,
+    current_page: usize,
+}
+
+impl LineList {
+    pub fn new(rect: Rectangle, context: &mut Context) -> LineList {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        LineList {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            lines: Vec::new(),
+            row_height: 3 * font.line_height() / 2,
+            current_page: 0,
+        }
+    }
+
+    pub fn update(&mut self, lines: Vec<String>, rq: &mut RenderQueue) {
+        self.lines = lines;
+        self.current_page = self.current_page.min(self.pages_count() - 1);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn rows_per_page(&self) -> usize {
+        (self.rect.height() as i32 / self.row_height.max(1)).max(1) as usize
+    }
+
+    fn pages_count(&self) -> usize {
+        // The row that follows the last line is kept for adding lines.
+        (self.lines.len() + 1).div_ceil(self.rows_per_page())
+    }
+
+    pub fn go_to_page(&mut self, next: bool, rq: &mut RenderQueue) {
+        let page = if next {
+            (self.current_page + 1).min(self.pages_count() - 1)
+        } else {
+            self.current_page.saturating_sub(1)
+        };
+        if page != self.current_page {
+            self.current_page = page;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+
+    pub fn go_to_last_page(&mut self, rq: &mut RenderQueue) {
+        self.current_page = self.pages_count() - 1;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn box_size(&self) -> i32 {
+        self.row_height / 2
+    }
+}
+
+impl View for LineList {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let row = ((center.y - self.rect.min.y) / self.row_height.max(1)) as usize;
+                let index = self.current_page * self.rows_per_page() + row;
+                let padding = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi).em() as i32;
+                match self.lines.get(index).map(|line| parse_line(line)) {
+                    Some(LineKind::Task(..)) if center.x < self.rect.min.x + 2 * padding + self.box_size() => {
+                        bus.push_back(Event::Select(EntryId::ToggleNoteTask(index)));
+                    },
+                    Some(_) => bus.push_back(Event::Select(EntryId::EditNoteLine(index))),
+                    None => bus.push_back(Event::Select(EntryId::AddNoteLine(false))),
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as u16;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let per_page = self.rows_per_page();
+        let start = self.current_page * per_page;
+        let row_height = self.row_height;
+        let box_size = self.box_size();
+
+        for (row, line) in self.lines.iter().skip(start).take(per_page).enumerate() {
+            let y = self.rect.min.y + row as i32 * row_height;
+            let kind = parse_line(line);
+            let font = font_from_style(fonts, if let LineKind::Heading(..) = kind { &BOLD_STYLE } else { &NORMAL_STYLE }, dpi);
+            let padding = font.em() as i32;
+            let x_height = font.x_heights.0 as i32;
+            let baseline = y + (row_height + x_height) / 2;
+            let mut x = self.rect.min.x + padding;
+            let (text, color) = match kind {
+                LineKind::Task(done, text) => {
+                    let box_rect = rect![x, y + (row_height - box_size) / 2,
+                                         x + box_size, y + (row_height + box_size) / 2];
+                    fb.draw_rounded_rectangle_with_border(&box_rect, &CornerSpec::Uniform(box_size / 6),
+                                                          &BorderSpec { thickness, color: BLACK },
+                                                          if done { &BLACK } else { &WHITE });
+                    x += box_size + padding;
+                    (text, if done { GRAY08 } else { BLACK })
+                },
+                LineKind::Heading(_, text) => (text, BLACK),
+                LineKind::Text(text) => (text, BLACK),
+            };
+            let plan = font.plan(text, Some(self.rect.max.x - padding - x), None);
+            font.render(fb, color, &plan, pt!(x, baseline));
+        }
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.rect = rect;
+        self.current_page = self.current_page.min(self.pages_count() - 1);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,587 @@
+mod note_list;
+mod line_list;
+
+use std::fs;
+use std::path::PathBuf;
+use anyhow::Error;
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, Dir, halves, divide};
+use crate::gesture::GestureEvent;
+use crate::notes::{self, Note, NoteKind, LineKind, parse_line, toggle_task};
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::menu::{Menu, MenuKind};
+use crate::view::modal::{Modal, ModalKind};
+use crate::view::keyboard::Keyboard;
+use crate::view::named_input::NamedInput;
+use crate::view::notification::Notification;
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::common::{locate, locate_by_id};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryKind, EntryId, AppCmd, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::session::{Session, ApplicationState};
+use crate::helpers::write_atomically;
+use crate::context::Context;
+use self::note_list::NoteList;
+use self::line_list::LineList;
+
+// What the text of the input is for.
+enum InputTarget {
+    Search,
+    Rename(String),
+    Line(usize),
+    // Whether the new line is a task.
+    NewLine(bool),
+}
+
+// The name and the lines of a text note.
+struct OpenNote {
+    name: String,
+    lines: Vec<String>,
+}
+
+// Lists the notes of the notes directory, and edits the text notes line by line.
+pub struct Notes {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    dir: PathBuf,
+    notes: Vec<Note>,
+    query: Option<String>,
+    open: Option<OpenNote>,
+    target: Option<InputTarget>,
+}
+
+impl Notes {
+    pub fn new(rect: Rectangle, note: Option<&str>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Notes {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  "Notes".to_string(),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let note_list = NoteList::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                            rect.max.x, rect.max.y - small_height - small_thickness],
+                                      context);
+        children.push(Box::new(note_list) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut notes = Notes {
+            id,
+            rect,
+            children,
+            dir: context.library.home.join(&context.settings.notes.path),
+            notes: Vec::new(),
+            query: None,
+            open: None,
+            target: None,
+        };
+
+        notes.load(context);
+        if let Some(name) = note.filter(|name| NoteKind::from_name(name) == Some(NoteKind::Text)) {
+            notes.open_note(name, hub, &mut RenderQueue::new(), context);
+        } else {
+            notes.update_content(&mut RenderQueue::new(), context);
+            notes.update_buttons(&mut RenderQueue::new());
+        }
+
+        notes
+    }
+
+    fn load(&mut self, context: &Context) {
+        self.notes = notes::list(&self.dir, &context.settings.notes.pinned);
+    }
+
+    fn update_content(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let rect = *self.children[2].rect();
+        let title = if let Some(open) = self.open.as_ref() {
+            if self.children[2].downcast_ref::<LineList>().is_none() {
+                self.children[2] = Box::new(LineList::new(rect, context)) as Box<dyn View>;
+            }
+            if let Some(list) = self.children[2].downcast_mut::<LineList>() {
+                list.update(open.lines.clone(), rq);
+            }
+            notes::title_and_excerpt(&open.lines.join("\n")).0
+        } else {
+            let visible: Vec<Note> = self.notes.iter()
+                                         .filter(|note| self.query.as_ref().is_none_or(|query| note.matches(query)))
+                                         .cloned().collect();
+            if self.children[2].downcast_ref::<NoteList>().is_none() {
+                self.children[2] = Box::new(NoteList::new(rect, context)) as Box<dyn View>;
+            }
+            if let Some(list) = self.children[2].downcast_mut::<NoteList>() {
+                list.update(visible, rq);
+            }
+            match self.query.as_ref() {
+                Some(query) => format!("Notes · {}", query),
+                None => "Notes".to_string(),
+            }
+        };
+        let title = if title.is_empty() { "Untitled".to_string() } else { title };
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title, rq);
+        }
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let buttons = if self.open.is_some() {
+            vec![(Event::Select(EntryId::AddNoteLine(false)), "Add Line"),
+                 (Event::Select(EntryId::AddNoteLine(true)), "Add Task"),
+                 (Event::Select(EntryId::CloseNote), "Done")]
+        } else {
+            vec![(Event::Select(EntryId::SearchNotes), "Search"),
+                 (Event::Select(EntryId::NewTextNote), "New Note"),
+                 (Event::Select(EntryId::NewHandwrittenNote), "New Drawing")]
+        };
+        let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for (index, ((event, text), w)) in buttons.into_iter().zip(widths).enumerate() {
+            let button_rect = rect![x, rect.min.y + padding, x + w - padding, rect.max.y - padding];
+            self.children.insert(5 + index, Box::new(Button::new(button_rect, event, text.to_string())) as Box<dyn View>);
+            x += w;
+        }
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn open_note(&mut self, name: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if NoteKind::from_name(name) == Some(NoteKind::Handwritten) {
+            hub.send(Event::Select(EntryId::Launch(AppCmd::HandwrittenNote { name: Some(name.to_string()) }))).ok();
+            return;
+        }
+        let text = match fs::read_to_string(self.dir.join(name)) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Can't read note {}: {:#}.", name, e);
+                return;
+            },
+        };
+        self.open = Some(OpenNote {
+            name: name.to_string(),
+            lines: text.lines().map(String::from).collect(),
+        });
+        self.update_content(rq, context);
+        self.update_buttons(rq);
+    }
+
+    fn close_note(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(open) = self.open.take() {
+            // A note whose text was erased is removed.
+            let path = self.dir.join(&open.name);
+            if open.lines.iter().all(|line| line.trim().is_empty()) && path.exists() {
+                fs::remove_file(&path).map_err(|e| eprintln!("Can't remove note {}: {:#}.", open.name, e)).ok();
+            }
+        }
+        self.load(context);
+        self.update_content(rq, context);
+        self.update_buttons(rq);
+    }
+
+    fn save(&self) {
+        if let Some(open) = self.open.as_ref() {
+            let mut text = open.lines.join("\n");
+            text.push('\n');
+            fs::create_dir_all(&self.dir).map_err(Error::from)
+               .and_then(|_| write_atomically(self.dir.join(&open.name), text.as_bytes()))
+               .map_err(|e| eprintln!("Can't save note {}: {:#}.", open.name, e))
+               .ok();
+        }
+    }
+
+    fn prompt(&mut self, target: InputTarget, label: &str, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.target.is_some() {
+            return;
+        }
+        let mut named_input = NamedInput::new(label.to_string(), ViewId::NoteInput,
+                                              ViewId::NoteInputInput, 32, context);
+        if !text.is_empty() {
+            named_input.set_text(text, &mut RenderQueue::new(), context);
+        }
+        rq.add(RenderData::new(named_input.id(), *named_input.rect(), UpdateMode::Gui));
+        hub.send(Event::Focus(Some(ViewId::NoteInputInput))).ok();
+        self.children.push(Box::new(named_input) as Box<dyn View>);
+        self.target = Some(target);
+    }
+
+    fn close_input(&mut self, rq: &mut RenderQueue) {
+        if let Some(index) = locate_by_id(self, ViewId::NoteInput) {
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        }
+    }
+
+    fn toggle_keyboard(&mut self, enable: bool, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate::<Keyboard>(self) {
+            if enable {
+                return;
+            }
+
+            let mut rect = *self.child(index).rect();
+            rect.absorb(self.child(index-1).rect());
+            self.children.drain(index - 1 ..= index);
+            rq.add(RenderData::expose(rect, UpdateMode::Gui));
+            hub.send(Event::Focus(None)).ok();
+        } else {
+            if !enable {
+                return;
+            }
+
+            let dpi = CURRENT_DEVICE.dpi;
+            let (small_height, big_height) = (scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32,
+                                              scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32);
+            let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+            let (small_thickness, big_thickness) = halves(thickness);
+            let mut kb_rect = rect![self.rect.min.x,
+                                    self.rect.max.y - (small_height + 3 * big_height) + big_thickness,
+                                    self.rect.max.x,
+                                    self.rect.max.y - small_height - small_thickness];
+
+            let keyboard = Keyboard::new(&mut kb_rect, false, context);
+            let separator = Filler::new(rect![self.rect.min.x, kb_rect.min.y - thickness,
+                                              self.rect.max.x, kb_rect.min.y],
+                                        BLACK);
+            rq.add(RenderData::new(separator.id(), *separator.rect(), UpdateMode::Gui));
+            rq.add(RenderData::new(keyboard.id(), *keyboard.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(separator) as Box<dyn View>);
+            self.children.push(Box::new(keyboard) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_note_menu(&mut self, index: usize, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::NoteMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let note = if let Some(note) = self.visible_note(index) { note } else { return };
+            let entries = vec![EntryKind::Command(if note.pinned { "Unpin" } else { "Pin" }.to_string(),
+                                                  EntryId::TogglePinNote(note.name.clone())),
+                               EntryKind::Command("Rename".to_string(),
+                                                  EntryId::RenameNote(note.name.clone())),
+                               EntryKind::Command("Remove".to_string(),
+                                                  EntryId::RemoveNote(note.name.clone()))];
+            let note_menu = Menu::new(rect, ViewId::NoteMenu, MenuKind::Contextual, entries, context);
+            rq.add(RenderData::new(note_menu.id(), *note_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(note_menu) as Box<dyn View>);
+        }
+    }
+
+    fn visible_note(&self, index: usize) -> Option<&Note> {
+        self.notes.iter()
+            .filter(|note| self.query.as_ref().is_none_or(|query| note.matches(query)))
+            .nth(index)
+    }
+
+    fn submit(&mut self, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        match self.target.take() {
+            Some(InputTarget::Search) => {
+                self.query = Some(text.trim().to_string()).filter(|query| !query.is_empty());
+                self.update_content(rq, context);
+            },
+            Some(InputTarget::Rename(name)) => {
+                match notes::rename(&self.dir, &name, text) {
+                    Ok(new_name) => {
+                        for pinned in context.settings.notes.pinned.iter_mut().filter(|pinned| **pinned == name) {
+                            *pinned = new_name.clone();
+                        }
+                        self.load(context);
+                        self.update_content(rq, context);
+                    },
+                    Err(e) => {
+                        let notif = Notification::new(format!("Can't rename {}: {}.", name, e), hub, rq, context);
+                        self.children.push(Box::new(notif) as Box<dyn View>);
+                    },
+                }
+            },
+            Some(InputTarget::Line(index)) => {
+                if let Some(open) = self.open.as_mut().filter(|open| index < open.lines.len()) {
+                    // Erasing the text of a line removes it.
+                    if text.is_empty() {
+                        open.lines.remove(index);
+                    } else {
+                        open.lines[index] = text.to_string();
+                    }
+                }
+                self.save();
+                self.update_content(rq, context);
+            },
+            Some(InputTarget::NewLine(task)) => {
+                if text.is_empty() {
+                    return;
+                }
+                if let Some(open) = self.open.as_mut() {
+                    open.lines.push(if task { format!("- [ ] {}", text) } else { text.to_string() });
+                }
+                self.save();
+                self.update_content(rq, context);
+                if let Some(list) = self.children[2].downcast_mut::<LineList>() {
+                    list.go_to_last_page(rq);
+                }
+            },
+            None => (),
+        }
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        if self.open.is_none() {
+            self.load(context);
+            self.update_content(&mut RenderQueue::new(), context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for Notes {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(EntryId::OpenNote(ref name)) => {
+                self.open_note(name, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::NewTextNote) => {
+                self.open = Some(OpenNote { name: notes::new_text_name(), lines: Vec::new() });
+                self.update_content(rq, context);
+                self.update_buttons(rq);
+                self.prompt(InputTarget::NewLine(false), "Text", "", hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::NewHandwrittenNote) => {
+                hub.send(Event::Select(EntryId::Launch(AppCmd::HandwrittenNote { name: None }))).ok();
+                true
+            },
+            Event::Select(EntryId::SearchNotes) => {
+                let query = self.query.clone().unwrap_or_default();
+                self.prompt(InputTarget::Search, "Search", &query, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::CloseNote) => {
+                self.close_note(rq, context);
+                true
+            },
+            Event::Select(EntryId::AddNoteLine(task)) => {
+                self.prompt(InputTarget::NewLine(task), if task { "Task" } else { "Text" }, "", hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::EditNoteLine(index)) => {
+                let line = self.open.as_ref().and_then(|open| open.lines.get(index)).cloned();
+                if let Some(line) = line {
+                    let label = match parse_line(&line) {
+                        LineKind::Task(..) => "Task",
+                        LineKind::Heading(..) => "Heading",
+                        LineKind::Text(..) => "Text",
+                    };
+                    self.prompt(InputTarget::Line(index), label, &line, hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::ToggleNoteTask(index)) => {
+                if let Some(line) = self.open.as_mut().and_then(|open| open.lines.get_mut(index)) {
+                    *line = toggle_task(line);
+                }
+                self.save();
+                self.update_content(rq, context);
+                true
+            },
+            Event::ToggleNoteMenu(rect, index) => {
+                self.toggle_note_menu(index, rect, None, rq, context);
+                true
+            },
+            Event::Select(EntryId::TogglePinNote(ref name)) => {
+                let pinned = &mut context.settings.notes.pinned;
+                if let Some(index) = pinned.iter().position(|pinned| pinned == name) {
+                    pinned.remove(index);
+                } else {
+                    pinned.push(name.clone());
+                }
+                self.load(context);
+                self.update_content(rq, context);
+                true
+            },
+            Event::Select(EntryId::RenameNote(ref name)) => {
+                let title = self.notes.iter().find(|note| note.name == *name)
+                                .map(|note| note.title.clone()).unwrap_or_default();
+                self.prompt(InputTarget::Rename(name.clone()), "Name", &title, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::RemoveNote(ref name)) => {
+                let title = self.notes.iter().find(|note| note.name == *name)
+                                .map_or(name.as_str(), |note| note.title.as_str());
+                let event = Event::Select(EntryId::ConfirmRemoveNote(name.clone()));
+                let modal = Modal::new(ViewId::Modal, format!("Remove {}?", title),
+                                       ModalKind::Confirm(event), hub, rq, context);
+                rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+                self.children.push(Box::new(modal) as Box<dyn View>);
+                true
+            },
+            Event::Select(EntryId::ConfirmRemoveNote(ref name)) => {
+                if let Err(e) = fs::remove_file(self.dir.join(name)) {
+                    eprintln!("Can't remove note {}: {:#}.", name, e);
+                }
+                context.settings.notes.pinned.retain(|pinned| pinned != name);
+                self.load(context);
+                self.update_content(rq, context);
+                true
+            },
+            Event::Submit(ViewId::NoteInputInput, ref text) => {
+                self.submit(text, hub, rq, context);
+                true
+            },
+            // The input is closed after being submitted, or to cancel.
+            Event::Close(ViewId::NoteInput) => {
+                self.close_input(rq);
+                self.toggle_keyboard(false, hub, rq, context);
+                self.target = None;
+                true
+            },
+            Event::Focus(Some(ViewId::NoteInputInput)) => {
+                self.toggle_keyboard(true, hub, rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.children[2].rect().includes(start) => {
+                let next = matches!(dir, Dir::West | Dir::North);
+                if let Some(list) = self.children[2].downcast_mut::<NoteList>() {
+                    list.go_to_page(next, rq);
+                } else if let Some(list) = self.children[2].downcast_mut::<LineList>() {
+                    list.go_to_page(next, rq);
+                }
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                if self.open.is_some() {
+                    self.close_note(rq, context);
+                } else {
+                    hub.send(Event::Back).ok();
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        if let Some(index) = locate::<Keyboard>(self) {
+            self.children.drain(index - 1 ..= index);
+        }
+        if let Some(index) = locate_by_id(self, ViewId::NoteInput) {
+            self.children.remove(index);
+        }
+        self.target = None;
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn save_state(&self, session: &mut Session) {
+        session.application = Some(ApplicationState::Notes { note: self.open.as_ref().map(|open| open.name.clone()) });
+    }
+}
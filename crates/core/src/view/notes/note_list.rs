@@ -0,0 +1,163 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE, MD_SIZE};
+use crate::gesture::GestureEvent;
+use crate::notes::{Note, NoteKind};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData, EntryId};
+use crate::view::{Id, ID_FEEDER, THICKNESS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+// One row per note: the title and the date, above the beginning of the text.
+// Tap a note to open it, hold it to bring up its menu.
+pub struct NoteList {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    notes: Vec<Note>,
+    row_height: i32,
+    current_page: usize,
+}
+
+impl NoteList {
+    pub fn new(rect: Rectangle, context: &mut Context) -> NoteList {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        NoteList {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            notes: Vec::new(),
+            row_height: 2 * font.line_height(),
+            current_page: 0,
+        }
+    }
+
+    pub fn update(&mut self, notes: Vec<Note>, rq: &mut RenderQueue) {
+        self.notes = notes;
+        self.current_page = self.current_page.min(self.pages_count() - 1);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn rows_per_page(&self) -> usize {
+        (self.rect.height() as i32 / self.row_height.max(1)).max(1) as usize
+    }
+
+    fn pages_count(&self) -> usize {
+        self.notes.len().max(1).div_ceil(self.rows_per_page())
+    }
+
+    pub fn go_to_page(&mut self, next: bool, rq: &mut RenderQueue) {
+        let page = if next {
+            (self.current_page + 1).min(self.pages_count() - 1)
+        } else {
+            self.current_page.saturating_sub(1)
+        };
+        if page != self.current_page {
+            self.current_page = page;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+
+    fn index_at(&self, y: i32) -> Option<usize> {
+        let row = ((y - self.rect.min.y) / self.row_height.max(1)) as usize;
+        let index = self.current_page * self.rows_per_page() + row;
+        if row < self.rows_per_page() && index < self.notes.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn row_rect(&self, index: usize) -> Rectangle {
+        let row = (index % self.rows_per_page()) as i32;
+        let y = self.rect.min.y + row * self.row_height;
+        rect![self.rect.min.x, y, self.rect.max.x, y + self.row_height]
+    }
+}
+
+impl View for NoteList {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                if let Some(index) = self.index_at(center.y) {
+                    bus.push_back(Event::Select(EntryId::OpenNote(self.notes[index].name.clone())));
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::HoldFingerShort(center, _)) if self.rect.includes(center) => {
+                if let Some(index) = self.index_at(center.y) {
+                    bus.push_back(Event::ToggleNoteMenu(self.row_rect(index), index));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let per_page = self.rows_per_page();
+        let start = self.current_page * per_page;
+
+        for (index, note) in self.notes.iter().enumerate().skip(start).take(per_page) {
+            let rect = self.row_rect(index);
+            let date = if note.pinned {
+                format!("Pinned · {}", note.modified.format("%-d %b"))
+            } else {
+                note.modified.format("%-d %b %Y").to_string()
+            };
+            let excerpt = match note.kind {
+                NoteKind::Handwritten => "Handwritten",
+                NoteKind::Text => note.excerpt.as_str(),
+            };
+
+            let font = font_from_style(fonts, &MD_SIZE, dpi);
+            let padding = font.em() as i32;
+            let width = rect.width() as i32 - 2 * padding;
+            let date_plan = font.plan(&date, None, None);
+            let baseline = rect.min.y + rect.height() as i32 / 2 - font.x_heights.0 as i32 / 2;
+            font.render(fb, GRAY08, &date_plan, pt!(rect.max.x - padding - date_plan.width, baseline));
+            let plan = font.plan(excerpt, Some(width), None);
+            font.render(fb, GRAY08, &plan, pt!(rect.min.x + padding, rect.max.y - padding / 2));
+
+            let font = font_from_style(fonts, if note.pinned { &BOLD_STYLE } else { &NORMAL_STYLE }, dpi);
+            let plan = font.plan(&note.title, Some(width - date_plan.width - padding), None);
+            font.render(fb, BLACK, &plan, pt!(rect.min.x + padding, baseline));
+
+            fb.draw_rectangle(&rect![rect.min.x + padding, rect.max.y - thickness,
+                                     rect.max.x - padding, rect.max.y],
+                              GRAY12);
+        }
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.rect = rect;
+        self.current_page = self.current_page.min(self.pages_count() - 1);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -1,18 +1,98 @@
 use std::thread;
 use std::time::Duration;
+use std::collections::VecDeque;
+use chrono::{Local, DateTime};
 use crate::device::CURRENT_DEVICE;
 use crate::framebuffer::{Framebuffer, UpdateMode};
 use crate::geom::{Rectangle, CornerSpec, BorderSpec};
-use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE};
 use crate::color::{BLACK, WHITE, TEXT_NORMAL};
 use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId};
 use super::{SMALL_BAR_HEIGHT, THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::common::shift;
+use super::progress_bar::ProgressBar;
 use crate::gesture::GestureEvent;
 use crate::input::DeviceEvent;
 use crate::unit::scale_by_dpi;
 use crate::context::Context;
 
 const NOTIFICATION_CLOSE_DELAY: Duration = Duration::from_secs(4);
+// Leave more time to the user when there's an action to trigger.
+const ACTION_CLOSE_DELAY: Duration = Duration::from_secs(8);
+// Number of notifications that can be displayed at once.
+const SLOTS_COUNT: usize = 6;
+const HISTORY_SIZE: usize = 32;
+
+pub struct NotificationRecord {
+    pub text: String,
+    pub time: DateTime<Local>,
+}
+
+// Assigns the screen slots of the displayed notifications, and remembers
+// the recent messages.
+pub struct NotificationQueue {
+    slots: [Option<Id>; SLOTS_COUNT],
+    next: usize,
+    history: VecDeque<NotificationRecord>,
+    unread: usize,
+}
+
+impl Default for NotificationQueue {
+    fn default() -> Self {
+        NotificationQueue {
+            slots: [None; SLOTS_COUNT],
+            next: 0,
+            history: VecDeque::new(),
+            unread: 0,
+        }
+    }
+}
+
+impl NotificationQueue {
+    // Returns the first free slot. When every slot is taken, the slots are
+    // reused in a round-robin fashion.
+    fn acquire(&mut self, id: Id) -> u8 {
+        let index = self.slots.iter().position(Option::is_none)
+                        .unwrap_or(self.next % SLOTS_COUNT);
+        self.slots[index] = Some(id);
+        self.next = index + 1;
+        index as u8
+    }
+
+    pub fn release(&mut self, id: Id) {
+        for slot in self.slots.iter_mut() {
+            if *slot == Some(id) {
+                *slot = None;
+            }
+        }
+    }
+
+    fn record(&mut self, text: &str) {
+        if self.history.len() >= HISTORY_SIZE {
+            self.history.pop_back();
+        }
+        self.history.push_front(NotificationRecord { text: text.to_string(), time: Local::now() });
+        self.unread = (self.unread + 1).min(self.history.len());
+    }
+
+    // The most recent records come first.
+    pub fn history(&self) -> &VecDeque<NotificationRecord> {
+        &self.history
+    }
+
+    pub fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.unread = 0;
+    }
+}
 
 pub struct Notification {
     id: Id,
@@ -22,20 +102,49 @@ pub struct Notification {
     max_width: i32,
     index: u8,
     view_id: ViewId,
+    action: Option<(String, Event)>,
 }
 
 impl Notification {
     pub fn new(text: String, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        Notification::message(text, None, NOTIFICATION_CLOSE_DELAY, hub, rq, context)
+    }
+
+    // Tapping the notification sends `event` and closes it.
+    pub fn with_action(text: String, label: String, event: Event, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        Notification::message(text, Some((label, event)), ACTION_CLOSE_DELAY, hub, rq, context)
+    }
+
+    // Like `with_action`, but closes after `delay`.
+    pub fn with_timed_action(text: String, label: String, event: Event, delay: Duration, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        Notification::message(text, Some((label, event)), delay, hub, rq, context)
+    }
+
+    fn message(text: String, action: Option<(String, Event)>, delay: Duration, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Notification {
         let id = ID_FEEDER.next();
         let view_id = ViewId::MessageNotif(id);
         let hub2 = hub.clone();
-        let index = context.notification_index;
 
         thread::spawn(move || {
-            thread::sleep(NOTIFICATION_CLOSE_DELAY);
+            thread::sleep(delay);
             hub2.send(Event::Close(view_id)).ok();
         });
 
+        context.notifications.record(&text);
+        hub.send(Event::NotificationsChanged).ok();
+
+        Notification::build(id, view_id, text, false, action, rq, context)
+    }
+
+    // A notification with a progress bar, that stays open until it's closed.
+    // Its progress is updated through `Event::TaskProgress`.
+    pub fn with_progress(view_id: ViewId, text: String, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        Notification::build(ID_FEEDER.next(), view_id, text, true, None, rq, context)
+    }
+
+    fn build(id: Id, view_id: ViewId, text: String, with_progress: bool, action: Option<(String, Event)>, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        let index = context.notifications.acquire(id);
+
         let dpi = CURRENT_DEVICE.dpi;
         let (width, _) = context.display.dims;
         let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
@@ -44,10 +153,19 @@ impl Notification {
         let x_height = font.x_heights.0 as i32;
         let padding = font.em() as i32;
 
-        let max_message_width = width as i32 - 5 * padding;
-        let plan = font.plan(&text, Some(max_message_width), None);
+        let action_width = action.as_ref().map_or(0, |(label, _)| {
+            let font = font_from_style(&mut context.fonts, &BOLD_STYLE, dpi);
+            font.plan(label, None, None).width + padding
+        });
 
-        let dialog_width = plan.width + 3 * padding;
+        let max_message_width = width as i32 - 5 * padding - action_width;
+        let plan = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi)
+                       .plan(&text, Some(max_message_width), None);
+
+        let mut dialog_width = plan.width + action_width + 3 * padding;
+        if with_progress {
+            dialog_width = dialog_width.max(width as i32 / 2);
+        }
         let dialog_height = 7 * x_height;
 
         let side = (index / 3) % 2;
@@ -62,24 +180,44 @@ impl Notification {
                          dx + dialog_width, dy + dialog_height];
 
         rq.add(RenderData::new(id, rect, UpdateMode::Gui));
-        context.notification_index = index.wrapping_add(1);
+
+        let mut children = Vec::new();
+        if with_progress {
+            let bar_rect = rect![rect.min.x + padding, rect.max.y - 3 * x_height,
+                                 rect.max.x - padding, rect.max.y - x_height];
+            children.push(Box::new(ProgressBar::new(bar_rect)) as Box<dyn View>);
+        }
 
         Notification {
             id,
             rect,
-            children: Vec::new(),
+            children,
             text,
             max_width: max_message_width,
             index,
             view_id,
+            action,
+        }
+    }
+
+    pub fn set_progress(&mut self, done: usize, total: usize, rq: &mut RenderQueue) {
+        if let Some(progress_bar) = self.children.first_mut()
+                                        .and_then(|child| child.downcast_mut::<ProgressBar>()) {
+            progress_bar.update(done, total, rq);
         }
     }
 }
 
 impl View for Notification {
-    fn handle_event(&mut self, evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
         match *evt {
-            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => true,
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                if let Some((_, event)) = self.action.take() {
+                    bus.push_back(event);
+                    hub.send(Event::Close(self.view_id)).ok();
+                }
+                true
+            },
             Event::Gesture(GestureEvent::Swipe { start, .. }) if self.rect.includes(start) => true,
             Event::Device(DeviceEvent::Finger { position, .. }) if self.rect.includes(position) => true,
             _ => false,
@@ -98,15 +236,33 @@ impl View for Notification {
                                                             color: BLACK },
                                               &WHITE);
 
+        let action_plan = self.action.as_ref().map(|(label, _)| {
+            let font = font_from_style(fonts, &BOLD_STYLE, dpi);
+            font.plan(label, None, None)
+        });
+
         let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
         let plan = font.plan(&self.text, Some(self.max_width), None);
         let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let action_width = action_plan.as_ref().map_or(0, |plan| plan.width + padding);
+
+        let dx = (self.rect.width() as i32 - plan.width - action_width) / 2;
+        let mut dy = (self.rect.height() as i32 - x_height) / 2;
+
+        if !self.children.is_empty() {
+            dy += x_height;
+        }
 
-        let dx = (self.rect.width() as i32 - plan.width) as i32 / 2;
-        let dy = (self.rect.height() as i32 - x_height) / 2;
         let pt = pt!(self.rect.min.x + dx, self.rect.max.y - dy);
 
         font.render(fb, TEXT_NORMAL[1], &plan, pt);
+
+        if let Some(action_plan) = action_plan {
+            let pt = pt!(pt.x + plan.width + padding, pt.y);
+            let font = font_from_style(fonts, &BOLD_STYLE, dpi);
+            font.render(fb, TEXT_NORMAL[1], &action_plan, pt);
+        }
     }
 
     fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
@@ -129,6 +285,10 @@ impl View for Notification {
         let dy = small_height + padding + (self.index % 3) as i32 * (dialog_height + padding);
         let rect = rect![dx, dy,
                          dx + dialog_width, dy + dialog_height];
+        let delta = rect.min - self.rect.min;
+        for child in self.children.iter_mut() {
+            shift(child.as_mut(), delta);
+        }
         self.rect = rect;
     }
 
@@ -0,0 +1,96 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use super::{View, ViewId, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData};
+use crate::gesture::GestureEvent;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::color::{BLACK, WHITE};
+use crate::geom::{Rectangle, CornerSpec};
+use crate::context::Context;
+
+// Shows the number of unread notifications. Tapping it toggles the history.
+pub struct NotificationBadge {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    unread: usize,
+}
+
+impl NotificationBadge {
+    pub fn new(rect: Rectangle, context: &mut Context) -> NotificationBadge {
+        NotificationBadge {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            unread: context.notifications.unread(),
+        }
+    }
+
+    pub fn update(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        let unread = context.notifications.unread();
+        if self.unread != unread {
+            self.unread = unread;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+}
+
+impl View for NotificationBadge {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::NotificationsChanged => {
+                self.update(rq, context);
+                false
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::ToggleNear(ViewId::NotificationsMenu, self.rect));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        if self.unread == 0 {
+            return;
+        }
+
+        let dpi = CURRENT_DEVICE.dpi;
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let text = if self.unread > 9 { "9+".to_string() } else { self.unread.to_string() };
+        let plan = font.plan(&text, None, None);
+        let x_height = font.x_heights.0 as i32;
+        let badge_height = 3 * x_height;
+        let badge_width = (plan.width + 2 * x_height).max(badge_height);
+        let center = self.rect.center();
+        let badge_rect = rect![center.x - badge_width / 2, center.y - badge_height / 2,
+                               center.x + badge_width - badge_width / 2,
+                               center.y + badge_height - badge_height / 2];
+
+        fb.draw_rounded_rectangle(&badge_rect, &CornerSpec::Uniform(badge_height / 2), BLACK);
+
+        let pt = pt!(center.x - plan.width / 2, center.y + x_height / 2);
+        font.render(fb, WHITE, &plan, pt);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -1,4 +1,5 @@
 use crate::geom::{Rectangle, Point, Vec2};
+use log::{debug, warn};
 
 
 const NULL_RECT : Rectangle = Rectangle {
@@ -103,3 +104,199 @@ impl Position {
         }
     }
 }
+
+// Places the children, in order, in the space left by the previous ones.
+pub fn compute_sizes(outer: Rectangle, positions: &[Position]) -> Vec<Rectangle> {
+    let mut sizes = Vec::new();
+    let full_size = pt!(outer.width() as i32, outer.height() as i32);
+
+    let mut availabilities = Vec::new();
+    availabilities.push(outer);
+
+    for (index, Position{pack, margin, align, valign}) in positions.iter().enumerate() {
+        if availabilities.is_empty() {
+            debug!("** No more space available **");
+            break;
+        }
+        debug!("Packed — computing size in {:?}", availabilities);
+        debug!("Packed — ||- placing {:?} (margin {:?}, align {:?}, valign {:?})", pack, margin, align, valign);
+
+        let outer_h_margin = match align {
+            Align::Left(h) | Align::Right(h) => *h,
+            _ => 0,
+        };
+        let outer_v_margin = match valign {
+            VAlign::Bottom(v) | VAlign::Top(v) => *v,
+            _ => 0,
+        };
+        let outter_margin = rect!(pt!(outer_h_margin, outer_v_margin), pt!(- outer_h_margin, - outer_v_margin));
+        let first = availabilities.first().unwrap();
+        let mut largest = (0, *first);
+        let mut highest = (0, *first);
+        let mut leftmost = (0, *first);
+        let mut rightmost = (0, *first);
+        let mut topmost = (0, *first);
+        let mut bottommost = (0, *first);
+        for (index, rect) in availabilities.iter().enumerate() {
+            if largest.1.width() + 2 * outer_h_margin.try_into().unwrap_or(0) < rect.width() {
+                largest.0 = index;
+                largest.1 = *rect + outter_margin;
+            }
+            if highest.1.height() + 2 * outer_v_margin.try_into().unwrap_or(0) < rect.height() {
+                highest.0 = index;
+                highest.1 = *rect + outter_margin;
+            }
+            if leftmost.1.min.x < rect.min.x + outer_h_margin {
+                leftmost.0 = index;
+                leftmost.1 = *rect + outter_margin;
+            }
+            if rightmost.1.max.x < rect.max.x - outer_h_margin {
+                rightmost.0 = index;
+                rightmost.1 = *rect + outter_margin
+            }
+            if topmost.1.min.y < rect.min.y + outer_v_margin {
+                topmost.0 = index;
+                topmost.1 = *rect + outter_margin;
+            }
+            if bottommost.1.max.y < rect.max.y - outer_v_margin {
+                bottommost.0 = index;
+                bottommost.1 = *rect + outter_margin;
+            }
+        }
+
+        let mut rect_into = match align {
+            Align::Left(_) => (leftmost.0, leftmost.1),
+            Align::Right(_) => (rightmost.0, rightmost.1),
+            Align::Center => largest,
+        };
+        if let Pack::Fixed(size) = pack {
+            if size.lt(pt!(rect_into.1.width() as i32, rect_into.1.height() as i32)) {
+                rect_into = largest;
+            }
+        }
+
+        let size = match pack {
+            Pack::Fixed(size) if size.le(pt!(rect_into.1.width() as i32, rect_into.1.height() as i32)) => {
+                *size
+            },
+            Pack::Fixed(size) => {
+                let limited_pt = size.min(pt!(rect_into.1.width() as i32, rect_into.1.height() as i32));
+                warn!("Required space ({:?}) unavailable for packed child {index}. \
+                       Limiting to {:?}", size, limited_pt);
+                limited_pt
+            },
+            Pack::Percent(pc) => Point::from(full_size * *pc),
+            Pack::Fill => pt!(rect_into.1.width() as i32, rect_into.1.height() as i32),
+            Pack::HFill(h) if *h <= rect_into.1.height() => pt!(rect_into.1.width() as i32, *h as i32),
+            Pack::HFill(_) => pt!(rect_into.1.width() as i32, rect_into.1.height() as i32),
+            Pack::VFill(w) if *w <= rect_into.1.width() => pt!(*w as i32, rect_into.1.height() as i32),
+            Pack::VFill(_) => pt!(rect_into.1.width() as i32, rect_into.1.height() as i32),
+        };
+
+        let min_x = match align {
+            Align::Left(_) => rect_into.1.min.x,
+            Align::Right(_) => rect_into.1.max.x - size.x,
+            Align::Center => rect_into.1.min.x + rect_into.1.width() as i32 / 2 - size.x / 2,
+        };
+        let min_y = match valign {
+            VAlign::Top(_) => rect_into.1.min.y,
+            VAlign::Bottom(_) => rect_into.1.max.y - size.y,
+            VAlign::Center => rect_into.1.min.y + rect_into.1.height() as i32 / 2 - size.y / 2,
+        };
+
+        // Finally compute children rect
+        let rect = rect!(pt!(min_x, min_y), pt!(min_x, min_y) + size) - *margin;
+
+        // Update availabilities
+        let mut cutted_availability = Vec::new();
+        let original_availability = availabilities[rect_into.0];
+        match align {
+            Align::Left(_) => cutted_availability.push(original_availability + rect!(pt!(size.x + 2 * outer_h_margin, 0), pt!(0, 0))),
+            Align::Right(_) => cutted_availability.push(original_availability + rect!(pt!(0, 0), pt!(- (size.x + 2 * outer_h_margin), 0))),
+            Align::Center => {
+                cutted_availability.push(rect!(original_availability.min, pt!(rect.min.x, original_availability.max.y)));
+                cutted_availability.push(rect!(pt!(rect.max.x, original_availability.min.y), original_availability.max));
+            },
+        }
+        match valign {
+            VAlign::Top(_) => cutted_availability.push(original_availability + rect!(pt!(0, size.y + 2 * outer_v_margin), pt!(0, 0))),
+            VAlign::Bottom(_) => cutted_availability.push(original_availability + rect!(pt!(0, 0), pt!(0, - (size.y + 2 * outer_v_margin)))),
+            VAlign::Center => {
+                cutted_availability.push(rect!(pt!(rect.min.x, original_availability.min.y), pt!(rect.max.x, rect.min.y)));
+                cutted_availability.push(rect!(pt!(rect.min.x, rect.max.y), pt!(rect.max.x, original_availability.max.y)));
+            },
+        };
+
+        availabilities.remove(rect_into.0);
+        for rect in cutted_availability {
+            if 0 < rect.width() && 0 < rect.height() {
+                availabilities.push(rect);
+            }
+        }
+
+
+        debug!("Packed — || ** found {:?}", rect);
+        // push computed size
+        sizes.push(rect);
+    }
+
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xoshiro::Xoroshiro128Plus;
+
+    const SEEDS: u64 = 512;
+
+    fn random(rng: &mut Xoroshiro128Plus, max: u32) -> i32 {
+        (rng.next_u32() % (max + 1)) as i32
+    }
+
+    fn random_position(rng: &mut Xoroshiro128Plus, outer: &Rectangle) -> Position {
+        let (width, height) = (outer.width(), outer.height());
+        let pack = match rng.next_u32() % 5 {
+            0 => Pack::Fixed(pt!(1 + random(rng, width), 1 + random(rng, height))),
+            1 => Pack::Percent(Vec2::new(random(rng, 100) as f32 / 100.0, random(rng, 100) as f32 / 100.0)),
+            2 => Pack::Fill,
+            3 => Pack::HFill(1 + random(rng, height) as u32),
+            _ => Pack::VFill(1 + random(rng, width) as u32),
+        };
+        let margin = rect![random(rng, 4), random(rng, 4), random(rng, 4), random(rng, 4)];
+        // The views don't use outer margins.
+        let align = match rng.next_u32() % 3 {
+            0 => Align::Left(0),
+            1 => Align::Right(0),
+            _ => Align::Center,
+        };
+        let valign = match rng.next_u32() % 3 {
+            0 => VAlign::Top(0),
+            1 => VAlign::Bottom(0),
+            _ => VAlign::Center,
+        };
+        Position { pack, margin, align, valign }
+    }
+
+    fn random_layout(seed: u64) -> (Rectangle, Vec<Position>) {
+        let mut rng = Xoroshiro128Plus::seed_from_u64(seed);
+        let (x, y) = (random(&mut rng, 200), random(&mut rng, 200));
+        let outer = rect![x, y, x + 40 + random(&mut rng, 1800), y + 40 + random(&mut rng, 1800)];
+        let count = 1 + rng.next_u32() % 10;
+        let positions = (0..count).map(|_| random_position(&mut rng, &outer)).collect();
+        (outer, positions)
+    }
+
+    // The children can overlap each other, or leave the outer rectangle, e.g. with percents
+    // above the available space: only the stability of the placement is checked.
+    #[test]
+    fn test_random_layouts() {
+        for seed in 0..SEEDS {
+            let (outer, positions) = random_layout(seed);
+            let sizes = compute_sizes(outer, &positions);
+            assert!(sizes.len() <= positions.len());
+            assert_eq!(sizes, compute_sizes(outer, &positions), "seed {}", seed);
+        }
+    }
+}
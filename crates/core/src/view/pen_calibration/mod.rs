@@ -0,0 +1,216 @@
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Point, Rectangle};
+use crate::input::{DeviceEvent, FingerStatus};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER};
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, DISPLAY_STYLE};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+
+const MESSAGE: &str = "Tap the center of the cross\n\
+                       with the pen, as precisely\n\
+                       as possible.";
+// The positions of the targets, relative to the screen's dimensions.
+const TARGETS: [(f32, f32); 5] = [(0.15, 0.15), (0.85, 0.15), (0.5, 0.5), (0.15, 0.85), (0.85, 0.85)];
+// The scales closer to 1 than this are attributed to the imprecision of the taps.
+const SCALE_TOLERANCE: f32 = 0.02;
+const CROSS_SIZE: f32 = 8.0;
+const CROSS_THICKNESS: f32 = 1.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Calibration {
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+}
+
+// Fits the affine map from the touched coordinates to the targeted ones, for a single axis.
+// Returns its scale and offset.
+fn fit_axis(samples: &[(f32, f32)]) -> (f32, f32) {
+    let n = samples.len() as f32;
+    let mean_t = samples.iter().map(|(t, _)| t).sum::<f32>() / n;
+    let mean_p = samples.iter().map(|(_, p)| p).sum::<f32>() / n;
+    let var_p = samples.iter().map(|(_, p)| (p - mean_p).powi(2)).sum::<f32>();
+    let cov = samples.iter().map(|(t, p)| (t - mean_t) * (p - mean_p)).sum::<f32>();
+    let scale = if var_p > 0.0 { cov / var_p } else { 1.0 };
+    if (scale - 1.0).abs() <= SCALE_TOLERANCE {
+        (1.0, mean_t - mean_p)
+    } else {
+        (scale, mean_t - scale * mean_p)
+    }
+}
+
+// Computes the correction from pairs of targeted and touched points.
+pub fn calibrate(samples: &[(Point, Point)]) -> Option<Calibration> {
+    if samples.is_empty() {
+        return None;
+    }
+    let xs: Vec<(f32, f32)> = samples.iter().map(|(t, p)| (t.x as f32, p.x as f32)).collect();
+    let ys: Vec<(f32, f32)> = samples.iter().map(|(t, p)| (t.y as f32, p.y as f32)).collect();
+    let (scale_x, offset_x) = fit_axis(&xs);
+    let (scale_y, offset_y) = fit_axis(&ys);
+    Some(Calibration {
+        offset_x: offset_x.round() as i32,
+        offset_y: offset_y.round() as i32,
+        scale_x,
+        scale_y,
+    })
+}
+
+pub struct PenCalibration {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    // The positions reported since the pen touched the screen.
+    positions: Vec<Point>,
+    samples: Vec<(Point, Point)>,
+    result: Option<Calibration>,
+}
+
+impl PenCalibration {
+    pub fn new(rect: Rectangle, rq: &mut RenderQueue, _context: &mut Context) -> PenCalibration {
+        let id = ID_FEEDER.next();
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+        PenCalibration {
+            id,
+            rect,
+            children: Vec::new(),
+            positions: Vec::new(),
+            samples: Vec::new(),
+            result: None,
+        }
+    }
+
+    fn target(&self, index: usize) -> Point {
+        let (fx, fy) = TARGETS[index];
+        self.rect.min + pt!((fx * self.rect.width() as f32) as i32,
+                            (fy * self.rect.height() as f32) as i32)
+    }
+}
+
+impl View for PenCalibration {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Device(DeviceEvent::Finger { status, position, .. }) => {
+                if self.result.is_some() {
+                    if status == FingerStatus::Up {
+                        hub.send(Event::Back).ok();
+                    }
+                    return true;
+                }
+
+                self.positions.push(position);
+
+                if status == FingerStatus::Up {
+                    // A short stroke is as good as a tap.
+                    let count = self.positions.len() as i32;
+                    let sum = self.positions.drain(..).fold(pt!(0, 0), |sum, pt| sum + pt);
+                    let touched = pt!(sum.x / count, sum.y / count);
+                    self.samples.push((self.target(self.samples.len()), touched));
+
+                    if self.samples.len() == TARGETS.len() {
+                        self.result = calibrate(&self.samples);
+                        if let Some(calibration) = self.result {
+                            let pen = &mut context.settings.sketch.pen;
+                            pen.offset_x = calibration.offset_x;
+                            pen.offset_y = calibration.offset_y;
+                            pen.scale_x = calibration.scale_x;
+                            pen.scale_y = calibration.scale_y;
+                        }
+                    }
+
+                    rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                }
+
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let width = self.rect.width() as i32;
+        let height = self.rect.height() as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let (title, message) = if let Some(c) = self.result.as_ref() {
+            ("Done".to_string(),
+             format!("Offset: {}, {}.\nScale: {:.3}, {:.3}.\nTap to close.",
+                     c.offset_x, c.offset_y, c.scale_x, c.scale_y))
+        } else {
+            (format!("{} / {}", self.samples.len() + 1, TARGETS.len()), MESSAGE.to_string())
+        };
+
+        let font = font_from_style(fonts, &DISPLAY_STYLE, dpi);
+        let plan = font.plan(&title, None, Some(&["lnum".to_string()]));
+        let dx = (width - plan.width) / 2;
+        let mut dy = (height - font.x_heights.1 as i32) / 3;
+        font.render(fb, BLACK, &plan, self.rect.min + pt!(dx, dy));
+
+        dy += 4 * (font.x_heights.1 as i32) / 3;
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+
+        for line in message.lines() {
+            let plan = font.plan(line, None, None);
+            let dx = (width - plan.width) / 2;
+            font.render(fb, BLACK, &plan, self.rect.min + pt!(dx, dy));
+            dy += 3 * font.x_heights.0 as i32;
+        }
+
+        if self.result.is_none() {
+            let center = self.target(self.samples.len());
+            let size = scale_by_dpi(CROSS_SIZE, dpi) as i32;
+            let thickness = (scale_by_dpi(CROSS_THICKNESS, dpi) as i32).max(1);
+            fb.draw_rectangle(&rect![center.x - size, center.y - thickness / 2,
+                                     center.x + size + 1, center.y - thickness / 2 + thickness], BLACK);
+            fb.draw_rectangle(&rect![center.x - thickness / 2, center.y - size,
+                                     center.x - thickness / 2 + thickness, center.y + size + 1], BLACK);
+        }
+    }
+
+    fn might_rotate(&self) -> bool {
+        false
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibrate() {
+        let shifted = [(pt!(100, 100), pt!(96, 103)), (pt!(500, 100), pt!(495, 102)),
+                       (pt!(300, 400), pt!(296, 402))];
+        assert_eq!(calibrate(&shifted), Some(Calibration { offset_x: 4, offset_y: -2, scale_x: 1.0, scale_y: 1.0 }));
+        let scaled = [(pt!(100, 100), pt!(90, 100)), (pt!(900, 900), pt!(810, 900))];
+        let c = calibrate(&scaled).unwrap();
+        assert!((c.scale_x - 1.0 / 0.9).abs() < 1e-3);
+        assert_eq!((c.offset_x, c.offset_y, c.scale_y), (0, 0, 1.0));
+        assert_eq!(calibrate(&[]), None);
+    }
+}
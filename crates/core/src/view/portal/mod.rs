@@ -0,0 +1,379 @@
+mod page_view;
+
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, halves};
+use crate::gesture::GestureEvent;
+use crate::portal::{PortalAction, PortalPage, PortalSession};
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::menu::Menu;
+use crate::view::keyboard::Keyboard;
+use crate::view::named_input::NamedInput;
+use crate::view::modal::{Modal, ModalKind};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::common::{locate, locate_by_id};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::page_view::PageView;
+
+const DEFAULT_TITLE: &str = "Network Sign-In";
+
+// The action being filled, and the values entered so far.
+struct PendingAction {
+    action: PortalAction,
+    values: Vec<(String, String)>,
+    prompted: usize,
+}
+
+// Shows the sign-in page of a captive portal: its text, and its forms and links as buttons.
+pub struct Portal {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    session: Option<PortalSession>,
+    page: Option<PortalPage>,
+    pending: Option<PendingAction>,
+}
+
+impl Portal {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Portal {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  DEFAULT_TITLE.to_string(),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let page_view = PageView::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                            rect.max.x, rect.max.y - small_height - small_thickness],
+                                      context);
+        children.push(Box::new(page_view) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        // The helper answers with the sign-in page, or tells that we're already online.
+        let session = PortalSession::new(hub).map_err(|e| {
+            hub.send(Event::Notify(format!("Can't reach the portal: {:#}.", e))).ok();
+        }).ok();
+
+        let mut portal = Portal {
+            id,
+            rect,
+            children,
+            session,
+            page: None,
+            pending: None,
+        };
+
+        portal.update_buttons(&mut RenderQueue::new());
+        portal
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let button_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                                rect.max.x - padding, rect.max.y - padding];
+        let button = Button::new(button_rect, Event::Select(EntryId::ReloadPortal),
+                                 "Reload".to_string()).disabled(self.page.is_none());
+        self.children.insert(5, Box::new(button) as Box<dyn View>);
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn update_page(&mut self, page: PortalPage, rq: &mut RenderQueue) {
+        let title = if page.title.is_empty() { DEFAULT_TITLE.to_string() } else { page.title.clone() };
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title, rq);
+        }
+        if let Some(page_view) = self.children[2].downcast_mut::<PageView>() {
+            page_view.update(Some(page.clone()), rq);
+        }
+        self.page = Some(page);
+        self.update_buttons(rq);
+    }
+
+    fn send(&mut self, action: &PortalAction, values: &[(String, String)], hub: &Hub) {
+        if let Some(session) = self.session.as_mut() {
+            if let Err(e) = session.send(action, values) {
+                hub.send(Event::Notify(format!("Can't send the request: {:#}.", e))).ok();
+            }
+        }
+    }
+
+    // Asks for the next text field of the pending action, or sends it when they're all filled.
+    fn next_input(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let mut pending = if let Some(pending) = self.pending.take() {
+            pending
+        } else {
+            return;
+        };
+
+        if let Some((_, label)) = pending.action.inputs.get(pending.values.len()) {
+            pending.prompted += 1;
+            let named_input = NamedInput::new(label.clone(), ViewId::PortalInput,
+                                              ViewId::PortalInputInput, 16, context);
+            rq.add(RenderData::new(named_input.id(), *named_input.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::PortalInputInput))).ok();
+            self.children.push(Box::new(named_input) as Box<dyn View>);
+            self.pending = Some(pending);
+        } else {
+            self.send(&pending.action, &pending.values, hub);
+        }
+    }
+
+    fn close_input(&mut self, rq: &mut RenderQueue) {
+        if let Some(index) = locate_by_id(self, ViewId::PortalInput) {
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        }
+    }
+
+    fn toggle_keyboard(&mut self, enable: bool, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate::<Keyboard>(self) {
+            if enable {
+                return;
+            }
+
+            let mut rect = *self.child(index).rect();
+            rect.absorb(self.child(index-1).rect());
+            self.children.drain(index - 1 ..= index);
+            rq.add(RenderData::expose(rect, UpdateMode::Gui));
+            hub.send(Event::Focus(None)).ok();
+        } else {
+            if !enable {
+                return;
+            }
+
+            let dpi = CURRENT_DEVICE.dpi;
+            let (small_height, big_height) = (scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32,
+                                              scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32);
+            let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+            let (small_thickness, big_thickness) = halves(thickness);
+            let mut kb_rect = rect![self.rect.min.x,
+                                    self.rect.max.y - (small_height + 3 * big_height) + big_thickness,
+                                    self.rect.max.x,
+                                    self.rect.max.y - small_height - small_thickness];
+
+            let keyboard = Keyboard::new(&mut kb_rect, false, context);
+            let separator = Filler::new(rect![self.rect.min.x, kb_rect.min.y - thickness,
+                                              self.rect.max.x, kb_rect.min.y],
+                                        BLACK);
+            rq.add(RenderData::new(separator.id(), *separator.rect(), UpdateMode::Gui));
+            rq.add(RenderData::new(keyboard.id(), *keyboard.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(separator) as Box<dyn View>);
+            self.children.push(Box::new(keyboard) as Box<dyn View>);
+        }
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for Portal {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::PortalPage(ref page) => {
+                self.update_page(page.as_ref().clone(), rq);
+                true
+            },
+            Event::PortalOnline => {
+                hub.send(Event::Notify("Signed in.".to_string())).ok();
+                hub.send(Event::Back).ok();
+                true
+            },
+            Event::Select(EntryId::PortalAction(index)) => {
+                if self.pending.is_some() {
+                    return true;
+                }
+                if let Some(action) = self.page.as_ref().and_then(|page| page.actions.get(index)).cloned() {
+                    // The consent boxes are never checked on the user's behalf.
+                    if !action.consents.is_empty() {
+                        let labels = action.consents.iter()
+                                           .map(|(_, _, label)| label.as_str())
+                                           .collect::<Vec<&str>>().join(", ");
+                        let modal = Modal::new(ViewId::Modal, format!("Accept: {}?", labels),
+                                               ModalKind::Confirm(Event::Select(EntryId::AcceptPortalTerms(index))),
+                                               hub, rq, context);
+                        rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+                        self.children.push(Box::new(modal) as Box<dyn View>);
+                        return true;
+                    }
+                    self.pending = Some(PendingAction { action, values: Vec::new(), prompted: 0 });
+                    self.next_input(hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::AcceptPortalTerms(index)) => {
+                if self.pending.is_some() {
+                    return true;
+                }
+                if let Some(mut action) = self.page.as_ref().and_then(|page| page.actions.get(index)).cloned() {
+                    let consents = action.consents.drain(..)
+                                         .map(|(name, value, _)| (name, value))
+                                         .collect::<Vec<(String, String)>>();
+                    action.fields.extend(consents);
+                    self.pending = Some(PendingAction { action, values: Vec::new(), prompted: 0 });
+                    self.next_input(hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::ReloadPortal) => {
+                if let Some(page) = self.page.as_ref() {
+                    let action = PortalAction {
+                        method: "get".to_string(),
+                        url: page.url.clone(),
+                        .. Default::default()
+                    };
+                    self.send(&action, &[], hub);
+                }
+                true
+            },
+            Event::Submit(ViewId::PortalInputInput, ref text) => {
+                if let Some(pending) = self.pending.as_mut() {
+                    if let Some((name, _)) = pending.action.inputs.get(pending.values.len()) {
+                        pending.values.push((name.clone(), text.clone()));
+                    }
+                }
+                true
+            },
+            // The input is closed after being submitted, or to cancel the pending action.
+            Event::Close(ViewId::PortalInput) => {
+                self.close_input(rq);
+                let (submitted, remaining) = self.pending.as_ref().map_or((false, 0), |pending| {
+                    (pending.values.len() == pending.prompted,
+                     pending.action.inputs.len() - pending.values.len())
+                });
+                if !submitted || remaining == 0 {
+                    self.toggle_keyboard(false, hub, rq, context);
+                }
+                if submitted {
+                    self.next_input(hub, rq, context);
+                } else {
+                    self.pending = None;
+                }
+                true
+            },
+            Event::Focus(Some(ViewId::PortalInputInput)) => {
+                self.toggle_keyboard(true, hub, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.close_input(&mut RenderQueue::new());
+        self.toggle_keyboard(false, hub, &mut RenderQueue::new(), context);
+        self.pending = None;
+        self.children.retain(|child| !child.is::<Menu>());
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
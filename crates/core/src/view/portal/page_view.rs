@@ -0,0 +1,130 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::gesture::GestureEvent;
+use crate::portal::PortalPage;
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryId, Id, ID_FEEDER, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08};
+use crate::context::Context;
+
+// The text of the page, followed by one row per form or link.
+pub struct PageView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    page: Option<PortalPage>,
+    row_height: i32,
+}
+
+impl PageView {
+    pub fn new(rect: Rectangle, context: &mut Context) -> PageView {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        PageView {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            page: None,
+            row_height: 2 * font.line_height(),
+        }
+    }
+
+    pub fn update(&mut self, page: Option<PortalPage>, rq: &mut RenderQueue) {
+        self.page = page;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn actions_rect(&self) -> Rectangle {
+        let count = self.page.as_ref().map_or(0, |page| page.actions.len()) as i32;
+        let height = (count * self.row_height).min(self.rect.height() as i32 / 2);
+        rect![self.rect.min.x, self.rect.max.y - height,
+              self.rect.max.x, self.rect.max.y]
+    }
+}
+
+impl View for PageView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let actions_rect = self.actions_rect();
+                if actions_rect.includes(center) {
+                    let index = ((center.y - actions_rect.min.y) / self.row_height.max(1)) as usize;
+                    bus.push_back(Event::Select(EntryId::PortalAction(index)));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let page = if let Some(page) = self.page.as_ref() {
+            page
+        } else {
+            return;
+        };
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32;
+        let line_height = font.line_height();
+        let x_height = font.x_heights.0 as i32;
+        let max_width = self.rect.width() as i32 - 2 * padding;
+        let actions_rect = self.actions_rect();
+
+        let mut y = self.rect.min.y + padding;
+        for line in font.wrap(&page.text, max_width) {
+            if y + line_height > actions_rect.min.y - padding {
+                break;
+            }
+            y += line_height;
+            let plan = font.plan(&line, Some(max_width), None);
+            font.render(fb, BLACK, &plan, pt!(self.rect.min.x + padding, y));
+        }
+
+        for (index, action) in page.actions.iter().enumerate() {
+            let y = actions_rect.min.y + index as i32 * self.row_height;
+            if y + self.row_height > self.rect.max.y {
+                break;
+            }
+            fb.draw_rectangle(&rect![self.rect.min.x + padding, y,
+                                     self.rect.max.x - padding, y + thickness],
+                              GRAY08);
+            let label = if action.inputs.is_empty() && action.consents.is_empty() {
+                action.label.clone()
+            } else {
+                format!("{}…", action.label)
+            };
+            let plan = font.plan(&label, Some(max_width), None);
+            let dx = (max_width - plan.width) / 2;
+            let baseline = y + (self.row_height + x_height) / 2;
+            font.render(fb, BLACK, &plan, pt!(self.rect.min.x + padding + dx, baseline));
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+use crate::device::CURRENT_DEVICE;
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, THICKNESS_SMALL};
+use crate::color::{BLACK, WHITE, TEXT_NORMAL, PROGRESS_FULL, PROGRESS_EMPTY};
+use crate::font::{Fonts, font_from_style, SLIDER_VALUE};
+use crate::geom::{Rectangle, BorderSpec, CornerSpec, halves};
+use crate::context::Context;
+
+const PROGRESS_HEIGHT: f32 = 7.0;
+// Don't estimate the remaining time before this much of the task is done.
+const ETA_MIN_FRACTION: f32 = 0.02;
+
+// Keeps track of the advancement of a long-running task.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    done: usize,
+    total: usize,
+    started: Instant,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            done: 0,
+            total: 0,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Progress {
+    pub fn update(&mut self, done: usize, total: usize) {
+        self.done = done.min(total);
+        self.total = total;
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    // Extrapolates the remaining time from the average speed since the beginning.
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_after(self.started.elapsed())
+    }
+
+    fn eta_after(&self, elapsed: Duration) -> Option<Duration> {
+        let fraction = self.fraction();
+        if fraction < ETA_MIN_FRACTION {
+            return None;
+        }
+        Some(elapsed.mul_f32((1.0 - fraction) / fraction))
+    }
+
+    pub fn label(&self) -> String {
+        let percent = (100.0 * self.fraction()) as u8;
+        match self.eta() {
+            Some(eta) if self.done < self.total => format!("{}% · {}", percent, format_duration(eta)),
+            _ => format!("{}%", percent),
+        }
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{}s", secs.max(1))
+    } else if secs < 3600 {
+        format!("{}m", secs.div_ceil(60))
+    } else {
+        format!("{}h{:02}", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+pub struct ProgressBar {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    progress: Progress,
+    label: String,
+}
+
+impl ProgressBar {
+    pub fn new(rect: Rectangle) -> ProgressBar {
+        let progress = Progress::default();
+        let label = progress.label();
+        ProgressBar {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            progress,
+            label,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.progress.fraction()
+    }
+
+    pub fn update(&mut self, done: usize, total: usize, rq: &mut RenderQueue) {
+        let fraction = self.progress.fraction();
+        self.progress.update(done, total);
+        let label = self.progress.label();
+        if label != self.label || (fraction - self.progress.fraction()).abs() >= 0.01 {
+            self.label = label;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+}
+
+impl View for ProgressBar {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let progress_height = scale_by_dpi(PROGRESS_HEIGHT, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as u16;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let font = font_from_style(fonts, &SLIDER_VALUE, dpi);
+        let plan = font.plan(&self.label, None, None);
+        let padding = font.em() as i32;
+        let x_height = font.x_heights.0 as i32;
+
+        // The label is right-aligned, the bar takes the remaining space.
+        let x_max = self.rect.max.x - plan.width - padding;
+        let (small_mini_radius, _) = halves(progress_height);
+        let (small_padding, big_padding) = halves(self.rect.height() as i32 - progress_height);
+        let bar_rect = rect![self.rect.min.x, self.rect.min.y + small_padding,
+                             x_max.max(self.rect.min.x), self.rect.max.y - big_padding];
+        let x_offset = bar_rect.min.x + (self.fraction() * bar_rect.width() as f32) as i32;
+
+        fb.draw_rounded_rectangle_with_border(&bar_rect,
+                                              &CornerSpec::Uniform(small_mini_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &|x, _| if x < x_offset { PROGRESS_FULL }
+                                                      else { PROGRESS_EMPTY });
+
+        let dy = (self.rect.height() as i32 - x_height) / 2;
+        let pt = pt!(self.rect.max.x - plan.width, self.rect.max.y - dy);
+        font.render(fb, TEXT_NORMAL[1], &plan, pt);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta() {
+        let mut progress = Progress::default();
+        assert_eq!(progress.eta_after(Duration::from_secs(10)), None);
+        progress.update(1, 100);
+        assert_eq!(progress.eta_after(Duration::from_secs(10)), None);
+        progress.update(25, 100);
+        assert_eq!(progress.eta_after(Duration::from_secs(10)), Some(Duration::from_secs(30)));
+        progress.update(150, 100);
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(progress.eta_after(Duration::from_secs(10)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(200)), "1s");
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(61)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 5 * 60)), "3h05");
+    }
+}
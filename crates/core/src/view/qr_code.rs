@@ -0,0 +1,142 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::qr::QrCode;
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::label::Label;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The width of the quiet zone, in modules.
+const QUIET_ZONE: usize = 4;
+
+// Shows a QR code with a caption underneath. Tapping anywhere closes it.
+pub struct QrCodeView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    code: QrCode,
+    caption: String,
+}
+
+impl QrCodeView {
+    pub fn new(view_id: ViewId, code: QrCode, caption: String, context: &mut Context) -> QrCodeView {
+        let mut qr_code_view = QrCodeView {
+            id: ID_FEEDER.next(),
+            rect: Rectangle::default(),
+            children: Vec::new(),
+            view_id,
+            code,
+            caption,
+        };
+        qr_code_view.layout(context);
+        qr_code_view
+    }
+
+    fn layout(&mut self, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let line_height = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+            font.line_height()
+        };
+        let modules = (self.code.size() + 2 * QUIET_ZONE) as i32;
+        let module_size = (2 * width.min(height) as i32 / 3 / modules).max(1);
+        let side = modules * module_size;
+        let view_width = side + 2 * margin();
+        let view_height = side + line_height + 2 * margin();
+        let dx = (width as i32 - view_width) / 2;
+        let dy = (height as i32 - view_height) / 2;
+        self.rect = rect![dx, dy, dx + view_width, dy + view_height];
+
+        let label_rect = rect![self.rect.min.x + margin(), self.rect.max.y - margin() - line_height,
+                               self.rect.max.x - margin(), self.rect.max.y - margin()];
+        self.children = vec![Box::new(Label::new(label_rect, self.caption.clone(), Align::Center)) as Box<dyn View>];
+    }
+
+    fn code_rect(&self) -> Rectangle {
+        let side = self.rect.width() as i32 - 2 * margin();
+        rect![self.rect.min.x + margin(), self.rect.min.y + margin(),
+              self.rect.min.x + margin() + side, self.rect.min.y + margin() + side]
+    }
+}
+
+fn margin() -> i32 {
+    let dpi = CURRENT_DEVICE.dpi;
+    scale_by_dpi(THICKNESS_LARGE, dpi) as i32 + scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32
+}
+
+impl View for QrCodeView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(..)) => {
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let code_rect = self.code_rect();
+        let module_size = code_rect.width() as i32 / (self.code.size() + 2 * QUIET_ZONE) as i32;
+        let origin = code_rect.min + module_size * QUIET_ZONE as i32;
+
+        for y in 0..self.code.size() {
+            for x in 0..self.code.size() {
+                if self.code.is_dark(x, y) {
+                    let pt = origin + pt!(x as i32 * module_size, y as i32 * module_size);
+                    fb.draw_rectangle(&rect![pt, pt + module_size], BLACK);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.layout(context);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
@@ -0,0 +1,207 @@
+use std::thread;
+use std::path::Path;
+use std::time::Duration;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, EntryId};
+use crate::view::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, MD_AUTHOR};
+use crate::color::{BLACK, WHITE, TEXT_NORMAL};
+use crate::gesture::GestureEvent;
+use crate::geom::{Rectangle, CornerSpec, BorderSpec, CycleDir};
+use crate::document::{Location, Document};
+use crate::document::pdf::PdfOpener;
+use crate::metadata::Info;
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The selected book is opened when it hasn't changed for this long.
+const SWITCH_DELAY: Duration = Duration::from_millis(1500);
+
+// Shows the cover of the recent book that the reader is about to switch to.
+pub struct BookSwitcher {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    books: Vec<Info>,
+    index: usize,
+    cover: Option<Pixmap>,
+    // Each change of the selection postpones the switch.
+    token: Id,
+}
+
+impl BookSwitcher {
+    pub fn new(books: Vec<Info>, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> BookSwitcher {
+        let id = ID_FEEDER.next();
+        let rect = switcher_rect(context);
+        let index = if dir == CycleDir::Next { 0 } else { books.len() - 1 };
+        let mut switcher = BookSwitcher {
+            id,
+            rect,
+            children: Vec::new(),
+            books,
+            index,
+            cover: None,
+            token: id,
+        };
+        switcher.select(index, hub, context);
+        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+        switcher
+    }
+
+    pub fn token(&self) -> Id {
+        self.token
+    }
+
+    pub fn selected(&self) -> &Info {
+        &self.books[self.index]
+    }
+
+    pub fn cycle(&mut self, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let len = self.books.len();
+        let index = match dir {
+            CycleDir::Next => (self.index + 1) % len,
+            CycleDir::Previous => (self.index + len - 1) % len,
+        };
+        self.select(index, hub, context);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    fn select(&mut self, index: usize, hub: &Hub, context: &mut Context) {
+        self.index = index;
+        let preview_path = context.library.thumbnail_preview(&self.books[index].file.path);
+        let (tw, th) = cover_dims(&self.rect);
+        self.cover = load_cover(&preview_path, tw, th);
+        self.token = ID_FEEDER.next();
+        let token = self.token;
+        let hub2 = hub.clone();
+        thread::spawn(move || {
+            thread::sleep(SWITCH_DELAY);
+            hub2.send(Event::Select(EntryId::SwitchBook(token))).ok();
+        });
+    }
+}
+
+fn switcher_rect(context: &Context) -> Rectangle {
+    let (width, height) = context.display.dims;
+    let side = width.min(height) as i32;
+    let dialog_width = side / 2;
+    let dialog_height = 5 * side / 6;
+    let dx = (width as i32 - dialog_width) / 2;
+    let dy = (height as i32 - dialog_height) / 2;
+    rect![dx, dy, dx + dialog_width, dy + dialog_height]
+}
+
+// The cover takes the top of the dialog, the title and the author are below it.
+fn cover_dims(rect: &Rectangle) -> (i32, i32) {
+    let th = 2 * rect.height() as i32 / 3;
+    let tw = (3 * th / 4).min(rect.width() as i32 * 4 / 5);
+    (tw, th)
+}
+
+fn load_cover(path: &Path, tw: i32, th: i32) -> Option<Pixmap> {
+    if !path.exists() {
+        return None;
+    }
+    PdfOpener::new().and_then(|opener| {
+        opener.open(path)
+    }).and_then(|mut doc| {
+        doc.dims(0).and_then(|dims| {
+            let scale = (tw as f32 / dims.0).min(th as f32 / dims.1);
+            doc.pixmap(Location::Exact(0), scale)
+        })
+    }).map(|(pixmap, _)| pixmap)
+}
+
+impl View for BookSwitcher {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                hub.send(Event::Select(EntryId::SwitchBook(self.token))).ok();
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                hub.send(Event::Close(ViewId::BookSwitcher)).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let (tw, th) = cover_dims(&self.rect);
+        let cover_top = self.rect.min.y + padding;
+
+        if let Some(pixmap) = self.cover.as_ref() {
+            let dx = (self.rect.width() as i32 - pixmap.width as i32) / 2;
+            let dy = (th - pixmap.height as i32) / 2;
+            let pt = pt!(self.rect.min.x + dx, cover_top + dy);
+            fb.draw_pixmap(pixmap, pt);
+            if fb.inverted() {
+                let rect = pixmap.rect() + pt;
+                fb.invert_region(&rect);
+            }
+        } else {
+            let dx = (self.rect.width() as i32 - tw) / 2;
+            let rect = rect![self.rect.min.x + dx, cover_top,
+                             self.rect.min.x + dx + tw, cover_top + th];
+            fb.draw_rectangle_outline(&rect, &BorderSpec { thickness: border_thickness, color: BLACK });
+        }
+
+        let info = self.selected();
+        let max_width = self.rect.width() as i32 - 2 * padding;
+        let mut y = cover_top + th + padding + 2 * x_height;
+
+        let plan = font.plan(info.title(), Some(max_width), None);
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        font.render(fb, TEXT_NORMAL[1], &plan, pt!(self.rect.min.x + dx, y));
+        y += 3 * x_height;
+
+        let font = font_from_style(fonts, &MD_AUTHOR, dpi);
+        let text = format!("{} · {}/{}", info.author, self.index + 1, self.books.len());
+        let plan = font.plan(&text, Some(max_width), None);
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        font.render(fb, TEXT_NORMAL[1], &plan, pt!(self.rect.min.x + dx, y));
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::BookSwitcher)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Rectangle, CornerSpec, BorderSpec, LinearDir};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, Align};
+use crate::view::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use crate::view::button::Button;
+use crate::view::label::Label;
+use crate::view::key::KeyKind;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// The longest numbers that can be typed.
+const MAX_DIGITS: usize = 7;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Target {
+    Page,
+    Percent,
+    Chapter,
+}
+
+// A numeric keypad that submits a page number, a percentage or a chapter number
+// to the go to page input, in its syntax.
+pub struct GoToPad {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    target: Target,
+    digits: String,
+    pages_count: usize,
+}
+
+impl GoToPad {
+    pub fn new(pages_count: usize, has_chapters: bool, context: &mut Context) -> GoToPad {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32;
+        let key_height = 4 * font.x_heights.0 as i32;
+
+        let panel_width = 2 * width.min(height) as i32 / 3;
+        let panel_height = 6 * key_height + 8 * padding;
+        let dx = (width as i32 - panel_width) / 2;
+        let dy = (height as i32 - panel_height) / 2;
+        let rect = rect![dx, dy, dx + panel_width, dy + panel_height];
+
+        let mut y = rect.min.y + padding;
+        let rect_label = rect![rect.min.x + padding, y, rect.max.x - padding, y + key_height];
+        children.push(Box::new(Label::new(rect_label, String::new(), Align::Center)) as Box<dyn View>);
+        y += key_height + 2 * padding;
+
+        let key_width = (panel_width - 4 * padding) / 3;
+        let rows = [[("Page", KeyKind::Output('p')), ("Percent", KeyKind::Output('%')), ("Chapter", KeyKind::Output('#'))],
+                    [("1", KeyKind::Output('1')), ("2", KeyKind::Output('2')), ("3", KeyKind::Output('3'))],
+                    [("4", KeyKind::Output('4')), ("5", KeyKind::Output('5')), ("6", KeyKind::Output('6'))],
+                    [("7", KeyKind::Output('7')), ("8", KeyKind::Output('8')), ("9", KeyKind::Output('9'))],
+                    [("Erase", KeyKind::Delete(LinearDir::Backward)), ("0", KeyKind::Output('0')), ("Go", KeyKind::Return)]];
+        for row in rows {
+            let mut x = rect.min.x + padding;
+            for (text, kind) in row {
+                let rect_key = rect![x, y, x + key_width, y + key_height];
+                let button = Button::new(rect_key, Event::Key(kind), text.to_string())
+                                    .disabled(kind == KeyKind::Output('#') && !has_chapters);
+                children.push(Box::new(button) as Box<dyn View>);
+                x += key_width + padding;
+            }
+            y += key_height + padding;
+        }
+
+        let mut go_to_pad = GoToPad {
+            id,
+            rect,
+            children,
+            target: Target::Page,
+            digits: String::new(),
+            pages_count,
+        };
+        go_to_pad.update_label(&mut RenderQueue::new());
+        go_to_pad
+    }
+
+    fn update_label(&mut self, rq: &mut RenderQueue) {
+        let digits = if self.digits.is_empty() { "…" } else { &self.digits };
+        let text = match self.target {
+            Target::Page => format!("Page {} / {}", digits, self.pages_count),
+            Target::Percent => format!("{} %", digits),
+            Target::Chapter => format!("Chapter {}", digits),
+        };
+        if let Some(label) = self.children[0].downcast_mut::<Label>() {
+            label.update(&text, rq);
+        }
+    }
+
+    fn query(&self) -> Option<String> {
+        if self.digits.is_empty() {
+            return None;
+        }
+        Some(match self.target {
+            Target::Page => self.digits.clone(),
+            Target::Percent => format!("{}%", self.digits),
+            Target::Chapter => format!("#{}", self.digits),
+        })
+    }
+}
+
+impl View for GoToPad {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Key(KeyKind::Output(c)) => {
+                match c {
+                    'p' => self.target = Target::Page,
+                    '%' => self.target = Target::Percent,
+                    '#' => self.target = Target::Chapter,
+                    _ if c.is_ascii_digit() && self.digits.len() < MAX_DIGITS => {
+                        if self.digits == "0" {
+                            self.digits.clear();
+                        }
+                        self.digits.push(c);
+                    },
+                    _ => (),
+                }
+                self.update_label(rq);
+                true
+            },
+            Event::Key(KeyKind::Delete(..)) => {
+                self.digits.pop();
+                self.update_label(rq);
+                true
+            },
+            Event::Key(KeyKind::Return) => {
+                if let Some(query) = self.query() {
+                    bus.push_back(Event::Submit(ViewId::GoToPageInput, query));
+                    bus.push_back(Event::Close(ViewId::GoToPad));
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::GoToPad));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let delta = pt!((width as i32 - self.rect.width() as i32) / 2,
+                        (height as i32 - self.rect.height() as i32) / 2) - self.rect.min;
+        self.rect += delta;
+        for child in &mut self.children {
+            *child.rect_mut() += delta;
+        }
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::GoToPad)
+    }
+}
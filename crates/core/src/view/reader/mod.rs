@@ -4,24 +4,32 @@ mod results_bar;
 mod margin_cropper;
 mod chapter_label;
 mod results_label;
+mod skim;
+mod go_to;
+mod thumbnails;
+mod tone_picker;
+mod zoom_view;
+mod book_switcher;
 
 use std::thread;
+use std::time::{Duration, Instant};
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::atomic::Ordering as AtomicOrdering;
 use std::path::PathBuf;
 use std::io::prelude::*;
 use std::fs::OpenOptions;
 use std::collections::{VecDeque, BTreeMap};
+use std::ops::Range;
 use fxhash::{FxHashMap, FxHashSet};
-use chrono::Local;
+use chrono::{DateTime, Local};
 use regex::Regex;
 use septem::prelude::*;
 use septem::{Roman, Digit};
 use rand_core::RngCore;
 use crate::input::{DeviceEvent, FingerStatus, ButtonCode, ButtonStatus};
 use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
-use crate::view::{View, Event, AppCmd, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{View, Event, AppCmd, Hub, Bus, RenderQueue, RenderData, PageTurn};
 use crate::view::{ViewId, Id, ID_FEEDER, EntryKind, EntryId, SliderId};
 use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
 use crate::unit::{scale_by_dpi, mm_to_px};
@@ -34,36 +42,75 @@ use super::top_bar::TopBar;
 use self::tool_bar::ToolBar;
 use self::bottom_bar::BottomBar;
 use self::results_bar::ResultsBar;
-use crate::view::common::{locate, rlocate, locate_by_id};
-use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
+use self::skim::Skim;
+use self::go_to::GoToPad;
+use self::thumbnails::{Thumbnails, ThumbnailsPage};
+use self::tone_picker::TonePicker;
+use crate::tone::ToneCurve;
+use self::zoom_view::{ZoomView, ZoomSource};
+use self::book_switcher::BookSwitcher;
+use crate::view::common::{locate, rlocate, locate_by_id, update_progress, close_progress};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
 use crate::view::filler::Filler;
 use crate::view::named_input::NamedInput;
 use crate::view::search_bar::SearchBar;
 use crate::view::keyboard::Keyboard;
 use crate::view::menu::{Menu, MenuKind};
+use crate::view::list_picker::ListPicker;
 use crate::view::menu_entry::MenuEntry;
 use crate::view::notification::Notification;
-use crate::settings::{guess_frontlight, FinishedAction, SouthEastCornerAction, BottomRightGestureAction, SouthStripAction, WestStripAction, EastStripAction};
+use crate::view::end_of_book::EndOfBook;
+use crate::view::light_gauge::LightGauge;
+use crate::view::translation::TranslationView;
+use crate::language::{detect_language, sample_text, LANGUAGES};
+use crate::translation::{language_pair, translate_offline, translate_online, AUTO_LANGUAGE};
+use crate::handoff::Handoff;
+use crate::share::{Payload, SNAPSHOT_PATH, format_quote, share};
+use crate::speech::{self, Speaker, language_key, sentences};
+use crate::ocr::{self, OcrCache};
+use crate::view::sketch::PAGE_SNAPSHOT_PATH;
+use crate::view::home::shelf::set_cover;
+use crate::settings::{guess_frontlight, FinishedAction, TranslationBackend, SouthEastCornerAction, BottomRightGestureAction, LetterAction, SouthStripAction, WestStripAction, EastStripAction};
 use crate::settings::{DEFAULT_FONT_FAMILY, DEFAULT_TEXT_ALIGN, DEFAULT_LINE_HEIGHT, DEFAULT_MARGIN_WIDTH};
-use crate::settings::{HYPHEN_PENALTY, STRETCH_TOLERANCE};
+use crate::settings::{HYPHEN_PENALTY, STRETCH_TOLERANCE, ReaderSettings, NightImages};
 use crate::frontlight::LightLevels;
 use crate::gesture::GestureEvent;
-use crate::document::{Document, open, Location, TextLocation, BoundedText, Neighbors, BYTES_PER_PAGE};
-use crate::document::{TocEntry, SimpleTocEntry, TocLocation, toc_as_html, annotations_as_html, bookmarks_as_html};
+use crate::document::{Document, open_with_passwords, Location, TextLocation, BoundedText, Neighbors, BYTES_PER_PAGE};
+use crate::document::{Landmark, LandmarkKind};
+use crate::document::{TocEntry, SimpleTocEntry, TocLocation, toc_entry_at, toc_as_html, annotations_as_html, bookmarks_as_html};
 use crate::document::html::HtmlDocument;
+use crate::document::html::layout::Typography;
 use crate::metadata::{Info, FileInfo, ReaderInfo, Annotation, TextAlign, ZoomMode, ScrollMode, PageScheme};
-use crate::metadata::{Margin, CroppingMargins, make_query};
+use crate::metadata::{Margin, CroppingMargins, FocusSession, RefreshPolicy, Cover, make_query};
 use crate::metadata::{DEFAULT_CONTRAST_EXPONENT, DEFAULT_CONTRAST_GRAY};
 use crate::geom::{Point, Vec2, Rectangle, Boundary, CornerSpec, BorderSpec};
 use crate::geom::{Dir, DiagDir, CycleDir, LinearDir, Axis, Region, halves};
 use crate::color::{BLACK, WHITE};
+use crate::session::Session;
 use crate::context::Context;
 
+// The maximum number of words on each side of a selection kept as its context.
+const MAX_CONTEXT_WORDS: usize = 32;
 const HISTORY_SIZE: usize = 32;
+// The minimum number of pages crossed by a jump for the return chip to be shown.
+const RETURN_CHIP_MIN_PAGES: f64 = 2.0;
 const RECT_DIST_JITTER: f32 = 24.0;
+// Images covering a larger part of a fixed layout page aren't zoomed on tap.
+const IMAGE_MAX_PAGE_RATIO: f32 = 0.75;
 const ANNOTATION_DRIFT: u8 =  0x44;
 const HIGHLIGHT_DRIFT: u8 =  0x22;
 const MEM_SCHEME: &str = "mem:";
+// Longer lists of font families are shown in a list picker.
+const FONT_FAMILY_MENU_MAX_ENTRIES: usize = 16;
+// The maximum number of characters of a note shown in the notes menu.
+const NOTE_LABEL_LENGTH: usize = 32;
+// The time spent on a page is counted in the reading speed when within this range, in seconds.
+const READING_SECONDS_RANGE: std::ops::RangeInclusive<u64> = 5..=600;
+// The pages that would need more pixels are rendered in tiles, as they're shown.
+const MAX_PAGE_PIXELS: u64 = 8_000_000;
+const TILE_SIZE: i32 = 512;
+// The number of recent books that the two-finger swipes cycle through.
+const MAX_RECENT_BOOKS: usize = 8;
 
 pub struct Reader {
     id: Id,
@@ -71,10 +118,15 @@ pub struct Reader {
     children: Vec<Box<dyn View>>,
     doc: Arc<Mutex<Box<dyn Document>>>,
     cache: BTreeMap<usize, Resource>,                // Cached page pixmaps.
+    prerendered: Arc<Mutex<BTreeMap<usize, Resource>>>, // Pixmaps rendered in the background.
+    thumbnails: BTreeMap<usize, Pixmap>,             // Cached page thumbnails.
+    tiles: FxHashMap<(usize, i32, i32), Pixmap>,     // Rendered tiles of the tiled pages.
+    generation: Arc<AtomicUsize>,                    // Incremented when the cached pixmaps become stale.
     chunks: Vec<RenderChunk>,                        // Chunks of pages being rendered.
     text: FxHashMap<usize, Vec<BoundedText>>,        // Text of the current chunks.
     annotations: FxHashMap<usize, Vec<Annotation>>,  // Annotations for the current chunks.
     noninverted_regions: FxHashMap<usize, Vec<Boundary>>,
+    image_tone: Option<[u8; 256]>,                   // Applied to the noninverted regions.
     focus: Option<ViewId>,
     search: Option<Search>,
     search_direction: LinearDir,
@@ -90,9 +142,23 @@ pub struct Reader {
     contrast: Contrast,
     synthetic: bool,
     page_turns: usize,
+    // The locations spanned by the chapter of the current page, until the layout changes.
+    chapter_bounds: Option<Range<usize>>,
+    // The page shown, when it was shown and its number of words.
+    page_clock: Option<(usize, Instant, usize)>,
     reflowable: bool,
     ephemeral: bool,
     finished: bool,
+    return_chip: Option<ViewId>,
+    // When the current focus session started, and the number of page turns at that time.
+    focus_start: Option<(DateTime<Local>, usize)>,
+    speaker: Speaker,
+    speech: Option<Speech>,
+    // The words recognized on the scanned pages.
+    ocr: OcrCache,
+    // The pages whose recognition was requested, and whether it was requested by the user.
+    ocr_requests: FxHashMap<usize, bool>,
+    landmarks: Vec<Landmark>,
 }
 
 #[derive(Debug)]
@@ -133,6 +199,7 @@ struct Resource {
     pixmap: Pixmap,
     frame: Rectangle,  // The pixmap's rectangle minus the cropping margins.
     scale: f32,
+    tiled: bool,       // The pixmap is empty and the page is drawn from its tiles.
 }
 
 #[derive(Debug, Clone)]
@@ -150,6 +217,8 @@ struct Search {
     running: Arc<AtomicBool>,
     current_page: usize,
     results_count: usize,
+    // The location and the index of the result that the jumps are relative to.
+    focus: Option<(usize, usize)>,
 }
 
 impl Default for Search {
@@ -160,10 +229,25 @@ impl Default for Search {
             running: Arc::new(AtomicBool::new(true)),
             current_page: 0,
             results_count: 0,
+            focus: None,
         }
     }
 }
 
+// The state of the reading aloud.
+#[derive(Debug)]
+struct Speech {
+    // The chunk locations and the indices of the words shown.
+    words: Vec<(usize, usize)>,
+    // The words being spoken.
+    utterance: Range<usize>,
+    // Where the reading continues once the utterance is spoken.
+    resume: usize,
+    // The word currently spoken.
+    current: Option<usize>,
+    token: usize,
+}
+
 #[derive(Debug)]
 struct Contrast {
     exponent: f32,
@@ -200,9 +284,59 @@ fn scaling_factor(rect: &Rectangle, cropping_margin: &Margin, screen_margin_widt
     }
 }
 
-fn build_pixmap(rect: &Rectangle, doc: &mut dyn Document, location: usize) -> (Pixmap, usize) {
-    let scale = scaling_factor(rect, &Margin::default(), 0, doc.dims(location).unwrap(), ZoomMode::FitToPage);
-    doc.pixmap(Location::Exact(location), scale).unwrap()
+// The area filled by the given page: half of the screen for the portrait pages of a spread.
+// The first page, usually the cover, and the landscape pages, which are already spreads, get the whole screen.
+fn page_rect(doc: &mut dyn Document, location: usize, rect: &Rectangle, spread: bool) -> Rectangle {
+    if spread && location > 0 && doc.dims(location).is_some_and(|(width, height)| width < height) {
+        rect![rect.min, pt!(rect.min.x + rect.width() as i32 / 2, rect.max.y)]
+    } else {
+        *rect
+    }
+}
+
+fn render_resource(doc: &mut dyn Document, location: usize, rect: &Rectangle, cropping_margin: &Margin, screen_margin_width: i32, zoom_mode: ZoomMode, tone_curve: Option<ToneCurve>) -> Resource {
+    let dims = doc.dims(location).unwrap_or((3.0, 4.0));
+    let scale = scaling_factor(rect, cropping_margin, screen_margin_width, dims, zoom_mode);
+    let width = (dims.0 as f32 * scale).max(1.0) as u32;
+    let height = (dims.1 as f32 * scale).max(1.0) as u32;
+    if width as u64 * height as u64 > MAX_PAGE_PIXELS && doc.has_tiles() {
+        let pixmap = Pixmap::empty(width, height);
+        let frame = cropped_frame(&pixmap, cropping_margin);
+        return Resource { pixmap, frame, scale, tiled: true };
+    }
+    if let Some((mut pixmap, _)) = doc.pixmap(Location::Exact(location), scale) {
+        if let Some(tone_curve) = tone_curve {
+            tone_curve.apply(&mut pixmap);
+        }
+        let frame = cropped_frame(&pixmap, cropping_margin);
+        Resource { pixmap, frame, scale, tiled: false }
+    } else {
+        let pixmap = Pixmap::empty(width, height);
+        let frame = pixmap.rect();
+        Resource { pixmap, frame, scale, tiled: false }
+    }
+}
+
+fn cropped_frame(pixmap: &Pixmap, cropping_margin: &Margin) -> Rectangle {
+    rect![(cropping_margin.left * pixmap.width as f32).ceil() as i32,
+          (cropping_margin.top * pixmap.height as f32).ceil() as i32,
+          ((1.0 - cropping_margin.right) * pixmap.width as f32).floor() as i32,
+          ((1.0 - cropping_margin.bottom) * pixmap.height as f32).floor() as i32]
+}
+
+// The columns and the rows of the tiles covering the given area of a page.
+fn tile_ranges(area: &Rectangle) -> (Range<i32>, Range<i32>) {
+    (area.min.x.div_euclid(TILE_SIZE)..(area.max.x + TILE_SIZE - 1).div_euclid(TILE_SIZE),
+     area.min.y.div_euclid(TILE_SIZE)..(area.max.y + TILE_SIZE - 1).div_euclid(TILE_SIZE))
+}
+
+fn tile_rect(column: i32, row: i32) -> Rectangle {
+    rect![column * TILE_SIZE, row * TILE_SIZE, (column + 1) * TILE_SIZE, (row + 1) * TILE_SIZE]
+}
+
+fn build_pixmap(rect: &Rectangle, doc: &mut dyn Document, location: usize) -> Option<(Pixmap, usize)> {
+    let scale = scaling_factor(rect, &Margin::default(), 0, doc.dims(location)?, ZoomMode::FitToPage);
+    doc.pixmap(Location::Exact(location), scale)
 }
 
 fn find_cut(frame: &Rectangle, y_pos: i32, scale: f32, dir: LinearDir, lines: &[BoundedText]) -> Option<i32> {
@@ -228,6 +362,16 @@ fn find_cut(frame: &Rectangle, y_pos: i32, scale: f32, dir: LinearDir, lines: &[
     })
 }
 
+// On an inverted screen, shows the images as they are, but darker.
+fn dimmed_tone(brightness: f32) -> [u8; 256] {
+    let brightness = brightness.clamp(0.0, 1.0);
+    let mut table = [0; 256];
+    for (color, value) in table.iter_mut().enumerate() {
+        *value = 255 - (color as f32 * brightness).round() as u8;
+    }
+    table
+}
+
 fn word_separator(lang: &str) -> &'static str {
     let l = lang.to_ascii_lowercase();
     match l.as_str() {
@@ -238,26 +382,68 @@ fn word_separator(lang: &str) -> &'static str {
     }
 }
 
+// The result after or before the focused one. When the focus isn't on the current page,
+// the search starts from the first or the last result of the current page.
+fn neighbor_result<T>(highlights: &BTreeMap<usize, Vec<T>>, current_page: usize, focus: Option<(usize, usize)>, dir: CycleDir) -> Option<(usize, usize)> {
+    let focus = focus.filter(|&(location, _)| location == current_page);
+    match dir {
+        CycleDir::Next => {
+            if let Some((location, index)) = focus {
+                if index + 1 < highlights.get(&location).map_or(0, Vec::len) {
+                    return Some((location, index + 1));
+                }
+            }
+            let mut range = if focus.is_some() {
+                highlights.range(current_page+1..)
+            } else {
+                highlights.range(current_page..)
+            };
+            range.find(|(_, groups)| !groups.is_empty())
+                 .map(|(location, _)| (*location, 0))
+        },
+        CycleDir::Previous => {
+            if let Some((location, index)) = focus {
+                if index > 0 {
+                    return Some((location, index - 1));
+                }
+            }
+            let mut range = if focus.is_some() {
+                highlights.range(..current_page)
+            } else {
+                highlights.range(..=current_page)
+            };
+            range.rfind(|(_, groups)| !groups.is_empty())
+                 .map(|(location, groups)| (*location, groups.len() - 1))
+        },
+    }
+}
+
 impl Reader {
     pub fn new(rect: Rectangle, mut info: Info, hub: &Hub, context: &mut Context) -> Option<Reader> {
         let id = ID_FEEDER.next();
         let settings = &context.settings;
         let path = context.library.home.join(&info.file.path);
-
-        open(&path).and_then(|mut doc| {
+        open_with_passwords(&path, &context.passwords).and_then(|mut doc| {
+            let reflowable = doc.is_reflowable();
+            let profile = settings.reader.profile(reflowable);
             let (width, height) = context.display.dims;
             let font_size = info.reader.as_ref().and_then(|r| r.font_size)
                                 .unwrap_or(settings.reader.font_size);
 
             doc.layout(width, height, font_size, CURRENT_DEVICE.dpi);
+            doc.set_hub(hub);
 
             let margin_width = info.reader.as_ref().and_then(|r| r.margin_width)
-                                   .unwrap_or(settings.reader.margin_width);
+                                   .unwrap_or_else(|| settings.reader.margin_width(true));
 
-            if margin_width != DEFAULT_MARGIN_WIDTH {
+            if reflowable && margin_width != DEFAULT_MARGIN_WIDTH {
                 doc.set_margin_width(margin_width);
             }
 
+            if let Some(width) = info.reader.as_ref().and_then(|r| r.vertical_margin_width) {
+                doc.set_vertical_margin_width(Some(width));
+            }
+
             let font_family = info.reader.as_ref().and_then(|r| r.font_family.as_ref())
                                   .unwrap_or(&settings.reader.font_family);
 
@@ -291,14 +477,57 @@ impl Reader {
                 doc.set_stretch_tolerance(stretch_tolerance);
             }
 
+            let typography = settings.reader.paragraph_breaker.typography();
+
+            if typography != Typography::default() {
+                doc.set_typography(typography);
+            }
+
             if settings.reader.ignore_document_css {
                 doc.set_ignore_document_css(true);
             }
 
+            // Guess the language of the books that don't specify one.
+            let mut language_detected = false;
+            if info.language.is_empty() {
+                if let Some(language) = doc.metadata("dc:language").filter(|l| !l.is_empty())
+                                           .or_else(|| detect_language(&sample_text(doc.as_mut())).map(String::from)) {
+                    info.language = language;
+                    language_detected = true;
+                }
+            }
+
+            if !info.language.is_empty() {
+                doc.set_language(&info.language);
+            }
+
+            if let Some(path) = context.library.layout_cache_path(&info.file.path) {
+                doc.load_layout_cache(&path);
+            }
+
             let first_location = doc.resolve_location(Location::Exact(0))?;
+            let landmarks = doc.landmarks();
 
             let mut view_port = ViewPort::default();
             let mut contrast = Contrast::default();
+
+            if let Some(zoom_mode) = profile.zoom_mode {
+                view_port.zoom_mode = zoom_mode;
+            }
+
+            if !reflowable {
+                view_port.margin_width = mm_to_px(settings.reader.margin_width(false) as f32,
+                                                  CURRENT_DEVICE.dpi) as i32;
+            }
+
+            if let Some(exponent) = profile.contrast_exponent {
+                contrast.exponent = exponent;
+            }
+
+            if let Some(gray) = profile.contrast_gray {
+                contrast.gray = gray;
+            }
+
             let pages_count = doc.pages_count();
             let current_page;
 
@@ -333,9 +562,8 @@ impl Reader {
                     view_port.page_offset = page_offset;
                 }
 
-                if !doc.is_reflowable() {
-                    view_port.margin_width = mm_to_px(r.screen_margin_width.unwrap_or(0) as f32,
-                                                      CURRENT_DEVICE.dpi) as i32;
+                if let Some(width) = r.screen_margin_width.filter(|_| !reflowable) {
+                    view_port.margin_width = mm_to_px(width as f32, CURRENT_DEVICE.dpi) as i32;
                 }
 
                 if let Some(exponent) = r.contrast_exponent {
@@ -346,7 +574,11 @@ impl Reader {
                     contrast.gray = gray;
                 }
             } else {
-                current_page = first_location;
+                current_page = landmarks.iter()
+                                        .filter(|_| settings.reader.skip_front_matter)
+                                        .find(|landmark| landmark.kind == LandmarkKind::BodyMatter)
+                                        .and_then(|landmark| doc.resolve_location(landmark.location.clone()))
+                                        .unwrap_or(first_location);
 
                 info.reader = Some(ReaderInfo {
                     current_page,
@@ -356,10 +588,17 @@ impl Reader {
             }
 
             let synthetic = doc.has_synthetic_page_numbers();
-            let reflowable = doc.is_reflowable();
+
+            if language_detected {
+                context.library.update_info(&info.file.path, |i| i.language = info.language.clone());
+            }
 
             println!("{}", info.file.path.display());
 
+            let ocr = context.library.ocr_cache_path(&info.file.path)
+                             .map(|path| OcrCache::load(&path))
+                             .unwrap_or_default();
+
             hub.send(Event::Update(UpdateMode::Partial)).ok();
 
             Some(Reader {
@@ -368,10 +607,15 @@ impl Reader {
                 children: Vec::new(),
                 doc: Arc::new(Mutex::new(doc)),
                 cache: BTreeMap::new(),
+                tiles: FxHashMap::default(),
+                prerendered: Arc::new(Mutex::new(BTreeMap::new())),
+                thumbnails: BTreeMap::new(),
+                generation: Arc::new(AtomicUsize::new(0)),
                 chunks: Vec::new(),
                 text: FxHashMap::default(),
                 annotations: FxHashMap::default(),
                 noninverted_regions: FxHashMap::default(),
+                image_tone: None,
                 focus: None,
                 search: None,
                 search_direction: LinearDir::Forward,
@@ -386,10 +630,19 @@ impl Reader {
                 view_port,
                 synthetic,
                 page_turns: 0,
+                chapter_bounds: None,
+                page_clock: None,
                 contrast,
                 ephemeral: false,
                 reflowable,
                 finished: false,
+                return_chip: None,
+                focus_start: Some((Local::now(), 0)).filter(|_| context.timer.is_focusing()),
+                speaker: Speaker::default(),
+                speech: None,
+                ocr,
+                ocr_requests: FxHashMap::default(),
+                landmarks,
             })
         })
     }
@@ -402,6 +655,7 @@ impl Reader {
                 path: PathBuf::from(MEM_SCHEME),
                 kind: "html".to_string(),
                 size: html.len() as u64,
+                hash: String::new(),
             },
             .. Default::default()
         };
@@ -433,10 +687,15 @@ impl Reader {
             children: Vec::new(),
             doc: Arc::new(Mutex::new(Box::new(doc))),
             cache: BTreeMap::new(),
+            tiles: FxHashMap::default(),
+            prerendered: Arc::new(Mutex::new(BTreeMap::new())),
+            thumbnails: BTreeMap::new(),
+            generation: Arc::new(AtomicUsize::new(0)),
             chunks: Vec::new(),
             text: FxHashMap::default(),
             annotations: FxHashMap::default(),
             noninverted_regions: FxHashMap::default(),
+                image_tone: None,
             focus: None,
             search: None,
             search_direction: LinearDir::Forward,
@@ -451,10 +710,19 @@ impl Reader {
             view_port: ViewPort::default(),
             synthetic: true,
             page_turns: 0,
+            chapter_bounds: None,
+            page_clock: None,
             contrast: Contrast::default(),
             ephemeral: true,
             reflowable: true,
             finished: false,
+            return_chip: None,
+            focus_start: None,
+            speaker: Speaker::default(),
+            speech: None,
+            ocr: OcrCache::default(),
+            ocr_requests: FxHashMap::default(),
+            landmarks: Vec::new(),
         }
     }
 
@@ -463,27 +731,133 @@ impl Reader {
             return;
         }
 
+        if let Some(resource) = self.prerendered.lock().unwrap().remove(&location) {
+            self.cache.insert(location, resource);
+            return;
+        }
+
         let mut doc = self.doc.lock().unwrap();
         let cropping_margin = self.info.reader.as_ref()
                                   .and_then(|r| r.cropping_margins.as_ref()
                                                  .map(|c| c.margin(location)))
                                   .cloned().unwrap_or_default();
-        let dims = doc.dims(location).unwrap_or((3.0, 4.0));
-        let screen_margin_width = self.view_port.margin_width;
-        let scale = scaling_factor(&self.rect, &cropping_margin, screen_margin_width, dims, self.view_port.zoom_mode);
-        if let Some((pixmap, _)) = doc.pixmap(Location::Exact(location), scale) {
-            let frame = rect![(cropping_margin.left * pixmap.width as f32).ceil() as i32,
-                              (cropping_margin.top * pixmap.height as f32).ceil() as i32,
-                              ((1.0 - cropping_margin.right) * pixmap.width as f32).floor() as i32,
-                              ((1.0 - cropping_margin.bottom) * pixmap.height as f32).floor() as i32];
-            self.cache.insert(location, Resource { pixmap, frame, scale });
-        } else {
-            let width = (dims.0 as f32 * scale).max(1.0) as u32;
-            let height = (dims.1 as f32 * scale).max(1.0) as u32;
-            let pixmap = Pixmap::empty(width, height);
-            let frame = pixmap.rect();
-            self.cache.insert(location, Resource { pixmap, frame, scale });
+        let tone_curve = self.info.reader.as_ref().and_then(|r| r.tone_curve);
+        let rect = page_rect(doc.as_mut(), location, &self.rect, self.shows_spreads());
+        let resource = render_resource(doc.as_mut(), location, &rect, &cropping_margin,
+                                       self.view_port.margin_width, self.view_port.zoom_mode, tone_curve);
+        self.cache.insert(location, resource);
+    }
+
+    // Drops the cached pixmaps, including those being rendered in the background.
+    fn clear_cache(&mut self) {
+        let mut prerendered = self.prerendered.lock().unwrap();
+        self.generation.fetch_add(1, AtomicOrdering::Relaxed);
+        prerendered.clear();
+        self.cache.clear();
+        self.thumbnails.clear();
+        self.tiles.clear();
+        self.chapter_bounds = None;
+    }
+
+    // Renders the visible tiles of the tiled pages, and drops the distant ones.
+    fn load_tiles(&mut self) {
+        let mut visible = Vec::new();
+        for chunk in &self.chunks {
+            let resource = &self.cache[&chunk.location];
+            if !resource.tiled {
+                continue;
+            }
+            let (columns, rows) = tile_ranges(&chunk.frame);
+            for row in rows {
+                for column in columns.clone() {
+                    visible.push((chunk.location, column, row, resource.scale, resource.pixmap.rect()));
+                }
+            }
+        }
+
+        self.tiles.retain(|&(location, column, row), _| {
+            visible.iter().any(|&(l, c, r, ..)| l == location && (c - column).abs() <= 1 && (r - row).abs() <= 1)
+        });
+
+        let tone_curve = self.info.reader.as_ref().and_then(|r| r.tone_curve);
+        let mut doc = self.doc.lock().unwrap();
+        for (location, column, row, scale, bounds) in visible {
+            if self.tiles.contains_key(&(location, column, row)) {
+                continue;
+            }
+            if let Some(rect) = tile_rect(column, row).intersection(&bounds) {
+                if let Some(mut tile) = doc.tile_pixmap(Location::Exact(location), scale, &rect) {
+                    if let Some(tone_curve) = tone_curve {
+                        tone_curve.apply(&mut tile);
+                    }
+                    self.tiles.insert((location, column, row), tile);
+                }
+            }
+        }
+    }
+
+    // Draws the given area of a page, tile by tile if the page is tiled.
+    fn draw_page_area<F>(&self, fb: &mut dyn Framebuffer, location: usize, frame: &Rectangle, position: Point, mut draw: F)
+                        where F: FnMut(&mut dyn Framebuffer, &Pixmap, &Rectangle, Point) {
+        let resource = &self.cache[&location];
+        if !resource.tiled {
+            draw(fb, &resource.pixmap, frame, position);
+            return;
         }
+        let (columns, rows) = tile_ranges(frame);
+        for row in rows {
+            for column in columns.clone() {
+                let Some(tile) = self.tiles.get(&(location, column, row)) else {
+                    continue;
+                };
+                let rect = tile_rect(column, row);
+                if let Some(part) = frame.intersection(&rect) {
+                    draw(fb, tile, &(part - rect.min), position + part.min - frame.min);
+                }
+            }
+        }
+    }
+
+    // Renders the pages surrounding the displayed chunks in the background.
+    fn prerender_neighbors(&mut self, first_location: usize, last_location: usize, hub: &Hub) {
+        let doc = self.doc.clone();
+        let hub = hub.clone();
+        let prerendered = self.prerendered.clone();
+        let generation = self.generation.clone();
+        let current_generation = generation.load(AtomicOrdering::Relaxed);
+        let cropping_margins = self.info.reader.as_ref()
+                                   .and_then(|r| r.cropping_margins.clone());
+        let rect = self.rect;
+        let spread = self.shows_spreads();
+        let screen_margin_width = self.view_port.margin_width;
+        let zoom_mode = self.view_port.zoom_mode;
+        let tone_curve = self.info.reader.as_ref().and_then(|r| r.tone_curve);
+        let cached = self.cache.keys().cloned().collect::<FxHashSet<usize>>();
+
+        thread::spawn(move || {
+            let mut doc = doc.lock().unwrap();
+            let locations = [doc.resolve_location(Location::Next(last_location)),
+                             doc.resolve_location(Location::Previous(first_location))];
+            prerendered.lock().unwrap()
+                       .retain(|location, _| locations.contains(&Some(*location)));
+            for location in locations.iter().flatten().cloned() {
+                if cached.contains(&location) || prerendered.lock().unwrap().contains_key(&location) {
+                    continue;
+                }
+                let cropping_margin = cropping_margins.as_ref()
+                                                      .map(|c| c.margin(location).clone())
+                                                      .unwrap_or_default();
+                let rect = page_rect(doc.as_mut(), location, &rect, spread);
+                let resource = render_resource(doc.as_mut(), location, &rect, &cropping_margin,
+                                               screen_margin_width, zoom_mode, tone_curve);
+                let mut prerendered = prerendered.lock().unwrap();
+                if generation.load(AtomicOrdering::Relaxed) != current_generation {
+                    break;
+                }
+                prerendered.insert(location, resource);
+                hub.send(Event::LoadPixmap(location)).ok();
+            }
+        });
     }
 
     fn load_text(&mut self, location: usize) {
@@ -495,10 +869,61 @@ impl Reader {
         let loc = Location::Exact(location);
         let words = doc.words(loc)
                        .map(|(words, _)| words)
+                       .filter(|words| !words.is_empty())
+                       .or_else(|| self.ocr.words(location))
                        .unwrap_or_default();
         self.text.insert(location, words);
     }
 
+    // Recognizes the text of the page in the background.
+    fn recognize_text(&mut self, location: usize, manual: bool, hub: &Hub, context: &Context) {
+        if self.ocr_requests.get(&location).is_some_and(|&requested| requested || !manual) {
+            return;
+        }
+        self.ocr_requests.insert(location, manual);
+        let doc = self.doc.clone();
+        let settings = context.settings.ocr.clone();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let mut doc = doc.lock().unwrap();
+            let words = ocr::recognize(doc.as_mut(), location, &settings)
+                            .map_err(|e| eprintln!("Can't recognize the text of page {}: {:#}.", location + 1, e))
+                            .ok();
+            hub.send(Event::RecognizedText(location, words)).ok();
+        });
+    }
+
+    fn is_manga(&self) -> bool {
+        !self.reflowable && self.info.reader.as_ref().is_some_and(|r| r.manga)
+    }
+
+    // Whether two pages are shown side by side.
+    fn shows_spreads(&self) -> bool {
+        self.is_manga() && self.view_port.zoom_mode == ZoomMode::FitToPage &&
+        self.rect.width() > self.rect.height()
+    }
+
+    // The page shown with the given one, on its left, in a spread.
+    fn spread_partner(&mut self, location: usize) -> Option<usize> {
+        if !self.shows_spreads() || location == 0 {
+            return None;
+        }
+        let mut doc = self.doc.lock().unwrap();
+        let next = doc.resolve_location(Location::Next(location))?;
+        let portrait = |doc: &mut Box<dyn Document>, location| doc.dims(location).is_some_and(|(width, height)| width < height);
+        Some(next).filter(|next| portrait(&mut doc, location) && portrait(&mut doc, *next))
+    }
+
+    // The page that starts the spread preceding the given page.
+    fn previous_spread(&mut self, location: usize) -> Location {
+        let previous = self.doc.lock().unwrap().resolve_location(Location::Previous(location));
+        let spread_start = previous.and_then(|previous| {
+            let start = self.doc.lock().unwrap().resolve_location(Location::Previous(previous))?;
+            Some(start).filter(|start| self.spread_partner(*start) == Some(previous))
+        });
+        spread_start.map_or(Location::Previous(location), Location::Exact)
+    }
+
     fn go_to_page(&mut self, location: usize, record: bool, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
         let loc = {
             let mut doc = self.doc.lock().unwrap();
@@ -528,6 +953,40 @@ impl Reader {
         }
     }
 
+    // Goes to the given page and, if it's far from the current page,
+    // offers to come back for a while.
+    fn jump_to_page(&mut self, location: usize, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let origin = self.current_page;
+        self.go_to_page(location, true, hub, rq, context);
+
+        let mut distance = self.current_page.abs_diff(origin) as f64;
+        if self.synthetic {
+            distance /= BYTES_PER_PAGE;
+        }
+        let duration = context.settings.reader.return_chip_duration;
+        if duration == 0 || distance < RETURN_CHIP_MIN_PAGES {
+            return;
+        }
+
+        if let Some(view_id) = self.return_chip.take() {
+            close_progress(self, view_id, rq, context);
+        }
+        let text = format!("Left page {}.", self.page_label(origin));
+        let notif = Notification::with_timed_action(text, "Return".to_string(),
+                                                    Event::Select(EntryId::ReturnToPage(origin)),
+                                                    Duration::from_secs(duration), hub, rq, context);
+        self.return_chip = notif.view_id();
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn return_to_page(&mut self, location: usize, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
+        self.return_chip = None;
+        if self.history.back() == Some(&location) {
+            self.history.pop_back();
+        }
+        self.go_to_page(location, false, hub, rq, context);
+    }
+
     fn go_to_chapter(&mut self, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
         let current_page = self.current_page;
         let loc = {
@@ -736,7 +1195,7 @@ impl Reader {
             let neighloc = match dir { 
                 CycleDir::Previous => {
                     match self.view_port.zoom_mode {
-                        ZoomMode::FitToPage => Location::Previous(current_page),
+                        ZoomMode::FitToPage => self.previous_spread(current_page),
                         ZoomMode::FitToWidth => match self.view_port.scroll_mode {
                             ScrollMode::Screen => {
                                 let first_chunk = self.chunks.first().cloned().unwrap();
@@ -804,7 +1263,7 @@ impl Reader {
                 },
                 CycleDir::Next => {
                     match self.view_port.zoom_mode {
-                        ZoomMode::FitToPage => Location::Next(current_page),
+                        ZoomMode::FitToPage => Location::Next(self.chunks.last().map_or(current_page, |chunk| chunk.location)),
                         ZoomMode::FitToWidth => match self.view_port.scroll_mode {
                             ScrollMode::Screen => {
                                 let &RenderChunk { location, frame, .. } = self.chunks.last().unwrap();
@@ -866,15 +1325,30 @@ impl Reader {
                         } else {
                             context.settings.reader.finished
                         };
+                        let next_in_series = if self.ephemeral {
+                            None
+                        } else {
+                            context.library.next_in_series(&self.info)
+                                   .map(|info| format!("Next in series: {}.", info.title()))
+                        };
                         match action {
                             FinishedAction::Notify => {
-                                let notif = Notification::new("No next page.".to_string(),
-                                                              hub, rq, context);
+                                let message = next_in_series.unwrap_or_else(|| "No next page.".to_string());
+                                let notif = Notification::new(message, hub, rq, context);
                                 self.children.push(Box::new(notif) as Box<dyn View>);
                             },
                             FinishedAction::Close => {
                                 self.quit(context);
                                 hub.send(Event::Back).ok();
+                                if let Some(message) = next_in_series {
+                                    hub.send(Event::Notify(message)).ok();
+                                }
+                            },
+                            FinishedAction::Panel => {
+                                self.toggle_end_of_book(Some(true), rq, context);
+                                if let Some(message) = next_in_series {
+                                    hub.send(Event::Notify(message)).ok();
+                                }
                             },
                         }
                     },
@@ -899,9 +1373,9 @@ impl Reader {
         if let Some(location) = loc {
             self.view_port.page_offset = pt!(0, 0);
             self.current_page = location;
+            self.update(None, hub, rq, context);
             self.update_results_bar(rq);
             self.update_bottom_bar(rq);
-            self.update(None, hub, rq, context);
         }
     }
 
@@ -920,9 +1394,31 @@ impl Reader {
             }
             self.view_port.page_offset = pt!(0, 0);
             self.current_page = location;
+            self.update(None, hub, rq, context);
             self.update_results_bar(rq);
             self.update_bottom_bar(rq);
-            self.update(None, hub, rq, context);
+        }
+    }
+
+    // Focuses the next or previous result, and turns to its page if needed.
+    fn go_to_result(&mut self, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
+        let focus = self.search.as_ref().and_then(|s| {
+            neighbor_result(&s.highlights, self.current_page, s.focus, dir)
+        });
+        if let Some((location, index)) = focus {
+            if let Some(ref mut s) = self.search {
+                s.focus = Some((location, index));
+                s.current_page = s.highlights.range(..=location).count().saturating_sub(1);
+            }
+            if location != self.current_page {
+                self.view_port.page_offset = pt!(0, 0);
+                self.current_page = location;
+                self.update(None, hub, rq, context);
+                self.update_results_bar(rq);
+                self.update_bottom_bar(rq);
+            } else {
+                self.render_results(rq);
+            }
         }
     }
 
@@ -975,7 +1471,7 @@ impl Reader {
             let reflowable = self.reflowable;
             let margin_width = self.info.reader.as_ref()
                                    .and_then(|r| if reflowable { r.margin_width } else { r.screen_margin_width })
-                                   .unwrap_or_else(|| if reflowable { settings.reader.margin_width } else { 0 });
+                                   .unwrap_or_else(|| settings.reader.margin_width(reflowable));
             tool_bar.update_margin_width(margin_width, rq);
         }
     }
@@ -984,22 +1480,37 @@ impl Reader {
         if self.search.is_none() {
             return;
         }
+        let visible = self.visible_results_count();
         let (count, current_page, pages_count) = {
             let s = self.search.as_ref().unwrap();
             (s.results_count, s.current_page, s.highlights.len())
         };
         if let Some(index) = locate::<ResultsBar>(self) {
             let results_bar = self.child_mut(index).downcast_mut::<ResultsBar>().unwrap();
-            results_bar.update_results_label(count, rq);
+            results_bar.update_results_label(count, visible, rq);
             results_bar.update_page_label(current_page, pages_count, rq);
             results_bar.update_icons(current_page, pages_count, rq);
         }
     }
 
+    // The number of results on the displayed pages.
+    fn visible_results_count(&self) -> usize {
+        self.search.as_ref().map_or(0, |s| {
+            self.chunks.iter()
+                .filter_map(|chunk| s.highlights.get(&chunk.location))
+                .map(Vec::len)
+                .sum()
+        })
+    }
+
     #[inline]
-    fn update_noninverted_regions(&mut self, inverted: bool) {
+    fn update_noninverted_regions(&mut self, inverted: bool, settings: &ReaderSettings) {
         self.noninverted_regions.clear();
-        if inverted {
+        self.image_tone = None;
+        if inverted && settings.night_images != NightImages::Inverted {
+            if settings.night_images == NightImages::Dimmed {
+                self.image_tone = Some(dimmed_tone(settings.night_images_brightness));
+            }
             for chunk in &self.chunks {
                 if let Some((images, _)) = self.doc.lock().unwrap().images(Location::Exact(chunk.location)) {
                     self.noninverted_regions.insert(chunk.location, images);
@@ -1030,20 +1541,41 @@ impl Reader {
         }
     }
 
+    // The locations from the start of the current chapter to the start of the next one.
+    // Without a table of contents, the whole document is one chapter.
+    fn current_chapter_bounds(&self) -> Range<usize> {
+        let mut doc = self.doc.lock().unwrap();
+        let Some(toc) = self.toc().or_else(|| doc.toc()) else {
+            return 0..usize::MAX;
+        };
+        let start = doc.chapter(self.current_page, &toc)
+                       .and_then(|(chap, _)| doc.resolve_location(chap.location.clone()))
+                       .filter(|&start| start <= self.current_page)
+                       .unwrap_or(0);
+        let end = doc.chapter_relative(self.current_page, CycleDir::Next, &toc)
+                     .and_then(|chap| doc.resolve_location(chap.location.clone()))
+                     .filter(|&end| end > self.current_page)
+                     .unwrap_or(usize::MAX);
+        start..end
+    }
+
+    // The page turn, with the refresh policy of the book, whose update mode is left to the scheduler.
+    fn page_turn(&mut self, context: &Context) -> PageTurn {
+        let policy = self.info.reader.as_ref()
+                         .and_then(|r| r.refresh_policy);
+        // The table of contents is only searched when the current page leaves the bounds.
+        let chapter_started = policy.unwrap_or(context.settings.reader.refresh_rate.policy) == RefreshPolicy::Chapter &&
+                              !self.chapter_bounds.as_ref().is_some_and(|b| b.contains(&self.current_page)) && {
+            let known = self.chapter_bounds.is_some();
+            self.chapter_bounds = Some(self.current_chapter_bounds());
+            known
+        };
+        PageTurn { policy, chapter_started, .. PageTurn::new(self.id, self.rect) }
+    }
+
     fn update(&mut self, update_mode: Option<UpdateMode>, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
         self.page_turns += 1;
-        let update_mode = update_mode.unwrap_or_else(|| {
-            let refresh_rate = if context.fb.inverted() {
-                context.settings.reader.refresh_rate.inverted
-            } else {
-                context.settings.reader.refresh_rate.regular
-            };
-            if refresh_rate == 0 || self.page_turns % (refresh_rate as usize) != 0 {
-                UpdateMode::Partial
-            } else {
-                UpdateMode::Full
-            }
-        });
+        let page_turn = self.page_turn(context);
 
         self.chunks.clear();
         let mut location = self.current_page;
@@ -1054,9 +1586,22 @@ impl Reader {
                 self.load_pixmap(location);
                 self.load_text(location);
                 let Resource { frame, scale, .. } = self.cache[&location];
-                let dx = smw + ((self.rect.width() - frame.width()) as i32 - 2 * smw) / 2;
                 let dy = smw + ((self.rect.height() - frame.height()) as i32 - 2 * smw) / 2;
-                self.chunks.push(RenderChunk { frame, location, position: pt!(dx, dy), scale });
+                if let Some(next_location) = self.spread_partner(location) {
+                    self.load_pixmap(next_location);
+                    self.load_text(next_location);
+                    let Resource { frame: next_frame, scale: next_scale, .. } = self.cache[&next_location];
+                    let next_dy = smw + ((self.rect.height() - next_frame.height()) as i32 - 2 * smw) / 2;
+                    let dx = smw + ((self.rect.width() - frame.width() - next_frame.width()) as i32 - 2 * smw) / 2;
+                    // The pages are read from right to left.
+                    self.chunks.push(RenderChunk { frame, location,
+                                                   position: pt!(dx + next_frame.width() as i32, dy), scale });
+                    self.chunks.push(RenderChunk { frame: next_frame, location: next_location,
+                                                   position: pt!(dx, next_dy), scale: next_scale });
+                } else {
+                    let dx = smw + ((self.rect.width() - frame.width()) as i32 - 2 * smw) / 2;
+                    self.chunks.push(RenderChunk { frame, location, position: pt!(dx, dy), scale });
+                }
             },
             ZoomMode::FitToWidth => match self.view_port.scroll_mode {
                 ScrollMode::Screen => {
@@ -1124,7 +1669,12 @@ impl Reader {
             },
         }
 
-        rq.add(RenderData::new(self.id, self.rect, update_mode));
+        self.load_tiles();
+        if let Some(update_mode) = update_mode {
+            rq.add(RenderData::new(self.id, self.rect, update_mode));
+        } else {
+            rq.turn_page(page_turn);
+        }
         let first_location = self.chunks.first().map(|c| c.location).unwrap();
         let last_location = self.chunks.last().map(|c| c.location).unwrap();
 
@@ -1140,30 +1690,45 @@ impl Reader {
         }
 
         self.update_annotations();
-        self.update_noninverted_regions(context.fb.inverted());
+        self.update_noninverted_regions(context.fb.inverted(), &context.settings.reader);
+        self.update_reading_speed();
+
+        if context.settings.ocr.auto && !self.reflowable {
+            let locations = self.chunks.iter().map(|chunk| chunk.location)
+                                .filter(|location| self.text.get(location).is_some_and(Vec::is_empty))
+                                .collect::<Vec<usize>>();
+            for location in locations {
+                self.recognize_text(location, false, hub, context);
+            }
+        }
 
         if self.view_port.zoom_mode == ZoomMode::FitToPage ||
            self.view_port.zoom_mode == ZoomMode::FitToWidth {
-            let doc2 = self.doc.clone();
-            let hub2 = hub.clone();
-            thread::spawn(move || {
-                let mut doc = doc2.lock().unwrap();
-                if let Some(next_location) = doc.resolve_location(Location::Next(last_location)) {
-                    hub2.send(Event::LoadPixmap(next_location)).ok();
-                }
-            });
-            let doc3 = self.doc.clone();
-            let hub3 = hub.clone();
-            thread::spawn(move || {
-                let mut doc = doc3.lock().unwrap();
-                if let Some(previous_location) = doc.resolve_location(Location::Previous(first_location)) {
-                    hub3.send(Event::LoadPixmap(previous_location)).ok();
+            self.prerender_neighbors(first_location, last_location, hub);
+        }
+    }
+
+    // Counts the time spent on the previous page when it was followed by the next one.
+    fn update_reading_speed(&mut self) {
+        if self.page_clock.is_some_and(|(location, ..)| location == self.current_page) {
+            return;
+        }
+        let words = self.chunks.iter()
+                        .map(|chunk| self.text.get(&chunk.location).map_or(0, Vec::len))
+                        .sum();
+        if let Some((location, shown, previous_words)) = self.page_clock.replace((self.current_page, Instant::now(), words)) {
+            let seconds = shown.elapsed().as_secs();
+            if self.current_page > location && previous_words > 0 && READING_SECONDS_RANGE.contains(&seconds) {
+                if let Some(ref mut r) = self.info.reader {
+                    let speed = r.reading_speed.get_or_insert_with(Default::default);
+                    speed.words += previous_words;
+                    speed.seconds += seconds;
                 }
-            });
+            }
         }
     }
 
-    fn search(&mut self, text: &str, query: Regex, hub: &Hub, rq: &mut RenderQueue) {
+    fn search(&mut self, text: &str, query: Regex, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         let s = Search {
             query: text.to_string(),
             .. Default::default()
@@ -1175,10 +1740,12 @@ impl Reader {
         let current_page = self.current_page;
         let search_direction = self.search_direction;
         let ws = word_separator(&self.info.language);
+        let ocr = self.ocr.clone();
 
         thread::spawn(move || {
             let mut loc = Location::Exact(current_page);
             let mut started = false;
+            let mut last_percent = None;
 
             loop {
                 if !running.load(AtomicOrdering::Relaxed) {
@@ -1193,7 +1760,11 @@ impl Reader {
                     if location == current_page && started {
                         break;
                     }
-                    if let Some((ref words, _)) = doc.words(Location::Exact(location)) {
+                    let words = doc.words(Location::Exact(location))
+                                   .map(|(words, _)| words)
+                                   .filter(|words| !words.is_empty())
+                                   .or_else(|| ocr.words(location));
+                    if let Some(ref words) = words {
                         for word in words {
                             if !running.load(AtomicOrdering::Relaxed) {
                                 break;
@@ -1220,6 +1791,18 @@ impl Reader {
                         LinearDir::Forward => Location::Next(location),
                         LinearDir::Backward => Location::Previous(location),
                     };
+                    // The locations of reflowable documents are offsets: the progress
+                    // is the part of the location range covered since the current page.
+                    let total = doc.pages_count().max(1);
+                    let scanned = match search_direction {
+                        LinearDir::Forward => (location + total - current_page % total) % total,
+                        LinearDir::Backward => (current_page % total + total - location) % total,
+                    };
+                    let percent = 100 * scanned / total;
+                    if last_percent != Some(percent) {
+                        last_percent = Some(percent);
+                        hub2.send(Event::TaskProgress(ViewId::SearchNotif, scanned, total)).ok();
+                    }
                 } else {
                     loc = match search_direction {
                         LinearDir::Forward => Location::Exact(0),
@@ -1238,6 +1821,11 @@ impl Reader {
             self.render_results(rq);
         }
 
+        if locate_by_id(self, ViewId::SearchNotif).is_none() {
+            let notif = Notification::with_progress(ViewId::SearchNotif, "Searching.".to_string(), rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+        }
+
         self.search = Some(s);
     }
 
@@ -1390,9 +1978,10 @@ impl Reader {
             let mut rect = rect![self.rect.min.x, y_min,
                                  self.rect.max.x, y_min + small_height - thickness];
 
+            let visible = self.visible_results_count();
             if let Some(ref s) = self.search {
                 let results_bar = ResultsBar::new(rect, s.current_page,
-                                                  s.highlights.len(), s.results_count,
+                                                  s.highlights.len(), s.results_count, visible,
                                                   !s.running.load(AtomicOrdering::Relaxed));
                 self.children.insert(index, Box::new(results_bar) as Box<dyn View>);
                 let separator = Filler::new(sp_rect, BLACK);
@@ -1518,6 +2107,7 @@ impl Reader {
             self.children.insert(index, Box::new(separator) as Box<dyn View>);
             index += 1;
 
+            let visible = self.visible_results_count();
             if let Some(ref s) = self.search {
                 if let Some(sindex) = rlocate::<SearchBar>(self) {
                     index = sindex + 2;
@@ -1535,7 +2125,8 @@ impl Reader {
                                                             self.rect.max.x,
                                                             self.rect.max.y - 2 * small_height - small_thickness],
                                                       s.current_page, s.highlights.len(),
-                                                      s.results_count, !s.running.load(AtomicOrdering::Relaxed));
+                                                      s.results_count, visible,
+                                                      !s.running.load(AtomicOrdering::Relaxed));
                     self.children.insert(index, Box::new(results_bar) as Box<dyn View>);
                     index += 1;
 
@@ -1634,7 +2225,9 @@ impl Reader {
                              .cloned().unwrap_or_default();
 
             let mut doc = self.doc.lock().unwrap();
-            let (pixmap, _) = build_pixmap(&pixmap_rect, doc.as_mut(), self.current_page);
+            let Some((pixmap, _)) = build_pixmap(&pixmap_rect, doc.as_mut(), self.current_page) else {
+                return;
+            };
 
             let margin_cropper = MarginCropper::new(self.rect, pixmap, &margin, context);
             rq.add(RenderData::new(margin_cropper.id(), *margin_cropper.rect(), UpdateMode::Gui));
@@ -1671,39 +2264,31 @@ impl Reader {
         }
     }
 
-    fn toggle_name_page(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
-        if let Some(index) = locate_by_id(self, ViewId::NamePage) {
+    fn toggle_end_of_book(&mut self, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::EndOfBook) {
             if let Some(true) = enable {
                 return;
             }
 
             rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
             self.children.remove(index);
-
-            if self.focus.map(|focus_id| focus_id == ViewId::NamePageInput).unwrap_or(false) {
-                self.toggle_keyboard(false, None, hub, rq, context);
-            }
         } else {
             if let Some(false) = enable {
                 return;
             }
 
-            let name_page = NamedInput::new("Name page".to_string(), ViewId::NamePage, ViewId::NamePageInput, 4, context);
-            rq.add(RenderData::new(name_page.id(), *name_page.rect(), UpdateMode::Gui));
-            hub.send(Event::Focus(Some(ViewId::NamePageInput))).ok();
-
-            self.children.push(Box::new(name_page) as Box<dyn View>);
+            let (rating, has_review) = self.info.reader.as_ref()
+                                           .map_or((0, false), |r| (r.rating.unwrap_or(0), !r.review.is_empty()));
+            let can_archive = !self.info.file.path.starts_with(&context.settings.reader.archive_directory);
+            let end_of_book = EndOfBook::new(ViewId::EndOfBook, &self.info.title(), rating,
+                                             has_review, can_archive, context);
+            rq.add(RenderData::new(end_of_book.id(), *end_of_book.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(end_of_book) as Box<dyn View>);
         }
     }
 
-    fn toggle_go_to_page(&mut self, enable: Option<bool>, id: ViewId, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
-        let (text, input_id) = if id == ViewId::GoToPage {
-            ("Go to page", ViewId::GoToPageInput)
-        } else {
-            ("Go to results page", ViewId::GoToResultsPageInput)
-        };
-
-        if let Some(index) = locate_by_id(self, id) {
+    fn toggle_book_review(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::BookReview) {
             if let Some(true) = enable {
                 return;
             }
@@ -1711,7 +2296,7 @@ impl Reader {
             rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
             self.children.remove(index);
 
-            if self.focus.map(|focus_id| focus_id == input_id).unwrap_or(false) {
+            if self.focus.map(|focus_id| focus_id == ViewId::BookReviewInput).unwrap_or(false) {
                 self.toggle_keyboard(false, None, hub, rq, context);
             }
         } else {
@@ -1719,16 +2304,35 @@ impl Reader {
                 return;
             }
 
-            let go_to_page = NamedInput::new(text.to_string(), id, input_id, 4, context);
-            rq.add(RenderData::new(go_to_page.id(), *go_to_page.rect(), UpdateMode::Gui));
-            hub.send(Event::Focus(Some(input_id))).ok();
+            let mut book_review = NamedInput::new("Note".to_string(), ViewId::BookReview, ViewId::BookReviewInput, 32, context);
+            if let Some(r) = self.info.reader.as_ref().filter(|r| !r.review.is_empty()) {
+                book_review.set_text(&r.review, &mut RenderQueue::new(), context);
+            }
 
-            self.children.push(Box::new(go_to_page) as Box<dyn View>);
+            rq.add(RenderData::new(book_review.id(), *book_review.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::BookReviewInput))).ok();
+
+            self.children.push(Box::new(book_review) as Box<dyn View>);
         }
     }
 
-    pub fn toggle_annotation_menu(&mut self, annot: &Annotation, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
-        if let Some(index) = locate_by_id(self, ViewId::AnnotationMenu) {
+    fn archive(&mut self, hub: &Hub, context: &mut Context) {
+        self.quit(context);
+        let archive_directory = context.settings.reader.archive_directory.clone();
+        match context.library.move_to_directory(&self.info.file.path, &archive_directory) {
+            Ok(..) => {
+                hub.send(Event::Notify("Book archived.".to_string())).ok();
+            },
+            Err(e) => {
+                eprintln!("Can't archive {}: {:#}.", self.info.file.path.display(), e);
+                hub.send(Event::Notify("Can't archive the book.".to_string())).ok();
+            },
+        }
+        hub.send(Event::Back).ok();
+    }
+
+    fn toggle_skim(&mut self, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::Skim) {
             if let Some(true) = enable {
                 return;
             }
@@ -1740,28 +2344,19 @@ impl Reader {
                 return;
             }
 
-            let sel = annot.selection;
-            let mut entries = Vec::new();
-
-            if annot.note.is_empty() {
-                entries.push(EntryKind::Command("Remove Highlight".to_string(), EntryId::RemoveAnnotation(sel)));
-                entries.push(EntryKind::Separator);
-                entries.push(EntryKind::Command("Add Note".to_string(), EntryId::EditAnnotationNote(sel)));
-            } else {
-                entries.push(EntryKind::Command("Remove Annotation".to_string(), EntryId::RemoveAnnotation(sel)));
-                entries.push(EntryKind::Separator);
-                entries.push(EntryKind::Command("Edit Note".to_string(), EntryId::EditAnnotationNote(sel)));
-                entries.push(EntryKind::Command("Remove Note".to_string(), EntryId::RemoveAnnotationNote(sel)));
+            let ticks = self.chapter_locations();
+            let label = self.skim_label(self.current_page);
+            let mut skim = Skim::new(self.current_page, self.pages_count, ticks, label.clone(), context);
+            if let Some((pixmap, _)) = self.render_preview(self.current_page, &skim.preview_rect()) {
+                skim.update(pixmap, &label, &mut RenderQueue::new());
             }
-
-            let selection_menu = Menu::new(rect, ViewId::AnnotationMenu, MenuKind::Contextual, entries, context);
-            rq.add(RenderData::new(selection_menu.id(), *selection_menu.rect(), UpdateMode::Gui));
-            self.children.push(Box::new(selection_menu) as Box<dyn View>);
+            rq.add(RenderData::new(skim.id(), *skim.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(skim) as Box<dyn View>);
         }
     }
 
-    pub fn toggle_selection_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
-        if let Some(index) = locate_by_id(self, ViewId::SelectionMenu) {
+    fn toggle_go_to_pad(&mut self, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::GoToPad) {
             if let Some(true) = enable {
                 return;
             }
@@ -1772,18 +2367,621 @@ impl Reader {
             if let Some(false) = enable {
                 return;
             }
-            let mut entries = vec![
-                EntryKind::Command("Highlight".to_string(), EntryId::HighlightSelection),
-                EntryKind::Command("Add Note".to_string(), EntryId::AnnotateSelection)
-            ];
-
-            entries.push(EntryKind::Separator);
-            entries.push(EntryKind::Command("Define".to_string(), EntryId::DefineSelection));
-            entries.push(EntryKind::Command("Search".to_string(), EntryId::SearchForSelection));
 
-            if self.info.reader.as_ref().map_or(false, |r| !r.page_names.is_empty()) {
-                entries.push(EntryKind::Command("Go To".to_string(), EntryId::GoToSelectedPageName));
-            }
+            let has_chapters = self.toc().or_else(|| self.doc.lock().unwrap().toc())
+                                   .is_some_and(|toc| !toc.is_empty());
+            let go_to_pad = GoToPad::new(self.pages_count, has_chapters, context);
+            rq.add(RenderData::new(go_to_pad.id(), *go_to_pad.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(go_to_pad) as Box<dyn View>);
+        }
+    }
+
+    // The location of the chapter at the given position of the flattened table of contents.
+    fn find_chapter_by_number(&self, number: usize) -> Option<usize> {
+        let mut doc = self.doc.lock().unwrap();
+        let toc = self.toc().or_else(|| doc.toc())?;
+        let entry = toc_entry_at(&toc, number)?;
+        doc.resolve_location(entry.location.clone())
+    }
+
+    fn update_skim(&mut self, location: usize, rq: &mut RenderQueue) {
+        let label = self.skim_label(location);
+        let preview_rect = if let Some(index) = locate_by_id(self, ViewId::Skim) {
+            self.children[index].downcast_ref::<Skim>().map(Skim::preview_rect)
+        } else {
+            None
+        };
+        if let Some((pixmap, _)) = preview_rect.and_then(|rect| self.render_preview(location, &rect)) {
+            if let Some(skim) = locate_by_id(self, ViewId::Skim)
+                                    .and_then(|index| self.children[index].downcast_mut::<Skim>()) {
+                skim.update(pixmap, &label, rq);
+            }
+        }
+    }
+
+    // The locations of the top-level chapters.
+    fn chapter_locations(&self) -> Vec<usize> {
+        let mut doc = self.doc.lock().unwrap();
+        self.toc().or_else(|| doc.toc())
+            .map(|toc| toc.iter()
+                          .filter_map(|entry| doc.resolve_location(entry.location.clone()))
+                          .collect())
+            .unwrap_or_default()
+    }
+
+    fn page_label(&self, location: usize) -> String {
+        if self.synthetic {
+            format!("{:.1}", location as f64 / BYTES_PER_PAGE)
+        } else {
+            format!("{}", location + 1)
+        }
+    }
+
+    fn skim_label(&self, location: usize) -> String {
+        let page = self.page_label(location);
+        let mut doc = self.doc.lock().unwrap();
+        let title = self.toc().or_else(|| doc.toc())
+                        .and_then(|toc| doc.chapter(location, &toc).map(|(chap, _)| chap.title.clone()));
+        match title {
+            Some(title) if !title.is_empty() => format!("{} — {}", page, title),
+            _ => page,
+        }
+    }
+
+    fn render_preview(&self, location: usize, rect: &Rectangle) -> Option<(Pixmap, usize)> {
+        let mut doc = self.doc.lock().unwrap();
+        let location = doc.resolve_location(Location::Exact(location))?;
+        let dims = doc.dims(location)?;
+        let scale = scaling_factor(rect, &Margin::default(), 0, dims, ZoomMode::FitToPage);
+        doc.pixmap(Location::Exact(location), scale)
+    }
+
+    fn toggle_thumbnails(&mut self, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::Thumbnails) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let mut thumbnails = Thumbnails::new(self.rect, context);
+            let count = thumbnails.cells_count();
+            // Fixed layout pages are grouped so that a page always appears in the same cell.
+            let start = if self.synthetic {
+                self.current_page
+            } else {
+                count * (self.current_page / count)
+            };
+            let page = self.thumbnails_page(start, CycleDir::Next, count, thumbnails.thumbnail_dims());
+            thumbnails.update(page, self.current_page, &mut RenderQueue::new());
+            rq.add(RenderData::new(thumbnails.id(), *thumbnails.rect(), UpdateMode::Full));
+            self.children.push(Box::new(thumbnails) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_tone_picker(&mut self, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::TonePicker) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let (width, height) = TonePicker::preview_dims(self.rect, context);
+            let preview_rect = rect![0, 0, width as i32, height as i32];
+            let page = if let Some((page, _)) = self.render_preview(self.current_page, &preview_rect) { page } else { return };
+            let current = self.info.reader.as_ref().and_then(|r| r.tone_curve);
+            let tone_picker = TonePicker::new(self.rect, &page, current, context);
+            rq.add(RenderData::new(tone_picker.id(), *tone_picker.rect(), UpdateMode::Full));
+            self.children.push(Box::new(tone_picker) as Box<dyn View>);
+        }
+    }
+
+    fn set_tone_curve(&mut self, tone_curve: Option<ToneCurve>, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
+        if let Some(ref mut r) = self.info.reader {
+            r.tone_curve = tone_curve;
+        }
+        self.clear_cache();
+        self.update(None, hub, rq, context);
+    }
+
+    // The table or image under the given point.
+    fn zoom_source_at(&self, pt: Point) -> Option<ZoomSource> {
+        let mut doc = self.doc.lock().ok()?;
+        let reflowable = doc.is_reflowable();
+        for chunk in &self.chunks {
+            let to_screen = |rect: Boundary| (rect * chunk.scale).to_rect() - chunk.frame.min + chunk.position;
+            if let Some((tables, _)) = doc.tables(Location::Exact(chunk.location)) {
+                if let Some((_, offset)) = tables.into_iter().find(|(rect, _)| to_screen(*rect).includes(pt)) {
+                    return Some(ZoomSource::Table(offset));
+                }
+            }
+            if let Some((images, _)) = doc.images(Location::Exact(chunk.location)) {
+                let page_area = doc.dims(chunk.location).map_or(0.0, |(width, height)| width * height);
+                for rect in images {
+                    // The scans of the fixed layout documents cover the whole page.
+                    let area = (rect.max.x - rect.min.x) * (rect.max.y - rect.min.y);
+                    if !reflowable && area > IMAGE_MAX_PAGE_RATIO * page_area {
+                        continue;
+                    }
+                    if to_screen(rect).includes(pt) {
+                        return Some(ZoomSource::Image(chunk.location, rect));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn toggle_zoom_view(&mut self, source: Option<ZoomSource>, rq: &mut RenderQueue) {
+        if let Some(index) = locate_by_id(self, ViewId::ZoomView) {
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else if let Some(source) = source {
+            if let Some(zoom_view) = ZoomView::new(self.rect, self.doc.clone(), source) {
+                rq.add(RenderData::new(zoom_view.id(), *zoom_view.rect(), UpdateMode::Gui));
+                self.children.push(Box::new(zoom_view) as Box<dyn View>);
+            }
+        }
+    }
+
+    fn go_to_thumbnails_neighbor(&mut self, dir: CycleDir, rq: &mut RenderQueue) {
+        let index = if let Some(index) = locate_by_id(self, ViewId::Thumbnails) { index } else { return };
+        let (bounds, count, dims) = if let Some(thumbnails) = self.children[index].downcast_ref::<Thumbnails>() {
+            ((thumbnails.first_location(), thumbnails.last_location()),
+             thumbnails.cells_count(), thumbnails.thumbnail_dims())
+        } else {
+            return;
+        };
+        let start = {
+            let mut doc = self.doc.lock().unwrap();
+            match (dir, bounds) {
+                (CycleDir::Next, (_, Some(last))) => doc.resolve_location(Location::Next(last)),
+                (CycleDir::Previous, (Some(first), _)) => doc.resolve_location(Location::Previous(first)),
+                _ => None,
+            }
+        };
+        if let Some(start) = start {
+            let page = self.thumbnails_page(start, dir, count, dims);
+            let current_page = self.current_page;
+            if let Some(thumbnails) = self.children[index].downcast_mut::<Thumbnails>() {
+                thumbnails.update(page, current_page, rq);
+            }
+        }
+    }
+
+    // Collects `count` pages starting from, or ending at, the given location.
+    fn thumbnails_page(&mut self, location: usize, dir: CycleDir, count: usize, dims: (u32, u32)) -> ThumbnailsPage {
+        let mut locations = vec![location];
+        {
+            let mut doc = self.doc.lock().unwrap();
+            while locations.len() < count {
+                let last = locations[locations.len() - 1];
+                let neighbor = match dir {
+                    CycleDir::Next => doc.resolve_location(Location::Next(last)),
+                    CycleDir::Previous => doc.resolve_location(Location::Previous(last)),
+                };
+                if let Some(neighbor) = neighbor {
+                    locations.push(neighbor);
+                } else {
+                    break;
+                }
+            }
+        }
+        if dir == CycleDir::Previous {
+            locations.reverse();
+        }
+        locations.into_iter().filter_map(|location| {
+            self.thumbnail(location, dims)
+                .map(|pixmap| (location, self.page_label(location), pixmap))
+        }).collect()
+    }
+
+    // Shrinks the cached rendering of the page, if any, instead of rendering it again.
+    fn thumbnail(&mut self, location: usize, (width, height): (u32, u32)) -> Option<Pixmap> {
+        if let Some(pixmap) = self.thumbnails.get(&location) {
+            return Some(pixmap.clone());
+        }
+        let pixmap = if let Some(resource) = self.cache.get(&location).filter(|r| !r.tiled) {
+            let source = &resource.pixmap;
+            let scale = (width as f32 / source.width as f32).min(height as f32 / source.height as f32);
+            source.downscaled((scale * source.width as f32) as u32, (scale * source.height as f32) as u32)
+        } else {
+            let rect = rect![0, 0, width as i32, height as i32];
+            self.render_preview(location, &rect).map(|(pixmap, _)| pixmap)?
+        };
+        self.thumbnails.insert(location, pixmap.clone());
+        Some(pixmap)
+    }
+
+    // The words of the visible parts of the chunks.
+    fn visible_words(&self) -> Vec<(usize, usize)> {
+        let mut words = Vec::new();
+        for chunk in &self.chunks {
+            if let Some(text) = self.text.get(&chunk.location) {
+                for (index, word) in text.iter().enumerate() {
+                    let rect = (word.rect * chunk.scale).to_rect();
+                    if chunk.frame.includes(rect.center()) && !words.contains(&(chunk.location, index)) {
+                        words.push((chunk.location, index));
+                    }
+                }
+            }
+        }
+        words
+    }
+
+    fn speech_language(&self, context: &Context) -> String {
+        let (source, _) = self.translation_languages(context);
+        if source == AUTO_LANGUAGE {
+            "en".to_string()
+        } else {
+            source
+        }
+    }
+
+    fn speech_word_rect(&self, (location, index): (usize, usize)) -> Option<Rectangle> {
+        let chunk = self.chunks.iter().find(|chunk| chunk.location == location)?;
+        let word = self.text.get(&location)?.get(index)?;
+        Some((word.rect * chunk.scale).to_rect() - chunk.frame.min + chunk.position)
+    }
+
+    // Moves the highlight to the given word.
+    fn set_speech_word(&mut self, current: Option<usize>, rq: &mut RenderQueue) {
+        let speech = match self.speech.as_mut() {
+            Some(speech) => speech,
+            None => return,
+        };
+        let previous = speech.current;
+        speech.current = current;
+        let words = speech.words.clone();
+        for index in [previous, current].into_iter().flatten() {
+            if let Some(rect) = words.get(index).and_then(|&word| self.speech_word_rect(word)) {
+                rq.add(RenderData::new(self.id, rect, UpdateMode::Fast));
+            }
+        }
+    }
+
+    // Speaks the sentence that holds the given word, turning the page
+    // once all the visible words are spoken.
+    fn read_aloud(&mut self, index: usize, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let words = match self.speech.as_ref() {
+            Some(speech) => speech.words.clone(),
+            None => return,
+        };
+
+        if index >= words.len() {
+            let locations: Vec<usize> = self.chunks.iter().map(|chunk| chunk.location).collect();
+            self.set_speech_word(None, rq);
+            self.go_to_neighbor(CycleDir::Next, hub, rq, context);
+            if self.chunks.iter().map(|chunk| chunk.location).eq(locations) {
+                self.stop_reading_aloud(rq);
+                return;
+            }
+            let words = self.visible_words();
+            if let Some(speech) = self.speech.as_mut() {
+                speech.words = words;
+                speech.current = None;
+            }
+            if self.speech.as_ref().is_some_and(|speech| !speech.words.is_empty()) {
+                self.read_aloud(0, hub, rq, context);
+            } else {
+                self.stop_reading_aloud(rq);
+            }
+            return;
+        }
+
+        let texts: Vec<&str> = words.iter().map(|(location, i)| self.text[location][*i].text.as_str()).collect();
+        let utterance = sentences(&texts).into_iter()
+                                         .find(|sentence| sentence.contains(&index))
+                                         .unwrap_or(index..index + 1);
+        let text = texts[utterance.clone()].iter().map(|word| word.to_string()).collect();
+        let language = self.speech_language(context);
+        let token = self.speaker.speak(text, &language, &context.settings.speech, hub);
+        if let Some(speech) = self.speech.as_mut() {
+            speech.resume = utterance.end;
+            speech.utterance = utterance;
+            speech.token = token;
+        }
+    }
+
+    // Speaks the given word alone, then its sentence.
+    fn replay_word(&mut self, index: usize, hub: &Hub, context: &mut Context) {
+        let word = match self.speech.as_ref().and_then(|speech| speech.words.get(index)) {
+            Some(&(location, i)) => self.text[&location][i].text.clone(),
+            None => return,
+        };
+        let language = self.speech_language(context);
+        let token = self.speaker.speak(vec![word], &language, &context.settings.speech, hub);
+        if let Some(speech) = self.speech.as_mut() {
+            speech.utterance = index..index + 1;
+            speech.resume = index;
+            speech.token = token;
+        }
+    }
+
+    fn toggle_read_aloud(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.speech.is_some() {
+            self.stop_reading_aloud(rq);
+            return;
+        }
+
+        let words = self.visible_words();
+        if words.is_empty() {
+            let notif = Notification::new("No text to read aloud.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        }
+
+        self.speech = Some(Speech {
+            words,
+            utterance: 0..0,
+            resume: 0,
+            current: None,
+            token: 0,
+        });
+        self.read_aloud(0, hub, rq, context);
+    }
+
+    fn stop_reading_aloud(&mut self, rq: &mut RenderQueue) {
+        self.speaker.stop();
+        self.set_speech_word(None, rq);
+        self.speech = None;
+    }
+
+    fn set_speech_voice(&mut self, speed: Option<f32>, pitch: Option<f32>, context: &mut Context) {
+        let key = language_key(&self.speech_language(context));
+        let voice = context.settings.speech.languages.entry(key).or_default();
+        if let Some(speed) = speed {
+            voice.speed = speed;
+        }
+        if let Some(pitch) = pitch {
+            voice.pitch = pitch;
+        }
+    }
+
+    fn translation_languages(&self, context: &Context) -> (String, String) {
+        let reader = self.info.reader.as_ref();
+        language_pair(reader.and_then(|r| r.translation_source.as_ref()),
+                      reader.and_then(|r| r.translation_target.as_ref()),
+                      &self.info.language,
+                      &context.settings.translation)
+    }
+
+    fn translate(&mut self, text: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let (source, target) = self.translation_languages(context);
+        // The bilingual dictionaries are used when the network is unavailable.
+        let translation = if context.settings.translation.backend == TranslationBackend::Dictionary || !context.online {
+            translate_offline(text, &source, &target, &context.settings.translation, &mut context.dictionaries)
+                .unwrap_or_else(|e| {
+                    eprintln!("Can't translate {}: {:#}.", text, e);
+                    "No translations found.".to_string()
+                })
+        } else {
+            translate_online(text.to_string(), source.clone(), target.clone(), &context.settings.translation, hub);
+            "Translating…".to_string()
+        };
+
+        if let Some(index) = locate_by_id(self, ViewId::Translation) {
+            if let Some(translation_view) = self.children[index].downcast_mut::<TranslationView>() {
+                translation_view.update(&translation, &source, &target, rq);
+            }
+        } else {
+            let translation_view = TranslationView::new(text, &translation, &source, &target, context);
+            rq.add(RenderData::new(translation_view.id(), *translation_view.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(translation_view) as Box<dyn View>);
+        }
+    }
+
+    fn share(&mut self, payload: Payload, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let message = if !context.online {
+            "The network is unavailable.".to_string()
+        } else {
+            share(payload, &self.info.title(), &context.settings.share, hub);
+            "Sharing…".to_string()
+        };
+        let notif = Notification::new(message, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    // Saves the rendered page that comes first on the screen.
+    fn save_snapshot(&self, path: &str) -> Option<PathBuf> {
+        let chunk = self.chunks.first()?;
+        let resource = self.cache.get(&chunk.location)?;
+        let result = if resource.tiled {
+            let mut doc = self.doc.lock().unwrap();
+            let (pixmap, _) = build_pixmap(&self.rect, doc.as_mut(), chunk.location)?;
+            pixmap.save(path)
+        } else {
+            resource.pixmap.save(path)
+        };
+        result.map_err(|e| eprintln!("Can't save the page snapshot: {:#}.", e))
+                .ok()?;
+        Some(PathBuf::from(path))
+    }
+
+    fn translated_text(&self) -> Option<String> {
+        locate_by_id(self, ViewId::Translation)
+            .and_then(|index| self.children[index].downcast_ref::<TranslationView>())
+            .map(|translation_view| translation_view.text().to_string())
+    }
+
+    fn toggle_translation(&mut self, enable: Option<bool>, rq: &mut RenderQueue) {
+        if let Some(index) = locate_by_id(self, ViewId::Translation) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        }
+    }
+
+    fn toggle_translation_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::TranslationMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let (source, target) = self.translation_languages(context);
+            let mut languages: Vec<String> = LANGUAGES.iter().map(|l| l.to_string()).collect();
+            for lang in [&source, &target] {
+                if lang != AUTO_LANGUAGE && !languages.contains(lang) {
+                    languages.push(lang.clone());
+                }
+            }
+
+            let mut sources = vec![EntryKind::RadioButton("Auto".to_string(),
+                                                          EntryId::SetTranslationSource(AUTO_LANGUAGE.to_string()),
+                                                          source == AUTO_LANGUAGE)];
+            sources.extend(languages.iter().map(|lang| {
+                EntryKind::RadioButton(lang.clone(), EntryId::SetTranslationSource(lang.clone()), *lang == source)
+            }));
+            let targets = languages.iter().map(|lang| {
+                EntryKind::RadioButton(lang.clone(), EntryId::SetTranslationTarget(lang.clone()), *lang == target)
+            }).collect();
+
+            let entries = vec![EntryKind::SubMenu("From".to_string(), sources),
+                               EntryKind::SubMenu("To".to_string(), targets)];
+            let translation_menu = Menu::new(rect, ViewId::TranslationMenu, MenuKind::Contextual, entries, context);
+            rq.add(RenderData::new(translation_menu.id(), *translation_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(translation_menu) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_name_page(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::NamePage) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+
+            if self.focus.map(|focus_id| focus_id == ViewId::NamePageInput).unwrap_or(false) {
+                self.toggle_keyboard(false, None, hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let name_page = NamedInput::new("Name page".to_string(), ViewId::NamePage, ViewId::NamePageInput, 4, context);
+            rq.add(RenderData::new(name_page.id(), *name_page.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(ViewId::NamePageInput))).ok();
+
+            self.children.push(Box::new(name_page) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_go_to_page(&mut self, enable: Option<bool>, id: ViewId, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let (text, input_id) = if id == ViewId::GoToPage {
+            ("Go to page", ViewId::GoToPageInput)
+        } else {
+            ("Go to results page", ViewId::GoToResultsPageInput)
+        };
+
+        if let Some(index) = locate_by_id(self, id) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+
+            if self.focus.map(|focus_id| focus_id == input_id).unwrap_or(false) {
+                self.toggle_keyboard(false, None, hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let go_to_page = NamedInput::new(text.to_string(), id, input_id, 4, context);
+            rq.add(RenderData::new(go_to_page.id(), *go_to_page.rect(), UpdateMode::Gui));
+            hub.send(Event::Focus(Some(input_id))).ok();
+
+            self.children.push(Box::new(go_to_page) as Box<dyn View>);
+        }
+    }
+
+    pub fn toggle_annotation_menu(&mut self, annot: &Annotation, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::AnnotationMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let sel = annot.selection;
+            let mut entries = Vec::new();
+
+            if annot.note.is_empty() {
+                entries.push(EntryKind::Command("Remove Highlight".to_string(), EntryId::RemoveAnnotation(sel)));
+                entries.push(EntryKind::Separator);
+                entries.push(EntryKind::Command("Add Note".to_string(), EntryId::EditAnnotationNote(sel)));
+            } else {
+                entries.push(EntryKind::Command("Remove Annotation".to_string(), EntryId::RemoveAnnotation(sel)));
+                entries.push(EntryKind::Separator);
+                entries.push(EntryKind::Command("Edit Note".to_string(), EntryId::EditAnnotationNote(sel)));
+                entries.push(EntryKind::Command("Remove Note".to_string(), EntryId::RemoveAnnotationNote(sel)));
+            }
+
+            let selection_menu = Menu::new(rect, ViewId::AnnotationMenu, MenuKind::Contextual, entries, context);
+            rq.add(RenderData::new(selection_menu.id(), *selection_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(selection_menu) as Box<dyn View>);
+        }
+    }
+
+    pub fn toggle_selection_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::SelectionMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let mut entries = vec![
+                EntryKind::Command("Highlight".to_string(), EntryId::HighlightSelection),
+                EntryKind::Command("Add Note".to_string(), EntryId::AnnotateSelection)
+            ];
+
+            entries.push(EntryKind::Separator);
+            entries.push(EntryKind::Command("Define".to_string(), EntryId::DefineSelection));
+            entries.push(EntryKind::Command("Translate".to_string(), EntryId::TranslateSelection));
+            entries.push(EntryKind::Command("Search".to_string(), EntryId::SearchForSelection));
+            entries.push(EntryKind::SubMenu("Share".to_string(), vec![
+                EntryKind::Command("Quote".to_string(), EntryId::ShareSelection),
+                EntryKind::Command("Page Snapshot".to_string(), EntryId::SharePage)]));
+
+            if self.info.reader.as_ref().map_or(false, |r| !r.page_names.is_empty()) {
+                entries.push(EntryKind::Command("Go To".to_string(), EntryId::GoToSelectedPageName));
+            }
 
             entries.push(EntryKind::Separator);
             entries.push(EntryKind::Command("Adjust Selection".to_string(), EntryId::AdjustSelection));
@@ -1848,10 +3046,43 @@ impl Reader {
                 entries.push(EntryKind::Command("Annotations".to_string(), EntryId::Annotations));
             }
 
+            let notes = self.notes_entries();
+            if !notes.is_empty() {
+                entries.push(EntryKind::SubMenu("Notes".to_string(), notes));
+            }
+
+            entries.push(EntryKind::Command("Go To".to_string(), EntryId::GoToDestination));
+            entries.push(EntryKind::Command("Annotate Page".to_string(), EntryId::AnnotatePage));
+            if !self.ephemeral {
+                entries.push(EntryKind::Command("Statistics".to_string(), EntryId::ShowStatistics(self.info.file.path.clone())));
+            }
+
+            if let Some(fp) = context.library.paths.get(&self.info.file.path).cloned() {
+                let handoff = Handoff::new(fp, self.current_page, &self.info.identifier);
+                let caption = format!("{} — {}", self.info.title(), handoff.to_text());
+                entries.push(EntryKind::Command("Hand Off".to_string(), EntryId::ShowQrCode(handoff.to_text(), caption)));
+            }
+
             if self.info.reader.as_ref().map_or(false, |r| !r.bookmarks.is_empty()) {
                 entries.push(EntryKind::Command("Bookmarks".to_string(), EntryId::Bookmarks));
             }
 
+            let voice = speech::voice(&context.settings.speech, &self.speech_language(context));
+            let speeds = [50, 75, 100, 125, 150].iter().map(|&percent| {
+                EntryKind::RadioButton(format!("{}%", percent),
+                                       EntryId::SetSpeechSpeed(percent),
+                                       (voice.speed * 100.0).round() as u16 == percent)
+            }).collect::<Vec<EntryKind>>();
+            let pitches = [("Low", 75), ("Normal", 100), ("High", 125)].iter().map(|&(name, percent)| {
+                EntryKind::RadioButton(name.to_string(),
+                                       EntryId::SetSpeechPitch(percent),
+                                       (voice.pitch * 100.0).round() as u16 == percent)
+            }).collect::<Vec<EntryKind>>();
+            entries.push(EntryKind::SubMenu("Read Aloud".to_string(), vec![
+                EntryKind::CheckBox("Read Aloud".to_string(), EntryId::ToggleReadAloud, self.speech.is_some()),
+                EntryKind::SubMenu("Speed".to_string(), speeds),
+                EntryKind::SubMenu("Pitch".to_string(), pitches)]));
+
             if !entries.is_empty() {
                 entries.push(EntryKind::Separator);
             }
@@ -1860,6 +3091,36 @@ impl Reader {
                                              EntryId::ToggleDithered,
                                              context.fb.dithered()));
 
+            if !self.reflowable {
+                entries.push(EntryKind::Command("Tone Curve".to_string(), EntryId::ToneCurves));
+                entries.push(EntryKind::Command("Recognize Text".to_string(), EntryId::RecognizeText));
+            }
+
+            let refresh_policy = self.info.reader.as_ref().and_then(|r| r.refresh_policy);
+            let mut policies = vec![EntryKind::RadioButton("Default".to_string(),
+                                                           EntryId::SetRefreshPolicy(None),
+                                                           refresh_policy.is_none())];
+            for policy in [RefreshPolicy::Interval, RefreshPolicy::Chapter, RefreshPolicy::Never] {
+                policies.push(EntryKind::RadioButton(policy.label().to_string(),
+                                                     EntryId::SetRefreshPolicy(Some(policy)),
+                                                     refresh_policy == Some(policy)));
+            }
+            entries.push(EntryKind::SubMenu("Full Refresh".to_string(), policies));
+
+            if !self.reflowable {
+                let manga = self.info.reader.as_ref().is_some_and(|r| r.manga);
+                entries.push(EntryKind::CheckBox("Manga Mode".to_string(),
+                                                 EntryId::ToggleManga,
+                                                 manga));
+            }
+
+            if context.has_orientation_sensor() {
+                let keep_orientation = self.info.reader.as_ref().is_some_and(|r| r.keep_orientation);
+                entries.push(EntryKind::CheckBox("Keep Orientation".to_string(),
+                                                 EntryId::ToggleKeepOrientation,
+                                                 keep_orientation));
+            }
+
             let mut title_menu = Menu::new(rect, ViewId::TitleMenu, MenuKind::DropDown, entries, context);
             title_menu.child_mut(1)
                       .downcast_mut::<MenuEntry>().unwrap()
@@ -1871,7 +3132,8 @@ impl Reader {
     }
 
     fn toggle_font_family_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
-        if let Some(index) = locate_by_id(self, ViewId::FontFamilyMenu) {
+        if let Some(index) = locate_by_id(self, ViewId::FontFamilyMenu)
+                                 .or_else(|| locate_by_id(self, ViewId::FontFamilyPicker)) {
             if let Some(true) = enable {
                 return;
             }
@@ -1890,6 +3152,21 @@ impl Reader {
                                      .and_then(|r| r.font_family.clone())
                                      .unwrap_or_else(|| context.settings.reader.font_family.clone());
             families.insert(DEFAULT_FONT_FAMILY.to_string());
+
+            if families.len() > FONT_FAMILY_MENU_MAX_ENTRIES {
+                let current = families.iter().position(|f| *f == current_family);
+                let items = families.into_iter()
+                                    .map(|f| (f.clone(), EntryId::SetFontFamily(f)))
+                                    .collect();
+                let dpi = CURRENT_DEVICE.dpi;
+                let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+                let picker_rect = rect![self.rect.min.x + small_height / 2, self.rect.min.y + small_height,
+                                        self.rect.max.x - small_height / 2, self.rect.max.y - small_height];
+                let picker = ListPicker::new(picker_rect, ViewId::FontFamilyPicker, items, current, rq);
+                self.children.push(Box::new(picker) as Box<dyn View>);
+                return;
+            }
+
             let entries = families.iter().map(|f| EntryKind::RadioButton(f.clone(),
                                                                          EntryId::SetFontFamily(f.clone()),
                                                                          *f == current_family)).collect();
@@ -2052,14 +3329,28 @@ impl Reader {
             let reflowable = self.reflowable;
             let margin_width = self.info.reader.as_ref()
                                    .and_then(|r| if reflowable { r.margin_width } else { r.screen_margin_width })
-                                   .unwrap_or_else(|| if reflowable { context.settings.reader.margin_width } else { 0 });
+                                   .unwrap_or_else(|| context.settings.reader.margin_width(reflowable));
             let min_margin_width = context.settings.reader.min_margin_width;
             let max_margin_width = context.settings.reader.max_margin_width;
-            let entries = (min_margin_width..=max_margin_width).map(|mw|
+            let mut entries: Vec<EntryKind> = (min_margin_width..=max_margin_width).map(|mw|
                 EntryKind::RadioButton(format!("{}", mw),
                                        EntryId::SetMarginWidth(mw),
                                        mw == margin_width)
             ).collect();
+            if reflowable {
+                let vertical_margin_width = self.info.reader.as_ref().and_then(|r| r.vertical_margin_width);
+                let mut vertical_entries = vec![EntryKind::RadioButton("Same".to_string(),
+                                                                       EntryId::SetVerticalMarginWidth(None),
+                                                                       vertical_margin_width.is_none()),
+                                                EntryKind::Separator];
+                vertical_entries.extend((min_margin_width..=max_margin_width).map(|mw|
+                    EntryKind::RadioButton(format!("{}", mw),
+                                           EntryId::SetVerticalMarginWidth(Some(mw)),
+                                           vertical_margin_width == Some(mw))
+                ));
+                entries.push(EntryKind::Separator);
+                entries.push(EntryKind::SubMenu("Vertical".to_string(), vertical_entries));
+            }
             let margin_width_menu = Menu::new(rect, ViewId::MarginWidthMenu, MenuKind::DropDown, entries, context);
             rq.add(RenderData::new(margin_width_menu.id(), *margin_width_menu.rect(), UpdateMode::Gui));
             self.children.push(Box::new(margin_width_menu) as Box<dyn View>);
@@ -2082,10 +3373,18 @@ impl Reader {
             let has_name = self.info.reader.as_ref()
                                .map_or(false, |r| r.page_names.contains_key(&self.current_page));
 
-            let mut entries = vec![EntryKind::Command("Name".to_string(), EntryId::SetPageName)];
+            let mut entries = vec![EntryKind::Command("Skim".to_string(), EntryId::Skim),
+                                   EntryKind::Command("Thumbnails".to_string(), EntryId::Thumbnails),
+                                   EntryKind::Separator,
+                                   EntryKind::Command("Name".to_string(), EntryId::SetPageName)];
             if has_name {
                 entries.push(EntryKind::Command("Remove Name".to_string(), EntryId::RemovePageName));
             }
+            if !self.reflowable {
+                entries.push(EntryKind::Command("Use as Cover".to_string(),
+                                                EntryId::SetCover(self.info.file.path.clone(),
+                                                                  Some(Cover::Page(self.current_page)))));
+            }
             let names = self.info.reader.as_ref()
                             .map(|r| r.page_names.iter()
                                       .map(|(i, s)| EntryKind::Command(s.to_string(), EntryId::GoTo(*i)))
@@ -2095,6 +3394,12 @@ impl Reader {
                 entries.push(EntryKind::Separator);
                 entries.push(EntryKind::SubMenu("Go To".to_string(), names));
             }
+            if !self.landmarks.is_empty() {
+                let landmarks = self.landmarks.iter().enumerate()
+                                    .map(|(i, landmark)| EntryKind::Command(landmark.title.clone(), EntryId::GoToLandmark(i)))
+                                    .collect::<Vec<EntryKind>>();
+                entries.push(EntryKind::SubMenu("Landmarks".to_string(), landmarks));
+            }
 
             let page_menu = Menu::new(rect, ViewId::PageMenu, MenuKind::DropDown, entries, context);
             rq.add(RenderData::new(page_menu.id(), *page_menu.rect(), UpdateMode::Gui));
@@ -2194,7 +3499,7 @@ impl Reader {
             }
         }
 
-        self.cache.clear();
+        self.clear_cache();
         self.text.clear();
         self.update(None, hub, rq, context);
         self.update_tool_bar(rq, context);
@@ -2225,7 +3530,7 @@ impl Reader {
             }
         }
 
-        self.cache.clear();
+        self.clear_cache();
         self.text.clear();
         self.update(None, hub, rq, context);
         self.update_tool_bar(rq, context);
@@ -2262,7 +3567,7 @@ impl Reader {
             }
         }
 
-        self.cache.clear();
+        self.clear_cache();
         self.text.clear();
         self.update(None, hub, rq, context);
         self.update_tool_bar(rq, context);
@@ -2293,7 +3598,7 @@ impl Reader {
             }
         }
 
-        self.cache.clear();
+        self.clear_cache();
         self.text.clear();
         self.update(None, hub, rq, context);
         self.update_tool_bar(rq, context);
@@ -2309,7 +3614,7 @@ impl Reader {
             if self.reflowable {
                 r.margin_width = Some(width);
             } else {
-                if width == 0 {
+                if width == context.settings.reader.margin_width(false) {
                     r.screen_margin_width = None;
                 } else {
                     r.screen_margin_width = Some(width);
@@ -2344,11 +3649,132 @@ impl Reader {
             self.view_port.margin_width = next_margin_width;
         }
 
-        self.text.clear();
-        self.cache.clear();
-        self.update(None, hub, rq, context);
-        self.update_tool_bar(rq, context);
-        self.update_bottom_bar(rq);
+        self.text.clear();
+        self.clear_cache();
+        self.update(None, hub, rq, context);
+        self.update_tool_bar(rq, context);
+        self.update_bottom_bar(rq);
+    }
+
+    // Sets the top and bottom margins independently of the left and right ones.
+    fn set_vertical_margin_width(&mut self, width: Option<i32>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if Arc::strong_count(&self.doc) > 1 || !self.reflowable {
+            return;
+        }
+
+        if let Some(ref mut r) = self.info.reader {
+            r.vertical_margin_width = width;
+        }
+
+        {
+            let mut doc = self.doc.lock().unwrap();
+            doc.set_vertical_margin_width(width);
+
+            if self.synthetic {
+                let current_page = self.current_page.min(doc.pages_count() - 1);
+                if let Some(location) =  doc.resolve_location(Location::Exact(current_page)) {
+                    self.current_page = location;
+                }
+            } else {
+                self.pages_count = doc.pages_count();
+                self.current_page = self.current_page.min(self.pages_count - 1);
+            }
+        }
+
+        self.text.clear();
+        self.clear_cache();
+        self.update(None, hub, rq, context);
+        self.update_bottom_bar(rq);
+    }
+
+    // Taps close to the edges held by the hand are ignored.
+    fn in_dead_zone(&self, pt: Point, context: &Context) -> bool {
+        let zones = &context.settings.reader.dead_zones;
+        let width = |mm: f32| mm_to_px(mm, CURRENT_DEVICE.dpi) as i32;
+        pt.y < self.rect.min.y + width(zones.top) ||
+        pt.x >= self.rect.max.x - width(zones.right) ||
+        pt.y >= self.rect.max.y - width(zones.bottom) ||
+        pt.x < self.rect.min.x + width(zones.left)
+    }
+
+    // The frontlight level set by a vertical swipe that starts at `start`, if any.
+    fn edge_light(&self, start: Point, dir: Dir, context: &Context) -> Option<SliderId> {
+        if !context.settings.reader.edge_light_swipes || !context.settings.frontlight ||
+           dir.axis() != Axis::Vertical {
+            return None;
+        }
+        match Region::from_point(start, self.rect,
+                                 context.settings.reader.strip_width(self.reflowable),
+                                 context.settings.reader.corner_width(self.reflowable)) {
+            Region::Strip(Dir::West) => Some(SliderId::LightIntensity),
+            Region::Strip(Dir::East) if CURRENT_DEVICE.has_natural_light() => Some(SliderId::LightWarmth),
+            _ => None,
+        }
+    }
+
+    // Swiping over the full height of the screen moves the level from one end to the other.
+    fn swipe_light(&mut self, slider_id: SliderId, start: Point, end: Point, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let delta = 100.0 * (start.y - end.y) as f32 / self.rect.height() as f32;
+        let levels = context.frontlight.levels();
+        let (value, side) = if slider_id == SliderId::LightWarmth {
+            let value = (levels.warmth + delta).clamp(0.0, 100.0);
+            context.frontlight.set_warmth(value);
+            (value, Dir::East)
+        } else {
+            let value = (levels.intensity + delta).clamp(0.0, 100.0);
+            context.frontlight.set_intensity(value);
+            // The intensity might have been moved into the flicker-free range.
+            (context.frontlight.levels().intensity, Dir::West)
+        };
+
+        if let Some(index) = rlocate::<LightGauge>(self) {
+            if self.children[index].downcast_ref::<LightGauge>().unwrap().slider_id() == slider_id {
+                self.children[index].downcast_mut::<LightGauge>().unwrap().update(value, hub, rq);
+                return;
+            }
+            rq.add(RenderData::expose(*self.children[index].rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        }
+
+        let gauge = LightGauge::new(slider_id, value, side, hub, rq, context);
+        self.children.push(Box::new(gauge) as Box<dyn View>);
+    }
+
+    // Shows the next or the previous recent book, which is opened once the selection settles.
+    fn cycle_recent_books(&mut self, dir: CycleDir, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate::<BookSwitcher>(self) {
+            self.children[index].downcast_mut::<BookSwitcher>().unwrap().cycle(dir, hub, rq, context);
+            return;
+        }
+
+        let books = context.library.recent(&self.info.file.path, MAX_RECENT_BOOKS)
+                           .into_iter().cloned().collect::<Vec<Info>>();
+        if books.is_empty() {
+            let notif = Notification::new("No other recent book.".to_string(), hub, rq, context);
+            self.children.push(Box::new(notif) as Box<dyn View>);
+            return;
+        }
+
+        let switcher = BookSwitcher::new(books, dir, hub, rq, context);
+        self.children.push(Box::new(switcher) as Box<dyn View>);
+    }
+
+    fn switch_book(&mut self, token: Id, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let Some(index) = locate::<BookSwitcher>(self) else {
+            return;
+        };
+        let switcher = self.children[index].downcast_ref::<BookSwitcher>().unwrap();
+        if switcher.token() != token {
+            return;
+        }
+        let path = switcher.selected().file.path.clone();
+        rq.add(RenderData::expose(*switcher.rect(), UpdateMode::Gui));
+        self.children.remove(index);
+        if let Some(info) = context.library.info_from_path(&path) {
+            self.quit(context);
+            hub.send(Event::Back).ok();
+            hub.send(Event::Open(Box::new(info))).ok();
+        }
     }
 
     fn toggle_bookmark(&mut self, rq: &mut RenderQueue) {
@@ -2400,7 +3826,7 @@ impl Reader {
         if reset_page_offset {
             self.view_port.page_offset = pt!(0, 0);
         }
-        self.cache.clear();
+        self.clear_cache();
         self.update(None, hub, rq, context);
     }
 
@@ -2443,7 +3869,7 @@ impl Reader {
                 *c.margin_mut(index) = margin.clone();
             }
         }
-        self.cache.clear();
+        self.clear_cache();
         self.update(None, hub, rq, context);
     }
 
@@ -2539,6 +3965,34 @@ impl Reader {
         Some(text)
     }
 
+    // The sentence that contains the given selection, on the current pages.
+    fn sentence_around(&self, sel: [TextLocation; 2]) -> Option<String> {
+        let mut words = self.text.values().flatten().collect::<Vec<&BoundedText>>();
+        words.sort_by_key(|bnd| bnd.location);
+        let start = words.iter().position(|bnd| bnd.location >= sel[0])?;
+        let end = words.iter().rposition(|bnd| bnd.location <= sel[1])?.max(start);
+        let is_final = |text: &str| text.trim_end_matches(|c: char| "\"'’”»)]".contains(c))
+                                        .ends_with(['.', '!', '?', '…']);
+        let first = words[..start].iter().rposition(|bnd| is_final(&bnd.text))
+                                  .map_or(0, |index| index + 1)
+                                  .max(start.saturating_sub(MAX_CONTEXT_WORDS));
+        let last = words[end..].iter().position(|bnd| is_final(&bnd.text))
+                               .map_or(words.len() - 1, |index| end + index)
+                               .min(end + MAX_CONTEXT_WORDS);
+        self.text_excerpt([words[first].location, words[last].location])
+    }
+
+    fn record_lookup(&self, query: &str, context: &mut Context) {
+        if self.ephemeral || !context.settings.dictionary.record_lookups {
+            return;
+        }
+        let sentence = self.selection.as_ref()
+                           .and_then(|sel| self.sentence_around([sel.start, sel.end]))
+                           .unwrap_or_default();
+        context.vocabulary.record(query, &self.info.language, &self.info.title(), &sentence);
+        context.save_vocabulary();
+    }
+
     fn selected_text(&self) -> Option<String> {
         self.selection.as_ref().and_then(|sel| self.text_excerpt([sel.start, sel.end]))
     }
@@ -2579,7 +4033,12 @@ impl Reader {
                         }
                     }
                     if let Some(rect) = rect_opt {
-                        rq.add(RenderData::new(self.id, rect, UpdateMode::Gui));
+                        // Includes the outline of the focused result.
+                        let padding = 2 * scale_by_dpi(THICKNESS_MEDIUM, CURRENT_DEVICE.dpi) as i32;
+                        let rect = rect![rect.min - pt!(padding), rect.max + pt!(padding)];
+                        if let Some(rect) = rect.intersection(&self.rect) {
+                            rq.add(RenderData::new(self.id, rect, UpdateMode::Gui));
+                        }
                     }
                 }
             }
@@ -2590,6 +4049,27 @@ impl Reader {
         self.selection.as_ref().and_then(|sel| self.text_rect([sel.start, sel.end]))
     }
 
+    // The notes of the document, in reading order, with their actions.
+    fn notes_entries(&self) -> Vec<EntryKind> {
+        let mut annotations = self.info.reader.as_ref()
+                                  .map(|r| r.annotations.iter().filter(|a| !a.note.is_empty()).collect::<Vec<_>>())
+                                  .unwrap_or_default();
+        annotations.sort_by_key(|annot| annot.selection[0]);
+        annotations.into_iter().map(|annot| {
+            let sel = annot.selection;
+            let mut label = annot.note.lines().next().unwrap_or_default().to_string();
+            if let Some((index, _)) = label.char_indices().nth(NOTE_LABEL_LENGTH) {
+                label.truncate(index);
+                label.push('…');
+            }
+            EntryKind::SubMenu(label,
+                               vec![EntryKind::Command("Go To".to_string(), EntryId::GoTo(sel[0].location())),
+                                    EntryKind::Separator,
+                                    EntryKind::Command("Edit Note".to_string(), EntryId::EditAnnotationNote(sel)),
+                                    EntryKind::Command("Remove Note".to_string(), EntryId::RemoveAnnotationNote(sel))])
+        }).collect()
+    }
+
     fn find_annotation_ref(&mut self, sel: [TextLocation; 2]) -> Option<&Annotation> {
         self.info.reader.as_ref()
             .and_then(|r| r.annotations.iter()
@@ -2612,26 +4092,54 @@ impl Reader {
         rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
     }
 
+    // Records the part of the current focus session spent in this document.
+    fn end_focus_session(&mut self) {
+        if let Some((start, page_turns)) = self.focus_start.take() {
+            let minutes = (Local::now() - start).num_minutes().max(0) as u32;
+            if let Some(r) = self.info.reader.as_mut().filter(|_| minutes > 0) {
+                r.focus_sessions.push(FocusSession {
+                    start,
+                    minutes,
+                    page_turns: self.page_turns - page_turns,
+                });
+            }
+        }
+    }
+
     fn quit(&mut self, context: &mut Context) {
         if let Some(ref mut s) = self.search {
             s.running.store(false, AtomicOrdering::Relaxed);
         }
 
+        self.speaker.stop();
+
         if self.ephemeral {
             return;
         }
 
+        self.end_focus_session();
+
+        // The values of the profile aren't saved with the book.
+        let profile = context.settings.reader.profile(self.reflowable);
+        let zoom_mode = profile.zoom_mode.unwrap_or(ZoomMode::FitToPage);
+        let contrast_exponent = profile.contrast_exponent.unwrap_or(DEFAULT_CONTRAST_EXPONENT);
+        let contrast_gray = profile.contrast_gray.unwrap_or(DEFAULT_CONTRAST_GRAY);
+
         if let Some(ref mut r) = self.info.reader {
             r.current_page = self.current_page;
             r.pages_count = self.pages_count;
             r.finished = self.finished;
             r.dithered = context.fb.dithered();
 
-            if self.view_port.zoom_mode == ZoomMode::FitToPage {
+            if self.view_port.zoom_mode == zoom_mode {
                 r.zoom_mode = None;
-                r.page_offset = None;
             } else {
                 r.zoom_mode = Some(self.view_port.zoom_mode);
+            }
+
+            if self.view_port.zoom_mode == ZoomMode::FitToPage {
+                r.page_offset = None;
+            } else {
                 r.page_offset = Some(self.view_port.page_offset);
             }
 
@@ -2643,9 +4151,9 @@ impl Reader {
 
             r.rotation = Some(CURRENT_DEVICE.to_canonical(context.display.rotation));
 
-            if (self.contrast.exponent - DEFAULT_CONTRAST_EXPONENT).abs() > f32::EPSILON {
+            if (self.contrast.exponent - contrast_exponent).abs() > f32::EPSILON {
                 r.contrast_exponent = Some(self.contrast.exponent);
-                if (self.contrast.gray - DEFAULT_CONTRAST_GRAY).abs() > f32::EPSILON {
+                if (self.contrast.gray - contrast_gray).abs() > f32::EPSILON {
                     r.contrast_gray = Some(self.contrast.gray);
                 } else {
                     r.contrast_gray = None;
@@ -2657,6 +4165,13 @@ impl Reader {
 
             context.library.sync_reader_info(&self.info.file.path, r);
         }
+
+        if let Some(path) = context.library.layout_cache_path(&self.info.file.path) {
+            let doc = self.doc.lock().unwrap();
+            if let Err(e) = doc.save_layout_cache(&path) {
+                eprintln!("Can't save layout cache: {:#}.", e);
+            }
+        }
     }
 
     fn scale_page(&mut self, center: Point, factor: f32, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
@@ -2689,18 +4204,27 @@ impl Reader {
 impl View for Reader {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
-            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 => {
+            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 && context.rotate_gesture_enabled() => {
                 let (_, dir) = CURRENT_DEVICE.mirroring_scheme();
                 let n = (4 + (context.display.rotation - dir * quarter_turns)) % 4;
                 hub.send(Event::Select(EntryId::Rotate(n))).ok();
                 true
             },
             Event::Gesture(GestureEvent::Swipe { dir, start, end }) if self.rect.includes(start) => {
+                if let Some(slider_id) = self.edge_light(start, dir, context) {
+                    self.swipe_light(slider_id, start, end, hub, rq, context);
+                    return true;
+                }
                 match self.view_port.zoom_mode {
                     ZoomMode::FitToPage | ZoomMode::FitToWidth => {
+                        let (west, east) = if self.is_manga() {
+                            (CycleDir::Previous, CycleDir::Next)
+                        } else {
+                            (CycleDir::Next, CycleDir::Previous)
+                        };
                         match dir {
-                            Dir::West => self.go_to_neighbor(CycleDir::Next, hub, rq, context),
-                            Dir::East => self.go_to_neighbor(CycleDir::Previous, hub, rq, context),
+                            Dir::West => self.go_to_neighbor(west, hub, rq, context),
+                            Dir::East => self.go_to_neighbor(east, hub, rq, context),
                             Dir::South | Dir::North => self.vertical_scroll(start.y - end.y, hub, rq, context),
                         };
                     },
@@ -2749,6 +4273,17 @@ impl View for Reader {
                 self.scale_page(center, factor, hub, rq, context);
                 true
             },
+            Event::Gesture(GestureEvent::MultiSwipe { dir, starts, .. }) if !self.ephemeral &&
+                                                                           dir.axis() == Axis::Horizontal &&
+                                                                           self.rect.includes(starts[0]) => {
+                let dir = if dir == Dir::West { CycleDir::Next } else { CycleDir::Previous };
+                self.cycle_recent_books(dir, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SwitchBook(token)) => {
+                self.switch_book(token, hub, rq, context);
+                true
+            },
             Event::Gesture(GestureEvent::Arrow { dir, .. }) => {
                 match dir {
                     Dir::West => {
@@ -2777,6 +4312,33 @@ impl View for Reader {
                 }
                 true
             },
+            Event::Gesture(GestureEvent::Letter(letter, _)) => {
+                let action = context.settings.reader.letter_gestures.get(&letter.to_string()).copied();
+                match action {
+                    Some(LetterAction::Search) => {
+                        self.search_direction = LinearDir::Forward;
+                        self.toggle_search_bar(true, hub, rq, context);
+                    },
+                    Some(LetterAction::ToggleBookmark) => self.toggle_bookmark(rq),
+                    Some(LetterAction::TableOfContents) => {
+                        hub.send(Event::Show(ViewId::TableOfContents)).ok();
+                    },
+                    Some(LetterAction::GoToPage) => {
+                        hub.send(Event::Toggle(ViewId::GoToPage)).ok();
+                    },
+                    Some(LetterAction::PreviousChapter) => self.go_to_chapter(CycleDir::Previous, hub, rq, context),
+                    Some(LetterAction::NextChapter) => self.go_to_chapter(CycleDir::Next, hub, rq, context),
+                    Some(LetterAction::ToggleBars) => self.toggle_bars(None, hub, rq, context),
+                    Some(LetterAction::ToggleInverted) => {
+                        hub.send(Event::Select(EntryId::ToggleInverted)).ok();
+                    },
+                    Some(LetterAction::ToggleDithered) => {
+                        hub.send(Event::Select(EntryId::ToggleDithered)).ok();
+                    },
+                    None => (),
+                }
+                true
+            },
             Event::Gesture(GestureEvent::Corner { dir, .. }) => {
                 match dir {
                     DiagDir::NorthWest => self.go_to_bookmark(CycleDir::Previous, hub, rq, context),
@@ -2843,14 +4405,14 @@ impl View for Reader {
                             if self.search.is_none() {
                                 self.go_to_neighbor(CycleDir::Previous, hub, rq, context);
                             } else {
-                                self.go_to_results_neighbor(CycleDir::Previous, hub, rq, context);
+                                self.go_to_result(CycleDir::Previous, hub, rq, context);
                             }
                         },
                         ButtonCode::Forward => {
                             if self.search.is_none() {
                                 self.go_to_neighbor(CycleDir::Next, hub, rq, context);
                             } else {
-                                self.go_to_results_neighbor(CycleDir::Next, hub, rq, context);
+                                self.go_to_result(CycleDir::Next, hub, rq, context);
                             }
                         },
                         _ => (),
@@ -3069,10 +4631,22 @@ impl View for Reader {
                 true
             },
             Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
-                if self.focus.is_some() {
+                if self.focus.is_some() || self.in_dead_zone(center, context) {
                     return true;
                 }
 
+                if let Some(speech) = self.speech.as_ref() {
+                    let dmax = (scale_by_dpi(RECT_DIST_JITTER, CURRENT_DEVICE.dpi) as i32).pow(2) as u32;
+                    let nearest = speech.words.iter().enumerate()
+                                        .filter_map(|(index, &word)| self.speech_word_rect(word).map(|rect| (index, center.rdist2(&rect))))
+                                        .filter(|&(_, d)| d < dmax)
+                                        .min_by_key(|&(_, d)| d);
+                    if let Some((index, _)) = nearest {
+                        self.replay_word(index, hub, context);
+                        return true;
+                    }
+                }
+
                 let mut nearest_link = None;
                 let mut dmin = u32::MAX;
                 let dmax = (scale_by_dpi(RECT_DIST_JITTER, CURRENT_DEVICE.dpi) as i32).pow(2) as u32;
@@ -3109,7 +4683,7 @@ impl View for Reader {
                         }
                     } else if let Some(caps) = pdf_page.captures(&link.text) {
                         if let Ok(index) = caps[1].parse::<usize>() {
-                            self.go_to_page(index.saturating_sub(1), true, hub, rq, context);
+                            self.jump_to_page(index.saturating_sub(1), hub, rq, context);
                         }
                     } else {
                         let mut doc = self.doc.lock().unwrap();
@@ -3143,8 +4717,8 @@ impl View for Reader {
                     let dx = self.rect.width() as i32 - 2 * self.view_port.margin_width;
                     let dy = self.rect.height() as i32 - 2 * self.view_port.margin_width;
                     match Region::from_point(center, self.rect,
-                                             context.settings.reader.strip_width,
-                                             context.settings.reader.corner_width) {
+                                             context.settings.reader.strip_width(self.reflowable),
+                                             context.settings.reader.corner_width(self.reflowable)) {
                         Region::Corner(diag_dir) => {
                             match diag_dir {
                                 DiagDir::NorthEast => self.directional_scroll(pt!(dx, -dy), hub, rq, context),
@@ -3168,15 +4742,15 @@ impl View for Reader {
                 }
 
                 match Region::from_point(center, self.rect,
-                                         context.settings.reader.strip_width,
-                                         context.settings.reader.corner_width) {
+                                         context.settings.reader.strip_width(self.reflowable),
+                                         context.settings.reader.corner_width(self.reflowable)) {
                     Region::Corner(diag_dir) => {
                         match diag_dir {
                             DiagDir::NorthWest => self.go_to_last_page(hub, rq, context),
                             DiagDir::NorthEast => self.toggle_bookmark(rq),
                             DiagDir::SouthEast => {
                                 if self.search.is_none() {
-                                    match context.settings.reader.south_east_corner {
+                                    match context.settings.reader.south_east_corner(self.reflowable) {
                                         SouthEastCornerAction::GoToPage => {
                                             hub.send(Event::Toggle(ViewId::GoToPage)).ok();
                                         },
@@ -3203,10 +4777,12 @@ impl View for Reader {
                         }
                     },
                     Region::Strip(dir) => {
+                        // The strips are mirrored when the pages are turned from right to left.
+                        let dir = if self.is_manga() && dir.axis() == Axis::Horizontal { dir.opposite() } else { dir };
                         match dir {
                             Dir::West => {
                                 if self.search.is_none() {
-                                    match context.settings.reader.west_strip {
+                                    match context.settings.reader.west_strip(self.reflowable) {
                                         WestStripAction::PreviousPage => {
                                             self.go_to_neighbor(CycleDir::Previous, hub, rq, context);
                                         }
@@ -3216,12 +4792,12 @@ impl View for Reader {
                                         WestStripAction::None => (),
                                     }
                                 } else {
-                                    self.go_to_results_neighbor(CycleDir::Previous, hub, rq, context);
+                                    self.go_to_result(CycleDir::Previous, hub, rq, context);
                                 }
                             },
                             Dir::East => {
                                 if self.search.is_none() {
-                                    match context.settings.reader.east_strip {
+                                    match context.settings.reader.east_strip(self.reflowable) {
                                         EastStripAction::PreviousPage => {
                                             self.go_to_neighbor(CycleDir::Previous, hub, rq, context);
                                         }
@@ -3231,7 +4807,7 @@ impl View for Reader {
                                         EastStripAction::None => (),
                                     }
                                 } else {
-                                    self.go_to_results_neighbor(CycleDir::Next, hub, rq, context);
+                                    self.go_to_result(CycleDir::Next, hub, rq, context);
                                 }
                             },
                             Dir::South => match context.settings.reader.south_strip {
@@ -3245,7 +4821,13 @@ impl View for Reader {
                             Dir::North => self.toggle_bars(None, hub, rq, context),
                         }
                     },
-                    Region::Center => self.toggle_bars(None, hub, rq, context),
+                    Region::Center => {
+                        if let Some(source) = self.zoom_source_at(center) {
+                            self.toggle_zoom_view(Some(source), rq);
+                        } else {
+                            self.toggle_bars(None, hub, rq, context);
+                        }
+                    },
                 }
 
                 true
@@ -3303,6 +4885,7 @@ impl View for Reader {
                 if let Some(text) = self.selected_text() {
                     let query = text.trim_matches(|c: char| !c.is_alphanumeric()).to_string();
                     let language = self.info.language.clone();
+                    self.record_lookup(&query, context);
                     hub.send(Event::Select(EntryId::Launch(AppCmd::Dictionary { query, language }))).ok();
                 }
                 self.selection = None;
@@ -3317,6 +4900,18 @@ impl View for Reader {
                 self.load_pixmap(location);
                 true
             },
+            Event::PageStreamed(location) => {
+                // Forget the placeholder rendered before its arrival.
+                let mut prerendered = self.prerendered.lock().unwrap();
+                self.generation.fetch_add(1, AtomicOrdering::Relaxed);
+                prerendered.remove(&location);
+                drop(prerendered);
+                self.cache.remove(&location);
+                if self.chunks.iter().any(|chunk| chunk.location == location) {
+                    self.update(None, hub, rq, context);
+                }
+                true
+            },
             Event::Submit(ViewId::GoToPageInput, ref text) => {
                 let re = Regex::new(r#"^([-+'])?(.+)$"#).unwrap();
                 if let Some(caps) = re.captures(text) {
@@ -3325,6 +4920,10 @@ impl View for Reader {
                         if let Some(location) = self.find_page_by_name(&caps[2]) {
                             self.go_to_page(location, true, hub, rq, context);
                         }
+                    } else if let Some(number) = text.strip_prefix('#') {
+                        if let Some(location) = number.parse().ok().and_then(|n| self.find_chapter_by_number(n)) {
+                            self.go_to_page(location, true, hub, rq, context);
+                        }
                     } else {
                         if text == "_" {
                             let location = (context.rng.next_u64() % self.pages_count as u64) as usize;
@@ -3403,10 +5002,36 @@ impl View for Reader {
                 self.toggle_keyboard(false, None, hub, rq, context);
                 true
             },
+            Event::Submit(ViewId::BookReviewInput, ref text) => {
+                if let Some(r) = self.info.reader.as_mut() {
+                    r.review = text.trim().to_string();
+                }
+                self.toggle_book_review(Some(false), hub, rq, context);
+                // Update the label of the note button.
+                if locate_by_id(self, ViewId::EndOfBook).is_some() {
+                    self.toggle_end_of_book(Some(false), rq, context);
+                    self.toggle_end_of_book(Some(true), rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::SetBookRating(rating)) => {
+                if let Some(r) = self.info.reader.as_mut() {
+                    r.rating = Some(rating).filter(|&rating| rating > 0);
+                }
+                true
+            },
+            Event::Select(EntryId::EditBookReview) => {
+                self.toggle_book_review(Some(true), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ArchiveBook) => {
+                self.archive(hub, context);
+                true
+            },
             Event::Submit(ViewId::ReaderSearchInput, ref text) => {
                 match make_query(text) {
                     Some(query) => {
-                        self.search(text, query, hub, rq);
+                        self.search(text, query, hub, rq, context);
                         self.toggle_keyboard(false, None, hub, rq, context);
                         self.toggle_results_bar(true, rq, context);
                     },
@@ -3423,7 +5048,30 @@ impl View for Reader {
                 true
             },
             Event::GoTo(location) | Event::Select(EntryId::GoTo(location)) => {
-                self.go_to_page(location, true, hub, rq, context);
+                self.jump_to_page(location, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ReturnToPage(location)) => {
+                self.return_to_page(location, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SetCover(ref path, ref cover)) => {
+                set_cover(path, cover.clone(), hub, context);
+                self.info.cover = cover.clone();
+                let notif = Notification::new("Cover updated.".to_string(), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                true
+            },
+            Event::Select(EntryId::GoToLandmark(index)) => {
+                if let Some(landmark) = self.landmarks.get(index) {
+                    let offset_opt = {
+                        let mut doc = self.doc.lock().unwrap();
+                        doc.resolve_location(landmark.location.clone())
+                    };
+                    if let Some(offset) = offset_opt {
+                        self.jump_to_page(offset, hub, rq, context);
+                    }
+                }
                 true
             },
             Event::GoToLocation(ref location) => {
@@ -3432,7 +5080,7 @@ impl View for Reader {
                     doc.resolve_location(location.clone())
                 };
                 if let Some(offset) = offset_opt {
-                    self.go_to_page(offset, true, hub, rq, context);
+                    self.jump_to_page(offset, hub, rq, context);
                 }
                 true
             },
@@ -3444,6 +5092,10 @@ impl View for Reader {
                 self.go_to_results_neighbor(dir, hub, rq, context);
                 true
             },
+            Event::ThumbnailsPage(dir) => {
+                self.go_to_thumbnails_neighbor(dir, rq);
+                true
+            },
             Event::CropMargins(ref margin) => {
                 let current_page = self.current_page;
                 self.crop_margins(current_page, margin.as_ref(), hub, rq, context);
@@ -3489,6 +5141,10 @@ impl View for Reader {
                 toggle_clock_menu(self, rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
             Event::ToggleNear(ViewId::MarginCropperMenu, rect) => {
                 self.toggle_margin_cropper_menu(rect, None, rq, context);
                 true
@@ -3525,6 +5181,10 @@ impl View for Reader {
                 self.toggle_contrast_gray_menu(rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::TranslationMenu, rect) => {
+                self.toggle_translation_menu(rect, None, rq, context);
+                true
+            },
             Event::ToggleNear(ViewId::PageMenu, rect) => {
                 self.toggle_page_menu(rect, None, rq, context);
                 true
@@ -3560,6 +5220,42 @@ impl View for Reader {
                 }
                 false
             },
+            Event::Close(ViewId::Translation) => {
+                self.toggle_translation(Some(false), rq);
+                true
+            },
+            Event::Close(ViewId::ZoomView) => {
+                self.toggle_zoom_view(None, rq);
+                true
+            },
+            Event::Close(ViewId::Thumbnails) => {
+                self.toggle_thumbnails(Some(false), rq, context);
+                true
+            },
+            Event::Close(ViewId::TonePicker) => {
+                self.toggle_tone_picker(Some(false), rq, context);
+                true
+            },
+            Event::Toggle(ViewId::GoToPad) | Event::Select(EntryId::GoToDestination) => {
+                self.toggle_go_to_pad(None, rq, context);
+                true
+            },
+            Event::Close(ViewId::GoToPad) => {
+                self.toggle_go_to_pad(Some(false), rq, context);
+                true
+            },
+            Event::Close(ViewId::Skim) => {
+                self.toggle_skim(Some(false), rq, context);
+                true
+            },
+            Event::Close(ViewId::EndOfBook) => {
+                self.toggle_end_of_book(Some(false), rq, context);
+                true
+            },
+            Event::Close(ViewId::BookReview) => {
+                self.toggle_book_review(Some(false), hub, rq, context);
+                false
+            },
             Event::Close(ViewId::EditNote) => {
                 self.toggle_edit_note(None, Some(false), hub, rq, context);
                 if let Some(rect) = self.selection_rect() {
@@ -3569,6 +5265,10 @@ impl View for Reader {
                 self.target_annotation = None;
                 false
             },
+            Event::Close(ViewId::FontFamilyPicker) => {
+                self.toggle_keyboard(false, None, hub, rq, context);
+                false
+            },
             Event::Close(ViewId::NamePage) => {
                 self.toggle_keyboard(false, None, hub, rq, context);
                 false
@@ -3647,6 +5347,9 @@ impl View for Reader {
                     s.highlights.entry(location).or_insert_with(Vec::new).push(rects.clone());
                     s.results_count += 1;
                     results_count = s.results_count;
+                    if results_count == 1 {
+                        s.focus = Some((location, 0));
+                    }
                     if results_count > 1 && location <= self.current_page && s.highlights.len() > pages_count {
                         s.current_page += 1;
                     }
@@ -3664,7 +5367,11 @@ impl View for Reader {
 
                 true
             },
+            Event::TaskProgress(view_id, done, total) => {
+                update_progress(self, view_id, done, total, rq)
+            },
             Event::EndOfSearch => {
+                close_progress(self, ViewId::SearchNotif, rq, context);
                 let results_count = self.search.as_ref().map(|s| s.results_count)
                                         .unwrap_or(usize::MAX);
                 if results_count == 0 {
@@ -3703,17 +5410,94 @@ impl View for Reader {
                 if let Some(text) = self.selected_text() {
                     let query = text.trim_matches(|c: char| !c.is_alphanumeric()).to_string();
                     let language = self.info.language.clone();
+                    self.record_lookup(&query, context);
                     hub.send(Event::Select(EntryId::Launch(AppCmd::Dictionary { query, language }))).ok();
                 }
                 self.selection = None;
                 true
             },
+            Event::Select(EntryId::TranslateSelection) => {
+                if let Some(text) = self.selected_text() {
+                    let text = text.trim_matches(|c: char| !c.is_alphanumeric()).to_string();
+                    self.translate(&text, hub, rq, context);
+                }
+                if let Some(rect) = self.selection_rect() {
+                    rq.add(RenderData::new(self.id, rect, UpdateMode::Gui));
+                }
+                self.selection = None;
+                true
+            },
+            Event::Select(EntryId::ShareSelection) => {
+                if let Some(text) = self.selected_text() {
+                    let quote = format_quote(&text, &self.info.title(), &self.info.author);
+                    self.share(Payload::Quote(quote), hub, rq, context);
+                }
+                if let Some(rect) = self.selection_rect() {
+                    rq.add(RenderData::new(self.id, rect, UpdateMode::Gui));
+                }
+                self.selection = None;
+                true
+            },
+            Event::Select(EntryId::AnnotatePage) => {
+                if let Some(background) = self.save_snapshot(PAGE_SNAPSHOT_PATH) {
+                    let page = self.current_page;
+                    let sketch = self.info.reader.as_ref()
+                                     .and_then(|r| r.sketches.get(&page).cloned());
+                    hub.send(Event::Select(EntryId::Launch(AppCmd::AnnotatePage { page, background, sketch }))).ok();
+                }
+                true
+            },
+            Event::PageSketched(page, ref sketch) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.sketches.insert(page, sketch.clone());
+                }
+                true
+            },
+            Event::Select(EntryId::SharePage) => {
+                if let Some(rect) = self.selection_rect() {
+                    rq.add(RenderData::new(self.id, rect, UpdateMode::Gui));
+                }
+                self.selection = None;
+                if let Some(path) = self.save_snapshot(SNAPSHOT_PATH) {
+                    self.share(Payload::Page(path), hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::SetTranslationSource(ref lang)) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.translation_source = Some(lang.clone());
+                }
+                if let Some(text) = self.translated_text() {
+                    self.translate(&text, hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::SetTranslationTarget(ref lang)) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.translation_target = Some(lang.clone());
+                }
+                if let Some(text) = self.translated_text() {
+                    self.translate(&text, hub, rq, context);
+                }
+                true
+            },
+            Event::Translation { ref text, ref translation } => {
+                let (source, target) = self.translation_languages(context);
+                if let Some(index) = locate_by_id(self, ViewId::Translation) {
+                    if let Some(translation_view) = self.children[index].downcast_mut::<TranslationView>() {
+                        if translation_view.text() == text {
+                            translation_view.update(translation, &source, &target, rq);
+                        }
+                    }
+                }
+                true
+            },
             Event::Select(EntryId::SearchForSelection) => {
                 if let Some(text) = self.selected_text() {
                     let text = text.trim_matches(|c: char| !c.is_alphanumeric());
                     match make_query(text) {
                         Some(query) => {
-                            self.search(text, query, hub, rq);
+                            self.search(text, query, hub, rq, context);
                         },
                         None => {
                             let notif = Notification::new("Invalid search query.".to_string(),
@@ -3809,7 +5593,7 @@ impl View for Reader {
                 if let Some(r) = self.info.reader.as_mut() {
                     r.cropping_margins = None;
                 }
-                self.cache.clear();
+                self.clear_cache();
                 self.update(None, hub, rq, context);
                 true
             },
@@ -3837,6 +5621,10 @@ impl View for Reader {
                 self.set_margin_width(width, hub, rq, context);
                 true
             },
+            Event::Select(EntryId::SetVerticalMarginWidth(width)) => {
+                self.set_vertical_margin_width(width, hub, rq, context);
+                true
+            },
             Event::Select(EntryId::SetLineHeight(v)) => {
                 let line_height = 1.0 + v as f32 / 10.0;
                 self.set_line_height(line_height, hub, rq, context);
@@ -3852,6 +5640,123 @@ impl View for Reader {
                 self.set_contrast_gray(gray, hub, rq, context);
                 true
             },
+            Event::Select(EntryId::Skim) => {
+                self.toggle_skim(Some(true), rq, context);
+                true
+            },
+            Event::Select(EntryId::Thumbnails) => {
+                self.toggle_thumbnails(Some(true), rq, context);
+                true
+            },
+            Event::Select(EntryId::SetRefreshPolicy(policy)) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.refresh_policy = policy;
+                }
+                true
+            },
+            Event::Select(EntryId::RecognizeText) => {
+                let locations = self.chunks.iter().map(|chunk| chunk.location).collect::<Vec<usize>>();
+                for location in locations {
+                    self.recognize_text(location, true, hub, context);
+                }
+                let notif = Notification::new("Recognizing the text…".to_string(), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                true
+            },
+            Event::RecognizedText(location, ref words) => {
+                let manual = self.ocr_requests.get(&location).copied().unwrap_or_default();
+                // The failed pages aren't recognized automatically again.
+                self.ocr_requests.insert(location, false);
+                if let Some(words) = words {
+                    self.ocr_requests.remove(&location);
+                    self.ocr.insert(location, words);
+                    if let Some(path) = context.library.ocr_cache_path(&self.info.file.path) {
+                        if let Err(e) = self.ocr.save(&path) {
+                            eprintln!("Can't save the OCR cache: {:#}.", e);
+                        }
+                    }
+                    if self.text.contains_key(&location) {
+                        self.text.insert(location, words.clone());
+                    }
+                }
+                if manual {
+                    let message = match words {
+                        None => "Can't recognize the text.".to_string(),
+                        Some(words) if words.is_empty() => "No text found.".to_string(),
+                        Some(words) => format!("{} words recognized.", words.len()),
+                    };
+                    let notif = Notification::new(message, hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
+                }
+                true
+            },
+            Event::Select(EntryId::ToggleReadAloud) => {
+                self.toggle_read_aloud(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SetSpeechSpeed(percent)) => {
+                self.set_speech_voice(Some(percent as f32 / 100.0), None, context);
+                true
+            },
+            Event::Select(EntryId::SetSpeechPitch(percent)) => {
+                self.set_speech_voice(None, Some(percent as f32 / 100.0), context);
+                true
+            },
+            Event::SpeechWord(token, index) => {
+                let current = self.speech.as_ref()
+                                  .filter(|speech| speech.token == token)
+                                  .map(|speech| speech.utterance.start + index);
+                if current.is_some() {
+                    self.set_speech_word(current, rq);
+                }
+                true
+            },
+            Event::SpeechDone(token) => {
+                if self.speech.as_ref().is_some_and(|speech| speech.token == token) {
+                    let words = self.visible_words();
+                    let mut resume = self.speech.as_ref().map_or(0, |speech| speech.resume);
+                    // The page was turned while reading.
+                    if let Some(speech) = self.speech.as_mut().filter(|speech| speech.words != words) {
+                        speech.words = words;
+                        speech.current = None;
+                        resume = 0;
+                    }
+                    self.read_aloud(resume, hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::ToggleManga) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.manga = !r.manga;
+                }
+                self.clear_cache();
+                self.update(None, hub, rq, context);
+                self.update_bottom_bar(rq);
+                true
+            },
+            Event::Select(EntryId::ToggleKeepOrientation) => {
+                if let Some(ref mut r) = self.info.reader {
+                    r.keep_orientation = !r.keep_orientation;
+                }
+                true
+            },
+            Event::Select(EntryId::ToneCurves) => {
+                self.toggle_tone_picker(Some(true), rq, context);
+                true
+            },
+            Event::Select(EntryId::SetToneCurve(tone_curve)) => {
+                self.set_tone_curve(tone_curve, hub, rq, context);
+                true
+            },
+            Event::Skim(location, status) => {
+                if status == FingerStatus::Up && location != self.current_page {
+                    self.toggle_skim(Some(false), rq, context);
+                    self.jump_to_page(location, hub, rq, context);
+                } else {
+                    self.update_skim(location, rq);
+                }
+                true
+            },
             Event::Select(EntryId::SetPageName) => {
                 self.toggle_name_page(None, hub, rq, context);
                 true
@@ -3863,13 +5768,23 @@ impl View for Reader {
                 true
             },
             Event::Select(EntryId::ToggleInverted) => {
-                self.update_noninverted_regions(!context.fb.inverted());
+                self.update_noninverted_regions(!context.fb.inverted(), &context.settings.reader);
                 false
             },
             Event::Reseed => {
                 self.reseed(rq, context);
                 true
             },
+            Event::FocusStarted => {
+                if self.focus_start.is_none() && !self.ephemeral {
+                    self.focus_start = Some((Local::now(), self.page_turns));
+                }
+                true
+            },
+            Event::FocusEnded => {
+                self.end_focus_session();
+                true
+            },
             Event::ToggleFrontlight => {
                 if let Some(index) = locate::<TopBar>(self) {
                     self.child_mut(index).downcast_mut::<TopBar>().unwrap()
@@ -3914,28 +5829,44 @@ impl View for Reader {
         fb.draw_rectangle(&rect, WHITE);
 
         for chunk in &self.chunks {
-            let Resource { ref pixmap, scale, .. } = self.cache[&chunk.location];
+            let scale = self.cache[&chunk.location].scale;
             let chunk_rect = chunk.frame - chunk.frame.min + chunk.position;
 
             if let Some(region_rect) = rect.intersection(&chunk_rect) {
                 let chunk_frame = region_rect - chunk.position + chunk.frame.min;
                 let chunk_position = region_rect.min;
-                fb.draw_framed_pixmap_contrast(pixmap, &chunk_frame, chunk_position, self.contrast.exponent, self.contrast.gray);
+                self.draw_page_area(fb, chunk.location, &chunk_frame, chunk_position, |fb, pixmap, frame, position| {
+                    fb.draw_framed_pixmap_contrast(pixmap, frame, position, self.contrast.exponent, self.contrast.gray);
+                });
 
                 if let Some(rects) = self.noninverted_regions.get(&chunk.location) {
                     for r in rects {
                         let rect = (*r * scale).to_rect() - chunk.frame.min + chunk.position;
                         if let Some(ref image_rect) = rect.intersection(&region_rect) {
-                            fb.invert_region(image_rect);
+                            if let Some(ref table) = self.image_tone {
+                                let image_frame = *image_rect - chunk.position + chunk.frame.min;
+                                self.draw_page_area(fb, chunk.location, &image_frame, image_rect.min, |fb, pixmap, frame, position| {
+                                    fb.draw_framed_pixmap_mapped(pixmap, frame, position, table);
+                                });
+                            } else {
+                                fb.invert_region(image_rect);
+                            }
                         }
                     }
                 }
 
                 if let Some(groups) = self.search.as_ref().and_then(|s| s.highlights.get(&chunk.location)) {
-                    for rects in groups {
+                    let focus = self.search.as_ref().and_then(|s| s.focus);
+                    for (index, rects) in groups.iter().enumerate() {
                         let mut last_rect: Option<Rectangle> = None;
+                        let mut group_rect: Option<Rectangle> = None;
                         for r in rects {
                             let rect = (*r * scale).to_rect() - chunk.frame.min + chunk.position;
+                            if let Some(ref mut gr) = group_rect {
+                                gr.absorb(&rect);
+                            } else {
+                                group_rect = Some(rect);
+                            }
                             if let Some(ref search_rect) = rect.intersection(&region_rect) {
                                 fb.invert_region(search_rect);
                             }
@@ -3956,6 +5887,22 @@ impl View for Reader {
                             }
                             last_rect = Some(rect);
                         }
+                        // The focused result is outlined.
+                        if focus == Some((chunk.location, index)) {
+                            if let Some(gr) = group_rect {
+                                let thickness = scale_by_dpi(THICKNESS_MEDIUM, CURRENT_DEVICE.dpi) as i32;
+                                let outer = rect![gr.min - pt!(2 * thickness), gr.max + pt!(2 * thickness)];
+                                let edges = [rect![outer.min.x, outer.min.y, outer.max.x, outer.min.y + thickness],
+                                             rect![outer.min.x, outer.max.y - thickness, outer.max.x, outer.max.y],
+                                             rect![outer.min.x, outer.min.y, outer.min.x + thickness, outer.max.y],
+                                             rect![outer.max.x - thickness, outer.min.y, outer.max.x, outer.max.y]];
+                                for edge in &edges {
+                                    if let Some(ref edge_rect) = edge.intersection(&region_rect) {
+                                        fb.draw_rectangle(edge_rect, BLACK);
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
 
@@ -4019,6 +5966,17 @@ impl View for Reader {
                         }
                     }
                 }
+
+                if let Some(&(location, index)) = self.speech.as_ref().and_then(|s| s.current.and_then(|i| s.words.get(i))) {
+                    if location == chunk.location {
+                        if let Some(word) = self.text.get(&location).and_then(|text| text.get(index)) {
+                            let rect = (word.rect * scale).to_rect() - chunk.frame.min + chunk.position;
+                            if let Some(ref word_rect) = rect.intersection(&region_rect) {
+                                fb.invert_region(word_rect);
+                            }
+                        }
+                    }
+                }
             }
         }
 
@@ -4049,7 +6007,8 @@ impl View for Reader {
                                               scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32);
             let mut floating_layer_start = 0;
 
-            self.children.retain(|child| !child.is::<Menu>());
+            self.children.retain(|child| !child.is::<Menu>() && !child.is::<Thumbnails>() &&
+                                    !child.is::<TonePicker>() && !child.is::<ZoomView>());
 
             if self.children[0].is::<TopBar>() {
                 let top_bar_rect = rect![rect.min.x, rect.min.y,
@@ -4167,7 +6126,7 @@ impl View for Reader {
             self.text.clear();
         }
 
-        self.cache.clear();
+        self.clear_cache();
         self.update(Some(UpdateMode::Full), hub, rq, context);
     }
 
@@ -4175,6 +6134,10 @@ impl View for Reader {
         self.search.is_none()
     }
 
+    fn auto_rotates(&self) -> bool {
+        self.might_rotate() && !self.info.reader.as_ref().is_some_and(|r| r.keep_orientation)
+    }
+
     fn is_background(&self) -> bool {
         true
     }
@@ -4198,4 +6161,11 @@ impl View for Reader {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn save_state(&self, session: &mut Session) {
+        if !self.ephemeral {
+            session.reader = Some(self.info.file.path.clone());
+            session.application = None;
+        }
+    }
 }
@@ -21,7 +21,7 @@ pub struct ResultsBar {
 }
 
 impl ResultsBar {
-    pub fn new(rect: Rectangle, current_page: usize, pages_count: usize, count: usize, completed: bool) -> ResultsBar {
+    pub fn new(rect: Rectangle, current_page: usize, pages_count: usize, count: usize, visible: usize, completed: bool) -> ResultsBar {
         let id = ID_FEEDER.next();
         let mut children = Vec::new();
         let side = rect.height() as i32;
@@ -44,6 +44,7 @@ impl ResultsBar {
         let results_label = ResultsLabel::new(rect![pt!(rect.min.x + side, rect.min.y),
                                                     pt!(rect.min.x + side + small_half_width, rect.max.y)],
                                               count,
+                                              visible,
                                               completed);
         children.push(Box::new(results_label) as Box<dyn View>);
 
@@ -75,9 +76,9 @@ impl ResultsBar {
         }
     }
 
-    pub fn update_results_label(&mut self, count: usize, rq: &mut RenderQueue) {
+    pub fn update_results_label(&mut self, count: usize, visible: usize, rq: &mut RenderQueue) {
         let results_label = self.children[1].as_mut().downcast_mut::<ResultsLabel>().unwrap();
-        results_label.update(count, rq);
+        results_label.update(count, visible, rq);
     }
 
     pub fn update_page_label(&mut self, current_page: usize, pages_count: usize, rq: &mut RenderQueue) {
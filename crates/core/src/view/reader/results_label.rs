@@ -11,22 +11,26 @@ pub struct ResultsLabel {
     rect: Rectangle,
     children: Vec<Box<dyn View>>,
     count: usize,
+    // The number of results on the displayed pages.
+    visible: usize,
     completed: bool,
 }
 
 impl ResultsLabel {
-    pub fn new(rect: Rectangle, count: usize, completed: bool) -> ResultsLabel {
+    pub fn new(rect: Rectangle, count: usize, visible: usize, completed: bool) -> ResultsLabel {
         ResultsLabel {
             id: ID_FEEDER.next(),
             rect,
             children: Vec::new(),
             count,
+            visible,
             completed,
         }
     }
 
-    pub fn update(&mut self, count: usize, rq: &mut RenderQueue) {
+    pub fn update(&mut self, count: usize, visible: usize, rq: &mut RenderQueue) {
         self.count = count;
+        self.visible = visible;
         rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
     }
 
@@ -39,6 +43,8 @@ impl ResultsLabel {
 
         if self.count == 0 {
             format!("No {}", qualifier)
+        } else if self.visible > 0 {
+            format!("{} {} ({} here)", self.count, qualifier, self.visible)
         } else {
             format!("{} {}", self.count, qualifier)
         }
@@ -0,0 +1,296 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec, halves};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
+use crate::view::{THICKNESS_SMALL, THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use crate::view::image::Image;
+use crate::view::label::Label;
+use crate::gesture::GestureEvent;
+use crate::input::{DeviceEvent, FingerStatus};
+use crate::color::{BLACK, WHITE, PROGRESS_FULL, PROGRESS_EMPTY};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+const BAR_HEIGHT: f32 = 7.0;
+const MARKER_DIAMETER: f32 = 46.0;
+const TICK_HEIGHT: f32 = 22.0;
+
+// A page slider with the chapter boundaries drawn as ticks and
+// a preview of the destination page.
+pub struct Skim {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+}
+
+impl Skim {
+    pub fn new(current_page: usize, pages_count: usize, ticks: Vec<usize>, label: String, context: &mut Context) -> Skim {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let line_height = 4 * x_height;
+        let bar_height = scale_by_dpi(MARKER_DIAMETER, dpi) as i32 + padding;
+
+        let panel_width = 4 * width as i32 / 5;
+        let preview_height = height as i32 / 2;
+        let panel_height = preview_height + line_height + bar_height + 4 * padding;
+        let dx = (width as i32 - panel_width) / 2;
+        let dy = (height as i32 - panel_height) / 2;
+        let rect = rect![dx, dy, dx + panel_width, dy + panel_height];
+
+        let mut y = rect.min.y + padding;
+        let rect_preview = rect![rect.min.x + padding, y, rect.max.x - padding, y + preview_height];
+        children.push(Box::new(Image::new(rect_preview, Pixmap::new(1, 1))) as Box<dyn View>);
+        y += preview_height + padding;
+
+        let rect_label = rect![rect.min.x + padding, y, rect.max.x - padding, y + line_height];
+        children.push(Box::new(Label::new(rect_label, label, Align::Center)) as Box<dyn View>);
+        y += line_height + padding;
+
+        let rect_bar = rect![rect.min.x + padding, y, rect.max.x - padding, y + bar_height];
+        children.push(Box::new(SkimBar::new(rect_bar, current_page, pages_count, ticks)) as Box<dyn View>);
+
+        Skim {
+            id,
+            rect,
+            children,
+        }
+    }
+
+    pub fn preview_rect(&self) -> Rectangle {
+        *self.children[0].rect()
+    }
+
+    pub fn update(&mut self, pixmap: Pixmap, label: &str, rq: &mut RenderQueue) {
+        if let Some(image) = self.children[0].downcast_mut::<Image>() {
+            image.update(pixmap, &mut RenderQueue::new());
+            rq.add(RenderData::no_wait(image.id(), *image.rect(), UpdateMode::Fast));
+        }
+        if let Some(text) = self.children[1].downcast_mut::<Label>() {
+            text.update(label, rq);
+        }
+    }
+}
+
+impl View for Skim {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::Skim));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let delta = pt!((width as i32 - self.rect.width() as i32) / 2,
+                        (height as i32 - self.rect.height() as i32) / 2) - self.rect.min;
+        self.rect += delta;
+        for child in &mut self.children {
+            *child.rect_mut() += delta;
+        }
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::Skim)
+    }
+}
+
+struct SkimBar {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    // The location the slider returns to when released outside of the bar.
+    origin: usize,
+    location: usize,
+    pages_count: usize,
+    // The locations of the chapters.
+    ticks: Vec<usize>,
+    active: bool,
+}
+
+impl SkimBar {
+    fn new(rect: Rectangle, location: usize, pages_count: usize, ticks: Vec<usize>) -> SkimBar {
+        SkimBar {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            origin: location,
+            location,
+            pages_count,
+            ticks,
+            active: false,
+        }
+    }
+
+    fn marker_radius(&self) -> (i32, i32) {
+        halves(scale_by_dpi(MARKER_DIAMETER, CURRENT_DEVICE.dpi) as i32)
+    }
+
+    fn x_from_location(&self, location: usize) -> i32 {
+        let (small_radius, big_radius) = self.marker_radius();
+        let width = self.rect.width() as i32 - small_radius - big_radius;
+        let progress = location as f32 / self.pages_count.saturating_sub(1).max(1) as f32;
+        self.rect.min.x + small_radius + (progress.min(1.0) * width as f32) as i32
+    }
+
+    fn location_from_x(&self, x: i32) -> usize {
+        let (small_radius, big_radius) = self.marker_radius();
+        let width = self.rect.width() as i32 - small_radius - big_radius;
+        let progress = ((x - self.rect.min.x - small_radius) as f32 / width as f32).clamp(0.0, 1.0);
+        (progress * self.pages_count.saturating_sub(1) as f32).round() as usize
+    }
+}
+
+impl View for SkimBar {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Device(DeviceEvent::Finger { status, position, .. }) => {
+                match status {
+                    FingerStatus::Down if self.rect.includes(position) => {
+                        self.active = true;
+                        self.location = self.location_from_x(position.x);
+                        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                        bus.push_back(Event::Skim(self.location, status));
+                        true
+                    },
+                    FingerStatus::Motion if self.active => {
+                        let location = self.location_from_x(position.x);
+                        if location != self.location {
+                            self.location = location;
+                            rq.add(RenderData::no_wait(self.id, self.rect, UpdateMode::FastMono));
+                            bus.push_back(Event::Skim(self.location, status));
+                        }
+                        true
+                    },
+                    FingerStatus::Up if self.active => {
+                        self.active = false;
+                        let (small_radius, big_radius) = self.marker_radius();
+                        let rect = rect![self.rect.min.x, self.rect.min.y - small_radius,
+                                         self.rect.max.x, self.rect.max.y + big_radius];
+                        if !rect.includes(position) {
+                            self.location = self.origin;
+                        }
+                        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                        bus.push_back(Event::Skim(self.location, status));
+                        true
+                    },
+                    _ => self.active,
+                }
+            },
+            // Type the destination instead.
+            Event::Gesture(GestureEvent::HoldFingerShort(center, ..)) if self.rect.includes(center) => {
+                self.active = false;
+                self.location = self.origin;
+                bus.push_back(Event::Close(ViewId::Skim));
+                bus.push_back(Event::Toggle(ViewId::GoToPad));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let bar_height = scale_by_dpi(BAR_HEIGHT, dpi) as i32;
+        let tick_height = scale_by_dpi(TICK_HEIGHT, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+        let (small_radius, big_radius) = self.marker_radius();
+        let x_offset = self.x_from_location(self.location);
+        let y_center = self.rect.min.y + self.rect.height() as i32 / 2;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let (small_bar_half, big_bar_half) = halves(bar_height);
+        let rect_bar = rect![self.rect.min.x + small_radius - big_bar_half, y_center - small_bar_half,
+                             self.rect.max.x - big_radius + small_bar_half, y_center + big_bar_half];
+        fb.draw_rounded_rectangle_with_border(&rect_bar,
+                                              &CornerSpec::Uniform(small_bar_half),
+                                              &BorderSpec { thickness: border_thickness as u16,
+                                                            color: BLACK },
+                                              &|x, _| if x < x_offset { PROGRESS_FULL }
+                                                      else { PROGRESS_EMPTY });
+
+        let (small_tick_half, big_tick_half) = halves(tick_height);
+        for &tick in &self.ticks {
+            let x = self.x_from_location(tick);
+            fb.draw_rectangle(&rect![x, y_center - small_tick_half,
+                                     x + border_thickness, y_center + big_tick_half],
+                              BLACK);
+        }
+
+        let rect_marker = rect![x_offset - small_radius, y_center - small_radius,
+                                x_offset + big_radius, y_center + big_radius];
+        let fill_color = if self.active { BLACK } else { WHITE };
+        fb.draw_rounded_rectangle_with_border(&rect_marker,
+                                              &CornerSpec::Uniform(small_radius),
+                                              &BorderSpec { thickness: 2 * border_thickness as u16,
+                                                            color: BLACK },
+                                              &fill_color);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
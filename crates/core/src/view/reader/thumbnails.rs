@@ -0,0 +1,261 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
+use crate::geom::{Rectangle, CycleDir, Dir, BorderSpec, divide};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_SMALL, THICKNESS_LARGE};
+use crate::view::button::Button;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// A page of thumbnails: the location, label and image of each cell.
+pub type ThumbnailsPage = Vec<(usize, String, Pixmap)>;
+
+// A grid of page thumbnails: tap a thumbnail to go to its page,
+// swipe to browse the neighboring pages.
+pub struct Thumbnails {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    cells_count: usize,
+}
+
+impl Thumbnails {
+    pub fn new(rect: Rectangle, context: &mut Context) -> Thumbnails {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let bar_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32 / 2;
+
+        let (columns, rows) = if rect.width() > rect.height() { (4, 2) } else { (3, 3) };
+        let grid_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                              rect.max.x - padding, rect.max.y - bar_height];
+        let widths = divide(grid_rect.width() as i32, columns);
+        let heights = divide(grid_rect.height() as i32, rows);
+
+        let mut y = grid_rect.min.y;
+        for h in heights {
+            let mut x = grid_rect.min.x;
+            for &w in &widths {
+                let cell_rect = rect![x + padding, y + padding, x + w - padding, y + h - padding];
+                children.push(Box::new(Thumbnail::new(cell_rect)) as Box<dyn View>);
+                x += w;
+            }
+            y += h;
+        }
+
+        let buttons = [(Event::ThumbnailsPage(CycleDir::Previous), "Previous"),
+                       (Event::Close(ViewId::Thumbnails), "Close"),
+                       (Event::ThumbnailsPage(CycleDir::Next), "Next")];
+        let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for ((event, text), w) in buttons.into_iter().zip(widths) {
+            let button_rect = rect![x, rect.max.y - bar_height + padding,
+                                    x + w - padding, rect.max.y - padding];
+            children.push(Box::new(Button::new(button_rect, event, text.to_string())) as Box<dyn View>);
+            x += w;
+        }
+
+        Thumbnails {
+            id,
+            rect,
+            children,
+            cells_count: (columns * rows) as usize,
+        }
+    }
+
+    pub fn cells_count(&self) -> usize {
+        self.cells_count
+    }
+
+    // The largest dimensions of a thumbnail, leaving room for its frame.
+    pub fn thumbnail_dims(&self) -> (u32, u32) {
+        let border_thickness = 2 * scale_by_dpi(THICKNESS_LARGE, CURRENT_DEVICE.dpi) as u32;
+        self.children.first()
+            .and_then(|child| child.downcast_ref::<Thumbnail>())
+            .map(Thumbnail::image_rect)
+            .map(|rect| (rect.width().saturating_sub(border_thickness).max(1),
+                         rect.height().saturating_sub(border_thickness).max(1)))
+            .unwrap_or((1, 1))
+    }
+
+    pub fn first_location(&self) -> Option<usize> {
+        self.children.first()
+            .and_then(|child| child.downcast_ref::<Thumbnail>())
+            .and_then(|thumbnail| thumbnail.location)
+    }
+
+    pub fn last_location(&self) -> Option<usize> {
+        self.children[..self.cells_count].iter().rev()
+            .filter_map(|child| child.downcast_ref::<Thumbnail>())
+            .find_map(|thumbnail| thumbnail.location)
+    }
+
+    pub fn update(&mut self, page: ThumbnailsPage, current_page: usize, rq: &mut RenderQueue) {
+        let mut page = page.into_iter();
+        for child in &mut self.children[..self.cells_count] {
+            if let Some(thumbnail) = child.downcast_mut::<Thumbnail>() {
+                if let Some((location, label, pixmap)) = page.next() {
+                    thumbnail.location = Some(location);
+                    thumbnail.label = label;
+                    thumbnail.pixmap = Some(pixmap);
+                    thumbnail.current = location == current_page;
+                } else {
+                    thumbnail.location = None;
+                    thumbnail.label.clear();
+                    thumbnail.pixmap = None;
+                    thumbnail.current = false;
+                }
+            }
+        }
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for Thumbnails {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Swipe { dir, start, .. }) if self.rect.includes(start) => {
+                match dir {
+                    Dir::West => bus.push_back(Event::ThumbnailsPage(CycleDir::Next)),
+                    Dir::East => bus.push_back(Event::ThumbnailsPage(CycleDir::Previous)),
+                    _ => (),
+                }
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        fb.draw_rectangle(&self.rect, WHITE);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::Thumbnails)
+    }
+}
+
+struct Thumbnail {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    location: Option<usize>,
+    label: String,
+    pixmap: Option<Pixmap>,
+    current: bool,
+}
+
+impl Thumbnail {
+    fn new(rect: Rectangle) -> Thumbnail {
+        Thumbnail {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            location: None,
+            label: String::new(),
+            pixmap: None,
+            current: false,
+        }
+    }
+
+    // The area above the label.
+    fn image_rect(&self) -> Rectangle {
+        let label_height = 2 * scale_by_dpi(SMALL_BAR_HEIGHT, CURRENT_DEVICE.dpi) as i32 / 3;
+        rect![self.rect.min.x, self.rect.min.y,
+              self.rect.max.x, (self.rect.max.y - label_height).max(self.rect.min.y + 1)]
+    }
+}
+
+impl View for Thumbnail {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                if let Some(location) = self.location {
+                    bus.push_back(Event::Close(ViewId::Thumbnails));
+                    bus.push_back(Event::GoTo(location));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let pixmap = if let Some(pixmap) = self.pixmap.as_ref() { pixmap } else { return };
+
+        let image_rect = self.image_rect();
+        let dx = (image_rect.width() as i32 - pixmap.width as i32) / 2;
+        let dy = (image_rect.height() as i32 - pixmap.height as i32) / 2;
+        let pt = image_rect.min + pt!(dx.max(0), dy.max(0));
+        fb.draw_pixmap(pixmap, pt);
+
+        // The current page is framed with a thicker border.
+        let thickness = if self.current { THICKNESS_LARGE } else { THICKNESS_SMALL };
+        let border_thickness = scale_by_dpi(thickness, dpi) as i32;
+        let frame = rect![pt.x - border_thickness, pt.y - border_thickness,
+                          pt.x + pixmap.width as i32 + border_thickness,
+                          pt.y + pixmap.height as i32 + border_thickness];
+        fb.draw_rectangle_outline(&frame, &BorderSpec { thickness: border_thickness as u16, color: BLACK });
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let plan = font.plan(&self.label, Some(self.rect.width() as i32), None);
+        let x_height = font.x_heights.0 as i32;
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        let dy = (self.rect.max.y - image_rect.max.y - x_height) / 2;
+        font.render(fb, BLACK, &plan, pt!(self.rect.min.x + dx, self.rect.max.y - dy));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,210 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, Pixmap};
+use crate::geom::{Rectangle, BorderSpec, divide};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::tone::{ToneCurve, TONE_CURVES};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, EntryId};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_SMALL, THICKNESS_LARGE};
+use crate::view::button::Button;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+const COLUMNS: i32 = 3;
+const ROWS: i32 = 2;
+
+// Previews the current page through each tone curve: tap a preview to apply its curve.
+pub struct TonePicker {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+}
+
+impl TonePicker {
+    // The page is rendered by the owner, at the dimensions given by `preview_dims`.
+    pub fn new(rect: Rectangle, page: &Pixmap, current: Option<ToneCurve>, context: &mut Context) -> TonePicker {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let bar_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32 / 2;
+
+        let mut curves = vec![None];
+        curves.extend(TONE_CURVES.iter().map(|&curve| Some(curve)));
+        let mut curves = curves.into_iter();
+
+        let (grid_rect, widths, heights) = grid(rect, padding, bar_height);
+        let mut y = grid_rect.min.y;
+        for h in heights {
+            let mut x = grid_rect.min.x;
+            for &w in &widths {
+                if let Some(curve) = curves.next() {
+                    let cell_rect = rect![x + padding, y + padding, x + w - padding, y + h - padding];
+                    let mut pixmap = page.clone();
+                    if let Some(curve) = curve {
+                        curve.apply(&mut pixmap);
+                    }
+                    children.push(Box::new(Preview::new(cell_rect, curve, pixmap, curve == current)) as Box<dyn View>);
+                }
+                x += w;
+            }
+            y += h;
+        }
+
+        let button_rect = rect![rect.min.x + padding, rect.max.y - bar_height + padding,
+                                rect.max.x - padding, rect.max.y - padding];
+        children.push(Box::new(Button::new(button_rect, Event::Close(ViewId::TonePicker), "Close".to_string())) as Box<dyn View>);
+
+        TonePicker {
+            id,
+            rect,
+            children,
+        }
+    }
+
+    // The largest dimensions of a preview, leaving room for its frame and label.
+    pub fn preview_dims(rect: Rectangle, context: &mut Context) -> (u32, u32) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let bar_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let padding = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi).em() as i32 / 2;
+        let (_, widths, heights) = grid(rect, padding, bar_height);
+        let border_thickness = 2 * scale_by_dpi(THICKNESS_LARGE, dpi) as i32;
+        let label_height = 2 * bar_height / 3;
+        let width = widths.iter().min().copied().unwrap_or(1) - 2 * padding - border_thickness;
+        let height = heights.iter().min().copied().unwrap_or(1) - 2 * padding - border_thickness - label_height;
+        (width.max(1) as u32, height.max(1) as u32)
+    }
+}
+
+fn grid(rect: Rectangle, padding: i32, bar_height: i32) -> (Rectangle, Vec<i32>, Vec<i32>) {
+    let grid_rect = rect![rect.min.x + padding, rect.min.y + padding,
+                          rect.max.x - padding, rect.max.y - bar_height];
+    let widths = divide(grid_rect.width() as i32, COLUMNS);
+    let heights = divide(grid_rect.height() as i32, ROWS);
+    (grid_rect, widths, heights)
+}
+
+impl View for TonePicker {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        matches!(*evt, Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..))
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        fb.draw_rectangle(&self.rect, WHITE);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::TonePicker)
+    }
+}
+
+struct Preview {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    curve: Option<ToneCurve>,
+    pixmap: Pixmap,
+    current: bool,
+}
+
+impl Preview {
+    fn new(rect: Rectangle, curve: Option<ToneCurve>, pixmap: Pixmap, current: bool) -> Preview {
+        Preview {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            curve,
+            pixmap,
+            current,
+        }
+    }
+}
+
+impl View for Preview {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::TonePicker));
+                bus.push_back(Event::Select(EntryId::SetToneCurve(self.curve)));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let label_height = 2 * scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32 / 3;
+        let image_rect = rect![self.rect.min.x, self.rect.min.y,
+                               self.rect.max.x, (self.rect.max.y - label_height).max(self.rect.min.y + 1)];
+        let dx = (image_rect.width() as i32 - self.pixmap.width as i32) / 2;
+        let dy = (image_rect.height() as i32 - self.pixmap.height as i32) / 2;
+        let pt = image_rect.min + pt!(dx.max(0), dy.max(0));
+        fb.draw_pixmap(&self.pixmap, pt);
+
+        // The current curve is framed with a thicker border.
+        let thickness = if self.current { THICKNESS_LARGE } else { THICKNESS_SMALL };
+        let border_thickness = scale_by_dpi(thickness, dpi) as i32;
+        let frame = rect![pt.x - border_thickness, pt.y - border_thickness,
+                          pt.x + self.pixmap.width as i32 + border_thickness,
+                          pt.y + self.pixmap.height as i32 + border_thickness];
+        fb.draw_rectangle_outline(&frame, &BorderSpec { thickness: border_thickness as u16, color: BLACK });
+
+        let label = self.curve.map_or("Original", ToneCurve::label);
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let plan = font.plan(label, Some(self.rect.width() as i32), None);
+        let x_height = font.x_heights.0 as i32;
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        let dy = (self.rect.max.y - image_rect.max.y - x_height) / 2;
+        font.render(fb, BLACK, &plan, pt!(self.rect.min.x + dx, self.rect.max.y - dy));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
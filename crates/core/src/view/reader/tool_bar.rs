@@ -16,6 +16,9 @@ use crate::font::Fonts;
 use crate::color::{SEPARATOR_NORMAL, WHITE};
 use crate::context::Context;
 
+const FONT_SIZE_STEP: f32 = 0.5;
+const CONTRAST_EXPONENT_STEP: f32 = 0.1;
+
 #[derive(Debug)]
 pub struct ToolBar {
     id: Id,
@@ -101,7 +104,8 @@ impl ToolBar {
                                      SliderId::FontSize,
                                      font_size,
                                      reader_settings.min_font_size,
-                                     reader_settings.max_font_size);
+                                     reader_settings.max_font_size)
+                             .step(FONT_SIZE_STEP);
             children.push(Box::new(slider) as Box<dyn View>);
         } else {
             let remaining_width = rect.width() as i32 - 2 * side;
@@ -121,7 +125,8 @@ impl ToolBar {
                                      SliderId::ContrastExponent,
                                      contrast_exponent,
                                      1.0,
-                                     5.0);
+                                     5.0)
+                             .step(CONTRAST_EXPONENT_STEP);
             children.push(Box::new(slider) as Box<dyn View>);
 
             let gray_icon_rect = rect![rect.min.x + side + slider_width, rect.min.y,
@@ -138,7 +143,8 @@ impl ToolBar {
                                      SliderId::ContrastGray,
                                      contrast_gray,
                                      0.0,
-                                     255.0);
+                                     255.0)
+                             .step(1.0);
             children.push(Box::new(slider) as Box<dyn View>);
 
             let filler = Filler::new(rect![rect.max.x - side / 3,
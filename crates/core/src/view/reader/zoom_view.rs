@@ -0,0 +1,197 @@
+use std::sync::{Arc, Mutex};
+use crate::document::{Document, Location};
+use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
+use crate::geom::{Point, Rectangle, Boundary, Axis};
+use crate::font::Fonts;
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId};
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::WHITE;
+use crate::context::Context;
+
+// The layout width of a table, relatively to the screen's.
+const TABLE_WIDTH_RATIO: u32 = 2;
+const ZOOM_STEP: f32 = 1.25;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+// The largest page rendered to extract an image.
+const MAX_PAGE_PIXELS: f32 = 16_000_000.0;
+
+#[derive(Debug, Clone)]
+pub enum ZoomSource {
+    // The offset of a table.
+    Table(usize),
+    // The location of a page and the area of one of its images.
+    Image(usize, Boundary),
+}
+
+// Shows a table laid out wider than the screen, or an image fitted to the screen:
+// swipe to pan, pinch or spread to zoom, rotate to turn the image, tap to close.
+pub struct ZoomView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    doc: Arc<Mutex<Box<dyn Document>>>,
+    source: ZoomSource,
+    zoom: f32,
+    // The clockwise quarter turns applied to the pixmap.
+    rotation: i8,
+    pixmap: Pixmap,
+    // The point of the pixmap shown at the top left corner.
+    origin: Point,
+}
+
+impl ZoomView {
+    pub fn new(rect: Rectangle, doc: Arc<Mutex<Box<dyn Document>>>, source: ZoomSource) -> Option<ZoomView> {
+        let pixmap = render(&doc, &source, &rect, 1.0, 0)?;
+        Some(ZoomView {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            doc,
+            source,
+            zoom: 1.0,
+            rotation: 0,
+            pixmap,
+            origin: Point::default(),
+        })
+    }
+
+    fn pan(&mut self, delta: Point, rq: &mut RenderQueue) {
+        let origin = self.clamp(self.origin + delta);
+        if origin != self.origin {
+            self.origin = origin;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Partial));
+        }
+    }
+
+    fn zoom(&mut self, factor: f32, rq: &mut RenderQueue) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        if (zoom - self.zoom).abs() < f32::EPSILON {
+            return;
+        }
+        if let Some(pixmap) = render(&self.doc, &self.source, &self.rect, zoom, self.rotation) {
+            // Keep the center of the visible region in place.
+            let ratio = pixmap.width as f32 / self.pixmap.width.max(1) as f32;
+            let half_size = pt!(self.rect.width() as i32 / 2, self.rect.height() as i32 / 2);
+            let center = self.origin + half_size;
+            let center = pt!((center.x as f32 * ratio) as i32, (center.y as f32 * ratio) as i32);
+            self.zoom = zoom;
+            self.pixmap = pixmap;
+            self.origin = self.clamp(center - half_size);
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+
+    fn rotate(&mut self, quarter_turns: i8, rq: &mut RenderQueue) {
+        let rotation = (self.rotation + quarter_turns).rem_euclid(4);
+        if let Some(pixmap) = render(&self.doc, &self.source, &self.rect, self.zoom, rotation) {
+            self.rotation = rotation;
+            self.pixmap = pixmap;
+            self.origin = Point::default();
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+    }
+
+    fn clamp(&self, origin: Point) -> Point {
+        let max_x = (self.pixmap.width as i32 - self.rect.width() as i32).max(0);
+        let max_y = (self.pixmap.height as i32 - self.rect.height() as i32).max(0);
+        pt!(origin.x.clamp(0, max_x), origin.y.clamp(0, max_y))
+    }
+}
+
+fn render(doc: &Arc<Mutex<Box<dyn Document>>>, source: &ZoomSource, rect: &Rectangle, zoom: f32, rotation: i8) -> Option<Pixmap> {
+    let mut doc = doc.lock().ok()?;
+    let pixmap = match *source {
+        ZoomSource::Table(offset) => {
+            doc.table_pixmap(offset, TABLE_WIDTH_RATIO * rect.width(), zoom)?
+        },
+        ZoomSource::Image(location, boundary) => {
+            let (width, height) = if rotation % 2 == 1 {
+                (rect.height() as f32, rect.width() as f32)
+            } else {
+                (rect.width() as f32, rect.height() as f32)
+            };
+            let (page_width, page_height) = doc.dims(location)?;
+            let scale = (width / (boundary.max.x - boundary.min.x))
+                            .min(height / (boundary.max.y - boundary.min.y)) * zoom;
+            let scale = scale.min((MAX_PAGE_PIXELS / (page_width * page_height)).sqrt());
+            let (pixmap, _) = doc.pixmap(Location::Exact(location), scale)?;
+            pixmap.cropped(&(boundary * scale).to_rect())
+        },
+    };
+    if rotation == 0 {
+        Some(pixmap)
+    } else {
+        Some(pixmap.rotated(rotation))
+    }
+}
+
+impl View for ZoomView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Swipe { start, end, .. }) if self.rect.includes(start) => {
+                self.pan(start - end, rq);
+                true
+            },
+            Event::Gesture(GestureEvent::Spread { axis: Axis::Horizontal, center, .. }) |
+            Event::Gesture(GestureEvent::Spread { axis: Axis::Diagonal, center, .. }) if self.rect.includes(center) => {
+                self.zoom(ZOOM_STEP, rq);
+                true
+            },
+            Event::Gesture(GestureEvent::Pinch { axis: Axis::Horizontal, center, .. }) |
+            Event::Gesture(GestureEvent::Pinch { axis: Axis::Diagonal, center, .. }) if self.rect.includes(center) => {
+                self.zoom(1.0 / ZOOM_STEP, rq);
+                true
+            },
+            Event::Gesture(GestureEvent::Rotate { quarter_turns, .. }) if quarter_turns != 0 => {
+                self.rotate(quarter_turns, rq);
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::ZoomView));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        fb.draw_rectangle(&self.rect, WHITE);
+        let width = (self.pixmap.width as i32 - self.origin.x).min(self.rect.width() as i32);
+        let height = (self.pixmap.height as i32 - self.origin.y).min(self.rect.height() as i32);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        // Small pixmaps are centered.
+        let dx = (self.rect.width() as i32 - width) / 2;
+        let dy = (self.rect.height() as i32 - height) / 2;
+        let frame = rect![self.origin, self.origin + pt!(width, height)];
+        fb.draw_framed_pixmap(&self.pixmap, &frame, self.rect.min + pt!(dx, dy));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::ZoomView)
+    }
+}
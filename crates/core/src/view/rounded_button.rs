@@ -103,4 +103,8 @@ impl View for RoundedButton {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
 }
@@ -76,6 +76,11 @@ impl SearchBar {
             input_field.set_text(text, true, rq, context);
         }
     }
+
+    pub fn text(&self) -> &str {
+        self.children[2].downcast_ref::<InputField>()
+            .map_or("", InputField::value)
+    }
 }
 
 impl View for SearchBar {
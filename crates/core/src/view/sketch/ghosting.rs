@@ -0,0 +1,97 @@
+use crate::geom::Rectangle;
+
+// The side, in pixels, of the square cells in which the fast updates are counted.
+const CELL_SIZE: i32 = 96;
+// The number of fast updates after which a cell needs a high quality refresh.
+const GHOSTING_THRESHOLD: u16 = 24;
+
+// Counts the fast updates of each region of the screen, to find the regions prone to ghosting.
+pub struct GhostingTracker {
+    rect: Rectangle,
+    columns: i32,
+    rows: i32,
+    counts: Vec<u16>,
+}
+
+impl GhostingTracker {
+    pub fn new(rect: Rectangle) -> GhostingTracker {
+        let columns = (rect.width() as i32 + CELL_SIZE - 1) / CELL_SIZE;
+        let rows = (rect.height() as i32 + CELL_SIZE - 1) / CELL_SIZE;
+        GhostingTracker {
+            rect,
+            columns,
+            rows,
+            counts: vec![0; (columns * rows) as usize],
+        }
+    }
+
+    pub fn record(&mut self, rect: &Rectangle) {
+        let rect = if let Some(rect) = rect.intersection(&self.rect) { rect } else { return };
+        let x0 = (rect.min.x - self.rect.min.x) / CELL_SIZE;
+        let y0 = (rect.min.y - self.rect.min.y) / CELL_SIZE;
+        let x1 = (rect.max.x - 1 - self.rect.min.x) / CELL_SIZE;
+        let y1 = (rect.max.y - 1 - self.rect.min.y) / CELL_SIZE;
+        for y in y0..=y1.min(self.rows - 1) {
+            for x in x0..=x1.min(self.columns - 1) {
+                let count = &mut self.counts[(y * self.columns + x) as usize];
+                *count = count.saturating_add(1);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+    }
+
+    // Returns the regions that need a refresh, and forgets their updates.
+    pub fn take_regions(&mut self) -> Vec<Rectangle> {
+        // The runs of consecutive cells, as rows and column ranges.
+        let mut runs: Vec<(i32, i32, i32, i32)> = Vec::new();
+
+        for y in 0..self.rows {
+            let mut x = 0;
+            while x < self.columns {
+                if self.counts[(y * self.columns + x) as usize] < GHOSTING_THRESHOLD {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.columns && self.counts[(y * self.columns + x) as usize] >= GHOSTING_THRESHOLD {
+                    self.counts[(y * self.columns + x) as usize] = 0;
+                    x += 1;
+                }
+                // Extend the run of the previous row with the same columns.
+                if let Some(run) = runs.iter_mut().find(|r| r.1 == y && r.2 == start && r.3 == x) {
+                    run.1 = y + 1;
+                } else {
+                    runs.push((y, y + 1, start, x));
+                }
+            }
+        }
+
+        runs.into_iter().filter_map(|(y0, y1, x0, x1)| {
+            let min = self.rect.min + pt!(x0 * CELL_SIZE, y0 * CELL_SIZE);
+            let max = self.rect.min + pt!(x1 * CELL_SIZE, y1 * CELL_SIZE);
+            Rectangle::new(min, max).intersection(&self.rect)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_regions() {
+        let mut tracker = GhostingTracker::new(rect![0, 0, 300, 300]);
+        for _ in 0..GHOSTING_THRESHOLD {
+            tracker.record(&rect![10, 10, 150, 150]);
+            tracker.record(&rect![290, 290, 295, 295]);
+        }
+        tracker.record(&rect![200, 10, 210, 20]);
+        let regions = tracker.take_regions();
+        assert_eq!(regions, vec![rect![0, 0, 192, 192], rect![288, 288, 300, 300]]);
+        assert!(tracker.take_regions().is_empty());
+        assert_eq!(tracker.counts[2], 1);
+    }
+}
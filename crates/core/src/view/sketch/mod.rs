@@ -1,54 +1,83 @@
-use std::fs::{self, File};
-use std::path::PathBuf;
+mod ghosting;
+mod words;
+pub mod strokes;
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use std::path::{Path, PathBuf};
 use fxhash::FxHashMap;
 use chrono::Local;
 use walkdir::WalkDir;
 use globset::Glob;
-use anyhow::Error;
+use anyhow::{Error, format_err};
 use crate::device::CURRENT_DEVICE;
 use crate::geom::{Point, Rectangle, CornerSpec};
+use crate::document::{Document, Location};
+use crate::document::pdf::PdfOpener;
 use crate::input::{DeviceEvent, FingerStatus};
 use crate::view::icon::{Icon, ICONS_PIXMAPS};
 use crate::view::notification::Notification;
+use crate::view::modal::{Modal, ModalKind};
 use crate::view::menu::{Menu, MenuKind};
+use crate::view::color_picker::{ColorPicker, push_recent_color};
 use crate::view::common::{locate_by_id};
 use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
-use crate::view::{EntryKind, EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{EntryKind, EntryId, ViewId, AppCmd, Id, ID_FEEDER};
 use crate::view::{SMALL_BAR_HEIGHT, BORDER_RADIUS_SMALL};
 use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
 use crate::settings::{ImportSettings, Pen};
-use crate::helpers::IsHidden;
+use crate::helpers::{IsHidden, write_atomically};
+use crate::storage::{self, Shortage};
+use crate::document::HumanSize;
+use crate::crypto::{encrypt, decrypt, is_encrypted};
+use crate::notebook;
+use crate::handwriting::{self, Jiix, JIIX_EXTENSION};
 use crate::font::Fonts;
 use crate::unit::scale_by_dpi;
-use crate::color::{BLACK, WHITE};
+use crate::color::WHITE;
+use crate::session::{Session, ApplicationState};
 use crate::context::Context;
+use self::ghosting::GhostingTracker;
+use self::words::WordsOverlay;
+use self::strokes::{StrokeLog, TouchState, STROKES_EXTENSION, draw_stroke_segment};
 
 const FILENAME_PATTERN: &str = "sketch-%Y%m%d_%H%M%S.png";
+const NOTE_FILENAME_PATTERN: &str = "%Y%m%d_%H%M%S.png";
+const ENCRYPTED_EXTENSION: &str = ".enc";
 const ICON_NAME: &str = "enclosed_menu";
+// Where the reader writes the snapshot of the page to annotate.
+pub const PAGE_SNAPSHOT_PATH: &str = "/tmp/plato-page.png";
 // https://oeis.org/A000041
 const PEN_SIZES: [i32; 12] = [1, 2, 3, 5, 7, 11, 15, 22, 30, 42, 56, 77];
-
-struct TouchState {
-    pt: Point,
-    time: f64,
-    radius: f32,
-}
-
-impl TouchState {
-    fn new(pt: Point, time: f64, radius: f32) -> TouchState {
-        TouchState { pt, time, radius }
-    }
-}
+// How long the fingers must be lifted before the ghosting is refreshed.
+const GHOSTING_REFRESH_DELAY: Duration = Duration::from_millis(1500);
 
 pub struct Sketch {
     id: Id,
     rect: Rectangle,
     children: Vec<Box<dyn View>>,
     pixmap: Pixmap,
+    // The template drawn beneath the new sketches.
+    background: Option<Pixmap>,
     fingers: FxHashMap<i32, TouchState>,
     pen: Pen,
     save_path: PathBuf,
     filename: String,
+    passphrase: Option<String>,
+    // Whether the sketch has unsaved changes.
+    dirty: bool,
+    // The page of the document being annotated.
+    page: Option<usize>,
+    saved: bool,
+    ghosting: GhostingTracker,
+    strokes: usize,
+    filename_pattern: &'static str,
+    // Whether the saved sketches are imported into the library.
+    import: bool,
+    record_strokes: bool,
+    // The strokes of the current sketch, when they're recorded.
+    stroke_log: Option<StrokeLog>,
 }
 
 impl Sketch {
@@ -72,17 +101,69 @@ impl Sketch {
                         .corners(Some(CornerSpec::Uniform(border_radius)));
         children.push(Box::new(icon) as Box<dyn View>);
         let save_path = context.library.home.join(&context.settings.sketch.save_path);
+        let background = context.settings.sketch.background.as_ref().and_then(|path| {
+            let path = context.library.home.join(path);
+            let background = load_background(&path, rect.width(), rect.height());
+            if background.is_none() {
+                eprintln!("Can't load sketch background {}.", path.display());
+            }
+            background
+        });
+        let pixmap = background.clone().unwrap_or_else(|| Pixmap::new(rect.width(), rect.height()));
         rq.add(RenderData::new(id, rect, UpdateMode::Full));
         Sketch {
             id,
             rect,
             children,
-            pixmap: Pixmap::new(rect.width(), rect.height()),
+            pixmap,
+            background,
             fingers: FxHashMap::default(),
             pen: context.settings.sketch.pen.clone(),
             save_path,
             filename: Local::now().format(FILENAME_PATTERN).to_string(),
+            passphrase: context.settings.sketch.passphrase.clone(),
+            dirty: false,
+            page: None,
+            saved: false,
+            ghosting: GhostingTracker::new(rect),
+            strokes: 0,
+            filename_pattern: FILENAME_PATTERN,
+            import: true,
+            record_strokes: context.settings.sketch.record_strokes,
+            stroke_log: Some(StrokeLog::new(rect.width(), rect.height()))
+                            .filter(|_| context.settings.sketch.record_strokes),
+        }
+    }
+
+    // Uses the snapshot of a page as the background, and reopens the page's previous sketch, if any.
+    pub fn annotate(mut self, page: usize, background: &Path, sketch: Option<&PathBuf>) -> Sketch {
+        if let Some(background) = load_background(background, self.rect.width(), self.rect.height()) {
+            self.pixmap.data_mut().copy_from_slice(background.data());
+            self.background = Some(background);
+        } else {
+            eprintln!("Can't load page snapshot {}.", background.display());
+        }
+        if let Some(sketch) = sketch {
+            if let Err(e) = self.load(sketch) {
+                eprintln!("Can't load sketch {}: {:#}.", sketch.display(), e);
+            }
+        }
+        self.page = Some(page);
+        self
+    }
+
+    // Draws a handwritten note of the given directory, or a new one.
+    pub fn note(mut self, dir: PathBuf, name: Option<&str>) -> Sketch {
+        self.save_path = dir;
+        self.filename_pattern = NOTE_FILENAME_PATTERN;
+        self.filename = Local::now().format(NOTE_FILENAME_PATTERN).to_string();
+        self.import = false;
+        if let Some(name) = name {
+            if let Err(e) = self.load(&PathBuf::from(name)) {
+                eprintln!("Can't load note {}: {:#}.", name, e);
+            }
         }
+        self
     }
 
     fn toggle_title_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
@@ -98,7 +179,7 @@ impl Sketch {
                 return;
             }
 
-            let glob = Glob::new("**/*.png").unwrap().compile_matcher();
+            let glob = Glob::new("**/*.{png,png.enc}").unwrap().compile_matcher();
             let mut loadables: Vec<PathBuf> =
                 WalkDir::new(&self.save_path).min_depth(1).into_iter()
                         .filter_map(|e| e.ok().filter(|e| !e.is_hidden())
@@ -120,28 +201,10 @@ impl Sketch {
                                                   self.pen.size == *s));
             }
 
-            let mut colors = vec![
-                EntryKind::RadioButton("White".to_string(),
-                                       EntryId::SetPenColor(WHITE),
-                                       self.pen.color == WHITE),
-                EntryKind::RadioButton("Black".to_string(),
-                                       EntryId::SetPenColor(BLACK),
-                                       self.pen.color == BLACK),
-            ];
-
-            for i in 1..=14 {
-                let c = i * 17;
-                if i % 7 == 1 {
-                    colors.push(EntryKind::Separator);
-                }
-                colors.push(EntryKind::RadioButton(format!("Gray {:02}", i),
-                                                   EntryId::SetPenColor(c),
-                                                   self.pen.color == c));
-            }
-
             let mut entries = vec![
                 EntryKind::SubMenu("Size".to_string(), sizes),
-                EntryKind::SubMenu("Color".to_string(), colors),
+                EntryKind::Command("Color".to_string(), EntryId::PickPenColor),
+                EntryKind::Command("Calibrate".to_string(), EntryId::Launch(AppCmd::PenCalibration)),
                 EntryKind::Separator,
                 EntryKind::Command("Save".to_string(), EntryId::Save),
                 EntryKind::Command("Refresh".to_string(), EntryId::Refresh),
@@ -149,11 +212,26 @@ impl Sketch {
                 EntryKind::Command("Quit".to_string(), EntryId::Quit),
             ];
 
+            if self.stroke_log.as_ref().is_some_and(StrokeLog::has_strokes) {
+                entries.insert(entries.len() - 2, EntryKind::Command("Replay".to_string(), EntryId::ReplayStrokes));
+                if self.passphrase.is_none() {
+                    entries.insert(entries.len() - 2, EntryKind::Command("Recognize Handwriting".to_string(), EntryId::RecognizeHandwriting));
+                }
+            }
+
+            if self.jiix_path().exists() {
+                entries.insert(entries.len() - 2, EntryKind::Command("Show Words".to_string(), EntryId::ShowWords));
+            }
+
             if !loadables.is_empty() {
                 entries.insert(entries.len() - 1, EntryKind::SubMenu("Load".to_string(),
                     loadables.into_iter().map(|e|
                         EntryKind::Command(e.to_string_lossy().into_owned(),
                                            EntryId::Load(e))).collect()));
+                // The exported document isn't encrypted.
+                if self.passphrase.is_none() {
+                    entries.insert(entries.len() - 1, EntryKind::Command("Export PDF".to_string(), EntryId::ExportSketches));
+                }
             }
 
             let sketch_menu = Menu::new(rect, ViewId::SketchMenu, MenuKind::Contextual, entries, context);
@@ -164,83 +242,293 @@ impl Sketch {
 
     fn load(&mut self, filename: &PathBuf) -> Result<(), Error> {
         let path = self.save_path.join(filename);
-        let decoder = png::Decoder::new(File::open(path)?);
+        let mut buf = fs::read(path)?;
+        if is_encrypted(&buf) {
+            let passphrase = self.passphrase.as_ref()
+                                 .ok_or_else(|| format_err!("missing passphrase"))?;
+            buf = decrypt(&buf, passphrase)?;
+        }
+        let decoder = png::Decoder::new(buf.as_slice());
         let mut reader = decoder.read_info()?;
         reader.next_frame(self.pixmap.data_mut())?;
         self.filename = filename.to_string_lossy().into_owned();
+        self.dirty = false;
+        // A sketch drawn without recording its strokes can't be replayed.
+        let path = self.stroke_log_path();
+        self.stroke_log = if self.record_strokes && path.exists() {
+            StrokeLog::load(&path)
+                      .map_err(|e| eprintln!("Can't load stroke log {}: {:#}.", path.display(), e))
+                      .ok()
+                      .filter(|log| log.width == self.rect.width() && log.height == self.rect.height())
+        } else {
+            None
+        };
         Ok(())
     }
 
-    fn save(&self) -> Result<(), Error> {
+    fn save(&mut self) -> Result<(), Error> {
         if !self.save_path.exists() {
             fs::create_dir_all(&self.save_path)?;
         }
-        let path = self.save_path.join(&self.filename);
-        self.pixmap.save(&path.to_string_lossy().into_owned())?;
+        let previous = self.filename.clone();
+        let mut buf = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut buf, self.pixmap.width, self.pixmap.height);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_color(png::ColorType::Grayscale);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(self.pixmap.data())?;
+        }
+        if let Some(passphrase) = self.passphrase.as_ref() {
+            if !self.filename.ends_with(ENCRYPTED_EXTENSION) {
+                self.filename.push_str(ENCRYPTED_EXTENSION);
+            }
+            buf = encrypt(&buf, passphrase)?;
+        } else if let Some(name) = self.filename.strip_suffix(ENCRYPTED_EXTENSION) {
+            self.filename = name.to_string();
+        }
+        write_atomically(self.save_path.join(&self.filename), &buf)?;
+        // The other version of the sketch, and its strokes, would be left behind.
+        if previous != self.filename {
+            fs::remove_file(self.save_path.join(&previous)).ok();
+            if self.passphrase.is_some() {
+                for extension in [STROKES_EXTENSION, JIIX_EXTENSION] {
+                    fs::remove_file(self.save_path.join(Path::new(&previous).with_extension(extension))).ok();
+                }
+            }
+        }
+        // The strokes would reveal the content of the encrypted sketches.
+        if let Some(log) = self.stroke_log.as_ref().filter(|_| self.passphrase.is_none()) {
+            let path = self.stroke_log_path();
+            if let Err(e) = log.save(&path) {
+                eprintln!("Can't save stroke log {}: {:#}.", path.display(), e);
+            }
+        }
+        self.dirty = false;
+        self.saved = true;
         Ok(())
     }
 
-    fn quit(&self, context: &mut Context) {
-        let import_settings = ImportSettings {
-            allowed_kinds: ["png".to_string()].iter().cloned().collect(),
-            .. Default::default()
+    fn stroke_log_path(&self) -> PathBuf {
+        self.save_path.join(Path::new(&self.filename).with_extension(STROKES_EXTENSION))
+    }
+
+    fn jiix_path(&self) -> PathBuf {
+        self.save_path.join(Path::new(&self.filename).with_extension(JIIX_EXTENSION))
+    }
+
+    // Sends the recorded strokes to the recognition service in the background,
+    // and saves its result next to the sketch.
+    fn recognize_handwriting(&self, hub: &Hub, context: &Context) {
+        let Some(log) = self.stroke_log.as_ref() else {
+            return;
         };
-        context.library.import(&import_settings);
+        let strokes = log.strokes();
+        let (width, height) = (log.width, log.height);
+        let settings = context.settings.handwriting.clone();
+        let path = self.jiix_path();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let msg = match handwriting::recognize(&strokes, width, height, CURRENT_DEVICE.dpi, &settings)
+                                        .and_then(|text| write_atomically(&path, text.as_bytes()).map(|_| text)) {
+                Ok(text) => {
+                    let count = Jiix::parse(&text).map(|jiix| jiix.words(CURRENT_DEVICE.dpi).len())
+                                                  .unwrap_or_default();
+                    format!("Recognized {} words.", count)
+                },
+                Err(e) => {
+                    eprintln!("Can't recognize handwriting: {:#}.", e);
+                    "Can't recognize the handwriting.".to_string()
+                },
+            };
+            hub.send(Event::Notify(msg)).ok();
+        });
     }
-}
 
-#[inline]
-fn draw_segment(pixmap: &mut Pixmap, ts: &mut TouchState, position: Point, time: f64, pen: &Pen, id: Id, fb_rect: &Rectangle, rq: &mut RenderQueue) {
-    let (start_radius, end_radius) = if pen.dynamic {
-        if time > ts.time {
-            let d = vec2!((position.x - ts.pt.x) as f32,
-                          (position.y - ts.pt.y) as f32).length();
-            let speed = d / (time - ts.time) as f32;
-            let base_radius = pen.size as f32 / 2.0;
-            let radius = base_radius * (1.0 + (pen.amplitude/base_radius) * speed.clamp(pen.min_speed, pen.max_speed) / (pen.max_speed - pen.min_speed));
-            (ts.radius, radius)
-        } else {
-            (ts.radius, ts.radius)
+    // Bundles the sketches into a PDF document in the background, and imports it.
+    fn export_sketches(&self, hub: &Hub) {
+        let dir = self.save_path.clone();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let msg = match notebook::bundle(&dir, CURRENT_DEVICE.dpi) {
+                Ok((path, count)) => {
+                    let import_settings = ImportSettings {
+                        allowed_kinds: ["pdf".to_string()].iter().cloned().collect(),
+                        .. Default::default()
+                    };
+                    hub.send(Event::Import(Box::new(import_settings))).ok();
+                    let name = path.file_name().map(|name| name.to_string_lossy().into_owned())
+                                   .unwrap_or_default();
+                    format!("Saved {} with {} pages.", name, count)
+                },
+                Err(e) => {
+                    eprintln!("Can't export sketches: {:#}.", e);
+                    "Can't export the sketches.".to_string()
+                },
+            };
+            hub.send(Event::Notify(msg)).ok();
+        });
+    }
+
+    fn show_words(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let path = self.jiix_path();
+        match fs::read_to_string(&path).map_err(Error::from).and_then(|text| Jiix::parse(&text)) {
+            Ok(jiix) => {
+                let overlay = WordsOverlay::new(self.rect, jiix.words(CURRENT_DEVICE.dpi));
+                self.children.push(Box::new(overlay) as Box<dyn View>);
+                rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+            },
+            Err(e) => {
+                eprintln!("Can't load words {}: {:#}.", path.display(), e);
+                let notif = Notification::new("Can't load the recognized words.".to_string(), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+            },
         }
-    } else {
-        let radius = pen.size as f32 / 2.0;
-        (radius, radius)
-    };
+    }
 
-    let rect = Rectangle::from_segment(ts.pt, position,
-                                       start_radius.ceil() as i32,
-                                       end_radius.ceil() as i32);
+    fn show_modal(&mut self, text: &str, kind: ModalKind, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let modal = Modal::new(ViewId::Modal, text.to_string(), kind, hub, rq, context);
+        rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+        self.children.push(Box::new(modal) as Box<dyn View>);
+    }
 
-    pixmap.draw_segment(ts.pt, position, start_radius, end_radius, pen.color);
+    // Saves the sketch, and reports the outcome. The lack of space or memory, on the file system
+    // of the save path, is only a warning: the save is attempted anyway.
+    // Returns whether the sketch was saved.
+    fn save_and_report(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        let shortage = self.save_path.ancestors()
+                           .find(|path| path.exists())
+                           .and_then(storage::check);
+        match (self.save(), shortage) {
+            (Err(e), Some(shortage @ Shortage::Space(..))) => {
+                let kind = ModalKind::Choice(vec![("Clear Caches".to_string(), EntryId::ClearCaches)]);
+                let text = format!("Can't save sketch: {}. {} Free some space, or empty the trash from the library.", e, shortage.message());
+                self.show_modal(&text, kind, hub, rq, context);
+                false
+            },
+            (Err(e), Some(shortage @ Shortage::Memory(..))) => {
+                let text = format!("Can't save sketch: {}. {} Restart the application.", e, shortage.message());
+                self.show_modal(&text, ModalKind::Message, hub, rq, context);
+                false
+            },
+            (Err(e), None) => {
+                let notif = Notification::new(format!("Can't save sketch: {}.", e), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                false
+            },
+            (Ok(..), Some(shortage)) => {
+                let msg = format!("Saved {}. {}", self.filename, shortage.message());
+                let notif = Notification::new(msg, hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                true
+            },
+            (Ok(..), None) => {
+                if context.settings.sketch.notify_success {
+                    let notif = Notification::new(format!("Saved {}.", self.filename), hub, rq, context);
+                    self.children.push(Box::new(notif) as Box<dyn View>);
+                }
+                true
+            },
+        }
+    }
 
-    if let Some(render_rect) = rect.intersection(fb_rect) {
-        rq.add(RenderData::no_wait(id, render_rect, UpdateMode::FastMono));
+    fn quit(&self, hub: &Hub) {
+        hub.send(Event::Back).ok();
+        // Link the sketch to the page once the reader is back.
+        if let Some(page) = self.page.filter(|_| self.saved) {
+            hub.send(Event::PageSketched(page, PathBuf::from(&self.filename))).ok();
+        }
+        if self.import {
+            let import_settings = ImportSettings {
+                allowed_kinds: ["png".to_string()].iter().cloned().collect(),
+                .. Default::default()
+            };
+            hub.send(Event::Import(Box::new(import_settings))).ok();
+        }
     }
+}
+
+// Rasterizes the template (PNG, SVG, PDF…) to fit the given dimensions, centered on a white page.
+fn load_background(path: &Path, width: u32, height: u32) -> Option<Pixmap> {
+    let (template, _) = PdfOpener::new().and_then(|opener| {
+        opener.open(path)
+    }).and_then(|mut doc| {
+        doc.dims(0).and_then(|dims| {
+            let scale = (width as f32 / dims.0).min(height as f32 / dims.1);
+            doc.pixmap(Location::Exact(0), scale)
+        })
+    })?;
+    let mut pixmap = Pixmap::new(width, height);
+    let dx = (width as i32 - template.width as i32) / 2;
+    let dy = (height as i32 - template.height as i32) / 2;
+    pixmap.draw_pixmap(&template, pt!(dx.max(0), dy.max(0)));
+    Some(pixmap)
+}
 
-    ts.pt = position;
-    ts.time = time;
-    ts.radius = end_radius;
+#[inline]
+// Returns the region of the screen that was updated, if any.
+fn draw_segment(pixmap: &mut Pixmap, ts: &mut TouchState, position: Point, time: f64, pen: &Pen, id: Id, fb_rect: &Rectangle, rq: &mut RenderQueue) -> Option<Rectangle> {
+    let rect = draw_stroke_segment(pixmap, ts, position, time, pen);
+    let render_rect = rect.intersection(fb_rect)?;
+    rq.add(RenderData::no_wait(id, render_rect, UpdateMode::FastMono));
+    Some(render_rect)
 }
 
 impl View for Sketch {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
             Event::Device(DeviceEvent::Finger { status: FingerStatus::Motion, id, position, time }) => {
+                let position = context.settings.sketch.pen.correct(position);
                 if let Some(ts) = self.fingers.get_mut(&id) {
-                    draw_segment(&mut self.pixmap, ts, position, time, &self.pen, self.id, &self.rect, rq);
+                    if let Some(rect) = draw_segment(&mut self.pixmap, ts, position, time, &self.pen, self.id, &self.rect, rq) {
+                        self.ghosting.record(&rect);
+                    }
+                    if let Some(log) = self.stroke_log.as_mut() {
+                        log.motion(id, position, time);
+                    }
+                    self.dirty = true;
                 }
                 true
             },
             Event::Device(DeviceEvent::Finger { status: FingerStatus::Down, id, position, time }) => {
+                let position = context.settings.sketch.pen.correct(position);
                 let radius = self.pen.size as f32 / 2.0;
                 self.fingers.insert(id, TouchState::new(position, time, radius));
+                if let Some(log) = self.stroke_log.as_mut() {
+                    log.down(id, position, time, &self.pen);
+                }
+                self.strokes += 1;
                 true
             },
             Event::Device(DeviceEvent::Finger { status: FingerStatus::Up, id, position, time }) => {
+                let position = context.settings.sketch.pen.correct(position);
                 if let Some(ts) = self.fingers.get_mut(&id) {
-                    draw_segment(&mut self.pixmap, ts, position, time, &self.pen, self.id, &self.rect, rq);
+                    if let Some(rect) = draw_segment(&mut self.pixmap, ts, position, time, &self.pen, self.id, &self.rect, rq) {
+                        self.ghosting.record(&rect);
+                    }
+                    if let Some(log) = self.stroke_log.as_mut() {
+                        log.up(id, position, time);
+                    }
+                    self.dirty = true;
                 }
                 self.fingers.remove(&id);
+                if self.fingers.is_empty() && context.settings.sketch.refresh_ghosting {
+                    let hub2 = hub.clone();
+                    let (view_id, strokes) = (self.id, self.strokes);
+                    thread::spawn(move || {
+                        thread::sleep(GHOSTING_REFRESH_DELAY);
+                        hub2.send(Event::RefreshGhosting(view_id, strokes)).ok();
+                    });
+                }
+                true
+            },
+            Event::RefreshGhosting(id, strokes) if id == self.id => {
+                // Only when no stroke was started in the meantime.
+                if strokes == self.strokes && self.fingers.is_empty() {
+                    for rect in self.ghosting.take_regions() {
+                        rq.add(RenderData::new(self.id, rect, UpdateMode::Full));
+                    }
+                }
                 true
             },
             Event::ToggleNear(ViewId::TitleMenu, rect) => {
@@ -251,8 +539,15 @@ impl View for Sketch {
                 self.pen.size = size;
                 true
             },
+            Event::Select(EntryId::PickPenColor) => {
+                let color_picker = ColorPicker::new(ViewId::PenColorPicker, self.pen.color, context);
+                rq.add(RenderData::new(color_picker.id(), *color_picker.rect(), UpdateMode::Gui));
+                self.children.push(Box::new(color_picker) as Box<dyn View>);
+                true
+            },
             Event::Select(EntryId::SetPenColor(color)) => {
                 self.pen.color = color;
+                push_recent_color(&mut context.settings.sketch.recent_colors, color);
                 true
             },
             Event::Select(EntryId::TogglePenDynamism) => {
@@ -270,35 +565,72 @@ impl View for Sketch {
                 true
             },
             Event::Select(EntryId::Refresh) => {
+                self.ghosting.clear();
                 rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
                 true
             },
-            Event::Select(EntryId::New) => {
-                self.pixmap.clear(WHITE);
-                self.filename = Local::now().format(FILENAME_PATTERN).to_string();
+            Event::Select(EntryId::New) if self.dirty => {
+                let kind = ModalKind::Confirm(Event::Select(EntryId::Discard));
+                self.show_modal("Discard the current sketch?", kind, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::New) | Event::Select(EntryId::Discard) => {
+                self.dirty = false;
+                if let Some(background) = self.background.as_ref() {
+                    self.pixmap.data_mut().copy_from_slice(background.data());
+                } else {
+                    self.pixmap.clear(WHITE);
+                }
+                self.filename = Local::now().format(self.filename_pattern).to_string();
+                self.stroke_log = Some(StrokeLog::new(self.rect.width(), self.rect.height()))
+                                      .filter(|_| self.record_strokes);
                 rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
                 true
             },
+            Event::Select(EntryId::ReplayStrokes) => {
+                if let Some(log) = self.stroke_log.as_ref() {
+                    self.pixmap = log.replay(self.background.as_ref());
+                    self.ghosting.clear();
+                    rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+                }
+                true
+            },
+            Event::Select(EntryId::ClearCaches) => {
+                let freed = storage::clear_caches(context.library.cache_home());
+                let notif = Notification::new(format!("Freed {}.", freed.human_size()), hub, rq, context);
+                self.children.push(Box::new(notif) as Box<dyn View>);
+                true
+            },
+            Event::Select(EntryId::RecognizeHandwriting) => {
+                self.recognize_handwriting(hub, context);
+                true
+            },
+            Event::Select(EntryId::ShowWords) => {
+                self.show_words(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ExportSketches) => {
+                self.export_sketches(hub);
+                true
+            },
             Event::Select(EntryId::Save) => {
-                let mut msg = match self.save() {
-                    Err(e) => Some(format!("Can't save sketch: {}.", e)),
-                    Ok(..) => {
-                        if context.settings.sketch.notify_success {
-                            Some(format!("Saved {}.", self.filename))
-                        } else {
-                            None
-                        }
-                    },
-                };
-                if let Some(msg) = msg.take() {
-                    let notif = Notification::new(msg, hub, rq, context);
-                    self.children.push(Box::new(notif) as Box<dyn View>);
+                self.save_and_report(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::Quit) if self.dirty => {
+                let kind = ModalKind::Choice(vec![("Save".to_string(), EntryId::SaveAndQuit),
+                                                  ("Discard".to_string(), EntryId::DiscardAndQuit)]);
+                self.show_modal("The sketch has unsaved changes.", kind, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::SaveAndQuit) => {
+                if self.save_and_report(hub, rq, context) {
+                    self.quit(hub);
                 }
                 true
             },
-            Event::Select(EntryId::Quit) => {
-                self.quit(context);
-                hub.send(Event::Back).ok();
+            Event::Select(EntryId::Quit) | Event::Select(EntryId::DiscardAndQuit) => {
+                self.quit(hub);
                 true
             },
             _ => false,
@@ -341,4 +673,17 @@ impl View for Sketch {
     fn id(&self) -> Id {
         self.id
     }
+
+    fn save_state(&self, session: &mut Session) {
+        if self.page.is_some() {
+            return;
+        }
+        let name = Some(self.filename.clone()).filter(|name| !name.ends_with(ENCRYPTED_EXTENSION) &&
+                                                            self.save_path.join(name).exists());
+        session.application = Some(if self.filename_pattern == NOTE_FILENAME_PATTERN {
+            ApplicationState::HandwrittenNote { name }
+        } else {
+            ApplicationState::Sketch { file: name }
+        });
+    }
 }
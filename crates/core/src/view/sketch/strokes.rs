@@ -0,0 +1,245 @@
+use std::path::Path;
+use fxhash::FxHashMap;
+use serde::{Serialize, Deserialize};
+use anyhow::Error;
+use crate::geom::{Point, Rectangle};
+use crate::framebuffer::{Framebuffer, Pixmap};
+use crate::settings::Pen;
+use crate::helpers::{load_json, save_json};
+use crate::handwriting::Stroke;
+
+// The extension of the stroke logs, saved next to their sketches.
+pub const STROKES_EXTENSION: &str = "strokes.json";
+
+pub struct TouchState {
+    pt: Point,
+    time: f64,
+    radius: f32,
+}
+
+impl TouchState {
+    pub fn new(pt: Point, time: f64, radius: f32) -> TouchState {
+        TouchState { pt, time, radius }
+    }
+}
+
+// The positions are the corrected ones, in the sketch's coordinates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum StrokeEvent {
+    // The pen of the following strokes.
+    Pen(Pen),
+    Down { id: i32, x: i32, y: i32, time: f64 },
+    Motion { id: i32, x: i32, y: i32, time: f64 },
+    Up { id: i32, x: i32, y: i32, time: f64 },
+}
+
+// The finger events of a sketch, enough to draw it again identically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct StrokeLog {
+    pub width: u32,
+    pub height: u32,
+    pub events: Vec<StrokeEvent>,
+}
+
+impl StrokeLog {
+    pub fn new(width: u32, height: u32) -> StrokeLog {
+        StrokeLog { width, height, events: Vec::new() }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<StrokeLog, Error> {
+        load_json(path)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        save_json(self, path)
+    }
+
+    pub fn down(&mut self, id: i32, pt: Point, time: f64, pen: &Pen) {
+        let current = self.events.iter().rev().find_map(|event| match event {
+            StrokeEvent::Pen(pen) => Some(pen),
+            _ => None,
+        });
+        if current != Some(pen) {
+            self.events.push(StrokeEvent::Pen(pen.clone()));
+        }
+        self.events.push(StrokeEvent::Down { id, x: pt.x, y: pt.y, time });
+    }
+
+    pub fn motion(&mut self, id: i32, pt: Point, time: f64) {
+        self.events.push(StrokeEvent::Motion { id, x: pt.x, y: pt.y, time });
+    }
+
+    pub fn up(&mut self, id: i32, pt: Point, time: f64) {
+        self.events.push(StrokeEvent::Up { id, x: pt.x, y: pt.y, time });
+    }
+
+    pub fn has_strokes(&self) -> bool {
+        self.events.iter().any(|event| matches!(event, StrokeEvent::Down { .. }))
+    }
+
+    // The points of each stroke, from the finger's down to its up, in the order they were started.
+    pub fn strokes(&self) -> Vec<Stroke> {
+        let mut strokes: Vec<Stroke> = Vec::new();
+        let mut fingers = FxHashMap::default();
+        for event in &self.events {
+            match *event {
+                StrokeEvent::Pen(..) => (),
+                StrokeEvent::Down { id, x, y, time } => {
+                    fingers.insert(id, strokes.len());
+                    strokes.push(vec![(x, y, time)]);
+                },
+                StrokeEvent::Motion { id, x, y, time } => {
+                    if let Some(&index) = fingers.get(&id) {
+                        strokes[index].push((x, y, time));
+                    }
+                },
+                StrokeEvent::Up { id, x, y, time } => {
+                    if let Some(index) = fingers.remove(&id) {
+                        strokes[index].push((x, y, time));
+                    }
+                },
+            }
+        }
+        strokes
+    }
+
+    // Draws the strokes on the given background, or on a white page.
+    pub fn replay(&self, background: Option<&Pixmap>) -> Pixmap {
+        let mut pixmap = background.filter(|b| b.width == self.width && b.height == self.height)
+                                   .cloned()
+                                   .unwrap_or_else(|| Pixmap::new(self.width, self.height));
+        let mut pen = Pen::default();
+        let mut fingers = FxHashMap::default();
+
+        for event in &self.events {
+            match *event {
+                StrokeEvent::Pen(ref p) => pen = p.clone(),
+                StrokeEvent::Down { id, x, y, time } => {
+                    fingers.insert(id, TouchState::new(pt!(x, y), time, pen.size as f32 / 2.0));
+                },
+                StrokeEvent::Motion { id, x, y, time } => {
+                    if let Some(ts) = fingers.get_mut(&id) {
+                        draw_stroke_segment(&mut pixmap, ts, pt!(x, y), time, &pen);
+                    }
+                },
+                StrokeEvent::Up { id, x, y, time } => {
+                    if let Some(mut ts) = fingers.remove(&id) {
+                        draw_stroke_segment(&mut pixmap, &mut ts, pt!(x, y), time, &pen);
+                    }
+                },
+            }
+        }
+
+        pixmap
+    }
+}
+
+// Draws the segment from the last position of the finger to the given one, and returns its bounding rectangle.
+// When the pen is dynamic, the radius grows with the finger's speed.
+pub fn draw_stroke_segment(pixmap: &mut Pixmap, ts: &mut TouchState, position: Point, time: f64, pen: &Pen) -> Rectangle {
+    let (start_radius, end_radius) = if pen.dynamic {
+        if time > ts.time {
+            let d = vec2!((position.x - ts.pt.x) as f32,
+                          (position.y - ts.pt.y) as f32).length();
+            let speed = d / (time - ts.time) as f32;
+            let base_radius = pen.size as f32 / 2.0;
+            let radius = base_radius * (1.0 + (pen.amplitude/base_radius) * speed.clamp(pen.min_speed, pen.max_speed) / (pen.max_speed - pen.min_speed));
+            (ts.radius, radius)
+        } else {
+            (ts.radius, ts.radius)
+        }
+    } else {
+        let radius = pen.size as f32 / 2.0;
+        (radius, radius)
+    };
+
+    let rect = Rectangle::from_segment(ts.pt, position,
+                                       start_radius.ceil() as i32,
+                                       end_radius.ceil() as i32);
+
+    pixmap.draw_segment(ts.pt, position, start_radius, end_radius, pen.color);
+
+    ts.pt = position;
+    ts.time = time;
+    ts.radius = end_radius;
+
+    rect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+
+    fn stroke(log: &mut StrokeLog, id: i32, points: &[(i32, i32)], start: f64, step: f64, pen: &Pen) {
+        let (x, y) = points[0];
+        log.down(id, pt!(x, y), start, pen);
+        for (i, &(x, y)) in points.iter().enumerate().skip(1) {
+            let time = start + i as f64 * step;
+            if i == points.len() - 1 {
+                log.up(id, pt!(x, y), time);
+            } else {
+                log.motion(id, pt!(x, y), time);
+            }
+        }
+    }
+
+    fn inked(pixmap: &Pixmap) -> usize {
+        pixmap.data().iter().filter(|v| **v != WHITE).count()
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let pen = Pen { size: 6, dynamic: true, amplitude: 4.0, min_speed: 0.0, max_speed: 2000.0, .. Default::default() };
+        let mut log = StrokeLog::new(120, 80);
+        stroke(&mut log, 0, &[(10, 10), (30, 14), (55, 30), (80, 32), (110, 70)], 1.0, 0.016, &pen);
+        stroke(&mut log, 1, &[(100, 10), (60, 40), (20, 70)], 2.0, 0.05, &Pen { color: 96, .. pen.clone() });
+        let first = log.replay(None);
+        let second = log.replay(None);
+        assert_eq!(first.data(), second.data());
+        assert!(inked(&first) > 0);
+
+        let json = serde_json::to_string(&log).unwrap();
+        let log2: StrokeLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(log2, log);
+        assert_eq!(log2.replay(None).data(), first.data());
+    }
+
+    #[test]
+    fn test_fixed_pen() {
+        let pen = Pen { size: 10, color: BLACK, dynamic: false, .. Default::default() };
+        let mut log = StrokeLog::new(100, 100);
+        stroke(&mut log, 0, &[(20, 50), (50, 50), (80, 50)], 0.0, 0.01, &pen);
+        let pixmap = log.replay(None);
+        assert_eq!(pixmap.get_pixel(50, 50), BLACK);
+        assert_eq!(pixmap.get_pixel(20, 54), BLACK);
+        assert_eq!(pixmap.get_pixel(50, 56), WHITE);
+        assert_eq!(pixmap.get_pixel(10, 50), WHITE);
+        assert_eq!(pixmap.get_pixel(90, 50), WHITE);
+        let mut background = Pixmap::new(100, 100);
+        background.set_pixel(90, 50, 128);
+        let pixmap = log.replay(Some(&background));
+        assert_eq!((pixmap.get_pixel(50, 50), pixmap.get_pixel(90, 50)), (BLACK, 128));
+        // The pen is only recorded when it changes.
+        assert_eq!(log.events.iter().filter(|e| matches!(e, StrokeEvent::Pen(..))).count(), 1);
+        stroke(&mut log, 1, &[(10, 10), (10, 90)], 1.0, 0.5, &pen);
+        let strokes = log.strokes();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0].len(), 3);
+        assert_eq!(strokes[1], vec![(10, 10, 1.0), (10, 90, 1.5)]);
+    }
+
+    #[test]
+    fn test_dynamic_pen() {
+        let pen = Pen { size: 4, dynamic: true, amplitude: 6.0, min_speed: 0.0, max_speed: 2000.0, .. Default::default() };
+        let points = [(10, 40), (40, 40), (70, 40), (100, 40), (130, 40)];
+        let mut slow = StrokeLog::new(140, 80);
+        stroke(&mut slow, 0, &points, 0.0, 1.0, &pen);
+        let mut fast = StrokeLog::new(140, 80);
+        stroke(&mut fast, 0, &points, 0.0, 0.01, &pen);
+        // The faster strokes are thicker.
+        assert!(inked(&fast.replay(None)) > inked(&slow.replay(None)));
+    }
+}
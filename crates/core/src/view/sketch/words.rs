@@ -0,0 +1,92 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Rectangle, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::color::{BLACK, WHITE};
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, THICKNESS_SMALL};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// Shows the recognized words over the strokes of the sketch, within their bounding boxes.
+pub struct WordsOverlay {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    words: Vec<(String, Rectangle)>,
+}
+
+impl WordsOverlay {
+    pub fn new(rect: Rectangle, words: Vec<(String, Rectangle)>) -> WordsOverlay {
+        WordsOverlay {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            words,
+        }
+    }
+}
+
+impl View for WordsOverlay {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            // Nothing is drawn while the words are shown.
+            Event::Device(DeviceEvent::Finger { .. }) => true,
+            Event::Gesture(GestureEvent::Tap(..)) => {
+                hub.send(Event::Close(ViewId::SketchWords)).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as u16;
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = x_height / 2;
+
+        for (label, rect) in &self.words {
+            fb.draw_rectangle_outline(rect, &BorderSpec { thickness, color: BLACK });
+            // The label goes above its box, or below when there's no room at the top.
+            let plan = font.plan(label, Some(self.rect.width() as i32), None);
+            let label_height = 2 * x_height + 2 * padding;
+            let top = if rect.min.y - label_height >= self.rect.min.y {
+                rect.min.y - label_height
+            } else {
+                rect.max.y
+            };
+            let left = rect.min.x.min(self.rect.max.x - plan.width - 2 * padding).max(self.rect.min.x);
+            let label_rect = rect![left, top, left + plan.width + 2 * padding, top + label_height];
+            fb.draw_rectangle(&label_rect, WHITE);
+            fb.draw_rectangle_outline(&label_rect, &BorderSpec { thickness, color: BLACK });
+            font.render(fb, BLACK, &plan, pt!(left + padding, top + padding + 2 * x_height));
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::SketchWords)
+    }
+}
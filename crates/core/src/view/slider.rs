@@ -19,6 +19,8 @@ pub struct Slider {
     value: f32,
     min_value: f32,
     max_value: f32,
+    // The value is a multiple of `step` away from `min_value`.
+    step: Option<f32>,
     active: bool,
     last_x: i32,
 }
@@ -33,12 +35,22 @@ impl Slider {
             value,
             min_value,
             max_value,
+            step: None,
             active: false,
             last_x: -1,
         }
     }
 
-    pub fn update_value(&mut self, x_hit: i32) {
+    pub fn step(mut self, step: f32) -> Slider {
+        if step > 0.0 {
+            self.step = Some(step);
+            self.value = snap_value(self.value, self.min_value, self.max_value, step);
+        }
+        self
+    }
+
+    // Returns `true` if the value changed.
+    pub fn update_value(&mut self, x_hit: i32) -> bool {
         let dpi = CURRENT_DEVICE.dpi;
         let button_diameter = scale_by_dpi(BUTTON_DIAMETER, dpi) as i32;
         let (small_radius, big_radius) = halves(button_diameter);
@@ -47,7 +59,24 @@ impl Slider {
         let progress = ((x_offset - self.rect.min.x - small_radius) as f32 /
                         (self.rect.width() as i32 - button_diameter) as f32)
                        .clamp(0.0, 1.0);
-        self.value = self.min_value + progress * (self.max_value - self.min_value);
+        let mut value = self.min_value + progress * (self.max_value - self.min_value);
+        if let Some(step) = self.step {
+            value = snap_value(value, self.min_value, self.max_value, step);
+        }
+        let changed = (self.value - value).abs() >= f32::EPSILON;
+        self.value = value;
+        changed
+    }
+
+    fn value_label(&self) -> String {
+        match self.step {
+            // Don't show more decimals than the step has.
+            Some(step) => {
+                let precision = (-step.log10()).ceil().max(0.0) as usize;
+                format!("{:.*}", precision, self.value)
+            },
+            None => format!("{:.1}", self.value),
+        }
     }
 
     pub fn update(&mut self, value: f32, rq: &mut RenderQueue) {
@@ -72,10 +101,12 @@ impl View for Slider {
                         true
                     },
                     FingerStatus::Motion if self.active && position.x != self.last_x => {
-                        self.update_value(position.x);
-                        rq.add(RenderData::no_wait(self.id, self.rect, UpdateMode::FastMono));
-                        bus.push_back(Event::Slider(self.slider_id, self.value, status));
                         self.last_x = position.x;
+                        // Only redraw when the value changes, each fast update adds to the ghosting.
+                        if self.update_value(position.x) {
+                            rq.add(RenderData::no_wait(self.id, self.rect, UpdateMode::FastMono));
+                            bus.push_back(Event::Slider(self.slider_id, self.value, status));
+                        }
                         true
                     },
                     FingerStatus::Up if self.active => {
@@ -132,7 +163,7 @@ impl View for Slider {
                                               &fill_color);
 
         let font = font_from_style(fonts, &SLIDER_VALUE, dpi);
-        let plan = font.plan(&format!("{:.1}", self.value), None, None);
+        let plan = font.plan(self.value_label(), None, None);
         let x_height = font.x_heights.1 as i32;
 
         let x_drift = if self.value > (self.min_value + self.max_value) / 2.0 {
@@ -165,3 +196,23 @@ impl View for Slider {
         self.id
     }
 }
+
+fn snap_value(value: f32, min_value: f32, max_value: f32, step: f32) -> f32 {
+    let steps = ((value - min_value) / step).round();
+    (min_value + steps * step).clamp(min_value.min(max_value), max_value.max(min_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snap_value;
+
+    #[test]
+    fn test_snap_value() {
+        assert_eq!(snap_value(42.4, 0.0, 100.0, 1.0), 42.0);
+        assert_eq!(snap_value(42.6, 0.0, 100.0, 5.0), 45.0);
+        assert_eq!(snap_value(99.9, 0.0, 100.0, 3.0), 99.0);
+        assert_eq!(snap_value(120.0, 0.0, 100.0, 1.0), 100.0);
+        assert!((snap_value(2.26, 1.0, 5.0, 0.1) - 2.3).abs() < 1e-5);
+        assert_eq!(snap_value(-3.0, 0.0, 10.0, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,187 @@
+use std::thread;
+use std::path::Path;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::label::Label;
+use super::button::Button;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE};
+use crate::unit::scale_by_dpi;
+use crate::document::open_with_passwords;
+use crate::metadata::{Info, Status};
+use crate::statistics::{compute, summary};
+use crate::context::Context;
+
+// Shows the statistics of a document under its title. Tapping anywhere closes it.
+pub struct StatisticsView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    title: String,
+    rows: Vec<(String, String)>,
+    // The label of the button shown below the rows, and the event it sends.
+    action: Option<(String, Event)>,
+}
+
+impl StatisticsView {
+    pub fn new(view_id: ViewId, title: String, rows: Vec<(String, String)>, context: &mut Context) -> StatisticsView {
+        let mut statistics_view = StatisticsView {
+            id: ID_FEEDER.next(),
+            rect: Rectangle::default(),
+            children: Vec::new(),
+            view_id,
+            title,
+            rows,
+            action: None,
+        };
+        statistics_view.layout(context);
+        statistics_view
+    }
+
+    pub fn from_info(view_id: ViewId, info: &Info, context: &mut Context) -> Option<StatisticsView> {
+        let statistics = info.statistics.as_ref()?;
+        let progress = match info.status() {
+            Status::New => 0.0,
+            Status::Reading(progress) => progress,
+            Status::Finished => 1.0,
+        };
+        let rows = summary(statistics, progress, context.library.words_per_minute());
+        let title = if info.title.is_empty() { info.file.path.display().to_string() } else { info.title.clone() };
+        Some(StatisticsView::new(view_id, title, rows, context))
+    }
+
+    pub fn action(mut self, label: String, event: Event, context: &mut Context) -> StatisticsView {
+        self.action = Some((label, event));
+        self.layout(context);
+        self
+    }
+
+    fn layout(&mut self, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let (line_height, padding) = {
+            let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+            (font.line_height(), font.em() as i32)
+        };
+        let view_width = 2 * width.min(height) as i32 / 3 + 2 * margin();
+        let action_height = if self.action.is_some() { 2 * line_height } else { 0 };
+        let view_height = (self.rows.len() as i32 + 2) * line_height + action_height + 2 * margin();
+        let dx = (width as i32 - view_width) / 2;
+        let dy = (height as i32 - view_height) / 2;
+        self.rect = rect![dx, dy, dx + view_width, dy + view_height];
+
+        let (left, right) = (self.rect.min.x + margin(), self.rect.max.x - margin());
+        let mut y = self.rect.min.y + margin();
+        self.children.clear();
+        self.children.push(Box::new(Label::new(rect![left, y, right, y + line_height],
+                                               self.title.clone(), Align::Center)) as Box<dyn View>);
+        y += 2 * line_height;
+        let middle = (left + right) / 2;
+        for (name, value) in &self.rows {
+            self.children.push(Box::new(Label::new(rect![left, y, middle, y + line_height],
+                                                   name.clone(), Align::Left(padding))) as Box<dyn View>);
+            self.children.push(Box::new(Label::new(rect![middle, y, right, y + line_height],
+                                                   value.clone(), Align::Right(padding))) as Box<dyn View>);
+            y += line_height;
+        }
+        if let Some((ref label, _)) = self.action {
+            y += line_height;
+            let button_width = (right - left) / 2;
+            let button_rect = rect![middle - button_width / 2, y, middle + button_width / 2, y + line_height];
+            self.children.push(Box::new(Button::new(button_rect, Event::Validate, label.clone())) as Box<dyn View>);
+        }
+    }
+}
+
+// Counts the words of the document in the background: `Event::StatisticsComputed` is sent when done.
+pub fn compute_statistics(path: &Path, hub: &Hub, context: &Context) {
+    let full_path = context.library.home.join(path);
+    let path = path.to_path_buf();
+    let passwords = context.passwords.clone();
+    let hub = hub.clone();
+    thread::spawn(move || {
+        match open_with_passwords(&full_path, &passwords) {
+            Some(mut doc) => {
+                let statistics = compute(doc.as_mut());
+                hub.send(Event::StatisticsComputed(path, statistics)).ok();
+            },
+            None => {
+                hub.send(Event::Notify("Can't open the document.".to_string())).ok();
+            },
+        }
+    });
+}
+
+fn margin() -> i32 {
+    let dpi = CURRENT_DEVICE.dpi;
+    scale_by_dpi(THICKNESS_LARGE, dpi) as i32 + scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32
+}
+
+impl View for StatisticsView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Validate => {
+                bus.push_back(Event::Close(self.view_id));
+                if let Some((_, ref event)) = self.action {
+                    bus.push_back(event.clone());
+                }
+                true
+            },
+            Event::Gesture(GestureEvent::Tap(..)) => {
+                bus.push_back(Event::Close(self.view_id));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.layout(context);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
@@ -0,0 +1,267 @@
+mod usage_list;
+
+use crate::device::CURRENT_DEVICE;
+use crate::document::HumanSize;
+use crate::storage::{StorageUsage, Category, scan, clear_caches};
+use crate::geom::{Rectangle, halves, divide};
+use crate::gesture::GestureEvent;
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::notification::Notification;
+use crate::view::modal::{Modal, ModalKind};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::usage_list::{UsageList, UsageRow};
+
+// Shows the disk usage by category, or the biggest documents and sketches:
+// tap one of them to go to its directory.
+pub struct Storage {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    usage: StorageUsage,
+    show_biggest: bool,
+}
+
+impl Storage {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Storage {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+        let usage = scan_library(context);
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  title(&usage),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let usage_list = UsageList::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                              rect.max.x, rect.max.y - small_height - small_thickness],
+                                        context);
+        children.push(Box::new(usage_list) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut storage = Storage {
+            id,
+            rect,
+            children,
+            usage,
+            show_biggest: false,
+        };
+
+        storage.update_list(&mut RenderQueue::new(), context);
+        storage.update_buttons(&mut RenderQueue::new());
+        storage
+    }
+
+    fn update_list(&mut self, rq: &mut RenderQueue, context: &Context) {
+        let home = &context.library.home;
+        let rows = if self.show_biggest {
+            self.usage.biggest.iter().map(|(path, size)| UsageRow {
+                label: path.display().to_string(),
+                size: *size,
+                count: None,
+                directory: path.parent().map(|parent| home.join(parent)),
+            }).collect()
+        } else {
+            self.usage.categories.iter().map(|usage| UsageRow {
+                label: usage.category.label(),
+                size: usage.size,
+                count: Some(usage.count),
+                directory: if usage.category == Category::Sketches {
+                    Some(home.join(&context.settings.sketch.save_path))
+                } else {
+                    None
+                },
+            }).collect()
+        };
+        if let Some(usage_list) = self.children[2].downcast_mut::<UsageList>() {
+            usage_list.update(rows, rq);
+        }
+    }
+
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let buttons = [(Event::Select(EntryId::ClearCaches), "Clear Caches"),
+                       (Event::Select(EntryId::ToggleBiggestFiles),
+                        if self.show_biggest { "Categories" } else { "Biggest Files" })];
+        let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for (index, ((event, text), w)) in buttons.into_iter().zip(widths).enumerate() {
+            let button_rect = rect![x, rect.min.y + padding, x + w - padding, rect.max.y - padding];
+            self.children.insert(5 + index, Box::new(Button::new(button_rect, event, text.to_string())) as Box<dyn View>);
+            x += w;
+        }
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn clear_caches(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let freed = clear_caches(context.library.cache_home());
+
+        self.usage = scan_library(context);
+        if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+            top_bar.update_title_label(&title(&self.usage), rq);
+        }
+        self.update_list(rq, context);
+        let notif = Notification::new(format!("Freed {}.", freed.human_size()), hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn scan_library(context: &Context) -> StorageUsage {
+    scan(&context.library.home, &context.settings.sketch.save_path,
+         &context.settings.import.allowed_kinds)
+}
+
+fn title(usage: &StorageUsage) -> String {
+    match usage.free.zip(usage.total) {
+        Some((free, total)) => format!("Storage ({} Free / {})", free.human_size(), total.human_size()),
+        None => "Storage".to_string(),
+    }
+}
+
+impl View for Storage {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(EntryId::ClearCaches) => {
+                let modal = Modal::new(ViewId::Modal, "Remove the thumbnails and the layout caches?".to_string(),
+                                       ModalKind::Confirm(Event::Select(EntryId::ConfirmClearCaches)), hub, rq, context);
+                rq.add(RenderData::new(modal.id(), *modal.rect(), UpdateMode::Gui));
+                self.children.push(Box::new(modal) as Box<dyn View>);
+                true
+            },
+            Event::Select(EntryId::ConfirmClearCaches) => {
+                self.clear_caches(hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ToggleBiggestFiles) => {
+                self.show_biggest = !self.show_biggest;
+                self.update_list(rq, context);
+                self.update_buttons(rq);
+                true
+            },
+            // Go to the directory in the library.
+            Event::SelectDirectory(ref path) => {
+                hub.send(Event::Back).ok();
+                hub.send(Event::SelectDirectory(path.clone())).ok();
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use crate::device::CURRENT_DEVICE;
+use crate::document::HumanSize;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::gesture::GestureEvent;
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12};
+use crate::context::Context;
+
+pub struct UsageRow {
+    pub label: String,
+    pub size: u64,
+    pub count: Option<usize>,
+    // The directory selected when the row is tapped.
+    pub directory: Option<PathBuf>,
+}
+
+// One row per entry: the label and the size, above a bar proportional to the size.
+pub struct UsageList {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    rows: Vec<UsageRow>,
+    row_height: i32,
+}
+
+impl UsageList {
+    pub fn new(rect: Rectangle, context: &mut Context) -> UsageList {
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        UsageList {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            rows: Vec::new(),
+            row_height: 2 * font.line_height(),
+        }
+    }
+
+    pub fn update(&mut self, rows: Vec<UsageRow>, rq: &mut RenderQueue) {
+        self.rows = rows;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for UsageList {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let index = ((center.y - self.rect.min.y) / self.row_height.max(1)) as usize;
+                if let Some(directory) = self.rows.get(index).and_then(|row| row.directory.clone()) {
+                    bus.push_back(Event::SelectDirectory(directory));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let padding = font.em() as i32;
+        let x_height = font.x_heights.0 as i32;
+        let row_height = self.row_height;
+        let max_size = self.rows.iter().map(|row| row.size).max().unwrap_or(0).max(1);
+        let width = self.rect.width() as i32 - 2 * padding;
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let y = self.rect.min.y + index as i32 * row_height;
+            if y + row_height > self.rect.max.y {
+                break;
+            }
+
+            let size = match row.count {
+                Some(count) => format!("{} ({})", row.size.human_size(), count),
+                None => row.size.human_size(),
+            };
+            let size_plan = font.plan(&size, None, None);
+            let label_plan = font.plan(&row.label, Some(width - size_plan.width - padding), None);
+            let baseline = y + (row_height + x_height) / 2 - thickness;
+            font.render(fb, BLACK, &label_plan, pt!(self.rect.min.x + padding, baseline));
+            font.render(fb, BLACK, &size_plan, pt!(self.rect.max.x - padding - size_plan.width, baseline));
+
+            let bar_width = (width as u64 * row.size / max_size) as i32;
+            let bar_y = y + row_height - 3 * thickness;
+            fb.draw_rectangle(&rect![self.rect.min.x + padding, bar_y,
+                                     self.rect.max.x - padding, bar_y + thickness],
+                              GRAY12);
+            fb.draw_rectangle(&rect![self.rect.min.x + padding, bar_y,
+                                     self.rect.min.x + padding + bar_width, bar_y + thickness],
+                              GRAY08);
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
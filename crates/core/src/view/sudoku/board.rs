@@ -0,0 +1,181 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::Rectangle;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE, BOLD_STYLE};
+use crate::gesture::GestureEvent;
+use crate::sudoku::{Grid, Puzzle, CELLS, conflicts, is_solved};
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{Id, ID_FEEDER, THICKNESS_SMALL, THICKNESS_LARGE};
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE, GRAY08, GRAY12, GRAY14};
+use crate::context::Context;
+
+// The grid of a sudoku. Tap a cell to select it: the digits are then written in the selected cell.
+pub struct Board {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    puzzle: Puzzle,
+    grid: Grid,
+    selected: Option<usize>,
+    // Whether the wrong digits are shown.
+    checked: bool,
+}
+
+impl Board {
+    pub fn new(rect: Rectangle, puzzle: Puzzle) -> Board {
+        let grid = puzzle.givens;
+        Board {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            puzzle,
+            grid,
+            selected: None,
+            checked: false,
+        }
+    }
+
+    pub fn set_puzzle(&mut self, puzzle: Puzzle, rq: &mut RenderQueue) {
+        self.grid = puzzle.givens;
+        self.puzzle = puzzle;
+        self.selected = None;
+        self.checked = false;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    // Writes the digit in the selected cell and returns whether the puzzle is solved.
+    // Zero erases the cell.
+    pub fn set_digit(&mut self, digit: u8, rq: &mut RenderQueue) -> bool {
+        if let Some(index) = self.selected.filter(|&index| self.puzzle.givens[index] == 0) {
+            self.grid[index] = digit;
+            rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        }
+        is_solved(&self.grid)
+    }
+
+    // Shows the wrong digits and returns their number.
+    pub fn check(&mut self, rq: &mut RenderQueue) -> usize {
+        self.checked = true;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+        (0..CELLS).filter(|&index| self.is_wrong(index)).count()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.grid.iter().filter(|&&digit| digit == 0).count()
+    }
+
+    fn is_wrong(&self, index: usize) -> bool {
+        self.grid[index] != 0 && self.grid[index] != self.puzzle.solution[index]
+    }
+
+    fn frame(&self) -> (Rectangle, i32) {
+        let side = self.rect.width().min(self.rect.height()) as i32 * 9 / 10;
+        let cell = side / 9;
+        let side = 9 * cell;
+        let x = self.rect.min.x + (self.rect.width() as i32 - side) / 2;
+        let y = self.rect.min.y + (self.rect.height() as i32 - side) / 2;
+        (rect![x, y, x + side, y + side], cell)
+    }
+}
+
+impl View for Board {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let (frame, cell) = self.frame();
+                if frame.includes(center) {
+                    let index = (9 * ((center.y - frame.min.y) / cell) + (center.x - frame.min.x) / cell) as usize;
+                    self.selected = if self.selected == Some(index) { None } else { Some(index) };
+                    rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let thin = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+        let thick = scale_by_dpi(THICKNESS_LARGE, dpi) as i32;
+        let (frame, cell) = self.frame();
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        let selected_digit = self.selected.map(|index| self.grid[index]).filter(|&digit| digit != 0);
+        for index in 0..CELLS {
+            let x = frame.min.x + (index % 9) as i32 * cell;
+            let y = frame.min.y + (index / 9) as i32 * cell;
+            let cell_rect = rect![x, y, x + cell, y + cell];
+            let digit = self.grid[index];
+            let wrong = self.checked && self.is_wrong(index);
+            let background = if wrong {
+                BLACK
+            } else if self.selected == Some(index) {
+                GRAY12
+            } else if digit != 0 && Some(digit) == selected_digit {
+                // The other cells holding the selected digit.
+                GRAY14
+            } else {
+                WHITE
+            };
+            fb.draw_rectangle(&cell_rect, background);
+
+            if digit == 0 {
+                continue;
+            }
+
+            let given = self.puzzle.givens[index] != 0;
+            let mut style = if given { BOLD_STYLE } else { NORMAL_STYLE };
+            // Digits about half as high as the cells.
+            style.size = (cell as u32 * 72 * 64 * 6) / (10 * dpi as u32);
+            let font = font_from_style(fonts, &style, dpi);
+            let plan = font.plan(digit.to_string(), None, None);
+            let x_height = font.x_heights.1 as i32;
+            let color = if wrong {
+                WHITE
+            } else if !given && conflicts(&self.grid, index) {
+                GRAY08
+            } else {
+                BLACK
+            };
+            font.render(fb, color, &plan, pt!(x + (cell - plan.width) / 2, y + (cell + x_height) / 2));
+        }
+
+        for i in 0..=9 {
+            let thickness = if i % 3 == 0 { thick } else { thin };
+            let offset = i * cell - thickness / 2;
+            fb.draw_rectangle(&rect![frame.min.x + offset, frame.min.y - thick / 2,
+                                     frame.min.x + offset + thickness, frame.max.y + thick / 2],
+                              BLACK);
+            fb.draw_rectangle(&rect![frame.min.x - thick / 2, frame.min.y + offset,
+                                     frame.max.x + thick / 2, frame.min.y + offset + thickness],
+                              BLACK);
+        }
+    }
+
+    fn resize(&mut self, rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, _context: &mut Context) {
+        self.rect = rect;
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
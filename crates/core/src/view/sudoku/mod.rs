@@ -0,0 +1,262 @@
+mod board;
+
+use crate::device::CURRENT_DEVICE;
+use crate::geom::{Rectangle, halves, divide};
+use crate::gesture::GestureEvent;
+use crate::sudoku::{Difficulty, generate};
+use crate::view::filler::Filler;
+use crate::view::button::Button;
+use crate::view::top_bar::TopBar;
+use crate::view::menu::{Menu, MenuKind};
+use crate::view::notification::Notification;
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, toggle_notifications_menu};
+use crate::view::common::locate_by_id;
+use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
+use crate::view::{EntryKind, EntryId, ViewId, Id, ID_FEEDER};
+use crate::view::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM};
+use crate::unit::scale_by_dpi;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::Fonts;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+use self::board::Board;
+
+pub struct Sudoku {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    difficulty: Difficulty,
+}
+
+impl Sudoku {
+    pub fn new(rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Sudoku {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+        let difficulty = Difficulty::Easy;
+
+        let top_bar = TopBar::new(rect![rect.min.x, rect.min.y,
+                                        rect.max.x, rect.min.y + small_height - small_thickness],
+                                  Event::Back,
+                                  title(difficulty),
+                                  hub, rq, context);
+        children.push(Box::new(top_bar) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                          rect.max.x, rect.min.y + small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let board = Board::new(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                     rect.max.x, rect.max.y - small_height - small_thickness],
+                               generate(difficulty, &mut context.rng));
+        children.push(Box::new(board) as Box<dyn View>);
+
+        let separator = Filler::new(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                          rect.max.x, rect.max.y - small_height + big_thickness],
+                                    BLACK);
+        children.push(Box::new(separator) as Box<dyn View>);
+
+        let bottom_bar = Filler::new(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                           rect.max.x, rect.max.y],
+                                     WHITE);
+        children.push(Box::new(bottom_bar) as Box<dyn View>);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Full));
+
+        let mut sudoku = Sudoku {
+            id,
+            rect,
+            children,
+            difficulty,
+        };
+
+        sudoku.update_buttons(&mut RenderQueue::new());
+
+        sudoku
+    }
+
+    // One button per digit, and one to erase.
+    fn update_buttons(&mut self, rq: &mut RenderQueue) {
+        let rect = *self.children[4].rect();
+        let padding = rect.height() as i32 / 8;
+        self.children.retain(|child| !child.is::<Button>());
+
+        let buttons = (1..=9).map(|digit| (digit, digit.to_string()))
+                             .chain(std::iter::once((0, "Erase".to_string())))
+                             .collect::<Vec<(u8, String)>>();
+        let widths = divide(rect.width() as i32 - padding, buttons.len() as i32);
+        let mut x = rect.min.x + padding;
+        for (index, ((digit, text), w)) in buttons.into_iter().zip(widths).enumerate() {
+            let button_rect = rect![x, rect.min.y + padding, x + w - padding, rect.max.y - padding];
+            let event = Event::Select(EntryId::SudokuDigit(digit));
+            self.children.insert(5 + index, Box::new(Button::new(button_rect, event, text)) as Box<dyn View>);
+            x += w;
+        }
+
+        rq.add(RenderData::new(self.children[4].id(), rect, UpdateMode::Gui));
+    }
+
+    fn toggle_title_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::SudokuMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+            let games = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard].iter().map(|&difficulty| {
+                EntryKind::RadioButton(difficulty.label().to_string(),
+                                       EntryId::NewSudoku(difficulty),
+                                       difficulty == self.difficulty)
+            }).collect::<Vec<EntryKind>>();
+            let entries = vec![EntryKind::SubMenu("New Game".to_string(), games),
+                               EntryKind::Command("Check".to_string(), EntryId::CheckSudoku)];
+            let menu = Menu::new(rect, ViewId::SudokuMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(menu.id(), *menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(menu) as Box<dyn View>);
+        }
+    }
+
+    fn notify(&mut self, text: String, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let notif = Notification::new(text, hub, rq, context);
+        self.children.push(Box::new(notif) as Box<dyn View>);
+    }
+
+    fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(top_bar) = self.child_mut(0).downcast_mut::<TopBar>() {
+            top_bar.reseed(rq, context);
+        }
+
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn title(difficulty: Difficulty) -> String {
+    format!("Sudoku · {}", difficulty.label())
+}
+
+impl View for Sudoku {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Select(EntryId::SudokuDigit(digit)) => {
+                let solved = self.children[2].downcast_mut::<Board>()
+                                             .is_some_and(|board| board.set_digit(digit, rq));
+                if solved {
+                    self.notify("Solved!".to_string(), hub, rq, context);
+                }
+                true
+            },
+            Event::Select(EntryId::NewSudoku(difficulty)) => {
+                self.difficulty = difficulty;
+                let puzzle = generate(difficulty, &mut context.rng);
+                if let Some(board) = self.children[2].downcast_mut::<Board>() {
+                    board.set_puzzle(puzzle, rq);
+                }
+                if let Some(top_bar) = self.children[0].downcast_mut::<TopBar>() {
+                    top_bar.update_title_label(&title(difficulty), rq);
+                }
+                true
+            },
+            Event::Select(EntryId::CheckSudoku) => {
+                let counts = self.children[2].downcast_mut::<Board>()
+                                             .map(|board| (board.check(rq), board.remaining()));
+                if let Some((wrong, remaining)) = counts {
+                    let text = match (wrong, remaining) {
+                        (0, 0) => "Solved!".to_string(),
+                        (0, _) => format!("No mistakes so far, {} cells left.", remaining),
+                        (1, _) => "1 mistake.".to_string(),
+                        _ => format!("{} mistakes.", wrong),
+                    };
+                    self.notify(text, hub, rq, context);
+                }
+                true
+            },
+            Event::ToggleNear(ViewId::TitleMenu, rect) => {
+                self.toggle_title_menu(rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::MainMenu, rect) => {
+                toggle_main_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::BatteryMenu, rect) => {
+                toggle_battery_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::ClockMenu, rect) => {
+                toggle_clock_menu(self, rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotificationsMenu, rect) => {
+                toggle_notifications_menu(self, rect, None, hub, rq, context);
+                true
+            },
+            Event::Reseed => {
+                self.reseed(rq, context);
+                true
+            },
+            Event::Gesture(GestureEvent::Cross(_)) => {
+                hub.send(Event::Back).ok();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, _fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+    }
+
+    fn resize(&mut self, rect: Rectangle, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+        let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
+        let (small_thickness, big_thickness) = halves(thickness);
+
+        self.children[0].resize(rect![rect.min.x, rect.min.y,
+                                      rect.max.x, rect.min.y + small_height - small_thickness],
+                                hub, rq, context);
+        self.children[1].resize(rect![rect.min.x, rect.min.y + small_height - small_thickness,
+                                      rect.max.x, rect.min.y + small_height + big_thickness],
+                                hub, rq, context);
+        self.children[2].resize(rect![rect.min.x, rect.min.y + small_height + big_thickness,
+                                      rect.max.x, rect.max.y - small_height - small_thickness],
+                                hub, rq, context);
+        self.children[3].resize(rect![rect.min.x, rect.max.y - small_height - small_thickness,
+                                      rect.max.x, rect.max.y - small_height + big_thickness],
+                                hub, rq, context);
+        self.children[4].resize(rect![rect.min.x, rect.max.y - small_height + big_thickness,
+                                      rect.max.x, rect.max.y],
+                                hub, rq, context);
+
+        self.rect = rect;
+        self.update_buttons(&mut RenderQueue::new());
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Full));
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,118 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, MD_SIZE};
+use crate::color::{BLACK, WHITE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId};
+use super::{SMALL_BAR_HEIGHT, THICKNESS_MEDIUM, BORDER_RADIUS_SMALL};
+use crate::gesture::GestureEvent;
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+// Shows the state of the focus timer in the bottom left corner of the screen.
+// Tapping it brings up the timer menu.
+pub struct TimerChip {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    text: String,
+}
+
+impl TimerChip {
+    pub fn new(text: String, rq: &mut RenderQueue, context: &mut Context) -> TimerChip {
+        let id = ID_FEEDER.next();
+        let rect = chip_rect(&text, context);
+        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+        TimerChip {
+            id,
+            rect,
+            children: Vec::new(),
+            text,
+        }
+    }
+
+    pub fn update(&mut self, text: String, rq: &mut RenderQueue, context: &mut Context) {
+        if self.text == text {
+            return;
+        }
+        let rect = chip_rect(&text, context);
+        if rect != self.rect {
+            rq.add(RenderData::expose(self.rect, UpdateMode::Gui));
+        }
+        self.rect = rect;
+        self.text = text;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+fn chip_rect(text: &str, context: &mut Context) -> Rectangle {
+    let dpi = CURRENT_DEVICE.dpi;
+    let (_, height) = context.display.dims;
+    let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
+    let font = font_from_style(&mut context.fonts, &MD_SIZE, dpi);
+    let padding = font.em() as i32;
+    let width = font.plan(text, None, None).width + 2 * padding;
+    let chip_height = 2 * font.x_heights.1 as i32 + padding;
+    let y = height as i32 - small_height - padding - chip_height;
+    rect![padding, y, padding + width, y + chip_height]
+}
+
+impl View for TimerChip {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::ToggleNear(ViewId::TimerMenu, self.rect));
+                true
+            },
+            Event::Gesture(GestureEvent::Swipe { start, .. }) if self.rect.includes(start) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_SMALL, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+
+        let font = font_from_style(fonts, &MD_SIZE, dpi);
+        let plan = font.plan(&self.text, None, None);
+        let x_height = font.x_heights.0 as i32;
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        let dy = (self.rect.height() as i32 - x_height) / 2;
+        font.render(fb, BLACK, &plan, pt!(self.rect.min.x + dx, self.rect.max.y - dy));
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        self.rect = chip_rect(&self.text, context);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::TimerChip)
+    }
+}
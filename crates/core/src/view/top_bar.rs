@@ -4,7 +4,9 @@ use crate::input::DeviceEvent;
 use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
 use crate::view::icon::Icon;
 use crate::view::clock::Clock;
+use crate::view::weather_label::WeatherLabel;
 use crate::view::battery::Battery;
+use crate::view::notification_badge::NotificationBadge;
 use crate::view::label::Label;
 use crate::view::packed_view::{PackedView, Position, VAlign, Pack};
 use crate::geom::{Rectangle};
@@ -23,7 +25,24 @@ const MENU : usize = 1;
 const BATTERY : usize = 2;
 const LIGHT : usize = 3;
 const CLOCK : usize = 4;
-const TITLE : usize = 5;
+const NOTIFICATIONS : usize = 5;
+// The title comes after the optional weather label.
+const WEATHER : usize = 6;
+
+// The positions of the children, in the order of the indices above, without the weather label.
+fn positions(side: i32, clock_width: i32) -> Vec<Position> {
+    vec![Position::squared_top_left(side),
+         Position::squared_top_right(side),
+         Position::squared_top_right(side),
+         Position::squared_top_right(side),
+         Position::top_right(clock_width, side),
+         Position::squared_top_right(side),
+         Position::filled_top_left()]
+}
+
+fn weather_position(side: i32, width: i32) -> Position {
+    Position::top_right(width, side)
+}
 
 impl TopBar {
     pub fn new(rect: Rectangle, root_event: Event, title: String, hub: &Hub, rq: &mut RenderQueue, context : &mut Context) -> TopBar {
@@ -41,20 +60,21 @@ impl TopBar {
         let name = if context.settings.frontlight { "frontlight" } else { "frontlight-disabled" };
         let clock_width = Clock::compute_width(context);
 
-        let views : PackedView = PackedView::new(rect)
-            .push(Box::new(Icon::new(icon_name, null_rect, root_event)),
-                  Position::squared_top_left(side), hub, rq, context)
-            .push(Box::new(Icon::new("menu", null_rect, Event::ToggleNear(ViewId::MainMenu, null_rect))),
-                  Position::squared_top_right(side), hub, rq, context)
-            .push(Box::new(Battery::new(null_rect, capacity, status)),
-                  Position::squared_top_right(side), hub, rq, context)
-            .push(Box::new(Icon::new(name, null_rect, Event::Show(ViewId::Frontlight))),
-                  Position::squared_top_right(side), hub, rq, context)
-            .push(Box::new(Clock::new(null_rect, context)),
-                  Position::top_right(clock_width as i32, side), hub, rq, context)
-            .push(Box::new(Label::new(null_rect, title, Align::Center)
-                           .event(Some(Event::ToggleNear(ViewId::TitleMenu, null_rect)))),
-                  Position::filled_top_left(), hub, rq, context);
+        let children: Vec<Box<dyn View>> = vec![
+            Box::new(Icon::new(icon_name, null_rect, root_event)),
+            Box::new(Icon::new("menu", null_rect, Event::ToggleNear(ViewId::MainMenu, null_rect))),
+            Box::new(Battery::new(null_rect, capacity, status)),
+            Box::new(Icon::new(name, null_rect, Event::Show(ViewId::Frontlight))),
+            Box::new(Clock::new(null_rect, context)),
+            Box::new(NotificationBadge::new(null_rect, context)),
+            Box::new(Label::new(null_rect, title, Align::Center)
+                     .event(Some(Event::ToggleNear(ViewId::TitleMenu, null_rect)))),
+        ];
+
+        let mut views = PackedView::new(rect);
+        for (child, position) in children.into_iter().zip(positions(side, clock_width as i32)) {
+            views = views.push(child, position, hub, rq, context);
+        }
 
         TopBar {
             id,
@@ -63,6 +83,19 @@ impl TopBar {
         }
     }
 
+    // Shows the current weather conditions left of the clock.
+    pub fn with_weather(mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> TopBar {
+        let side = self.rect.height() as i32;
+        let width = WeatherLabel::compute_width(context);
+        self.views.insert(WEATHER, Box::new(WeatherLabel::new(rect!(0, 0, 0, 0), context)),
+                          weather_position(side, width as i32), hub, rq, context);
+        self
+    }
+
+    fn title_index(&self) -> usize {
+        self.views.children().len() - 1
+    }
+
     pub fn update_root_icon(&mut self, name: &str, rq: &mut RenderQueue) {
         let icon = self.child_mut(SEARCH).downcast_mut::<Icon>().unwrap();
         if icon.name != name {
@@ -72,7 +105,8 @@ impl TopBar {
     }
 
     pub fn update_title_label(&mut self, title: &str, rq: &mut RenderQueue) {
-        let title_label = self.child_mut(TITLE).downcast_mut::<Label>().unwrap();
+        let index = self.title_index();
+        let title_label = self.child_mut(index).downcast_mut::<Label>().unwrap();
         title_label.update(title, rq);
     }
 
@@ -95,10 +129,33 @@ impl TopBar {
         }
     }
 
+    pub fn update_notification_badge(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(badge) = self.child_mut(NOTIFICATIONS).downcast_mut::<NotificationBadge>() {
+            badge.update(rq, context);
+        }
+    }
+
+    pub fn update_weather_label(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if self.child(WEATHER).is::<WeatherLabel>() {
+            let side = self.rect.height() as i32;
+            let width = WeatherLabel::compute_width(context);
+            self.views.update_position(WEATHER, weather_position(side, width as i32), hub, rq, context);
+            if let Some(weather_label) = self.child_mut(WEATHER).downcast_mut::<WeatherLabel>() {
+                weather_label.update(rq, context);
+            }
+            let index = self.title_index();
+            rq.add(RenderData::new(self.child(index).id(), *self.child(index).rect(), UpdateMode::Gui));
+        }
+    }
+
     pub fn reseed(&mut self, rq: &mut RenderQueue, context: &mut Context) {
         self.update_frontlight_icon(rq, context);
         self.update_clock_label(rq);
         self.update_battery_widget(rq, context);
+        self.update_notification_badge(rq, context);
+        if let Some(weather_label) = self.child_mut(WEATHER).downcast_mut::<WeatherLabel>() {
+            weather_label.update(rq, context);
+        }
     }
 }
 
@@ -124,7 +181,13 @@ impl View for TopBar {
         self.views.update_position(BATTERY, Position::squared_top_right(side), hub, rq, context);
         self.views.update_position(LIGHT, Position::squared_top_right(side), hub, rq, context);
         self.views.update_position(CLOCK, Position::top_right(clock_width as i32, side), hub, rq, context);
-        self.views.update_position(TITLE, Position::filled_top_left(), hub, rq, context);
+        self.views.update_position(NOTIFICATIONS, Position::squared_top_right(side), hub, rq, context);
+        if self.child(WEATHER).is::<WeatherLabel>() {
+            let weather_width = WeatherLabel::compute_width(context);
+            self.views.update_position(WEATHER, Position::top_right(weather_width as i32, side), hub, rq, context);
+        }
+        let title_index = self.title_index();
+        self.views.update_position(title_index, Position::filled_top_left(), hub, rq, context);
 
         self.views.resize(rect, hub, rq, context);
         self.rect = rect;
@@ -150,3 +213,55 @@ impl View for TopBar {
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::packed_view::compute_sizes;
+
+    // The clock is twice as wide as the bar is high, and the weather label three times.
+    fn layout(width: i32, side: i32, weather: bool) -> Vec<[i32; 4]> {
+        let mut positions = positions(side, 2 * side);
+        if weather {
+            positions.insert(WEATHER, weather_position(side, 3 * side));
+        }
+        compute_sizes(rect![0, 0, width, side], &positions).iter()
+            .map(|r| [r.min.x, r.min.y, r.max.x, r.max.y])
+            .collect()
+    }
+
+    #[test]
+    fn test_layouts() {
+        // Aura.
+        assert_eq!(layout(758, 85, false),
+                   vec![[0, 0, 85, 85], [673, 0, 758, 85], [588, 0, 673, 85], [503, 0, 588, 85],
+                        [333, 0, 503, 85], [248, 0, 333, 85], [85, 0, 248, 85]]);
+        // Clara HD.
+        assert_eq!(layout(1072, 120, false),
+                   vec![[0, 0, 120, 120], [952, 0, 1072, 120], [832, 0, 952, 120], [712, 0, 832, 120],
+                        [472, 0, 712, 120], [352, 0, 472, 120], [120, 0, 352, 120]]);
+        // Libra 2, in portrait and landscape.
+        assert_eq!(layout(1264, 120, true),
+                   vec![[0, 0, 120, 120], [1144, 0, 1264, 120], [1024, 0, 1144, 120], [904, 0, 1024, 120],
+                        [664, 0, 904, 120], [544, 0, 664, 120], [184, 0, 544, 120], [120, 0, 184, 120]]);
+        assert_eq!(layout(1680, 120, true),
+                   vec![[0, 0, 120, 120], [1560, 0, 1680, 120], [1440, 0, 1560, 120], [1320, 0, 1440, 120],
+                        [1080, 0, 1320, 120], [960, 0, 1080, 120], [600, 0, 960, 120], [120, 0, 600, 120]]);
+        // Elipsa.
+        assert_eq!(layout(1404, 91, false),
+                   vec![[0, 0, 91, 91], [1313, 0, 1404, 91], [1222, 0, 1313, 91], [1131, 0, 1222, 91],
+                        [949, 0, 1131, 91], [858, 0, 949, 91], [91, 0, 858, 91]]);
+        // Forma.
+        assert_eq!(layout(1440, 120, true),
+                   vec![[0, 0, 120, 120], [1320, 0, 1440, 120], [1200, 0, 1320, 120], [1080, 0, 1200, 120],
+                        [840, 0, 1080, 120], [720, 0, 840, 120], [360, 0, 720, 120], [120, 0, 360, 120]]);
+    }
+
+    #[test]
+    fn test_narrow_layout() {
+        // The weather label is shrunk to the remaining space, and no space is left for the title.
+        assert_eq!(layout(758, 85, true),
+                   vec![[0, 0, 85, 85], [673, 0, 758, 85], [588, 0, 673, 85], [503, 0, 588, 85],
+                        [333, 0, 503, 85], [248, 0, 333, 85], [85, 0, 248, 85]]);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM};
+use super::button::Button;
+use super::label::Label;
+use crate::gesture::GestureEvent;
+use crate::input::DeviceEvent;
+use crate::color::{BLACK, WHITE, GRAY07};
+use crate::unit::scale_by_dpi;
+use crate::context::Context;
+
+const TEXT_LINES: i32 = 8;
+
+// Shows the translation of the selected text.
+pub struct TranslationView {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    text: String,
+}
+
+impl TranslationView {
+    pub fn new(text: &str, translation: &str, source: &str, target: &str, context: &mut Context) -> TranslationView {
+        let id = ID_FEEDER.next();
+        let mut children = Vec::new();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let line_height = 4 * x_height;
+        let text_height = TEXT_LINES * font.line_height();
+
+        let panel_width = 4 * width.min(height) as i32 / 5;
+        let panel_height = 2 * line_height + text_height + 4 * padding;
+        let dx = (width as i32 - panel_width) / 2;
+        let dy = (height as i32 - panel_height) / 2;
+        let rect = rect![dx, dy, dx + panel_width, dy + panel_height];
+
+        let mut y = rect.min.y + padding;
+        let rect_title = rect![rect.min.x + padding, y, rect.max.x - padding, y + line_height];
+        children.push(Box::new(Label::new(rect_title, languages_label(source, target), Align::Center)) as Box<dyn View>);
+        y += line_height + padding;
+
+        let rect_text = rect![rect.min.x + padding, y, rect.max.x - padding, y + text_height];
+        children.push(Box::new(TranslationText::new(rect_text, text, translation)) as Box<dyn View>);
+        y += text_height + padding;
+
+        let button_width = (rect.width() as i32 - 3 * padding) / 2;
+        let rect_languages = rect![rect.min.x + padding, y,
+                                   rect.min.x + padding + button_width, y + line_height];
+        children.push(Box::new(Button::new(rect_languages,
+                                           Event::ToggleNear(ViewId::TranslationMenu, rect_languages),
+                                           "Languages".to_string())) as Box<dyn View>);
+        let rect_close = rect![rect.max.x - padding - button_width, y,
+                               rect.max.x - padding, y + line_height];
+        children.push(Box::new(Button::new(rect_close,
+                                           Event::Close(ViewId::Translation),
+                                           "Close".to_string())) as Box<dyn View>);
+
+        TranslationView {
+            id,
+            rect,
+            children,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn update(&mut self, translation: &str, source: &str, target: &str, rq: &mut RenderQueue) {
+        if let Some(label) = self.children[0].downcast_mut::<Label>() {
+            label.update(&languages_label(source, target), rq);
+        }
+        if let Some(text) = self.children[1].downcast_mut::<TranslationText>() {
+            text.translation = translation.to_string();
+            rq.add(RenderData::new(text.id, text.rect, UpdateMode::Gui));
+        }
+    }
+}
+
+fn languages_label(source: &str, target: &str) -> String {
+    format!("{} → {}", source, target)
+}
+
+impl View for TranslationView {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if !self.rect.includes(center) => {
+                bus.push_back(Event::Close(ViewId::Translation));
+                true
+            },
+            Event::Device(DeviceEvent::Finger { .. }) | Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn resize(&mut self, _rect: Rectangle, _hub: &Hub, _rq: &mut RenderQueue, context: &mut Context) {
+        let (width, height) = context.display.dims;
+        let delta = pt!((width as i32 - self.rect.width() as i32) / 2,
+                        (height as i32 - self.rect.height() as i32) / 2) - self.rect.min;
+        self.rect += delta;
+        for child in &mut self.children {
+            *child.rect_mut() += delta;
+        }
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::Translation)
+    }
+}
+
+struct TranslationText {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    text: String,
+    translation: String,
+}
+
+impl TranslationText {
+    fn new(rect: Rectangle, text: &str, translation: &str) -> TranslationText {
+        TranslationText {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            text: text.to_string(),
+            translation: translation.to_string(),
+        }
+    }
+}
+
+impl View for TranslationText {
+    fn handle_event(&mut self, _evt: &Event, _hub: &Hub, _bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let line_height = font.line_height();
+        let max_width = self.rect.width() as i32;
+
+        fb.draw_rectangle(&self.rect, WHITE);
+
+        // The original text is limited to two lines.
+        let mut lines: Vec<(String, u8)> = font.wrap(&self.text, max_width).into_iter()
+                                               .take(2).map(|line| (line, GRAY07)).collect();
+        for paragraph in self.translation.lines() {
+            lines.extend(font.wrap(paragraph, max_width).into_iter().map(|line| (line, BLACK)));
+        }
+
+        let mut y = self.rect.min.y;
+        for (line, color) in lines {
+            if y + line_height > self.rect.max.y {
+                break;
+            }
+            y += line_height;
+            let plan = font.plan(&line, Some(max_width), None);
+            font.render(fb, color, &plan, pt!(self.rect.min.x, y));
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
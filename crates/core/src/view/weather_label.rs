@@ -0,0 +1,90 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use super::{View, ViewId, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData};
+use crate::gesture::GestureEvent;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::color::{BLACK, WHITE};
+use crate::geom::{Rectangle};
+use crate::context::Context;
+
+// The current conditions, shown next to the clock.
+pub struct WeatherLabel {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    text: String,
+}
+
+fn text(context: &Context) -> String {
+    context.weather.forecast.as_ref()
+           .map(|forecast| forecast.summary())
+           .unwrap_or_else(|| "—".to_string())
+}
+
+impl WeatherLabel {
+    pub fn new(rect: Rectangle, context: &mut Context) -> WeatherLabel {
+        WeatherLabel {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            text: text(context),
+        }
+    }
+
+    pub fn update(&mut self, rq: &mut RenderQueue, context: &mut Context) {
+        self.text = text(context);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+
+    pub fn compute_width(context: &mut Context) -> u32 {
+        let text = text(context);
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, CURRENT_DEVICE.dpi);
+        let width = font.plan(&text, None, None).width + font.em() as i32;
+
+        width as u32
+    }
+}
+
+impl View for WeatherLabel {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                bus.push_back(Event::ToggleNear(ViewId::WeatherMenu, self.rect));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        let plan = font.plan(&self.text, Some(self.rect.width() as i32), None);
+        let dx = (self.rect.width() as i32 - plan.width) / 2;
+        let dy = (self.rect.height() as i32 - font.x_heights.0 as i32) / 2;
+        let pt = pt!(self.rect.min.x + dx, self.rect.max.y - dy);
+
+        fb.draw_rectangle(&self.rect, WHITE);
+        font.render(fb, BLACK, &plan, pt);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::Path;
+use chrono::{Local, DateTime, Duration};
+use serde::{Serialize, Deserialize};
+use anyhow::Error;
+use crate::helpers::{load_json, save_json, datetime_format};
+
+pub const VOCABULARY_PATH: &str = "vocabulary.json";
+
+const DEFAULT_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+// Cards answered with `Again` come back after this many minutes.
+const RELEARN_DELAY: i64 = 10;
+const MAX_DEFINITION_LENGTH: usize = 320;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Card {
+    pub word: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub language: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub book: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub context: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub definition: String,
+    #[serde(with = "datetime_format")]
+    pub added: DateTime<Local>,
+    #[serde(with = "datetime_format")]
+    pub due: DateTime<Local>,
+    // In days.
+    pub interval: u32,
+    pub ease: f32,
+    pub repetitions: u32,
+}
+
+impl Default for Card {
+    fn default() -> Self {
+        let now = Local::now();
+        Card {
+            word: String::new(),
+            language: String::new(),
+            book: String::new(),
+            context: String::new(),
+            definition: String::new(),
+            added: now,
+            due: now,
+            interval: 0,
+            ease: DEFAULT_EASE,
+            repetitions: 0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Card {
+    // A variant of the SM-2 algorithm.
+    pub fn review(&mut self, grade: Grade, now: DateTime<Local>) {
+        match grade {
+            Grade::Again => {
+                self.repetitions = 0;
+                self.interval = 0;
+                self.ease = (self.ease - 0.2).max(MIN_EASE);
+            },
+            Grade::Hard => {
+                self.interval = ((self.interval as f32 * 1.2).round() as u32).max(1);
+                self.ease = (self.ease - 0.15).max(MIN_EASE);
+                self.repetitions += 1;
+            },
+            Grade::Good => {
+                self.interval = match self.repetitions {
+                    0 => 1,
+                    1 => 3,
+                    _ => (self.interval as f32 * self.ease).round() as u32,
+                }.max(self.interval + 1);
+                self.repetitions += 1;
+            },
+            Grade::Easy => {
+                self.interval = match self.repetitions {
+                    0 => 4,
+                    _ => (self.interval as f32 * self.ease * 1.3).round() as u32,
+                }.max(self.interval + 1);
+                self.ease += 0.15;
+                self.repetitions += 1;
+            },
+        }
+
+        self.due = if self.interval == 0 {
+            now + Duration::minutes(RELEARN_DELAY)
+        } else {
+            now + Duration::days(self.interval as i64)
+        };
+    }
+
+    pub fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.due <= now
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Vocabulary {
+    pub cards: Vec<Card>,
+}
+
+impl Vocabulary {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vocabulary, Error> {
+        if !path.as_ref().exists() {
+            return Ok(Vocabulary::default());
+        }
+        load_json(path)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        save_json(self, path)
+    }
+
+    fn position(&self, word: &str, language: &str) -> Option<usize> {
+        self.cards.iter().position(|card| card.language == language &&
+                                          card.word.to_lowercase() == word.to_lowercase())
+    }
+
+    // Adds a looked up word. If the word is already known, its context is refreshed.
+    pub fn record(&mut self, word: &str, language: &str, book: &str, context: &str) {
+        if word.is_empty() {
+            return;
+        }
+
+        if let Some(index) = self.position(word, language) {
+            let card = &mut self.cards[index];
+            if !context.is_empty() {
+                card.book = book.to_string();
+                card.context = context.to_string();
+            }
+        } else {
+            self.cards.push(Card {
+                word: word.to_string(),
+                language: language.to_string(),
+                book: book.to_string(),
+                context: context.to_string(),
+                .. Default::default()
+            });
+        }
+    }
+
+    // Sets the definition of the words that don't have one yet.
+    // Returns true if a card was updated.
+    pub fn define(&mut self, word: &str, definition: &str) -> bool {
+        let definition = truncate(definition.trim(), MAX_DEFINITION_LENGTH);
+        let mut updated = false;
+        for card in self.cards.iter_mut()
+                        .filter(|card| card.definition.is_empty() &&
+                                       card.word.to_lowercase() == word.to_lowercase()) {
+            card.definition = definition.clone();
+            updated = true;
+        }
+        updated
+    }
+
+    // The indices of the cards to review, the most overdue first.
+    pub fn due(&self, now: DateTime<Local>) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.cards.len()).filter(|&i| self.cards[i].is_due(now)).collect();
+        indices.sort_by_key(|&i| self.cards[i].due);
+        indices
+    }
+
+    // Exports the cards as tab separated text importable by Anki.
+    pub fn to_anki(&self) -> String {
+        let mut text = String::from("#separator:tab\n#html:false\n#tags column:3\n");
+        for card in &self.cards {
+            let mut back = card.definition.clone();
+            if !card.context.is_empty() {
+                if !back.is_empty() {
+                    back.push_str(" — ");
+                }
+                back.push_str(&card.context);
+                if !card.book.is_empty() {
+                    back.push_str(&format!(" ({})", card.book));
+                }
+            }
+            let mut tags = "plato".to_string();
+            if !card.language.is_empty() {
+                tags.push(' ');
+                tags.push_str(&card.language.replace(' ', "_"));
+            }
+            text.push_str(&format!("{}\t{}\t{}\n", sanitize(&card.word), sanitize(&back), tags));
+        }
+        text
+    }
+}
+
+fn sanitize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn truncate(text: &str, max_length: usize) -> String {
+    let text = sanitize(text);
+    match text.char_indices().nth(max_length) {
+        Some((index, _)) => format!("{}…", text[..index].trim_end()),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.record("Ephemeral", "en", "Dune", "");
+        vocabulary.record("ephemeral", "en", "Solaris", "An ephemeral sight.");
+        vocabulary.record("ephemeral", "fr", "", "");
+        assert_eq!(vocabulary.cards.len(), 2);
+        assert_eq!(vocabulary.cards[0].book, "Solaris");
+        assert!(vocabulary.define("EPHEMERAL", "Lasting a\nvery short time."));
+        assert_eq!(vocabulary.cards[1].definition, "Lasting a very short time.");
+        assert!(!vocabulary.define("ephemeral", "Something else."));
+    }
+
+    #[test]
+    fn test_review() {
+        let now = Local::now();
+        let mut card = Card::default();
+        card.review(Grade::Good, now);
+        assert_eq!(card.interval, 1);
+        card.review(Grade::Good, now);
+        assert_eq!(card.interval, 3);
+        card.review(Grade::Good, now);
+        assert_eq!(card.interval, 8);
+        assert!(!card.is_due(now));
+        card.review(Grade::Again, now);
+        assert_eq!(card.interval, 0);
+        assert!(card.is_due(now + Duration::minutes(RELEARN_DELAY)));
+        assert!(card.ease < DEFAULT_EASE);
+    }
+
+    #[test]
+    fn test_to_anki() {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.record("tarn", "en", "The Hobbit", "A\tsmall lake.");
+        vocabulary.define("tarn", "A mountain lake.");
+        let text = vocabulary.to_anki();
+        assert_eq!(text.lines().last(),
+                   Some("tarn\tA mountain lake. — A small lake. (The Hobbit)\tplato en"));
+    }
+}
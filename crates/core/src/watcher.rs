@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+use crate::view::{Event, Hub};
+use crate::settings::SETTINGS_PATH;
+use crate::context::KEYBOARD_LAYOUTS_DIRNAME;
+
+pub const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConfigFile {
+    Settings,
+    KeyboardLayouts,
+}
+
+fn modified<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// The most recent modification time of the files of a directory,
+// also changed when a file is removed.
+fn latest_modified<P: AsRef<Path>>(dir: P) -> Option<SystemTime> {
+    WalkDir::new(dir.as_ref()).into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+            .max()
+}
+
+// Sends a *ConfigChanged* event whenever the settings or the keyboard layouts are modified.
+pub fn watch_config(hub: &Hub) {
+    let hub = hub.clone();
+    thread::spawn(move || {
+        let mut settings = modified(SETTINGS_PATH);
+        let mut layouts = latest_modified(KEYBOARD_LAYOUTS_DIRNAME);
+        loop {
+            thread::sleep(CONFIG_POLL_INTERVAL);
+            let current = modified(SETTINGS_PATH);
+            if current != settings {
+                settings = current;
+                if current.is_some() && hub.send(Event::ConfigChanged(ConfigFile::Settings)).is_err() {
+                    break;
+                }
+            }
+            let current = latest_modified(KEYBOARD_LAYOUTS_DIRNAME);
+            if current != layouts {
+                layouts = current;
+                if hub.send(Event::ConfigChanged(ConfigFile::KeyboardLayouts)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+}
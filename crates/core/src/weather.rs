@@ -0,0 +1,193 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use chrono::{Local, DateTime, NaiveDate};
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+use anyhow::{Error, format_err};
+use crate::helpers::{load_json, save_json, datetime_format};
+use crate::settings::{WeatherSettings, TemperatureUnit, network_env};
+use crate::view::{Event, Hub};
+use crate::jobs::{Jobs, RetryPolicy};
+
+pub const WEATHER_PATH: &str = ".weather.json";
+pub const WEATHER_PROGRAM: &str = "bin/weather/weather";
+// The minimum delay between two requests, even when they fail.
+const RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DayForecast {
+    pub date: NaiveDate,
+    pub code: u8,
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Forecast {
+    #[serde(with = "datetime_format")]
+    pub updated: DateTime<Local>,
+    pub temperature: f32,
+    pub code: u8,
+    pub wind_speed: f32,
+    pub days: Vec<DayForecast>,
+}
+
+// The WMO weather interpretation codes.
+pub fn condition(code: u8) -> &'static str {
+    match code {
+        0 => "Clear",
+        1 => "Mostly Clear",
+        2 => "Partly Cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51..=57 => "Drizzle",
+        61..=67 => "Rain",
+        71..=77 => "Snow",
+        80..=82 => "Showers",
+        85 | 86 => "Snow Showers",
+        95..=99 => "Thunderstorm",
+        _ => "Unknown",
+    }
+}
+
+impl Forecast {
+    pub fn summary(&self) -> String {
+        format!("{:.0}° {}", self.temperature, condition(self.code))
+    }
+
+    pub fn days_summaries(&self) -> Vec<String> {
+        self.days.iter().map(|day| {
+            format!("{} {:.0}°/{:.0}° {}", day.date.format("%a"), day.min, day.max, condition(day.code))
+        }).collect()
+    }
+
+    pub fn is_stale(&self, refresh_interval: u64) -> bool {
+        (Local::now() - self.updated).num_minutes() >= refresh_interval as i64
+    }
+}
+
+// Reads the response of the Open-Meteo forecast API.
+pub fn parse_forecast(value: &JsonValue) -> Result<Forecast, Error> {
+    let current = value.get("current")
+                       .ok_or_else(|| format_err!("missing current conditions"))?;
+    let number = |v: &JsonValue, key: &str| v.get(key).and_then(JsonValue::as_f64);
+    let temperature = number(current, "temperature_2m")
+                            .ok_or_else(|| format_err!("missing temperature"))? as f32;
+    let code = number(current, "weather_code").unwrap_or(255.0) as u8;
+    let wind_speed = number(current, "wind_speed_10m").unwrap_or_default() as f32;
+
+    let mut days = Vec::new();
+    if let Some(daily) = value.get("daily") {
+        let column = |key: &str| daily.get(key).and_then(JsonValue::as_array).cloned().unwrap_or_default();
+        let (dates, codes) = (column("time"), column("weather_code"));
+        let (maxima, minima) = (column("temperature_2m_max"), column("temperature_2m_min"));
+        for (index, date) in dates.iter().enumerate() {
+            let date = match date.as_str().and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) {
+                Some(date) => date,
+                None => continue,
+            };
+            let at = |values: &[JsonValue]| values.get(index).and_then(JsonValue::as_f64);
+            if let (Some(min), Some(max)) = (at(&minima), at(&maxima)) {
+                days.push(DayForecast {
+                    date,
+                    code: at(&codes).unwrap_or(255.0) as u8,
+                    min: min as f32,
+                    max: max as f32,
+                });
+            }
+        }
+    }
+
+    Ok(Forecast {
+        updated: Local::now(),
+        temperature,
+        code,
+        wind_speed,
+        days,
+    })
+}
+
+#[derive(Default)]
+pub struct WeatherService {
+    pub forecast: Option<Forecast>,
+    last_request: Option<Instant>,
+}
+
+impl WeatherService {
+    pub fn load<P: AsRef<Path>>(path: P) -> WeatherService {
+        let forecast = load_json::<Forecast, _>(path)
+                                .map_err(|e| eprintln!("Can't load the weather forecast: {:#}.", e))
+                                .ok();
+        WeatherService { forecast, last_request: None }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        if let Some(forecast) = self.forecast.as_ref() {
+            save_json(forecast, path)
+                     .map_err(|e| eprintln!("Can't save the weather forecast: {:#}.", e))
+                     .ok();
+        }
+    }
+
+    // Asks for a new forecast when the current one is too old.
+    pub fn update(&mut self, settings: &WeatherSettings, online: bool, jobs: &Jobs, hub: &Hub) {
+        if !settings.enabled || !online {
+            return;
+        }
+        if self.forecast.as_ref().is_some_and(|f| !f.is_stale(settings.refresh_interval)) {
+            return;
+        }
+        if self.last_request.is_some_and(|t| t.elapsed() < RETRY_DELAY) {
+            return;
+        }
+        self.request(settings, jobs, hub);
+    }
+
+    pub fn request(&mut self, settings: &WeatherSettings, jobs: &Jobs, hub: &Hub) {
+        self.last_request = Some(Instant::now());
+        let unit = match settings.temperature_unit {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        };
+        let args = vec![settings.latitude.to_string(), settings.longitude.to_string(),
+                        settings.forecast_days.to_string(), unit.to_string()];
+        let hub2 = hub.clone();
+        jobs.spawn("Get the weather forecast", RetryPolicy::NETWORK, hub, move |_| {
+            let output = network_env(&mut Command::new(WEATHER_PROGRAM)).args(&args).output()?;
+            if !output.status.success() {
+                return Err(format_err!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            let value = serde_json::from_slice::<JsonValue>(&output.stdout)?;
+            let forecast = parse_forecast(&value)?;
+            hub2.send(Event::WeatherUpdated(Box::new(forecast))).ok();
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_forecast() {
+        let value = json!({
+            "current": {"temperature_2m": 12.4, "weather_code": 61, "wind_speed_10m": 8.0},
+            "daily": {
+                "time": ["2024-06-03", "2024-06-04"],
+                "weather_code": [3, 95],
+                "temperature_2m_max": [18.2, 21.0],
+                "temperature_2m_min": [9.6, null],
+            },
+        });
+        let forecast = parse_forecast(&value).unwrap();
+        assert_eq!(forecast.summary(), "12° Rain");
+        assert_eq!(forecast.days.len(), 1);
+        assert_eq!(forecast.days_summaries(), vec!["Mon 10°/18° Overcast".to_string()]);
+        assert!(parse_forecast(&json!({"daily": {}})).is_err());
+    }
+}
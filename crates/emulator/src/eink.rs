@@ -0,0 +1,202 @@
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+use sdl2::render::WindowCanvas;
+use sdl2::pixels::{Color as SdlColor, PixelFormatEnum};
+use sdl2::rect::Rect as SdlRect;
+use plato_core::framebuffer::UpdateMode;
+use plato_core::geom::Rectangle;
+
+// The delays of each update mode, as a list of `mode=milliseconds` pairs.
+// The modes that aren't listed keep their default delay.
+const DELAYS_VAR: &str = "PLATO_EINK_DELAYS";
+// Whether the accumulated ghosting is shown.
+const GHOSTING_VAR: &str = "PLATO_EINK_GHOSTING";
+
+// Roughly the delays of a Kobo Libra 2.
+const DEFAULT_DELAYS: [(UpdateMode, &str, u64); 5] = [(UpdateMode::Gui, "gui", 260),
+                                                      (UpdateMode::Partial, "partial", 300),
+                                                      (UpdateMode::Full, "full", 680),
+                                                      (UpdateMode::Fast, "fast", 120),
+                                                      (UpdateMode::FastMono, "fast-mono", 40)];
+
+// The ghosting is tracked per square tile of this size.
+const TILE_SIZE: u32 = 16;
+const MAX_GHOST_LEVEL: u8 = 8;
+const MAX_GHOST_ALPHA: f32 = 0.5;
+
+struct Pending {
+    token: u32,
+    deadline: Instant,
+}
+
+// Simulates the latency and the ghosting of an e-ink display: an update
+// only appears once its delay has elapsed, and the updates that don't flash
+// the screen leave a residue that only a full update clears.
+pub struct Eink {
+    delays: [Duration; 5],
+    ghosting: bool,
+    pending: Vec<Pending>,
+    next_token: u32,
+    ghosts: Vec<u8>,
+    columns: u32,
+    rows: u32,
+}
+
+impl Eink {
+    pub fn from_env(width: u32, height: u32) -> Option<Eink> {
+        let ghosting = env::var(GHOSTING_VAR).is_ok_and(|v| v == "1" || v == "true");
+        let delays = env::var(DELAYS_VAR).ok();
+
+        if delays.is_none() && !ghosting {
+            return None;
+        }
+
+        let mut eink = Eink {
+            delays: [Duration::ZERO; 5],
+            ghosting,
+            pending: Vec::new(),
+            next_token: 0,
+            ghosts: Vec::new(),
+            columns: 0,
+            rows: 0,
+        };
+
+        if let Some(delays) = delays {
+            for (index, (_, _, millis)) in DEFAULT_DELAYS.iter().enumerate() {
+                eink.delays[index] = Duration::from_millis(*millis);
+            }
+            for pair in delays.split(',').map(str::trim).filter(|p| !p.is_empty() && *p != "default") {
+                let parsed = pair.split_once('=').and_then(|(name, millis)| {
+                    let index = DEFAULT_DELAYS.iter().position(|(_, n, _)| *n == name.trim())?;
+                    millis.trim().parse::<u64>().ok().map(|millis| (index, millis))
+                });
+                match parsed {
+                    Some((index, millis)) => eink.delays[index] = Duration::from_millis(millis),
+                    None => eprintln!("Can't parse the update delay {}.", pair),
+                }
+            }
+        }
+
+        eink.resize(width, height);
+        Some(eink)
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.columns = width.div_ceil(TILE_SIZE);
+        self.rows = height.div_ceil(TILE_SIZE);
+        self.ghosts = vec![0; (self.columns * self.rows) as usize];
+    }
+
+    fn delay(&self, mode: UpdateMode) -> Duration {
+        DEFAULT_DELAYS.iter().position(|(m, _, _)| *m == mode)
+                      .map(|index| self.delays[index])
+                      .unwrap_or_default()
+    }
+
+    pub fn submit(&mut self, rect: &Rectangle, mode: UpdateMode) -> u32 {
+        let token = self.next_token;
+        self.next_token = self.next_token.wrapping_add(1);
+        self.pending.push(Pending { token, deadline: Instant::now() + self.delay(mode) });
+        if self.ghosting {
+            self.accumulate(rect, mode);
+        }
+        token
+    }
+
+    fn accumulate(&mut self, rect: &Rectangle, mode: UpdateMode) {
+        let increment = match mode {
+            UpdateMode::Full => None,
+            UpdateMode::Gui | UpdateMode::Partial => Some(1),
+            UpdateMode::Fast | UpdateMode::FastMono => Some(2),
+        };
+        let (min_x, min_y) = (rect.min.x.max(0) as u32 / TILE_SIZE, rect.min.y.max(0) as u32 / TILE_SIZE);
+        let max_x = (rect.max.x.max(0) as u32).div_ceil(TILE_SIZE).min(self.columns);
+        let max_y = (rect.max.y.max(0) as u32).div_ceil(TILE_SIZE).min(self.rows);
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let ghost = &mut self.ghosts[(y * self.columns + x) as usize];
+                *ghost = increment.map_or(0, |i| ghost.saturating_add(i).min(MAX_GHOST_LEVEL));
+            }
+        }
+    }
+
+    // Blocks until the given update has appeared.
+    pub fn wait(&self, token: u32) {
+        if let Some(pending) = self.pending.iter().find(|p| p.token == token) {
+            thread::sleep(pending.deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+
+    // Forgets the updates whose delay has elapsed, and tells whether there were any.
+    pub fn take_due(&mut self) -> bool {
+        let now = Instant::now();
+        let count = self.pending.len();
+        self.pending.retain(|p| p.deadline > now);
+        self.pending.len() < count
+    }
+
+    // Presents the canvas with the ghosting drawn over it, then restores its content,
+    // which the views might read back.
+    pub fn present(&self, canvas: &mut WindowCanvas) {
+        if !self.ghosting || self.ghosts.iter().all(|g| *g == 0) {
+            canvas.present();
+            return;
+        }
+
+        let (width, height) = canvas.window().size();
+        let data = match canvas.read_pixels(None, PixelFormatEnum::RGB24) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Can't read the canvas: {}.", e);
+                canvas.present();
+                return;
+            },
+        };
+
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                let level = self.ghosts[(y * self.columns + x) as usize];
+                if level == 0 {
+                    continue;
+                }
+                let alpha = MAX_GHOST_ALPHA * level as f32 / MAX_GHOST_LEVEL as f32;
+                canvas.set_draw_color(SdlColor::RGBA(255, 0, 0, (alpha * 255.0) as u8));
+                canvas.fill_rect(SdlRect::new((x * TILE_SIZE) as i32, (y * TILE_SIZE) as i32,
+                                              TILE_SIZE, TILE_SIZE)).ok();
+            }
+        }
+
+        canvas.present();
+
+        let texture_creator = canvas.texture_creator();
+        match texture_creator.create_texture_static(PixelFormatEnum::RGB24, width, height) {
+            Ok(mut texture) => {
+                if let Err(e) = texture.update(None, &data, 3 * width as usize) {
+                    eprintln!("Can't restore the canvas: {}.", e);
+                } else if let Err(e) = canvas.copy(&texture, None, None) {
+                    eprintln!("Can't restore the canvas: {}.", e);
+                }
+            },
+            Err(e) => eprintln!("Can't restore the canvas: {}.", e),
+        };
+    }
+}
+
+// The canvas of the emulator's window, shared between the framebuffer
+// and the event loop, which presents the delayed updates.
+pub struct Screen {
+    pub canvas: WindowCanvas,
+    pub eink: Option<Eink>,
+}
+
+impl Screen {
+    // Presents the canvas if one of the delayed updates is due.
+    pub fn present_due(&mut self) {
+        if let Some(eink) = self.eink.as_mut() {
+            if eink.take_due() {
+                eink.present(&mut self.canvas);
+            }
+        }
+    }
+}
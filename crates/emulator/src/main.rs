@@ -1,29 +1,42 @@
+mod eink;
+
 use std::mem;
 use std::thread;
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::fs::File;
 use std::sync::mpsc;
 use std::collections::VecDeque;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use env_logger;
 use plato_core::anyhow::{Error, Context as ResultExt};
 use plato_core::chrono::Local;
 use sdl2::event::Event as SdlEvent;
 use sdl2::keyboard::{Scancode, Keycode, Mod};
-use sdl2::render::{WindowCanvas, BlendMode};
+use sdl2::render::BlendMode;
 use sdl2::pixels::{Color as SdlColor, PixelFormatEnum};
 use sdl2::mouse::MouseState;
 use sdl2::rect::Point as SdlPoint;
 use sdl2::rect::Rect as SdlRect;
 use plato_core::framebuffer::{Framebuffer, UpdateMode};
-use plato_core::input::{DeviceEvent, FingerStatus};
-use plato_core::document::sys_info_as_html;
+use plato_core::input::{DeviceEvent, FingerStatus, ButtonCode, ButtonStatus};
+use plato_core::input::{KEY_TAB, KEY_ENTER, KEY_SPACE, KEY_UP, KEY_DOWN, KEY_LEFT, KEY_RIGHT};
+use plato_core::document::{sys_info_as_html, is_protected};
+use plato_core::document::pse::relocate_page_caches;
+use plato_core::handoff::Handoff;
+use plato_core::notebook;
 use plato_core::view::{View, Event, ViewId, EntryId, AppCmd, EntryKind};
-use plato_core::view::{process_render_queue, wait_for_all, handle_event, RenderQueue, RenderData};
+use plato_core::view::{hub, focus, wait_for_all, handle_event, RenderQueue, RenderData, FramePacer};
 use plato_core::view::home::Home;
 use plato_core::view::reader::Reader;
 use plato_core::view::notification::Notification;
 use plato_core::view::dialog::Dialog;
+use plato_core::view::date_picker::DatePicker;
+use plato_core::view::qr_code::QrCodeView;
+use plato_core::view::statistics::{StatisticsView, compute_statistics};
+use plato_core::view::jobs::JobsView;
+use plato_core::qr::QrCode;
 use plato_core::view::frontlight::FrontlightWindow;
 use plato_core::view::menu::{Menu, MenuKind};
 use plato_core::view::intermission::Intermission;
@@ -31,22 +44,38 @@ use plato_core::view::dictionary::Dictionary;
 use plato_core::view::calculator::Calculator;
 use plato_core::view::sketch::Sketch;
 use plato_core::view::touch_events::TouchEvents;
+use plato_core::view::flashcards::Flashcards;
+use plato_core::view::battery_graph::BatteryGraph;
+use plato_core::view::storage::Storage;
+use plato_core::view::downloads::Downloads;
+use plato_core::view::portal::Portal;
+use plato_core::view::agenda::Agenda;
+use plato_core::view::notes::Notes;
+use plato_core::view::apps;
 use plato_core::view::rotation_values::RotationValues;
+use plato_core::view::pen_calibration::PenCalibration;
 use plato_core::view::common::{locate, locate_by_id, transfer_notifications, overlapping_rectangle};
 use plato_core::view::common::{toggle_input_history_menu, toggle_keyboard_layout_menu};
+use plato_core::view::common::{toggle_timer_menu, update_timer_chip, update_metrics_overlay, poll_timer, finish_download};
+use plato_core::timer::{TimerState, TimerPhase};
 use plato_core::helpers::{load_toml, save_toml};
-use plato_core::settings::{Settings, SETTINGS_PATH, IntermKind};
+use plato_core::settings::{Settings, ImportSettings, SETTINGS_PATH, IntermKind};
 use plato_core::geom::{Rectangle, Axis};
 use plato_core::gesture::{GestureEvent, gesture_events};
 use plato_core::device::CURRENT_DEVICE;
 use plato_core::battery::{Battery, FakeBattery};
-use plato_core::frontlight::{Frontlight, LightLevels};
+use plato_core::frontlight::{Frontlight, LightLevels, PwmFrontlight};
 use plato_core::lightsensor::LightSensor;
 use plato_core::library::Library;
 use plato_core::font::Fonts;
 use plato_core::context::Context;
+use plato_core::watcher::{ConfigFile, watch_config};
+use plato_core::remote::{self, RemoteCommand};
 use plato_core::pt;
 use plato_core::png;
+use plato_core::{ntp, timezone};
+use plato_core::weather::WEATHER_PATH;
+use crate::eink::{Eink, Screen};
 
 pub const APP_NAME: &str = "Plato";
 const DEFAULT_ROTATION: i8 = 1;
@@ -56,10 +85,14 @@ const CLOCK_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
 pub fn build_context(fb: Box<dyn Framebuffer>) -> Result<Context, Error> {
     let settings = load_toml::<Settings, _>(SETTINGS_PATH)?;
     let library_settings = &settings.libraries[settings.selected_library];
-    let library = Library::new(&library_settings.path, library_settings.mode)?;
+    let mut library = Library::new(&library_settings.path, library_settings.mode)?;
+    library.set_profile(&settings.profile);
+    library.relocate_caches(settings.cache_path.as_deref());
+    relocate_page_caches(settings.cache_path.as_deref());
 
     let battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let frontlight = Box::new(LightLevels::default()) as Box<dyn Frontlight>;
+    let frontlight = PwmFrontlight::wrap(frontlight, &settings.frontlight_pwm);
     let lightsensor = Box::new(0u16) as Box<dyn LightSensor>;
     let fonts = Fonts::load()?;
 
@@ -90,28 +123,56 @@ pub fn device_event(event: SdlEvent) -> Option<DeviceEvent> {
                                        status: FingerStatus::Motion,
                                        position: pt!(x, y),
                                        time: seconds(timestamp) }),
+        SdlEvent::KeyDown { timestamp, scancode: Some(scancode), repeat, .. } =>
+            navigation_code(scancode).map(|code| DeviceEvent::Button {
+                code: ButtonCode::Raw(code),
+                status: if repeat { ButtonStatus::Repeated } else { ButtonStatus::Pressed },
+                time: seconds(timestamp),
+            }),
+        SdlEvent::KeyUp { timestamp, scancode: Some(scancode), .. } =>
+            navigation_code(scancode).map(|code| DeviceEvent::Button {
+                code: ButtonCode::Raw(code),
+                status: ButtonStatus::Released,
+                time: seconds(timestamp),
+            }),
         _ => None,
     }
 }
 
-struct FBCanvas(WindowCanvas);
+fn navigation_code(scancode: Scancode) -> Option<u16> {
+    match scancode {
+        Scancode::Tab => Some(KEY_TAB),
+        Scancode::Return => Some(KEY_ENTER),
+        Scancode::Space => Some(KEY_SPACE),
+        Scancode::Up => Some(KEY_UP),
+        Scancode::Down => Some(KEY_DOWN),
+        Scancode::Left => Some(KEY_LEFT),
+        Scancode::Right => Some(KEY_RIGHT),
+        _ => None,
+    }
+}
+
+struct FBCanvas(Rc<RefCell<Screen>>);
 
 impl Framebuffer for FBCanvas {
     fn set_pixel(&mut self, x: u32, y: u32, color: u8) {
-        self.0.set_draw_color(SdlColor::RGB(color, color, color));
-        self.0.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
+        let canvas = &mut self.0.borrow_mut().canvas;
+        canvas.set_draw_color(SdlColor::RGB(color, color, color));
+        canvas.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
     }
 
     fn set_blended_pixel(&mut self, x: u32, y: u32, color: u8, alpha: f32) {
-        self.0.set_draw_color(SdlColor::RGBA(color, color, color, (alpha * 255.0) as u8));
-        self.0.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
+        let canvas = &mut self.0.borrow_mut().canvas;
+        canvas.set_draw_color(SdlColor::RGBA(color, color, color, (alpha * 255.0) as u8));
+        canvas.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
     }
 
     fn invert_region(&mut self, rect: &Rectangle) {
         let width = rect.width();
         let s_rect = Some(SdlRect::new(rect.min.x, rect.min.y,
                                        width, rect.height()));
-        if let Ok(data) = self.0.read_pixels(s_rect, PixelFormatEnum::RGB24) {
+        let pixels = self.0.borrow().canvas.read_pixels(s_rect, PixelFormatEnum::RGB24);
+        if let Ok(data) = pixels {
             for y in rect.min.y..rect.max.y {
                 let v = (y - rect.min.y) as u32;
                 for x in rect.min.x..rect.max.x {
@@ -128,7 +189,8 @@ impl Framebuffer for FBCanvas {
         let width = rect.width();
         let s_rect = Some(SdlRect::new(rect.min.x, rect.min.y,
                                        width, rect.height()));
-        if let Ok(data) = self.0.read_pixels(s_rect, PixelFormatEnum::RGB24) {
+        let pixels = self.0.borrow().canvas.read_pixels(s_rect, PixelFormatEnum::RGB24);
+        if let Ok(data) = pixels {
             for y in rect.min.y..rect.max.y {
                 let v = (y - rect.min.y) as u32;
                 for x in rect.min.x..rect.max.x {
@@ -141,12 +203,21 @@ impl Framebuffer for FBCanvas {
         }
     }
 
-    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<u32, Error> {
-        self.0.present();
-        Ok(Local::now().timestamp_subsec_millis())
+    fn update(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<Option<u32>, Error> {
+        let mut screen = self.0.borrow_mut();
+        if let Some(token) = screen.eink.as_mut().map(|eink| eink.submit(rect, mode)) {
+            screen.present_due();
+            return Ok(Some(token));
+        }
+        screen.canvas.present();
+        Ok(Some(Local::now().timestamp_subsec_millis()))
     }
 
-    fn wait(&self, _tok: u32) -> Result<i32, Error> {
+    fn wait(&self, tok: u32) -> Result<i32, Error> {
+        if let Some(eink) = self.0.borrow().eink.as_ref() {
+            eink.wait(tok);
+        }
+        self.0.borrow_mut().present_due();
         Ok(1)
     }
 
@@ -157,7 +228,8 @@ impl Framebuffer for FBCanvas {
         encoder.set_depth(png::BitDepth::Eight);
         encoder.set_color(png::ColorType::Rgb);
         let mut writer = encoder.write_header().with_context(|| format!("can't write PNG header for {}", path))?;
-        let data = self.0.read_pixels(self.0.viewport(), PixelFormatEnum::RGB24).unwrap_or_default();
+        let canvas = &self.0.borrow().canvas;
+        let data = canvas.read_pixels(canvas.viewport(), PixelFormatEnum::RGB24).unwrap_or_default();
         writer.write_image_data(&data).with_context(|| format!("can't write PNG data to {}", path))?;
         Ok(())
     }
@@ -171,7 +243,11 @@ impl Framebuffer for FBCanvas {
         if (width < height && n % 2 == 0) || (width > height && n % 2 == 1) {
             mem::swap(&mut width, &mut height);
         }
-        self.0.window_mut().set_size(width, height).ok();
+        let mut screen = self.0.borrow_mut();
+        screen.canvas.window_mut().set_size(width, height).ok();
+        if let Some(eink) = screen.eink.as_mut() {
+            eink.resize(width, height);
+        }
         Ok((width, height))
     }
 
@@ -197,11 +273,11 @@ impl Framebuffer for FBCanvas {
     }
 
     fn width(&self) -> u32 {
-        self.0.window().size().0
+        self.0.borrow().canvas.window().size().0
     }
 
     fn height(&self) -> u32 {
-        self.0.window().size().1
+        self.0.borrow().canvas.window().size().1
     }
 }
 
@@ -209,6 +285,11 @@ fn main() -> Result<(), Error> {
 
     env_logger::init();
 
+    // The environment is set before any thread is started.
+    let timezone = load_toml::<Settings, _>(SETTINGS_PATH).ok()
+                             .and_then(|settings| settings.clock.timezone);
+    timezone::export(timezone.as_deref());
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let (width, height) = CURRENT_DEVICE.dims;
@@ -221,16 +302,23 @@ fn main() -> Result<(), Error> {
     let mut fb = window.into_canvas().software().build().unwrap();
     fb.set_blend_mode(BlendMode::Blend);
 
-    let mut context = build_context(Box::new(FBCanvas(fb)))?;
+    let eink = Eink::from_env(width, height);
+    let screen = Rc::new(RefCell::new(Screen { canvas: fb, eink }));
+    let mut context = build_context(Box::new(FBCanvas(Rc::clone(&screen))))?;
 
     if context.settings.import.startup_trigger {
         context.batch_import();
     }
 
     context.load_dictionaries();
+    context.load_vocabulary();
+    context.load_downloads();
+    context.load_weather();
+    context.update_planner(false);
+    context.settings.network.export();
     context.load_keyboard_layouts();
 
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = hub::channel();
     let (ty, ry) = mpsc::channel();
     let touch_screen = gesture_events(ry);
 
@@ -270,7 +358,16 @@ fn main() -> Result<(), Error> {
     println!("The framebuffer resolution is {} by {}.", context.fb.rect().width(),
                                                         context.fb.rect().height());
 
+    if context.settings.hot_reload {
+        watch_config(&tx);
+    }
+
+    if let Some(path) = context.settings.command_socket.as_ref() {
+        remote::listen(path, &tx).map_err(|e| eprintln!("Can't listen to {}: {:#}.", path.display(), e)).ok();
+    }
+
     let mut bus = VecDeque::with_capacity(4);
+    let mut pacer = FramePacer::new(Duration::from_millis(context.settings.frame_interval));
 
     'outer: loop {
         let mut event_pump = sdl_context.event_pump().unwrap();
@@ -299,6 +396,11 @@ fn main() -> Result<(), Error> {
                                 Scancode::S => {
                                     tx.send(Event::Select(EntryId::TakeScreenshot)).ok();
                                 },
+                                _ if navigation_code(scancode).is_some() => {
+                                    if let Some(dev_evt) = device_event(sdl_evt) {
+                                        ty.send(dev_evt).ok();
+                                    }
+                                },
                                 Scancode::I | Scancode::O => {
                                     let mouse_state = MouseState::new(&event_pump);
                                     let x = mouse_state.x() as i32;
@@ -352,7 +454,10 @@ fn main() -> Result<(), Error> {
         }
 
         while let Ok(evt) = rx.recv_timeout(Duration::from_millis(20)) {
+            let event_start = Instant::now();
             match evt {
+                Event::Device(DeviceEvent::Button { .. }) |
+                Event::Gesture(GestureEvent::HoldButtonShort(..)) if focus::handle_event(view.as_ref(), &evt, &tx, &mut rq, &mut context) => (),
                 Event::Open(info) => {
                     let rotation = context.display.rotation;
                     if let Some(n) = info.reader.as_ref()
@@ -377,7 +482,11 @@ fn main() -> Result<(), Error> {
                                 context.display.dims = dims;
                             }
                         }
-                        handle_event(view.as_mut(), &Event::Invalid(path), &tx, &mut bus, &mut rq, &mut context);
+                        if is_protected(context.library.home.join(&path)) {
+                            handle_event(view.as_mut(), &Event::Protected(path), &tx, &mut bus, &mut rq, &mut context);
+                        } else {
+                            handle_event(view.as_mut(), &Event::Invalid(path), &tx, &mut bus, &mut rq, &mut context);
+                        }
                     }
                 },
                 Event::OpenHtml(ref html, ref link_uri) => {
@@ -394,18 +503,59 @@ fn main() -> Result<(), Error> {
                         AppCmd::Sketch => {
                             Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context))
                         },
+                        AppCmd::AnnotatePage { page, ref background, ref sketch } => {
+                            Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context)
+                                            .annotate(page, background, sketch.as_ref()))
+                        },
                         AppCmd::Calculator => {
                             Box::new(Calculator::new(context.fb.rect(), &tx, &mut rq, &mut context)?)
                         },
                         AppCmd::Dictionary { ref query, ref language } => {
                             Box::new(Dictionary::new(context.fb.rect(), query, language, &tx, &mut rq, &mut context))
                         },
+                        AppCmd::Flashcards => {
+                            Box::new(Flashcards::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::BatteryGraph => {
+                            Box::new(BatteryGraph::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::Storage => {
+                            Box::new(Storage::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::Downloads => {
+                            Box::new(Downloads::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::Portal => {
+                            Box::new(Portal::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::Agenda => {
+                            Box::new(Agenda::new(context.fb.rect(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::MiniApp { name } => {
+                            if let Some(next_view) = apps::build(name, context.fb.rect(), &tx, &mut rq, &mut context) {
+                                next_view
+                            } else {
+                                eprintln!("Unknown application: {}.", name);
+                                continue;
+                            }
+                        },
+                        AppCmd::Notes { ref note } => {
+                            Box::new(Notes::new(context.fb.rect(), note.as_deref(), &tx, &mut rq, &mut context))
+                        },
+                        AppCmd::HandwrittenNote { ref name } => {
+                            let dir = context.library.home.join(&context.settings.notes.path);
+                            Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context)
+                                            .note(dir, name.as_deref()))
+                        },
                         AppCmd::TouchEvents => {
                             Box::new(TouchEvents::new(context.fb.rect(), &mut rq, &mut context))
                         },
                         AppCmd::RotationValues => {
                             Box::new(RotationValues::new(context.fb.rect(), &mut rq, &mut context))
                         },
+                        AppCmd::PenCalibration => {
+                            Box::new(PenCalibration::new(context.fb.rect(), &mut rq, &mut context))
+                        },
                     };
                     transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
                     history.push(view as Box<dyn View>);
@@ -468,10 +618,64 @@ fn main() -> Result<(), Error> {
                 Event::Close(id) => {
                     if let Some(index) = locate_by_id(view.as_ref(), id) {
                         let rect = overlapping_rectangle(view.child(index));
+                        if let Some(notif) = view.child(index).downcast_ref::<Notification>() {
+                            context.notifications.release(notif.id());
+                        }
                         rq.add(RenderData::expose(rect, UpdateMode::Gui));
                         view.children_mut().remove(index);
                     }
                 },
+                Event::Select(EntryId::ShowCalendar) => {
+                    view.children_mut().retain(|child| !child.is::<Menu>());
+                    let now = Local::now().naive_local();
+                    let calendar = DatePicker::new(ViewId::Calendar, now, false, &mut context);
+                    rq.add(RenderData::new(calendar.id(), *calendar.rect(), UpdateMode::Gui));
+                    view.children_mut().push(Box::new(calendar) as Box<dyn View>);
+                },
+                Event::Select(EntryId::ShowQrCode(ref text, ref caption)) => {
+                    view.children_mut().retain(|child| !child.is::<Menu>());
+                    match QrCode::encode(text.as_bytes()) {
+                        Ok(code) => {
+                            let qr_code_view = QrCodeView::new(ViewId::QrCode, code, caption.clone(), &mut context);
+                            rq.add(RenderData::new(qr_code_view.id(), *qr_code_view.rect(), UpdateMode::Gui));
+                            view.children_mut().push(Box::new(qr_code_view) as Box<dyn View>);
+                        },
+                        Err(e) => {
+                            let notif = Notification::new(format!("Can't create QR code: {}.", e),
+                                                          &tx, &mut rq, &mut context);
+                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                        },
+                    }
+                },
+                Event::Select(EntryId::ShowJobs) => {
+                    view.children_mut().retain(|child| !child.is::<Menu>());
+                    let jobs_view = JobsView::new(&mut context);
+                    rq.add(RenderData::new(jobs_view.id(), *jobs_view.rect(), UpdateMode::Gui));
+                    view.children_mut().push(Box::new(jobs_view) as Box<dyn View>);
+                },
+                Event::Select(EntryId::ShowStatistics(ref path)) => {
+                    view.children_mut().retain(|child| !child.is::<Menu>());
+                    if let Some(info) = context.library.info_from_path(path) {
+                        if let Some(statistics_view) = StatisticsView::from_info(ViewId::Statistics, &info, &mut context) {
+                            rq.add(RenderData::new(statistics_view.id(), *statistics_view.rect(), UpdateMode::Gui));
+                            view.children_mut().push(Box::new(statistics_view) as Box<dyn View>);
+                        } else {
+                            compute_statistics(path, &tx, &context);
+                            let notif = Notification::new("Counting the words…".to_string(), &tx, &mut rq, &mut context);
+                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                        }
+                    }
+                },
+                Event::StatisticsComputed(ref path, statistics) => {
+                    context.library.update_info(path, |info| info.statistics = Some(statistics));
+                    if let Some(mut info) = context.library.info_from_path(path) {
+                        info.statistics = Some(statistics);
+                        if let Some(statistics_view) = StatisticsView::from_info(ViewId::Statistics, &info, &mut context) {
+                            rq.add(RenderData::new(statistics_view.id(), *statistics_view.rect(), UpdateMode::Gui));
+                            view.children_mut().push(Box::new(statistics_view) as Box<dyn View>);
+                        }
+                    }
+                },
                 Event::Select(EntryId::About) => {
                     let dialog = Dialog::new(ViewId::AboutDialog,
                                              None,
@@ -503,10 +707,32 @@ fn main() -> Result<(), Error> {
                 Event::Select(EntryId::SetButtonScheme(button_scheme)) => {
                     context.settings.button_scheme = button_scheme;
                 },
+                Event::Select(EntryId::SetTimezone(ref name)) => {
+                    context.settings.clock.timezone = Some(name.clone());
+                    let notif = Notification::new("The time zone will be applied after a restart.".to_string(),
+                                                  &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                },
+                Event::Select(EntryId::SyncClock) => {
+                    ntp::sync(&context.settings.clock.ntp_servers, true, &context.jobs, &tx);
+                },
+                Event::ClockSynced { offset, notify } => {
+                    if notify || offset.abs() >= 60_000 {
+                        let notif = Notification::new(format!("The clock was moved {}.", ntp::describe_offset(offset)),
+                                                      &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                    tx.send(Event::ClockTick).ok();
+                },
                 Event::Select(EntryId::ToggleInverted) => {
                     context.fb.toggle_inverted();
                     rq.add(RenderData::new(view.id(), context.fb.rect(), UpdateMode::Gui));
                 },
+                Event::Select(EntryId::ToggleMetrics) => {
+                    let enabled = !context.metrics.is_enabled();
+                    context.metrics.set_enabled(enabled);
+                    update_metrics_overlay(view.as_mut(), &mut rq, &mut context);
+                },
                 Event::Select(EntryId::TakeScreenshot) => {
                     let name = Local::now().format("screenshot-%Y%m%d_%H%M%S.png");
                     let msg = match context.fb.save(&name.to_string()) {
@@ -520,13 +746,184 @@ fn main() -> Result<(), Error> {
                     let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                     view.children_mut().push(Box::new(notif) as Box<dyn View>);
                 },
+                // The host is assumed to be online.
+                Event::QueueDownload(ref download) => {
+                    if context.downloads.push(*download.clone(), &context.library.home).is_some() {
+                        context.downloads.process(true, &tx);
+                        context.save_downloads();
+                    }
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::ClockTick => {
+                    context.weather.update(&context.settings.weather, true, &context.jobs, &tx);
+                    poll_timer(view.as_mut(), &tx, &mut rq, &mut context);
+                    context.update_planner(false);
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::Select(EntryId::StartTimer) => {
+                    context.timer.start(&context.settings.timer, Local::now());
+                    context.timer.schedule(&tx);
+                    tx.send(Event::FocusStarted).ok();
+                    update_timer_chip(view.as_mut(), &mut rq, &mut context);
+                },
+                Event::Select(EntryId::PauseTimer) => {
+                    context.timer.pause(Local::now());
+                    update_timer_chip(view.as_mut(), &mut rq, &mut context);
+                },
+                Event::Select(EntryId::ResumeTimer) => {
+                    context.timer.resume(Local::now());
+                    context.timer.schedule(&tx);
+                    if context.timer.is_focusing() {
+                        tx.send(Event::FocusStarted).ok();
+                    }
+                    update_timer_chip(view.as_mut(), &mut rq, &mut context);
+                },
+                Event::Select(EntryId::StopTimer) => {
+                    if let TimerState::Running { phase: TimerPhase::Focus, .. } |
+                           TimerState::Paused { phase: TimerPhase::Focus, .. } = context.timer.state {
+                        tx.send(Event::FocusEnded).ok();
+                    }
+                    context.timer.stop();
+                    update_timer_chip(view.as_mut(), &mut rq, &mut context);
+                },
+                Event::TimerTick => {
+                    poll_timer(view.as_mut(), &tx, &mut rq, &mut context);
+                },
+                Event::ToggleNear(ViewId::TimerMenu, rect) => {
+                    toggle_timer_menu(view.as_mut(), rect, None, &mut rq, &mut context);
+                },
+                Event::Select(EntryId::RefreshWeather) => {
+                    context.weather.request(&context.settings.weather, &context.jobs, &tx);
+                },
+                Event::WeatherUpdated(ref forecast) => {
+                    context.weather.forecast = Some(*forecast.clone());
+                    context.weather.save(WEATHER_PATH);
+                    context.update_planner(true);
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::DownloadProgress(id, done, total) => {
+                    context.downloads.update_progress(id, done, total);
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::DownloadFinished(id, ref error) => {
+                    finish_download(view.as_mut(), id, error.clone(), &tx, &mut bus, &mut rq, &mut context);
+                    context.downloads.process(true, &tx);
+                    context.save_downloads();
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::RetryDownloads => {
+                    context.downloads.process(true, &tx);
+                    context.save_downloads();
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+                },
+                Event::PortalDetected => {
+                    if !view.is::<Portal>() {
+                        let notif = Notification::with_action("Sign-in required by the network.".to_string(),
+                                                              "Open".to_string(),
+                                                              Event::Select(EntryId::Launch(AppCmd::Portal)),
+                                                              &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
+                Event::Remote(cmd) if locate_by_id(view.as_ref(), ViewId::LockScreen).is_some() => {
+                    eprintln!("Ignored {:?}: the device is locked.", cmd);
+                },
+                Event::Remote(cmd) => {
+                    match cmd {
+                        RemoteCommand::Open { path } => {
+                            if let Some(info) = context.library.info_from_path(&path) {
+                                tx.send(Event::Open(Box::new(info))).ok();
+                            } else {
+                                eprintln!("Can't open {}: not found.", path.display());
+                            }
+                        },
+                        RemoteCommand::GoTo { page } => {
+                            if view.is::<Reader>() {
+                                view.handle_event(&Event::Submit(ViewId::GoToPageInput, page), &tx, &mut bus, &mut rq, &mut context);
+                            }
+                        },
+                        RemoteCommand::SetFrontlight { intensity, warmth } => {
+                            if !context.settings.frontlight {
+                                context.set_frontlight(true);
+                                view.handle_event(&Event::ToggleFrontlight, &tx, &mut bus, &mut rq, &mut context);
+                            }
+                            if let Some(intensity) = intensity {
+                                context.frontlight.set_intensity(intensity.clamp(0.0, 100.0));
+                            }
+                            if let Some(warmth) = warmth {
+                                context.frontlight.set_warmth(warmth.clamp(0.0, 100.0));
+                            }
+                            context.settings.frontlight_levels = context.frontlight.levels();
+                        },
+                        RemoteCommand::Screenshot => {
+                            tx.send(Event::Select(EntryId::TakeScreenshot)).ok();
+                        },
+                        RemoteCommand::Import => {
+                            tx.send(Event::Import(Box::new(context.settings.import.clone()))).ok();
+                        },
+                        RemoteCommand::Sync => {
+                            save_toml(&context.settings, SETTINGS_PATH)
+                                     .map_err(|e| eprintln!("Can't save settings: {:#}.", e)).ok();
+                            context.library.flush();
+                        },
+                        RemoteCommand::Handoff { payload } => {
+                            match Handoff::parse(&payload).and_then(|handoff| context.library.accept_handoff(&handoff)) {
+                                Some(info) => {
+                                    tx.send(Event::Open(Box::new(info))).ok();
+                                },
+                                None => eprintln!("Can't accept the handoff {}.", payload),
+                            }
+                        },
+                        RemoteCommand::BundleSketches { path } if context.library.relative_path(&path).is_none() => {
+                            eprintln!("Can't bundle sketches outside of the library: {}.", path.display());
+                        },
+                        RemoteCommand::BundleSketches { path } => {
+                            let dir = context.library.home.join(&path);
+                            let tx2 = tx.clone();
+                            thread::spawn(move || {
+                                match notebook::bundle(&dir, CURRENT_DEVICE.dpi) {
+                                    Ok(..) => {
+                                        let import_settings = ImportSettings {
+                                            allowed_kinds: ["pdf".to_string()].iter().cloned().collect(),
+                                            .. Default::default()
+                                        };
+                                        tx2.send(Event::Import(Box::new(import_settings))).ok();
+                                    },
+                                    Err(e) => eprintln!("Can't bundle sketches: {:#}.", e),
+                                }
+                            });
+                        },
+                    }
+                },
+                Event::ConfigChanged(file) if context.settings.hot_reload => {
+                    match file {
+                        ConfigFile::Settings => match context.reload_settings() {
+                            Ok(true) => {
+                                println!("Reloaded the settings.");
+                                view.handle_event(&Event::Reseed, &tx, &mut bus, &mut rq, &mut context);
+                                let notif = Notification::new("Settings reloaded.".to_string(), &tx, &mut rq, &mut context);
+                                view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                            },
+                            Ok(false) => (),
+                            Err(e) => eprintln!("Can't reload settings: {:#}.", e),
+                        },
+                        ConfigFile::KeyboardLayouts => {
+                            println!("Reloaded the keyboard layouts.");
+                            context.reload_keyboard_layouts();
+                        },
+                    }
+                },
+                // The background tasks of the home need a working hub.
+                Event::Import(..) |
                 Event::Device(DeviceEvent::NetUp) |
                 Event::CheckFetcher(..) |
                 Event::FetcherAddDocument(..) |
                 Event::FetcherRemoveDocument(..) |
-                Event::FetcherSearch { .. } if !view.is::<Home>() => {
+                Event::FetcherUpdateDocument { .. } |
+                Event::FetcherSearch { .. } |
+                Event::TaskProgress(ViewId::ImportNotif | ViewId::FetcherNotif(..), ..) |
+                Event::ImportFinished(..) if !view.is::<Home>() => {
                     if let Some(home) = history.get_mut(0).filter(|view| view.is::<Home>()) {
-                        let (tx, _rx) = mpsc::channel();
                         home.handle_event(&evt, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
                     }
                 },
@@ -547,6 +944,10 @@ fn main() -> Result<(), Error> {
                 Event::Device(DeviceEvent::RotateScreen(n)) => {
                     tx.send(Event::Select(EntryId::Rotate(n))).ok();
                 },
+                Event::Select(EntryId::ClearNotifications) => {
+                    context.notifications.clear();
+                    tx.send(Event::NotificationsChanged).ok();
+                },
                 Event::Select(EntryId::Quit) => {
                     break 'outer;
                 },
@@ -554,9 +955,15 @@ fn main() -> Result<(), Error> {
                     handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
                 },
             }
+
+            context.metrics.record_event(event_start.elapsed());
         }
 
-        process_render_queue(view.as_ref(), &mut rq, &mut context, &mut updating);
+        update_metrics_overlay(view.as_mut(), &mut rq, &mut context);
+
+        // The queue is polled again on the next iteration.
+        pacer.process(view.as_ref(), &mut rq, &mut context, &mut updating);
+        screen.borrow_mut().present_due();
 
         while let Some(ce) = bus.pop_front() {
             tx.send(ce).ok();
@@ -564,7 +971,7 @@ fn main() -> Result<(), Error> {
     }
 
     if !history.is_empty() {
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = hub::channel();
         view.handle_event(&Event::Back, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
         while let Some(mut view) = history.pop() {
             view.handle_event(&Event::Back, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
@@ -576,6 +983,8 @@ fn main() -> Result<(), Error> {
     }
 
     context.library.flush();
+    context.downloads.stop_all();
+    context.save_downloads();
 
     let path = Path::new(SETTINGS_PATH);
     save_toml(&context.settings, path).context("can't save settings")?;
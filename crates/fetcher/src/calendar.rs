@@ -0,0 +1,73 @@
+mod http;
+
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::process;
+use std::path::Path;
+use reqwest::Url;
+use plato_core::anyhow::{Error, format_err};
+
+// Saves the calendar found at `url` into `path`. The previous file is kept when the download fails.
+fn fetch(client: &reqwest::blocking::Client, url: &str, path: &str) -> Result<(), Error> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let mut url = Url::parse(&url)?;
+    let username = url.username().to_string();
+    let password = url.password().map(String::from);
+    url.set_username("").ok();
+    url.set_password(None).ok();
+
+    let mut request = client.get(url);
+    if !username.is_empty() {
+        request = request.basic_auth(username, password);
+    }
+    let mut response = request.send()?.error_for_status()?;
+
+    let partial = format!("{}.part", path);
+    let mut file = File::create(&partial)?;
+    io::copy(&mut response, &mut file)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+// The errors are shown to the user: the credentials are left out.
+fn redact(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) => {
+            url.set_username("").ok();
+            url.set_password(None).ok();
+            url.to_string()
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+// The arguments are pairs of addresses and paths.
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.len().is_multiple_of(2) {
+        return Err(format_err!("missing argument: path"));
+    }
+
+    let client = http::builder()?.build()?;
+    let mut failed = false;
+
+    for pair in args.chunks(2) {
+        if let Some(parent) = Path::new(&pair[1]).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Err(e) = fetch(&client, &pair[0], &pair[1]) {
+            eprintln!("{}: {:#}", redact(&pair[0]), e);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+
+    Ok(())
+}
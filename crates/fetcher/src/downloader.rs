@@ -0,0 +1,128 @@
+mod http;
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::process;
+use reqwest::{StatusCode, Url};
+use reqwest::blocking::Response;
+use reqwest::header::{RANGE, CONTENT_LENGTH, CONTENT_RANGE};
+use plato_core::serde_json::json;
+use plato_core::anyhow::{Error, format_err};
+
+// The minimum number of bytes received between two progress reports.
+const PROGRESS_STEP: u64 = 1 << 18;
+
+// The first byte of the content of a partial response: `bytes START-END/TOTAL`.
+fn range_start(response: &Response) -> Option<u64> {
+    response.headers().get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("bytes "))
+            .and_then(|value| value.split('-').next())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+}
+
+// Downloads a file queued by the download manager, resuming from the existing content of the
+// given path when the server supports range requests. The remaining arguments are headers,
+// in the `Name: value` form.
+fn run() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let url = args.next()
+                  .ok_or_else(|| format_err!("missing argument: url"))?;
+    let path = args.next()
+                   .ok_or_else(|| format_err!("missing argument: path"))?;
+    let headers = args.map(|arg| {
+        arg.split_once(": ")
+           .map(|(name, value)| (name.to_string(), value.to_string()))
+           .ok_or_else(|| format_err!("invalid header: {}", arg))
+    }).collect::<Result<Vec<(String, String)>, Error>>()?;
+
+    let mut url = Url::parse(&url)?;
+    let username = url.username().to_string();
+    let password = url.password().map(String::from);
+    url.set_username("").ok();
+    url.set_password(None).ok();
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let offset = file.metadata()?.len();
+
+    let client = http::builder()?.build()?;
+    let send = |offset: u64| {
+        let mut request = client.get(url.clone());
+        if !username.is_empty() {
+            request = request.basic_auth(&username, password.as_ref());
+        }
+        for (name, value) in &headers {
+            request = request.header(name, value);
+        }
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        request.send()
+    };
+
+    let mut response = send(offset)?;
+
+    // The file was already complete.
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+        return Ok(());
+    }
+
+    // A range that doesn't start where the file ends can't be appended to it.
+    let mut resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+    if resumed && range_start(&response) != Some(offset) {
+        response = send(0)?;
+        resumed = false;
+    }
+
+    let mut response = response.error_for_status()?;
+
+    let mut done = if resumed {
+        offset
+    } else {
+        file.set_len(0)?;
+        0
+    };
+
+    let total = if resumed {
+        response.headers().get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit('/').next())
+                .and_then(|value| value.parse::<u64>().ok())
+    } else {
+        response.headers().get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+    };
+
+    let mut buf = vec![0; 1 << 16];
+    let mut reported = done;
+
+    loop {
+        let n = response.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        done += n as u64;
+        if done - reported >= PROGRESS_STEP {
+            println!("{}", json!({"type": "progress", "done": done, "total": total}));
+            reported = done;
+        }
+    }
+
+    println!("{}", json!({"type": "progress", "done": done, "total": total}));
+
+    if let Some(total) = total.filter(|total| done < *total) {
+        return Err(format_err!("incomplete: {} of {} bytes", done, total));
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        println!("{}", json!({"type": "error", "message": format!("{:#}", e)}));
+        process::exit(1);
+    }
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::fs;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Certificate;
+use reqwest::blocking::{Client, ClientBuilder};
+use plato_core::anyhow::{Error, Context};
+use plato_core::settings::CA_CERTIFICATES_VAR;
+
+const BEGIN_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----";
+const END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+
+// The DER encoded certificates of the PEM file given by the environment.
+pub fn extra_certificates() -> Result<Vec<Vec<u8>>, Error> {
+    let path = match env::var_os(CA_CERTIFICATES_VAR) {
+        Some(path) if !path.is_empty() => path,
+        _ => return Ok(Vec::new()),
+    };
+    let text = fs::read_to_string(&path)
+                  .with_context(|| format!("can't read {}", path.to_string_lossy()))?;
+    let mut certificates = Vec::new();
+    let mut body: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if line == BEGIN_CERTIFICATE {
+            body = Some(String::new());
+        } else if line == END_CERTIFICATE {
+            if let Some(body) = body.take() {
+                certificates.push(BASE64.decode(body)?);
+            }
+        } else if let Some(body) = body.as_mut() {
+            body.push_str(line);
+        }
+    }
+    Ok(certificates)
+}
+
+// The proxy, if any, is read from the standard environment variables.
+pub fn builder() -> Result<ClientBuilder, Error> {
+    let mut builder = Client::builder();
+    for der in extra_certificates()? {
+        builder = builder.add_root_certificate(Certificate::from_der(&der)?);
+    }
+    Ok(builder)
+}
@@ -1,3 +1,5 @@
+mod http;
+
 use std::io;
 use std::env;
 use std::fs::{self, File};
@@ -161,7 +163,7 @@ fn main() -> Result<(), Error> {
         fs::create_dir(&save_path)?;
     }
 
-    let client = Client::new();
+    let client = http::builder()?.build()?;
 
     if session.access_token.valid_until <= Utc::now() {
         update_token(&client, &mut session, &settings)?;
@@ -305,6 +307,8 @@ fn main() -> Result<(), Error> {
 
     let mut page = 1;
     let mut pages_count = 0;
+    let mut articles_count = 0;
+    let mut processed_count = 0;
     let last_downloads_count = session.downloads_count;
     let url = format!("{}/api/entries", &settings.base_url);
     let mut query = json!({
@@ -346,6 +350,7 @@ fn main() -> Result<(), Error> {
                     "message": &message,
                 });
                 println!("{}", event);
+                articles_count = total;
                 if total > 0 {
                     pages_count = entries.get("pages")
                                          .and_then(|v| v.as_u64())
@@ -406,6 +411,14 @@ fn main() -> Result<(), Error> {
 
                 session.since = updated_at.timestamp();
 
+                let event = json!({
+                    "type": "progress",
+                    "done": processed_count.min(articles_count),
+                    "total": articles_count,
+                });
+                println!("{}", event);
+                processed_count += 1;
+
                 let epub_path = save_path.join(&format!("{}.epub", id));
                 if epub_path.exists() {
                     continue;
@@ -466,6 +479,15 @@ fn main() -> Result<(), Error> {
         query["page"] = JsonValue::from(page);
     }
 
+    if articles_count > 0 {
+        let event = json!({
+            "type": "progress",
+            "done": articles_count,
+            "total": articles_count,
+        });
+        println!("{}", event);
+    }
+
     if pages_count > 0 {
         let downloads_count = session.downloads_count
                                      .saturating_sub(last_downloads_count);
@@ -0,0 +1,239 @@
+mod http;
+
+use std::io;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use plato_core::serde_json::{json, Value as JsonValue};
+use reqwest::blocking::Client;
+use plato_core::anyhow::{Error, format_err};
+
+// Relative to the library's path. Hidden directories are skipped by the importer.
+const COVERS_DIRNAME: &str = ".covers";
+
+#[derive(Default, Debug)]
+struct BookData {
+    title: String,
+    subtitle: String,
+    authors: Vec<String>,
+    publisher: String,
+    year: String,
+    language: String,
+    categories: Vec<String>,
+    cover_url: Option<String>,
+}
+
+fn strings(value: Option<&JsonValue>, key: Option<&str>) -> Vec<String> {
+    value.and_then(JsonValue::as_array)
+         .map(|items| items.iter().filter_map(|item| {
+             key.map_or(Some(item), |key| item.get(key))
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+         }).collect())
+         .unwrap_or_default()
+}
+
+fn string(value: Option<&JsonValue>) -> String {
+    value.and_then(JsonValue::as_str)
+         .map(String::from)
+         .unwrap_or_default()
+}
+
+fn year(date: &str) -> String {
+    date.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .unwrap_or_default()
+        .to_string()
+}
+
+// https://openlibrary.org/dev/docs/api/books
+fn query_open_library(client: &Client, isbn: &str) -> Result<Option<BookData>, Error> {
+    let url = format!("https://openlibrary.org/api/books?bibkeys=ISBN:{}&format=json&jscmd=data", isbn);
+    let body: JsonValue = client.get(&url).send()?.error_for_status()?.json()?;
+    let book = if let Some(book) = body.get(format!("ISBN:{}", isbn)) {
+        book
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(BookData {
+        title: string(book.get("title")),
+        subtitle: string(book.get("subtitle")),
+        authors: strings(book.get("authors"), Some("name")),
+        publisher: strings(book.get("publishers"), Some("name")).into_iter().next().unwrap_or_default(),
+        year: year(&string(book.get("publish_date"))),
+        language: String::new(),
+        categories: strings(book.get("subjects"), Some("name")).into_iter().take(3).collect(),
+        cover_url: book.get("cover")
+                       .and_then(|cover| cover.get("large").or_else(|| cover.get("medium")))
+                       .and_then(JsonValue::as_str)
+                       .map(String::from),
+    }))
+}
+
+// https://developers.google.com/books/docs/v1/using
+fn query_google_books(client: &Client, isbn: &str) -> Result<Option<BookData>, Error> {
+    let url = format!("https://www.googleapis.com/books/v1/volumes?q=isbn:{}", isbn);
+    let body: JsonValue = client.get(&url).send()?.error_for_status()?.json()?;
+    let volume = if let Some(volume) = body.get("items")
+                                           .and_then(|items| items.get(0))
+                                           .and_then(|item| item.get("volumeInfo")) {
+        volume
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(BookData {
+        title: string(volume.get("title")),
+        subtitle: string(volume.get("subtitle")),
+        authors: strings(volume.get("authors"), None),
+        publisher: string(volume.get("publisher")),
+        year: year(&string(volume.get("publishedDate"))),
+        language: string(volume.get("language")),
+        categories: strings(volume.get("categories"), None),
+        cover_url: volume.get("imageLinks")
+                         .and_then(|links| links.get("thumbnail"))
+                         .and_then(JsonValue::as_str)
+                         .map(|url| url.replacen("http://", "https://", 1)),
+    }))
+}
+
+fn download_cover(client: &Client, url: &str, library_path: &Path, isbn: &str) -> Result<PathBuf, Error> {
+    let covers_path = library_path.join(COVERS_DIRNAME);
+    if !covers_path.exists() {
+        fs::create_dir(&covers_path)?;
+    }
+    let relat = PathBuf::from(COVERS_DIRNAME).join(format!("{}.jpg", isbn));
+    let full_path = library_path.join(&relat);
+    let mut file = File::create(&full_path)?;
+    let result = client.get(url).send()
+                       .and_then(|response| response.error_for_status())
+                       .and_then(|mut response| response.copy_to(&mut file));
+    if let Err(e) = result {
+        fs::remove_file(&full_path).ok();
+        return Err(e.into());
+    }
+    Ok(relat)
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let library_path = PathBuf::from(args.next()
+                                         .ok_or_else(|| format_err!("missing argument: library path"))?);
+    let wifi = args.next()
+                   .ok_or_else(|| format_err!("missing argument: wifi status"))
+                   .and_then(|v| v.parse::<bool>().map_err(Into::into))?;
+    let online = args.next()
+                     .ok_or_else(|| format_err!("missing argument: online status"))
+                     .and_then(|v| v.parse::<bool>().map_err(Into::into))?;
+    let mut documents = Vec::new();
+    while let Some(path) = args.next() {
+        let isbn = args.next()
+                       .ok_or_else(|| format_err!("missing argument: ISBN of {}", path))?;
+        documents.push((path, isbn));
+    }
+
+    if !online {
+        if !wifi {
+            let event = json!({
+                "type": "notify",
+                "message": "Establishing a network connection.",
+            });
+            println!("{}", event);
+            let event = json!({
+                "type": "setWifi",
+                "enable": true,
+            });
+            println!("{}", event);
+        } else {
+            let event = json!({
+                "type": "notify",
+                "message": "Waiting for the network to come up.",
+            });
+            println!("{}", event);
+        }
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+    }
+
+    let client = http::builder()?.build()?;
+    let total = documents.len();
+    let mut updated_count = 0;
+
+    for (index, (path, isbn)) in documents.iter().enumerate() {
+        if total > 1 {
+            let event = json!({
+                "type": "progress",
+                "done": index,
+                "total": total,
+            });
+            println!("{}", event);
+        }
+
+        let data = query_open_library(&client, isbn)
+                        .and_then(|data| match data {
+                            Some(data) if !data.title.is_empty() => Ok(Some(data)),
+                            _ => query_google_books(&client, isbn),
+                        });
+
+        let data = match data {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                eprintln!("No metadata found for {}.", isbn);
+                continue;
+            },
+            Err(e) => {
+                eprintln!("Can't fetch the metadata of {}: {:#}.", isbn, e);
+                continue;
+            },
+        };
+
+        let cover = data.cover_url.as_ref().and_then(|url| {
+            download_cover(&client, url, &library_path, isbn)
+                .map_err(|e| eprintln!("Can't download the cover of {}: {:#}.", isbn, e))
+                .ok()
+        });
+
+        let info = json!({
+            "title": data.title,
+            "subtitle": data.subtitle,
+            "author": data.authors.join(", "),
+            "publisher": data.publisher,
+            "year": data.year,
+            "language": data.language,
+            "identifier": isbn,
+            "categories": data.categories,
+        });
+
+        let event = json!({
+            "type": "updateDocument",
+            "path": path,
+            "info": info,
+            "cover": cover,
+        });
+        println!("{}", event);
+        updated_count += 1;
+    }
+
+    if total > 1 {
+        let event = json!({
+            "type": "progress",
+            "done": total,
+            "total": total,
+        });
+        println!("{}", event);
+    }
+
+    let message = match updated_count {
+        0 => "No metadata found.".to_string(),
+        1 if total == 1 => "Metadata updated.".to_string(),
+        n => format!("Updated the metadata of {} documents.", n),
+    };
+    let event = json!({
+        "type": "notify",
+        "message": message,
+    });
+    println!("{}", event);
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+mod http;
+
+use std::env;
+use std::io::{self, BufRead};
+use reqwest::{Method, StatusCode, Url};
+use reqwest::redirect::Policy;
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use reqwest::blocking::Client;
+use plato_core::serde_json::{self, json, Value as JsonValue};
+use plato_core::anyhow::{Error, format_err};
+
+// Answers with an empty response when the Internet is reachable.
+const CHECK_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+const MAX_REDIRECTIONS: usize = 10;
+
+// A cookie is only sent back to the host, or the domain, and the path it was set for.
+struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+}
+
+impl Cookie {
+    // Parses a *Set-Cookie* header received from the given URL.
+    // Returns the cookie, and whether it's expired.
+    fn parse(header: &str, url: &Url) -> Option<(Cookie, bool)> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            secure: false,
+        };
+        let mut expired = false;
+
+        for part in parts {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    // A host can't set cookies for another domain.
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                },
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "max-age" => expired = value.parse::<i64>().is_ok_and(|age| age <= 0),
+                "secure" => cookie.secure = true,
+                _ => (),
+            }
+        }
+
+        Some((cookie, expired))
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_ok = if self.host_only { host == self.domain } else { domain_matches(&host, &self.domain) };
+        domain_ok && path_matches(url.path(), &self.path) && (!self.secure || url.scheme() == "https")
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path ||
+    path.strip_prefix(cookie_path).is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+// The directory of the URL's path.
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => url.path()[..index].to_string(),
+    }
+}
+
+// The portals are reached again and again during a session: the cookies they set are kept.
+struct Session {
+    client: Client,
+    cookies: Vec<Cookie>,
+}
+
+impl Session {
+    fn new() -> Result<Session, Error> {
+        let client = http::builder()?.redirect(Policy::none()).build()?;
+        Ok(Session { client, cookies: Vec::new() })
+    }
+
+    // Follows the redirections and returns the final status, URL and content.
+    fn fetch(&mut self, method: &str, url: &str, fields: &[(String, String)]) -> Result<(StatusCode, Url, String), Error> {
+        let mut url = Url::parse(url)?;
+        let mut method = if method.eq_ignore_ascii_case("post") { Method::POST } else { Method::GET };
+        let mut fields = fields.to_vec();
+
+        for _ in 0..MAX_REDIRECTIONS {
+            let mut request = if method == Method::GET {
+                if !fields.is_empty() {
+                    url.query_pairs_mut().extend_pairs(fields.iter());
+                }
+                self.client.get(url.clone())
+            } else {
+                self.client.post(url.clone()).form(&fields)
+            };
+
+            let cookie = self.cookies.iter()
+                             .filter(|cookie| cookie.matches(&url))
+                             .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                             .collect::<Vec<String>>().join("; ");
+            if !cookie.is_empty() {
+                request = request.header(COOKIE, cookie);
+            }
+
+            let response = request.send()?;
+
+            for value in response.headers().get_all(SET_COOKIE).iter().filter_map(|v| v.to_str().ok()) {
+                if let Some((cookie, expired)) = Cookie::parse(value, &url) {
+                    self.cookies.retain(|c| c.name != cookie.name || c.domain != cookie.domain || c.path != cookie.path);
+                    if !expired {
+                        self.cookies.push(cookie);
+                    }
+                }
+            }
+
+            let status = response.status();
+            let location = response.headers().get(LOCATION)
+                                   .and_then(|value| value.to_str().ok())
+                                   .map(String::from);
+
+            match location {
+                Some(location) if status.is_redirection() => {
+                    url = url.join(&location)?;
+                    if status != StatusCode::TEMPORARY_REDIRECT && status != StatusCode::PERMANENT_REDIRECT {
+                        method = Method::GET;
+                        fields.clear();
+                    }
+                },
+                _ => return Ok((status, url, response.text()?)),
+            }
+        }
+
+        Err(format_err!("too many redirections"))
+    }
+
+    // Prints the sign-in page, or tells that the Internet is reachable.
+    fn check(&mut self) -> Result<bool, Error> {
+        let (status, url, html) = self.fetch("get", CHECK_URL, &[])?;
+        if status == StatusCode::NO_CONTENT {
+            println!("{}", json!({"type": "online"}));
+            Ok(true)
+        } else {
+            println!("{}", json!({"type": "page", "url": url.as_str(), "html": html}));
+            Ok(false)
+        }
+    }
+
+    fn request(&mut self, event: &JsonValue) -> Result<bool, Error> {
+        let method = event.get("method").and_then(JsonValue::as_str).unwrap_or("get");
+        let url = event.get("url").and_then(JsonValue::as_str)
+                       .ok_or_else(|| format_err!("missing URL"))?;
+        let fields = event.get("fields").and_then(JsonValue::as_array)
+                          .map(|fields| fields.iter().filter_map(|field| {
+                              let name = field.get(0).and_then(JsonValue::as_str)?;
+                              let value = field.get(1).and_then(JsonValue::as_str)?;
+                              Some((name.to_string(), value.to_string()))
+                          }).collect::<Vec<(String, String)>>())
+                          .unwrap_or_default();
+        let (_, url, html) = self.fetch(method, url, &fields)?;
+        if let (StatusCode::NO_CONTENT, _, _) = self.fetch("get", CHECK_URL, &[])? {
+            println!("{}", json!({"type": "online"}));
+            return Ok(true);
+        }
+        println!("{}", json!({"type": "page", "url": url.as_str(), "html": html}));
+        Ok(false)
+    }
+}
+
+fn print_error(e: &Error) {
+    println!("{}", json!({"type": "error", "message": format!("{:#}", e)}));
+}
+
+// With the `check` argument, looks for a captive portal and exits.
+// Otherwise, reads the requests made through the portal's page from the standard input.
+fn main() -> Result<(), Error> {
+    if env::args().nth(1).as_deref() == Some("check") {
+        let client = http::builder()?.build()?;
+        match client.get(CHECK_URL).send() {
+            Ok(response) if response.status() == StatusCode::NO_CONTENT => {
+                println!("{}", json!({"type": "online"}));
+            },
+            Ok(response) => {
+                let url = response.url().to_string();
+                let html = response.text().unwrap_or_default();
+                println!("{}", json!({"type": "page", "url": url, "html": html}));
+            },
+            Err(e) => print_error(&e.into()),
+        }
+        return Ok(());
+    }
+
+    let mut session = Session::new()?;
+
+    match session.check() {
+        Ok(true) => return Ok(()),
+        Ok(false) => (),
+        Err(e) => print_error(&e),
+    }
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let event = match serde_json::from_str::<JsonValue>(&line) {
+            Ok(event) => event,
+            Err(e) => {
+                print_error(&e.into());
+                continue;
+            },
+        };
+        if event.get("type").and_then(JsonValue::as_str) != Some("request") {
+            continue;
+        }
+        match session.request(&event) {
+            Ok(true) => break,
+            Ok(false) => (),
+            Err(e) => print_error(&e),
+        }
+    }
+
+    Ok(())
+}
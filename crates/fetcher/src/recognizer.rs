@@ -0,0 +1,32 @@
+mod http;
+
+use std::env;
+use std::io::{self, Read};
+use plato_core::anyhow::{Error, format_err};
+
+// https://developer.myscript.com/docs/interactive-ink/latest/web/reference/rest-api/
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let url = args.next()
+                  .ok_or_else(|| format_err!("missing argument: url"))?;
+    let application_key = env::var("PLATO_MYSCRIPT_APPLICATION_KEY")
+                              .map_err(|_| format_err!("missing application key"))?;
+    let hmac = env::var("PLATO_MYSCRIPT_HMAC").unwrap_or_default();
+    let mut body = String::new();
+    io::stdin().read_to_string(&mut body)?;
+
+    let client = http::builder()?.build()?;
+    let jiix = client.post(&url)
+                     .header("applicationKey", application_key)
+                     .header("hmac", hmac)
+                     .header("Accept", "application/vnd.myscript.jiix,application/json")
+                     .header("Content-Type", "application/json")
+                     .body(body)
+                     .send()?
+                     .error_for_status()?
+                     .text()?;
+
+    println!("{}", jiix);
+
+    Ok(())
+}
@@ -0,0 +1,607 @@
+mod http;
+
+use std::io;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use plato_core::chrono::Local;
+use plato_core::serde::{Serialize, Deserialize};
+use plato_core::serde_json::{self, json, Value as JsonValue};
+use reqwest::blocking::{Client, RequestBuilder};
+use plato_core::anyhow::{Error, Context, format_err};
+use plato_core::helpers::{load_toml, load_json, save_json};
+
+const SETTINGS_PATH: &str = "Settings.toml";
+const SESSION_PATH: &str = ".session.json";
+// Relative to the save path. Hidden directories are skipped by the importer.
+const COVERS_DIRNAME: &str = ".covers";
+const KAVITA_PLUGIN_NAME: &str = "Plato";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "plato_core::serde")]
+#[serde(rename_all = "kebab-case")]
+enum Backend {
+    Komga,
+    Kavita,
+    CalibreWeb,
+}
+
+impl Backend {
+    fn label(&self) -> &'static str {
+        match self {
+            Backend::Komga => "komga",
+            Backend::Kavita => "kavita",
+            Backend::CalibreWeb => "calibre-web",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "plato_core::serde")]
+#[serde(default, rename_all = "kebab-case")]
+struct Settings {
+    backend: Backend,
+    base_url: String,
+    username: String,
+    password: String,
+    // Komga accepts an API key instead of the credentials, Kavita requires one.
+    api_key: String,
+    // The number of recent books downloaded.
+    limit: usize,
+    // The formats tried, in order, when a server offers several.
+    formats: Vec<String>,
+    // Mark the finished books as read on the server.
+    sync_finished: bool,
+    // Stream the comics page by page instead of downloading them.
+    stream_comics: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            backend: Backend::Komga,
+            base_url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            api_key: String::new(),
+            limit: 20,
+            formats: vec!["epub".to_string(), "cbz".to_string(), "pdf".to_string()],
+            sync_finished: true,
+            stream_comics: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "plato_core::serde")]
+#[serde(default, rename_all = "camelCase")]
+struct Session {
+    token: String,
+    last_opened: String,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            token: String::new(),
+            last_opened: "0000-00-00 00:00:00".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Book {
+    // The backend's label followed by the book's identifiers on the server.
+    identifier: String,
+    title: String,
+    author: String,
+    year: String,
+    // The candidate formats, with their download URLs.
+    downloads: Vec<(String, String)>,
+    cover_url: Option<String>,
+    // The URL template and the number of pages of the server's OPDS page streaming link.
+    stream: Option<(String, usize)>,
+}
+
+struct Server {
+    client: Client,
+    settings: Settings,
+    token: String,
+}
+
+fn string(value: Option<&JsonValue>) -> String {
+    value.and_then(JsonValue::as_str)
+         .map(String::from)
+         .unwrap_or_default()
+}
+
+fn year(date: &str) -> String {
+    date.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn notify(message: &str) {
+    let event = json!({
+        "type": "notify",
+        "message": message,
+    });
+    println!("{}", event);
+}
+
+fn is_comic(kind: &str) -> bool {
+    matches!(kind, "cbz" | "cbr")
+}
+
+fn kind_from_media_type(media_type: &str) -> &'static str {
+    match media_type {
+        "application/epub+zip" => "epub",
+        "application/pdf" => "pdf",
+        "application/x-rar-compressed" | "application/vnd.rar" => "cbr",
+        _ => "cbz",
+    }
+}
+
+// The covers are small: they're fetched at once, instead of going through the download manager.
+fn fetch_cover(request: RequestBuilder, path: &Path) -> Result<(), Error> {
+    let bytes = request.send()?.error_for_status()?.bytes()?;
+    let partial = PathBuf::from(format!("{}.part", path.display()));
+    fs::write(&partial, &bytes)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+// The comic is added to the library at once, as a file that describes its stream.
+fn add_stream(book: &Book, url: &str, count: usize, path: &Path, library_path: &Path) -> Result<(), Error> {
+    let relative_path = path.strip_prefix(library_path)?;
+    let stream = json!({
+        "url": url,
+        "count": count,
+        "title": book.title,
+        "author": book.author,
+    });
+    let text = stream.to_string();
+    fs::write(path, &text)?;
+    let event = json!({
+        "type": "addDocument",
+        "info": {
+            "title": book.title,
+            "author": book.author,
+            "year": book.year,
+            "identifier": book.identifier,
+            "added": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "file": {
+                "path": relative_path,
+                "kind": "pse",
+                "size": text.len(),
+            },
+        },
+    });
+    println!("{}", event);
+    Ok(())
+}
+
+impl Server {
+    fn new(settings: Settings, session: &Session) -> Result<Server, Error> {
+        Ok(Server {
+            client: http::builder()?.build()?,
+            token: session.token.clone(),
+            settings,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.settings.base_url.trim_end_matches('/'), path)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match self.settings.backend {
+            Backend::Komga if !self.settings.api_key.is_empty() => request.header("X-API-Key", &self.settings.api_key),
+            Backend::Kavita => request.bearer_auth(&self.token),
+            _ => request.basic_auth(&self.settings.username, Some(&self.settings.password)),
+        }
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.authorize(self.client.get(url))
+    }
+
+    // The headers that the download manager needs to send to get *url*.
+    fn download_headers(&self, url: &str) -> Result<Vec<(String, String)>, Error> {
+        let request = self.get(url).build()?;
+        request.headers().iter()
+               .map(|(name, value)| Ok((name.to_string(), value.to_str()?.to_string())))
+               .collect()
+    }
+
+    // The first of the candidate formats that the server actually offers.
+    fn available_download<'a>(&self, book: &'a Book) -> Option<&'a (String, String)> {
+        if book.downloads.len() < 2 {
+            return book.downloads.first();
+        }
+        book.downloads.iter().find(|(_, url)| {
+            self.authorize(self.client.head(url)).send()
+                .map(|response| response.status().is_success())
+                .unwrap_or(false)
+        })
+    }
+
+    // Kavita's images expect the API key rather than the token.
+    fn get_cover(&self, url: &str) -> RequestBuilder {
+        match self.settings.backend {
+            Backend::Kavita => self.client.get(url).header("X-Api-Key", &self.settings.api_key),
+            _ => self.get(url),
+        }
+    }
+
+    // Kavita trades its API key for a short-lived token.
+    fn authenticate(&mut self) -> Result<(), Error> {
+        if self.settings.backend != Backend::Kavita {
+            return Ok(());
+        }
+        let url = self.url("/api/Plugin/authenticate");
+        let body: JsonValue = self.client.post(&url)
+                                  .query(&[("apiKey", self.settings.api_key.as_str()),
+                                           ("pluginName", KAVITA_PLUGIN_NAME)])
+                                  .send()?.error_for_status()?.json()?;
+        self.token = body.get("token")
+                         .and_then(JsonValue::as_str)
+                         .map(String::from)
+                         .ok_or_else(|| format_err!("missing token"))?;
+        Ok(())
+    }
+
+    fn recent_books(&self) -> Result<Vec<Book>, Error> {
+        match self.settings.backend {
+            Backend::Komga => self.komga_books(),
+            Backend::Kavita => self.kavita_books(),
+            Backend::CalibreWeb => self.calibre_web_books(),
+        }
+    }
+
+    // https://komga.org/docs/openapi/komga-api
+    fn komga_books(&self) -> Result<Vec<Book>, Error> {
+        let limit = self.settings.limit.to_string();
+        let body: JsonValue = self.get(&self.url("/api/v1/books"))
+                                  .query(&[("page", "0"), ("size", limit.as_str()), ("sort", "createdDate,desc")])
+                                  .send()?.error_for_status()?.json()?;
+        let items = body.get("content").and_then(JsonValue::as_array).cloned().unwrap_or_default();
+        Ok(items.iter().filter_map(|item| {
+            let id = item.get("id").and_then(JsonValue::as_str)?;
+            let metadata = item.get("metadata");
+            let title = Some(string(metadata.and_then(|m| m.get("title"))))
+                            .filter(|t| !t.is_empty())
+                            .unwrap_or_else(|| string(item.get("name")));
+            let author = metadata.and_then(|m| m.get("authors"))
+                                 .and_then(JsonValue::as_array)
+                                 .map(|authors| authors.iter()
+                                                       .filter_map(|a| a.get("name").and_then(JsonValue::as_str))
+                                                       .collect::<Vec<&str>>().join(", "))
+                                 .filter(|a| !a.is_empty())
+                                 .unwrap_or_else(|| string(item.get("seriesTitle")));
+            let kind = kind_from_media_type(&string(item.pointer("/media/mediaType")));
+            let pages = item.pointer("/media/pagesCount").and_then(JsonValue::as_u64).unwrap_or_default();
+            Some(Book {
+                identifier: format!("komga:{}", id),
+                title,
+                author,
+                year: year(&string(metadata.and_then(|m| m.get("releaseDate")))),
+                downloads: vec![(kind.to_string(), self.url(&format!("/api/v1/books/{}/file", id)))],
+                cover_url: Some(self.url(&format!("/api/v1/books/{}/thumbnail", id))),
+                stream: Some((self.url(&format!("/opds/v1.2/books/{}/pages/{{pageNumber}}?zero_based=true", id)),
+                              pages as usize)).filter(|(_, count)| is_comic(kind) && *count > 0),
+            })
+        }).collect())
+    }
+
+    // https://www.kavitareader.com/docs/api
+    fn kavita_books(&self) -> Result<Vec<Book>, Error> {
+        let limit = self.settings.limit.to_string();
+        let series: JsonValue = self.authorize(self.client.post(self.url("/api/Series/recently-added")))
+                                    .query(&[("PageNumber", "1"), ("PageSize", limit.as_str()), ("libraryId", "0")])
+                                    .json(&json!({}))
+                                    .send()?.error_for_status()?.json()?;
+        let mut books = Vec::new();
+
+        for series in series.as_array().into_iter().flatten() {
+            let series_id = series.get("id").and_then(JsonValue::as_u64).unwrap_or_default();
+            let library_id = series.get("libraryId").and_then(JsonValue::as_u64).unwrap_or_default();
+            let series_name = string(series.get("name"));
+            let volumes: JsonValue = self.get(&self.url("/api/Series/volumes"))
+                                         .query(&[("seriesId", series_id)])
+                                         .send()?.error_for_status()?.json()?;
+            for volume in volumes.as_array().into_iter().flatten() {
+                let volume_id = volume.get("id").and_then(JsonValue::as_u64).unwrap_or_default();
+                for chapter in volume.get("chapters").and_then(JsonValue::as_array).into_iter().flatten() {
+                    if books.len() >= self.settings.limit {
+                        return Ok(books);
+                    }
+                    let chapter_id = chapter.get("id").and_then(JsonValue::as_u64).unwrap_or_default();
+                    let pages = chapter.get("pages").and_then(JsonValue::as_u64).unwrap_or_default();
+                    let kind = chapter.pointer("/files/0/filePath")
+                                      .and_then(JsonValue::as_str)
+                                      .and_then(|p| Path::new(p).extension())
+                                      .map(|ext| ext.to_string_lossy().to_lowercase())
+                                      .unwrap_or_else(|| "cbz".to_string());
+                    let title = Some(string(chapter.get("titleName")))
+                                    .filter(|t| !t.is_empty())
+                                    .map(|t| format!("{} — {}", series_name, t))
+                                    .unwrap_or_else(|| format!("{} {}", series_name, string(chapter.get("range"))));
+                    // Kavita's OPDS feeds carry the API key in their URLs.
+                    let stream = Some((self.url(&format!("/api/opds/{}/image?libraryId={}&seriesId={}&volumeId={}&chapterId={}&pageNumber={{pageNumber}}",
+                                                        self.settings.api_key, library_id, series_id, volume_id, chapter_id)),
+                                       pages as usize)).filter(|(_, count)| is_comic(&kind) && *count > 0);
+                    books.push(Book {
+                        identifier: format!("kavita:{}:{}:{}:{}:{}", library_id, series_id, volume_id, chapter_id, pages),
+                        title: title.trim().to_string(),
+                        author: string(chapter.pointer("/writers/0/name")),
+                        year: year(&string(chapter.get("releaseDate"))),
+                        downloads: vec![(kind, self.url(&format!("/api/Download/chapter?chapterId={}", chapter_id)))],
+                        cover_url: Some(self.url(&format!("/api/Image/chapter-cover?chapterId={}", chapter_id))),
+                        stream,
+                    });
+                }
+            }
+        }
+
+        Ok(books)
+    }
+
+    // The JSON list of the books table.
+    fn calibre_web_books(&self) -> Result<Vec<Book>, Error> {
+        let limit = self.settings.limit.to_string();
+        let body: JsonValue = self.get(&self.url("/ajax/listbooks"))
+                                  .query(&[("offset", "0"), ("limit", limit.as_str()),
+                                           ("sort", "timestamp"), ("order", "desc")])
+                                  .send()?.error_for_status()?.json()?;
+        let rows = body.get("rows").and_then(JsonValue::as_array).cloned().unwrap_or_default();
+        Ok(rows.iter().filter_map(|row| {
+            let id = row.get("id").and_then(JsonValue::as_u64)?;
+            Some(Book {
+                identifier: format!("calibre-web:{}", id),
+                title: string(row.get("title")),
+                author: string(row.get("authors")).replace('&', ","),
+                year: year(&string(row.get("pubdate"))),
+                downloads: self.settings.formats.iter()
+                               .map(|kind| (kind.clone(), self.url(&format!("/download/{}/{}", id, kind))))
+                               .collect(),
+                cover_url: Some(self.url(&format!("/cover/{}", id))),
+                stream: None,
+            })
+        }).collect())
+    }
+
+    fn mark_as_read(&self, identifier: &str) -> Result<bool, Error> {
+        let parts: Vec<&str> = identifier.split(':').collect();
+        match parts.as_slice() {
+            ["komga", id] => {
+                self.authorize(self.client.patch(self.url(&format!("/api/v1/books/{}/read-progress", id))))
+                    .json(&json!({"completed": true}))
+                    .send()?.error_for_status()?;
+                Ok(true)
+            },
+            ["kavita", library_id, series_id, volume_id, chapter_id, pages] => {
+                let progress = json!({
+                    "libraryId": library_id.parse::<u64>()?,
+                    "seriesId": series_id.parse::<u64>()?,
+                    "volumeId": volume_id.parse::<u64>()?,
+                    "chapterId": chapter_id.parse::<u64>()?,
+                    "pageNum": pages.parse::<u64>()?,
+                });
+                self.authorize(self.client.post(self.url("/api/Reader/progress")))
+                    .json(&progress)
+                    .send()?.error_for_status()?;
+                Ok(true)
+            },
+            // Calibre-Web's read status can only be changed from its web interface.
+            _ => Ok(false),
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let library_path = PathBuf::from(args.next()
+                                         .ok_or_else(|| format_err!("missing argument: library path"))?);
+    let save_path = PathBuf::from(args.next()
+                                      .ok_or_else(|| format_err!("missing argument: save path"))?);
+    let wifi = args.next()
+                   .ok_or_else(|| format_err!("missing argument: wifi status"))
+                   .and_then(|v| v.parse::<bool>().map_err(Into::into))?;
+    let online = args.next()
+                     .ok_or_else(|| format_err!("missing argument: online status"))
+                     .and_then(|v| v.parse::<bool>().map_err(Into::into))?;
+    let settings = load_toml::<Settings, _>(SETTINGS_PATH)
+                             .with_context(|| format!("can't load settings from {}", SETTINGS_PATH))?;
+    let mut session = load_json::<Session, _>(SESSION_PATH)
+                                .unwrap_or_default();
+
+    if !online {
+        if !wifi {
+            notify("Establishing a network connection.");
+            let event = json!({
+                "type": "setWifi",
+                "enable": true,
+            });
+            println!("{}", event);
+        } else {
+            notify("Waiting for the network to come up.");
+        }
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+    }
+
+    let covers_path = save_path.join(COVERS_DIRNAME);
+    fs::create_dir_all(&covers_path)?;
+
+    let mut server = Server::new(settings, &session)?;
+    server.authenticate().context("can't authenticate")?;
+    session.token = server.token.clone();
+
+    let sigterm = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&sigterm))?;
+
+    if server.settings.sync_finished {
+        let event = json!({
+            "type": "search",
+            "path": save_path,
+            "query": format!("'F 'O {}", session.last_opened),
+            "sortBy": ("opened", false),
+        });
+        println!("{}", event);
+
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+
+        let mut synced_count = 0;
+        if let Ok(event) = serde_json::from_str::<JsonValue>(&line) {
+            for entry in event.get("results").and_then(JsonValue::as_array).into_iter().flatten() {
+                if sigterm.load(Ordering::Relaxed) {
+                    break;
+                }
+                let identifier = string(entry.get("identifier"));
+                match server.mark_as_read(&identifier) {
+                    Ok(true) => synced_count += 1,
+                    Ok(false) => (),
+                    Err(e) => {
+                        // The results are sorted by opening date: the failed books
+                        // and the following ones are retried during the next session.
+                        eprintln!("Can't mark {} as read: {:#}.", identifier, e);
+                        break;
+                    },
+                }
+                if let Some(opened) = entry.pointer("/reader/opened").and_then(JsonValue::as_str) {
+                    session.last_opened = opened.to_string();
+                }
+            }
+        }
+
+        if synced_count > 0 {
+            notify(&format!("Marked {} finished book{} as read.", synced_count, if synced_count != 1 { "s" } else { "" }));
+        }
+    }
+
+    let books = server.recent_books().context("can't list the books")?;
+    let total = books.len();
+    let mut queued_count = 0;
+    let mut streamed_count = 0;
+
+    for (index, book) in books.iter().enumerate() {
+        if sigterm.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let event = json!({
+            "type": "progress",
+            "done": index,
+            "total": total,
+        });
+        println!("{}", event);
+
+        let name = book.identifier.replace(':', "-");
+        let existing = server.settings.formats.iter().map(String::as_str).chain(Some("pse"))
+                             .map(|kind| save_path.join(format!("{}.{}", name, kind)))
+                             .find(|path| path.exists());
+
+        // The cover of a downloaded book is fetched once the book is in the library.
+        if let Some(path) = existing {
+            let cover_path = covers_path.join(format!("{}.jpg", name));
+            if cover_path.exists() {
+                continue;
+            }
+            let Some(url) = book.cover_url.as_ref() else {
+                continue;
+            };
+            if let Err(e) = fetch_cover(server.get_cover(url), &cover_path) {
+                eprintln!("Can't fetch the cover of {}: {:#}.", book.identifier, e);
+                continue;
+            }
+            if let (Ok(relative_path), Ok(cover)) = (path.strip_prefix(&library_path),
+                                                     cover_path.strip_prefix(&library_path)) {
+                let event = json!({
+                    "type": "updateDocument",
+                    "path": relative_path,
+                    "info": {},
+                    "cover": cover,
+                });
+                println!("{}", event);
+            }
+            continue;
+        }
+
+        if let Some((url, count)) = book.stream.as_ref().filter(|_| server.settings.stream_comics) {
+            let path = save_path.join(format!("{}.pse", name));
+            match add_stream(book, url, *count, &path, &library_path) {
+                Ok(()) => streamed_count += 1,
+                Err(e) => eprintln!("Can't add the stream of {}: {:#}.", book.identifier, e),
+            }
+            continue;
+        }
+
+        let Some((kind, url)) = server.available_download(book) else {
+            eprintln!("Can't find a format to download {}.", book.identifier);
+            continue;
+        };
+        let path = save_path.join(format!("{}.{}", name, kind));
+        let relative_path = if let Ok(path) = path.strip_prefix(&library_path) { path } else { continue };
+        let headers = match server.download_headers(url) {
+            Ok(headers) => headers,
+            Err(e) => {
+                eprintln!("Can't prepare the download of {}: {:#}.", book.identifier, e);
+                continue;
+            },
+        };
+
+        // The download manager adds the book to the library once downloaded.
+        let event = json!({
+            "type": "download",
+            "url": url,
+            "path": relative_path,
+            "headers": headers,
+            "info": {
+                "title": book.title,
+                "author": book.author,
+                "year": book.year,
+                "identifier": book.identifier,
+                "added": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            },
+        });
+        println!("{}", event);
+        queued_count += 1;
+    }
+
+    if total > 0 {
+        let event = json!({
+            "type": "progress",
+            "done": total,
+            "total": total,
+        });
+        println!("{}", event);
+    }
+
+    let message = match (queued_count, streamed_count) {
+        (0, 0) => "No new books.".to_string(),
+        (_, 0) => format!("Queued {} book{} from {}.", queued_count,
+                          if queued_count != 1 { "s" } else { "" }, server.settings.backend.label()),
+        _ => format!("Queued {} book{} and added {} streamed comic{} from {}.",
+                     queued_count, if queued_count != 1 { "s" } else { "" },
+                     streamed_count, if streamed_count != 1 { "s" } else { "" },
+                     server.settings.backend.label()),
+    };
+    notify(&message);
+
+    // The queued downloads need the network.
+    if !wifi && queued_count == 0 {
+        let event = json!({
+            "type": "setWifi",
+            "enable": false,
+        });
+        println!("{}", event);
+    }
+
+    save_json(&session, SESSION_PATH).context("can't save session")?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+mod http;
+
+use std::env;
+use std::fs;
+use std::sync::Arc;
+use std::net::TcpStream;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use plato_core::serde_json::json;
+use plato_core::chrono::Local;
+use plato_core::anyhow::{Error, format_err};
+use rustls::{Certificate, ClientConfig, ClientConnection, OwnedTrustAnchor, RootCertStore, ServerName, StreamOwned};
+
+// The maximum length of the lines of the base64 encoded attachments.
+const LINE_LENGTH: usize = 76;
+
+fn env_var(name: &str) -> String {
+    env::var(name).unwrap_or_default()
+}
+
+fn post_webhook(kind: &str, content: &str, title: &str) -> Result<(), Error> {
+    let url = env_var("PLATO_SHARE_URL");
+    if url.is_empty() {
+        return Err(format_err!("the webhook's URL is missing"));
+    }
+    let client = http::builder()?.build()?;
+    let mut request = client.post(&url);
+    let api_key = env_var("PLATO_SHARE_API_KEY");
+    if !api_key.is_empty() {
+        request = request.bearer_auth(api_key);
+    }
+    request = match kind {
+        "quote" => request.json(&json!({
+            "text": content,
+            "title": title,
+        })),
+        _ => request.header("Content-Type", "image/png")
+                    .header("X-Title", title)
+                    .body(fs::read(content)?),
+    };
+    request.send()?.error_for_status()?;
+    Ok(())
+}
+
+struct Smtp {
+    stream: BufReader<StreamOwned<ClientConnection, TcpStream>>,
+}
+
+impl Smtp {
+    // Connects over implicit TLS, usually on port 465.
+    fn connect(server: &str) -> Result<Smtp, Error> {
+        let host = server.rsplit_once(':').map_or(server, |(host, _)| host);
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+        }));
+        for der in http::extra_certificates()? {
+            roots.add(&Certificate(der))?;
+        }
+        let config = ClientConfig::builder()
+                                  .with_safe_defaults()
+                                  .with_root_certificates(roots)
+                                  .with_no_client_auth();
+        let connection = ClientConnection::new(Arc::new(config), ServerName::try_from(host)?)?;
+        let socket = TcpStream::connect(server)?;
+        let mut smtp = Smtp { stream: BufReader::new(StreamOwned::new(connection, socket)) };
+        smtp.expect(220)?;
+        Ok(smtp)
+    }
+
+    // Reads a possibly multiline reply and checks its code.
+    fn expect(&mut self, code: u16) -> Result<(), Error> {
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                return Err(format_err!("the connection was closed"));
+            }
+            let reply: u16 = line.get(0..3).and_then(|c| c.parse().ok())
+                                 .ok_or_else(|| format_err!("invalid reply: {}", line.trim_end()))?;
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            if reply != code {
+                return Err(format_err!("unexpected reply: {}", line.trim_end()));
+            }
+            return Ok(());
+        }
+    }
+
+    fn command(&mut self, command: &str, code: u16) -> Result<(), Error> {
+        let stream = self.stream.get_mut();
+        stream.write_all(command.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        self.expect(code)
+    }
+}
+
+// Line breaks would start new headers, or new SMTP commands.
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+fn send_email(kind: &str, content: &str, title: &str) -> Result<(), Error> {
+    let server = env_var("PLATO_SHARE_SMTP_SERVER");
+    let (from, to) = (env_var("PLATO_SHARE_FROM"), env_var("PLATO_SHARE_TO"));
+    if server.is_empty() || from.is_empty() || to.is_empty() {
+        return Err(format_err!("the SMTP server, the sender or the recipient is missing"));
+    }
+    if [&server, &from, &to].iter().any(|value| value.contains(['\r', '\n'])) {
+        return Err(format_err!("the SMTP server, the sender or the recipient spans several lines"));
+    }
+
+    let boundary = format!("plato-{}-{}", Local::now().timestamp(), std::process::id());
+    let subject = if title.is_empty() { "Shared from Plato".to_string() } else { single_line(title) };
+    // Non-ASCII subjects are encoded as per RFC 2047.
+    let encoded_subject = format!("=?utf-8?B?{}?=", BASE64.encode(&subject));
+    let mut message = format!("From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n",
+                              from, to, encoded_subject, Local::now().to_rfc2822());
+    match kind {
+        "quote" => {
+            message += "Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n";
+            message += &wrap(&BASE64.encode(content));
+        },
+        _ => {
+            let name = Path::new(content).file_name()
+                           .and_then(|name| name.to_str())
+                           .map(|name| single_line(name).replace('"', ""))
+                           .unwrap_or_else(|| "page.png".to_string());
+            message += &format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary);
+            message += &format!("--{}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                                 Content-Transfer-Encoding: base64\r\n\r\n", boundary);
+            message += &wrap(&BASE64.encode(&subject));
+            message += &format!("--{}\r\nContent-Type: image/png\r\nContent-Transfer-Encoding: base64\r\n\
+                                 Content-Disposition: attachment; filename=\"{}\"\r\n\r\n", boundary, name);
+            message += &wrap(&BASE64.encode(fs::read(content)?));
+            message += &format!("--{}--\r\n", boundary);
+        },
+    }
+
+    let mut smtp = Smtp::connect(&server)?;
+    smtp.command("EHLO plato", 250)?;
+    let username = env_var("PLATO_SHARE_USERNAME");
+    if !username.is_empty() {
+        let credentials = format!("\0{}\0{}", username, env_var("PLATO_SHARE_PASSWORD"));
+        smtp.command(&format!("AUTH PLAIN {}", BASE64.encode(credentials)), 235)?;
+    }
+    smtp.command(&format!("MAIL FROM:<{}>", from), 250)?;
+    smtp.command(&format!("RCPT TO:<{}>", to), 250)?;
+    smtp.command("DATA", 354)?;
+    smtp.command(&format!("{}\r\n.", dot_stuff(message.trim_end())), 250)?;
+    smtp.command("QUIT", 221).ok();
+    Ok(())
+}
+
+// A line made of a single dot ends the data: the lines starting with a dot get a second one (RFC 5321, 4.5.2).
+fn dot_stuff(message: &str) -> String {
+    message.split("\r\n")
+           .map(|line| if line.starts_with('.') { format!(".{}", line) } else { line.to_string() })
+           .collect::<Vec<String>>()
+           .join("\r\n")
+}
+
+fn wrap(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2 * text.len() / LINE_LENGTH + 2);
+    for chunk in text.as_bytes().chunks(LINE_LENGTH) {
+        result.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        result.push_str("\r\n");
+    }
+    result
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let backend = args.next()
+                      .ok_or_else(|| format_err!("missing argument: backend"))?;
+    let kind = args.next()
+                   .ok_or_else(|| format_err!("missing argument: kind"))?;
+    let content = args.next()
+                      .ok_or_else(|| format_err!("missing argument: content"))?;
+    let title = args.next().unwrap_or_default();
+
+    match backend.as_str() {
+        "webhook" => post_webhook(&kind, &content, &title),
+        "email" => send_email(&kind, &content, &title),
+        _ => Err(format_err!("unknown backend: {}", backend)),
+    }
+}
@@ -0,0 +1,64 @@
+mod http;
+
+use std::env;
+use std::fs;
+use std::time::Duration;
+use std::collections::HashMap;
+use reqwest::Url;
+use plato_core::serde::{Serialize, Deserialize};
+use plato_core::anyhow::{Error, format_err};
+use plato_core::helpers::load_toml;
+
+const SETTINGS_PATH: &str = "Settings.toml";
+// A page that takes longer is given up, and downloaded again when it's needed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "plato_core::serde")]
+#[serde(default, rename_all = "kebab-case")]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "plato_core::serde")]
+#[serde(default, rename_all = "kebab-case")]
+struct Settings {
+    // The credentials of each server, by host name.
+    credentials: HashMap<String, Credentials>,
+}
+
+// Downloads one page of a streamed comic.
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let url = args.next()
+                  .ok_or_else(|| format_err!("missing argument: url"))?;
+    let path = args.next()
+                   .ok_or_else(|| format_err!("missing argument: path"))?;
+    let settings = load_toml::<Settings, _>(SETTINGS_PATH).unwrap_or_default();
+
+    // The credentials written in the stream's URL are ignored.
+    let mut url = Url::parse(&url)?;
+    url.set_username("").ok();
+    url.set_password(None).ok();
+    let credentials = url.host_str().and_then(|host| settings.credentials.get(host));
+
+    let client = http::builder()?.timeout(REQUEST_TIMEOUT).build()?;
+    let mut request = client.get(url);
+    if let Some(credentials) = credentials {
+        request = request.basic_auth(&credentials.username, Some(&credentials.password));
+    }
+
+    let response = request.send()?.error_for_status()?;
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+                               .and_then(|value| value.to_str().ok())
+                               .unwrap_or_default().to_string();
+    if !content_type.is_empty() && !content_type.starts_with("image/") {
+        return Err(format_err!("unexpected content type: {}", content_type));
+    }
+
+    fs::write(&path, response.bytes()?)?;
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+mod http;
+
+use std::env;
+use plato_core::serde_json::{json, Value as JsonValue};
+use reqwest::blocking::Client;
+use plato_core::anyhow::{Error, format_err};
+
+const AUTO_LANGUAGE: &str = "auto";
+
+// https://libretranslate.com/docs
+fn query_libre_translate(client: &Client, url: &str, api_key: &str, source: &str, target: &str, text: &str) -> Result<String, Error> {
+    let mut query = json!({
+        "q": text,
+        "source": source,
+        "target": target,
+        "format": "text",
+    });
+    if !api_key.is_empty() {
+        query["api_key"] = json!(api_key);
+    }
+    let url = format!("{}/translate", url.trim_end_matches('/'));
+    let body: JsonValue = client.post(&url).json(&query).send()?.error_for_status()?.json()?;
+    body.get("translatedText")
+        .and_then(JsonValue::as_str)
+        .map(String::from)
+        .ok_or_else(|| format_err!("unexpected response: {}", body))
+}
+
+// https://www.deepl.com/docs-api/translate-text
+fn query_deepl(client: &Client, url: &str, api_key: &str, source: &str, target: &str, text: &str) -> Result<String, Error> {
+    let target = target.to_uppercase();
+    let source = source.to_uppercase();
+    let mut params = vec![("text", text), ("target_lang", &target)];
+    if !source.eq_ignore_ascii_case(AUTO_LANGUAGE) {
+        params.push(("source_lang", &source));
+    }
+    let url = format!("{}/v2/translate", url.trim_end_matches('/'));
+    let body: JsonValue = client.post(&url)
+                                .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
+                                .form(&params)
+                                .send()?.error_for_status()?.json()?;
+    body.get("translations")
+        .and_then(|translations| translations.get(0))
+        .and_then(|translation| translation.get("text"))
+        .and_then(JsonValue::as_str)
+        .map(String::from)
+        .ok_or_else(|| format_err!("unexpected response: {}", body))
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let backend = args.next()
+                      .ok_or_else(|| format_err!("missing argument: backend"))?;
+    let url = args.next()
+                  .ok_or_else(|| format_err!("missing argument: url"))?;
+    let source = args.next()
+                     .ok_or_else(|| format_err!("missing argument: source language"))?;
+    let target = args.next()
+                     .ok_or_else(|| format_err!("missing argument: target language"))?;
+    let text = args.next()
+                   .ok_or_else(|| format_err!("missing argument: text"))?;
+    let api_key = env::var("PLATO_TRANSLATION_API_KEY").unwrap_or_default();
+
+    let client = http::builder()?.build()?;
+    let translation = match backend.as_str() {
+        "libretranslate" => query_libre_translate(&client, &url, &api_key, &source, &target, &text)?,
+        "deepl" => query_deepl(&client, &url, &api_key, &source, &target, &text)?,
+        _ => return Err(format_err!("unknown backend: {}", backend)),
+    };
+
+    println!("{}", translation);
+
+    Ok(())
+}
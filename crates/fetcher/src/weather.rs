@@ -0,0 +1,32 @@
+mod http;
+
+use std::env;
+use plato_core::anyhow::{Error, format_err};
+
+// https://open-meteo.com/en/docs
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let latitude = args.next()
+                       .ok_or_else(|| format_err!("missing argument: latitude"))?;
+    let longitude = args.next()
+                        .ok_or_else(|| format_err!("missing argument: longitude"))?;
+    let days = args.next()
+                   .ok_or_else(|| format_err!("missing argument: forecast days"))?;
+    let unit = args.next().unwrap_or_else(|| "celsius".to_string());
+
+    let client = http::builder()?.build()?;
+    let body = client.get(FORECAST_URL)
+                     .query(&[("latitude", latitude.as_str()),
+                              ("longitude", longitude.as_str()),
+                              ("current", "temperature_2m,weather_code,wind_speed_10m"),
+                              ("daily", "weather_code,temperature_2m_max,temperature_2m_min"),
+                              ("timezone", "auto"),
+                              ("forecast_days", days.as_str()),
+                              ("temperature_unit", unit.as_str())])
+                     .send()?.error_for_status()?.text()?;
+    println!("{}", body);
+
+    Ok(())
+}
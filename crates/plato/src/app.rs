@@ -2,43 +2,73 @@ use std::fs::File;
 use std::env;
 use std::thread;
 use std::process::Command;
-use std::path::Path;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
 use std::collections::VecDeque;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use plato_core::anyhow::{Error, Context as ResultExt, format_err};
-use plato_core::chrono::Local;
+use plato_core::chrono::{Local, Utc};
 use plato_core::framebuffer::{Framebuffer, KoboFramebuffer1, KoboFramebuffer2, UpdateMode};
-use plato_core::view::{View, Event, EntryId, EntryKind, ViewId, AppCmd, RenderData, RenderQueue, UpdateData};
-use plato_core::view::{handle_event, process_render_queue, wait_for_all};
+use plato_core::view::{View, Event, Hub, EntryId, EntryKind, ViewId, AppCmd, RenderData, RenderQueue, UpdateData};
+use plato_core::view::{hub, focus, handle_event, wait_for_all, FramePacer};
 use plato_core::view::common::{locate, locate_by_id, transfer_notifications, overlapping_rectangle};
 use plato_core::view::common::{toggle_input_history_menu, toggle_keyboard_layout_menu};
+use plato_core::view::common::{toggle_timer_menu, update_timer_chip, update_metrics_overlay, poll_timer, finish_download};
+use plato_core::timer::{TimerState, TimerPhase};
 use plato_core::view::frontlight::FrontlightWindow;
 use plato_core::view::menu::{Menu, MenuKind};
 use plato_core::view::dictionary::Dictionary as DictionaryApp;
 use plato_core::view::calculator::Calculator;
 use plato_core::view::sketch::Sketch;
 use plato_core::view::touch_events::TouchEvents;
+use plato_core::view::flashcards::Flashcards;
+use plato_core::view::battery_graph::BatteryGraph;
+use plato_core::view::storage::Storage;
+use plato_core::view::downloads::Downloads;
+use plato_core::view::portal::Portal;
+use plato_core::view::agenda::Agenda;
+use plato_core::view::notes::Notes;
+use plato_core::view::apps;
 use plato_core::view::rotation_values::RotationValues;
-use plato_core::document::sys_info_as_html;
+use plato_core::view::pen_calibration::PenCalibration;
+use plato_core::document::{sys_info_as_html, is_protected};
+use plato_core::document::pse::relocate_page_caches;
+use plato_core::handoff::Handoff;
+use plato_core::notebook;
 use plato_core::input::{DeviceEvent, PowerSource, ButtonCode, ButtonStatus, VAL_RELEASE, VAL_PRESS};
 use plato_core::input::{raw_events, device_events, usb_events, display_rotate_event, button_scheme_event};
 use plato_core::gesture::{GestureEvent, gesture_events};
 use plato_core::helpers::{load_toml, save_toml};
-use plato_core::settings::{ButtonScheme, Settings, SETTINGS_PATH, RotationLock, IntermKind};
-use plato_core::frontlight::{Frontlight, StandardFrontlight, NaturalFrontlight, PremixedFrontlight};
+use plato_core::settings::{ButtonScheme, Settings, ImportSettings, SETTINGS_PATH, RotationLock, IntermKind};
+use plato_core::frontlight::{Frontlight, StandardFrontlight, NaturalFrontlight, PremixedFrontlight, PwmFrontlight};
 use plato_core::lightsensor::{LightSensor, KoboLightSensor};
-use plato_core::battery::{Battery, KoboBattery};
+use plato_core::accelerometer::{Accelerometer, orientation_index};
+use plato_core::battery::{Battery, KoboBattery, Status};
+use plato_core::battery::history::{BatteryHistory, Record, RecordKind, BATTERY_HISTORY_PATH};
 use plato_core::geom::{Rectangle, DiagDir, Region};
 use plato_core::view::home::Home;
 use plato_core::view::reader::Reader;
 use plato_core::view::dialog::Dialog;
+use plato_core::view::date_picker::DatePicker;
+use plato_core::view::qr_code::QrCodeView;
+use plato_core::view::statistics::{StatisticsView, compute_statistics};
+use plato_core::view::jobs::JobsView;
+use plato_core::qr::QrCode;
 use plato_core::view::intermission::Intermission;
+use plato_core::view::lock_screen::LockScreen;
 use plato_core::view::notification::Notification;
 use plato_core::device::{CURRENT_DEVICE, Orientation, FrontlightKind};
 use plato_core::library::Library;
+use plato_core::snapshot;
+use plato_core::portal;
+use plato_core::ntp;
+use plato_core::weather::WEATHER_PATH;
+use plato_core::watcher::{ConfigFile, watch_config};
+use plato_core::remote::{self, RemoteCommand};
 use plato_core::font::Fonts;
 use plato_core::rtc::Rtc;
+use plato_core::session::{Session, ApplicationState, SESSION_PATH};
 use plato_core::context::Context;
 
 pub const APP_NAME: &str = "Plato";
@@ -55,12 +85,17 @@ const BUTTON_INPUTS: [&str; 4] = ["/dev/input/by-path/platform-gpio-keys-event",
 const POWER_INPUT: &str = "/dev/input/by-path/platform-bd71828-pwrkey-event";
 
 const KOBO_UPDATE_BUNDLE: &str = "/mnt/onboard/.kobo/KoboRoot.tgz";
+// The exit status of `usb-disable.sh` when the file system had to be repaired.
+const FS_REPAIRED_STATUS: i32 = 3;
 
 const CLOCK_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
 const BATTERY_REFRESH_INTERVAL: Duration = Duration::from_secs(299);
 const AUTO_SUSPEND_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
 const SUSPEND_WAIT_DELAY: Duration = Duration::from_secs(15);
 const PREPARE_SUSPEND_WAIT_DELAY: Duration = Duration::from_secs(3);
+const ACCELEROMETER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// A snapshot is taken by the first check of each day.
+const SNAPSHOT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
 
 struct Task {
     id: TaskId,
@@ -78,6 +113,9 @@ enum TaskId {
     CheckBattery,
     PrepareSuspend,
     Suspend,
+    AutoRotate,
+    RenderFrame,
+    TakeSnapshot,
 }
 
 struct HistoryItem {
@@ -107,7 +145,10 @@ fn build_context(fb: Box<dyn Framebuffer>) -> Result<Context, Error> {
     }
 
     let library_settings = &settings.libraries[settings.selected_library];
-    let library = Library::new(&library_settings.path, library_settings.mode)?;
+    let mut library = Library::new(&library_settings.path, library_settings.mode)?;
+    library.set_profile(&settings.profile);
+    library.relocate_caches(settings.cache_path.as_deref());
+    relocate_page_caches(settings.cache_path.as_deref());
 
     let fonts = Fonts::load().context("can't load fonts")?;
 
@@ -128,12 +169,13 @@ fn build_context(fb: Box<dyn Framebuffer>) -> Result<Context, Error> {
         FrontlightKind::Premixed => Box::new(PremixedFrontlight::new(levels.intensity, levels.warmth)
                                         .context("can't create premixed frontlight")?) as Box<dyn Frontlight>,
     };
+    let frontlight = PwmFrontlight::wrap(frontlight, &settings.frontlight_pwm);
 
     Ok(Context::new(fb, rtc, library, settings,
                     fonts, battery, frontlight, lightsensor))
 }
 
-fn schedule_task(id: TaskId, event: Event, delay: Duration, hub: &Sender<Event>, tasks: &mut Vec<Task>) {
+fn schedule_task(id: TaskId, event: Event, delay: Duration, hub: &Hub, tasks: &mut Vec<Task>) {
     let (ty, ry) = mpsc::channel();
     let hub2 = hub.clone();
     tasks.push(Task { id, chan: ry });
@@ -145,8 +187,17 @@ fn schedule_task(id: TaskId, event: Event, delay: Duration, hub: &Sender<Event>,
     });
 }
 
-fn resume(id: TaskId, tasks: &mut Vec<Task>, view: &mut dyn View, hub: &Sender<Event>, rq: &mut RenderQueue, context: &mut Context) {
+fn resume(id: TaskId, tasks: &mut Vec<Task>, view: &mut dyn View, hub: &Hub, rq: &mut RenderQueue,
+          context: &mut Context, asleep_since: &mut Option<SystemTime>) {
+    let mut locked = false;
     if id == TaskId::Suspend {
+        // The monotonic clock doesn't advance while the device sleeps.
+        let asleep = asleep_since.take()
+                                 .and_then(|time| time.elapsed().ok())
+                                 .unwrap_or_default();
+        locked = !context.settings.lock.pin.is_empty() &&
+                 asleep >= Duration::from_secs(60 * context.settings.lock.timeout as u64) &&
+                 locate_by_id(view, ViewId::LockScreen).is_none();
         tasks.retain(|task| task.id != TaskId::Suspend);
         if context.settings.frontlight {
             let levels = context.settings.frontlight_levels;
@@ -164,15 +215,58 @@ fn resume(id: TaskId, tasks: &mut Vec<Task>, view: &mut dyn View, hub: &Sender<E
         if let Some(index) = locate::<Intermission>(view) {
             let rect = *view.child(index).rect();
             view.children_mut().remove(index);
-            rq.add(RenderData::expose(rect, UpdateMode::Full));
+            if locked {
+                let lock_screen = LockScreen::new(context.fb.rect(), context);
+                rq.add(RenderData::new(lock_screen.id(), *lock_screen.rect(), UpdateMode::Full));
+                view.children_mut().push(Box::new(lock_screen) as Box<dyn View>);
+            } else {
+                rq.add(RenderData::expose(rect, UpdateMode::Full));
+            }
         }
         hub.send(Event::ClockTick).ok();
         hub.send(Event::BatteryTick).ok();
     }
 }
 
+fn current_session(view: &dyn View, history: &[HistoryItem]) -> Session {
+    Session::from_views(history.iter().map(|item| item.view.as_ref()).chain(iter::once(view)))
+}
+
+// Only writes the session when it has changed since the last save.
+fn save_session(view: &dyn View, history: &[HistoryItem], session: &mut Session) {
+    let current = current_session(view, history);
+    if current != *session {
+        current.save(Path::new(SESSION_PATH))
+               .map_err(|e| eprintln!("Can't save the session: {:#}.", e)).ok();
+        *session = current;
+    }
+}
+
+// The home's state is applied at once, the other views are reopened through the hub.
+// In kiosk mode, the applications aren't reopened, nor the books outside of the kiosk's directory.
+fn restore_session(home: &mut Home, session: &Session, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+    home.restore(&session.home, hub, rq, context);
+    let kiosk = &context.settings.kiosk;
+    if let Some(info) = session.reader.as_ref().and_then(|path| context.library.info_from_path(path)) {
+        let allowed = !kiosk.enabled || kiosk.directory.as_ref().is_none_or(|dir| {
+            context.library.home.join(&info.file.path).starts_with(dir)
+        });
+        if allowed {
+            hub.send(Event::Open(Box::new(info))).ok();
+        }
+    }
+    if let Some(application) = session.application.as_ref().filter(|_| !kiosk.enabled) {
+        hub.send(Event::Select(EntryId::Launch(application.app_cmd()))).ok();
+        if let ApplicationState::Sketch { file: Some(file) } = application {
+            hub.send(Event::Select(EntryId::Load(PathBuf::from(file)))).ok();
+        }
+    }
+}
+
 fn power_off(view: &mut dyn View, history: &mut Vec<HistoryItem>, updating: &mut Vec<UpdateData>, context: &mut Context) {
-    let (tx, _rx) = mpsc::channel();
+    current_session(view, history).save(Path::new(SESSION_PATH))
+                                  .map_err(|e| eprintln!("Can't save the session: {:#}.", e)).ok();
+    let (tx, _rx) = hub::channel();
     view.handle_event(&Event::Back, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), context);
     while let Some(mut item) = history.pop() {
         item.view.handle_event(&Event::Back, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), context);
@@ -200,6 +294,16 @@ fn set_wifi(enable: bool, context: &mut Context) {
     }
 }
 
+fn log_battery(history: &mut BatteryHistory, kind: RecordKind, context: &mut Context) {
+    if let Ok(capacity) = context.battery.capacity().map(|v| v[0]) {
+        let charging = context.battery.status().is_ok_and(|v| v[0] == Status::Charging);
+        let record = Record { time: Local::now().timestamp(), capacity, charging, kind };
+        history.push(record)
+               .map_err(|e| eprintln!("Can't log the battery state: {:#}.", e))
+               .ok();
+    }
+}
+
 enum ExitStatus {
     Quit,
     Reboot,
@@ -208,6 +312,8 @@ enum ExitStatus {
 
 pub fn run() -> Result<(), Error> {
     let mut inactive_since = Instant::now();
+    let mut asleep_since = None;
+    let mut battery_history = BatteryHistory::new(BATTERY_HISTORY_PATH);
     let mut exit_status = ExitStatus::Quit;
 
     let mut fb: Box<dyn Framebuffer> = if CURRENT_DEVICE.mark() != 8 {
@@ -227,6 +333,11 @@ pub fn run() -> Result<(), Error> {
         context.batch_import();
     }
     context.load_dictionaries();
+    context.load_vocabulary();
+    context.load_downloads();
+    context.load_weather();
+    context.update_planner(false);
+    context.settings.network.export();
     context.load_keyboard_layouts();
 
     let mut paths = Vec::new();
@@ -250,7 +361,7 @@ pub fn run() -> Result<(), Error> {
     let touch_screen = gesture_events(device_events(raw_receiver, context.display, context.settings.button_scheme));
     let usb_port = usb_events();
 
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = hub::channel();
     let tx2 = tx.clone();
 
     thread::spawn(move || {
@@ -282,6 +393,30 @@ pub fn run() -> Result<(), Error> {
         }
     });
 
+    // The devices without a gyroscope might still have an accelerometer.
+    if !CURRENT_DEVICE.has_gyroscope() {
+        if let Some(accelerometer) = Accelerometer::find() {
+            context.accelerometer = true;
+            let hysteresis = context.settings.auto_rotation.hysteresis;
+            let tx7 = tx.clone();
+            thread::spawn(move || {
+                let mut current = None;
+                loop {
+                    thread::sleep(ACCELEROMETER_POLL_INTERVAL);
+                    let acceleration = if let Ok(acceleration) = accelerometer.read() { acceleration } else { continue };
+                    let index = orientation_index(acceleration, current, hysteresis);
+                    if index != current {
+                        current = index;
+                        if let Some(index) = index {
+                            let rotation = CURRENT_DEVICE.transformed_gyroscope_rotation(index);
+                            tx7.send(Event::Device(DeviceEvent::RotateScreen(rotation))).ok();
+                        }
+                    }
+                }
+            });
+        }
+    }
+
     if context.settings.auto_suspend > 0 {
         let tx6 = tx.clone();
         thread::spawn(move || {
@@ -312,8 +447,12 @@ pub fn run() -> Result<(), Error> {
     let mut tasks: Vec<Task> = Vec::new();
     let mut history: Vec<HistoryItem> = Vec::new();
     let mut rq = RenderQueue::new();
-    let mut view: Box<dyn View> = Box::new(Home::new(context.fb.rect(), &tx,
-                                                     &mut rq, &mut context)?);
+    let mut session = Session::load(Path::new(SESSION_PATH));
+    let mut home = Home::new(context.fb.rect(), &tx, &mut rq, &mut context)?;
+    if context.settings.restore_session {
+        restore_session(&mut home, &session, &tx, &mut rq, &mut context);
+    }
+    let mut view: Box<dyn View> = Box::new(home);
 
     let mut updating = Vec::new();
     let current_dir = env::current_dir()?;
@@ -323,14 +462,28 @@ pub fn run() -> Result<(), Error> {
     println!("The framebuffer resolution is {} by {}.", context.fb.rect().width(),
                                                         context.fb.rect().height());
 
+    if context.settings.hot_reload {
+        watch_config(&tx);
+    }
+
+    if let Some(path) = context.settings.command_socket.as_ref() {
+        remote::listen(path, &tx).map_err(|e| eprintln!("Can't listen to {}: {:#}.", path.display(), e)).ok();
+    }
+
     let mut bus = VecDeque::with_capacity(4);
+    let mut pacer = FramePacer::new(Duration::from_millis(context.settings.frame_interval));
 
     schedule_task(TaskId::CheckBattery, Event::CheckBattery,
                   BATTERY_REFRESH_INTERVAL, &tx, &mut tasks);
+    tx.send(Event::TakeSnapshot).ok();
     tx.send(Event::WakeUp).ok();
 
     while let Ok(evt) = rx.recv() {
+        let event_start = Instant::now();
         match evt {
+            Event::Device(DeviceEvent::Button { .. }) |
+            Event::Gesture(GestureEvent::HoldButtonShort(..)) if !context.shared && !context.covered &&
+                                                                  focus::handle_event(view.as_ref(), &evt, &tx, &mut rq, &mut context) => (),
             Event::Device(de) => {
                 match de {
                     DeviceEvent::Button { code: ButtonCode::Power, status: ButtonStatus::Released, .. } => {
@@ -339,9 +492,9 @@ pub fn run() -> Result<(), Error> {
                         }
 
                         if tasks.iter().any(|task| task.id == TaskId::PrepareSuspend) {
-                            resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                            resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                         } else if tasks.iter().any(|task| task.id == TaskId::Suspend) {
-                            resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                            resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                         } else {
                             view.handle_event(&Event::Suspend, &tx, &mut bus, &mut rq, &mut context);
                             let interm = Intermission::new(context.fb.rect(), IntermKind::Suspend, &context);
@@ -387,9 +540,9 @@ pub fn run() -> Result<(), Error> {
                         }
 
                         if tasks.iter().any(|task| task.id == TaskId::PrepareSuspend) {
-                            resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                            resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                         } else if tasks.iter().any(|task| task.id == TaskId::Suspend) {
-                            resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                            resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                         }
                     },
                     DeviceEvent::NetUp => {
@@ -406,11 +559,17 @@ pub fn run() -> Result<(), Error> {
                         let notif = Notification::new(format!("Network is up ({}, {}).", ip, essid),
                                                       &tx, &mut rq, &mut context);
                         context.online = true;
+                        context.downloads.process(true, &tx);
+                        if context.settings.network.detect_portal {
+                            portal::detect(&tx);
+                        }
+                        if context.settings.clock.sync {
+                            ntp::sync(&context.settings.clock.ntp_servers, false, &context.jobs, &tx);
+                        }
                         view.children_mut().push(Box::new(notif) as Box<dyn View>);
                         if view.is::<Home>() {
                             view.handle_event(&evt, &tx, &mut bus, &mut rq, &mut context);
                         } else if let Some(entry) = history.get_mut(0).filter(|entry| entry.view.is::<Home>()) {
-                            let (tx, _rx) = mpsc::channel();
                             entry.view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
                         }
                     },
@@ -438,9 +597,9 @@ pub fn run() -> Result<(), Error> {
                             },
                             PowerSource::Host => {
                                 if tasks.iter().any(|task| task.id == TaskId::PrepareSuspend) {
-                                    resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                                    resume(TaskId::PrepareSuspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                                 } else if tasks.iter().any(|task| task.id == TaskId::Suspend) {
-                                    resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                                    resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                                 }
 
                                 if context.settings.auto_share {
@@ -467,7 +626,8 @@ pub fn run() -> Result<(), Error> {
 
                         if context.shared {
                             context.shared = false;
-                            Command::new("scripts/usb-disable.sh").status().ok();
+                            let fs_repaired = Command::new("scripts/usb-disable.sh").status().ok()
+                                                     .and_then(|status| status.code()) == Some(FS_REPAIRED_STATUS);
                             env::set_current_dir(&current_dir)
                                 .map_err(|e| eprintln!("Can't set current directory to {}: {:#}.", current_dir.display(), e))
                                 .ok();
@@ -498,6 +658,9 @@ pub fn run() -> Result<(), Error> {
                             if context.settings.import.unshare_trigger {
                                 context.batch_import();
                             }
+                            if fs_repaired {
+                                tx.send(Event::CheckIntegrity).ok();
+                            }
                             view.handle_event(&Event::Reseed, &tx, &mut bus, &mut rq, &mut context);
                         } else {
                             context.plugged = false;
@@ -509,7 +672,7 @@ pub fn run() -> Result<(), Error> {
                                     schedule_task(TaskId::Suspend, Event::Suspend,
                                                   SUSPEND_WAIT_DELAY, &tx, &mut tasks);
                                 } else {
-                                    resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context);
+                                    resume(TaskId::Suspend, &mut tasks, view.as_mut(), &tx, &mut rq, &mut context, &mut asleep_since);
                                 }
                             } else {
                                 tx.send(Event::BatteryTick).ok();
@@ -535,7 +698,12 @@ pub fn run() -> Result<(), Error> {
                             }
                         }
 
-                        tx.send(Event::Select(EntryId::Rotate(n))).ok();
+                        // Only rotate once the orientation is stable.
+                        tasks.retain(|task| task.id != TaskId::AutoRotate);
+                        if n != context.display.rotation {
+                            let delay = Duration::from_millis(context.settings.auto_rotation.delay);
+                            schedule_task(TaskId::AutoRotate, Event::AutoRotate(n), delay, &tx, &mut tasks);
+                        }
                     },
                     DeviceEvent::UserActivity if context.settings.auto_suspend > 0 => {
                         inactive_since = Instant::now();
@@ -545,6 +713,12 @@ pub fn run() -> Result<(), Error> {
                     }
                 }
             },
+            Event::AutoRotate(n) => {
+                tasks.retain(|task| task.id != TaskId::AutoRotate);
+                if view.auto_rotates() {
+                    tx.send(Event::Select(EntryId::Rotate(n))).ok();
+                }
+            },
             Event::CheckBattery => {
                 schedule_task(TaskId::CheckBattery, Event::CheckBattery,
                               BATTERY_REFRESH_INTERVAL, &tx, &mut tasks);
@@ -564,8 +738,19 @@ pub fn run() -> Result<(), Error> {
                     }
                 }
             },
+            Event::TakeSnapshot => {
+                tasks.retain(|task| task.id != TaskId::TakeSnapshot);
+                schedule_task(TaskId::TakeSnapshot, Event::TakeSnapshot,
+                              SNAPSHOT_CHECK_INTERVAL, &tx, &mut tasks);
+                if context.settings.snapshots.enabled {
+                    context.library.flush();
+                    snapshot::take(&context.library, context.settings.snapshots.keep)
+                             .map_err(|e| eprintln!("Can't take snapshot: {:#}.", e)).ok();
+                }
+            },
             Event::PrepareSuspend => {
                 tasks.retain(|task| task.id != TaskId::PrepareSuspend);
+                save_session(view.as_ref(), &history, &mut session);
                 wait_for_all(&mut updating, &mut context);
                 let path = Path::new(SETTINGS_PATH);
                 save_toml(&context.settings, path).map_err(|e| eprintln!("Can't save settings: {:#}.", e)).ok();
@@ -594,6 +779,8 @@ pub fn run() -> Result<(), Error> {
                            .ok();
                     });
                 }
+                asleep_since.get_or_insert_with(SystemTime::now);
+                log_battery(&mut battery_history, RecordKind::Suspend, &mut context);
                 println!("{}", Local::now().format("Went to sleep on %B %-d, %Y at %H:%M."));
                 Command::new("scripts/suspend.sh")
                         .status()
@@ -602,6 +789,7 @@ pub fn run() -> Result<(), Error> {
                 Command::new("scripts/resume.sh")
                         .status()
                         .ok();
+                log_battery(&mut battery_history, RecordKind::Resume, &mut context);
                 inactive_since = Instant::now();
                 if context.settings.auto_power_off > 0 {
                     if let Some(enabled) = context.rtc.as_ref()
@@ -628,6 +816,7 @@ pub fn run() -> Result<(), Error> {
                 }
 
                 tasks.clear();
+                save_session(view.as_ref(), &history, &mut session);
                 view.handle_event(&Event::Back, &tx, &mut bus, &mut rq, &mut context);
                 while let Some(mut item) = history.pop() {
                     item.view.handle_event(&Event::Back, &tx, &mut bus, &mut rq, &mut context);
@@ -704,6 +893,10 @@ pub fn run() -> Result<(), Error> {
                     },
                 }
             },
+            Event::BatteryTick => {
+                log_battery(&mut battery_history, RecordKind::Sample, &mut context);
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
             Event::ToggleFrontlight => {
                 context.set_frontlight(!context.settings.frontlight);
                 view.handle_event(&Event::ToggleFrontlight, &tx, &mut bus, &mut rq, &mut context);
@@ -746,7 +939,62 @@ pub fn run() -> Result<(), Error> {
                         }
                     }
                     context.fb.set_dithered(dithered);
-                    handle_event(view.as_mut(), &Event::Invalid(path), &tx, &mut bus, &mut rq, &mut context);
+                    if is_protected(context.library.home.join(&path)) {
+                        handle_event(view.as_mut(), &Event::Protected(path), &tx, &mut bus, &mut rq, &mut context);
+                    } else {
+                        handle_event(view.as_mut(), &Event::Invalid(path), &tx, &mut bus, &mut rq, &mut context);
+                    }
+                }
+            },
+            Event::Select(EntryId::ShowCalendar) => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                let now = Local::now().naive_local();
+                let calendar = DatePicker::new(ViewId::Calendar, now, false, &mut context);
+                rq.add(RenderData::new(calendar.id(), *calendar.rect(), UpdateMode::Gui));
+                view.children_mut().push(Box::new(calendar) as Box<dyn View>);
+            },
+            Event::Select(EntryId::ShowQrCode(ref text, ref caption)) => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                match QrCode::encode(text.as_bytes()) {
+                    Ok(code) => {
+                        let qr_code_view = QrCodeView::new(ViewId::QrCode, code, caption.clone(), &mut context);
+                        rq.add(RenderData::new(qr_code_view.id(), *qr_code_view.rect(), UpdateMode::Gui));
+                        view.children_mut().push(Box::new(qr_code_view) as Box<dyn View>);
+                    },
+                    Err(e) => {
+                        let notif = Notification::new(format!("Can't create QR code: {}.", e),
+                                                      &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    },
+                }
+            },
+            Event::Select(EntryId::ShowJobs) => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                let jobs_view = JobsView::new(&mut context);
+                rq.add(RenderData::new(jobs_view.id(), *jobs_view.rect(), UpdateMode::Gui));
+                view.children_mut().push(Box::new(jobs_view) as Box<dyn View>);
+            },
+            Event::Select(EntryId::ShowStatistics(ref path)) => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                if let Some(info) = context.library.info_from_path(path) {
+                    if let Some(statistics_view) = StatisticsView::from_info(ViewId::Statistics, &info, &mut context) {
+                        rq.add(RenderData::new(statistics_view.id(), *statistics_view.rect(), UpdateMode::Gui));
+                        view.children_mut().push(Box::new(statistics_view) as Box<dyn View>);
+                    } else {
+                        compute_statistics(path, &tx, &context);
+                        let notif = Notification::new("Counting the words…".to_string(), &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                }
+            },
+            Event::StatisticsComputed(ref path, statistics) => {
+                context.library.update_info(path, |info| info.statistics = Some(statistics));
+                if let Some(mut info) = context.library.info_from_path(path) {
+                    info.statistics = Some(statistics);
+                    if let Some(statistics_view) = StatisticsView::from_info(ViewId::Statistics, &info, &mut context) {
+                        rq.add(RenderData::new(statistics_view.id(), *statistics_view.rect(), UpdateMode::Gui));
+                        view.children_mut().push(Box::new(statistics_view) as Box<dyn View>);
+                    }
                 }
             },
             Event::Select(EntryId::About) => {
@@ -784,6 +1032,18 @@ pub fn run() -> Result<(), Error> {
                 });
                 view = next_view;
             },
+            Event::Select(EntryId::Launch(ref app_cmd)) if context.settings.kiosk.enabled && !app_cmd.is_kiosk_safe() => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+            },
+            Event::Select(EntryId::ToggleWifi) |
+            Event::Select(EntryId::Quit) if context.settings.kiosk.enabled => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+            },
+            Event::Select(EntryId::UnlockKiosk) if !view.is::<Home>() => {
+                let notif = Notification::new("Go back to the library to unlock.".to_string(),
+                                              &tx, &mut rq, &mut context);
+                view.children_mut().push(Box::new(notif) as Box<dyn View>);
+            },
             Event::Select(EntryId::Launch(app_cmd)) => {
                 view.children_mut().retain(|child| !child.is::<Menu>());
                 let monochrome = context.fb.monochrome();
@@ -792,15 +1052,44 @@ pub fn run() -> Result<(), Error> {
                         context.fb.set_monochrome(true);
                         Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context))
                     },
+                    AppCmd::AnnotatePage { page, ref background, ref sketch } => {
+                        context.fb.set_monochrome(true);
+                        Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context)
+                                        .annotate(page, background, sketch.as_ref()))
+                    },
                     AppCmd::Calculator => Box::new(Calculator::new(context.fb.rect(), &tx, &mut rq, &mut context)?),
                     AppCmd::Dictionary { ref query, ref language } => Box::new(DictionaryApp::new(context.fb.rect(), query,
                                                                                                   language, &tx, &mut rq, &mut context)),
+                    AppCmd::Flashcards => Box::new(Flashcards::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::BatteryGraph => Box::new(BatteryGraph::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::Storage => Box::new(Storage::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::Downloads => Box::new(Downloads::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::Portal => Box::new(Portal::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::Agenda => Box::new(Agenda::new(context.fb.rect(), &tx, &mut rq, &mut context)),
+                    AppCmd::MiniApp { name } => {
+                        if let Some(next_view) = apps::build(name, context.fb.rect(), &tx, &mut rq, &mut context) {
+                            next_view
+                        } else {
+                            eprintln!("Unknown application: {}.", name);
+                            continue;
+                        }
+                    },
+                    AppCmd::Notes { ref note } => Box::new(Notes::new(context.fb.rect(), note.as_deref(), &tx, &mut rq, &mut context)),
+                    AppCmd::HandwrittenNote { ref name } => {
+                        context.fb.set_monochrome(true);
+                        let dir = context.library.home.join(&context.settings.notes.path);
+                        Box::new(Sketch::new(context.fb.rect(), &mut rq, &mut context)
+                                        .note(dir, name.as_deref()))
+                    },
                     AppCmd::TouchEvents => {
                         Box::new(TouchEvents::new(context.fb.rect(), &mut rq, &mut context))
                     },
                     AppCmd::RotationValues => {
                         Box::new(RotationValues::new(context.fb.rect(), &mut rq, &mut context))
                     },
+                    AppCmd::PenCalibration => {
+                        Box::new(PenCalibration::new(context.fb.rect(), &mut rq, &mut context))
+                    },
                 };
                 transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
                 history.push(HistoryItem {
@@ -872,15 +1161,47 @@ pub fn run() -> Result<(), Error> {
             Event::Close(id) => {
                 if let Some(index) = locate_by_id(view.as_ref(), id) {
                     let rect = overlapping_rectangle(view.child(index));
+                    if let Some(notif) = view.child(index).downcast_ref::<Notification>() {
+                        context.notifications.release(notif.id());
+                    }
                     rq.add(RenderData::expose(rect, UpdateMode::Gui));
                     view.children_mut().remove(index);
                 }
             },
+            Event::Select(EntryId::SetTimezone(ref name)) => {
+                context.settings.clock.timezone = Some(name.clone());
+                let notif = Notification::new("The time zone will be applied after a restart.".to_string(),
+                                              &tx, &mut rq, &mut context);
+                view.children_mut().push(Box::new(notif) as Box<dyn View>);
+            },
+            Event::Select(EntryId::SyncClock) => {
+                ntp::sync(&context.settings.clock.ntp_servers, true, &context.jobs, &tx);
+            },
+            Event::ClockSynced { offset, notify } => {
+                context.rtc.iter().for_each(|rtc| {
+                    rtc.set_time(Utc::now())
+                       .map_err(|e| eprintln!("Can't set the hardware clock: {:#}.", e))
+                       .ok();
+                });
+                println!("The clock was moved {}.", ntp::describe_offset(offset));
+                // Small drifts are corrected silently.
+                if notify || offset.abs() >= 60_000 {
+                    let notif = Notification::new(format!("The clock was moved {}.", ntp::describe_offset(offset)),
+                                                  &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+                tx.send(Event::ClockTick).ok();
+            },
             Event::Select(EntryId::ToggleInverted) => {
                 context.fb.toggle_inverted();
                 context.settings.inverted = context.fb.inverted();
                 rq.add(RenderData::new(view.id(), context.fb.rect(), UpdateMode::Full));
             },
+            Event::Select(EntryId::ToggleMetrics) => {
+                let enabled = !context.metrics.is_enabled();
+                context.metrics.set_enabled(enabled);
+                update_metrics_overlay(view.as_mut(), &mut rq, &mut context);
+            },
             Event::Select(EntryId::ToggleDithered) => {
                 context.fb.toggle_dithered();
                 rq.add(RenderData::new(view.id(), context.fb.rect(), UpdateMode::Full));
@@ -931,12 +1252,18 @@ pub fn run() -> Result<(), Error> {
                 let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                 view.children_mut().push(Box::new(notif) as Box<dyn View>);
             },
+            // The background tasks of the home need a working hub.
+            Event::Import(..) |
+            Event::CheckIntegrity |
             Event::CheckFetcher(..) |
             Event::FetcherAddDocument(..) |
             Event::FetcherRemoveDocument(..) |
-            Event::FetcherSearch { .. } if !view.is::<Home>() => {
+            Event::FetcherUpdateDocument { .. } |
+            Event::FetcherSearch { .. } |
+            Event::TaskProgress(ViewId::ImportNotif | ViewId::IntegrityNotif | ViewId::FetcherNotif(..), ..) |
+            Event::ImportFinished(..) |
+            Event::IntegrityChecked(..) if !view.is::<Home>() => {
                 if let Some(entry) = history.get_mut(0).filter(|entry| entry.view.is::<Home>()) {
-                    let (tx, _rx) = mpsc::channel();
                     entry.view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
                 }
             },
@@ -944,10 +1271,184 @@ pub fn run() -> Result<(), Error> {
                 let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                 view.children_mut().push(Box::new(notif) as Box<dyn View>);
             },
+            Event::QueueDownload(ref download) => {
+                if context.downloads.push(*download.clone(), &context.library.home).is_some() {
+                    context.downloads.process(context.online, &tx);
+                    context.save_downloads();
+                }
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::ClockTick => {
+                save_session(view.as_ref(), &history, &mut session);
+                context.weather.update(&context.settings.weather, context.online, &context.jobs, &tx);
+                poll_timer(view.as_mut(), &tx, &mut rq, &mut context);
+                context.update_planner(false);
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::Select(EntryId::StartTimer) => {
+                context.timer.start(&context.settings.timer, Local::now());
+                context.timer.schedule(&tx);
+                tx.send(Event::FocusStarted).ok();
+                update_timer_chip(view.as_mut(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::PauseTimer) => {
+                context.timer.pause(Local::now());
+                update_timer_chip(view.as_mut(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::ResumeTimer) => {
+                context.timer.resume(Local::now());
+                context.timer.schedule(&tx);
+                if context.timer.is_focusing() {
+                    tx.send(Event::FocusStarted).ok();
+                }
+                update_timer_chip(view.as_mut(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::StopTimer) => {
+                if let TimerState::Running { phase: TimerPhase::Focus, .. } |
+                       TimerState::Paused { phase: TimerPhase::Focus, .. } = context.timer.state {
+                    tx.send(Event::FocusEnded).ok();
+                }
+                context.timer.stop();
+                update_timer_chip(view.as_mut(), &mut rq, &mut context);
+            },
+            Event::TimerTick => {
+                poll_timer(view.as_mut(), &tx, &mut rq, &mut context);
+            },
+            Event::ToggleNear(ViewId::TimerMenu, rect) => {
+                toggle_timer_menu(view.as_mut(), rect, None, &mut rq, &mut context);
+            },
+            Event::Select(EntryId::RefreshWeather) => {
+                context.weather.request(&context.settings.weather, &context.jobs, &tx);
+            },
+            Event::WeatherUpdated(ref forecast) => {
+                context.weather.forecast = Some(*forecast.clone());
+                context.weather.save(WEATHER_PATH);
+                context.update_planner(true);
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::DownloadProgress(id, done, total) => {
+                context.downloads.update_progress(id, done, total);
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::DownloadFinished(id, ref error) => {
+                finish_download(view.as_mut(), id, error.clone(), &tx, &mut bus, &mut rq, &mut context);
+                context.downloads.process(context.online, &tx);
+                context.save_downloads();
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::RetryDownloads => {
+                context.downloads.process(context.online, &tx);
+                context.save_downloads();
+                handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut rq, &mut context);
+            },
+            Event::PortalDetected => {
+                if !view.is::<Portal>() {
+                    let notif = Notification::with_action("Sign-in required by the network.".to_string(),
+                                                          "Open".to_string(),
+                                                          Event::Select(EntryId::Launch(AppCmd::Portal)),
+                                                          &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+            },
+            Event::RenderFrame => {
+                tasks.retain(|task| task.id != TaskId::RenderFrame);
+            },
+            Event::Remote(cmd) if locate_by_id(view.as_ref(), ViewId::LockScreen).is_some() => {
+                eprintln!("Ignored {:?}: the device is locked.", cmd);
+            },
+            Event::Remote(cmd) => {
+                match cmd {
+                    RemoteCommand::Open { path } => {
+                        if let Some(info) = context.library.info_from_path(&path) {
+                            tx.send(Event::Open(Box::new(info))).ok();
+                        } else {
+                            eprintln!("Can't open {}: not found.", path.display());
+                        }
+                    },
+                    RemoteCommand::GoTo { page } => {
+                        if view.is::<Reader>() {
+                            view.handle_event(&Event::Submit(ViewId::GoToPageInput, page), &tx, &mut bus, &mut rq, &mut context);
+                        }
+                    },
+                    RemoteCommand::SetFrontlight { intensity, warmth } => {
+                        if !context.settings.frontlight {
+                            context.set_frontlight(true);
+                            view.handle_event(&Event::ToggleFrontlight, &tx, &mut bus, &mut rq, &mut context);
+                        }
+                        if let Some(intensity) = intensity {
+                            context.frontlight.set_intensity(intensity.clamp(0.0, 100.0));
+                        }
+                        if let Some(warmth) = warmth {
+                            context.frontlight.set_warmth(warmth.clamp(0.0, 100.0));
+                        }
+                        context.settings.frontlight_levels = context.frontlight.levels();
+                    },
+                    RemoteCommand::Screenshot => {
+                        tx.send(Event::Select(EntryId::TakeScreenshot)).ok();
+                    },
+                    RemoteCommand::Import => {
+                        tx.send(Event::Import(Box::new(context.settings.import.clone()))).ok();
+                    },
+                    RemoteCommand::Sync => {
+                        save_toml(&context.settings, SETTINGS_PATH)
+                                 .map_err(|e| eprintln!("Can't save settings: {:#}.", e)).ok();
+                        context.library.flush();
+                    },
+                    RemoteCommand::Handoff { payload } => {
+                        match Handoff::parse(&payload).and_then(|handoff| context.library.accept_handoff(&handoff)) {
+                            Some(info) => {
+                                tx.send(Event::Open(Box::new(info))).ok();
+                            },
+                            None => eprintln!("Can't accept the handoff {}.", payload),
+                        }
+                    },
+                    RemoteCommand::BundleSketches { path } if context.library.relative_path(&path).is_none() => {
+                        eprintln!("Can't bundle sketches outside of the library: {}.", path.display());
+                    },
+                    RemoteCommand::BundleSketches { path } => {
+                        let dir = context.library.home.join(&path);
+                        let tx2 = tx.clone();
+                        thread::spawn(move || {
+                            match notebook::bundle(&dir, CURRENT_DEVICE.dpi) {
+                                Ok(..) => {
+                                    let import_settings = ImportSettings {
+                                        allowed_kinds: ["pdf".to_string()].iter().cloned().collect(),
+                                        .. Default::default()
+                                    };
+                                    tx2.send(Event::Import(Box::new(import_settings))).ok();
+                                },
+                                Err(e) => eprintln!("Can't bundle sketches: {:#}.", e),
+                            }
+                        });
+                    },
+                }
+            },
+            Event::ConfigChanged(file) if context.settings.hot_reload => {
+                match file {
+                    ConfigFile::Settings => match context.reload_settings() {
+                        Ok(true) => {
+                            println!("Reloaded the settings.");
+                            view.handle_event(&Event::Reseed, &tx, &mut bus, &mut rq, &mut context);
+                            let notif = Notification::new("Settings reloaded.".to_string(), &tx, &mut rq, &mut context);
+                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                        },
+                        Ok(false) => (),
+                        Err(e) => eprintln!("Can't reload settings: {:#}.", e),
+                    },
+                    ConfigFile::KeyboardLayouts => {
+                        println!("Reloaded the keyboard layouts.");
+                        context.reload_keyboard_layouts();
+                    },
+                }
+            },
             Event::Select(EntryId::Reboot) => {
                 exit_status = ExitStatus::Reboot;
                 break;
             },
+            Event::Select(EntryId::ClearNotifications) => {
+                context.notifications.clear();
+                tx.send(Event::NotificationsChanged).ok();
+            },
             Event::Select(EntryId::Quit) => {
                 break;
             },
@@ -972,11 +1473,24 @@ pub fn run() -> Result<(), Error> {
             },
         }
 
-        process_render_queue(view.as_ref(), &mut rq, &mut context, &mut updating);
+        update_metrics_overlay(view.as_mut(), &mut rq, &mut context);
+
+        if let Some(delay) = pacer.process(view.as_ref(), &mut rq, &mut context, &mut updating) {
+            if !tasks.iter().any(|task| task.id == TaskId::RenderFrame) {
+                schedule_task(TaskId::RenderFrame, Event::RenderFrame, delay, &tx, &mut tasks);
+            }
+        }
 
         while let Some(ce) = bus.pop_front() {
             tx.send(ce).ok();
         }
+
+        context.metrics.record_event(event_start.elapsed());
+    }
+
+    // The views were already closed by `power_off`, which saved the session beforehand.
+    if !matches!(exit_status, ExitStatus::PowerOff) {
+        save_session(view.as_ref(), &history, &mut session);
     }
 
     if !CURRENT_DEVICE.has_gyroscope() && context.display.rotation != initial_rotation {
@@ -990,6 +1504,8 @@ pub fn run() -> Result<(), Error> {
     }
 
     context.library.flush();
+    context.downloads.stop_all();
+    context.save_downloads();
 
     let path = Path::new(SETTINGS_PATH);
     save_toml(&context.settings, path).context("can't save settings")?;
@@ -1,9 +1,16 @@
 mod app;
 
 use plato_core::anyhow::Error;
+use plato_core::helpers::load_toml;
+use plato_core::settings::{Settings, SETTINGS_PATH};
+use plato_core::timezone;
 use crate::app::run;
 
 fn main() -> Result<(), Error> {
+    // The environment is set before any thread is started.
+    let timezone = load_toml::<Settings, _>(SETTINGS_PATH).ok()
+                             .and_then(|settings| settings.clock.timezone);
+    timezone::export(timezone.as_deref());
     run()?;
     Ok(())
 }
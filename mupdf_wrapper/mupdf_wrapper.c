@@ -15,5 +15,29 @@ WRAP(load_outline, fz_outline*, NULL, fz_load_outline(ctx, doc), fz_document *do
 WRAP(load_links, fz_link*, NULL, fz_load_links(ctx, page), fz_page *page)
 WRAP(count_pages, int, -1, fz_count_pages(ctx, doc), fz_document *doc)
 WRAP(page_number_from_location, int, -1, fz_page_number_from_location(ctx, doc, loc), fz_document *doc, fz_location loc)
+WRAP(resolve_link, fz_location, fz_make_location(-1, -1), fz_resolve_link(ctx, doc, uri, xp, yp), fz_document *doc, const char *uri, float *xp, float *yp)
 WRAP(new_pixmap_from_page, fz_pixmap*, NULL, fz_new_pixmap_from_page(ctx, page, mat, cs, alpha), fz_page *page, fz_matrix mat, fz_colorspace *cs, int alpha)
 WRAP(new_stext_page_from_page, fz_stext_page*, NULL, fz_new_stext_page_from_page(ctx, page, options), fz_page *page, fz_stext_options *options)
+
+// Renders the given area, in device space, of the page transformed by the matrix.
+fz_pixmap *mp_new_pixmap_from_page_area(fz_context *ctx, fz_page *page, fz_matrix mat, fz_irect area, fz_colorspace *cs) {
+    fz_pixmap *pix = NULL;
+    fz_device *dev = NULL;
+    fz_var(pix);
+    fz_var(dev);
+    fz_try (ctx) {
+        pix = fz_new_pixmap_with_bbox(ctx, cs, area, NULL, 0);
+        fz_clear_pixmap_with_value(ctx, pix, 0xff);
+        dev = fz_new_draw_device(ctx, fz_identity, pix);
+        fz_run_page(ctx, page, dev, mat, NULL);
+        fz_close_device(ctx, dev);
+    }
+    fz_always (ctx) {
+        fz_drop_device(ctx, dev);
+    }
+    fz_catch (ctx) {
+        fz_drop_pixmap(ctx, pix);
+        pix = NULL;
+    }
+    return pix;
+}
@@ -0,0 +1,12 @@
+#! /bin/sh
+
+# Arguments: voice, speed, pitch, text.
+# The speed and the pitch are relative to the normal ones.
+
+VOICE=$1
+SPEED=$(awk -v s="$2" 'BEGIN { printf "%d", 175 * s }')
+PITCH=$(awk -v p="$3" 'BEGIN { v = 50 * p; if (v > 99) v = 99; printf "%d", v }')
+
+command -v espeak-ng > /dev/null || exit 1
+
+espeak-ng -v "$VOICE" -s "$SPEED" -p "$PITCH" --stdout -- "$4" | aplay -q
@@ -32,7 +32,8 @@ PARTITION=${DISK}0p3
 MOUNT_ARGS="noatime,nodiratime,shortname=mixed,utf8"
 
 FS_CORRUPT=0
-dosfsck -a -w "$PARTITION" || dosfsck -a -w "$PARTITION" || FS_CORRUPT=1
+FS_REPAIRED=0
+dosfsck -a -w "$PARTITION" || { FS_REPAIRED=1; dosfsck -a -w "$PARTITION"; } || FS_CORRUPT=1
 [ "$FS_CORRUPT" -eq 1 ] && reboot
 
 mount -o "$MOUNT_ARGS" -t vfat "$PARTITION" /mnt/onboard
@@ -40,3 +41,8 @@ mount -o "$MOUNT_ARGS" -t vfat "$PARTITION" /mnt/onboard
 PARTITION=${DISK}1p1
 
 [ -e "$PARTITION" ] && mount -o "$MOUNT_ARGS" -t vfat "$PARTITION" /mnt/sd
+
+# The internal storage wasn't properly unmounted by the computer.
+[ "$FS_REPAIRED" -eq 1 ] && exit 3
+
+exit 0